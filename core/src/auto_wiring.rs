@@ -0,0 +1,216 @@
+//! Connecting the same-named ports of two components, like the bus interfaces of units
+
+use crate::grid_db::{Component, GridDB, GridDBConnectionPoint, Id, Net, Rotation};
+
+/// True for inputs and false for outputs, None if direction is unknown.
+/// Unit ports on the left side are inputs and ports on the right side are outputs.
+fn get_pin_direction(comp: &Component, connection_id: Id) -> Option<bool> {
+    match comp {
+        Component::Unit(unit) => match unit.ports.get(connection_id)?.align {
+            Rotation::ROT0 => Some(true),
+            Rotation::ROT180 => Some(false),
+            _ => None,
+        },
+        _ => comp.is_input_connection(connection_id),
+    }
+}
+
+/// Pins of interfaces are named by the interface port, like `axil.wdata`,
+/// so bundles are connected regardless of their instance names
+fn get_pin_key(comp: &Component, connection_id: Id) -> Option<String> {
+    let key = match comp.get_connection_bundle(connection_id) {
+        Some(bundle) => format!("{}.{}", bundle.interface, bundle.port),
+        None => comp.get_connection_name(connection_id)?,
+    };
+    Some(key.to_lowercase())
+}
+
+fn get_named_pins(comp: &Component) -> Vec<(Id, String)> {
+    (0..comp.get_connections_number())
+        .filter_map(|i| Some((i, get_pin_key(comp, i)?)))
+        .filter(|(_, name)| !name.is_empty())
+        .collect()
+}
+
+fn is_wired(db: &GridDB, a: &GridDBConnectionPoint, b: &GridDBConnectionPoint) -> bool {
+    db.get_point_nets(a).any(|net_id| {
+        db.get_net(&net_id)
+            .is_some_and(|net| net.start_point == *b || net.end_point == *b)
+    })
+}
+
+/// Pairs of the same-named pins of the components, ignoring case.
+/// Ports of interfaces are paired with the same ports of other instances of the interface.
+/// When a name is repeated, like `vld` of both the input and output interfaces,
+/// outputs are paired with inputs. Every pin is used once, already wired pairs are skipped.
+pub fn find_pin_pairs(
+    db: &GridDB,
+    a: Id,
+    b: Id,
+) -> Vec<(GridDBConnectionPoint, GridDBConnectionPoint)> {
+    let (Some(comp_a), Some(comp_b)) = (db.get_component(&a), db.get_component(&b)) else {
+        return vec![];
+    };
+    let pins_a = get_named_pins(comp_a);
+    let pins_b = get_named_pins(comp_b);
+    let (mut used_a, mut used_b) = (vec![false; pins_a.len()], vec![false; pins_b.len()]);
+    let mut result = vec![];
+    // Outputs are paired with inputs first, then the pins of unknown or same directions:
+    for opposite_only in [true, false] {
+        for (i, (pin_a, name)) in pins_a.iter().enumerate() {
+            let direction = get_pin_direction(comp_a, *pin_a);
+            let Some(j) = (0..pins_b.len()).find(|j| {
+                let other = get_pin_direction(comp_b, pins_b[*j].0);
+                !used_a[i]
+                    && !used_b[*j]
+                    && pins_b[*j].1 == *name
+                    && (!opposite_only
+                        || direction.is_some() && other.is_some() && direction != other)
+            }) else {
+                continue;
+            };
+            (used_a[i], used_b[j]) = (true, true);
+            let start = GridDBConnectionPoint {
+                component_id: a,
+                connection_id: *pin_a,
+            };
+            let end = GridDBConnectionPoint {
+                component_id: b,
+                connection_id: pins_b[j].0,
+            };
+            if !is_wired(db, &start, &end) {
+                result.push((start, end));
+            }
+        }
+    }
+    result
+}
+
+/// Routed nets between the same-named pins of the components, from outputs to inputs.
+/// IDs are allocated in `db`.
+pub fn auto_wire(db: &mut GridDB, a: Id, b: Id) -> Vec<(Id, Net)> {
+    let mut nets = vec![];
+    for (mut start, mut end) in find_pin_pairs(db, a, b) {
+        let direction = |p: &GridDBConnectionPoint| {
+            get_pin_direction(db.get_component(&p.component_id)?, p.connection_id)
+        };
+        if direction(&start) == Some(true) || direction(&end) == Some(false) {
+            std::mem::swap(&mut start, &mut end);
+        }
+        let dock_cell = |p: &GridDBConnectionPoint| {
+            db.get_component(&p.component_id)?
+                .get_connection_dock_cell(p.connection_id)
+        };
+        let (Some(start_cell), Some(end_cell)) = (dock_cell(&start), dock_cell(&end)) else {
+            continue;
+        };
+        let net = db.route_net(start, start_cell, end, end_cell);
+        nets.push((db.allocate_net(), net));
+    }
+    nets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::auto_wire;
+    use crate::{
+        grid_db::{Component, Interface, InterfacePort, Port, Rotation, Unit, grid_pos},
+        testing::GridDBBuilder,
+    };
+
+    fn unit(x: i32, ports: &[(i32, Rotation, &str)]) -> Component {
+        Component::Unit(Unit {
+            pos: grid_pos(x, 0),
+            width: 4,
+            height: 5,
+            ports: ports
+                .iter()
+                .map(|(offset, align, name)| Port {
+                    offset: *offset,
+                    align: *align,
+                    name: (*name).into(),
+                    bundle: None,
+                })
+                .collect(),
+            parameters: vec![],
+            description: String::new(),
+            name: Default::default(),
+            source: None,
+            schematic: None,
+        })
+    }
+
+    #[test]
+    fn same_named_ports_wiring() {
+        let mut b = GridDBBuilder::new();
+        let source = b.component(unit(
+            0,
+            &[
+                (1, Rotation::ROT0, "vld"),
+                (1, Rotation::ROT180, "vld"),
+                (2, Rotation::ROT180, "DATA1"),
+                (3, Rotation::ROT180, "error"),
+            ],
+        ));
+        let sink = b.component(unit(
+            10,
+            &[
+                (1, Rotation::ROT0, "vld"),
+                (2, Rotation::ROT0, "data1"),
+                (3, Rotation::ROT0, "clk"),
+            ],
+        ));
+        let mut db = b.build();
+        let nets = auto_wire(&mut db, sink, source);
+        let pins: Vec<_> = nets
+            .iter()
+            .map(|(_, net)| {
+                (
+                    (net.start_point.component_id, net.start_point.connection_id),
+                    (net.end_point.component_id, net.end_point.connection_id),
+                )
+            })
+            .collect();
+        // From the outputs of the source to the inputs of the sink:
+        assert_eq!(pins, [((source, 1), (sink, 0)), ((source, 2), (sink, 1))]);
+        for (id, net) in nets {
+            db.insert_net(id, net);
+        }
+        assert!(auto_wire(&mut db, source, sink).is_empty());
+    }
+
+    #[test]
+    fn interface_bundles_wiring() {
+        let interface = Interface {
+            name: "stream".into(),
+            ports: vec![
+                InterfacePort {
+                    name: "data".into(),
+                    is_input: false,
+                    width: 8,
+                },
+                InterfacePort {
+                    name: "ready".into(),
+                    is_input: true,
+                    width: 1,
+                },
+            ],
+        };
+        let with_interface = |x, bundle, mirrored| {
+            let Component::Unit(mut unit) = unit(x, &[]) else {
+                unreachable!()
+            };
+            unit.add_interface(&interface, bundle, mirrored);
+            Component::Unit(unit)
+        };
+        let mut b = GridDBBuilder::new();
+        let master = b.component(with_interface(0, "m", false));
+        let slave = b.component(with_interface(10, "s", true));
+        let mut db = b.build();
+        let pins: Vec<_> = auto_wire(&mut db, slave, master)
+            .iter()
+            .map(|(_, net)| (net.start_point.component_id, net.end_point.component_id))
+            .collect();
+        assert_eq!(pins, [(master, slave), (slave, master)]);
+    }
+}
@@ -0,0 +1,86 @@
+use epaint::{
+    CircleShape, Color32, CornerRadius, FontId, Fonts, PathStroke, Pos2, Rect, RectShape, Shape,
+    Stroke, StrokeKind, emath::Align2,
+};
+
+use crate::grid_db::Theme;
+
+/// Painter of the visible part of the field, the provided methods mirror the egui painter
+pub trait Canvas: Sized {
+    fn add(&self, shape: impl Into<Shape>);
+
+    fn fonts<R>(&self, reader: impl FnOnce(&Fonts) -> R) -> R;
+
+    /// Visible area, nothing outside of it has to be drawn
+    fn clip_rect(&self) -> Rect;
+
+    /// Same canvas clipped by `rect`
+    fn with_clip_rect(&self, rect: Rect) -> Self;
+
+    fn theme(&self) -> Theme;
+
+    /// Asks to draw the next frame, e.g. the text is drawn again after the zoom debounce
+    fn request_repaint(&self) {}
+
+    fn line_segment(&self, points: [Pos2; 2], stroke: impl Into<Stroke>) {
+        self.add(Shape::LineSegment {
+            points,
+            stroke: stroke.into(),
+        });
+    }
+
+    fn line(&self, points: Vec<Pos2>, stroke: impl Into<PathStroke>) {
+        self.add(Shape::line(points, stroke));
+    }
+
+    fn circle_filled(&self, center: Pos2, radius: f32, fill_color: impl Into<Color32>) {
+        self.add(CircleShape {
+            center,
+            radius,
+            fill: fill_color.into(),
+            stroke: Default::default(),
+        });
+    }
+
+    fn rect(
+        &self,
+        rect: Rect,
+        corner_radius: impl Into<CornerRadius>,
+        fill_color: impl Into<Color32>,
+        stroke: impl Into<Stroke>,
+        stroke_kind: StrokeKind,
+    ) {
+        self.add(RectShape::new(
+            rect,
+            corner_radius,
+            fill_color,
+            stroke,
+            stroke_kind,
+        ));
+    }
+
+    fn rect_filled(
+        &self,
+        rect: Rect,
+        corner_radius: impl Into<CornerRadius>,
+        fill_color: impl Into<Color32>,
+    ) {
+        self.add(RectShape::filled(rect, corner_radius, fill_color));
+    }
+
+    /// Single line text, returns its bounds
+    fn text(
+        &self,
+        pos: Pos2,
+        anchor: Align2,
+        text: impl ToString,
+        font_id: FontId,
+        text_color: Color32,
+    ) -> Rect {
+        let galley =
+            self.fonts(|fonts| fonts.layout_no_wrap(text.to_string(), font_id, text_color));
+        let rect = anchor.anchor_size(pos, galley.size());
+        self.add(Shape::galley(rect.min, galley, text_color));
+        rect
+    }
+}
@@ -0,0 +1,257 @@
+//! Documentation pages of the project units: symbol image, ports, parameters and description
+
+use crate::{
+    grid_db::{Component, GridDB, NetStyle, Port, Rotation, Theme, Unit, grid_pos},
+    netlist::{TOP_SCOPE, extract_netlist},
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DatasheetFormat {
+    Markdown,
+    Html,
+}
+
+pub const SUPPORTED_DATASHEET_FORMATS: [DatasheetFormat; 2] =
+    [DatasheetFormat::Markdown, DatasheetFormat::Html];
+
+impl DatasheetFormat {
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            Self::Markdown => "Markdown",
+            Self::Html => "HTML",
+        }
+    }
+
+    pub fn get_extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+        }
+    }
+}
+
+/// Ports on the right side are outputs, like in the Yosys import, other ones are inputs
+fn get_port_direction(port: &Port) -> &'static str {
+    if port.align == Rotation::ROT180 {
+        "output"
+    } else {
+        "input"
+    }
+}
+
+/// Width of the bus port named like `data[7:0]`, 1 for other ports
+fn get_port_width(name: &str) -> usize {
+    let range = name
+        .strip_suffix(']')
+        .and_then(|name| name.rsplit_once('['))
+        .and_then(|(_, range)| range.split_once(':'));
+    match range {
+        Some((msb, lsb)) => match (msb.trim().parse::<usize>(), lsb.trim().parse::<usize>()) {
+            (Ok(msb), Ok(lsb)) => msb.abs_diff(lsb) + 1,
+            _ => 1,
+        },
+        None => 1,
+    }
+}
+
+/// Symbol of the unit as an inline SVG element, without the project data
+fn get_symbol_svg(unit: &Unit, theme: Theme) -> String {
+    let mut db = GridDB::new();
+    let id = db.allocate_component();
+    db.insert_component(
+        id,
+        Component::Unit(Unit {
+            pos: grid_pos(0, 0),
+            ..unit.clone()
+        }),
+    );
+    let svg = db.dump_to_svg(theme, 20.0, &NetStyle::DEFAULT);
+    // Blank lines would end the HTML block in Markdown:
+    let start = svg.find("<svg").unwrap_or(0);
+    svg[start..]
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Interface instance of the port, like `m_axil: axil`
+fn get_port_interface(port: &Port) -> String {
+    port.bundle
+        .as_ref()
+        .map(|bundle| format!("{}: {}", bundle.name, bundle.interface))
+        .unwrap_or_default()
+}
+
+fn escape_markdown(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+/// Headings are escaped as HTML, so names like `<buffer>` are not taken for tags
+fn escape_markdown_heading(text: &str) -> String {
+    html_escape::encode_text(text).to_string()
+}
+
+fn write_markdown_page(out: &mut String, title: &str, unit: &Unit, theme: Theme) {
+    *out += &format!("## {}\n\n", escape_markdown_heading(title));
+    if !unit.description.is_empty() {
+        *out += &format!("{}\n\n", unit.description);
+    }
+    *out += &format!("{}\n\n", get_symbol_svg(unit, theme));
+    // The interface column is added only for units with interfaces:
+    let has_bundles = unit.ports.iter().any(|p| p.bundle.is_some());
+    *out += match has_bundles {
+        true => "|Port|Direction|Width|Interface|\n|-|-|-|-|\n",
+        false => "|Port|Direction|Width|\n|-|-|-|\n",
+    };
+    for port in &unit.ports {
+        *out += &format!(
+            "|{}|{}|{}|",
+            escape_markdown(&port.name),
+            get_port_direction(port),
+            get_port_width(&port.name)
+        );
+        if has_bundles {
+            *out += &format!("{}|", escape_markdown(&get_port_interface(port)));
+        }
+        *out += "\n";
+    }
+    if !unit.parameters.is_empty() {
+        *out += "\n|Parameter|Value|\n|-|-|\n";
+        for param in &unit.parameters {
+            *out += &format!(
+                "|{}|{}|\n",
+                escape_markdown(&param.name),
+                escape_markdown(&param.value)
+            );
+        }
+    }
+    *out += "\n";
+}
+
+fn write_html_page(out: &mut String, title: &str, unit: &Unit, theme: Theme) {
+    let encode = |text: &str| html_escape::encode_text(text).to_string();
+    *out += &format!("<h2>{}</h2>\n", encode(title));
+    if !unit.description.is_empty() {
+        *out += &format!("<p>{}</p>\n", encode(&unit.description));
+    }
+    *out += &format!("{}\n", get_symbol_svg(unit, theme));
+    let has_bundles = unit.ports.iter().any(|p| p.bundle.is_some());
+    *out += "<table>\n<tr><th>Port</th><th>Direction</th><th>Width</th>";
+    if has_bundles {
+        *out += "<th>Interface</th>";
+    }
+    *out += "</tr>\n";
+    for port in &unit.ports {
+        *out += &format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td>",
+            encode(&port.name),
+            get_port_direction(port),
+            get_port_width(&port.name)
+        );
+        if has_bundles {
+            *out += &format!("<td>{}</td>", encode(&get_port_interface(port)));
+        }
+        *out += "</tr>\n";
+    }
+    *out += "</table>\n";
+    if !unit.parameters.is_empty() {
+        *out += "<table>\n<tr><th>Parameter</th><th>Value</th></tr>\n";
+        for param in &unit.parameters {
+            *out += &format!(
+                "<tr><td>{}</td><td>{}</td></tr>\n",
+                encode(&param.name),
+                encode(&param.value)
+            );
+        }
+        *out += "</table>\n";
+    }
+}
+
+/// Document with pages of all units of the project, titled by their hierarchical names
+pub fn generate_datasheets(
+    db: &GridDB,
+    project_name: &str,
+    format: DatasheetFormat,
+    theme: Theme,
+) -> String {
+    let netlist = extract_netlist(db);
+    let mut out = match format {
+        DatasheetFormat::Markdown => format!("# {}\n\n", escape_markdown_heading(project_name)),
+        DatasheetFormat::Html => format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{0}</title>\n</head>\n<body>\n<h1>{0}</h1>\n",
+            html_escape::encode_text(project_name)
+        ),
+    };
+    for id in db.get_component_ids() {
+        let Some(Component::Unit(unit)) = db.get_component(&id) else {
+            continue;
+        };
+        let title = netlist
+            .get_instance_path(TOP_SCOPE, id)
+            .unwrap_or_else(|| format!("Unit #{id}"));
+        match format {
+            DatasheetFormat::Markdown => write_markdown_page(&mut out, &title, unit, theme),
+            DatasheetFormat::Html => write_html_page(&mut out, &title, unit, theme),
+        }
+    }
+    if format == DatasheetFormat::Html {
+        out += "</body>\n</html>\n";
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DatasheetFormat, escape_markdown_heading, generate_datasheets, get_port_width};
+    use crate::{
+        grid_db::{Component, Parameter, Port, PrimitiveType, Rotation, Theme, Unit, grid_pos},
+        testing::{GridDBBuilder, assert_golden},
+    };
+
+    #[test]
+    fn unit_datasheets() {
+        assert_eq!(get_port_width("data[7:0]"), 8);
+        assert_eq!(get_port_width("addr[0:3]"), 4);
+        assert_eq!(get_port_width("clk"), 1);
+        assert_eq!(
+            escape_markdown_heading("top.<buffer>"),
+            "top.&lt;buffer&gt;"
+        );
+        let mut b = GridDBBuilder::new();
+        b.primitive(PrimitiveType::Not, 0, 0, Rotation::ROT0);
+        let port = |offset, align, name: &str| Port {
+            offset,
+            align,
+            name: name.into(),
+            bundle: None,
+        };
+        b.component(Component::Unit(Unit {
+            pos: grid_pos(10, 10),
+            width: 4,
+            height: 4,
+            ports: vec![
+                port(1, Rotation::ROT0, "data[7:0]"),
+                port(2, Rotation::ROT0, "a|b"),
+                port(1, Rotation::ROT180, "valid"),
+            ],
+            parameters: vec![Parameter {
+                name: "DEPTH".into(),
+                value: "16".into(),
+            }],
+            description: "FIFO <buffer>".into(),
+            name: Default::default(),
+            source: None,
+            schematic: None,
+        }));
+        let db = b.build();
+        assert_golden(
+            "datasheet.md",
+            &generate_datasheets(&db, "fifo", DatasheetFormat::Markdown, Theme::Light),
+        );
+        assert_golden(
+            "datasheet.html",
+            &generate_datasheets(&db, "fifo", DatasheetFormat::Html, Theme::Light),
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use epaint::{Color32, FontFamily, FontId, Pos2, Rect, Shape, Vec2, pos2, vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::grid_db::{GridPos, LodLevel, NetStyle};
+
+/// Grid drawn on the field
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum GridType {
+    Dots,
+    Cells,
+    None,
+}
+
+#[derive(Clone)]
+pub struct FieldState {
+    pub scale: f32,
+    pub offset: Vec2,
+    pub grid_size: f32,
+    pub rect: Rect,
+    pub label_font: FontId,
+    pub label_visible: bool,
+    pub cursor_pos: Option<Pos2>,
+    pub debounce: bool,
+    pub debounce_scale: f32,
+    pub net_style: NetStyle,
+    /// Multiplier of the LOD thresholds, raised when the frames are slow
+    pub lod_bias: f32,
+}
+
+// Dummy state parameters used to generate SVG
+pub const SVG_DUMMY_STATE: FieldState = FieldState {
+    scale: 1.0 / FieldState::BASE_GRID_SIZE,
+    offset: vec2(0.0, 0.0),
+    grid_size: 1.0,
+    cursor_pos: None,
+    label_font: FontId {
+        size: 0.8,
+        family: FontFamily::Monospace,
+    },
+    label_visible: true,
+    rect: Rect::from_min_max(pos2(0.0, 0.0), pos2(0.0, 0.0)),
+    debounce: false,
+    debounce_scale: 1.0,
+    net_style: NetStyle::DEFAULT,
+    lod_bias: 1.0,
+};
+
+impl FieldState {
+    pub const BASE_GRID_SIZE: f32 = 10.0;
+    pub const MAX_FONT_SIZE: f32 = 32.0;
+    pub const MIN_DISPLAY_TEXT_SIZE: f32 = 3.0;
+    pub const LOD_LEVEL_MID_SCALE: f32 = 1.0; // ??
+    pub const LOD_LEVEL_MIN_SCALE: f32 = 0.5;
+
+    pub fn grid_to_screen(&self, grid_pos: &GridPos) -> Pos2 {
+        return pos2(
+            self.rect.left() + self.offset.x + grid_pos.x as f32 * self.grid_size,
+            self.rect.top() + self.offset.y + grid_pos.y as f32 * self.grid_size,
+        );
+    }
+
+    pub fn screen_to_grid(&self, screen_pos: Pos2) -> GridPos {
+        let grid_x = (screen_pos.x - self.rect.left() - self.offset.x) / self.grid_size;
+        let grid_y = (screen_pos.y - self.rect.top() - self.offset.y) / self.grid_size;
+
+        GridPos {
+            x: grid_x.floor() as i32,
+            y: grid_y.floor() as i32,
+        }
+    }
+
+    /// Updates the grid size and the labels after the scale is changed
+    pub fn update_grid_size(&mut self) {
+        self.grid_size = Self::BASE_GRID_SIZE * self.scale;
+        let label_text_size = self.grid_size * 0.5;
+        self.label_visible = label_text_size > Self::MIN_DISPLAY_TEXT_SIZE * self.lod_bias;
+        self.label_font = FontId::monospace(label_text_size);
+    }
+
+    pub fn lod_level(&self) -> LodLevel {
+        if self.scale <= Self::LOD_LEVEL_MIN_SCALE * self.lod_bias {
+            LodLevel::Min
+        } else if self.scale <= Self::LOD_LEVEL_MID_SCALE * self.lod_bias {
+            LodLevel::Mid
+        } else {
+            LodLevel::Max
+        }
+    }
+}
+
+pub fn filled_cells(
+    state: &FieldState,
+    grid_pos: &GridPos,
+    width: i32,
+    height: i32,
+    color: Color32,
+) -> Shape {
+    let rect = Rect::from_min_size(
+        state.grid_to_screen(&grid_pos),
+        vec2(
+            state.grid_size * width as f32,
+            state.grid_size * height as f32,
+        ),
+    );
+    Shape::rect_filled(rect, 0.0, color)
+}
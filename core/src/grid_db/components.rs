@@ -1,21 +1,22 @@
 use std::{
-    collections::HashMap, f32::consts::PI, ops::{Add, AddAssign}, vec
+    collections::{BTreeMap, HashMap}, ops::{Add, AddAssign, Sub}, vec
 };
 
-use egui::{
-    Align2, Color32, FontId, Painter, Pos2, Rect, Shape, Stroke, StrokeKind, Theme, Vec2,
-    epaint::{PathShape, PathStroke},
+use epaint::{
+    Color32, FontId, Pos2, Rect, StrokeKind, Vec2,
+    emath::{Align2, TSTransform},
     pos2, vec2,
 };
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 
 use crate::{
-    field::{Field, FieldState, SVG_DUMMY_STATE},
+    canvas::Canvas,
+    field_state::FieldState,
     grid_db::{
-        ComponentColor, GridRect, Id, LodLevel, PrimitiveType,
-        Rotation, STROKE_SCALE, TextField, grid_rect, show_text_with_debounce,
-        svg_circle_filled, svg_rect, svg_single_line_text,
+        ComponentColor, GridRect, Id, LodLevel, Net, NetStyle, PortBundle, PrimitiveType,
+        Rotation, STROKE_SCALE, SharedStr, SymbolGeometry, SymbolLabel, TextField, Theme,
+        grid_rect, show_text_with_debounce, svg_rect,
     },
 };
 
@@ -60,6 +61,14 @@ impl Add for GridPos {
     }
 }
 
+impl Sub for GridPos {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self::Output {
+        grid_pos(self.x - rhs.x, self.y - rhs.y)
+    }
+}
+
 impl AddAssign for GridPos {
     fn add_assign(&mut self, rhs: Self) {
         *self = grid_pos(self.x + rhs.x, self.y + rhs.y);
@@ -95,12 +104,42 @@ impl TextAlignment for Rotation {
     }
 }
 
+/// Library part a component was placed from, to offer updates of the library
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct LibrarySource {
+    pub library: SharedStr,
+    /// Name of the library entry
+    pub entry: SharedStr,
+    pub version: u32,
+}
+
+/// Inner schematic of a hierarchical unit.
+/// Ports of the unit are connected to the inputs and outputs with the same names.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Schematic {
+    pub components: BTreeMap<Id, Component>,
+    pub nets: BTreeMap<Id, Net>,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Unit {
     pub pos: GridPos,
     pub width: i32,
     pub height: i32,
     pub ports: Vec<Port>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parameters: Vec<Parameter>,
+    /// Free text shown in the generated documentation
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// Instance name in the netlist, generated from the type and ID if empty
+    #[serde(default, skip_serializing_if = "str::is_empty")]
+    pub name: SharedStr,
+    /// Library part the unit was placed from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<LibrarySource>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schematic: Option<Box<Schematic>>,
 }
 
 impl Unit {
@@ -108,10 +147,12 @@ impl Unit {
         ComponentAction::AddPort,
         ComponentAction::EditPort,
         ComponentAction::RemovePort,
+        ComponentAction::Customize,
+        ComponentAction::Replace,
         ComponentAction::Remove,
     ];
 
-    pub fn display(&self, state: &FieldState, painter: &Painter, theme: Theme) {
+    pub fn display(&self, state: &FieldState, painter: &impl Canvas, theme: Theme) {
         let fill_color = theme.get_fill_color();
         let rect = Rect::from_min_size(
             state.grid_to_screen(&self.pos) + vec2(0.05, 0.05) * state.grid_size,
@@ -128,10 +169,41 @@ impl Unit {
             StrokeKind::Middle,
         );
 
-        if state.scale > Field::LOD_LEVEL_MIN_SCALE {
-            for port in &self.ports {
-                port.display(&self.pos, (self.width, self.height), state, &painter, theme);
+        if state.lod_level() != LodLevel::Min {
+            let geometry = self.get_ports_geometry();
+            let screen_pos = state.grid_to_screen(&self.pos).to_vec2();
+            let to_screen = |p: Pos2| p * state.grid_size + screen_pos;
+            for port in &geometry.ports {
+                painter.circle_filled(
+                    to_screen(*port),
+                    state.grid_size * Port::PORT_SCALE,
+                    theme.get_stroke_color(),
+                );
             }
+            if state.lod_level() == LodLevel::Max {
+                for label in geometry.labels {
+                    show_text_with_debounce(
+                        to_screen(label.pos),
+                        label.text,
+                        state,
+                        painter,
+                        None,
+                        label.rotation,
+                        label.align,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Port dots and names relative to the unit position, in grid units.
+    /// Both the canvas and the SVG export draw ports from it, the body is a plain rectangle.
+    pub fn get_ports_geometry(&self) -> SymbolGeometry {
+        let dim = (self.width, self.height);
+        SymbolGeometry {
+            ports: self.ports.iter().map(|p| p.get_center_raw(dim)).collect(),
+            labels: self.ports.iter().map(|p| p.get_label(dim)).collect(),
+            ..Default::default()
         }
     }
 
@@ -142,6 +214,7 @@ impl Unit {
             align,
             offset,
             name: _name,
+            bundle: _,
         } in &self.ports
         {
             if [Rotation::ROT0, Rotation::ROT180].contains(align) && offset + 1 > min_h {
@@ -219,27 +292,13 @@ impl Unit {
             theme,
         );
         result += &"\n";
-        for port in &self.ports {
-            let center: Pos2 =
-                (port.center(&self.pos, (self.width, self.height), &SVG_DUMMY_STATE)
-                    + vec2(offset.x as f32, offset.y as f32))
-                    * scale;
-            result += &svg_circle_filled(center, 0.1 * scale, theme.get_stroke_color());
-            result += &"\n";
-        }
-        for p in &self.ports {
-            let cell = p.get_cell(&self.pos, (self.width, self.height)) + offset;
-            let text_pos =
-                pos2(cell.x as f32 * scale, cell.y as f32 * scale) + vec2(0.5, 0.5) * scale;
-            result += &svg_single_line_text(
-                p.name.clone(),
-                text_pos,
-                0.5 * scale,
-                p.align.to_text_rotation(),
-                theme,
-                p.align.to_text_align2(),
-            );
-        }
+        let transform = TSTransform::new(vec2(pos.x as f32, pos.y as f32) * scale, scale);
+        result += &self.get_ports_geometry().transformed(transform).to_svg(
+            theme,
+            STROKE_SCALE * scale,
+            Port::PORT_SCALE * scale,
+            0.5 * scale,
+        );
         result
     }
 }
@@ -269,7 +328,7 @@ impl Component {
         )
     }
 
-    pub fn display(&self, state: &FieldState, painter: &Painter, theme: Theme) {
+    pub fn display(&self, state: &FieldState, painter: &impl Canvas, theme: Theme) {
         match self {
             Component::Unit(u) => u.display(state, painter, theme),
             Component::Primitive(g) => g.display(state, painter, theme),
@@ -300,26 +359,28 @@ impl Component {
         }
     }
 
-    pub fn draw_preview(&self, rect: &Rect, painter: &Painter, theme: Theme) {
+    pub fn draw_preview(&self, rect: &Rect, painter: &impl Canvas, theme: Theme) {
         let (mut w, mut h) = self.get_dimension();
         w += 2;
         h += 2;
         let x_grid_size = rect.width() / w as f32;
         let y_grid_size = rect.height() / h as f32;
         let grid_size = x_grid_size.min(y_grid_size);
-        let scale = grid_size / Field::BASE_GRID_SIZE;
+        let scale = grid_size / FieldState::BASE_GRID_SIZE;
         let state = FieldState {
-            scale: grid_size / Field::BASE_GRID_SIZE,
+            scale: grid_size / FieldState::BASE_GRID_SIZE,
             offset: Vec2::default(),
             grid_size: grid_size,
             rect: rect.clone(), // ?? TODO make it as Option
             label_font: FontId::monospace(
-                (Field::BASE_GRID_SIZE * scale * 0.5).min(Field::MAX_FONT_SIZE),
+                (FieldState::BASE_GRID_SIZE * scale * 0.5).min(FieldState::MAX_FONT_SIZE),
             ),
             label_visible: true,
             cursor_pos: None,
             debounce_scale: 1.0,
             debounce: false,
+            net_style: NetStyle::DEFAULT,
+            lod_bias: 1.0,
         };
         self.display(&state, painter, theme);
     }
@@ -364,7 +425,7 @@ impl Component {
         }
     }
 
-    pub fn highlight_connection(&self, connection_id: Id, state: &FieldState, painter: &Painter) {
+    pub fn highlight_connection(&self, connection_id: Id, state: &FieldState, painter: &impl Canvas) {
         match self {
             Component::Unit(unit) => {
                 if let Some(p) = unit.ports.get(connection_id) {
@@ -412,6 +473,149 @@ impl Component {
         }
     }
 
+    pub fn get_type_name(&self) -> String {
+        match self {
+            Component::Unit(_) => "Unit".to_owned(),
+            Component::Primitive(g) => g.typ.get_type_name(),
+            Component::TextField(_) => "Text".to_owned(),
+        }
+    }
+
+    /// Name given by the user, None for text fields
+    pub fn get_name_mut(&mut self) -> Option<&mut SharedStr> {
+        match self {
+            Component::Unit(unit) => Some(&mut unit.name),
+            Component::Primitive(g) => Some(&mut g.name),
+            Component::TextField(_) => None,
+        }
+    }
+
+    /// Library part the component was placed from, None for text fields
+    pub fn get_library_source(&self) -> Option<&LibrarySource> {
+        match self {
+            Component::Unit(unit) => unit.source.as_ref(),
+            Component::Primitive(g) => g.source.as_ref(),
+            Component::TextField(_) => None,
+        }
+    }
+
+    pub fn set_library_source(&mut self, source: LibrarySource) {
+        match self {
+            Component::Unit(unit) => unit.source = Some(source),
+            Component::Primitive(g) => g.source = Some(source),
+            Component::TextField(_) => {}
+        }
+    }
+
+    /// Unique name of the instance like `and2_5`, unless the user has named it
+    pub fn get_instance_name(&self, id: Id) -> Option<String> {
+        let name = match self {
+            Component::Unit(unit) => &unit.name,
+            Component::Primitive(g) => &g.name,
+            Component::TextField(_) => return None,
+        };
+        if name.is_empty() {
+            Some(format!("{}_{}", self.get_type_name().to_lowercase(), id))
+        } else {
+            Some(name.to_string())
+        }
+    }
+
+    pub fn get_connections_number(&self) -> usize {
+        match self {
+            Component::Unit(unit) => unit.ports.len(),
+            Component::Primitive(g) => g.typ.get_connections_number(),
+            _ => 0,
+        }
+    }
+
+    pub fn get_connection_name(&self, connection_id: Id) -> Option<String> {
+        match self {
+            Component::Unit(unit) => Some(unit.ports.get(connection_id)?.name.to_string()),
+            Component::Primitive(g) => g.typ.get_connection_name(connection_id),
+            _ => None,
+        }
+    }
+
+    /// Interface instance of the unit port, None for ports outside of interfaces
+    pub fn get_connection_bundle(&self, connection_id: Id) -> Option<&PortBundle> {
+        match self {
+            Component::Unit(unit) => unit.ports.get(connection_id)?.bundle.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Returns true for inputs and false for outputs, None if direction is unknown
+    pub fn is_input_connection(&self, connection_id: Id) -> Option<bool> {
+        match self {
+            Component::Primitive(g) => g.typ.is_input_connection(connection_id),
+            _ => None,
+        }
+    }
+
+    /// Maps connections of this component to connections of `other` placed at the same position.
+    /// Connections are matched by name first, then by dock cell. Unmatched ones are mapped to None.
+    pub fn get_replacement_connections(&self, other: &Component) -> HashMap<Id, Option<Id>> {
+        let mut result = HashMap::new();
+        let mut used = vec![false; other.get_connections_number()];
+
+        // By name:
+        for id in 0..self.get_connections_number() {
+            let Some(name) = self.get_connection_name(id) else {
+                continue;
+            };
+            if name.is_empty() {
+                continue;
+            }
+            let found = (0..used.len()).find(|&other_id| {
+                !used[other_id]
+                    && other
+                        .get_connection_name(other_id)
+                        .is_some_and(|n| n.eq_ignore_ascii_case(&name))
+            });
+            if let Some(other_id) = found {
+                used[other_id] = true;
+                result.insert(id, Some(other_id));
+            }
+        }
+
+        // By position:
+        let ofs = self.get_position();
+        let other_ofs = other.get_position();
+        for id in 0..self.get_connections_number() {
+            if result.contains_key(&id) {
+                continue;
+            }
+            let cell = self.get_connection_dock_cell(id).map(|c| c - ofs);
+            let found = (0..used.len()).find(|&other_id| {
+                !used[other_id]
+                    && cell.is_some()
+                    && other.get_connection_dock_cell(other_id).map(|c| c - other_ofs) == cell
+            });
+            if let Some(other_id) = found {
+                used[other_id] = true;
+            }
+            result.insert(id, found);
+        }
+        result
+    }
+
+    pub fn is_swappable_pair(&self, a: Id, b: Id) -> bool {
+        match self {
+            Component::Primitive(g) => g.typ.is_swappable_pair(a, b),
+            _ => false,
+        }
+    }
+
+    /// Returns hovered connection, which has at least one equivalent connection to swap with
+    pub fn get_hovered_swappable_connection(&self, state: &FieldState) -> Option<Id> {
+        let n = self.get_connections_number();
+        (0..n).find(|&id| {
+            self.is_connection_hovered(id, state)
+                && (0..n).any(|other| other != id && self.is_swappable_pair(id, other))
+        })
+    }
+
     pub fn is_connection_hovered(&self, connection_id: Id, state: &FieldState) -> bool {
         match self {
             Component::Unit(unit) => unit
@@ -423,9 +627,9 @@ impl Component {
         }
     }
 
-    pub fn to_svg(&self, offset: GridPos, scale: f32, theme: Theme) -> String {
+    pub fn to_svg(&self, offset: GridPos, scale: f32, theme: Theme, style: &NetStyle) -> String {
         match self {
-            Component::Primitive(g) => g.get_svg(offset, scale, theme),
+            Component::Primitive(g) => g.get_svg(offset, scale, theme, style),
             Component::TextField(f) => f.get_svg(offset, scale, theme),
             Component::Unit(u) => u.to_svg(offset, scale, theme),
         }
@@ -469,22 +673,22 @@ impl Component {
     }
 
     /// Returns immutable reference to the text in a text edit field
-    pub fn get_text_edit(&self, id: Id) -> Option<&String> {
+    pub fn get_text_edit(&self, id: Id) -> Option<&str> {
         match self {
             Component::TextField(f) => {
                 if id == 0 {
-                    Some(&f.text)
+                    Some(f.text.as_str())
                 } else {
                     None
                 }
             }
-            Component::Unit(u) => Some(&u.ports.get(id)?.name),
+            Component::Unit(u) => Some(u.ports.get(id)?.name.as_str()),
             _ => None,
         }
     }
 
     /// Returns mutable reference to the text in a text edit field
-    pub fn get_text_edit_mut(&mut self, id: Id) -> Option<&mut String> {
+    pub fn get_text_edit_mut(&mut self, id: Id) -> Option<&mut SharedStr> {
         match self {
             Component::TextField(f) => {
                 if id == 0 {
@@ -551,19 +755,40 @@ impl Component {
             _ => panic!("Can't remove port"),
         }
     }
+}
 
-    pub fn show_customization_panel(
-        &mut self,
-        ui: &mut egui::Ui,
-        locale: &'static crate::locale::Locale,
-    ) -> Option<Self> {
-        match self {
-            Self::Primitive(p) => {
-                p.typ.show_customization_panel(ui, locale);
-                return None;
+/// Generic parameter of the unit instance, like `WIDTH=8`
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Parameter {
+    pub name: SharedStr,
+    pub value: SharedStr,
+}
+
+impl Parameter {
+    /// Numeric value: decimal, `0x`/`0b`/`0o` prefixed or Verilog literal like `8'hFF`
+    pub fn get_number(&self) -> Option<u64> {
+        let value = self.value.trim().replace('_', "");
+        let value = match value.split_once('\'') {
+            Some((_size, literal)) => {
+                let literal = literal.trim_start_matches(['s', 'S']);
+                let (base, digits) = literal.split_at_checked(1)?;
+                let radix = match base {
+                    "h" | "H" => 16,
+                    "d" | "D" => 10,
+                    "o" | "O" => 8,
+                    "b" | "B" => 2,
+                    _ => return None,
+                };
+                return u64::from_str_radix(digits, radix).ok();
+            }
+            None => value,
+        };
+        for (prefix, radix) in [("0x", 16), ("0b", 2), ("0o", 8)] {
+            if let Some(digits) = value.strip_prefix(prefix) {
+                return u64::from_str_radix(digits, radix).ok();
             }
-            _ => panic!(),
         }
+        value.parse().ok()
     }
 }
 
@@ -572,35 +797,38 @@ pub struct Port {
     // Connection
     pub offset: i32,
     pub align: Rotation,
-    pub name: String,
+    pub name: SharedStr,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bundle: Option<PortBundle>,
 }
 
 impl Port {
     const PORT_SCALE: f32 = 0.1;
 
-    pub fn center(
-        &self,
-        unit_pos: &GridPos,
-        (width, height): (i32, i32),
-        state: &FieldState,
-    ) -> Pos2 {
+    pub fn center(&self, unit_pos: &GridPos, dim: (i32, i32), state: &FieldState) -> Pos2 {
+        state.grid_to_screen(unit_pos) + self.get_center_raw(dim).to_vec2() * state.grid_size
+    }
+
+    /// Center of the port dot relative to the unit position, in grid units
+    fn get_center_raw(&self, (width, height): (i32, i32)) -> Pos2 {
+        let offset = self.offset as f32;
         match self.align {
-            Rotation::ROT0 => {
-                state.grid_to_screen(&grid_pos(unit_pos.x, unit_pos.y + self.offset))
-                    + vec2(0.0, 0.5 * state.grid_size)
-            }
-            Rotation::ROT90 => {
-                state.grid_to_screen(&grid_pos(unit_pos.x + self.offset, unit_pos.y))
-                    + vec2(0.5 * state.grid_size, 0.0)
-            }
-            Rotation::ROT180 => {
-                state.grid_to_screen(&grid_pos(unit_pos.x + width, unit_pos.y + self.offset))
-                    + vec2(0.0, 0.5 * state.grid_size)
-            }
-            Rotation::ROT270 => {
-                state.grid_to_screen(&grid_pos(unit_pos.x + self.offset, unit_pos.y + height))
-                    + vec2(0.5 * state.grid_size, 0.0)
-            }
+            Rotation::ROT0 => pos2(0.0, offset + 0.5),
+            Rotation::ROT90 => pos2(offset + 0.5, 0.0),
+            Rotation::ROT180 => pos2(width as f32, offset + 0.5),
+            Rotation::ROT270 => pos2(offset + 0.5, height as f32),
+        }
+    }
+
+    /// Name of the port inside the unit, relative to the unit position.
+    /// Names of the top and bottom ports are vertical.
+    fn get_label(&self, dim: (i32, i32)) -> SymbolLabel {
+        let cell = self.get_cell(&grid_pos(0, 0), dim);
+        SymbolLabel {
+            pos: pos2(cell.x as f32 + 0.5, cell.y as f32 + 0.5),
+            text: self.name.to_string(),
+            rotation: self.align.to_text_rotation(),
+            align: self.align.to_text_align2(),
         }
     }
 
@@ -622,32 +850,6 @@ impl Port {
         }
     }
 
-    pub fn display(
-        &self,
-        unit_pos: &GridPos,
-        dim: (i32, i32),
-        state: &FieldState,
-        painter: &Painter,
-        theme: Theme,
-    ) {
-        let stroke_color = theme.get_stroke_color();
-        let pos = self.center(unit_pos, dim, state);
-        painter.circle_filled(pos, state.grid_size * Self::PORT_SCALE, stroke_color);
-        if state.lod_level() == LodLevel::Max {
-            let text_pos: Pos2 = state.grid_to_screen(&self.get_cell(unit_pos, dim))
-                + vec2(0.5, 0.5) * state.grid_size;
-            show_text_with_debounce(
-                text_pos,
-                self.name.clone(),
-                state,
-                painter,
-                None,
-                self.align.to_text_rotation(),
-                self.align.to_text_align2(),
-            );
-        }
-    }
-
     pub fn is_hovered(&self, state: &FieldState, unit_pos: &GridPos, dim: (i32, i32)) -> bool {
         if let Some(cursor_pos) = state.cursor_pos {
             let d = self.center(unit_pos, dim, state).distance(cursor_pos);
@@ -662,7 +864,7 @@ impl Port {
         state: &FieldState,
         unit_pos: &GridPos,
         dim: (i32, i32),
-        painter: &Painter,
+        painter: &impl Canvas,
     ) {
         let p = self.center(unit_pos, dim, state);
         painter.circle_filled(
@@ -684,52 +886,13 @@ pub enum ComponentAction {
     EditPort,
     EditText,
     Customize,
+    Replace,
+    SwapPins,
+    /// Select the next connector with the same signal name
+    JumpToConnector,
 }
 
 impl ComponentAction {
-    fn draw_rotation_arrow(
-        painter: &Painter,
-        center: Pos2,
-        radius: f32,
-        clockwise: bool,
-        stroke: Stroke,
-    ) {
-        let sweep_angle = if clockwise { 1.7 * PI } else { -1.7 * PI };
-        let start_angle = if clockwise { 0.0 } else { PI };
-        let eps = if clockwise { 0.2 } else { -0.2 };
-
-        let num_segments = 30;
-        let mut points = Vec::with_capacity(num_segments + 1);
-
-        for i in 0..=num_segments {
-            let t = i as f32 / num_segments as f32;
-            let angle = start_angle + t * sweep_angle;
-            points.push(center + radius * Vec2::angled(angle));
-        }
-
-        painter.add(Shape::Path(PathShape {
-            points,
-            closed: false,
-            fill: Color32::TRANSPARENT,
-            stroke: PathStroke::new(stroke.width, stroke.color),
-        }));
-
-        let r_vec = radius * Vec2::angled(start_angle + sweep_angle - eps);
-        let p1 = center + r_vec + r_vec.normalized() * stroke.width;
-        let p2 = center + r_vec - r_vec.normalized() * stroke.width;
-        let p3 = if clockwise {
-            ((p2 - p1).rot90() + (p1.to_vec2() + p2.to_vec2()) / 2.0).to_pos2()
-        } else {
-            ((p1 - p2).rot90() + (p1.to_vec2() + p2.to_vec2()) / 2.0).to_pos2()
-        };
-
-        painter.add(Shape::convex_polygon(
-            vec![p1, p2, p3],
-            stroke.color,
-            Stroke::NONE,
-        ));
-    }
-
     pub fn actions_grid(comp: &Component, state: &FieldState, n_actions: usize) -> Vec<Rect> {
         let (w, _h) = comp.get_dimension();
         let size = 50.0;
@@ -753,148 +916,6 @@ impl ComponentAction {
             );
         Rect::from_min_size(pos, vec2(size * n_actions as f32, size))
     }
-
-    pub fn draw_connection_icon(center: Pos2, radius: f32, painter: &Painter, stroke: Stroke) {
-        let num_segments = 30;
-        let mut points = Vec::with_capacity(num_segments + 1);
-        let start_angle = PI * 0.25;
-        let sweep_angle = 1.5 * PI;
-        for i in 0..=num_segments {
-            let t = i as f32 / num_segments as f32;
-            let angle = start_angle + t * sweep_angle;
-            points.push(center + radius * Vec2::angled(angle));
-        }
-        painter.add(Shape::Path(PathShape {
-            points,
-            closed: false,
-            fill: Color32::TRANSPARENT,
-            stroke: PathStroke::new(stroke.width, stroke.color),
-        }));
-        painter.line_segment(
-            [
-                center - vec2(radius, 0.0),
-                center - vec2(radius * 1.75, 0.0),
-            ],
-            stroke,
-        );
-        painter.line_segment([center, center + vec2(radius * 1.0, 0.0)], stroke);
-        painter.circle_filled(center, stroke.width, stroke.color);
-    }
-
-    pub fn draw(
-        &self,
-        rect: &Rect,
-        painter: &egui::Painter,
-        selected: bool,
-        visuals: &egui::Visuals,
-    ) {
-        let stroke = if selected {
-            Stroke::new(rect.height() / 8.0, visuals.strong_text_color())
-        } else {
-            Stroke::new(rect.height() / 8.0, visuals.text_color())
-        };
-        match self {
-            Self::RotateDown => {
-                Self::draw_rotation_arrow(
-                    &painter,
-                    rect.center(),
-                    rect.height() * 0.3,
-                    false,
-                    stroke,
-                );
-            }
-            Self::RotateUp => {
-                Self::draw_rotation_arrow(
-                    &painter,
-                    rect.center(),
-                    rect.height() * 0.3,
-                    true,
-                    stroke,
-                );
-            }
-            Self::Remove => {
-                let scaled = rect.scale_from_center(0.6);
-                painter.line_segment([scaled.left_top(), scaled.right_bottom()], stroke);
-                painter.line_segment([scaled.left_bottom(), scaled.right_top()], stroke);
-            }
-            Self::AddPort => {
-                painter.text(
-                    rect.min + vec2(rect.height() * 0.05, rect.height() * 0.05),
-                    Align2::LEFT_TOP,
-                    "+",
-                    FontId::monospace(rect.height() * 0.5),
-                    stroke.color,
-                );
-                let stroke2 = Stroke {
-                    color: stroke.color,
-                    width: stroke.width * 0.75,
-                };
-                Self::draw_connection_icon(
-                    rect.center() + vec2(rect.height() * 0.1, rect.height() * 0.1),
-                    rect.height() * 0.3 * 0.75,
-                    painter,
-                    stroke2,
-                );
-            }
-            Self::RemovePort => {
-                painter.text(
-                    rect.min + vec2(rect.height() * 0.05, rect.height() * 0.05),
-                    Align2::LEFT_TOP,
-                    "×",
-                    FontId::monospace(rect.height() * 0.5),
-                    stroke.color,
-                );
-                let stroke2 = Stroke {
-                    color: stroke.color,
-                    width: stroke.width * 0.75,
-                };
-                Self::draw_connection_icon(
-                    rect.center() + vec2(rect.height() * 0.1, rect.height() * 0.1),
-                    rect.height() * 0.3 * 0.75,
-                    painter,
-                    stroke2,
-                );
-            }
-            Self::EditPort => {
-                painter.text(
-                    rect.min + vec2(rect.height() * 0.05, rect.height() * 0.05),
-                    Align2::LEFT_TOP,
-                    "📝",
-                    FontId::monospace(rect.height() * 0.5),
-                    stroke.color,
-                );
-                let stroke2 = Stroke {
-                    color: stroke.color,
-                    width: stroke.width * 0.75,
-                };
-                Self::draw_connection_icon(
-                    rect.center() + vec2(rect.height() * 0.1, rect.height() * 0.1),
-                    rect.height() * 0.3 * 0.75,
-                    painter,
-                    stroke2,
-                );
-            }
-            Self::EditText => {
-                painter.text(
-                    rect.center(),
-                    Align2::CENTER_CENTER,
-                    "📝",
-                    FontId::monospace(rect.height()),
-                    stroke.color,
-                );
-            }
-            Self::Customize => {
-                painter.text(
-                    rect.center(),
-                    Align2::CENTER_CENTER,
-                    "⚙",
-                    FontId::monospace(rect.height()),
-                    stroke.color,
-                );
-            }
-            _ => {}
-        }
-    }
 }
 
 #[derive(Clone, Copy)]
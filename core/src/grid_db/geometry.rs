@@ -0,0 +1,98 @@
+use epaint::{
+    Pos2,
+    emath::{Align2, TSTransform},
+    pos2, vec2,
+};
+
+use crate::grid_db::{
+    ComponentColor, Rotation, Theme, svg_circle_filled, svg_line, svg_polygon, svg_single_line_text,
+};
+
+/// Text label of a symbol
+#[derive(Clone)]
+pub struct SymbolLabel {
+    pub pos: Pos2,
+    pub text: String,
+    pub rotation: Rotation,
+    pub align: Align2,
+}
+
+/// Renderer-agnostic drawing of a symbol: filled polygons, open lines, port dots and text labels.
+/// Coordinates are in grid units unless the geometry is transformed.
+/// Every output backend draws symbols from this structure only.
+#[derive(Clone, Default)]
+pub struct SymbolGeometry {
+    pub polygons: Vec<Vec<Pos2>>,
+    pub lines: Vec<Vec<Pos2>>,
+    pub ports: Vec<Pos2>,
+    pub labels: Vec<SymbolLabel>,
+}
+
+impl SymbolGeometry {
+    /// Applies `f` to every point of the geometry
+    pub fn map_points(&mut self, f: impl Fn(Pos2) -> Pos2) {
+        for p in self
+            .polygons
+            .iter_mut()
+            .chain(self.lines.iter_mut())
+            .flatten()
+            .chain(self.ports.iter_mut())
+        {
+            *p = f(*p);
+        }
+        for label in &mut self.labels {
+            label.pos = f(label.pos);
+        }
+    }
+
+    /// Scales and translates the geometry, e.g. from grid units into screen or SVG coordinates
+    pub fn transformed(mut self, transform: TSTransform) -> Self {
+        self.map_points(|p| transform * p);
+        self
+    }
+
+    /// Rotates the geometry of a symbol with `raw_dim` size, keeping it in the same grid cells
+    pub fn rotated(mut self, rotation: Rotation, raw_dim: (i32, i32)) -> Self {
+        let dim = rotation.get_rotated_dim(raw_dim);
+        let rot_ofs = match rotation {
+            Rotation::ROT0 => vec2(0.0, 0.0),
+            Rotation::ROT90 => vec2(dim.0 as f32, 0.0),
+            Rotation::ROT180 => vec2(dim.0 as f32, dim.1 as f32),
+            Rotation::ROT270 => vec2(0.0, dim.1 as f32),
+        };
+        self.map_points(|p| rotation.rotate_point(p, pos2(0.0, 0.0)) + rot_ofs);
+        for label in &mut self.labels {
+            label.rotation = label.rotation + rotation;
+        }
+        self
+    }
+
+    /// SVG elements of the already transformed geometry
+    pub fn to_svg(&self, theme: Theme, stroke_w: f32, port_radius: f32, font_size: f32) -> String {
+        let fill_color = theme.get_fill_color();
+        let stroke_color = theme.get_stroke_color();
+        let mut result = String::new();
+        for line in &self.lines {
+            result.push_str(&(svg_line(line, stroke_color, stroke_w) + "\n"));
+        }
+        for port in &self.ports {
+            result.push_str(&(svg_circle_filled(*port, port_radius, stroke_color) + "\n"));
+        }
+        for points in &self.polygons {
+            result.push_str(&(svg_polygon(points, fill_color, stroke_color, stroke_w) + "\n"));
+        }
+        for label in &self.labels {
+            result.push_str(
+                &(svg_single_line_text(
+                    label.text.clone(),
+                    label.pos,
+                    font_size,
+                    label.rotation,
+                    theme,
+                    label.align,
+                ) + "\n"),
+            );
+        }
+        result
+    }
+}
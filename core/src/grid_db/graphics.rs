@@ -1,6 +1,6 @@
-use egui::ecolor::HexColor;
-use egui::epaint::Vertex;
-use egui::{Align, Align2, Color32, Mesh, Painter, Pos2, Rect, Stroke, Theme, Vec2, pos2};
+use epaint::ecolor::HexColor;
+use epaint::emath::{Align, Align2};
+use epaint::{Color32, Mesh, Pos2, Rect, Stroke, Vec2, Vertex, pos2};
 use lyon::geom::point;
 use lyon::{
     path::{LineCap, LineJoin, Path},
@@ -10,9 +10,11 @@ use lyon::{
     },
 };
 
+use serde::{Deserialize, Serialize};
 use std::cell::RefCell;
 
-use crate::field::FieldState;
+use crate::canvas::Canvas;
+use crate::field_state::FieldState;
 use crate::grid_db::Rotation;
 
 pub fn tesselate_polygon(
@@ -32,7 +34,7 @@ pub fn tesselate_polygon(
     }
     let path = builder.build();
 
-    let mut geometry: VertexBuffers<egui::epaint::Vertex, u32> = VertexBuffers::new();
+    let mut geometry: VertexBuffers<epaint::Vertex, u32> = VertexBuffers::new();
 
     thread_local! {
         static TESSELLATOR: RefCell<FillTessellator> = RefCell::new(FillTessellator::new());
@@ -47,9 +49,9 @@ pub fn tesselate_polygon(
                 &FillOptions::default(),
                 &mut lyon::tessellation::BuffersBuilder::new(
                     &mut geometry,
-                    |vertex: FillVertex| egui::epaint::Vertex {
+                    |vertex: FillVertex| epaint::Vertex {
                         pos: pos2(vertex.position().x, vertex.position().y),
-                        uv: egui::epaint::WHITE_UV,
+                        uv: epaint::WHITE_UV,
                         color: fill_color,
                     },
                 ),
@@ -59,10 +61,10 @@ pub fn tesselate_polygon(
     let mut mesh = Mesh {
         vertices: geometry.vertices,
         indices: geometry.indices,
-        texture_id: egui::TextureId::default(),
+        texture_id: epaint::TextureId::default(),
     };
     if stroked {
-        let mut stroke_geometry: VertexBuffers<egui::epaint::Vertex, u32> = VertexBuffers::new();
+        let mut stroke_geometry: VertexBuffers<epaint::Vertex, u32> = VertexBuffers::new();
         STROKE_TESSELLATOR.with(|tessellator| {
             let mut tessellator = tessellator.borrow_mut();
             let stroke_options = StrokeOptions::default()
@@ -76,7 +78,7 @@ pub fn tesselate_polygon(
                     &stroke_options,
                     &mut BuffersBuilder::new(&mut stroke_geometry, |vertex: StrokeVertex| Vertex {
                         pos: pos2(vertex.position().x, vertex.position().y),
-                        uv: egui::epaint::WHITE_UV,
+                        uv: epaint::WHITE_UV,
                         color: stroke_color,
                     }),
                 )
@@ -85,7 +87,7 @@ pub fn tesselate_polygon(
         mesh.append(Mesh {
             vertices: stroke_geometry.vertices,
             indices: stroke_geometry.indices,
-            texture_id: egui::TextureId::default(),
+            texture_id: epaint::TextureId::default(),
         });
     }
     mesh
@@ -147,6 +149,52 @@ pub fn mesh_line(pts: Vec<Pos2>, width: f32, color: Color32) -> Mesh {
     mesh
 }
 
+/// Same as `mesh_line`, but with rounded corners and ends
+pub fn mesh_round_line(pts: Vec<Pos2>, width: f32, color: Color32) -> Mesh {
+    const CIRCLE_SEGMENTS: u32 = 12;
+    let half_w = width * 0.5;
+    let mut mesh = Mesh::default();
+    for i in 0..pts.len() - 1 {
+        let delta = pts[i + 1] - pts[i];
+        let length = delta.length();
+        if length == 0.0 {
+            continue;
+        }
+        let half = Vec2::new(-delta.y, delta.x) / length * half_w;
+        let idx_base = mesh.vertices.len() as u32;
+        for p in [pts[i] + half, pts[i] - half, pts[i + 1] + half, pts[i + 1] - half] {
+            mesh.colored_vertex(p, color);
+        }
+        mesh.add_triangle(idx_base, idx_base + 1, idx_base + 2);
+        mesh.add_triangle(idx_base + 2, idx_base + 1, idx_base + 3);
+    }
+    for p in &pts {
+        let center = mesh.vertices.len() as u32;
+        mesh.colored_vertex(*p, color);
+        for i in 0..CIRCLE_SEGMENTS {
+            let angle = i as f32 / CIRCLE_SEGMENTS as f32 * std::f32::consts::TAU;
+            mesh.colored_vertex(*p + Vec2::angled(angle) * half_w, color);
+        }
+        for i in 0..CIRCLE_SEGMENTS {
+            mesh.add_triangle(center, center + 1 + i, center + 1 + (i + 1) % CIRCLE_SEGMENTS);
+        }
+    }
+    mesh
+}
+
+/// Filled triangle with the tip at `tip` directed along `dir`
+pub fn mesh_arrow(tip: Pos2, dir: Vec2, size: f32, color: Color32) -> Mesh {
+    let dir = dir.normalized();
+    let base = tip - dir * size;
+    let half = dir.rot90() * size * 0.4;
+    let mut mesh = Mesh::default();
+    mesh.colored_vertex(tip, color);
+    mesh.colored_vertex(base + half, color);
+    mesh.colored_vertex(base - half, color);
+    mesh.add_triangle(0, 1, 2);
+    mesh
+}
+
 pub fn svg_polygon(
     points: &Vec<Pos2>,
     fill_color: Color32,
@@ -168,7 +216,7 @@ pub fn svg_polygon(
     )
 }
 
-pub fn svg_line(points: &Vec<Pos2>, color: Color32, width: f32) -> String {
+fn svg_path_data(points: &[Pos2]) -> String {
     let mut path = String::new();
     path.push_str(&format!("M {} {}", points[0].x, points[0].y));
 
@@ -180,15 +228,35 @@ pub fn svg_line(points: &Vec<Pos2>, color: Color32, width: f32) -> String {
         points[points.len() - 1].x,
         points[points.len() - 1].y
     ));
+    path
+}
 
+pub fn svg_line(points: &Vec<Pos2>, color: Color32, width: f32) -> String {
     format!(
         r#"<path d="{}" stroke="{}" stroke-width="{}" fill="none"/>"#,
-        path,
+        svg_path_data(points),
         color.to_svg_hex(),
         width
     )
 }
 
+/// Line with explicit corners style: rounded or squared (same as `mesh_line`)
+pub fn svg_styled_line(points: &Vec<Pos2>, color: Color32, width: f32, round: bool) -> String {
+    let (join, cap) = if round {
+        ("round", "round")
+    } else {
+        ("miter", "square")
+    };
+    format!(
+        r#"<path d="{}" stroke="{}" stroke-width="{}" stroke-linejoin="{}" stroke-linecap="{}" fill="none"/>"#,
+        svg_path_data(points),
+        color.to_svg_hex(),
+        width,
+        join,
+        cap
+    )
+}
+
 pub fn svg_circle_filled(center: Pos2, radius: f32, fill_color: Color32) -> String {
     format!(
         r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
@@ -218,6 +286,13 @@ pub fn svg_circle(
     )
 }
 
+/// Color scheme of the drawn and exported schematic
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
 #[allow(unused)]
 pub trait ComponentColor {
     fn get_fill_color(&self) -> Color32;
@@ -281,12 +356,7 @@ pub fn svg_single_line_text(
 ) -> String {
     let color = theme.get_text_color().to_svg_hex();
     let Pos2 { x, y } = pos;
-    let deg_angle = match rotation {
-        Rotation::ROT0 => "0",
-        Rotation::ROT90 => "90",
-        Rotation::ROT180 => "180",
-        Rotation::ROT270 => "270",
-    };
+    let deg_angle = rotation.to_degrees();
 
     let text_anchor = match anchor.x() {
         Align::LEFT => "start",
@@ -334,9 +404,8 @@ pub fn svg_rect(pos: Pos2, (width, height): (f32, f32), stroke_w: f32, theme: Th
     )
 }
 
-#[allow(unused)]
 pub fn draw_dashed_line(
-    painter: &Painter,
+    painter: &impl Canvas,
     start: Pos2,
     end: Pos2,
     color: Color32,
@@ -362,9 +431,8 @@ pub fn draw_dashed_line(
     }
 }
 
-#[allow(unused)]
 pub fn draw_dashed_rect(
-    painter: &Painter,
+    painter: &impl Canvas,
     rect: Rect,
     color: Color32,
     stroke_width: f32,
@@ -0,0 +1,1648 @@
+use std::{
+    borrow::Cow,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use rstar::{AABB, PointDistance, RTree, RTreeObject};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    field_state::FieldState,
+    grid_db::{
+        Component, ExportGrid, GridPos, Interface, Logic, Net, NetClass, NetSegment, NetStyle,
+        Schematic, SvgExportJob, Theme, XmlEscapeWriter, current_date, expand_template, grid_pos,
+        segment_cells, simplify_path,
+    },
+    parallel,
+};
+
+const SVG_DATA_ID: &str = "editor-project";
+
+type Point = [i32; 2]; // Точка (x, y)
+
+pub type Id = usize;
+
+/// Component or net inserted, removed or modified in the database
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Change {
+    Component(Id),
+    Net(Id),
+}
+
+/// State of a particular database after some number of changes
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Revision {
+    db_id: u64,
+    changes: u64,
+}
+
+static NEXT_DB_ID: AtomicU64 = AtomicU64::new(0);
+
+pub struct GridRect {
+    pub id: usize,
+    pub min: GridPos,
+    pub max: GridPos,
+}
+
+impl GridRect {
+    pub fn contains(&self, pos: GridPos) -> bool {
+        pos.x >= self.min.x && pos.y >= self.min.y && pos.x <= self.max.x && pos.y <= self.max.y
+    }
+}
+
+impl PartialEq for GridRect {
+    fn eq(&self, other: &Self) -> bool {
+        other.id == self.id
+    }
+}
+
+impl RTreeObject for GridRect {
+    type Envelope = AABB<Point>;
+
+    fn envelope(&self) -> Self::Envelope {
+        AABB::from_corners(self.min.to_point(), self.max.to_point())
+    }
+}
+
+impl PointDistance for GridRect {
+    fn distance_2(&self, point: &Point) -> i32 {
+        let x = point[0];
+        let y = point[1];
+
+        let dx = if x < self.min.x {
+            self.min.x - x
+        } else if x > self.max.x {
+            x - self.max.x
+        } else {
+            0
+        };
+
+        let dy = if y < self.min.y {
+            self.min.y - y
+        } else if y > self.max.y {
+            y - self.max.y
+        } else {
+            0
+        };
+
+        dx * dx + dy * dy
+    }
+}
+
+pub fn grid_rect(id: usize, min: GridPos, max: GridPos) -> GridRect {
+    return GridRect { id, min, max };
+}
+
+/// Components and nets with IDs allocated in the database, to be inserted there
+pub struct AllocatedFragment {
+    pub components: Vec<(Id, Component)>,
+    pub nets: Vec<(Id, Net)>,
+}
+
+/// Net of the loaded file, which refers to a missing component or pin, so it was dropped
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DanglingNet {
+    pub id: Id,
+    pub pos: GridPos,
+}
+
+/// What was cleaned up by [`GridDB::compact`]
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CompactionReport {
+    /// IDs were renumbered or nets dropped, so the undo history is outdated
+    pub changed: bool,
+    /// Empty entries of the connection indices
+    pub index_entries: usize,
+    /// Nets to the removed components
+    pub orphaned_nets: usize,
+    /// Net classes named like an earlier class, which are never used
+    pub duplicate_classes: usize,
+    /// Size of the project JSON in bytes
+    pub size_before: usize,
+    pub size_after: usize,
+}
+
+/// Viewport and selection saved with the project, restored when it is opened
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProjectView {
+    /// Point in the center of the field, in cells
+    pub center: [f32; 2],
+    pub scale: f32,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub selection: Vec<Id>,
+}
+
+/// Area reserved for future blocks or a title: components can't be placed and nets routed there
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeepOutRegion {
+    pub min: GridPos,
+    pub max: GridPos,
+}
+
+impl KeepOutRegion {
+    /// Region with the corners `a` and `b`, both included
+    pub fn new(a: GridPos, b: GridPos) -> Self {
+        Self {
+            min: grid_pos(a.x.min(b.x), a.y.min(b.y)),
+            max: grid_pos(a.x.max(b.x), a.y.max(b.y)),
+        }
+    }
+
+    pub fn contains(&self, pos: GridPos) -> bool {
+        pos.x >= self.min.x && pos.y >= self.min.y && pos.x <= self.max.x && pos.y <= self.max.y
+    }
+
+    /// First cell of the straight segment from `a` to `b` inside the region
+    pub fn find_crossing(&self, a: GridPos, b: GridPos) -> Option<GridPos> {
+        segment_cells(a, b).find(|cell| self.contains(*cell))
+    }
+}
+
+pub struct GridDB {
+    components: HashMap<usize, Component>,
+    tree: RTree<GridRect>,
+    connections: HashMap<GridPos, HashSet<GridDBConnectionPoint>>,
+    pub nets: HashMap<usize, Net>,
+    connected_nets: HashMap<GridDBConnectionPoint, HashSet<Id>>,
+    net_tree: RTree<NetSegment>,
+    // Segments of each net as they are in `net_tree`, so they are removed without recomputing
+    net_segments: HashMap<Id, Vec<NetSegment>>,
+    next_component_id: Id,
+    next_net_id: Id,
+    db_id: u64,
+    // Recent changes, used by incremental analysis
+    change_log: Vec<Change>,
+    // Number of changes before the first one in the log
+    change_log_start: u64,
+    // Values of `{{name}}` placeholders in text fields, saved with the project
+    variables: BTreeMap<String, String>,
+    // Net classes of the project, saved with it
+    net_classes: Vec<NetClass>,
+    // Port groups added to units, saved with the project
+    interfaces: Vec<Interface>,
+    // Name of the opened file, value of `{{project}}`
+    project_name: String,
+    // Reserved areas, saved with the project
+    keep_out: Vec<KeepOutRegion>,
+    // Cell shown as (0, 0) to the user, saved with the project
+    user_origin: Option<GridPos>,
+    // Last view of the project, restored after opening
+    view: Option<ProjectView>,
+    // Nets dropped on loading, reported as problems until the project is reloaded
+    dangling_nets: Vec<DanglingNet>,
+    // Components referred by the editor state, but missing in the database, reported as problems
+    missing_components: BTreeSet<Id>,
+}
+
+impl Default for GridDB {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GridDB {
+    /// Gap in cells between the current content and the appended one
+    const APPEND_GAP: i32 = 5;
+    /// Older changes are dropped, so consumers which are behind have to process the whole database
+    const MAX_CHANGE_LOG_SIZE: usize = 4096;
+    /// Max shift of the net path from the middle to go around keep-out regions
+    const MAX_KEEP_OUT_DETOUR: i32 = 32;
+    /// Max distance in cells a dropped component is moved to a free location
+    const MAX_PLACEMENT_SHIFT: i32 = 32;
+
+    pub fn new() -> GridDB {
+        Self {
+            components: HashMap::new(),
+            tree: RTree::new(),
+            connections: HashMap::new(),
+            nets: HashMap::new(),
+            net_tree: RTree::new(),
+            net_segments: HashMap::new(),
+            connected_nets: HashMap::new(),
+            next_component_id: 0,
+            next_net_id: 0,
+            db_id: NEXT_DB_ID.fetch_add(1, Ordering::Relaxed),
+            change_log: vec![],
+            change_log_start: 0,
+            variables: BTreeMap::new(),
+            net_classes: NetClass::defaults(),
+            interfaces: vec![],
+            project_name: String::new(),
+            keep_out: vec![],
+            user_origin: None,
+            view: None,
+            dangling_nets: vec![],
+            missing_components: BTreeSet::new(),
+        }
+    }
+
+    /// User defined project variables, like `revision`
+    pub fn get_variables(&self) -> &BTreeMap<String, String> {
+        &self.variables
+    }
+
+    pub fn get_variables_mut(&mut self) -> &mut BTreeMap<String, String> {
+        &mut self.variables
+    }
+
+    pub fn get_net_classes(&self) -> &[NetClass] {
+        &self.net_classes
+    }
+
+    pub fn get_net_classes_mut(&mut self) -> &mut Vec<NetClass> {
+        &mut self.net_classes
+    }
+
+    /// Class of the net, None if it is unclassified or its class was removed
+    pub fn get_net_class(&self, net: &Net) -> Option<&NetClass> {
+        if net.class.is_empty() {
+            return None;
+        }
+        self.net_classes.iter().find(|c| *c.name == *net.class)
+    }
+
+    pub fn get_interfaces(&self) -> &[Interface] {
+        &self.interfaces
+    }
+
+    pub fn get_interfaces_mut(&mut self) -> &mut Vec<Interface> {
+        &mut self.interfaces
+    }
+
+    pub fn get_keep_out_regions(&self) -> &[KeepOutRegion] {
+        &self.keep_out
+    }
+
+    pub fn get_keep_out_regions_mut(&mut self) -> &mut Vec<KeepOutRegion> {
+        &mut self.keep_out
+    }
+
+    pub fn is_keep_out(&self, cell: GridPos) -> bool {
+        self.keep_out.iter().any(|region| region.contains(cell))
+    }
+
+    /// First cell of the path, given by its corners, inside a keep-out region
+    pub fn find_keep_out_crossing(&self, points: &[GridPos]) -> Option<GridPos> {
+        points.windows(2).find_map(|segment| {
+            self.keep_out
+                .iter()
+                .find_map(|region| region.find_crossing(segment[0], segment[1]))
+        })
+    }
+
+    /// Cell the shown coordinates are relative to
+    pub fn get_origin(&self) -> GridPos {
+        self.user_origin.unwrap_or(grid_pos(0, 0))
+    }
+
+    /// Sets the cell shown as (0, 0), None resets it to the grid origin
+    pub fn set_user_origin(&mut self, origin: Option<GridPos>) {
+        self.user_origin = origin.filter(|pos| *pos != grid_pos(0, 0));
+    }
+
+    /// Nets of the damaged file dropped on loading
+    pub fn get_dangling_nets(&self) -> &[DanglingNet] {
+        &self.dangling_nets
+    }
+
+    /// Records the reference of the editor to the missing component, it's reported as a problem
+    pub fn report_missing_component(&mut self, id: Id) {
+        if !self.components.contains_key(&id) {
+            self.missing_components.insert(id);
+        }
+    }
+
+    /// Components referred by the editor, but missing in the database
+    pub fn get_missing_components(&self) -> &BTreeSet<Id> {
+        &self.missing_components
+    }
+
+    /// Does the point refer to an existing pin
+    fn is_valid_point(&self, point: &GridDBConnectionPoint) -> bool {
+        self.components
+            .get(&point.component_id)
+            .is_some_and(|comp| point.connection_id < comp.get_connections_number())
+    }
+
+    /// Extends the net to the pins moved since the project was saved,
+    /// e.g. by the compact layout of the gates with many inputs
+    fn reattach_net(&self, net: &mut Net) {
+        let dock = |p: &GridDBConnectionPoint| {
+            self.get_component(&p.component_id)?.get_connection_dock_cell(p.connection_id)
+        };
+        let (Some(start), Some(end), Some(&first), Some(&last)) = (
+            dock(&net.start_point),
+            dock(&net.end_point),
+            net.points.first(),
+            net.points.last(),
+        ) else {
+            return;
+        };
+        if first == start && last == end {
+            return;
+        }
+        if last != end {
+            net.points.extend([grid_pos(last.x, end.y), end]);
+        }
+        if first != start {
+            net.points.splice(0..0, [start, grid_pos(first.x, start.y)]);
+        }
+        net.points = simplify_path(std::mem::take(&mut net.points));
+    }
+
+    /// View saved in the opened file, returned once to be restored
+    pub fn take_view(&mut self) -> Option<ProjectView> {
+        self.view.take()
+    }
+
+    /// Sets the view written with the project, None to not save it
+    pub fn set_view(&mut self, view: Option<ProjectView>) {
+        self.view = view;
+    }
+
+    pub fn set_project_name(&mut self, name: &str) {
+        if self.project_name != name {
+            self.project_name = name.to_owned();
+        }
+    }
+
+    /// Replaces `{{name}}` placeholders with the project variables.
+    /// `project` and `date` are defined unless they are overridden by the user.
+    pub fn expand_variables<'a>(&self, text: &'a str) -> Cow<'a, str> {
+        expand_template(text, |name| match self.variables.get(name) {
+            Some(value) => Some(value.clone()),
+            None if name == "project" => Some(self.project_name.clone()),
+            None if name == "date" => Some(current_date()),
+            None => None,
+        })
+    }
+
+    fn log_change(&mut self, change: Change) {
+        if self.change_log.len() >= Self::MAX_CHANGE_LOG_SIZE {
+            self.clear_change_log();
+        }
+        self.change_log.push(change);
+    }
+
+    /// Forgets the logged changes, caches of the older revisions are rebuilt from scratch
+    pub fn clear_change_log(&mut self) {
+        self.change_log_start += self.change_log.len() as u64;
+        self.change_log.clear();
+        self.change_log.shrink_to_fit();
+    }
+
+    /// Caches of the derived data are rebuilt or updated after the revision is changed
+    pub fn get_revision(&self) -> Revision {
+        Revision {
+            db_id: self.db_id,
+            changes: self.change_log_start + self.change_log.len() as u64,
+        }
+    }
+
+    /// False if `revision` belongs to another database, like the replaced project
+    pub fn owns_revision(&self, revision: Revision) -> bool {
+        revision.db_id == self.db_id
+    }
+
+    /// Changes made after `revision`, None if the revision belongs to another database
+    /// or is too old. A changed item may appear several times.
+    pub fn get_changes_since(&self, revision: Revision) -> Option<&[Change]> {
+        if revision.db_id != self.db_id || revision.changes < self.change_log_start {
+            return None;
+        }
+        self.change_log
+            .get((revision.changes - self.change_log_start) as usize..)
+    }
+
+    pub fn insert_component(&mut self, id: Id, component: Component) {
+        let rect = self.add_component(id, component);
+        self.tree.insert(rect);
+    }
+
+    /// Adds the component to all indices except the R-tree, returns its rectangle for the tree
+    fn add_component(&mut self, id: Id, component: Component) -> GridRect {
+        self.log_change(Change::Component(id));
+        let rect: GridRect = component.get_grid_rect(id);
+        component
+            .get_connection_dock_cells()
+            .iter()
+            .enumerate()
+            .for_each(|(i, cell)| {
+                if let Some(set) = self.connections.get_mut(cell) {
+                    set.insert(GridDBConnectionPoint {
+                        component_id: id,
+                        connection_id: i,
+                    });
+                } else {
+                    self.connections.insert(
+                        *cell,
+                        HashSet::from([GridDBConnectionPoint {
+                            component_id: id,
+                            connection_id: i,
+                        }]),
+                    );
+                }
+            });
+        self.components.insert(rect.id, component);
+        self.missing_components.remove(&id);
+        rect
+    }
+
+    pub fn allocate_component(&mut self) -> Id {
+        let comp_id = self.next_component_id;
+        self.next_component_id += 1;
+        return comp_id;
+    }
+
+    pub fn remove_component(&mut self, id: &Id) -> Option<Component> {
+        if !self.components.contains_key(id) {
+            return None;
+        }
+        self.log_change(Change::Component(*id));
+        let component = self.components.get(&id)?;
+        for cell in component.get_connection_dock_cells() {
+            if let Some(connections_set) = self.connections.get_mut(&cell) {
+                if let Some(connection) = connections_set
+                    .iter()
+                    .find(|it| it.component_id == *id)
+                    .cloned()
+                {
+                    connections_set.remove(&connection);
+                    if connections_set.is_empty() {
+                        self.connections.remove(&cell);
+                    }
+                }
+            }
+        }
+        self.tree.remove(&component.get_grid_rect(*id));
+
+        return self.components.remove(&id);
+    }
+
+    pub fn get_hovered_connection(&self, state: &FieldState) -> Option<GridDBConnectionPoint> {
+        if let Some(cursor_pos) = state.cursor_pos {
+            let grid_hoverpos = state.screen_to_grid(cursor_pos);
+            // TODO: Simplify it (HOW??)
+            for i in 0..3 {
+                for j in 0..3 {
+                    if let Some(connections) = self
+                        .connections
+                        .get(&grid_pos(grid_hoverpos.x + i - 1, grid_hoverpos.y + j - 1))
+                    {
+                        for connection in connections {
+                            if let Some(component) = self.components.get(&connection.component_id) {
+                                if component.is_connection_hovered(connection.connection_id, state)
+                                {
+                                    return Some(connection.clone());
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    pub fn get_hovered_component_id(&self, state: &FieldState) -> Option<&Id> {
+        let cell = state.screen_to_grid(state.cursor_pos?);
+        if let Some(rect) = self
+            .tree
+            .locate_in_envelope_intersecting(&cell.to_point().envelope())
+            .next()
+        {
+            return Some(&rect.id);
+        }
+        return None;
+    }
+
+    pub fn get_visible_components(&self, rect: &GridRect) -> Vec<&Component> {
+        self.tree
+            .locate_in_envelope_intersecting(&rect.envelope())
+            .map(|rect| self.components.get(&rect.id).unwrap())
+            .collect()
+    }
+
+    pub fn get_visible_component_ids(&self, rect: &GridRect) -> Vec<Id> {
+        self.tree
+            .locate_in_envelope_intersecting(&rect.envelope())
+            .map(|rect| rect.id)
+            .collect()
+    }
+
+    /// Connection point has a net or touches a connection of another component
+    pub fn is_connected(&self, point: &GridDBConnectionPoint) -> bool {
+        if self
+            .connected_nets
+            .get(point)
+            .is_some_and(|nets| !nets.is_empty())
+        {
+            return true;
+        }
+        let Some(cell) = self
+            .get_component(&point.component_id)
+            .and_then(|c| c.get_connection_dock_cell(point.connection_id))
+        else {
+            return false;
+        };
+        self.connections
+            .get(&cell)
+            .is_some_and(|points| points.iter().any(|p| p.component_id != point.component_id))
+    }
+
+    /// Returns all unconnected connection points sorted by component and connection ids
+    pub fn get_unconnected_points(&self) -> Vec<GridDBConnectionPoint> {
+        parallel::flat_map(&self.get_component_ids(), |id| {
+            let comp = &self.components[id];
+            (0..comp.get_connections_number())
+                .map(|i| GridDBConnectionPoint {
+                    component_id: *id,
+                    connection_id: i,
+                })
+                .filter(|p| !self.is_connected(p))
+                .collect()
+        })
+    }
+
+    pub fn get_component(&self, id: &Id) -> Option<&Component> {
+        return self.components.get(&id);
+    }
+
+    pub fn get_component_mut(&mut self, id: &Id) -> Option<&mut Component> {
+        if self.components.contains_key(id) {
+            self.log_change(Change::Component(*id));
+        }
+        return self.components.get_mut(&id);
+    }
+
+    /// Corners of the path between two cells, shifted aside to go around keep-out regions
+    pub fn find_net_path(&self, pos1: GridPos, pos2: GridPos) -> Vec<GridPos> {
+        let mid_x = (pos1.x + pos2.x) / 2;
+        let path = |x| vec![grid_pos(x, pos1.y), grid_pos(x, pos2.y)];
+        if self.keep_out.is_empty() {
+            return path(mid_x);
+        }
+        (0..=Self::MAX_KEEP_OUT_DETOUR)
+            .flat_map(|detour| [mid_x + detour, mid_x - detour])
+            .map(path)
+            .find(|corners| {
+                self.find_keep_out_crossing(&[pos1, corners[0], corners[1], pos2])
+                    .is_none()
+            })
+            .unwrap_or_else(|| path(mid_x))
+    }
+
+    pub fn allocate_net(&mut self) -> Id {
+        let net_id = self.next_net_id;
+        self.next_net_id += 1;
+        net_id
+    }
+
+    pub fn insert_net(&mut self, net_id: Id, net: Net) {
+        for segment in self.add_net(net_id, net) {
+            self.net_tree.insert(segment);
+        }
+    }
+
+    /// Adds the net to all indices except the R-tree, returns its segments for the tree
+    fn add_net(&mut self, net_id: Id, net: Net) -> Vec<NetSegment> {
+        self.log_change(Change::Net(net_id));
+        let segments = net.get_segments(net_id);
+        self.register_net(net_id, net);
+        self.net_segments.insert(net_id, segments.clone());
+        segments
+    }
+
+    fn register_net(&mut self, net_id: Id, net: Net) {
+        for p in [net.start_point, net.end_point] {
+            if let Some(nets) = self.connected_nets.get_mut(&p) {
+                nets.insert(net_id);
+            } else {
+                let mut set = HashSet::new();
+                set.insert(net_id);
+                self.connected_nets.insert(p, set);
+            }
+        }
+        self.nets.insert(net_id, net);
+    }
+
+    fn unregister_net(&mut self, id: &Id) -> Option<Net> {
+        let net = self.nets.remove(id)?;
+        for p in [net.start_point, net.end_point] {
+            if let Some(nets) = self.connected_nets.get_mut(&p) {
+                nets.remove(id);
+            }
+        }
+        Some(net)
+    }
+
+    /// Replaces the net, returns the old one. Only the changed segments are updated
+    /// in the R-tree, so rerouting a long net is cheap.
+    pub fn replace_net(&mut self, net_id: Id, net: Net) -> Option<Net> {
+        self.log_change(Change::Net(net_id));
+        let old_net = self.unregister_net(&net_id);
+        let old_segments = self.net_segments.remove(&net_id).unwrap_or_default();
+        let segments = net.get_segments(net_id);
+        for i in 0..old_segments.len().max(segments.len()) {
+            let (old, new) = (old_segments.get(i), segments.get(i));
+            if let (Some(old), Some(new)) = (old, new)
+                && old.same_geometry(new)
+            {
+                continue;
+            }
+            if let Some(old) = old {
+                self.net_tree.remove(old);
+            }
+            if let Some(new) = new {
+                self.net_tree.insert(new.clone());
+            }
+        }
+        self.register_net(net_id, net);
+        self.net_segments.insert(net_id, segments);
+        old_net
+    }
+
+    pub fn get_net(&self, id: &Id) -> Option<&Net> {
+        self.nets.get(id)
+    }
+
+    pub fn remove_net(&mut self, id: &Id) -> Option<Net> {
+        if !self.nets.contains_key(id) {
+            return None;
+        }
+        self.log_change(Change::Net(*id));
+        for segment in self.net_segments.remove(id).unwrap_or_default() {
+            self.net_tree.remove(&segment);
+        }
+        self.unregister_net(id)
+    }
+
+    pub fn get_hovered_segment(&self, state: &FieldState) -> Option<&NetSegment> {
+        let cell = state.screen_to_grid(state.cursor_pos?);
+        let segments = self
+            .net_tree
+            .locate_in_envelope_intersecting(&cell.to_point().envelope());
+        for s in segments {
+            if s.is_hovered(state) {
+                return Some(s);
+            }
+        }
+        return None;
+    }
+
+    pub fn get_net_segments(&self) -> impl Iterator<Item = &NetSegment> {
+        self.net_tree.iter()
+    }
+
+    pub fn get_visible_net_segments(&self, rect: &GridRect) -> Vec<&NetSegment> {
+        self.net_tree
+            .locate_in_envelope_intersecting(&rect.envelope())
+            .collect()
+    }
+
+    /// Is cell free to place a new component
+    pub fn is_free_cell(&self, cell: GridPos, overlap_only: bool) -> bool {
+        // Text labels may be placed into the reserved areas, e.g. for titles
+        if !overlap_only && self.is_keep_out(cell) {
+            return false;
+        }
+        for nearest in self.tree.locate_within_distance(cell.to_point(), 2) {
+            if overlap_only || self.get_component(&nearest.id).unwrap().is_overlap_only() {
+                if nearest.contains(cell) {
+                    return false;
+                }
+            } else {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    /// Are all cells of the new component placed at `p` free
+    pub fn is_free_location(&self, p: GridPos, dim: (i32, i32), overlap_only: bool) -> bool {
+        (0..dim.0)
+            .all(|x| (0..dim.1).all(|y| self.is_free_cell(p + grid_pos(x, y), overlap_only)))
+    }
+
+    /// Nearest to `p` free location of the new component, searched in growing squares around it
+    pub fn find_free_location(
+        &self,
+        p: GridPos,
+        dim: (i32, i32),
+        overlap_only: bool,
+    ) -> Option<GridPos> {
+        for r in 0..=Self::MAX_PLACEMENT_SHIFT {
+            let mut ring: Vec<GridPos> = (-r..=r)
+                .flat_map(|d| [grid_pos(d, -r), grid_pos(d, r), grid_pos(-r, d), grid_pos(r, d)])
+                .collect();
+            ring.sort_by_key(|d| (d.x * d.x + d.y * d.y, d.y, d.x));
+            ring.dedup();
+            if let Some(d) = ring
+                .into_iter()
+                .find(|d| self.is_free_location(p + *d, dim, overlap_only))
+            {
+                return Some(p + d);
+            }
+        }
+        None
+    }
+
+    /// Is cell available for moving an existing component
+    pub fn is_available_cell(&self, cell: GridPos, component_id: Id) -> bool {
+        self.is_available_cell_except(cell, component_id, &HashSet::new())
+    }
+
+    /// Same as `is_available_cell`, but also ignores components from `ignored`
+    pub fn is_available_cell_except(
+        &self,
+        cell: GridPos,
+        component_id: Id,
+        ignored: &HashSet<Id>,
+    ) -> bool {
+        if !self.get_component(&component_id).unwrap().is_overlap_only() && self.is_keep_out(cell) {
+            return false;
+        }
+        for nearest in self.tree.locate_within_distance(cell.to_point(), 2) {
+            if nearest.id != component_id && !ignored.contains(&nearest.id) {
+                if self.get_component(&component_id).unwrap().is_overlap_only()
+                    || self.get_component(&nearest.id).unwrap().is_overlap_only()
+                {
+                    // Check only overlap
+                    if nearest.contains(cell) {
+                        return false;
+                    }
+                } else {
+                    return false;
+                }
+            }
+        }
+        return true;
+    }
+
+    /// Nets ending at the connection point
+    pub fn get_point_nets(&self, point: &GridDBConnectionPoint) -> impl Iterator<Item = Id> + '_ {
+        self.connected_nets.get(point).into_iter().flatten().copied()
+    }
+
+    /// Connection points docked in the cell
+    pub fn get_cell_connections(
+        &self,
+        cell: &GridPos,
+    ) -> impl Iterator<Item = GridDBConnectionPoint> + '_ {
+        self.connections.get(cell).into_iter().flatten().copied()
+    }
+
+    pub fn get_connected_nets(&self, component_id: &Id) -> HashSet<Id> {
+        let mut result = HashSet::new();
+        if let Some(comp) = self.get_component(component_id) {
+            comp.get_connection_dock_cells()
+                .iter()
+                .enumerate()
+                .for_each(|(inner_id, _cell)| {
+                    // TODO: simplify it
+                    if let Some(set) = self.connected_nets.get(&&GridDBConnectionPoint {
+                        component_id: *component_id,
+                        connection_id: inner_id,
+                    }) {
+                        result.extend(set);
+                    }
+                });
+        }
+        result
+    }
+
+    /// Names of the connected pins of the component, which have no match in `new_comp`
+    /// placed instead of it
+    pub fn get_lost_connections(&self, comp_id: Id, new_comp: &Component) -> Vec<String> {
+        let Some(old_comp) = self.get_component(&comp_id) else {
+            return vec![];
+        };
+        let connections_map = old_comp.get_replacement_connections(new_comp);
+        let mut lost = vec![];
+        for net_id in self.get_connected_nets(&comp_id) {
+            let net = self.get_net(&net_id).unwrap();
+            for point in [net.start_point, net.end_point] {
+                if point.component_id == comp_id
+                    && connections_map.get(&point.connection_id) == Some(&None)
+                {
+                    lost.push(
+                        old_comp
+                            .get_connection_name(point.connection_id)
+                            .filter(|name| !name.is_empty())
+                            .unwrap_or_else(|| format!("#{}", point.connection_id)),
+                    );
+                }
+            }
+        }
+        lost
+    }
+
+    pub fn is_available_location(&self, p: GridPos, dim: (i32, i32), component_id: Id) -> bool {
+        self.is_available_location_except(p, dim, component_id, &HashSet::new())
+    }
+
+    pub fn is_available_location_except(
+        &self,
+        p: GridPos,
+        dim: (i32, i32),
+        component_id: Id,
+        ignored: &HashSet<Id>,
+    ) -> bool {
+        for x in 0..dim.0 {
+            for y in 0..dim.1 {
+                if !self.is_available_cell_except(p + grid_pos(x, y), component_id, ignored) {
+                    return false;
+                }
+            }
+        }
+        return true;
+    }
+
+    pub fn dump_to_json(&self) -> Option<String> {
+        let mut result = vec![];
+        self.write_json(&mut result).ok()?;
+        String::from_utf8(result).ok()
+    }
+
+    /// Writes the project JSON without copying the components
+    pub fn write_json<W: std::io::Write>(&self, writer: W) -> serde_json::Result<()> {
+        serde_json::to_writer_pretty(
+            writer,
+            &GridDBDumpRef {
+                components: self.components.iter().map(|(id, c)| (*id, c)).collect(),
+                nets: self.nets.iter().map(|(id, n)| (*id, n)).collect(),
+                variables: &self.variables,
+                net_classes: &self.net_classes,
+                interfaces: &self.interfaces,
+                keep_out: &self.keep_out,
+                user_origin: self.user_origin,
+                view: self.view.as_ref(),
+            },
+        )
+    }
+
+    pub fn dump_to_svg(&self, theme: Theme, scale: f32, style: &NetStyle) -> String {
+        self.dump_to_annotated_svg(theme, scale, style, HashMap::new(), ExportGrid::NONE)
+    }
+
+    /// SVG with the simulation values of nets, see [`SvgExportJob::with_net_values`],
+    /// and the grid under the schematic
+    pub fn dump_to_annotated_svg(
+        &self,
+        theme: Theme,
+        scale: f32,
+        style: &NetStyle,
+        net_values: HashMap<Id, Logic>,
+        grid: ExportGrid,
+    ) -> String {
+        SvgExportJob::new(self, theme, scale, *style, vec![])
+            .and_then(|job| job.with_grid(grid))
+            .map(|job| job.with_net_values(net_values))
+            .and_then(|mut job| {
+                while !job.step(self, 1024)? {}
+                job.finish(None)
+            })
+            .ok()
+            .and_then(|svg| String::from_utf8(svg).ok())
+            .unwrap_or_default()
+    }
+
+    /// Bounding box of components and nets: (min, max)
+    pub fn get_bounds(&self) -> (GridPos, GridPos) {
+        let [min_x, min_y, max_x, max_y];
+        if self.components.values().len() >= 1 {
+            let [c_min_x, c_min_y, c_max_x, c_max_y];
+            let c_bbox = self.tree.root().envelope();
+            [c_min_x, c_min_y] = c_bbox.lower();
+            [c_max_x, c_max_y] = c_bbox.upper();
+
+            if self.nets.values().len() >= 1 {
+                let [n_min_x, n_min_y, n_max_x, n_max_y];
+                let n_bbox = self.net_tree.root().envelope();
+                [n_min_x, n_min_y] = n_bbox.lower();
+                [n_max_x, n_max_y] = n_bbox.upper();
+                min_x = c_min_x.min(n_min_x);
+                min_y = c_min_y.min(n_min_y);
+                max_x = c_max_x.max(n_max_x);
+                max_y = c_max_y.max(n_max_y);
+            } else {
+                [min_x, min_y, max_x, max_y] = [c_min_x, c_min_y, c_max_x, c_max_y];
+            }
+        } else {
+            [min_x, min_y, max_x, max_y] = [0, 0, 0, 0];
+        }
+        (grid_pos(min_x, min_y), grid_pos(max_x, max_y))
+    }
+
+    /// IDs of all components in ascending order
+    pub fn get_component_ids(&self) -> Vec<Id> {
+        let mut ids: Vec<Id> = self.components.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// IDs of all nets in ascending order
+    pub fn get_net_ids(&self) -> Vec<Id> {
+        let mut ids: Vec<Id> = self.nets.keys().copied().collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Renumbers components and nets with consecutive IDs keeping their order.
+    /// Nets connected to missing components are dropped.
+    /// Returns false if the IDs are already compact.
+    pub fn compact_ids(&mut self) -> bool {
+        let ids = self.get_component_ids();
+        let net_ids = self.get_net_ids();
+        let is_compact = |ids: &[Id]| ids.iter().enumerate().all(|(i, id)| i == *id);
+        if is_compact(&ids) && is_compact(&net_ids) {
+            return false;
+        }
+        let mut old = std::mem::take(self);
+        self.variables = std::mem::take(&mut old.variables);
+        self.net_classes = std::mem::take(&mut old.net_classes);
+        self.interfaces = std::mem::take(&mut old.interfaces);
+        self.project_name = std::mem::take(&mut old.project_name);
+        self.keep_out = std::mem::take(&mut old.keep_out);
+        self.user_origin = old.user_origin;
+        self.dangling_nets = std::mem::take(&mut old.dangling_nets);
+        let new_ids: HashMap<Id, Id> = ids.iter().enumerate().map(|(i, id)| (*id, i)).collect();
+        self.view = old.view.take().map(|view| ProjectView {
+            selection: view.selection.iter().filter_map(|id| new_ids.get(id).copied()).collect(),
+            ..view
+        });
+        for id in ids {
+            let mut component = old.components.remove(&id).unwrap();
+            if let Component::TextField(text) = &mut component {
+                text.remap_anchor(&new_ids);
+            }
+            let new_id = self.allocate_component();
+            self.insert_component(new_id, component);
+        }
+        for id in net_ids {
+            let mut net = old.nets.remove(&id).unwrap();
+            let (Some(start), Some(end)) = (
+                new_ids.get(&net.start_point.component_id),
+                new_ids.get(&net.end_point.component_id),
+            ) else {
+                continue;
+            };
+            net.start_point.component_id = *start;
+            net.end_point.component_id = *end;
+            let new_id = self.allocate_net();
+            self.insert_net(new_id, net);
+        }
+        true
+    }
+
+    /// Drops the orphaned nets, index entries and duplicate net classes, then renumbers IDs
+    pub fn compact(&mut self) -> CompactionReport {
+        let json_size = |db: &Self| db.dump_to_json().map_or(0, |json| json.len());
+        let mut report = CompactionReport {
+            size_before: json_size(self),
+            ..Default::default()
+        };
+        let orphaned_nets: Vec<Id> = self
+            .nets
+            .iter()
+            .filter(|(_, net)| {
+                [net.start_point, net.end_point]
+                    .iter()
+                    .any(|p| !self.components.contains_key(&p.component_id))
+            })
+            .map(|(id, _)| *id)
+            .collect();
+        for id in &orphaned_nets {
+            self.remove_net(id);
+        }
+        report.orphaned_nets = orphaned_nets.len();
+        let index_size = self.connections.len() + self.connected_nets.len();
+        self.connections.retain(|_, points| !points.is_empty());
+        self.connected_nets.retain(|_, nets| !nets.is_empty());
+        report.index_entries = index_size - self.connections.len() - self.connected_nets.len();
+        let mut names = HashSet::new();
+        let classes_number = self.net_classes.len();
+        self.net_classes.retain(|class| names.insert(class.name.clone()));
+        report.duplicate_classes = classes_number - self.net_classes.len();
+        report.changed = self.compact_ids() || report.orphaned_nets > 0;
+        report.size_after = json_size(self);
+        report
+    }
+
+    /// Writes the project JSON as SVG metadata, so the exported file can be opened again
+    pub fn write_svg_project_data<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        write!(writer, "<metadata id=\"{SVG_DATA_ID}\">")?;
+        self.write_json(XmlEscapeWriter(&mut *writer))?;
+        writeln!(writer, "</metadata>")
+    }
+
+    /// Extracts the project embedded by `write_svg_project_data`
+    pub fn load_from_svg(svg: &str) -> Option<Result<Self, serde_json::Error>> {
+        let start_tag = format!("<metadata id=\"{SVG_DATA_ID}\">");
+        let start = svg.find(&start_tag)? + start_tag.len();
+        let end = start + svg[start..].find("</metadata>")?;
+        let json = html_escape::decode_html_entities(&svg[start..end]);
+        Some(Self::load_from_json(json.into_owned()))
+    }
+
+    pub fn load_from_json(json: String) -> Result<Self, serde_json::Error> {
+        let dump: GridDBDump = serde_json::from_str(&json)?;
+        let mut result = Self::new();
+
+        // Allocate new nets and components:
+        if let Some(max_id) = dump.components.keys().max() {
+            result.next_component_id = max_id + 1;
+        }
+        if let Some(max_id) = dump.nets.keys().max() {
+            result.next_net_id = max_id + 1;
+        }
+
+        // The R-trees are bulk loaded, it's much faster than inserting the elements one by one:
+        let rects = dump.components.into_iter()
+            .map(|(id, component)| result.add_component(id, component))
+            .collect();
+        result.tree = RTree::bulk_load(rects);
+        let mut segments = vec![];
+        for (id, mut net) in dump.nets {
+            if result.is_valid_point(&net.start_point) && result.is_valid_point(&net.end_point) {
+                result.reattach_net(&mut net);
+                segments.extend(result.add_net(id, net));
+                continue;
+            }
+            let pos = net.points.first().copied().or_else(|| {
+                [net.start_point, net.end_point]
+                    .iter()
+                    .find_map(|p| result.get_component(&p.component_id))
+                    .map(|comp| comp.get_position())
+            });
+            result.dangling_nets.push(DanglingNet {
+                id,
+                pos: pos.unwrap_or(grid_pos(0, 0)),
+            });
+        }
+        result.net_tree = RTree::bulk_load(segments);
+        result.dangling_nets.sort_unstable_by_key(|net| net.id);
+        result.variables = dump.variables;
+        result.net_classes = dump.net_classes;
+        result.interfaces = dump.interfaces;
+        result.keep_out = dump.keep_out;
+        result.user_origin = dump.user_origin;
+        result.view = dump.view;
+        Ok(result)
+    }
+
+    /// Adds components and nets of `other` to the right of the current content
+    pub fn append(&mut self, other: GridDB) {
+        let offset = match (self.components.is_empty(), other.components.is_empty()) {
+            (false, false) => {
+                let [max_x, _] = self.tree.root().envelope().upper();
+                let [min_x, min_y] = other.tree.root().envelope().lower();
+                let [_, self_min_y] = self.tree.root().envelope().lower();
+                grid_pos(max_x + Self::APPEND_GAP - min_x, self_min_y - min_y)
+            }
+            _ => grid_pos(0, 0),
+        };
+        // Keep the classes of the appended nets:
+        for class in &other.net_classes {
+            if !self.net_classes.iter().any(|c| c.name == class.name) {
+                self.net_classes.push(class.clone());
+            }
+        }
+        for interface in &other.interfaces {
+            if !self.interfaces.iter().any(|i| i.name == interface.name) {
+                self.interfaces.push(interface.clone());
+            }
+        }
+        self.keep_out
+            .extend(other.keep_out.iter().map(|region| KeepOutRegion {
+                min: region.min + offset,
+                max: region.max + offset,
+            }));
+        let fragment = self.allocate_fragment(&other, offset);
+        for (id, component) in fragment.components {
+            self.insert_component(id, component);
+        }
+        for (id, net) in fragment.nets {
+            self.insert_net(id, net);
+        }
+    }
+
+    /// Components and nets of `fragment` moved by `offset` with newly allocated IDs,
+    /// to be inserted into this database. Nets to the missing components are dropped.
+    pub fn allocate_fragment(
+        &mut self,
+        fragment: &GridDB,
+        offset: GridPos,
+    ) -> AllocatedFragment {
+        let mut ids = HashMap::new();
+        let mut components = vec![];
+        for id in fragment.get_component_ids() {
+            let mut component = fragment.components[&id].clone();
+            let new_id = self.allocate_component();
+            component.set_pos(component.get_position() + offset);
+            components.push((new_id, component));
+            ids.insert(id, new_id);
+        }
+        for (_, component) in components.iter_mut() {
+            if let Component::TextField(text) = component {
+                text.remap_anchor(&ids);
+            }
+        }
+        let mut nets = vec![];
+        for id in fragment.get_net_ids() {
+            let mut net = fragment.nets[&id].clone();
+            let (Some(start), Some(end)) = (
+                ids.get(&net.start_point.component_id),
+                ids.get(&net.end_point.component_id),
+            ) else {
+                continue;
+            };
+            net.start_point.component_id = *start;
+            net.end_point.component_id = *end;
+            for p in net.points.iter_mut() {
+                *p += offset;
+            }
+            nets.push((self.allocate_net(), net));
+        }
+        AllocatedFragment { components, nets }
+    }
+
+    /// Copy of the components with nets between them, e.g. for the clipboard
+    pub fn extract_fragment(&self, ids: &[Id]) -> GridDB {
+        let mut result = GridDB::new();
+        for id in ids {
+            if let Some(component) = self.components.get(id) {
+                result.insert_component(*id, component.clone());
+            }
+        }
+        for id in self.get_net_ids() {
+            let net = &self.nets[&id];
+            if result.components.contains_key(&net.start_point.component_id)
+                && result.components.contains_key(&net.end_point.component_id)
+            {
+                result.insert_net(id, net.clone());
+            }
+        }
+        result.next_component_id = self.next_component_id;
+        result.next_net_id = self.next_net_id;
+        result.net_classes = self.net_classes.clone();
+        result
+    }
+
+    /// Database with the inner schematic of a hierarchical unit
+    pub fn from_schematic(schematic: &Schematic) -> GridDB {
+        let mut result = GridDB::new();
+        let rects = schematic.components.iter()
+            .map(|(id, component)| result.add_component(*id, component.clone()))
+            .collect();
+        result.tree = RTree::bulk_load(rects);
+        let segments = schematic.nets.iter()
+            .flat_map(|(id, net)| result.add_net(*id, net.clone()))
+            .collect();
+        result.net_tree = RTree::bulk_load(segments);
+        result.next_component_id = schematic.components.keys().max().map_or(0, |id| id + 1);
+        result.next_net_id = schematic.nets.keys().max().map_or(0, |id| id + 1);
+        result
+    }
+
+    /// Components and nets to be stored as the inner schematic of a unit
+    pub fn to_schematic(&self) -> Schematic {
+        Schematic {
+            components: self
+                .components
+                .iter()
+                .map(|(id, c)| (*id, c.clone()))
+                .collect(),
+            nets: self.nets.iter().map(|(id, n)| (*id, n.clone())).collect(),
+        }
+    }
+
+    /// Net between two connection points, which are docked in `start` and `end` cells
+    pub fn route_net(
+        &self,
+        start_point: GridDBConnectionPoint,
+        start: GridPos,
+        end_point: GridDBConnectionPoint,
+        end: GridPos,
+    ) -> Net {
+        let mut points = vec![start];
+        points.extend(self.find_net_path(start, end));
+        points.push(end);
+        Net {
+            start_point,
+            end_point,
+            points: simplify_path(points),
+            class: Default::default(),
+        }
+    }
+
+    /// Are all cells of the fragment components moved by `offset` free
+    pub fn is_free_fragment_location(&self, fragment: &GridDB, offset: GridPos) -> bool {
+        fragment.components.values().all(|c| {
+            self.is_free_location(c.get_position() + offset, c.get_dimension(), c.is_overlap_only())
+        })
+    }
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl GridDB {
+    /// Checks that the spatial and connection indices match the stored components and nets
+    pub fn check_indices(&self) -> Result<(), String> {
+        if self.tree.size() != self.components.len() {
+            return Err(format!(
+                "{} components, but {} rects in the tree",
+                self.components.len(),
+                self.tree.size()
+            ));
+        }
+        let mut connections: HashMap<GridPos, HashSet<GridDBConnectionPoint>> = HashMap::new();
+        for (id, component) in &self.components {
+            if !self.tree.contains(&component.get_grid_rect(*id)) {
+                return Err(format!("Component {id} is missing in the tree"));
+            }
+            for (i, cell) in component.get_connection_dock_cells().into_iter().enumerate() {
+                connections.entry(cell).or_default().insert(GridDBConnectionPoint {
+                    component_id: *id,
+                    connection_id: i,
+                });
+            }
+        }
+        if connections != self.connections {
+            return Err("Connections index is out of date".into());
+        }
+
+        let n_segments: usize = self.nets.values().map(|net| net.points.len() - 1).sum();
+        if self.net_tree.size() != n_segments {
+            return Err(format!(
+                "{n_segments} net segments, but {} in the tree",
+                self.net_tree.size()
+            ));
+        }
+        for (id, net) in &self.nets {
+            let segments = net.get_segments(*id);
+            if segments.iter().any(|s| !self.net_tree.contains(s)) {
+                return Err(format!("Segments of net {id} are missing in the tree"));
+            }
+            let stored = self.net_segments.get(id).map_or(&[][..], |s| s.as_slice());
+            if stored.len() != segments.len()
+                || stored.iter().zip(&segments).any(|(a, b)| !a.same_geometry(b))
+            {
+                return Err(format!("Stored segments of net {id} are out of date"));
+            }
+            for p in [net.start_point, net.end_point] {
+                if !self.connected_nets.get(&p).is_some_and(|nets| nets.contains(id)) {
+                    return Err(format!("Net {id} is not registered at {p:?}"));
+                }
+            }
+        }
+        if self.net_segments.len() != self.nets.len() {
+            return Err("Segments of removed nets are still stored".into());
+        }
+        for (p, nets) in &self.connected_nets {
+            for id in nets {
+                if !self
+                    .nets
+                    .get(id)
+                    .is_some_and(|net| net.start_point == *p || net.end_point == *p)
+                {
+                    return Err(format!("Removed net {id} is still registered at {p:?}"));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct GridDBDump {
+    components: HashMap<Id, Component>,
+    nets: HashMap<Id, Net>,
+    #[serde(default)]
+    variables: BTreeMap<String, String>,
+    #[serde(default = "NetClass::defaults")]
+    net_classes: Vec<NetClass>,
+    #[serde(default)]
+    interfaces: Vec<Interface>,
+    #[serde(default)]
+    keep_out: Vec<KeepOutRegion>,
+    #[serde(default)]
+    user_origin: Option<GridPos>,
+    #[serde(default)]
+    view: Option<ProjectView>,
+}
+
+// Sorted by ID, so saved files diff cleanly
+#[derive(Serialize)]
+struct GridDBDumpRef<'a> {
+    components: BTreeMap<Id, &'a Component>,
+    nets: BTreeMap<Id, &'a Net>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    variables: &'a BTreeMap<String, String>,
+    #[serde(skip_serializing_if = "NetClass::is_default_set")]
+    net_classes: &'a [NetClass],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    interfaces: &'a [Interface],
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    keep_out: &'a [KeepOutRegion],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    user_origin: Option<GridPos>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    view: Option<&'a ProjectView>,
+}
+
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize)]
+pub struct GridDBConnectionPoint {
+    pub component_id: Id,
+    pub connection_id: Id,
+}
+
+impl RTreeObject for NetSegment {
+    type Envelope = AABB<Point>;
+
+    fn envelope(&self) -> Self::Envelope {
+        AABB::from_corners(self.pos1.to_point(), self.pos2.to_point())
+    }
+}
+
+impl PartialEq for NetSegment {
+    fn eq(&self, other: &Self) -> bool {
+        other.inner_id == self.inner_id && self.net_id == other.net_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        grid_db::{
+            GridDB, GridPos, KeepOutRegion, Net, NetClass, PrimitiveType, Rotation, Theme,
+            grid_pos, grid_rect,
+        },
+        testing::{
+            GridDBBuilder, assert_golden, half_adder, json_snapshot, rotated_symbols, svg_snapshot,
+        },
+    };
+
+    #[test]
+    fn half_adder_json_golden() {
+        assert_golden("half_adder.json", &json_snapshot(&half_adder()));
+    }
+
+    #[test]
+    fn half_adder_svg_golden() {
+        assert_golden("half_adder.svg", &svg_snapshot(&half_adder(), Theme::Light));
+    }
+
+    #[test]
+    fn rotated_symbols_svg_golden() {
+        assert_golden(
+            "rotated_symbols.svg",
+            &svg_snapshot(&rotated_symbols(), Theme::Light),
+        );
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let db = half_adder();
+        let loaded = GridDB::load_from_json(db.dump_to_json().unwrap()).unwrap();
+        loaded.check_indices().unwrap();
+        assert_eq!(json_snapshot(&db), json_snapshot(&loaded));
+    }
+
+    #[test]
+    fn bulk_loaded_trees() {
+        // Chains of inverters:
+        let mut b = GridDBBuilder::new();
+        for row in 0..20 {
+            let mut prev = None;
+            for col in 0..20 {
+                let not = b.primitive(PrimitiveType::Not, col * 6, row * 4, Rotation::ROT0);
+                if let Some(prev) = prev {
+                    b.connect((prev, 1), (not, 0));
+                }
+                prev = Some(not);
+            }
+        }
+        let db = b.build();
+        let loaded = GridDB::load_from_json(db.dump_to_json().unwrap()).unwrap();
+        loaded.check_indices().unwrap();
+        let rect = grid_rect(0, grid_pos(10, 10), grid_pos(50, 30));
+        let ids = |db: &GridDB| {
+            let mut ids: Vec<_> = (db.get_visible_components(&rect).iter())
+                .map(|comp| comp.get_position())
+                .collect();
+            ids.sort_unstable_by_key(|pos| (pos.x, pos.y));
+            ids
+        };
+        assert_eq!(ids(&loaded), ids(&db));
+        assert!(!ids(&db).is_empty());
+        let segments = |db: &GridDB| db.get_visible_net_segments(&rect).len();
+        assert_eq!(segments(&loaded), segments(&db));
+        GridDB::from_schematic(&db.to_schematic()).check_indices().unwrap();
+    }
+
+    #[test]
+    fn json_is_sorted_by_id() {
+        let db = half_adder();
+        let json = db.dump_to_json().unwrap();
+        let loaded = GridDB::load_from_json(json.clone()).unwrap();
+        assert_eq!(json, loaded.dump_to_json().unwrap());
+        let positions: Vec<usize> = (0..7)
+            .map(|id| json.find(&format!("\"{id}\": {{")).unwrap())
+            .collect();
+        assert!(positions.is_sorted());
+    }
+
+    #[test]
+    fn compact_ids() {
+        let mut db = half_adder();
+        assert!(!db.compact_ids());
+        db.remove_component(&1);
+        db.remove_net(&0);
+        assert!(db.compact_ids());
+        db.check_indices().unwrap();
+        assert_eq!(db.get_component_ids(), (0..6).collect::<Vec<_>>());
+        // Nets of the removed input are dropped:
+        assert_eq!(db.get_net_ids(), (0..3).collect::<Vec<_>>());
+        assert_eq!(db.allocate_component(), 6);
+        assert!(!db.compact_ids());
+    }
+
+    #[test]
+    fn reattach_moved_pins() {
+        let mut b = GridDBBuilder::new();
+        let input = b.primitive(PrimitiveType::Input, 0, 0, Rotation::ROT0);
+        let gate = b.primitive(PrimitiveType::And(20), 10, 4, Rotation::ROT0);
+        let net = b.connect((input, 0), (gate, 20));
+        let db = b.build();
+        // Saved by a version with the other layout of the pins:
+        let json = db.dump_to_json().unwrap();
+        let mut json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let points = json["nets"][net.to_string()]["points"].as_array_mut().unwrap();
+        for point in points.iter_mut().rev().take(2) {
+            point[1] = (point[1].as_i64().unwrap() + 19).into();
+        }
+        let loaded = GridDB::load_from_json(json.to_string()).unwrap();
+        loaded.check_indices().unwrap();
+        let gate = loaded.get_component(&gate).unwrap();
+        let points = &loaded.get_net(&net).unwrap().points;
+        assert_eq!(points.last(), gate.get_connection_dock_cell(20).as_ref());
+        assert!(points.windows(2).all(|w| w[0].x == w[1].x || w[0].y == w[1].y));
+    }
+
+    #[test]
+    fn replace_net() {
+        let mut db = half_adder();
+        let id = db.get_net_ids()[0];
+        let old = db.get_net(&id).unwrap().clone();
+        let mut net = old.clone();
+        let last = net.points.len() - 1;
+        net.points.insert(last, net.points[last] + grid_pos(0, 3));
+        net.points.insert(last, net.points[last - 1] + grid_pos(0, 3));
+        let points = |net: Option<Net>| net.unwrap().points;
+        assert_eq!(points(db.replace_net(id, net.clone())), old.points);
+        db.check_indices().unwrap();
+        assert_eq!(db.get_net(&id).unwrap().points, net.points);
+        assert_eq!(points(db.replace_net(id, old.clone())), net.points);
+        db.check_indices().unwrap();
+        assert_eq!(points(db.remove_net(&id)), old.points);
+        db.check_indices().unwrap();
+        // Replacing the missing net inserts it:
+        assert!(db.replace_net(id, old).is_none());
+        db.check_indices().unwrap();
+    }
+
+    #[test]
+    fn project_compaction() {
+        let mut db = half_adder();
+        let mut duplicate = db.get_net_classes()[0].clone();
+        duplicate.color = None;
+        db.get_net_classes_mut().push(duplicate);
+        // Nets of the input are left behind, like in a damaged file:
+        db.remove_component(&1);
+        let report = db.compact();
+        assert!(report.changed);
+        assert_eq!(report.orphaned_nets, 2);
+        assert_eq!(report.duplicate_classes, 1);
+        assert!(report.size_after < report.size_before);
+        db.check_indices().unwrap();
+        assert_eq!(db.get_net_ids(), (0..4).collect::<Vec<_>>());
+        assert_eq!(db.get_net_classes().len(), NetClass::defaults().len());
+
+        let report = db.compact();
+        assert!(!report.changed);
+        assert_eq!(report.size_after, report.size_before);
+    }
+
+    #[test]
+    fn svg_round_trip() {
+        let db = half_adder();
+        let svg = db.dump_to_svg(Theme::Dark, 20.0, &Default::default());
+        assert!(GridDB::load_from_svg(&svg).is_none());
+
+        let mut svg = vec![];
+        db.write_svg_project_data(&mut svg).unwrap();
+        let loaded = GridDB::load_from_svg(&String::from_utf8(svg).unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(json_snapshot(&db), json_snapshot(&loaded));
+    }
+
+    #[test]
+    fn template_variables() {
+        let mut db = half_adder();
+        db.set_project_name("adder");
+        db.get_variables_mut().insert("revision".into(), "B".into());
+        let loaded = GridDB::load_from_json(db.dump_to_json().unwrap()).unwrap();
+        assert_eq!(loaded.get_variables(), db.get_variables());
+        assert_eq!(
+            db.expand_variables("{{project}} rev. {{revision}}, {{sheet}}"),
+            "adder rev. B, {{sheet}}"
+        );
+        assert_eq!(db.expand_variables("{{date}}").len(), "2026-01-01".len());
+    }
+
+    #[test]
+    fn keep_out_regions() {
+        let mut db = GridDB::new();
+        let region = KeepOutRegion::new(grid_pos(6, 5), grid_pos(4, 0));
+        assert_eq!(region.min, grid_pos(4, 0));
+        db.get_keep_out_regions_mut().push(region);
+        assert!(!db.is_free_cell(grid_pos(5, 0), false));
+        assert!(db.is_free_cell(grid_pos(5, 0), true));
+        assert!(db.is_free_cell(grid_pos(7, 0), false));
+
+        // The middle of the path is moved aside:
+        let path = db.find_net_path(grid_pos(0, -2), grid_pos(10, 2));
+        assert_eq!(path, [grid_pos(7, -2), grid_pos(7, 2)]);
+        assert_eq!(
+            db.find_keep_out_crossing(&[grid_pos(0, 3), grid_pos(10, 3)]),
+            Some(grid_pos(4, 3))
+        );
+
+        let loaded = GridDB::load_from_json(db.dump_to_json().unwrap()).unwrap();
+        assert_eq!(loaded.get_keep_out_regions(), [region]);
+    }
+
+    #[test]
+    fn user_origin() {
+        let mut db = half_adder();
+        assert_eq!(db.get_origin(), grid_pos(0, 0));
+        db.set_user_origin(Some(grid_pos(3, -2)));
+        let loaded = GridDB::load_from_json(db.dump_to_json().unwrap()).unwrap();
+        assert_eq!(loaded.get_origin(), grid_pos(3, -2));
+        db.set_user_origin(Some(grid_pos(0, 0)));
+        assert_eq!(db.dump_to_json(), half_adder().dump_to_json());
+    }
+
+    #[test]
+    fn free_location_search() {
+        let db = half_adder();
+        let (min, max) = db.get_bounds();
+        let center = grid_pos((min.x + max.x) / 2, (min.y + max.y) / 2);
+        assert!(!db.is_free_location(center, (2, 2), false));
+        let found = db.find_free_location(center, (2, 2), false).unwrap();
+        assert!(db.is_free_location(found, (2, 2), false));
+        // Nothing closer is free:
+        let dist = |p: GridPos| (p.x - center.x).abs().max((p.y - center.y).abs());
+        for x in -dist(found) + 1..dist(found) {
+            for y in -dist(found) + 1..dist(found) {
+                assert!(!db.is_free_location(center + grid_pos(x, y), (2, 2), false));
+            }
+        }
+        let free = grid_pos(max.x + 10, 0);
+        assert_eq!(db.find_free_location(free, (2, 2), false), Some(free));
+    }
+
+    #[test]
+    fn fragment_copy() {
+        let mut db = half_adder();
+        let ids = db.get_component_ids();
+        // The first input and XOR, with the net between them:
+        let fragment = db.extract_fragment(&[ids[0], ids[2]]);
+        assert_eq!(fragment.get_component_ids(), [ids[0], ids[2]]);
+        assert_eq!(fragment.get_net_ids().len(), 1);
+
+        let offset = grid_pos(0, 20);
+        assert!(!db.is_free_fragment_location(&fragment, grid_pos(0, 0)));
+        assert!(db.is_free_fragment_location(&fragment, offset));
+        let allocated = db.allocate_fragment(&fragment, offset);
+        let new_ids: Vec<_> = allocated.components.iter().map(|(id, _)| *id).collect();
+        assert!(new_ids.iter().all(|id| !ids.contains(id)));
+        let (_, net) = &allocated.nets[0];
+        assert_eq!(
+            (net.start_point.component_id, net.end_point.component_id),
+            (new_ids[0], new_ids[1])
+        );
+        for (id, component) in allocated.components {
+            db.insert_component(id, component);
+        }
+        for (id, net) in allocated.nets {
+            db.insert_net(id, net);
+        }
+        db.check_indices().unwrap();
+        let pasted = db.get_component(&new_ids[1]).unwrap();
+        let original = db.get_component(&ids[2]).unwrap();
+        assert_eq!(pasted.get_position(), original.get_position() + offset);
+    }
+}
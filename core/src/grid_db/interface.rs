@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+
+use crate::grid_db::{Port, Rotation, SharedStr, Unit};
+
+/// Port of the interface definition
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct InterfacePort {
+    pub name: String,
+    /// Direction on the master side, it's inverted on the mirrored (slave) side
+    pub is_input: bool,
+    pub width: usize,
+}
+
+/// Named set of ports repeated on many units, like a subset of AXI-lite
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Interface {
+    pub name: String,
+    pub ports: Vec<InterfacePort>,
+}
+
+/// Membership of the unit port in the interface instance
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PortBundle {
+    /// Name of the interface instance, the prefix of the port names like `s_axil`
+    pub name: SharedStr,
+    pub interface: SharedStr,
+    /// Name of the port in the interface definition
+    pub port: SharedStr,
+}
+
+impl Interface {
+    pub const MAX_WIDTH: usize = 1024;
+
+    /// Name of the port in the bundle, like `s_axil_wdata[31:0]`
+    fn get_port_name(bundle: &str, port: &InterfacePort) -> String {
+        match port.width > 1 {
+            true => format!("{bundle}_{}[{}:0]", port.name, port.width - 1),
+            false => format!("{bundle}_{}", port.name),
+        }
+    }
+}
+
+impl Unit {
+    /// Adds ports of the interface instance named `bundle` below the existing ports:
+    /// inputs on the left side and outputs on the right side. The unit grows to fit them.
+    pub fn add_interface(&mut self, interface: &Interface, bundle: &str, mirrored: bool) {
+        let last_row = |align| {
+            self.ports
+                .iter()
+                .filter(|p| p.align == align)
+                .map(|p| p.offset)
+                .max()
+                .unwrap_or(0)
+        };
+        let (mut left, mut right) = (last_row(Rotation::ROT0), last_row(Rotation::ROT180));
+        for port in &interface.ports {
+            let (align, row) = match port.is_input != mirrored {
+                true => (Rotation::ROT0, &mut left),
+                false => (Rotation::ROT180, &mut right),
+            };
+            *row += 1;
+            self.ports.push(Port {
+                offset: *row,
+                align,
+                name: Interface::get_port_name(bundle, port).into(),
+                bundle: Some(PortBundle {
+                    name: bundle.into(),
+                    interface: interface.name.as_str().into(),
+                    port: port.name.as_str().into(),
+                }),
+            });
+        }
+        self.height = self.height.max(left.max(right) + 1);
+    }
+
+    /// Input ports named like `clk` or `s_axi_aclock` are placed on the bottom side
+    fn is_clock_port(port: &Port) -> bool {
+        let name = port.name.to_lowercase();
+        port.align != Rotation::ROT180 && (name.contains("clk") || name.contains("clock"))
+    }
+
+    /// Places the ports like a generated symbol: inputs on the left, outputs on the right
+    /// and clocks on the bottom, spaced evenly in their order. Ports on the right side are outputs.
+    /// The unit is resized to fit them, port indices are kept.
+    pub fn generate_symbol(&mut self) {
+        const MIN_WIDTH: i32 = 4;
+        let sides: Vec<Rotation> = self
+            .ports
+            .iter()
+            .map(|port| match port.align {
+                Rotation::ROT180 => Rotation::ROT180,
+                _ if Self::is_clock_port(port) => Rotation::ROT270,
+                _ => Rotation::ROT0,
+            })
+            .collect();
+        let count = |side| sides.iter().filter(|s| **s == side).count() as i32;
+        let (left, right, bottom) = (
+            count(Rotation::ROT0),
+            count(Rotation::ROT180),
+            count(Rotation::ROT270),
+        );
+        self.height = (left.max(right) + 1).max(2);
+        self.width = (bottom + 1).max(MIN_WIDTH);
+        for side in [Rotation::ROT0, Rotation::ROT180, Rotation::ROT270] {
+            let length = match side {
+                Rotation::ROT270 => self.width,
+                _ => self.height,
+            };
+            let n = count(side);
+            let ports = self.ports.iter_mut().zip(&sides);
+            for (i, (port, _)) in ports.filter(|(_, s)| **s == side).enumerate() {
+                port.align = side;
+                port.offset = (i as i32 + 1) * length / (n + 1);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Interface, InterfacePort};
+    use crate::grid_db::{Port, Rotation, Unit, grid_pos};
+
+    #[test]
+    fn interface_ports() {
+        let port = |name: &str, is_input, width| InterfacePort {
+            name: name.into(),
+            is_input,
+            width,
+        };
+        let interface = Interface {
+            name: "axil_w".into(),
+            ports: vec![
+                port("awvalid", false, 1),
+                port("wdata", false, 32),
+                port("wready", true, 1),
+            ],
+        };
+        let mut unit = Unit {
+            pos: grid_pos(0, 0),
+            width: 4,
+            height: 2,
+            ports: vec![],
+            parameters: vec![],
+            description: String::new(),
+            name: Default::default(),
+            source: None,
+            schematic: None,
+        };
+        unit.add_interface(&interface, "m", false);
+        unit.add_interface(&interface, "s", true);
+        let ports: Vec<_> = unit
+            .ports
+            .iter()
+            .map(|p| (p.name.to_string(), p.align, p.offset))
+            .collect();
+        let [left, right] = [Rotation::ROT0, Rotation::ROT180];
+        assert_eq!(
+            ports,
+            [
+                ("m_awvalid".into(), right, 1),
+                ("m_wdata[31:0]".into(), right, 2),
+                ("m_wready".into(), left, 1),
+                ("s_awvalid".into(), left, 2),
+                ("s_wdata[31:0]".into(), left, 3),
+                ("s_wready".into(), right, 3),
+            ]
+        );
+        assert_eq!(unit.height, 4);
+        let bundle = unit.ports[4].bundle.as_ref().unwrap();
+        assert_eq!(
+            (&*bundle.name, &*bundle.interface, &*bundle.port),
+            ("s", "axil_w", "wdata")
+        );
+    }
+
+    #[test]
+    fn generated_symbol() {
+        let port = |name: &str, align| Port {
+            offset: 0,
+            align,
+            name: name.into(),
+            bundle: None,
+        };
+        let mut unit = Unit {
+            pos: grid_pos(0, 0),
+            width: 10,
+            height: 10,
+            ports: vec![
+                port("q", Rotation::ROT180),
+                port("d", Rotation::ROT90),
+                port("clk", Rotation::ROT0),
+                port("en", Rotation::ROT0),
+                port("rst", Rotation::ROT270),
+            ],
+            parameters: vec![],
+            description: String::new(),
+            name: Default::default(),
+            source: None,
+            schematic: None,
+        };
+        unit.generate_symbol();
+        let ports: Vec<_> = unit.ports.iter().map(|p| (p.align, p.offset)).collect();
+        let [left, right, bottom] = [Rotation::ROT0, Rotation::ROT180, Rotation::ROT270];
+        assert_eq!(
+            ports,
+            [(right, 2), (left, 1), (bottom, 2), (left, 2), (left, 3)]
+        );
+        assert_eq!((unit.width, unit.height), (4, 4));
+        // Running again keeps the symbol:
+        let before = unit.ports.clone();
+        unit.generate_symbol();
+        assert!(
+            unit.ports
+                .iter()
+                .zip(&before)
+                .all(|(a, b)| { (a.align, a.offset) == (b.align, b.offset) })
+        );
+    }
+}
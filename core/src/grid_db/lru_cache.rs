@@ -0,0 +1,116 @@
+//! Caches with a limited number of entries, the least recently used ones are dropped first.
+
+use std::{collections::HashMap, hash::Hash};
+
+/// Counters of the cache use, shown in the profiler
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub budget: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+pub struct LruCache<K, V> {
+    /// Values with the ticks of their last use
+    entries: HashMap<K, (V, u64)>,
+    tick: u64,
+    stats: CacheStats,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> LruCache<K, V> {
+    pub fn new(budget: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            tick: 0,
+            stats: CacheStats {
+                budget: budget.max(1),
+                ..Default::default()
+            },
+        }
+    }
+
+    /// Value of the key, it becomes the most recently used
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        self.tick += 1;
+        match self.entries.get_mut(key) {
+            Some((value, used)) => {
+                *used = self.tick;
+                self.stats.hits += 1;
+                Some(value.clone())
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: V) {
+        self.tick += 1;
+        self.entries.insert(key, (value, self.tick));
+        self.evict();
+    }
+
+    pub fn get_or_insert_with(&mut self, key: K, f: impl FnOnce() -> V) -> V {
+        if let Some(value) = self.get(&key) {
+            return value;
+        }
+        let value = f();
+        self.insert(key, value.clone());
+        value
+    }
+
+    /// Changes the max number of entries, the extra ones are dropped
+    pub fn set_budget(&mut self, budget: usize) {
+        self.stats.budget = budget.max(1);
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        let extra = self.entries.len().saturating_sub(self.stats.budget);
+        if extra == 0 {
+            return;
+        }
+        let mut keys: Vec<(u64, K)> = self
+            .entries
+            .iter()
+            .map(|(key, (_, used))| (*used, key.clone()))
+            .collect();
+        keys.select_nth_unstable_by_key(extra - 1, |(used, _)| *used);
+        for (_, key) in &keys[..extra] {
+            self.entries.remove(key);
+        }
+        self.stats.evictions += extra as u64;
+    }
+
+    pub fn get_stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.len(),
+            ..self.stats
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LruCache;
+
+    #[test]
+    fn lru_eviction() {
+        let mut cache = LruCache::new(2);
+        assert_eq!(cache.get_or_insert_with(1, || "a"), "a");
+        cache.get_or_insert_with(2, || "b");
+        // The first entry is used again, so the second one is dropped:
+        assert_eq!(cache.get_or_insert_with(1, || "new"), "a");
+        cache.get_or_insert_with(3, || "c");
+        assert_eq!(cache.get_or_insert_with(2, || "new"), "new");
+        let stats = cache.get_stats();
+        assert_eq!((stats.entries, stats.hits, stats.misses), (2, 1, 4));
+        assert_eq!(stats.evictions, 2);
+        cache.set_budget(1);
+        assert_eq!(cache.get_stats().entries, 1);
+        assert_eq!(cache.get_or_insert_with(2, || "newer"), "new");
+    }
+}
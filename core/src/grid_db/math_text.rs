@@ -0,0 +1,628 @@
+//! Inline math in text fields, like `$f = \overline{a \cdot b}$`.
+//! A small subset of TeX is laid out with the glyphs of the monospace font,
+//! so that the SVG export gets the same formulas as outlined paths.
+
+use std::{fmt::Write, iter::Peekable, str::Chars, sync::OnceLock};
+
+use ab_glyph::{Font, FontArc, OutlineCurve, ScaleFont};
+use epaint::{
+    Color32, FontFamily, FontId, Pos2, Stroke, Vec2, emath::Align2, pos2, text::FontDefinitions,
+    vec2,
+};
+
+use crate::canvas::Canvas;
+
+/// Scripts are smaller than their base
+const SCRIPT_SCALE: f32 = 0.7;
+/// Numerators and denominators are smaller than the surrounding text
+const FRACTION_SCALE: f32 = 0.8;
+/// Height of the fraction bars above the baseline, relative to the font size
+const AXIS_HEIGHT: f32 = 0.25;
+/// Thickness of the bars, relative to the font size
+const RULE_THICKNESS: f32 = 0.06;
+
+/// Named symbols of TeX
+const SYMBOLS: &[(&str, char)] = &[
+    ("alpha", 'α'),
+    ("beta", 'β'),
+    ("gamma", 'γ'),
+    ("delta", 'δ'),
+    ("epsilon", 'ε'),
+    ("theta", 'θ'),
+    ("lambda", 'λ'),
+    ("mu", 'μ'),
+    ("pi", 'π'),
+    ("rho", 'ρ'),
+    ("sigma", 'σ'),
+    ("tau", 'τ'),
+    ("phi", 'φ'),
+    ("omega", 'ω'),
+    ("Gamma", 'Γ'),
+    ("Delta", 'Δ'),
+    ("Theta", 'Θ'),
+    ("Pi", 'Π'),
+    ("Sigma", 'Σ'),
+    ("Phi", 'Φ'),
+    ("Omega", 'Ω'),
+    ("cdot", '·'),
+    ("times", '×'),
+    ("div", '÷'),
+    ("pm", '±'),
+    ("oplus", '⊕'),
+    ("otimes", '⊗'),
+    ("wedge", '∧'),
+    ("land", '∧'),
+    ("vee", '∨'),
+    ("lor", '∨'),
+    ("neg", '¬'),
+    ("lnot", '¬'),
+    ("le", '≤'),
+    ("leq", '≤'),
+    ("ge", '≥'),
+    ("geq", '≥'),
+    ("ne", '≠'),
+    ("neq", '≠'),
+    ("equiv", '≡'),
+    ("approx", '≈'),
+    ("to", '→'),
+    ("rightarrow", '→'),
+    ("leftarrow", '←'),
+    ("Rightarrow", '⇒'),
+    ("leftrightarrow", '↔'),
+    ("uparrow", '↑'),
+    ("downarrow", '↓'),
+    ("infty", '∞'),
+    ("sum", '∑'),
+    ("prod", '∏'),
+    ("forall", '∀'),
+    ("exists", '∃'),
+    ("in", '∈'),
+    ("partial", '∂'),
+    ("ldots", '…'),
+];
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum MathNode {
+    Symbol(char),
+    Group(Vec<MathNode>),
+    Scripts {
+        base: Box<MathNode>,
+        sup: Option<Box<MathNode>>,
+        sub: Option<Box<MathNode>>,
+    },
+    Overline(Box<MathNode>),
+    Fraction(Box<MathNode>, Box<MathNode>),
+    Sqrt(Box<MathNode>),
+}
+
+impl MathNode {
+    fn with_script(self, is_sup: bool, script: MathNode) -> MathNode {
+        let (base, mut sup, mut sub) = match self {
+            MathNode::Scripts { base, sup, sub }
+                if (is_sup && sup.is_none()) || (!is_sup && sub.is_none()) =>
+            {
+                (base, sup, sub)
+            }
+            node => (Box::new(node), None, None),
+        };
+        match is_sup {
+            true => sup = Some(Box::new(script)),
+            false => sub = Some(Box::new(script)),
+        }
+        MathNode::Scripts { base, sup, sub }
+    }
+}
+
+struct MathParser<'a> {
+    chars: Peekable<Chars<'a>>,
+}
+
+impl MathParser<'_> {
+    /// Nodes until the end of the formula or the closing brace of the group
+    fn parse_list(&mut self, in_group: bool) -> Vec<MathNode> {
+        let mut nodes = vec![];
+        while let Some(c) = self.chars.peek().copied() {
+            match c {
+                '}' if in_group => {
+                    self.chars.next();
+                    break;
+                }
+                '^' | '_' => {
+                    self.chars.next();
+                    let script = self.parse_arg();
+                    let base = nodes.pop().unwrap_or(MathNode::Group(vec![]));
+                    nodes.push(base.with_script(c == '^', script));
+                }
+                // Spaces are ignored in TeX math
+                c if c.is_whitespace() => {
+                    self.chars.next();
+                }
+                _ => nodes.extend(self.parse_atom()),
+            }
+        }
+        nodes
+    }
+
+    /// Argument of a command or a script, empty if it's missing
+    fn parse_arg(&mut self) -> MathNode {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+        self.parse_atom().unwrap_or(MathNode::Group(vec![]))
+    }
+
+    fn parse_atom(&mut self) -> Option<MathNode> {
+        Some(match self.chars.next()? {
+            '{' => MathNode::Group(self.parse_list(true)),
+            '\\' => self.parse_command(),
+            c => MathNode::Symbol(c),
+        })
+    }
+
+    fn parse_command(&mut self) -> MathNode {
+        let mut name = String::new();
+        while let Some(c) = self.chars.next_if(|c| c.is_ascii_alphabetic()) {
+            name.push(c);
+        }
+        if name.is_empty() {
+            // Escaped character, like `\{` or `\$`
+            return match self.chars.next() {
+                Some(',' | ';' | ':' | ' ') => MathNode::Symbol(' '),
+                Some(c) => MathNode::Symbol(c),
+                None => MathNode::Symbol('\\'),
+            };
+        }
+        match name.as_str() {
+            "overline" | "bar" => MathNode::Overline(Box::new(self.parse_arg())),
+            "frac" => {
+                let numerator = self.parse_arg();
+                MathNode::Fraction(Box::new(numerator), Box::new(self.parse_arg()))
+            }
+            "sqrt" => MathNode::Sqrt(Box::new(self.parse_arg())),
+            "quad" => MathNode::Group(vec![MathNode::Symbol(' '); 2]),
+            name => match SYMBOLS.iter().find(|(symbol, _)| *symbol == name) {
+                Some((_, c)) => MathNode::Symbol(*c),
+                // Unknown commands are shown as is
+                None => {
+                    MathNode::Group(format!("\\{name}").chars().map(MathNode::Symbol).collect())
+                }
+            },
+        }
+    }
+}
+
+pub fn parse_math(formula: &str) -> Vec<MathNode> {
+    MathParser {
+        chars: formula.chars().peekable(),
+    }
+    .parse_list(false)
+}
+
+/// Parts of the line: plain text and formulas between `$` signs.
+/// `\$` is a dollar sign in the plain text, unpaired `$` is kept as is.
+fn split_math(line: &str) -> Vec<(bool, String)> {
+    let mut parts = vec![];
+    let mut text = String::new();
+    let mut rest = line;
+    while let Some(i) = rest.find(['$', '\\']) {
+        text.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if let Some(escaped) = rest.strip_prefix("\\$") {
+            text.push('$');
+            rest = escaped;
+        } else if rest.starts_with('\\') {
+            text.push('\\');
+            rest = &rest[1..];
+        } else if let Some(end) = find_closing_dollar(&rest[1..]) {
+            if !text.is_empty() {
+                parts.push((false, std::mem::take(&mut text)));
+            }
+            parts.push((true, rest[1..end + 1].to_string()));
+            rest = &rest[end + 2..];
+        } else {
+            break;
+        }
+    }
+    text.push_str(rest);
+    if !text.is_empty() {
+        parts.push((false, text));
+    }
+    parts
+}
+
+/// Position of the first `$`, which is not escaped
+fn find_closing_dollar(formula: &str) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in formula.char_indices() {
+        match c {
+            '$' if !escaped => return Some(i),
+            '\\' => escaped = !escaped,
+            _ => escaped = false,
+        }
+    }
+    None
+}
+
+/// Fonts of the monospace family of egui in the order of fallback,
+/// the same glyphs are drawn on the canvas and exported to SVG
+fn get_fonts() -> &'static [FontArc] {
+    static FONTS: OnceLock<Vec<FontArc>> = OnceLock::new();
+    FONTS.get_or_init(|| {
+        let definitions = FontDefinitions::default();
+        definitions.families[&FontFamily::Monospace]
+            .iter()
+            .filter_map(|name| definitions.font_data.get(name))
+            .filter_map(|data| FontArc::try_from_vec(data.font.to_vec()).ok())
+            .collect()
+    })
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct MathGlyph {
+    pub ch: char,
+    /// Index of the font in the fallback list
+    font: usize,
+    /// Left end of the baseline
+    pub pos: Pos2,
+    pub size: f32,
+    /// Height of the font above the baseline
+    pub ascent: f32,
+}
+
+/// Fraction bars, overlines and radicals
+#[derive(Clone, PartialEq, Debug)]
+pub struct MathRule {
+    pub from: Pos2,
+    pub to: Pos2,
+    pub width: f32,
+}
+
+/// Laid out glyphs and rules. The origin is at the left end of the baseline
+/// for the parts of formulas, and at the top left corner for the laid out text.
+#[derive(Clone, Default, PartialEq, Debug)]
+pub struct MathBox {
+    pub glyphs: Vec<MathGlyph>,
+    pub rules: Vec<MathRule>,
+    pub width: f32,
+    /// Extents above and below the baseline
+    pub ascent: f32,
+    pub descent: f32,
+}
+
+impl MathBox {
+    fn translate(&mut self, offset: Vec2) {
+        for glyph in &mut self.glyphs {
+            glyph.pos += offset;
+        }
+        for rule in &mut self.rules {
+            rule.from += offset;
+            rule.to += offset;
+        }
+    }
+
+    /// Puts the other box to the right on the same baseline
+    fn append(&mut self, mut other: MathBox) {
+        other.translate(vec2(self.width, 0.0));
+        self.glyphs.append(&mut other.glyphs);
+        self.rules.append(&mut other.rules);
+        self.width += other.width;
+        self.ascent = self.ascent.max(other.ascent);
+        self.descent = self.descent.max(other.descent);
+    }
+
+    /// Puts the other box at `offset` without changing the extents
+    fn add(&mut self, mut other: MathBox, offset: Vec2) {
+        other.translate(offset);
+        self.glyphs.append(&mut other.glyphs);
+        self.rules.append(&mut other.rules);
+    }
+
+    fn rule(&mut self, from: Pos2, to: Pos2, size: f32) {
+        self.rules.push(MathRule {
+            from,
+            to,
+            width: size * RULE_THICKNESS,
+        });
+    }
+
+    /// Draws the box with the top left corner at `pos`
+    pub fn paint(&self, painter: &impl Canvas, pos: Pos2, color: Color32) {
+        for glyph in &self.glyphs {
+            painter.text(
+                pos + vec2(glyph.pos.x, glyph.pos.y - glyph.ascent),
+                Align2::LEFT_TOP,
+                glyph.ch,
+                FontId::monospace(glyph.size),
+                color,
+            );
+        }
+        for rule in &self.rules {
+            let points = [pos + rule.from.to_vec2(), pos + rule.to.to_vec2()];
+            painter.line_segment(points, Stroke::new(rule.width, color));
+        }
+    }
+
+    /// SVG path data of the outlines of the glyphs and the rules,
+    /// with the top left corner at `pos`
+    pub fn to_svg_path(&self, pos: Pos2) -> String {
+        let fonts = get_fonts();
+        let mut d = String::new();
+        for glyph in &self.glyphs {
+            let font = &fonts[glyph.font];
+            let Some(outline) = font.outline(font.glyph_id(glyph.ch)) else {
+                continue;
+            };
+            let scale = font.as_scaled(glyph.size).h_scale_factor();
+            let origin = pos + glyph.pos.to_vec2();
+            // Font units are upside down
+            let point = |p: ab_glyph::Point| origin + vec2(p.x * scale, -p.y * scale);
+            let mut last = None;
+            for curve in &outline.curves {
+                let (start, end) = match curve {
+                    OutlineCurve::Line(a, b) => (*a, *b),
+                    OutlineCurve::Quad(a, _, b) => (*a, *b),
+                    OutlineCurve::Cubic(a, _, _, b) => (*a, *b),
+                };
+                if last != Some(start) {
+                    if last.is_some() {
+                        d.push('Z');
+                    }
+                    let p = point(start);
+                    _ = write!(d, "M{:.2} {:.2}", p.x, p.y);
+                }
+                _ = match curve {
+                    OutlineCurve::Line(_, b) => {
+                        let b = point(*b);
+                        write!(d, "L{:.2} {:.2}", b.x, b.y)
+                    }
+                    OutlineCurve::Quad(_, c, b) => {
+                        let (c, b) = (point(*c), point(*b));
+                        write!(d, "Q{:.2} {:.2} {:.2} {:.2}", c.x, c.y, b.x, b.y)
+                    }
+                    OutlineCurve::Cubic(_, c1, c2, b) => {
+                        let (c1, c2, b) = (point(*c1), point(*c2), point(*b));
+                        let (x1, y1, x2, y2) = (c1.x, c1.y, c2.x, c2.y);
+                        write!(d, "C{x1:.2} {y1:.2} {x2:.2} {y2:.2} {:.2} {:.2}", b.x, b.y)
+                    }
+                };
+                last = Some(end);
+            }
+            if last.is_some() {
+                d.push('Z');
+            }
+        }
+        // Rules are filled rectangles around the segments
+        for rule in &self.rules {
+            let normal = (rule.to - rule.from).normalized().rot90() * rule.width * 0.5;
+            let (from, to) = (pos + rule.from.to_vec2(), pos + rule.to.to_vec2());
+            let corners = [from + normal, to + normal, to - normal, from - normal];
+            for (i, p) in corners.iter().enumerate() {
+                let command = if i == 0 { 'M' } else { 'L' };
+                _ = write!(d, "{command}{:.2} {:.2}", p.x, p.y);
+            }
+            d.push('Z');
+        }
+        d
+    }
+}
+
+fn layout_symbol(ch: char, size: f32) -> MathBox {
+    let fonts = get_fonts();
+    let font_index = fonts
+        .iter()
+        .position(|font| font.glyph_id(ch).0 != 0)
+        .unwrap_or(0);
+    let Some(font) = fonts.get(font_index) else {
+        return MathBox::default();
+    };
+    let font = font.as_scaled(size);
+    let ascent = font.ascent();
+    MathBox {
+        glyphs: match ch.is_whitespace() {
+            true => vec![],
+            false => vec![MathGlyph {
+                ch,
+                font: font_index,
+                pos: Pos2::ZERO,
+                size,
+                ascent,
+            }],
+        },
+        rules: vec![],
+        width: font.h_advance(font.glyph_id(ch)),
+        ascent,
+        descent: -font.descent(),
+    }
+}
+
+fn layout_list(nodes: &[MathNode], size: f32) -> MathBox {
+    let mut result = MathBox::default();
+    for node in nodes {
+        result.append(layout_node(node, size));
+    }
+    result
+}
+
+fn layout_node(node: &MathNode, size: f32) -> MathBox {
+    let gap = size * 0.1;
+    match node {
+        MathNode::Symbol(ch) => layout_symbol(*ch, size),
+        MathNode::Group(nodes) => layout_list(nodes, size),
+        MathNode::Scripts { base, sup, sub } => {
+            let mut result = layout_node(base, size);
+            let x = result.width;
+            let mut width = 0.0f32;
+            if let Some(sup) = sup {
+                let sup = layout_node(sup, size * SCRIPT_SCALE);
+                let shift = result.ascent * 0.5;
+                result.ascent = result.ascent.max(shift + sup.ascent);
+                width = width.max(sup.width);
+                result.add(sup, vec2(x, -shift));
+            }
+            if let Some(sub) = sub {
+                let sub = layout_node(sub, size * SCRIPT_SCALE);
+                let shift = (result.descent * 0.8).max(size * 0.15);
+                result.descent = result.descent.max(shift + sub.descent);
+                width = width.max(sub.width);
+                result.add(sub, vec2(x, shift));
+            }
+            result.width += width;
+            result
+        }
+        MathNode::Overline(node) => {
+            let mut result = layout_node(node, size);
+            let y = -(result.ascent + gap);
+            result.rule(pos2(0.0, y), pos2(result.width, y), size);
+            result.ascent += gap + size * RULE_THICKNESS;
+            result
+        }
+        MathNode::Fraction(numerator, denominator) => {
+            let numerator = layout_node(numerator, size * FRACTION_SCALE);
+            let denominator = layout_node(denominator, size * FRACTION_SCALE);
+            let axis = size * AXIS_HEIGHT;
+            let width = numerator.width.max(denominator.width) + gap * 2.0;
+            let mut result = MathBox {
+                width,
+                ascent: axis + gap + numerator.descent + numerator.ascent,
+                descent: (gap + denominator.ascent + denominator.descent - axis).max(0.0),
+                ..Default::default()
+            };
+            result.rule(pos2(0.0, -axis), pos2(width, -axis), size);
+            let offset = vec2(
+                (width - numerator.width) / 2.0,
+                -axis - gap - numerator.descent,
+            );
+            result.add(numerator, offset);
+            let offset = vec2(
+                (width - denominator.width) / 2.0,
+                -axis + gap + denominator.ascent,
+            );
+            result.add(denominator, offset);
+            result
+        }
+        MathNode::Sqrt(node) => {
+            let inner = layout_node(node, size);
+            let radical = size * 0.4;
+            let top = -(inner.ascent + gap);
+            let mut result = MathBox {
+                width: radical + inner.width + gap,
+                ascent: -top + size * RULE_THICKNESS,
+                descent: inner.descent,
+                ..Default::default()
+            };
+            let points = [
+                pos2(0.0, -size * 0.2),
+                pos2(radical * 0.25, -size * 0.3),
+                pos2(radical * 0.55, inner.descent),
+                pos2(radical, top),
+                pos2(result.width, top),
+            ];
+            for segment in points.windows(2) {
+                result.rule(segment[0], segment[1], size);
+            }
+            result.add(inner, vec2(radical + gap * 0.5, 0.0));
+            result
+        }
+    }
+}
+
+/// Lays out the text with formulas between `$` signs, lines are not wrapped.
+/// None if there are no formulas, then the text is drawn as usual.
+pub fn layout_math_text(text: &str, size: f32) -> Option<MathBox> {
+    let lines: Vec<_> = text.split('\n').map(split_math).collect();
+    if !lines.iter().flatten().any(|(is_math, _)| *is_math) {
+        return None;
+    }
+    let empty_line = layout_symbol(' ', size);
+    let mut result = MathBox::default();
+    let mut top = 0.0;
+    for parts in lines {
+        let mut line = MathBox {
+            ascent: empty_line.ascent,
+            descent: empty_line.descent,
+            ..Default::default()
+        };
+        for (is_math, part) in parts {
+            line.append(match is_math {
+                true => layout_list(&parse_math(&part), size),
+                false => part.chars().map(|c| layout_symbol(c, size)).fold(
+                    MathBox::default(),
+                    |mut text, symbol| {
+                        text.append(symbol);
+                        text
+                    },
+                ),
+            });
+        }
+        top += line.ascent;
+        result.width = result.width.max(line.width);
+        let descent = line.descent;
+        result.add(line, vec2(0.0, top));
+        top += descent;
+    }
+    result.descent = top;
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use epaint::pos2;
+
+    use super::{MathNode, layout_math_text, parse_math, split_math};
+
+    #[test]
+    fn math_parsing() {
+        let (a, b) = (MathNode::Symbol('a'), MathNode::Symbol('b'));
+        assert_eq!(
+            parse_math(r"f = \overline{a \cdot b}"),
+            [
+                MathNode::Symbol('f'),
+                MathNode::Symbol('='),
+                MathNode::Overline(Box::new(MathNode::Group(vec![
+                    a.clone(),
+                    MathNode::Symbol('·'),
+                    b.clone()
+                ])))
+            ]
+        );
+        assert_eq!(
+            parse_math(r"a_1^2 \frac a b"),
+            [
+                MathNode::Scripts {
+                    base: Box::new(a.clone()),
+                    sup: Some(Box::new(MathNode::Symbol('2'))),
+                    sub: Some(Box::new(MathNode::Symbol('1'))),
+                },
+                MathNode::Fraction(Box::new(a), Box::new(b)),
+            ]
+        );
+        assert_eq!(parse_math(r"\foo").len(), 1);
+        assert_eq!(
+            split_math(r"cost \$5, $x^2$ and $y"),
+            [
+                (false, "cost $5, ".into()),
+                (true, "x^2".into()),
+                (false, " and $y".into())
+            ]
+        );
+    }
+
+    #[test]
+    fn math_layout() {
+        assert!(layout_math_text(r"price: \$5", 10.0).is_none());
+        let layout = layout_math_text("$x^2$\n$\\overline{T}$", 10.0).unwrap();
+        let [x, square, t] = &layout.glyphs[..] else {
+            panic!("Wrong glyphs");
+        };
+        assert!(square.size < x.size && square.pos.y < x.pos.y && square.pos.x > x.pos.x);
+        assert!(t.pos.y > x.pos.y + 10.0);
+        // The overline is above the glyph:
+        assert!(layout.rules[0].from.y < t.pos.y - t.ascent);
+        assert!(layout.descent > 20.0);
+        // With the first baseline at zero, the outlines of the glyph are above it:
+        let path = layout.to_svg_path(pos2(0.0, -x.pos.y));
+        assert!(path.starts_with('M') && path.ends_with('Z'));
+        let mut numbers = path
+            .split(|c: char| c.is_ascii_alphabetic() || c == ' ')
+            .filter_map(|n| n.parse::<f32>().ok());
+        assert!(numbers.any(|n| n < -2.0));
+    }
+}
@@ -0,0 +1,27 @@
+mod components;
+mod graphics;
+mod geometry;
+mod grid_db;
+mod interface;
+mod lru_cache;
+mod math_text;
+mod primitives;
+mod text_field;
+mod net;
+mod shared_str;
+mod svg_export;
+mod yosys;
+
+pub use components::*;
+pub use graphics::*;
+pub use geometry::*;
+pub use grid_db::*;
+pub use interface::*;
+pub use lru_cache::*;
+pub use math_text::*;
+pub use primitives::*;
+pub use text_field::*;
+pub use net::*;
+pub use shared_str::*;
+pub use svg_export::*;
+pub use yosys::*;
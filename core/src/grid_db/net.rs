@@ -0,0 +1,625 @@
+use std::collections::{HashMap, HashSet};
+
+use epaint::{pos2, vec2, Color32, Mesh, Pos2, Rect, Stroke, Vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::{canvas::Canvas, field_state::{FieldState, SVG_DUMMY_STATE}, grid_db::{draw_dashed_line, grid_pos, grid_rect, mesh_arrow, mesh_line, mesh_round_line, svg_polygon, svg_styled_line, ComponentColor, GridDB, GridDBConnectionPoint, GridPos, GridRect, Id, Logic, Revision, SharedStr, Theme}};
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum CornerStyle {
+    Square,
+    Round,
+}
+
+/// Appearance of nets, junctions and net endpoints
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NetStyle {
+    /// Radius of junction point in cells
+    pub junction_size: f32,
+    pub corners: CornerStyle,
+    /// Draw arrowhead at the receiving end of net
+    pub arrows: bool,
+    #[serde(default)]
+    pub color_mode: NetColorMode,
+}
+
+impl NetStyle {
+    pub const DEFAULT: Self = Self {
+        junction_size: 0.2,
+        corners: CornerStyle::Square,
+        arrows: false,
+        color_mode: NetColorMode::NetClass,
+    };
+    pub const MIN_JUNCTION_SIZE: f32 = 0.1;
+    pub const MAX_JUNCTION_SIZE: f32 = 0.5;
+    /// Arrowhead length in cells
+    const ARROW_SIZE: f32 = 0.4;
+}
+
+impl Default for NetStyle {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// What the colors of the nets show
+#[derive(Clone, Copy, PartialEq, Default, Debug, Serialize, Deserialize)]
+pub enum NetColorMode {
+    #[default]
+    NetClass,
+    /// All nets have the theme color
+    Uniform,
+    /// Values of the simulation, nets without values have the colors of their classes
+    SimulationValues,
+}
+
+impl NetColorMode {
+    pub const ALL: [Self; 3] = [Self::NetClass, Self::Uniform, Self::SimulationValues];
+
+    pub fn get_value_color(value: Logic, theme: Theme) -> Color32 {
+        match value {
+            Logic::Low => theme.get_stroke_color(),
+            Logic::High => Color32::from_rgb(0, 170, 0),
+            Logic::X => Color32::from_rgb(255, 140, 0),
+            Logic::Z => Color32::from_rgb(60, 120, 255),
+        }
+    }
+
+    /// Color of the net of `class` with the simulated `value`, if it's known
+    pub fn get_net_color(
+        &self,
+        class: Option<&NetClass>,
+        value: Option<Logic>,
+        theme: Theme,
+    ) -> Color32 {
+        match (self, value) {
+            (Self::Uniform, _) => theme.get_stroke_color(),
+            (Self::SimulationValues, Some(value)) => Self::get_value_color(value, theme),
+            _ => class.map_or(theme.get_stroke_color(), |c| c.get_color(theme)),
+        }
+    }
+}
+
+/// How the problems found in nets of a class are reported
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum Severity {
+    Ignore,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub const ALL: [Self; 3] = [Self::Ignore, Self::Warning, Self::Error];
+}
+
+/// Named group of nets with common appearance and check rules, like `clock`
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct NetClass {
+    pub name: String,
+    /// RGB color, nets are drawn with the theme color if None
+    pub color: Option<[u8; 3]>,
+    /// Line width relative to the width of other nets
+    pub width: f32,
+    pub severity: Severity,
+}
+
+impl NetClass {
+    pub const MIN_WIDTH: f32 = 0.5;
+    pub const MAX_WIDTH: f32 = 3.0;
+
+    /// Classes of new projects
+    pub fn defaults() -> Vec<Self> {
+        let class = |name: &str, color, width, severity| Self {
+            name: name.to_owned(),
+            color,
+            width,
+            severity,
+        };
+        vec![
+            class("clock", Some([0x30, 0x70, 0xe0]), 1.5, Severity::Error),
+            class("reset", Some([0xe0, 0x70, 0x20]), 1.5, Severity::Error),
+            class("data", None, 1.0, Severity::Warning),
+            class("debug", Some([0x90, 0x90, 0x90]), 0.7, Severity::Ignore),
+        ]
+    }
+
+    pub fn is_default_set(classes: &[Self]) -> bool {
+        classes == Self::defaults()
+    }
+
+    pub fn get_color(&self, theme: Theme) -> Color32 {
+        match self.color {
+            Some([r, g, b]) => Color32::from_rgb(r, g, b),
+            None => theme.get_stroke_color(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Net {
+    pub start_point: GridDBConnectionPoint,
+    pub end_point: GridDBConnectionPoint,
+    pub points: Vec<GridPos>,
+    /// Name of the net class, empty for unclassified nets
+    #[serde(default, skip_serializing_if = "str::is_empty")]
+    pub class: SharedStr,
+}
+
+impl Net {
+    pub fn get_segments(&self, net_id: Id) -> Vec<NetSegment> {
+        let mut result = Vec::with_capacity(self.points.len() - 1);
+        for i in 0..self.points.len() - 1 {
+            result.push(NetSegment::new(
+                i,
+                net_id,
+                self.points[i],
+                self.points[i + 1],
+                (i == 0).then_some(self.start_point),
+                (i == self.points.len() - 2).then_some(self.end_point),
+            ));
+        }
+        result
+    }
+
+    pub fn get_segment(&self, segment_id: Id, net_id: Id) -> Option<NetSegment> {
+        Some(NetSegment::new(
+            segment_id,
+            net_id,
+            *self.points.get(segment_id)?,
+            *self.points.get(segment_id + 1)?,
+            (segment_id == 0).then_some(self.start_point),
+            (segment_id == self.points.len() - 2).then_some(self.end_point),
+        ))
+    }
+
+    /// Routed length in cells
+    pub fn get_length(&self) -> i32 {
+        self.points
+            .windows(2)
+            .map(|w| (w[1].x - w[0].x).abs() + (w[1].y - w[0].y).abs())
+            .sum()
+    }
+
+    /// Manhattan distance between the net ends in cells
+    pub fn get_manhattan_distance(&self) -> i32 {
+        match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) => (last.x - first.x).abs() + (last.y - first.y).abs(),
+            _ => 0,
+        }
+    }
+
+    /// Arrowhead is drawn at the input pin, at the end point if direction is unknown
+    pub fn is_arrow_at_end(&self, db: &GridDB) -> bool {
+        let is_input = |p: &GridDBConnectionPoint| {
+            db.get_component(&p.component_id)
+                .and_then(|c| c.is_input_connection(p.connection_id))
+        };
+        if is_input(&self.end_point) == Some(true) {
+            return true;
+        }
+        !(is_input(&self.start_point) == Some(true) || is_input(&self.end_point) == Some(false))
+    }
+
+    pub fn to_svg(
+        &self,
+        color: Color32,
+        width: f32,
+        offset: GridPos,
+        scale: f32,
+        db: &GridDB,
+        style: &NetStyle,
+    ) -> Option<String> {
+        if self.points.is_empty() {
+            return Some(String::new());
+        }
+        let offset_vec2 = vec2(offset.x as f32, offset.y as f32);
+        let first_point = db
+            .get_component(&self.start_point.component_id)?
+            .get_connection_position(self.start_point.connection_id, &SVG_DUMMY_STATE)?
+            + offset_vec2;
+        let last_point = db
+            .get_component(&self.end_point.component_id)?
+            .get_connection_position(self.end_point.connection_id, &SVG_DUMMY_STATE)?
+            + offset_vec2;
+        let mut points = Vec::with_capacity(self.points.len() + 2);
+        points.push(first_point * scale);
+
+        for i in 0..self.points.len() {
+            points.push(
+                pos2(
+                    (self.points[i].x + offset.x) as f32 + 0.5,
+                    (self.points[i].y + offset.y) as f32 + 0.5,
+                ) * scale,
+            );
+        }
+        points.push(last_point * scale);
+        let mut result = svg_styled_line(&points, color, width, style.corners == CornerStyle::Round);
+        if style.arrows && points.len() >= 2 {
+            let (tip, prev) = if self.is_arrow_at_end(db) {
+                (points[points.len() - 1], points[points.len() - 2])
+            } else {
+                (points[0], points[1])
+            };
+            let arrow = mesh_arrow(tip, tip - prev, NetStyle::ARROW_SIZE * scale, color);
+            result.push('\n');
+            result.push_str(&svg_polygon(
+                &arrow.vertices.iter().map(|v| v.pos).collect(),
+                color,
+                color,
+                0.0,
+            ));
+        }
+        Some(result)
+    }
+}
+
+#[derive(Clone)]
+pub struct NetSegment {
+    pub inner_id: Id, // ID of segment in net
+    pub net_id: Id,   // ID of net
+    pub pos1: GridPos,
+    pub pos2: GridPos,
+    con1: Option<GridDBConnectionPoint>, // if segment
+    con2: Option<GridDBConnectionPoint>, // Second position
+}
+
+impl NetSegment {
+    pub fn new(
+        inner_id: Id,
+        net_id: Id,
+        pos1: GridPos,
+        pos2: GridPos,
+        con1: Option<GridDBConnectionPoint>,
+        con2: Option<GridDBConnectionPoint>,
+    ) -> Self {
+        Self {
+            inner_id,
+            net_id,
+            pos1,
+            pos2,
+            con1,
+            con2,
+        }
+    }
+
+    /// Same positions and connections, unlike `==` which compares the IDs only
+    pub fn same_geometry(&self, other: &NetSegment) -> bool {
+        self.pos1 == other.pos1
+            && self.pos2 == other.pos2
+            && self.con1 == other.con1
+            && self.con2 == other.con2
+    }
+
+    pub fn is_horizontal(&self) -> bool {
+        self.pos1.y == self.pos2.y
+    }
+
+    /// Mesh of the segment colored by `state.net_style`, `value` is the simulated value of the net
+    pub fn get_mesh(
+        &self,
+        db: &GridDB,
+        state: &FieldState,
+        theme: Theme,
+        value: Option<Logic>,
+    ) -> Mesh {
+        let class = db.get_net(&self.net_id).and_then(|net| db.get_net_class(net));
+        let w = (state.grid_size * 0.1).max(1.0) * class.map_or(1.0, |c| c.width);
+        let ofs = Vec2::new(0.5 * state.grid_size, 0.5 * state.grid_size);
+        let color = state.net_style.color_mode.get_net_color(class, value, theme);
+
+        let p1 = state.grid_to_screen(&self.pos1) + ofs;
+        let p2 = state.grid_to_screen(&self.pos2) + ofs;
+
+        let mut pts = vec![p1, p2];
+
+        if let Some(cp) = &self.con1
+            && let Some(pos) = db
+                .get_component(&cp.component_id)
+                .and_then(|comp| comp.get_connection_position(cp.connection_id, state))
+        {
+            pts.insert(0, pos);
+        }
+
+        if let Some(cp) = &self.con2
+            && let Some(pos) = db
+                .get_component(&cp.component_id)
+                .and_then(|comp| comp.get_connection_position(cp.connection_id, state))
+        {
+            pts.push(pos);
+        }
+
+        let mut mesh = match state.net_style.corners {
+            CornerStyle::Square => mesh_line(pts.clone(), w, color),
+            CornerStyle::Round => mesh_round_line(pts.clone(), w, color),
+        };
+
+        // Arrowhead:
+        if state.net_style.arrows
+            && (self.con1.is_some() || self.con2.is_some())
+            && let Some(net) = db.get_net(&self.net_id)
+        {
+            let at_end = net.is_arrow_at_end(db);
+            let n = pts.len();
+            let tip_and_prev = if at_end && self.con2.is_some() {
+                Some((pts[n - 1], pts[n - 2]))
+            } else if !at_end && self.con1.is_some() {
+                Some((pts[0], pts[1]))
+            } else {
+                None
+            };
+            if let Some((tip, prev)) = tip_and_prev
+                && tip != prev
+            {
+                mesh.append(mesh_arrow(
+                    tip,
+                    tip - prev,
+                    NetStyle::ARROW_SIZE * state.grid_size,
+                    color,
+                ));
+            }
+        }
+        mesh
+    }
+
+    pub fn is_hovered(&self, state: &FieldState) -> bool {
+        let ofs = Vec2::new(0.5 * state.grid_size, 0.5 * state.grid_size);
+        let Pos2 { x: ax, y: ay } = state.grid_to_screen(&self.pos1) + ofs;
+        let Pos2 { x: bx, y: by } = state.grid_to_screen(&self.pos2) + ofs;
+        if let Some(Pos2 { x: px, y: py }) = state.cursor_pos {
+            if if self.is_horizontal() {
+                ax.min(bx) > px || px > ax.max(bx)
+            } else {
+                ay.min(by) > py || py > ay.max(by)
+            } {
+                return false;
+            }
+            let abx = bx - ax;
+            let aby = by - ay;
+            let apx = px - ax;
+            let apy = py - ay;
+            let cross = abx * apy - aby * apx;
+            let length_ab_sq = abx.powi(2) + aby.powi(2);
+            return ((cross * cross) / length_ab_sq) < (state.grid_size * 0.3).powi(2);
+        }
+        false
+    }
+
+    pub fn highlight(&self, state: &FieldState, painter: &impl Canvas) {
+        let ofs = Vec2::new(0.5 * state.grid_size, 0.5 * state.grid_size);
+
+        let p1 = state.grid_to_screen(&self.pos1) + ofs;
+        let p2 = state.grid_to_screen(&self.pos2) + ofs;
+
+        painter.line_segment(
+            [p1, p2],
+            Stroke::new(
+                (state.grid_size * 0.3).max(1.0),
+                Color32::from_rgba_unmultiplied(100, 100, 0, 100),
+            ),
+        );
+    }
+
+    /// Highlight of the selected segment, dashed unlike the hovered one,
+    /// so they are distinguished without colors
+    pub fn highlight_selected(&self, state: &FieldState, painter: &impl Canvas) {
+        self.highlight(state, painter);
+        let ofs = Vec2::new(0.5 * state.grid_size, 0.5 * state.grid_size);
+        draw_dashed_line(
+            painter,
+            state.grid_to_screen(&self.pos1) + ofs,
+            state.grid_to_screen(&self.pos2) + ofs,
+            painter.theme().get_text_color(),
+            (state.grid_size * 0.05).max(1.0),
+            state.grid_size * 0.4,
+            state.grid_size * 0.2,
+        );
+    }
+}
+
+/// Group of parallel neighbouring segments, which can be drawn as a single trunk
+pub struct NetBundle {
+    pub horizontal: bool,
+    /// First and last lanes (rows for horizontal bundles, columns for vertical ones)
+    pub lanes: (i32, i32),
+    /// Common range along the bundle direction
+    pub range: (i32, i32),
+}
+
+impl NetBundle {
+    /// Minimal number of different nets to form a bundle
+    const MIN_NETS: usize = 3;
+    /// Minimal length of the common range
+    const MIN_LENGTH: i32 = 2;
+
+    /// Lane and range of the segment across and along the bundle direction
+    fn get_span(segment: &NetSegment, horizontal: bool) -> (i32, i32, i32) {
+        let (a, b) = (segment.pos1, segment.pos2);
+        if horizontal {
+            (a.y, a.x.min(b.x), a.x.max(b.x))
+        } else {
+            (a.x, a.y.min(b.y), a.y.max(b.y))
+        }
+    }
+
+    /// Finds bundles among the segments of the database,
+    /// segments in the next lane are looked up in the R-tree.
+    /// Nets themselves stay separate, bundles are used only for visualization.
+    pub fn find(db: &GridDB) -> Vec<NetBundle> {
+        let mut result = vec![];
+        for horizontal in [true, false] {
+            let is_lane_segment =
+                |s: &&NetSegment| s.is_horizontal() == horizontal && s.pos1 != s.pos2;
+            let segments: Vec<&NetSegment> = db.get_net_segments().filter(is_lane_segment).collect();
+            let index: HashMap<(Id, Id), usize> = segments
+                .iter()
+                .enumerate()
+                .map(|(i, s)| ((s.net_id, s.inner_id), i))
+                .collect();
+
+            // Union neighbouring segments:
+            let mut parent: Vec<usize> = (0..segments.len()).collect();
+            fn find_root(parent: &mut [usize], mut i: usize) -> usize {
+                while parent[i] != i {
+                    parent[i] = parent[parent[i]];
+                    i = parent[i];
+                }
+                i
+            }
+            for (i, segment) in segments.iter().enumerate() {
+                let (lane, start, end) = Self::get_span(segment, horizontal);
+                let next_lane = if horizontal {
+                    grid_rect(0, grid_pos(start, lane + 1), grid_pos(end, lane + 1))
+                } else {
+                    grid_rect(0, grid_pos(lane + 1, start), grid_pos(lane + 1, end))
+                };
+                for other in db.get_visible_net_segments(&next_lane) {
+                    let Some(&j) = index.get(&(other.net_id, other.inner_id)) else {
+                        continue;
+                    };
+                    let (other_lane, other_start, other_end) = Self::get_span(other, horizontal);
+                    if other_lane == lane + 1
+                        && other.net_id != segment.net_id
+                        && end.min(other_end) - start.max(other_start) >= Self::MIN_LENGTH
+                    {
+                        let (a, b) = (find_root(&mut parent, i), find_root(&mut parent, j));
+                        parent[a] = b;
+                    }
+                }
+            }
+
+            let mut groups: HashMap<usize, Vec<(i32, i32, i32)>> = HashMap::new();
+            let mut group_nets: HashMap<usize, HashSet<Id>> = HashMap::new();
+            for (i, segment) in segments.iter().enumerate() {
+                let root = find_root(&mut parent, i);
+                groups
+                    .entry(root)
+                    .or_default()
+                    .push(Self::get_span(segment, horizontal));
+                group_nets.entry(root).or_default().insert(segment.net_id);
+            }
+            for (root, group) in &groups {
+                if group_nets[root].len() < Self::MIN_NETS {
+                    continue;
+                }
+                let start = group.iter().map(|span| span.1).max().unwrap();
+                let end = group.iter().map(|span| span.2).min().unwrap();
+                if end - start < Self::MIN_LENGTH {
+                    continue;
+                }
+                let lanes = (
+                    group.iter().map(|span| span.0).min().unwrap(),
+                    group.iter().map(|span| span.0).max().unwrap(),
+                );
+                result.push(NetBundle {
+                    horizontal,
+                    lanes,
+                    range: (start, end),
+                });
+            }
+        }
+        result
+    }
+
+    /// Is any part of the bundle inside the rectangle
+    pub fn is_visible(&self, rect: &GridRect) -> bool {
+        let (along, across) = if self.horizontal {
+            ((rect.min.x, rect.max.x), (rect.min.y, rect.max.y))
+        } else {
+            ((rect.min.y, rect.max.y), (rect.min.x, rect.max.x))
+        };
+        self.range.0 <= along.1
+            && self.range.1 >= along.0
+            && self.lanes.0 <= across.1
+            && self.lanes.1 >= across.0
+    }
+
+    /// Draws trunk over the bundled segments with entry/exit ticks for every lane
+    pub fn draw(&self, state: &FieldState, painter: &impl Canvas, color: Color32) {
+        let to_screen = |along: f32, lane: f32| {
+            let p = if self.horizontal {
+                pos2(along, lane)
+            } else {
+                pos2(lane, along)
+            };
+            state.grid_to_screen(&grid_pos(0, 0)) + p.to_vec2() * state.grid_size
+        };
+        let (start, end) = (self.range.0 as f32 + 1.0, self.range.1 as f32);
+        let (lane_min, lane_max) = (self.lanes.0 as f32 + 0.25, self.lanes.1 as f32 + 0.75);
+        painter.rect_filled(
+            Rect::from_two_pos(to_screen(start, lane_min), to_screen(end, lane_max)),
+            state.grid_size * 0.25,
+            color,
+        );
+        let stroke = Stroke::new((state.grid_size * 0.1).max(1.0), color);
+        for lane in self.lanes.0..=self.lanes.1 {
+            let y = lane as f32 + 0.5;
+            for x in [start, end] {
+                painter.line_segment(
+                    [to_screen(x - 0.25, y + 0.25), to_screen(x + 0.25, y - 0.25)],
+                    stroke,
+                );
+            }
+        }
+    }
+}
+
+/// Bundles of the database, found again only after it is changed
+#[derive(Default)]
+pub struct NetBundleCache {
+    bundles: Vec<NetBundle>,
+    revision: Option<Revision>,
+}
+
+impl NetBundleCache {
+    pub fn get(&mut self, db: &GridDB) -> &[NetBundle] {
+        let revision = db.get_revision();
+        if self.revision != Some(revision) {
+            self.bundles = NetBundle::find(db);
+            self.revision = Some(revision);
+        }
+        &self.bundles
+    }
+}
+
+#[derive(Clone, Copy)]
+pub enum NetAction {
+    RemoveNet,
+    InsertPoint,
+}
+
+impl NetAction {
+    pub const ACTIONS: &[Self] = &[Self::InsertPoint, Self::RemoveNet];
+}
+
+/// Cells of the segment from `a` to `b`, both ends included
+pub fn segment_cells(a: GridPos, b: GridPos) -> impl Iterator<Item = GridPos> {
+    let len = (b.x - a.x).abs().max((b.y - a.y).abs());
+    let step = grid_pos((b.x - a.x).signum(), (b.y - a.y).signum());
+    (0..=len).map(move |i| grid_pos(a.x + step.x * i, a.y + step.y * i))
+}
+
+/// Removes redundant points lying on straight lines
+pub fn simplify_path(mut path: Vec<GridPos>) -> Vec<GridPos> {
+    loop {
+        let prev_size = path.len();
+        let mut i = 1;
+        while i < (path.len() - 1) {
+            let prev = path[i - 1];
+            let curr = path[i];
+            let next = path[i + 1];
+
+            let same_x = prev.x == curr.x && curr.x == next.x;
+            let same_y = prev.y == curr.y && curr.y == next.y;
+
+            if same_x || same_y {
+                path.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+        if prev_size == path.len() {
+            break;
+        }
+    }
+    path
+}
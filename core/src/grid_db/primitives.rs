@@ -0,0 +1,2922 @@
+use std::collections::HashMap;
+use std::f32::consts::{FRAC_PI_2, TAU};
+use std::ops::{Add, BitAnd, BitOr, BitXor, Not};
+use std::{
+    cell::{LazyCell, RefCell},
+    f32::consts::PI,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    vec,
+};
+
+use epaint::emath::{Align2, TSTransform};
+use epaint::{Color32, Mesh, Pos2, Rect, Shape, Stroke, Vec2, pos2, vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::grid_db::{
+    ComponentColor, STROKE_SCALE, SharedStr, SymbolGeometry, SymbolLabel, Theme,
+    show_text_with_debounce,
+};
+
+use crate::{
+    canvas::Canvas,
+    field_state::FieldState,
+    grid_db::tesselate_polygon,
+};
+
+use super::{
+    CacheStats, ComponentAction, GridPos, Id, LibrarySource, LruCache, NetStyle, grid_pos,
+};
+
+#[derive(Clone, Copy, Hash, PartialEq, Eq)]
+pub enum LodLevel {
+    Max,
+    Mid,
+    Min, // Minimal quality
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Hash, PartialEq, Eq, Debug)]
+pub enum Rotation {
+    ROT0,
+    ROT90,
+    ROT180,
+    ROT270,
+}
+
+impl Rotation {
+    pub fn to_radians(&self) -> f32 {
+        match self {
+            Rotation::ROT0 => 0.0,
+            Rotation::ROT90 => FRAC_PI_2,
+            Rotation::ROT180 => PI,
+            Rotation::ROT270 => PI + FRAC_PI_2,
+        }
+    }
+
+    /// Clockwise angle on the screen, the same as [`Self::to_radians`]
+    pub fn to_degrees(&self) -> u32 {
+        match self {
+            Rotation::ROT0 => 0,
+            Rotation::ROT90 => 90,
+            Rotation::ROT180 => 180,
+            Rotation::ROT270 => 270,
+        }
+    }
+
+    /// Offset from the top-left corner of the rotated text box of `size` to its `anchor`.
+    /// The canvas places text by it, SVG gets the same placement from the text anchor attributes.
+    pub fn get_text_anchor_offset(&self, anchor: Align2, size: Vec2) -> Vec2 {
+        let unrotated = pos2(anchor.x().to_factor() * size.x, anchor.y().to_factor() * size.y);
+        self.rotate_point(unrotated, Pos2::ZERO).to_vec2()
+    }
+
+    pub fn rotated_up(&self) -> Rotation {
+        match self {
+            Rotation::ROT0 => Rotation::ROT90,
+            Rotation::ROT90 => Rotation::ROT180,
+            Rotation::ROT180 => Rotation::ROT270,
+            Rotation::ROT270 => Rotation::ROT0,
+        }
+    }
+
+    pub fn rotated_down(&self) -> Rotation {
+        match self {
+            Rotation::ROT90 => Rotation::ROT0,
+            Rotation::ROT180 => Rotation::ROT90,
+            Rotation::ROT270 => Rotation::ROT180,
+            Rotation::ROT0 => Rotation::ROT270,
+        }
+    }
+
+    fn cos(&self) -> i32 {
+        match self {
+            Rotation::ROT90 => 0,
+            Rotation::ROT180 => -1,
+            Rotation::ROT270 => 0,
+            Rotation::ROT0 => 1,
+        }
+    }
+
+    fn sin(&self) -> i32 {
+        match self {
+            Rotation::ROT90 => 1,
+            Rotation::ROT180 => 0,
+            Rotation::ROT270 => -1,
+            Rotation::ROT0 => 0,
+        }
+    }
+
+    fn rotate_grid_pos(&self, point: GridPos, center: GridPos) -> GridPos {
+        let dx = point.x - center.x;
+        let dy = point.y - center.y;
+        let cos_a = self.cos();
+        let sin_a = self.sin();
+        grid_pos(
+            center.x + dx * cos_a - dy * sin_a,
+            center.y + dx * sin_a + dy * cos_a,
+        )
+    }
+
+    pub fn rotate_point(&self, point: Pos2, center: Pos2) -> Pos2 {
+        let dx = point.x - center.x;
+        let dy = point.y - center.y;
+        let cos_a = self.cos() as f32;
+        let sin_a = self.sin() as f32;
+        Pos2::new(
+            center.x + dx * cos_a - dy * sin_a,
+            center.y + dx * sin_a + dy * cos_a,
+        )
+    }
+
+    pub fn get_rotated_dim(&self, (w, h): (i32, i32)) -> (i32, i32) {
+        match self {
+            Rotation::ROT0 => (w, h),
+            Rotation::ROT90 => (h, w),
+            Rotation::ROT180 => (w, h),
+            Rotation::ROT270 => (h, w),
+        }
+    }
+}
+
+impl Add for Rotation {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self::Output {
+        fn to_u8(a: Rotation) -> u8 {
+            match a {
+                Rotation::ROT0 => 0,
+                Rotation::ROT90 => 1,
+                Rotation::ROT180 => 2,
+                Rotation::ROT270 => 3,
+            }
+        }
+
+        fn from_u8(a: u8) -> Rotation {
+            match a {
+                0 => Rotation::ROT0,
+                1 => Rotation::ROT90,
+                2 => Rotation::ROT180,
+                _ => Rotation::ROT270,
+            }
+        }
+
+        return from_u8((to_u8(self) + to_u8(rhs)) % 4);
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct PrimitiveComponent {
+    pub typ: PrimitiveType,
+    pub pos: GridPos,
+    pub rotation: Rotation,
+    /// Instance name in the netlist, generated from the type and ID if empty
+    #[serde(default, skip_serializing_if = "str::is_empty")]
+    pub name: SharedStr,
+    /// Library part the primitive was placed from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<LibrarySource>,
+}
+
+impl PrimitiveComponent {
+    pub fn get_actions(&self) -> &'static [ComponentAction] {
+        if self.typ == PrimitiveType::Connector {
+            &[
+                ComponentAction::RotateDown,
+                ComponentAction::RotateUp,
+                ComponentAction::Customize,
+                ComponentAction::JumpToConnector,
+                ComponentAction::Remove,
+            ]
+        } else if self.typ.has_swappable_pins() {
+            &[
+                ComponentAction::RotateDown,
+                ComponentAction::RotateUp,
+                ComponentAction::Customize,
+                ComponentAction::SwapPins,
+                ComponentAction::Replace,
+                ComponentAction::Remove,
+            ]
+        } else if self.typ.is_customizable() {
+            &[
+                ComponentAction::RotateDown,
+                ComponentAction::RotateUp,
+                ComponentAction::Customize,
+                ComponentAction::Replace,
+                ComponentAction::Remove,
+            ]
+        } else {
+            &[
+                ComponentAction::RotateDown,
+                ComponentAction::RotateUp,
+                ComponentAction::Replace,
+                ComponentAction::Remove,
+            ]
+        }
+    }
+
+    const CONNECTION_SCALE: f32 = 0.1;
+
+    pub fn get_dimension(&self) -> (i32, i32) {
+        let (w, h) = self.typ.get_dimension_raw();
+        match self.rotation {
+            Rotation::ROT0 => (w, h),
+            Rotation::ROT90 => (h, w),
+            Rotation::ROT180 => (w, h),
+            Rotation::ROT270 => (h, w),
+        }
+    }
+
+    fn apply_rotation(&self, point: Pos2, state: &FieldState) -> Pos2 {
+        let rot_center = state.grid_to_screen(&self.pos);
+        let dim = self.get_dimension();
+        let rot_ofs = match self.rotation {
+            Rotation::ROT0 => vec2(0.0, 0.0),
+            Rotation::ROT90 => vec2(dim.0 as f32, 0.0) * state.grid_size,
+            Rotation::ROT180 => vec2(dim.0 as f32, dim.1 as f32) * state.grid_size,
+            Rotation::ROT270 => vec2(0.0, dim.1 as f32) * state.grid_size,
+        };
+        self.rotation.rotate_point(point, rot_center) + rot_ofs
+    }
+
+    fn get_rotation_offset(&self) -> Vec2 {
+        let dim = self.get_dimension();
+        match self.rotation {
+            Rotation::ROT0 => vec2(0.0, 0.0),
+            Rotation::ROT90 => vec2(dim.0 as f32, 0.0),
+            Rotation::ROT180 => vec2(dim.0 as f32, dim.1 as f32),
+            Rotation::ROT270 => vec2(0.0, dim.1 as f32),
+        }
+    }
+
+    /// Converts a point of the symbol without rotation into the grid coordinates
+    pub fn raw_to_grid(&self, p: Pos2) -> Pos2 {
+        self.rotation.rotate_point(p, Pos2::ZERO)
+            + self.get_rotation_offset()
+            + vec2(self.pos.x as f32, self.pos.y as f32)
+    }
+
+    /// Converts a point in the grid coordinates into the coordinates of the symbol without rotation
+    pub fn grid_to_raw(&self, p: Pos2) -> Pos2 {
+        let inverse = self.rotation + self.rotation + self.rotation;
+        let p = p - vec2(self.pos.x as f32, self.pos.y as f32) - self.get_rotation_offset();
+        inverse.rotate_point(p, Pos2::ZERO)
+    }
+
+    fn apply_rotation_grid_pos(&self, point: GridPos) -> GridPos {
+        let rot_center = self.pos;
+        let dim = self.get_dimension();
+        let rot_ofs = match self.rotation {
+            Rotation::ROT0 => grid_pos(0, 0),
+            Rotation::ROT90 => grid_pos(dim.0 - 1, 0),
+            Rotation::ROT180 => grid_pos(dim.0 - 1, dim.1 - 1),
+            Rotation::ROT270 => grid_pos(0, dim.1 - 1),
+        };
+        self.rotation.rotate_grid_pos(point, rot_center) + rot_ofs
+    }
+
+    pub fn is_connection_hovered(&self, connection_id: Id, state: &FieldState) -> bool {
+        if let Some(cursor_pos) = state.cursor_pos {
+            if let Some(con_pos) = self.get_connection_position(connection_id, state) {
+                let d = con_pos.distance(cursor_pos);
+                d <= state.grid_size * Self::CONNECTION_SCALE * 2.0
+            } else {
+                false
+            }
+        } else {
+            false
+        }
+    }
+
+    pub fn highlight_connection(&self, connection_id: Id, state: &FieldState, painter: &impl Canvas) {
+        if let Some(p) = self.get_connection_position(connection_id, state) {
+            painter.circle_filled(
+                p,
+                state.grid_size * Self::CONNECTION_SCALE * 3.0,
+                Color32::from_rgba_unmultiplied(100, 100, 0, 100),
+            );
+        }
+    }
+
+    pub fn get_connection_dock_cell(&self, connection_id: Id) -> Option<GridPos> {
+        if connection_id >= self.typ.get_connections_number() {
+            return None;
+        }
+        Some(self.apply_rotation_grid_pos(self.typ.get_dock_cell_raw(connection_id) + self.pos))
+    }
+
+    pub fn get_connection_position(&self, connection_id: Id, state: &FieldState) -> Option<Pos2> {
+        if connection_id >= self.typ.get_connections_number() {
+            return None;
+        }
+        Some(self.apply_rotation(
+            self.typ.get_connection_position_raw(connection_id) * state.grid_size
+                + state.grid_to_screen(&self.pos).to_vec2(),
+            state,
+        ))
+    }
+
+    /// Geometry of the rotated symbol in grid units relative to the component position
+    pub fn get_geometry(&self, lod_level: LodLevel) -> Arc<SymbolGeometry> {
+        get_cached_geometry(self.typ, self.rotation, lod_level)
+    }
+
+    /// Signal name of the connector shown next to it, relative to the component position
+    fn get_signal_label(&self) -> Option<SymbolLabel> {
+        if self.typ != PrimitiveType::Connector || self.name.is_empty() {
+            return None;
+        }
+        Some(SymbolLabel {
+            pos: self.raw_to_grid(pos2(2.2, 0.5)) - vec2(self.pos.x as f32, self.pos.y as f32),
+            text: self.name.to_string(),
+            rotation: self.rotation,
+            align: Align2::LEFT_CENTER,
+        })
+    }
+
+    pub fn display(&self, state: &FieldState, painter: &impl Canvas, theme: Theme) {
+        let stroke_w = 1.0 * state.scale;
+        let _fill_color = theme.get_fill_color();
+        let stroke_color = theme.get_stroke_color();
+        let stroke = Stroke {
+            color: stroke_color,
+            width: stroke_w,
+        };
+        let lod_level = state.lod_level();
+        let screen_pos = state.grid_to_screen(&self.pos).to_vec2();
+        let geometry = self.get_geometry(lod_level);
+        let to_screen = |p: Pos2| p * state.grid_size + screen_pos;
+        // Draw lines:
+        if lod_level != LodLevel::Min {
+            for line in &geometry.lines {
+                painter.line(line.iter().map(|p| to_screen(*p)).collect(), stroke);
+            }
+        }
+        for mesh in get_cached_meshes(self.typ, self.rotation, lod_level, theme) {
+            let mut shape = Shape::Mesh(mesh);
+            shape.transform(TSTransform {
+                scaling: state.grid_size,
+                translation: screen_pos,
+            });
+            painter.add(shape);
+        }
+
+        // Draw connections:
+        if lod_level != LodLevel::Min {
+            let radius = match self.typ {
+                PrimitiveType::Point => state.grid_size * state.net_style.junction_size,
+                _ => state.grid_size * Self::CONNECTION_SCALE,
+            };
+            for port in &geometry.ports {
+                painter.circle_filled(to_screen(*port), radius, stroke_color);
+            }
+        }
+
+        // Draw text labels:
+        if state.lod_level() == LodLevel::Max {
+            for label in geometry.labels.iter().chain(&self.get_signal_label()) {
+                show_text_with_debounce(
+                    to_screen(label.pos),
+                    label.text.clone(),
+                    state,
+                    painter,
+                    None,
+                    label.rotation,
+                    label.align,
+                );
+            }
+        }
+    }
+
+    pub fn get_svg(&self, offset: GridPos, scale: f32, theme: Theme, style: &NetStyle) -> String {
+        let pos: GridPos = self.pos + offset;
+        let radius = match self.typ {
+            PrimitiveType::Point => scale * style.junction_size,
+            _ => scale * Self::CONNECTION_SCALE,
+        };
+        let mut geometry = SymbolGeometry::clone(&self.get_geometry(LodLevel::Max));
+        geometry.labels.extend(self.get_signal_label());
+        geometry
+            .transformed(TSTransform::new(
+                vec2(pos.x as f32, pos.y as f32) * scale,
+                scale,
+            ))
+            .to_svg(theme, STROKE_SCALE * scale, radius, 0.5 * scale)
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Hash, PartialEq, Eq)]
+pub struct DFFParams {
+    pub has_enable: bool,
+    pub has_async_reset: bool,
+    pub has_sync_reset: bool,
+
+    pub async_reset_inverted: bool,
+    pub sync_reset_inverted: bool,
+}
+
+/// Four-valued logic of the simulation, like in HDLs
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
+pub enum Logic {
+    Low,
+    High,
+    /// Unknown value, e.g. driven by conflicting outputs
+    X,
+    /// High impedance, nothing drives the net
+    Z,
+}
+
+impl Logic {
+    pub fn to_bool(self) -> Option<bool> {
+        match self {
+            Self::Low => Some(false),
+            Self::High => Some(true),
+            Self::X | Self::Z => None,
+        }
+    }
+
+    pub fn is_known(self) -> bool {
+        self.to_bool().is_some()
+    }
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            Self::Low => "0",
+            Self::High => "1",
+            Self::X => "X",
+            Self::Z => "Z",
+        }
+    }
+
+    /// Value read by a gate input, a floating input is unknown
+    pub fn read(self) -> Self {
+        if self == Self::Z { Self::X } else { self }
+    }
+
+    /// Value of the net driven by both `self` and `other`
+    pub fn resolve(self, other: Self) -> Self {
+        match (self, other) {
+            (Self::Z, v) | (v, Self::Z) => v,
+            (a, b) if a == b => a,
+            _ => Self::X,
+        }
+    }
+}
+
+impl From<bool> for Logic {
+    fn from(value: bool) -> Self {
+        if value { Self::High } else { Self::Low }
+    }
+}
+
+impl Not for Logic {
+    type Output = Self;
+
+    fn not(self) -> Self {
+        match self.read() {
+            Self::Low => Self::High,
+            Self::High => Self::Low,
+            v => v,
+        }
+    }
+}
+
+impl BitAnd for Logic {
+    type Output = Self;
+
+    fn bitand(self, rhs: Self) -> Self {
+        match (self.read(), rhs.read()) {
+            (Self::Low, _) | (_, Self::Low) => Self::Low,
+            (Self::High, Self::High) => Self::High,
+            _ => Self::X,
+        }
+    }
+}
+
+impl BitOr for Logic {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        match (self.read(), rhs.read()) {
+            (Self::High, _) | (_, Self::High) => Self::High,
+            (Self::Low, Self::Low) => Self::Low,
+            _ => Self::X,
+        }
+    }
+}
+
+impl BitXor for Logic {
+    type Output = Self;
+
+    fn bitxor(self, rhs: Self) -> Self {
+        match (self.to_bool(), rhs.to_bool()) {
+            (Some(a), Some(b)) => (a ^ b).into(),
+            _ => Self::X,
+        }
+    }
+}
+
+/// State of a primitive during simulation
+#[derive(Clone, Copy, Default, PartialEq, Debug, Serialize, Deserialize)]
+pub struct PrimitiveState {
+    /// Value driven by sources and stored by flip-flops
+    pub value: bool,
+    /// Clock value of the previous evaluation, to detect edges
+    pub last_clk: bool,
+    /// Outputs of multi-output sources
+    #[serde(default)]
+    pub bits: u64,
+    /// The stored value is unknown (X)
+    #[serde(default)]
+    pub unknown: bool,
+}
+
+impl PrimitiveState {
+    /// Stored value
+    pub fn get(&self) -> Logic {
+        if self.unknown {
+            Logic::X
+        } else {
+            self.value.into()
+        }
+    }
+
+    pub fn set(&mut self, value: Logic) {
+        let value = value.read();
+        self.unknown = value == Logic::X;
+        self.value = value == Logic::High;
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum PPort {
+    // Common ports:
+    Input(usize),
+    Output(usize),
+
+    // Mux:
+    Select,
+
+    // Adder ports:
+    Cin,
+    Cout,
+
+    // DFF ports:
+    D,
+    Q,
+    AsyncReset,
+    SyncReset,
+    Enable,
+    Clk,
+}
+
+impl PPort {
+    /// Returns the additional ports of dff (beyond D, CLK, Q) based on parameters
+    fn dff_additional_ports(params: &DFFParams) -> &'static [Option<Self>; 3] {
+        // Pre-define all possible combinations for quick lookup
+        static PORT_COMBINATIONS: [[Option<PPort>; 3]; 8] = [
+            // 0: no additional ports
+            [None, None, None], // None placeholder
+            // 1: sync reset only
+            [Some(PPort::SyncReset), None, None],
+            // 2: async reset only
+            [Some(PPort::AsyncReset), None, None],
+            // 3: sync + async reset
+            [Some(PPort::SyncReset), Some(PPort::AsyncReset), None],
+            // 4: enable only
+            [Some(PPort::Enable), None, None],
+            // 5: sync reset + enable
+            [Some(PPort::SyncReset), Some(PPort::Enable), None],
+            // 6: async reset + enable
+            [Some(PPort::AsyncReset), Some(PPort::Enable), None],
+            // 7: all additional ports
+            [
+                Some(PPort::SyncReset),
+                Some(PPort::AsyncReset),
+                Some(PPort::Enable),
+            ],
+        ];
+
+        let index = (params.has_enable as usize) << 2
+            | (params.has_async_reset as usize) << 1
+            | params.has_sync_reset as usize;
+
+        &PORT_COMBINATIONS[index]
+    }
+
+    /// Short pin name used to match connections between different components
+    fn name(&self) -> String {
+        match self {
+            Self::Input(i) => format!("in{}", i),
+            Self::Output(0) => "out".to_owned(),
+            Self::Output(i) => format!("out{}", i),
+            Self::Select => "sel".to_owned(),
+            Self::Cin => "cin".to_owned(),
+            Self::Cout => "cout".to_owned(),
+            Self::D => "d".to_owned(),
+            Self::Q => "q".to_owned(),
+            Self::AsyncReset => "arst".to_owned(),
+            Self::SyncReset => "srst".to_owned(),
+            Self::Enable => "en".to_owned(),
+            Self::Clk => "clk".to_owned(),
+        }
+    }
+
+    /// Converts a connection ID to a port type
+    fn from_id(typ: &PrimitiveType, id: usize) -> Option<Self> {
+        match typ {
+            PrimitiveType::And(n_inputs)
+            | PrimitiveType::Or(n_inputs)
+            | PrimitiveType::Xor(n_inputs)
+            | PrimitiveType::Nand(n_inputs) => match id {
+                0 => Some(Self::Output(0)),
+                _ => {
+                    if id <= *n_inputs {
+                        Some(Self::Input(id - 1))
+                    } else {
+                        None
+                    }
+                }
+            },
+
+            PrimitiveType::Mux(n_inputs) => match id {
+                0 => Some(Self::Output(0)),
+                1 => Some(Self::Select),
+                _ => {
+                    if id <= *n_inputs + 1 {
+                        Some(Self::Input(id - 2))
+                    } else {
+                        None
+                    }
+                }
+            },
+            PrimitiveType::DFF(params) => match id {
+                0 => Some(Self::Clk),
+                1 => Some(Self::D),
+                2 => Some(Self::Q),
+                3..=5 => *Self::dff_additional_ports(params).get(id - 3)?,
+                _ => None,
+            },
+            PrimitiveType::Not => match id {
+                0 => Some(Self::Input(0)),
+                1 => Some(Self::Output(0)),
+                _ => None,
+            },
+            PrimitiveType::TriState => match id {
+                0 => Some(Self::Input(0)),
+                1 => Some(Self::Output(0)),
+                2 => Some(Self::Enable),
+                _ => None,
+            },
+            PrimitiveType::Point | PrimitiveType::Connector => match id {
+                0 => Some(Self::Output(0)),
+                _ => None,
+            },
+            PrimitiveType::Input
+            | PrimitiveType::Clock
+            | PrimitiveType::Button
+            | PrimitiveType::Switch => match id {
+                0 => Some(Self::Output(0)),
+                _ => None,
+            },
+            PrimitiveType::DipSwitch(n) => (id < *n).then_some(Self::Output(id)),
+            PrimitiveType::HexKeypad => {
+                (id < PrimitiveType::KEYPAD_OUTPUTS).then_some(Self::Output(id))
+            }
+            PrimitiveType::Assertion { n_inputs, .. } => {
+                (id < *n_inputs).then_some(Self::Input(id))
+            }
+            PrimitiveType::Pattern { .. } | PrimitiveType::Random { .. } => match id {
+                0 => Some(Self::Clk),
+                _ => (id <= typ.get_stimulus_width()).then(|| Self::Output(id - 1)),
+            },
+            PrimitiveType::Output => match id {
+                0 => Some(Self::Input(0)),
+                _ => None,
+            },
+            PrimitiveType::Comparator(_) => match id {
+                0 => Some(Self::Input(0)),
+                1 => Some(Self::Input(1)),
+                2 => Some(Self::Output(0)),
+                _ => None,
+            },
+            PrimitiveType::Adder { cin, cout } => match id {
+                0 => Some(Self::Input(0)),
+                1 => Some(Self::Input(1)),
+                2 => Some(Self::Output(0)),
+                3 => {
+                    if *cin {
+                        Some(Self::Cin)
+                    } else if *cout {
+                        Some(Self::Cout)
+                    } else {
+                        None
+                    }
+                }
+                4 => {
+                    if *cin && *cout {
+                        Some(Self::Cout)
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize, Hash, PartialEq, Eq)]
+pub enum ComparisonType {
+    /// Equal (==)
+    EQ,
+    /// Less than (<)
+    LT,
+    /// Less than or equal (<)
+    LTE,
+    /// Greater than (>)
+    GT,
+    /// Greater than or equal (>=)
+    GTE,
+}
+
+impl ComparisonType {
+    pub const TYPES: &[ComparisonType] = &[Self::EQ, Self::LT, Self::LTE, Self::GT, Self::GTE];
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            ComparisonType::EQ => "==",
+            ComparisonType::LT => "<",
+            ComparisonType::LTE => "<=",
+            ComparisonType::GT => ">",
+            ComparisonType::GTE => ">=",
+        }
+    }
+}
+
+/// Condition over the inputs of the assertion, checked during simulation
+#[derive(Clone, Copy, Serialize, Deserialize, Hash, PartialEq, Eq)]
+pub enum AssertionType {
+    /// Inputs are never all high
+    NotAll,
+    /// At most one input is high
+    AtMostOne,
+    /// Exactly one input is high
+    OneHot,
+    /// All inputs have the same value
+    AllEqual,
+    /// All inputs are high
+    AllHigh,
+    /// All inputs are low
+    AllLow,
+}
+
+impl AssertionType {
+    pub const TYPES: &[AssertionType] = &[
+        Self::NotAll,
+        Self::AtMostOne,
+        Self::OneHot,
+        Self::AllEqual,
+        Self::AllHigh,
+        Self::AllLow,
+    ];
+
+    pub fn to_str(self) -> &'static str {
+        match self {
+            AssertionType::NotAll => "!ALL",
+            AssertionType::AtMostOne => "<=1",
+            AssertionType::OneHot => "ONEHOT",
+            AssertionType::AllEqual => "EQ",
+            AssertionType::AllHigh => "ALL",
+            AssertionType::AllLow => "NONE",
+        }
+    }
+
+    /// Returns true if the condition holds for the input values
+    pub fn check(self, inputs: &[bool]) -> bool {
+        let n_high = inputs.iter().filter(|v| **v).count();
+        match self {
+            AssertionType::NotAll => n_high < inputs.len(),
+            AssertionType::AtMostOne => n_high <= 1,
+            AssertionType::OneHot => n_high == 1,
+            AssertionType::AllEqual => n_high == 0 || n_high == inputs.len(),
+            AssertionType::AllHigh => n_high == inputs.len(),
+            AssertionType::AllLow => n_high == 0,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Clone, Copy, Hash, PartialEq, Eq)]
+pub enum PrimitiveType {
+    // Logic gates:
+    And(usize),
+    Or(usize),
+    Xor(usize),
+    Nand(usize),
+    Not,
+    /// Buffer with the output in high impedance while the enable input is low
+    TriState,
+    Point,
+
+    // Muxes:
+    Mux(usize),
+
+    // I/O:
+    Input,
+    Output,
+    /// Clock source for simulation
+    Clock,
+    /// Off-sheet connector, connected to all connectors with the same signal name.
+    /// The signal name is the name of the component.
+    Connector,
+
+    // Interactive inputs, changed by clicking during simulation:
+    /// Momentary push button
+    Button,
+    /// Toggle switch
+    Switch,
+    /// Bank of toggle switches
+    DipSwitch(usize),
+    /// 4x4 keypad with the code of the pressed key and the "pressed" outputs
+    HexKeypad,
+
+    // Stimulus generators, advanced by the rising edge of the clock input:
+    /// Repeating pattern of `length` bits, starting from the least significant bit
+    Pattern { pattern: u32, length: usize },
+    /// Pseudo-random value of `width` bits, the sequence is defined by the seed
+    Random { width: usize, seed: u32 },
+
+    // Verification:
+    /// Checks the condition over the inputs, violations pause the simulation
+    Assertion { condition: AssertionType, n_inputs: usize },
+
+    // Arithmetic:
+    Comparator(ComparisonType),
+    Adder { cin: bool, cout: bool },
+
+    // D-type flip-flop:
+    DFF(DFFParams),
+}
+
+impl PrimitiveType {
+    /// Default limit of the inputs of gates and muxes
+    pub const DEFAULT_MAX_GATE_INPUTS: usize = 99;
+    /// The limit of the inputs can't be raised above it
+    pub const MAX_GATE_INPUTS_LIMIT: usize = 1024;
+    /// Gates with more inputs have the compact layout: the inputs are stacked
+    /// on every row of the pin block, the gate next to it is drawn like a small one
+    pub const COMPACT_GATE_INPUTS: usize = 16;
+    /// Gate drawn next to the pin block has this number of inputs
+    const COMPACT_BODY_INPUTS: usize = 5;
+    /// Pins indexed at each end of the pin block, the ones between are shown by an ellipsis
+    const COMPACT_INDEXED_PINS: usize = 3;
+    const PIN_BLOCK_WIDTH: f32 = 0.5;
+
+    /// Max inputs of gates and muxes, configured by [`set_max_gate_inputs`]
+    pub fn get_max_gate_inputs() -> usize {
+        MAX_GATE_INPUTS.load(Ordering::Relaxed)
+    }
+
+    /// Max inputs of gates and muxes in the allowed range
+    pub fn clamp_max_gate_inputs(n: usize) -> usize {
+        n.clamp(2, Self::MAX_GATE_INPUTS_LIMIT)
+    }
+
+    fn is_compact_gate(n_inputs: usize) -> bool {
+        n_inputs > Self::COMPACT_GATE_INPUTS
+    }
+
+    //
+    // *** Compact gate ***
+    //
+
+    /// Number of inputs of AND, OR, XOR and NAND gates
+    fn get_gate_inputs(&self) -> Option<usize> {
+        match self {
+            Self::And(n) | Self::Or(n) | Self::Xor(n) | Self::Nand(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// Gate of the same type drawn next to the pin block
+    fn get_compact_body(&self) -> Self {
+        let n = Self::COMPACT_BODY_INPUTS;
+        match self {
+            Self::And(_) => Self::And(n),
+            Self::Or(_) => Self::Or(n),
+            Self::Xor(_) => Self::Xor(n),
+            _ => Self::Nand(n),
+        }
+    }
+
+    /// Vertical offset of the body, it's at the middle of the pin block
+    fn get_compact_body_offset(n_inputs: usize) -> Vec2 {
+        let height = Self::get_and_gate_dimension_raw(n_inputs).1;
+        vec2(0.0, ((height - Self::COMPACT_BODY_INPUTS as i32) / 2) as f32)
+    }
+
+    fn get_compact_gate_polygons_points_raw(&self, lod_level: LodLevel) -> Vec<Vec<Pos2>> {
+        let n_inputs = self.get_gate_inputs().unwrap();
+        let offset = Self::get_compact_body_offset(n_inputs);
+        let mut result: Vec<Vec<Pos2>> = (self.get_compact_body())
+            .get_polygons_points_raw(lod_level)
+            .into_iter()
+            .map(|points| points.into_iter().map(|p| p + offset).collect())
+            .collect();
+        // The block covers the left side of the body:
+        let height = Self::get_and_gate_dimension_raw(n_inputs).1 as f32;
+        let ofs = STROKE_SCALE * 0.5;
+        result.push(vec![
+            pos2(ofs, ofs),
+            pos2(Self::PIN_BLOCK_WIDTH, ofs),
+            pos2(Self::PIN_BLOCK_WIDTH, height - ofs),
+            pos2(ofs, height - ofs),
+        ]);
+        result
+    }
+
+    fn get_compact_gate_lines_raw(&self, lod_level: LodLevel) -> Vec<Vec<Pos2>> {
+        let offset = Self::get_compact_body_offset(self.get_gate_inputs().unwrap());
+        (self.get_compact_body())
+            .get_lines(lod_level)
+            .into_iter()
+            .map(|line| line.into_iter().map(|p| p + offset).collect())
+            .collect()
+    }
+
+    fn get_compact_gate_text_labels(n_inputs: usize) -> Vec<(Pos2, String, Rotation, Align2)> {
+        let x = Self::PIN_BLOCK_WIDTH + 0.1;
+        let indexed = Self::COMPACT_INDEXED_PINS;
+        let mut result: Vec<_> = (0..indexed)
+            .chain(n_inputs - indexed..n_inputs)
+            .map(|i| {
+                let pos = pos2(x, i as f32 + 0.5);
+                (pos, i.to_string(), Rotation::ROT0, Align2::LEFT_CENTER)
+            })
+            .collect();
+        // Ellipses between the indexed pins and the body:
+        let body_top = Self::get_compact_body_offset(n_inputs).y;
+        let body_bottom = body_top + Self::COMPACT_BODY_INPUTS as f32;
+        for y in [
+            (indexed as f32 + body_top) / 2.0,
+            (body_bottom + (n_inputs - indexed) as f32) / 2.0,
+        ] {
+            let pos = pos2(x, y);
+            result.push((pos, "…".into(), Rotation::ROT90, Align2::CENTER_CENTER));
+        }
+        result
+    }
+
+    //
+    // *** And gate ***
+    //
+    fn get_and_gate_dimension_raw(n_inputs: usize) -> (i32, i32) {
+        if Self::is_compact_gate(n_inputs) {
+            // Odd, so the output is at the middle row:
+            (3, (n_inputs | 1) as i32)
+        } else if n_inputs.is_multiple_of(2) {
+            (3, (2 * n_inputs - 1) as i32)
+        } else {
+            (3, n_inputs as i32)
+        }
+    }
+
+    fn get_and_gate_dock_cell_raw(port: PPort, n_inputs: usize) -> GridPos {
+        match port {
+            PPort::Output(0) => {
+                let raw_dim = Self::get_and_gate_dimension_raw(n_inputs);
+                grid_pos(raw_dim.0, raw_dim.1 / 2)
+            }
+            PPort::Input(inp_id) => {
+                if n_inputs.is_multiple_of(2) && !Self::is_compact_gate(n_inputs) {
+                    grid_pos(-1, 2 * inp_id as i32)
+                } else {
+                    grid_pos(-1, inp_id as i32)
+                }
+            }
+            _ => panic!("Unexpected port"),
+        }
+    }
+
+    fn get_and_gate_connection_position_raw(port: PPort, n_inputs: usize) -> Pos2 {
+        match port {
+            PPort::Output(0) => {
+                let (w, h) = Self::get_and_gate_dimension_raw(n_inputs);
+                pos2(w as f32, h as f32 / 2.0)
+            }
+            PPort::Input(inp_id) => {
+                if n_inputs.is_multiple_of(2) && !Self::is_compact_gate(n_inputs) {
+                    pos2(0.0, (2 * inp_id) as f32 + 0.5)
+                } else {
+                    pos2(0.0, inp_id as f32 + 0.5)
+                }
+            }
+            _ => panic!("Unexpected port"),
+        }
+    }
+
+    fn get_and_gate_shape_points(
+        stroke_w: f32,
+        radius_x: f32,
+        radius_y: f32,
+        center: Pos2,
+        height: f32,
+        lod_level: LodLevel,
+    ) -> Vec<Pos2> {
+        let n_points = match lod_level {
+            LodLevel::Max => 30,
+            LodLevel::Mid => 8,
+            LodLevel::Min => 4,
+        }; // Number of points per curve segment
+        let mut points = (0..=n_points)
+            .map(|i| {
+                let angle = -PI / 2.0 + PI * (i as f32 / n_points as f32);
+                let x = center.x + radius_x * angle.cos();
+                let y = center.y + radius_y * angle.sin();
+                Pos2::new(x, y)
+            })
+            .collect::<Vec<_>>();
+        points.insert(0, pos2(stroke_w / 2.0, stroke_w / 2.0));
+        points.insert(0, pos2(stroke_w / 2.0, height - stroke_w / 2.0));
+        points
+    }
+
+    fn get_and_gate_polygon_points_raw(n_inputs: usize, lod_level: LodLevel) -> Vec<Pos2> {
+        let stroke_w = STROKE_SCALE;
+        let height = if n_inputs % 2 == 0 {
+            (2 * n_inputs - 1) as f32
+        } else {
+            n_inputs as f32
+        };
+        let radius_x = 1.0 - stroke_w / 2.0;
+        let radius_y = height as f32 / 2.0 - stroke_w / 2.0;
+        let center = pos2(2.0, height / 2.0);
+        Self::get_and_gate_shape_points(stroke_w, radius_x, radius_y, center, height, lod_level)
+    }
+
+    //
+    // *** Or gate ***
+    //
+
+    fn get_or_gate_dimension_raw(n_inputs: usize) -> (i32, i32) {
+        return Self::get_and_gate_dimension_raw(n_inputs);
+    }
+
+    fn get_or_gate_dock_cell_raw(port: PPort, n_inputs: usize) -> GridPos {
+        Self::get_and_gate_dock_cell_raw(port, n_inputs)
+    }
+
+    fn get_or_gate_connection_position_raw(port: PPort, n_inputs: usize) -> Pos2 {
+        Self::get_and_gate_connection_position_raw(port, n_inputs)
+    }
+
+    fn get_or_left_curve(
+        top_point: Pos2,
+        bottom_point: Pos2,
+        left_control: Pos2,
+        n_curve_points: usize,
+    ) -> Vec<Pos2> {
+        let mut points = Vec::with_capacity(n_curve_points);
+        // Left concave curve (single quadratic Bezier from top to bottom)
+        for i in 0..=n_curve_points {
+            let t = i as f32 / n_curve_points as f32;
+            // Quadratic Bezier formula: P0 = top_point, P1 = left_control, P2 = bottom_point
+            let x = (1.0 - t).powi(2) * top_point.x
+                + 2.0 * (1.0 - t) * t * left_control.x
+                + t.powi(2) * bottom_point.x;
+            let y = (1.0 - t).powi(2) * top_point.y
+                + 2.0 * (1.0 - t) * t * left_control.y
+                + t.powi(2) * bottom_point.y;
+            points.push(pos2(x, y));
+        }
+        points
+    }
+
+    fn get_or_gate_shape_points(
+        top_point: Pos2,
+        bottom_point: Pos2,
+        left_control: Pos2,
+        tip_point: Pos2,
+        middle_x: f32,
+        n_curve_points: usize,
+    ) -> Vec<Pos2> {
+        let mut points = Vec::new();
+        // Configurable parameters
+        let curve_strength = 0.0; // Right curve bend strength (0.0-1.0)
+
+        points.extend(Self::get_or_left_curve(
+            top_point,
+            bottom_point,
+            left_control,
+            n_curve_points,
+        ));
+
+        // Calculate control points for right curves
+        let bottom_control = pos2(
+            middle_x,
+            bottom_point.y + (tip_point.y - bottom_point.y) * curve_strength,
+        );
+
+        let top_control = pos2(
+            middle_x,
+            top_point.y + (tip_point.y - top_point.y) * curve_strength,
+        );
+
+        // Bottom right curve (from bottom point to tip)
+        for i in 1..=n_curve_points {
+            let t = i as f32 / n_curve_points as f32;
+            let x = (1.0 - t).powi(2) * bottom_point.x
+                + 2.0 * (1.0 - t) * t * bottom_control.x
+                + t.powi(2) * tip_point.x;
+            let y = (1.0 - t).powi(2) * bottom_point.y
+                + 2.0 * (1.0 - t) * t * bottom_control.y
+                + t.powi(2) * tip_point.y;
+            points.push(pos2(x, y));
+        }
+
+        // Top right curve (from tip to top point)
+        for i in 1..=n_curve_points {
+            let t = i as f32 / n_curve_points as f32;
+            let x = (1.0 - t).powi(2) * tip_point.x
+                + 2.0 * (1.0 - t) * t * top_control.x
+                + t.powi(2) * top_point.x;
+            let y = (1.0 - t).powi(2) * tip_point.y
+                + 2.0 * (1.0 - t) * t * top_control.y
+                + t.powi(2) * top_point.y;
+            points.push(pos2(x, y));
+        }
+
+        points
+    }
+
+    fn get_or_gate_polygon_points_raw(n_inputs: usize, lod_level: LodLevel) -> Vec<Pos2> {
+        let grid_size = 1.0;
+        let pos = pos2(0.0, 0.0);
+        let stroke_w = STROKE_SCALE;
+        let height_factor = if n_inputs % 2 == 0 {
+            (2 * n_inputs - 1) as f32
+        } else {
+            n_inputs as f32
+        };
+        let height = height_factor * grid_size;
+
+        // Configurable parameters
+        let center_y = pos.y + height / 2.0;
+        let tip_x_factor = 3.0; // Tip position multiplier
+        let left_curve_strength = 1.0; // Left curve concavity strength
+        let n_curve_points = match lod_level {
+            LodLevel::Max => 30,
+            LodLevel::Mid => 5,
+            LodLevel::Min => 2,
+        }; // Number of points per curve segment
+
+        // Key points
+        let top_point = pos2(pos.x + stroke_w * 0.5, pos.y + stroke_w * 0.5);
+        let bottom_point = pos2(pos.x + stroke_w * 0.5, pos.y + height - stroke_w * 0.5);
+        let left_control = pos2(pos.x + grid_size * left_curve_strength, center_y);
+        let tip_point = pos2(pos.x + tip_x_factor * grid_size, center_y);
+        let middle_x = pos.x + 2.2 * grid_size;
+
+        Self::get_or_gate_shape_points(
+            top_point,
+            bottom_point,
+            left_control,
+            tip_point,
+            middle_x,
+            n_curve_points,
+        )
+    }
+
+    fn get_or_gate_lines_raw(n_inputs: usize) -> Vec<Vec<Pos2>> {
+        let mut result = Vec::with_capacity(n_inputs);
+
+        let grid_size = 1.0;
+        let pos = pos2(0.0, 0.0);
+        let stroke_w = STROKE_SCALE;
+        let height_factor = if n_inputs % 2 == 0 {
+            (2 * n_inputs - 1) as f32
+        } else {
+            n_inputs as f32
+        };
+        let height = height_factor * grid_size;
+
+        let top_point = pos2(pos.x + stroke_w * 0.5, pos.y + stroke_w * 0.5);
+        let bottom_point = pos2(pos.x + stroke_w * 0.5, pos.y + height - stroke_w * 0.5);
+        let left_curve_strength = 1.0;
+        let left_control = pos2(
+            pos.x + grid_size * left_curve_strength,
+            pos.y + height / 2.0,
+        );
+
+        let min_y = top_point.y;
+        let max_y = bottom_point.y;
+        let y_range = max_y - min_y;
+
+        for i in 0..n_inputs {
+            let p0 = Self::get_or_gate_connection_position_raw(PPort::Input(i), n_inputs);
+            let y = p0.y;
+
+            let t = if y_range.abs() < f32::EPSILON {
+                0.5
+            } else {
+                ((y - min_y) / y_range).clamp(0.0, 1.0)
+            };
+
+            let x = (1.0 - t).powi(2) * top_point.x
+                + 2.0 * (1.0 - t) * t * left_control.x
+                + t.powi(2) * bottom_point.x;
+
+            result.push(vec![p0, pos2(x, y)]);
+        }
+
+        result
+    }
+
+    //
+    // *** Xor gate ***
+    //
+    fn get_xor_gate_dimension_raw(n_inputs: usize) -> (i32, i32) {
+        return Self::get_and_gate_dimension_raw(n_inputs);
+    }
+
+    fn get_xor_gate_dock_cell_raw(port: PPort, n_inputs: usize) -> GridPos {
+        Self::get_and_gate_dock_cell_raw(port, n_inputs)
+    }
+
+    fn get_xor_gate_connection_position_raw(port: PPort, n_inputs: usize) -> Pos2 {
+        Self::get_and_gate_connection_position_raw(port, n_inputs)
+    }
+
+    fn get_xor_gate_polygon_points_raw(n_inputs: usize, lod_level: LodLevel) -> Vec<Pos2> {
+        let grid_size = 1.0;
+        let pos = pos2(0.0, 0.0);
+        let stroke_w = STROKE_SCALE;
+        let height_factor = if n_inputs % 2 == 0 {
+            (2 * n_inputs - 1) as f32
+        } else {
+            n_inputs as f32
+        };
+        let height = height_factor * grid_size;
+
+        let center_y = pos.y + height / 2.0;
+
+        // Configurable parameters
+        let tip_x_factor = 3.0; // Tip position multiplier
+        let n_curve_points = match lod_level {
+            LodLevel::Max => 30,
+            LodLevel::Mid => 5,
+            LodLevel::Min => 2,
+        }; // Number of points per curve segment
+        let left_curve_strength = 1.0; // Left curve concavity strength
+
+        // Key points
+        let top_point = pos2(
+            pos.x + 0.25 * grid_size + stroke_w * 0.5,
+            pos.y + stroke_w * 0.5,
+        );
+        let bottom_point = pos2(
+            pos.x + 0.25 * grid_size + stroke_w * 0.5,
+            pos.y + height - stroke_w * 0.5,
+        );
+        let left_control = pos2(
+            pos.x + 0.25 * grid_size + grid_size * left_curve_strength,
+            center_y,
+        );
+        let tip_point = pos2(pos.x + tip_x_factor * grid_size, center_y);
+        let middle_x = pos.x + 2.2 * grid_size;
+
+        Self::get_or_gate_shape_points(
+            top_point,
+            bottom_point,
+            left_control,
+            tip_point,
+            middle_x,
+            n_curve_points,
+        )
+    }
+
+    fn get_xor_gate_lines_raw(n_inputs: usize, lod_level: LodLevel) -> Vec<Vec<Pos2>> {
+        let mut result = Vec::with_capacity(n_inputs + 1);
+        result.extend(Self::get_or_gate_lines_raw(n_inputs));
+        let grid_size = 1.0;
+        let pos = pos2(0.0, 0.0);
+        let stroke_w = STROKE_SCALE;
+        let height_factor = if n_inputs % 2 == 0 {
+            (2 * n_inputs - 1) as f32
+        } else {
+            n_inputs as f32
+        };
+        let height: f32 = height_factor * grid_size;
+
+        let top_point = pos2(pos.x + stroke_w * 0.5, pos.y + stroke_w * 0.5);
+        let bottom_point = pos2(pos.x + stroke_w * 0.5, pos.y + height - stroke_w * 0.5);
+        let left_curve_strength = 1.0;
+        let left_control = pos2(
+            pos.x + grid_size * left_curve_strength,
+            pos.y + height / 2.0,
+        );
+
+        let n_curve_points = match lod_level {
+            LodLevel::Max => 30,
+            LodLevel::Mid => 5,
+            LodLevel::Min => 2,
+        }; // Number of points per curve segment
+        result.push(Self::get_or_left_curve(
+            top_point,
+            bottom_point,
+            left_control,
+            n_curve_points,
+        ));
+
+        result
+    }
+
+    //
+    // *** Nand gate ***
+    //
+    fn get_nand_gate_dimension_raw(n_inputs: usize) -> (i32, i32) {
+        Self::get_and_gate_dimension_raw(n_inputs)
+    }
+
+    fn get_nand_gate_dock_cell_raw(port: PPort, n_inputs: usize) -> GridPos {
+        Self::get_and_gate_dock_cell_raw(port, n_inputs)
+    }
+
+    fn get_nand_gate_connection_position_raw(port: PPort, n_inputs: usize) -> Pos2 {
+        Self::get_and_gate_connection_position_raw(port, n_inputs)
+    }
+
+    fn get_circle_points(center: Pos2, radius: f32, lod_level: LodLevel) -> Vec<Pos2> {
+        let n_circle_points = match lod_level {
+            LodLevel::Max => 40,
+            LodLevel::Mid => 6,
+            LodLevel::Min => 4,
+        };
+        let mut circle_points: Vec<Pos2> = Vec::with_capacity(n_circle_points);
+        for i in 0..n_circle_points {
+            let angle = (i as f32 / n_circle_points as f32) * TAU;
+            let x = center.x + radius * angle.cos();
+            let y = center.y + radius * angle.sin();
+            circle_points.push(Pos2::new(x, y));
+        }
+        circle_points
+    }
+
+    fn get_nand_gate_polygons_points_raw(n_inputs: usize, lod_level: LodLevel) -> Vec<Vec<Pos2>> {
+        let stroke_w = STROKE_SCALE;
+        let height = if n_inputs % 2 == 0 {
+            (2 * n_inputs - 1) as f32
+        } else {
+            n_inputs as f32
+        };
+        let radius_x = 1.0 - stroke_w / 2.0;
+        let radius_y = height as f32 / 2.0 - stroke_w / 2.0;
+        let center = pos2(1.5, height / 2.0);
+        vec![
+            Self::get_and_gate_shape_points(
+                stroke_w, radius_x, radius_y, center, height, lod_level,
+            ),
+            Self::get_circle_points(center + vec2(radius_x, 0.0), 0.25, lod_level),
+        ]
+    }
+
+    fn get_nand_gate_lines_raw(n_inputs: usize) -> Vec<Vec<Pos2>> {
+        let height = if n_inputs % 2 == 0 {
+            (2 * n_inputs - 1) as f32
+        } else {
+            n_inputs as f32
+        };
+        let stroke_w = STROKE_SCALE;
+        let radius_x = 1.0 - stroke_w / 2.0;
+        let center = pos2(1.5, height / 2.0);
+        return vec![vec![center + vec2(radius_x, 0.0), pos2(3.0, height / 2.0)]];
+    }
+
+    //
+    // *** Mux ***
+    //
+    fn get_mux_dimension_raw(n_inputs: usize) -> (i32, i32) {
+        let w = if n_inputs > 3 { 2 } else { 1 };
+        let h = if n_inputs % 2 == 0 {
+            (2 * n_inputs - 1) as i32
+        } else {
+            n_inputs as i32
+        };
+        return (w, h);
+    }
+
+    fn get_mux_dock_cell_raw(port: PPort, n_inputs: usize) -> GridPos {
+        match port {
+            PPort::Output(0) => {
+                let (w, h) = Self::get_mux_dimension_raw(n_inputs);
+                grid_pos(w, h / 2)
+            }
+            PPort::Select => {
+                let (w, h) = Self::get_mux_dimension_raw(n_inputs);
+                if w == 1 {
+                    grid_pos(0, h)
+                } else {
+                    grid_pos(1, h)
+                }
+            }
+            PPort::Input(inp_id) => {
+                if n_inputs % 2 == 0 {
+                    grid_pos(-1, 2 * inp_id as i32)
+                } else {
+                    grid_pos(-1, inp_id as i32)
+                }
+            }
+            _ => panic!("Unexpected port"),
+        }
+    }
+
+    fn get_mux_connection_position_raw(port: PPort, n_inputs: usize) -> Pos2 {
+        match port {
+            PPort::Output(0) => {
+                // Output:
+                let (w, h) = Self::get_mux_dimension_raw(n_inputs);
+                pos2(w as f32, h as f32 / 2.0)
+            }
+            PPort::Select => {
+                let (w, h) = Self::get_mux_dimension_raw(n_inputs);
+                if w == 1 {
+                    pos2(0.5, h as f32 - 0.25)
+                } else {
+                    pos2(1.5, h as f32 - 0.75)
+                }
+            }
+            PPort::Input(inp_id) => {
+                // Inputs:
+                if n_inputs % 2 == 0 {
+                    pos2(0.0, (2 * inp_id) as f32 + 0.5)
+                } else {
+                    pos2(0.0, inp_id as f32 + 0.5)
+                }
+            }
+            _ => panic!("Unexpected port"),
+        }
+    }
+
+    fn get_mux_polygon_points_raw(n_inputs: usize) -> Vec<Pos2> {
+        let (w, h) = Self::get_mux_dimension_raw(n_inputs);
+        let stroke_ofs = STROKE_SCALE * 0.5;
+        return vec![
+            pos2(stroke_ofs, stroke_ofs),
+            pos2(w as f32 - stroke_ofs, stroke_ofs + 0.5 * w as f32),
+            pos2(
+                w as f32 - stroke_ofs,
+                h as f32 - 0.5 * w as f32 - stroke_ofs,
+            ),
+            pos2(stroke_ofs, h as f32 - stroke_ofs),
+        ];
+    }
+
+    //
+    // *** Input ***
+    //
+    fn get_input_dock_cell_raw() -> GridPos {
+        grid_pos(2, 0)
+    }
+
+    fn get_input_polygon_points_raw() -> Vec<Pos2> {
+        let pos = pos2(0.0, 0.0);
+        let grid_size = 1.0;
+        let stroke_w = STROKE_SCALE;
+
+        let p0 = pos + vec2(stroke_w * 0.5, stroke_w * 0.5);
+        let p1 = pos + vec2(grid_size * 1.5 - stroke_w * 0.5, stroke_w * 0.5);
+        let p2 = pos + vec2(2.0 * grid_size - stroke_w * 0.5, 0.5 * grid_size);
+        let p3 = pos + vec2(grid_size * 1.5 - stroke_w * 0.5, grid_size - stroke_w * 0.5);
+        let p4 = pos + vec2(stroke_w * 0.5, grid_size - stroke_w * 0.5);
+        let p5 = pos + vec2(stroke_w * 0.5 + grid_size * 0.5, grid_size * 0.5);
+
+        return vec![p0, p1, p2, p3, p4, p5];
+    }
+
+    fn get_input_connection_position_raw(_port: PPort) -> Pos2 {
+        pos2(2.0, 0.5)
+    }
+
+    //
+    // *** Output ***
+    //
+    fn get_output_dock_cell_raw() -> GridPos {
+        grid_pos(-1, 0)
+    }
+
+    fn get_output_polygon_points_raw() -> Vec<Pos2> {
+        Self::get_input_polygon_points_raw()
+    }
+
+    fn get_output_connection_position_raw(_port: PPort) -> Pos2 {
+        pos2(0.0, 0.5)
+    }
+
+    fn get_output_lines_raw() -> Vec<Vec<Pos2>> {
+        vec![vec![pos2(0.0, 0.5), pos2(0.5, 0.5)]]
+    }
+
+    //
+    // *** Connector ***
+    //
+    fn get_connector_polygon_points_raw() -> Vec<Pos2> {
+        let w = STROKE_SCALE * 0.5;
+        vec![
+            pos2(w, 0.5),
+            pos2(0.5, w),
+            pos2(1.5, w),
+            pos2(2.0 - w, 0.5),
+            pos2(1.5, 1.0 - w),
+            pos2(0.5, 1.0 - w),
+        ]
+    }
+
+    //
+    // *** Clock ***
+    //
+    fn get_clock_lines_raw() -> Vec<Vec<Pos2>> {
+        // Square wave inside the input symbol:
+        vec![vec![
+            pos2(0.6, 0.7),
+            pos2(0.8, 0.7),
+            pos2(0.8, 0.3),
+            pos2(1.05, 0.3),
+            pos2(1.05, 0.7),
+            pos2(1.3, 0.7),
+        ]]
+    }
+
+    //
+    // *** Interactive inputs ***
+    //
+    const KEYPAD_DIMENSION: (i32, i32) = (5, 5);
+    const KEYPAD_OUTPUTS: usize = 5;
+    const KEYPAD_KEYS: &str = "0123456789ABCDEF";
+    pub const MAX_DIP_SWITCHES: usize = 32;
+    /// Right edge of the body, the outputs are on the right of it
+    fn get_source_body_width(dim: (i32, i32)) -> f32 {
+        dim.0 as f32 - 0.5
+    }
+
+    fn get_source_dock_cell_raw(dim: (i32, i32), port: PPort) -> GridPos {
+        match port {
+            PPort::Output(i) => grid_pos(dim.0, i as i32),
+            _ => panic!("Unexpected port"),
+        }
+    }
+
+    fn get_source_connection_position_raw(dim: (i32, i32), port: PPort) -> Pos2 {
+        match port {
+            PPort::Output(i) => pos2(dim.0 as f32, i as f32 + 0.5),
+            _ => panic!("Unexpected port"),
+        }
+    }
+
+    fn get_source_polygon_points_raw(dim: (i32, i32)) -> Vec<Pos2> {
+        let ofs = STROKE_SCALE * 0.5;
+        let w = Self::get_source_body_width(dim);
+        vec![
+            pos2(ofs, ofs),
+            pos2(w - ofs, ofs),
+            pos2(w - ofs, dim.1 as f32 - ofs),
+            pos2(ofs, dim.1 as f32 - ofs),
+        ]
+    }
+
+    fn rect_line(rect: Rect) -> Vec<Pos2> {
+        vec![
+            rect.left_top(),
+            rect.right_top(),
+            rect.right_bottom(),
+            rect.left_bottom(),
+            rect.left_top(),
+        ]
+    }
+
+    /// Slot of the switch in a DIP switch bank
+    fn get_dip_slot_raw(i: usize) -> Rect {
+        Rect::from_min_max(pos2(0.3, i as f32 + 0.3), pos2(1.2, i as f32 + 0.7))
+    }
+
+    fn get_keypad_key_raw(key: usize) -> Rect {
+        let (col, row) = ((key % 4) as f32, (key / 4) as f32);
+        Rect::from_min_max(pos2(col + 0.15, row + 0.15), pos2(col + 0.85, row + 0.85))
+    }
+
+    fn get_source_lines_raw(&self) -> Vec<Vec<Pos2>> {
+        let dim = self.get_dimension_raw();
+        let w = Self::get_source_body_width(dim);
+        // Output stubs:
+        let mut result: Vec<Vec<Pos2>> = (0..self.get_connections_number())
+            .map(|i| vec![pos2(w, i as f32 + 0.5), pos2(dim.0 as f32, i as f32 + 0.5)])
+            .collect();
+        match self {
+            Self::Button => {
+                result.push(vec![pos2(0.3, 0.7), pos2(1.2, 0.7)]);
+                result.push(vec![pos2(0.75, 0.7), pos2(0.75, 0.3)]);
+                result.push(vec![pos2(0.5, 0.3), pos2(1.0, 0.3)]);
+            }
+            Self::Switch => {
+                result.push(vec![pos2(0.2, 0.7), pos2(0.5, 0.7), pos2(1.1, 0.3)]);
+                result.push(vec![pos2(1.0, 0.7), pos2(1.3, 0.7)]);
+            }
+            Self::DipSwitch(n) => {
+                result.extend((0..*n).map(|i| Self::rect_line(Self::get_dip_slot_raw(i))));
+            }
+            Self::HexKeypad => {
+                result.extend((0..16).map(|key| Self::rect_line(Self::get_keypad_key_raw(key))));
+            }
+            _ => {}
+        }
+        result
+    }
+
+    fn get_keypad_text_labels() -> Vec<(Pos2, String, Rotation, Align2)> {
+        Self::KEYPAD_KEYS
+            .chars()
+            .enumerate()
+            .map(|(key, c)| {
+                (
+                    Self::get_keypad_key_raw(key).center(),
+                    c.to_string(),
+                    Rotation::ROT0,
+                    Align2::CENTER_CENTER,
+                )
+            })
+            .chain([(
+                pos2(2.0, 4.5),
+                "HEX".to_owned(),
+                Rotation::ROT0,
+                Align2::CENTER_CENTER,
+            )])
+            .collect()
+    }
+
+    /// Inputs which are changed by clicking during simulation
+    pub fn is_interactive(&self) -> bool {
+        matches!(
+            self,
+            Self::Button | Self::Switch | Self::DipSwitch(_) | Self::HexKeypad
+        )
+    }
+
+    /// Handles pressing (or releasing) of the interactive input at the `raw` point of the symbol.
+    /// Returns true if the state is changed.
+    pub fn click(&self, raw: Pos2, pressed: bool, state: &mut PrimitiveState) -> bool {
+        let old = *state;
+        match self {
+            Self::Button => state.value = pressed,
+            Self::Switch if pressed => state.value = !state.value,
+            Self::DipSwitch(n) if pressed && *n > 0 => {
+                let i = (raw.y.max(0.0) as usize).min(n - 1);
+                state.bits ^= 1 << i;
+            }
+            Self::HexKeypad => {
+                match (0..16).find(|key| Self::get_keypad_key_raw(*key).contains(raw)) {
+                    Some(key) if pressed => {
+                        state.bits = key as u64;
+                        state.value = true;
+                    }
+                    _ => state.value = false,
+                }
+            }
+            _ => {}
+        }
+        *state != old
+    }
+
+    /// Areas of the symbol shown as active during simulation, in raw coordinates
+    pub fn get_active_areas(&self, state: &PrimitiveState) -> Vec<Rect> {
+        let body = Rect::from_min_max(
+            pos2(0.0, 0.0),
+            pos2(Self::get_source_body_width(self.get_dimension_raw()), 1.0),
+        );
+        match self {
+            Self::Button | Self::Switch if state.value => vec![body],
+            Self::DipSwitch(n) => (0..*n)
+                .filter(|i| state.bits >> i & 1 == 1)
+                .map(Self::get_dip_slot_raw)
+                .collect(),
+            Self::HexKeypad if state.value => vec![Self::get_keypad_key_raw(state.bits as usize)],
+            _ => vec![],
+        }
+    }
+
+    //
+    // *** Stimulus generators ***
+    //
+    pub const MAX_PATTERN_LENGTH: usize = 32;
+    pub const MAX_RANDOM_WIDTH: usize = 16;
+
+    fn get_stimulus_width(&self) -> usize {
+        match self {
+            Self::Random { width, .. } => *width,
+            _ => 1,
+        }
+    }
+
+    fn get_stimulus_dimension_raw(&self) -> (i32, i32) {
+        (4, self.get_stimulus_width() as i32 + 1)
+    }
+
+    fn get_stimulus_dock_cell_raw(&self, port: PPort) -> GridPos {
+        let (w, h) = self.get_stimulus_dimension_raw();
+        match port {
+            PPort::Clk => grid_pos(0, h - 1),
+            PPort::Output(i) => grid_pos(w - 1, i as i32),
+            _ => panic!("Unexpected port"),
+        }
+    }
+
+    fn get_stimulus_connection_position_raw(&self, port: PPort) -> Pos2 {
+        let cell = self.get_stimulus_dock_cell_raw(port);
+        pos2(cell.x as f32 + 0.5, cell.y as f32 + 0.5)
+    }
+
+    fn get_stimulus_polygons_points_raw(&self) -> Vec<Vec<Pos2>> {
+        let (w, h) = self.get_stimulus_dimension_raw();
+        let (w, h) = (w as f32, h as f32);
+        vec![
+            vec![
+                pos2(1.05, 0.05),
+                pos2(w - 1.05, 0.05),
+                pos2(w - 1.05, h - 0.05),
+                pos2(1.05, h - 0.05),
+            ],
+            // Clock edge mark:
+            vec![pos2(1.05, h - 0.9), pos2(1.6, h - 0.5), pos2(1.05, h - 0.1)],
+        ]
+    }
+
+    fn get_stimulus_lines_raw(&self) -> Vec<Vec<Pos2>> {
+        let (w, h) = self.get_stimulus_dimension_raw();
+        let (w, h) = (w as f32, h as f32);
+        let mut result = vec![vec![pos2(0.5, h - 0.5), pos2(1.0, h - 0.5)]];
+        result.extend(
+            (0..self.get_stimulus_width())
+                .map(|i| vec![pos2(w - 1.0, i as f32 + 0.5), pos2(w - 0.5, i as f32 + 0.5)]),
+        );
+        result
+    }
+
+    fn get_stimulus_text_labels(&self) -> Vec<(Pos2, String, Rotation, Align2)> {
+        let name = match self {
+            Self::Random { .. } => "RND",
+            _ => "PAT",
+        };
+        let (w, _) = self.get_stimulus_dimension_raw();
+        vec![(
+            pos2(w as f32 * 0.5, 0.25),
+            name.to_owned(),
+            Rotation::ROT0,
+            Align2::CENTER_TOP,
+        )]
+    }
+
+    /// Pattern as a string of '0' and '1', the first character is the first output value
+    pub fn pattern_to_string(pattern: u32, length: usize) -> String {
+        (0..length)
+            .map(|i| if pattern >> i & 1 == 1 { '1' } else { '0' })
+            .collect()
+    }
+
+    /// Parses the pattern from a string of '0' and '1', returns `(pattern, length)`
+    pub fn pattern_from_str(s: &str) -> Option<(u32, usize)> {
+        if s.is_empty() || s.len() > Self::MAX_PATTERN_LENGTH {
+            return None;
+        }
+        s.chars()
+            .enumerate()
+            .try_fold((0, s.len()), |(pattern, length), (i, c)| match c {
+                '0' => Some((pattern, length)),
+                '1' => Some((pattern | 1 << i, length)),
+                _ => None,
+            })
+    }
+
+    /// Next state of the xorshift generator, the state is never zero
+    fn random_next(state: u64, seed: u32) -> u64 {
+        let mut x = if state == 0 {
+            // Initial state from the seed (splitmix64):
+            let mut z = (seed as u64).wrapping_add(0x9e37_79b9_7f4a_7c15);
+            z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+            z ^ (z >> 31)
+        } else {
+            state
+        };
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        x.max(1)
+    }
+
+    //
+    // *** Assertion ***
+    //
+    pub const MAX_ASSERTION_INPUTS: usize = 32;
+
+    fn get_assertion_dimension_raw(n_inputs: usize) -> (i32, i32) {
+        (4, n_inputs.max(2) as i32)
+    }
+
+    fn get_assertion_dock_cell_raw(port: PPort) -> GridPos {
+        match port {
+            PPort::Input(i) => grid_pos(0, i as i32),
+            _ => panic!("Unexpected port"),
+        }
+    }
+
+    fn get_assertion_connection_position_raw(port: PPort) -> Pos2 {
+        match port {
+            PPort::Input(i) => pos2(0.5, i as f32 + 0.5),
+            _ => panic!("Unexpected port"),
+        }
+    }
+
+    fn get_assertion_polygon_points_raw(n_inputs: usize) -> Vec<Pos2> {
+        let (w, h) = Self::get_assertion_dimension_raw(n_inputs);
+        vec![
+            pos2(1.05, 0.05),
+            pos2(w as f32 - 0.05, 0.05),
+            pos2(w as f32 - 0.05, h as f32 - 0.05),
+            pos2(1.05, h as f32 - 0.05),
+        ]
+    }
+
+    fn get_assertion_lines_raw(n_inputs: usize) -> Vec<Vec<Pos2>> {
+        (0..n_inputs)
+            .map(|i| vec![pos2(0.5, i as f32 + 0.5), pos2(1.0, i as f32 + 0.5)])
+            .collect()
+    }
+
+    fn get_assertion_text_labels(
+        condition: AssertionType,
+        n_inputs: usize,
+    ) -> Vec<(Pos2, String, Rotation, Align2)> {
+        let (w, h) = Self::get_assertion_dimension_raw(n_inputs);
+        let center = (w as f32 + 1.0) * 0.5;
+        vec![
+            (
+                pos2(center, 0.25),
+                "ASSERT".to_owned(),
+                Rotation::ROT0,
+                Align2::CENTER_TOP,
+            ),
+            (
+                pos2(center, h as f32 - 0.25),
+                condition.to_str().to_owned(),
+                Rotation::ROT0,
+                Align2::CENTER_BOTTOM,
+            ),
+        ]
+    }
+
+    //
+    // *** Not ***
+    //
+    fn get_not_dock_cell_raw(port: PPort) -> GridPos {
+        match port {
+            PPort::Input(0) => grid_pos(-1, 1),
+            PPort::Output(0) => grid_pos(3, 1),
+            _ => panic!("Unexpected port"),
+        }
+    }
+
+    fn get_not_polygons_points_raw(lod_level: LodLevel) -> Vec<Vec<Pos2>> {
+        let stroke_w = STROKE_SCALE;
+        let grid_size = 1.0;
+        let p0 = pos2(
+            grid_size * 0.5 + stroke_w * 0.5,
+            grid_size * 0.5 + stroke_w * 0.5,
+        );
+        let p1 = pos2(2.5 * grid_size - stroke_w * 0.5, grid_size * 1.5);
+        let p2 = pos2(
+            grid_size * 0.5 + stroke_w * 0.5,
+            2.5 * grid_size - stroke_w * 0.5,
+        );
+        return vec![
+            vec![p0, p1, p2],
+            Self::get_circle_points(p1, grid_size * 0.25, lod_level),
+        ];
+    }
+
+    fn get_not_connection_position_raw(port: PPort) -> Pos2 {
+        match port {
+            PPort::Input(0) => pos2(0.0, 1.5),
+            PPort::Output(0) => pos2(3.0, 1.5),
+            _ => panic!("Unexpected port"),
+        }
+    }
+
+    fn get_not_lines_raw() -> Vec<Vec<Pos2>> {
+        let grid_size = 1.0;
+        vec![
+            vec![
+                pos2(0.0, grid_size * 1.5),
+                pos2(0.5 * grid_size, grid_size * 1.5),
+            ],
+            vec![
+                pos2(2.5 * grid_size, grid_size * 1.5),
+                pos2(3.0 * grid_size, grid_size * 1.5),
+            ],
+        ]
+    }
+
+    //
+    // *** Tri-state buffer ***
+    //
+    fn get_tristate_dock_cell_raw(port: PPort) -> GridPos {
+        match port {
+            PPort::Enable => grid_pos(1, 3),
+            _ => Self::get_not_dock_cell_raw(port),
+        }
+    }
+
+    fn get_tristate_connection_position_raw(port: PPort) -> Pos2 {
+        match port {
+            PPort::Enable => pos2(1.5, 3.0),
+            _ => Self::get_not_connection_position_raw(port),
+        }
+    }
+
+    fn get_tristate_polygons_points_raw(lod_level: LodLevel) -> Vec<Vec<Pos2>> {
+        // Triangle of the inverter without the circle:
+        let mut polygons = Self::get_not_polygons_points_raw(lod_level);
+        polygons.truncate(1);
+        polygons
+    }
+
+    fn get_tristate_lines_raw() -> Vec<Vec<Pos2>> {
+        let mut lines = Self::get_not_lines_raw();
+        lines.push(vec![pos2(1.5, 3.0), pos2(1.5, 2.0)]);
+        lines
+    }
+
+    //
+    // *** Comparator ***
+    //
+    const CMP_DIMENSION: (i32, i32) = (3, 3);
+    const CMP_N_CONNECTIONS: usize = 3;
+
+    fn get_cmp_dock_cell_raw(port: PPort) -> GridPos {
+        match port {
+            PPort::Input(0) => grid_pos(-1, 0),
+            PPort::Input(1) => grid_pos(-1, 2),
+            PPort::Output(0) => grid_pos(3, 1),
+            _ => panic!("Unexpected port"),
+        }
+    }
+
+    fn get_cmp_connection_position_raw(port: PPort) -> Pos2 {
+        match port {
+            PPort::Input(0) => pos2(0.0, 0.5),
+            PPort::Input(1) => pos2(0.0, 2.5),
+            PPort::Output(0) => pos2(3.0, 1.5),
+            _ => panic!("Unexpected port"),
+        }
+    }
+
+    fn get_cmp_polygons_points_raw(lod_level: LodLevel) -> Vec<Vec<Pos2>> {
+        vec![Self::get_circle_points(pos2(1.5, 1.5), 1.2, lod_level)]
+    }
+
+    fn get_cmp_lines_raw() -> Vec<Vec<Pos2>> {
+        vec![
+            vec![pos2(0.0, 0.5), pos2(0.85, 0.5)],
+            vec![pos2(0.0, 2.5), pos2(0.85, 2.5)],
+            vec![pos2(3.0, 1.5), pos2(2.7, 1.5)],
+        ]
+    }
+
+    fn get_cmp_text_labels(
+        comparison_type: &ComparisonType,
+    ) -> Vec<(Pos2, String, Rotation, Align2)> {
+        vec![(
+            pos2(1.5, 1.5),
+            comparison_type.to_str().to_owned(),
+            Rotation::ROT0,
+            Align2::CENTER_CENTER,
+        )]
+    }
+
+    //
+    // *** Adder ***
+    //
+    fn get_adder_dimension_raw(cin: bool, _cout: bool) -> (i32, i32) {
+        if cin { (3, 4) } else { (3, 3) }
+    }
+
+    fn get_adder_connections_number(cin: bool, cout: bool) -> usize {
+        return 3 + if cin { 1 } else { 0 } + if cout { 1 } else { 0 };
+    }
+
+    fn get_adder_dock_cell_raw(port: PPort, cin: bool) -> GridPos {
+        let y_offs = if cin { 1 } else { 0 };
+
+        match port {
+            PPort::Input(0) => grid_pos(-1, 0 + y_offs),
+            PPort::Input(1) => grid_pos(-1, 2 + y_offs),
+            PPort::Output(0) => grid_pos(3, 1 + y_offs),
+            PPort::Cin => grid_pos(-1, 0),
+            PPort::Cout => grid_pos(3, 2 + y_offs),
+            _ => panic!("Unexpected port"),
+        }
+    }
+
+    fn get_adder_connection_position_raw(port: PPort, cin: bool) -> Pos2 {
+        let y_offs = if cin { 1.0 } else { 0.0 };
+
+        match port {
+            PPort::Input(0) => pos2(0.0, 0.5 + y_offs),
+            PPort::Input(1) => pos2(0.0, 2.5 + y_offs),
+            PPort::Output(0) => pos2(3.0, 1.5 + y_offs),
+            PPort::Cin => pos2(0.0, 0.5),
+            PPort::Cout => pos2(3.0, 2.5 + y_offs),
+            _ => panic!("Unexpected port"),
+        }
+    }
+
+    fn get_adder_polygons_points_raw(lod_level: LodLevel, cin: bool) -> Vec<Vec<Pos2>> {
+        let y_offs = if cin { 1.0 } else { 0.0 };
+
+        vec![Self::get_circle_points(
+            pos2(1.5, 1.5 + y_offs),
+            1.2,
+            lod_level,
+        )]
+    }
+
+    fn get_adder_lines_raw(cin: bool, cout: bool) -> Vec<Vec<Pos2>> {
+        let y_offs = if cin { 1.0 } else { 0.0 };
+        let mut result = vec![
+            vec![pos2(0.0, 0.5 + y_offs), pos2(0.85, 0.5 + y_offs)],
+            vec![pos2(0.0, 2.5 + y_offs), pos2(0.85, 2.5 + y_offs)],
+            vec![pos2(3.0, 1.5 + y_offs), pos2(2.7, 1.5 + y_offs)],
+        ];
+        if cin {
+            result.push(vec![pos2(0.0, 0.5), pos2(0.5, 0.5), pos2(1.3, 1.3)]);
+        }
+        if cout {
+            result.push(vec![
+                pos2(2.5, 2.0 + y_offs),
+                pos2(2.75, 2.5 + y_offs),
+                pos2(3.0, 2.5 + y_offs),
+            ]);
+        }
+        result
+    }
+
+    fn get_adder_text_labels(cin: bool, cout: bool) -> Vec<(Pos2, String, Rotation, Align2)> {
+        let y_offs = if cin { 1.0 } else { 0.0 };
+
+        let mut result = vec![(
+            pos2(1.5, 1.5 + y_offs),
+            "+".to_owned(),
+            Rotation::ROT0,
+            Align2::CENTER_CENTER,
+        )];
+        if cin {
+            result.push((
+                pos2(0.0, -0.15),
+                "cin".to_owned(),
+                Rotation::ROT0,
+                Align2::LEFT_TOP,
+            ));
+        }
+        if cout {
+            result.push((
+                pos2(3.0, 2.5 + y_offs),
+                "cout".to_owned(),
+                Rotation::ROT0,
+                Align2::RIGHT_TOP,
+            ));
+        }
+        result
+    }
+
+    //
+    // *** DFF (D-type flip-flop) ***
+    //
+    const DFF_DIMENSION: (i32, i32) = (5, 5);
+
+    fn get_dff_connections_number(params: &DFFParams) -> usize {
+        3 + if params.has_sync_reset { 1 } else { 0 }
+            + if params.has_async_reset { 1 } else { 0 }
+            + if params.has_enable { 1 } else { 0 }
+    }
+
+    fn get_dff_dock_cell_raw(port: PPort) -> GridPos {
+        match port {
+            PPort::D => grid_pos(0, 1),
+            PPort::Clk => grid_pos(0, 3),
+            PPort::Q => grid_pos(4, 2),
+            PPort::AsyncReset => grid_pos(2, 0),
+            PPort::SyncReset => grid_pos(0, 2),
+            PPort::Enable => grid_pos(0, 4),
+            _ => panic!("Unexpected port"),
+        }
+    }
+
+    fn get_dff_polygons_points_raw(params: &DFFParams, lod_level: LodLevel) -> Vec<Vec<Pos2>> {
+        let (width, height) = Self::DFF_DIMENSION;
+        let mut result = Vec::with_capacity(4);
+        result.extend([
+            vec![
+                pos2(1.05, 1.05),
+                pos2(width as f32 - 1.05, 1.05),
+                pos2(width as f32 - 1.05, height as f32 - 0.05),
+                pos2(1.05, height as f32 - 0.05),
+            ],
+            vec![pos2(1.05, 3.0), pos2(2.0, 3.5), pos2(1.05, 4.0)],
+        ]);
+        if params.has_sync_reset && params.sync_reset_inverted {
+            result.push(Self::get_circle_points(pos2(1.0, 2.5), 0.17, lod_level));
+        }
+        if params.has_async_reset && params.async_reset_inverted {
+            result.push(Self::get_circle_points(pos2(2.5, 1.0), 0.17, lod_level));
+        }
+        result
+    }
+
+    fn get_dff_connection_position_raw(port: PPort) -> Pos2 {
+        match port {
+            PPort::D => pos2(0.5, 1.5),
+            PPort::Clk => pos2(0.5, 3.5),
+            PPort::Q => pos2(4.5, 2.5),
+            PPort::AsyncReset => pos2(2.5, 0.5),
+            PPort::SyncReset => pos2(0.5, 2.5),
+            PPort::Enable => pos2(0.5, 4.5),
+            _ => panic!("Unexpected port"),
+        }
+    }
+
+    fn get_dff_text_labels(params: &DFFParams) -> Vec<(Pos2, String, Rotation, Align2)> {
+        let n_connections = Self::get_dff_connections_number(params);
+        let mut result: Vec<(Pos2, String, Rotation, Align2)> =
+            Vec::with_capacity(n_connections - 1);
+        result.extend([
+            (
+                pos2(1.25, 1.25),
+                "D".into(),
+                Rotation::ROT0,
+                Align2::LEFT_TOP,
+            ),
+            (
+                pos2(3.45, 2.25),
+                "Q".into(),
+                Rotation::ROT0,
+                Align2::LEFT_TOP,
+            ),
+        ]);
+        if params.has_enable {
+            result.push((
+                pos2(1.25, 4.25),
+                "EN".into(),
+                Rotation::ROT0,
+                Align2::LEFT_TOP,
+            ));
+        }
+        if params.has_async_reset {
+            result.push((
+                pos2(1.9, 1.1),
+                "ARST".to_string()
+                    + if params.async_reset_inverted {
+                        "_N"
+                    } else {
+                        ""
+                    },
+                Rotation::ROT0,
+                Align2::LEFT_TOP,
+            ));
+        }
+        if params.has_sync_reset {
+            result.push((
+                pos2(1.25, 2.25),
+                "RST".to_string() + if params.sync_reset_inverted { "_N" } else { "" },
+                Rotation::ROT0,
+                Align2::LEFT_TOP,
+            ));
+        }
+        result
+    }
+
+    fn get_dff_lines_raw(params: &DFFParams) -> Vec<Vec<Pos2>> {
+        let n_connections = Self::get_dff_connections_number(params);
+        let mut result = Vec::with_capacity(n_connections);
+
+        result.extend([
+            vec![pos2(0.5, 1.5), pos2(1.0, 1.5)], // D
+            vec![pos2(0.5, 3.5), pos2(1.0, 3.5)], // Clk
+            vec![pos2(4.5, 2.5), pos2(3.5, 2.5)], // Q
+        ]);
+        if params.has_enable {
+            result.push(vec![pos2(0.5, 4.5), pos2(1.0, 4.5)]);
+        }
+        if params.has_sync_reset {
+            result.push(vec![pos2(0.5, 2.5), pos2(1.0, 2.5)]);
+        }
+        if params.has_async_reset {
+            result.push(vec![pos2(2.5, 0.5), pos2(2.5, 1.0)]);
+        }
+        result
+    }
+
+    //
+    // *** Common ***
+    //
+
+    fn get_port_type(&self, id: Id) -> Option<PPort> {
+        PPort::from_id(self, id)
+    }
+
+    pub fn get_connections_number(&self) -> usize {
+        match self {
+            Self::And(n_inputs) => *n_inputs + 1,
+            Self::Or(n_inputs) => *n_inputs + 1,
+            Self::Xor(n_inputs) => *n_inputs + 1,
+            Self::Nand(n_inputs) => *n_inputs + 1,
+            Self::Not => 2,
+            Self::TriState => 3,
+            Self::Mux(n_inputs) => *n_inputs + 2,
+            Self::Comparator(_) => Self::CMP_N_CONNECTIONS,
+            Self::Adder { cin, cout } => Self::get_adder_connections_number(*cin, *cout),
+            Self::DFF(params) => Self::get_dff_connections_number(params),
+            Self::Input => 1,
+            Self::Output => 1,
+            Self::Clock => 1,
+            Self::Connector => 1,
+            Self::Button | Self::Switch => 1,
+            Self::DipSwitch(n) => *n,
+            Self::HexKeypad => Self::KEYPAD_OUTPUTS,
+            Self::Pattern { .. } | Self::Random { .. } => self.get_stimulus_width() + 1,
+            Self::Assertion { n_inputs, .. } => *n_inputs,
+            Self::Point => 1,
+        }
+    }
+
+    fn get_dimension_raw(&self) -> (i32, i32) {
+        match self {
+            Self::And(n_inputs) => Self::get_and_gate_dimension_raw(*n_inputs),
+            Self::Or(n_inputs) => Self::get_or_gate_dimension_raw(*n_inputs),
+            Self::Xor(n_inputs) => Self::get_xor_gate_dimension_raw(*n_inputs),
+            Self::Nand(n_inputs) => Self::get_nand_gate_dimension_raw(*n_inputs),
+            Self::Not | Self::TriState => (3, 3),
+            Self::Mux(n_inputs) => Self::get_mux_dimension_raw(*n_inputs),
+            Self::Comparator(_) => Self::CMP_DIMENSION,
+            Self::Adder { cin, cout } => Self::get_adder_dimension_raw(*cin, *cout),
+            Self::DFF(_) => Self::DFF_DIMENSION,
+            Self::Input => (2, 1),
+            Self::Output => (2, 1),
+            Self::Clock => (2, 1),
+            Self::Connector => (2, 1),
+            Self::Button | Self::Switch => (2, 1),
+            Self::DipSwitch(n) => (2, *n as i32),
+            Self::HexKeypad => Self::KEYPAD_DIMENSION,
+            Self::Pattern { .. } | Self::Random { .. } => self.get_stimulus_dimension_raw(),
+            Self::Assertion { n_inputs, .. } => Self::get_assertion_dimension_raw(*n_inputs),
+            Self::Point => (1, 1),
+        }
+    }
+
+    fn get_dock_cell_raw(&self, connection_id: Id) -> GridPos {
+        let port = self.get_port_type(connection_id).unwrap(); // Check that port is exist
+        match self {
+            Self::And(n_inputs) => Self::get_and_gate_dock_cell_raw(port, *n_inputs),
+            Self::Or(n_inputs) => Self::get_or_gate_dock_cell_raw(port, *n_inputs),
+            Self::Xor(n_inputs) => Self::get_xor_gate_dock_cell_raw(port, *n_inputs),
+            Self::Nand(n_inputs) => Self::get_nand_gate_dock_cell_raw(port, *n_inputs),
+            Self::Not => Self::get_not_dock_cell_raw(port),
+            Self::TriState => Self::get_tristate_dock_cell_raw(port),
+            Self::Mux(n_inputs) => Self::get_mux_dock_cell_raw(port, *n_inputs),
+            Self::Comparator(_) => Self::get_cmp_dock_cell_raw(port),
+            Self::Adder { cin, cout: _ } => Self::get_adder_dock_cell_raw(port, *cin),
+            Self::DFF(_) => Self::get_dff_dock_cell_raw(port),
+            Self::Input | Self::Clock => Self::get_input_dock_cell_raw(),
+            Self::Button | Self::Switch | Self::DipSwitch(_) | Self::HexKeypad => {
+                Self::get_source_dock_cell_raw(self.get_dimension_raw(), port)
+            }
+            Self::Pattern { .. } | Self::Random { .. } => self.get_stimulus_dock_cell_raw(port),
+            Self::Assertion { .. } => Self::get_assertion_dock_cell_raw(port),
+            Self::Output | Self::Connector => Self::get_output_dock_cell_raw(),
+            Self::Point => grid_pos(0, 0),
+        }
+    }
+
+    fn get_connection_position_raw(&self, connection_id: Id) -> Pos2 {
+        let port = self.get_port_type(connection_id).unwrap(); // Check that port is exist
+        match self {
+            Self::And(n_inputs) => Self::get_and_gate_connection_position_raw(port, *n_inputs),
+            Self::Or(n_inputs) => Self::get_or_gate_connection_position_raw(port, *n_inputs),
+            Self::Xor(n_inputs) => Self::get_xor_gate_connection_position_raw(port, *n_inputs),
+            Self::Nand(n_inputs) => Self::get_nand_gate_connection_position_raw(port, *n_inputs),
+            Self::Not => Self::get_not_connection_position_raw(port),
+            Self::TriState => Self::get_tristate_connection_position_raw(port),
+            Self::Mux(n_inputs) => Self::get_mux_connection_position_raw(port, *n_inputs),
+            Self::Comparator(_) => Self::get_cmp_connection_position_raw(port),
+            Self::Adder { cin, cout: _ } => Self::get_adder_connection_position_raw(port, *cin),
+            Self::DFF(_) => Self::get_dff_connection_position_raw(port),
+            Self::Input | Self::Clock => Self::get_input_connection_position_raw(port),
+            Self::Button | Self::Switch | Self::DipSwitch(_) | Self::HexKeypad => {
+                Self::get_source_connection_position_raw(self.get_dimension_raw(), port)
+            }
+            Self::Pattern { .. } | Self::Random { .. } => {
+                self.get_stimulus_connection_position_raw(port)
+            }
+            Self::Assertion { .. } => Self::get_assertion_connection_position_raw(port),
+            Self::Output | Self::Connector => Self::get_output_connection_position_raw(port),
+            Self::Point => pos2(0.5, 0.5),
+        }
+    }
+
+    /// Geometry of the symbol without rotation, in grid units
+    pub fn get_geometry(&self, lod_level: LodLevel) -> SymbolGeometry {
+        SymbolGeometry {
+            polygons: self.get_polygons_points_raw(lod_level),
+            lines: self.get_lines(lod_level),
+            ports: (0..self.get_connections_number())
+                .map(|i| self.get_connection_position_raw(i))
+                .collect(),
+            labels: self
+                .get_text_labels()
+                .into_iter()
+                .map(|(pos, text, rotation, align)| SymbolLabel {
+                    pos,
+                    text,
+                    rotation,
+                    align,
+                })
+                .collect(),
+        }
+    }
+
+    fn get_polygons_points_raw(&self, lod_level: LodLevel) -> Vec<Vec<Pos2>> {
+        match self {
+            Self::And(n) | Self::Or(n) | Self::Xor(n) | Self::Nand(n)
+                if Self::is_compact_gate(*n) =>
+            {
+                self.get_compact_gate_polygons_points_raw(lod_level)
+            }
+            Self::And(n_inputs) => {
+                vec![Self::get_and_gate_polygon_points_raw(*n_inputs, lod_level)]
+            }
+            Self::Or(n_inputs) => vec![Self::get_or_gate_polygon_points_raw(*n_inputs, lod_level)],
+            Self::Xor(n_inputs) => {
+                vec![Self::get_xor_gate_polygon_points_raw(*n_inputs, lod_level)]
+            }
+            Self::Nand(n_inputs) => Self::get_nand_gate_polygons_points_raw(*n_inputs, lod_level),
+            Self::Input | Self::Clock => vec![Self::get_input_polygon_points_raw()],
+            Self::Button | Self::Switch | Self::DipSwitch(_) | Self::HexKeypad => {
+                vec![Self::get_source_polygon_points_raw(
+                    self.get_dimension_raw(),
+                )]
+            }
+            Self::Pattern { .. } | Self::Random { .. } => self.get_stimulus_polygons_points_raw(),
+            Self::Assertion { n_inputs, .. } => {
+                vec![Self::get_assertion_polygon_points_raw(*n_inputs)]
+            }
+            Self::Output => vec![Self::get_output_polygon_points_raw()],
+            Self::Connector => vec![Self::get_connector_polygon_points_raw()],
+            Self::Not => Self::get_not_polygons_points_raw(lod_level),
+            Self::TriState => Self::get_tristate_polygons_points_raw(lod_level),
+            Self::Comparator(_) => Self::get_cmp_polygons_points_raw(lod_level),
+            Self::Adder { cin, cout: _ } => Self::get_adder_polygons_points_raw(lod_level, *cin),
+            Self::Mux(n_inputs) => vec![Self::get_mux_polygon_points_raw(*n_inputs)],
+            Self::DFF(params) => Self::get_dff_polygons_points_raw(params, lod_level),
+            Self::Point => vec![],
+        }
+    }
+
+    fn get_lines(&self, lod_level: LodLevel) -> Vec<Vec<Pos2>> {
+        match self {
+            Self::And(n) | Self::Or(n) | Self::Xor(n) | Self::Nand(n)
+                if Self::is_compact_gate(*n) =>
+            {
+                self.get_compact_gate_lines_raw(lod_level)
+            }
+            Self::Or(n_inputs) => Self::get_or_gate_lines_raw(*n_inputs),
+            Self::Xor(n_inputs) => Self::get_xor_gate_lines_raw(*n_inputs, lod_level),
+            Self::Nand(n_inputs) => Self::get_nand_gate_lines_raw(*n_inputs),
+            Self::Output => Self::get_output_lines_raw(),
+            Self::Clock => Self::get_clock_lines_raw(),
+            Self::Button | Self::Switch | Self::DipSwitch(_) | Self::HexKeypad => {
+                Self::get_source_lines_raw(self)
+            }
+            Self::Pattern { .. } | Self::Random { .. } => self.get_stimulus_lines_raw(),
+            Self::Assertion { n_inputs, .. } => Self::get_assertion_lines_raw(*n_inputs),
+            Self::Not => Self::get_not_lines_raw(),
+            Self::TriState => Self::get_tristate_lines_raw(),
+            Self::DFF(params) => Self::get_dff_lines_raw(params),
+            Self::Comparator(_) => Self::get_cmp_lines_raw(),
+            Self::Adder { cin, cout } => Self::get_adder_lines_raw(*cin, *cout),
+            _ => vec![],
+        }
+    }
+
+    fn get_text_labels(&self) -> Vec<(Pos2, String, Rotation, Align2)> {
+        match self {
+            Self::And(n) | Self::Or(n) | Self::Xor(n) | Self::Nand(n)
+                if Self::is_compact_gate(*n) =>
+            {
+                Self::get_compact_gate_text_labels(*n)
+            }
+            Self::DFF(params) => Self::get_dff_text_labels(params),
+            Self::Comparator(typ) => Self::get_cmp_text_labels(typ),
+            Self::Adder { cin, cout } => Self::get_adder_text_labels(*cin, *cout),
+            Self::HexKeypad => Self::get_keypad_text_labels(),
+            Self::Pattern { .. } | Self::Random { .. } => self.get_stimulus_text_labels(),
+            Self::Assertion {
+                condition,
+                n_inputs,
+            } => Self::get_assertion_text_labels(*condition, *n_inputs),
+            _ => vec![],
+        }
+    }
+
+    pub fn is_customizable(&self) -> bool {
+        match self {
+            Self::And(_)
+            | Self::Or(_)
+            | Self::Xor(_)
+            | Self::Nand(_)
+            | Self::Mux(_)
+            | Self::DFF(_)
+            | Self::Adder { cin: _, cout: _ }
+            | Self::Comparator(_)
+            | Self::DipSwitch(_)
+            | Self::Pattern { .. }
+            | Self::Random { .. }
+            | Self::Assertion { .. }
+            | Self::Connector => true,
+            Self::Not
+            | Self::TriState
+            | Self::Input
+            | Self::Output
+            | Self::Clock
+            | Self::Button
+            | Self::Switch
+            | Self::HexKeypad
+            | Self::Point => false,
+        }
+    }
+
+    /// Type with `delta` more inputs, None if the number can't be changed or is out of range,
+    /// gates and muxes get at most `max_gate_inputs`
+    pub fn with_inputs_delta(&self, delta: isize, max_gate_inputs: usize) -> Option<Self> {
+        let change = |n: usize, min: usize, max: usize| {
+            Some(n.checked_add_signed(delta)?).filter(|n| (min..=max).contains(n))
+        };
+        Some(match *self {
+            Self::And(n) => Self::And(change(n, 2, max_gate_inputs)?),
+            Self::Or(n) => Self::Or(change(n, 2, max_gate_inputs)?),
+            Self::Xor(n) => Self::Xor(change(n, 2, max_gate_inputs)?),
+            Self::Nand(n) => Self::Nand(change(n, 2, max_gate_inputs)?),
+            Self::Mux(n) => Self::Mux(change(n, 2, max_gate_inputs)?),
+            Self::DipSwitch(n) => Self::DipSwitch(change(n, 1, Self::MAX_DIP_SWITCHES)?),
+            Self::Assertion {
+                condition,
+                n_inputs,
+            } => Self::Assertion {
+                condition,
+                n_inputs: change(n_inputs, 1, Self::MAX_ASSERTION_INPUTS)?,
+            },
+            _ => return None,
+        })
+    }
+
+    /// Type with the next operation, like `<` after `==` of comparators
+    pub fn with_next_operation(&self) -> Option<Self> {
+        fn next<T: Copy + PartialEq>(types: &[T], current: T) -> T {
+            let i = types.iter().position(|t| *t == current).unwrap_or(0);
+            types[(i + 1) % types.len()]
+        }
+        match *self {
+            Self::Comparator(typ) => Some(Self::Comparator(next(ComparisonType::TYPES, typ))),
+            Self::Assertion {
+                condition,
+                n_inputs,
+            } => Some(Self::Assertion {
+                condition: next(AssertionType::TYPES, condition),
+                n_inputs,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns true if connections can be swapped without changing the logic function
+    /// (e.g. inputs of AND/OR/XOR gates)
+    pub fn is_swappable_pair(&self, a: Id, b: Id) -> bool {
+        match self {
+            Self::And(n_inputs)
+            | Self::Or(n_inputs)
+            | Self::Xor(n_inputs)
+            | Self::Nand(n_inputs) => (1..=*n_inputs).contains(&a) && (1..=*n_inputs).contains(&b),
+            Self::Adder { cin: _, cout: _ } => a <= 1 && b <= 1,
+            _ => false,
+        }
+    }
+
+    pub fn has_swappable_pins(&self) -> bool {
+        self.is_swappable_pair(0, 1) || self.is_swappable_pair(1, 2)
+    }
+
+    /// Returns a list of connection permutations.
+    pub fn get_connections_diff(&self, other: &Self) -> HashMap<Id, Option<Id>> {
+        let mut self_port_map = HashMap::new();
+        let self_n_ports = self.get_connections_number();
+        for id in 0..self_n_ports {
+            self_port_map.insert(PPort::from_id(self, id).unwrap(), id);
+        }
+
+        let mut other_port_map = HashMap::new();
+        let other_n_ports = other.get_connections_number();
+        for id in 0..other_n_ports {
+            other_port_map.insert(PPort::from_id(other, id).unwrap(), id);
+        }
+
+        let mut result = HashMap::new();
+        for (port, id) in &self_port_map {
+            let other_id = other_port_map.get(port).cloned();
+            if other_id != Some(*id) {
+                result.insert(*id, other_id);
+            }
+        }
+        return result;
+    }
+
+    /// Short human readable type name, like in the component library
+    pub fn get_type_name(&self) -> String {
+        match self {
+            Self::And(n) => format!("AND{}", n),
+            Self::Or(n) => format!("OR{}", n),
+            Self::Xor(n) => format!("XOR{}", n),
+            Self::Nand(n) => format!("NAND{}", n),
+            Self::Not => "NOT".to_owned(),
+            Self::TriState => "TRIBUF".to_owned(),
+            Self::Point => "POINT".to_owned(),
+            Self::Mux(n) => format!("MUX{}", n),
+            Self::Input => "INPUT".to_owned(),
+            Self::Output => "OUTPUT".to_owned(),
+            Self::Clock => "CLOCK".to_owned(),
+            Self::Connector => "CONNECTOR".to_owned(),
+            Self::Button => "BUTTON".to_owned(),
+            Self::Switch => "SWITCH".to_owned(),
+            Self::DipSwitch(n) => format!("DIP{}", n),
+            Self::HexKeypad => "KEYPAD".to_owned(),
+            Self::Pattern { .. } => "PATTERN".to_owned(),
+            Self::Random { width, .. } => format!("RANDOM{}", width),
+            Self::Assertion { .. } => "ASSERT".to_owned(),
+            Self::Comparator(_) => "Comparator".to_owned(),
+            Self::Adder { cin: _, cout: _ } => "Adder".to_owned(),
+            Self::DFF(_) => "DFF".to_owned(),
+        }
+    }
+
+    /// Returns true for inputs and false for outputs
+    pub fn is_input_connection(&self, connection_id: Id) -> Option<bool> {
+        match PPort::from_id(self, connection_id)? {
+            PPort::Output(_) | PPort::Cout | PPort::Q => Some(false),
+            _ => Some(true),
+        }
+    }
+
+    pub fn get_connection_name(&self, connection_id: Id) -> Option<String> {
+        Some(PPort::from_id(self, connection_id)?.name())
+    }
+
+    /// Evaluates the logic function for simulation.
+    /// `pins` are values of the connections, returns values of the output connections.
+    pub fn evaluate(&self, pins: &[Logic], state: &mut PrimitiveState) -> Vec<(Id, Logic)> {
+        let n_connections = self.get_connections_number();
+        let port_id =
+            |port: PPort| (0..n_connections).find(|id| PPort::from_id(self, *id) == Some(port));
+        // Absent optional ports are low, unconnected inputs are floating:
+        let get = |port: PPort| {
+            port_id(port).map_or(Logic::Low, |id| pins.get(id).copied().unwrap_or(Logic::Z))
+        };
+        let out = |port: PPort, value: Logic| port_id(port).map(|id| (id, value));
+        let inputs = |n: usize| (0..n).map(move |i| get(PPort::Input(i)));
+        let rising_edge = |state: &mut PrimitiveState| {
+            let clk = get(PPort::Clk) == Logic::High;
+            let edge = clk && !state.last_clk;
+            state.last_clk = clk;
+            edge
+        };
+        let result = match self {
+            Self::And(n) => vec![out(
+                PPort::Output(0),
+                inputs(*n).fold(Logic::High, |acc, v| acc & v),
+            )],
+            Self::Or(n) => vec![out(
+                PPort::Output(0),
+                inputs(*n).fold(Logic::Low, |acc, v| acc | v),
+            )],
+            Self::Xor(n) => vec![out(
+                PPort::Output(0),
+                inputs(*n).fold(Logic::Low, |acc, v| acc ^ v),
+            )],
+            Self::Nand(n) => vec![out(
+                PPort::Output(0),
+                !inputs(*n).fold(Logic::High, |acc, v| acc & v),
+            )],
+            Self::Not => vec![out(PPort::Output(0), !get(PPort::Input(0)))],
+            Self::TriState => {
+                let value = match get(PPort::Enable).read() {
+                    Logic::High => get(PPort::Input(0)).read(),
+                    Logic::Low => Logic::Z,
+                    _ => Logic::X,
+                };
+                vec![out(PPort::Output(0), value)]
+            }
+            Self::Mux(n) => {
+                let first = get(PPort::Input(0)).read();
+                let second = get(PPort::Input(1.min(n - 1))).read();
+                let value = match get(PPort::Select).read() {
+                    Logic::Low => first,
+                    Logic::High => second,
+                    // Unknown selection is fine if the inputs are the same:
+                    _ if first == second => first,
+                    _ => Logic::X,
+                };
+                vec![out(PPort::Output(0), value)]
+            }
+            Self::Comparator(typ) => {
+                let value = match (
+                    get(PPort::Input(0)).to_bool(),
+                    get(PPort::Input(1)).to_bool(),
+                ) {
+                    (Some(a), Some(b)) => match typ {
+                        ComparisonType::EQ => a == b,
+                        ComparisonType::LT => !a && b,
+                        ComparisonType::LTE => !a || b,
+                        ComparisonType::GT => a && !b,
+                        ComparisonType::GTE => a || !b,
+                    }
+                    .into(),
+                    _ => Logic::X,
+                };
+                vec![out(PPort::Output(0), value)]
+            }
+            Self::Adder { cin: _, cout: _ } => {
+                let (a, b, c) = (get(PPort::Input(0)), get(PPort::Input(1)), get(PPort::Cin));
+                vec![
+                    out(PPort::Output(0), a ^ b ^ c),
+                    out(PPort::Cout, (a & b) | (c & (a ^ b))),
+                ]
+            }
+            Self::DFF(params) => {
+                // Value of the reset input: Some(true) if active, None if unknown
+                let reset_active = |port: PPort, inverted: bool| {
+                    get(port).to_bool().map(|value| value != inverted)
+                };
+                if rising_edge(state) {
+                    let loaded = if !params.has_enable {
+                        get(PPort::D).read()
+                    } else {
+                        match get(PPort::Enable).read() {
+                            Logic::High => get(PPort::D).read(),
+                            Logic::Low => state.get(),
+                            _ if get(PPort::D).read() == state.get() => state.get(),
+                            _ => Logic::X,
+                        }
+                    };
+                    let next = if params.has_sync_reset {
+                        match reset_active(PPort::SyncReset, params.sync_reset_inverted) {
+                            Some(true) => Logic::Low,
+                            Some(false) => loaded,
+                            None => Logic::X,
+                        }
+                    } else {
+                        loaded
+                    };
+                    state.set(next);
+                }
+                if params.has_async_reset {
+                    match reset_active(PPort::AsyncReset, params.async_reset_inverted) {
+                        Some(true) => state.set(Logic::Low),
+                        Some(false) => {}
+                        None => state.set(Logic::X),
+                    }
+                }
+                vec![out(PPort::Q, state.get())]
+            }
+            Self::Input | Self::Clock | Self::Button | Self::Switch => {
+                vec![out(PPort::Output(0), state.value.into())]
+            }
+            Self::DipSwitch(n) => (0..*n)
+                .map(|i| out(PPort::Output(i), (state.bits >> i & 1 == 1).into()))
+                .collect(),
+            Self::HexKeypad => (0..Self::KEYPAD_OUTPUTS - 1)
+                .map(|i| out(PPort::Output(i), (state.bits >> i & 1 == 1).into()))
+                .chain([out(
+                    PPort::Output(Self::KEYPAD_OUTPUTS - 1),
+                    state.value.into(),
+                )])
+                .collect(),
+            Self::Pattern { pattern, length } => {
+                // `bits` is the position in the pattern, a stale state may be out of it:
+                let length = (*length).max(1) as u64;
+                if rising_edge(state) {
+                    state.bits = (state.bits % length + 1) % length;
+                }
+                let bit = pattern.checked_shr((state.bits % length) as u32).unwrap_or(0);
+                vec![out(PPort::Output(0), (bit & 1 == 1).into())]
+            }
+            Self::Random { width, seed } => {
+                // `bits` is the generator state:
+                if rising_edge(state) || state.bits == 0 {
+                    state.bits = Self::random_next(state.bits, *seed);
+                }
+                (0..*width)
+                    .map(|i| out(PPort::Output(i), (state.bits >> (63 - i) & 1 == 1).into()))
+                    .collect()
+            }
+            Self::Assertion {
+                condition,
+                n_inputs,
+            } => {
+                // `value` is true while the condition is violated, unknown inputs violate it:
+                let values: Option<Vec<bool>> = inputs(*n_inputs).map(Logic::to_bool).collect();
+                state.value = !values.is_some_and(|values| condition.check(&values));
+                vec![]
+            }
+            Self::Output | Self::Point | Self::Connector => vec![],
+        };
+        result.into_iter().flatten().collect()
+    }
+
+}
+
+type GeometryCache = LruCache<(PrimitiveType, Rotation, LodLevel), Arc<SymbolGeometry>>;
+type MeshCache = LruCache<(PrimitiveType, Rotation, LodLevel, Theme), Vec<Arc<Mesh>>>;
+
+/// Default number of cached symbol meshes, one per type, rotation, LOD level and theme
+pub const DEFAULT_MESH_CACHE_BUDGET: usize = 1024;
+/// Number of cached symbol geometries, one per type, rotation and LOD level
+const GEOMETRY_CACHE_BUDGET: usize = 1024;
+
+thread_local! {
+    static CACHE: LazyCell<RefCell<MeshCache>> =
+        LazyCell::new(|| RefCell::new(LruCache::new(DEFAULT_MESH_CACHE_BUDGET)));
+    static GEOMETRY_CACHE: LazyCell<RefCell<GeometryCache>> =
+        LazyCell::new(|| RefCell::new(LruCache::new(GEOMETRY_CACHE_BUDGET)));
+}
+
+/// Geometry of the symbol, gates of the same type and parameters share it.
+/// Points of the unrotated symbol are computed once and rotated for the other rotations.
+fn get_cached_geometry(
+    typ: PrimitiveType,
+    rotation: Rotation,
+    lod_level: LodLevel,
+) -> Arc<SymbolGeometry> {
+    let key = (typ, rotation, lod_level);
+    if let Some(geometry) = GEOMETRY_CACHE.with(|cell| cell.borrow_mut().get(&key)) {
+        return geometry;
+    }
+    let geometry = match rotation {
+        Rotation::ROT0 => Arc::new(typ.get_geometry(lod_level)),
+        _ => {
+            let unrotated = get_cached_geometry(typ, Rotation::ROT0, lod_level);
+            Arc::new(SymbolGeometry::clone(&unrotated).rotated(rotation, typ.get_dimension_raw()))
+        }
+    };
+    GEOMETRY_CACHE.with(|cell| cell.borrow_mut().insert(key, geometry.clone()));
+    geometry
+}
+
+fn get_cached_meshes(
+    typ: PrimitiveType,
+    rotation: Rotation,
+    lod_level: LodLevel,
+    theme: Theme,
+) -> Vec<Arc<Mesh>> {
+    CACHE.with(|cell| {
+        cell.borrow_mut().get_or_insert_with((typ, rotation, lod_level, theme), || {
+            let geometry = get_cached_geometry(typ, rotation, lod_level);
+            let mut result = Vec::with_capacity(geometry.polygons.len());
+            for points in &geometry.polygons {
+                let mesh = tesselate_polygon(
+                    points,
+                    theme.get_fill_color(),
+                    lod_level != LodLevel::Min || theme == Theme::Light, // Do not optimize stroke on light theme
+                    theme.get_stroke_color(),
+                    STROKE_SCALE,
+                );
+                result.push(Arc::new(mesh));
+            }
+            result
+        })
+    })
+}
+
+/// Changes the max number of cached symbol meshes
+pub fn set_mesh_cache_budget(budget: usize) {
+    CACHE.with(|cell| cell.borrow_mut().set_budget(budget));
+}
+
+pub fn get_mesh_cache_stats() -> CacheStats {
+    CACHE.with(|cell| cell.borrow().get_stats())
+}
+
+pub fn get_geometry_cache_stats() -> CacheStats {
+    GEOMETRY_CACHE.with(|cell| cell.borrow().get_stats())
+}
+
+static MAX_GATE_INPUTS: AtomicUsize = AtomicUsize::new(PrimitiveType::DEFAULT_MAX_GATE_INPUTS);
+
+/// Changes the max inputs of gates and muxes, stored in the settings
+pub fn set_max_gate_inputs(n: usize) {
+    MAX_GATE_INPUTS.store(PrimitiveType::clamp_max_gate_inputs(n), Ordering::Relaxed);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::{
+        AssertionType, ComparisonType, LodLevel, PrimitiveComponent, PrimitiveType, Rotation,
+        get_cached_geometry, get_geometry_cache_stats,
+    };
+    use crate::grid_db::grid_pos;
+
+    #[test]
+    fn compact_gates() {
+        // Inputs are stacked on every row, the height is odd for the output to be at the middle:
+        assert_eq!(PrimitiveType::And(16).get_dimension_raw(), (3, 31));
+        assert_eq!(PrimitiveType::Nand(17).get_dimension_raw(), (3, 17));
+        assert_eq!(PrimitiveType::Xor(18).get_dimension_raw(), (3, 19));
+        let gate = PrimitiveComponent {
+            typ: PrimitiveType::Or(40),
+            pos: grid_pos(10, 0),
+            rotation: Rotation::ROT90,
+            name: "".into(),
+            source: None,
+        };
+        let cells: Vec<_> = (0..=40).map(|i| gate.get_connection_dock_cell(i).unwrap()).collect();
+        assert_eq!(gate.get_dimension(), (41, 3));
+        // Output and the inputs:
+        assert_eq!(cells[0], grid_pos(30, 3));
+        assert_eq!(cells[1], grid_pos(50, -1));
+        assert_eq!(cells[40], grid_pos(11, -1));
+
+        let geometry = PrimitiveType::Or(40).get_geometry(LodLevel::Max);
+        assert_eq!(geometry.ports.len(), 41);
+        let texts: Vec<_> = geometry.labels.iter().map(|l| l.text.as_str()).collect();
+        assert_eq!(texts, ["0", "1", "2", "37", "38", "39", "…", "…"]);
+        // The body is drawn like the small gate:
+        let body = PrimitiveType::Or(5).get_geometry(LodLevel::Max);
+        assert_eq!(geometry.polygons.len(), body.polygons.len() + 1);
+        assert!(geometry.polygons.iter().flatten().all(|p| p.y >= 0.0 && p.y <= 41.0));
+    }
+
+    #[test]
+    fn quick_parameter_changes() {
+        let max = PrimitiveType::DEFAULT_MAX_GATE_INPUTS;
+        assert!(PrimitiveType::And(2).with_inputs_delta(1, max) == Some(PrimitiveType::And(3)));
+        assert!(PrimitiveType::Mux(2).with_inputs_delta(-1, max).is_none());
+        assert!(PrimitiveType::Or(max).with_inputs_delta(1, max).is_none());
+        assert!(PrimitiveType::Not.with_inputs_delta(1, max).is_none());
+        // The limit is configurable:
+        let raised = PrimitiveType::Or(max).with_inputs_delta(1, 200);
+        assert!(raised == Some(PrimitiveType::Or(max + 1)));
+        let limit = PrimitiveType::clamp_max_gate_inputs(usize::MAX);
+        assert_eq!(limit, PrimitiveType::MAX_GATE_INPUTS_LIMIT);
+        assert_eq!(PrimitiveType::clamp_max_gate_inputs(0), 2);
+
+        let eq = PrimitiveType::Comparator(ComparisonType::EQ);
+        let lt = PrimitiveType::Comparator(ComparisonType::LT);
+        assert!(eq.with_next_operation() == Some(lt));
+        let mut typ = eq;
+        for _ in 0..ComparisonType::TYPES.len() {
+            typ = typ.with_next_operation().unwrap();
+        }
+        // The operations are cycled:
+        assert!(typ == eq);
+        let assertion = PrimitiveType::Assertion {
+            condition: AssertionType::AllLow,
+            n_inputs: 3,
+        };
+        let next = PrimitiveType::Assertion {
+            condition: AssertionType::NotAll,
+            n_inputs: 3,
+        };
+        assert!(assertion.with_next_operation() == Some(next));
+        assert!(PrimitiveType::And(2).with_next_operation().is_none());
+    }
+
+    #[test]
+    fn shared_geometry() {
+        let typ = PrimitiveType::Mux(63);
+        let rotated = get_cached_geometry(typ, Rotation::ROT90, LodLevel::Max);
+        let unrotated = get_cached_geometry(typ, Rotation::ROT0, LodLevel::Max);
+        let stats = get_geometry_cache_stats();
+        // The unrotated geometry is computed for the rotated one and reused:
+        assert_eq!((stats.misses, stats.hits), (2, 1));
+        assert!(Arc::ptr_eq(&unrotated, &get_cached_geometry(typ, Rotation::ROT0, LodLevel::Max)));
+        get_cached_geometry(typ, Rotation::ROT180, LodLevel::Max);
+        assert_eq!(get_geometry_cache_stats().misses, 3);
+        assert_eq!(rotated.polygons.len(), unrotated.polygons.len());
+        assert_ne!(rotated.polygons, unrotated.polygons);
+    }
+}
@@ -0,0 +1,126 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    ops::Deref,
+    sync::{Arc, Mutex},
+};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+static POOL: Mutex<Option<StringPool>> = Mutex::new(None);
+
+struct StringPool {
+    strings: HashSet<Arc<str>>,
+    // Size after the last removal of unused strings
+    last_size: usize,
+}
+
+impl StringPool {
+    const MIN_CLEANUP_SIZE: usize = 1024;
+
+    fn intern(&mut self, s: &str) -> Arc<str> {
+        if let Some(shared) = self.strings.get(s) {
+            return shared.clone();
+        }
+        if self.strings.len() >= (2 * self.last_size).max(Self::MIN_CLEANUP_SIZE) {
+            // Only the pool refers to the string:
+            self.strings.retain(|s| Arc::strong_count(s) > 1);
+            self.last_size = self.strings.len();
+        }
+        let shared: Arc<str> = Arc::from(s);
+        self.strings.insert(shared.clone());
+        shared
+    }
+}
+
+/// Immutable reference-counted string used for port names and labels.
+/// Equal strings share one allocation, so big imported designs are cheap to store and clone.
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SharedStr(Arc<str>);
+
+impl SharedStr {
+    pub fn new(s: &str) -> Self {
+        let mut pool = POOL.lock().unwrap_or_else(|e| e.into_inner());
+        let pool = pool.get_or_insert_with(|| StringPool {
+            strings: HashSet::new(),
+            last_size: 0,
+        });
+        Self(pool.intern(s))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for SharedStr {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl Deref for SharedStr {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for SharedStr {
+    fn from(s: &str) -> Self {
+        Self::new(s)
+    }
+}
+
+impl From<String> for SharedStr {
+    fn from(s: String) -> Self {
+        Self::new(&s)
+    }
+}
+
+impl From<&String> for SharedStr {
+    fn from(s: &String) -> Self {
+        Self::new(s)
+    }
+}
+
+impl fmt::Display for SharedStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl fmt::Debug for SharedStr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl Serialize for SharedStr {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for SharedStr {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self::new(&String::deserialize(deserializer)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::SharedStr;
+
+    #[test]
+    fn equal_strings_are_shared() {
+        let a = SharedStr::from("data_in");
+        let b: SharedStr = serde_json::from_str("\"data_in\"").unwrap();
+        assert!(Arc::ptr_eq(&a.0, &b.0));
+        assert_eq!(serde_json::to_string(&b).unwrap(), "\"data_in\"");
+        assert_ne!(a, SharedStr::from("data_out"));
+    }
+}
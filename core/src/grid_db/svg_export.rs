@@ -0,0 +1,363 @@
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
+
+use epaint::{emath::Align2, pos2};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    field_state::GridType,
+    grid_db::{
+        Component, ComponentColor, GridDB, GridPos, Id, Logic, NetColorMode, NetStyle,
+        PrimitiveType, Rotation, STROKE_SCALE, SvgColor, Theme, grid_pos, svg_single_line_text,
+    },
+    parallel,
+};
+
+/// Escapes XML special characters of the written text
+pub struct XmlEscapeWriter<W: Write>(pub W);
+
+impl<W: Write> Write for XmlEscapeWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut start = 0;
+        for (i, byte) in buf.iter().enumerate() {
+            let escaped: &[u8] = match byte {
+                b'&' => b"&amp;",
+                b'<' => b"&lt;",
+                b'>' => b"&gt;",
+                _ => continue,
+            };
+            self.0.write_all(&buf[start..i])?;
+            self.0.write_all(escaped)?;
+            start = i + 1;
+        }
+        self.0.write_all(&buf[start..])?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// Width and height of the SVG document, the drawing is scaled to fit them
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct DocumentSize {
+    pub width: f32,
+    pub height: f32,
+    /// Physical size, pixels otherwise
+    pub millimeters: bool,
+}
+
+impl DocumentSize {
+    fn to_svg_attributes(self) -> String {
+        let unit = if self.millimeters { "mm" } else { "" };
+        format!(
+            " width=\"{}{unit}\" height=\"{}{unit}\"",
+            self.width, self.height
+        )
+    }
+}
+
+/// Kinds of components, which can be left out of the published figures
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ExportLayer {
+    /// Text fields
+    Notes,
+    /// Assertions checked by the simulation
+    Assertions,
+}
+
+impl ExportLayer {
+    pub const ALL: [Self; 2] = [Self::Notes, Self::Assertions];
+
+    pub fn contains(self, comp: &Component) -> bool {
+        match (self, comp) {
+            (Self::Notes, Component::TextField(_)) => true,
+            (Self::Assertions, Component::Primitive(p)) => {
+                matches!(p.typ, PrimitiveType::Assertion { .. })
+            }
+            _ => false,
+        }
+    }
+
+    /// Components of `ids` which are not in the excluded layers
+    pub fn filter(db: &GridDB, ids: Vec<Id>, excluded: &[Self]) -> Vec<Id> {
+        ids.into_iter()
+            .filter(|id| {
+                db.get_component(id)
+                    .is_some_and(|comp| !excluded.iter().any(|layer| layer.contains(comp)))
+            })
+            .collect()
+    }
+}
+
+/// Grid drawn under the exported schematic, like on the screen
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub struct ExportGrid {
+    pub grid_type: GridType,
+    pub opacity: f32,
+}
+
+impl ExportGrid {
+    /// Opacity of the grid on the screen
+    pub const DEFAULT_OPACITY: f32 = 0.1;
+    pub const NONE: Self = Self {
+        grid_type: GridType::None,
+        opacity: Self::DEFAULT_OPACITY,
+    };
+    /// Width of the lines and radius of the dots in cells
+    const STROKE_SCALE: f32 = 0.04;
+
+    /// Pattern of the grid filling the document, cell corners are at multiples of `scale`
+    fn to_svg(self, scale: f32, theme: Theme) -> Option<String> {
+        let color = theme.get_stroke_color().to_svg_hex();
+        let (half, width) = (scale / 2.0, Self::STROKE_SCALE * scale);
+        // The tile is shifted by half of the cell, so its marks are not clipped at the edges
+        let mark = match self.grid_type {
+            GridType::None => return None,
+            GridType::Cells => format!(
+                r#"<path d="M {half} 0 V {scale} M 0 {half} H {scale}" stroke="{color}" stroke-width="{width}"/>"#
+            ),
+            GridType::Dots => {
+                format!(r#"<circle cx="{half}" cy="{half}" r="{width}" fill="{color}"/>"#)
+            }
+        };
+        Some(format!(
+            r#"<defs><pattern id="grid" x="-{half}" y="-{half}" width="{scale}" height="{scale}" patternUnits="userSpaceOnUse">{mark}</pattern></defs>
+            <rect width="100%" height="100%" fill="url(#grid)" opacity="{}"/>"#,
+            self.opacity
+        ))
+    }
+}
+
+impl Default for ExportGrid {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+/// SVG export split into steps, so that big designs don't freeze the UI.
+/// Items are written into the writer one by one, the whole document is never kept in memory.
+pub struct SvgExportJob<W: Write> {
+    theme: Theme,
+    scale: f32,
+    style: NetStyle,
+    offset: GridPos,
+    components: Vec<Id>,
+    nets: Vec<Id>,
+    // Simulation values written over the nets
+    net_values: HashMap<Id, Logic>,
+    next_item: usize,
+    writer: W,
+}
+
+/// Size of the exported drawing in cells: the design with a margin
+pub fn get_svg_drawing_cells(db: &GridDB) -> (f32, f32) {
+    let (min, max) = db.get_bounds();
+    // Fixme:
+    ((max.x - min.x + 3) as f32, (max.y - min.y + 3) as f32)
+}
+
+impl<W: Write> SvgExportJob<W> {
+    pub fn new(
+        db: &GridDB,
+        theme: Theme,
+        scale: f32,
+        style: NetStyle,
+        writer: W,
+    ) -> io::Result<Self> {
+        Self::new_with_size(db, theme, scale, style, None, writer)
+    }
+
+    /// Export into the document of the given size instead of the drawing size
+    pub fn new_with_size(
+        db: &GridDB,
+        theme: Theme,
+        scale: f32,
+        style: NetStyle,
+        size: Option<DocumentSize>,
+        mut writer: W,
+    ) -> io::Result<Self> {
+        let (w, h) = get_svg_drawing_cells(db);
+        let (w, h) = (w * scale, h * scale);
+        let size = size
+            .map(DocumentSize::to_svg_attributes)
+            .unwrap_or_default();
+        let (min, _) = db.get_bounds();
+        let backgound = theme.get_bg_color().to_svg_hex();
+        writeln!(
+            writer,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>
+            <svg viewBox=\"0 0 {w} {h}\"{size} xmlns=\"http://www.w3.org/2000/svg\" style=\"background-color: {backgound}\">"
+        )?;
+        Ok(Self {
+            theme,
+            scale,
+            style,
+            offset: grid_pos(-min.x + 1, -min.y + 1),
+            components: db.get_component_ids(),
+            nets: db.get_net_ids(),
+            net_values: HashMap::new(),
+            next_item: 0,
+            writer,
+        })
+    }
+
+    /// Draws the grid under the schematic, must be called before the first step
+    pub fn with_grid(mut self, grid: ExportGrid) -> io::Result<Self> {
+        if let Some(svg) = grid.to_svg(self.scale, self.theme) {
+            writeln!(self.writer, "{svg}")?;
+        }
+        Ok(self)
+    }
+
+    /// Back-annotates the simulation: nets are colored by their values and labeled
+    pub fn with_net_values(mut self, net_values: HashMap<Id, Logic>) -> Self {
+        self.net_values = net_values;
+        self
+    }
+
+    fn items_number(&self) -> usize {
+        self.components.len() + self.nets.len()
+    }
+
+    /// Exports next `n_items` components or nets, returns true when everything is exported.
+    /// Items removed from `db` since the job start are skipped.
+    pub fn step(&mut self, db: &GridDB, n_items: usize) -> io::Result<bool> {
+        let end = (self.next_item + n_items).min(self.items_number());
+        let items: Vec<usize> = (self.next_item..end).collect();
+        let Self {
+            theme,
+            scale,
+            style,
+            offset,
+            components,
+            nets,
+            net_values,
+            ..
+        } = self;
+        // Items are generated on all cores and written in the original order
+        let svg_items = parallel::map(&items, |i| {
+            if *i < components.len() {
+                db.get_component(&components[*i]).map(|comp| match comp {
+                    Component::TextField(f) => {
+                        f.get_text_svg(&db.expand_variables(&f.text), *offset, *scale, *theme)
+                    }
+                    _ => comp.to_svg(*offset, *scale, *theme, style),
+                })
+            } else {
+                let id = nets[*i - components.len()];
+                let value = net_values.get(&id).copied();
+                db.get_net(&id).and_then(|net| {
+                    let class = db.get_net_class(net);
+                    // Back-annotated values are shown in any color mode:
+                    let mode = match value {
+                        Some(_) => NetColorMode::SimulationValues,
+                        None => style.color_mode,
+                    };
+                    let color = mode.get_net_color(class, value, *theme);
+                    let width = STROKE_SCALE * *scale * class.map_or(1.0, |c| c.width);
+                    let mut svg = net.to_svg(color, width, *offset, *scale, db, style)?;
+                    if let Some(value) = value
+                        && let Some(point) = net.points.first()
+                    {
+                        let pos = pos2((point.x + offset.x) as f32, (point.y + offset.y) as f32);
+                        svg.push('\n');
+                        svg.push_str(&svg_single_line_text(
+                            value.to_str().into(),
+                            pos * *scale,
+                            0.5 * *scale,
+                            Rotation::ROT0,
+                            *theme,
+                            Align2::LEFT_TOP,
+                        ));
+                    }
+                    Some(svg)
+                })
+            }
+        });
+        for item in svg_items.into_iter().flatten() {
+            writeln!(self.writer, "{item}")?;
+        }
+        self.next_item = end;
+        Ok(self.next_item == self.items_number())
+    }
+
+    /// Part of the exported items in range 0..=1
+    pub fn progress(&self) -> f32 {
+        if self.items_number() == 0 {
+            1.0
+        } else {
+            self.next_item as f32 / self.items_number() as f32
+        }
+    }
+
+    /// Closes the document, embedding the project data if `project` is given
+    pub fn finish(mut self, project: Option<&GridDB>) -> io::Result<W> {
+        if let Some(db) = project {
+            db.write_svg_project_data(&mut self.writer)?;
+        }
+        write!(self.writer, "</svg>")?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExportGrid, ExportLayer, SvgExportJob};
+    use crate::{
+        field_state::GridType,
+        grid_db::{AssertionType, GridDB, PrimitiveType, Rotation, Theme},
+        testing::{GridDBBuilder, half_adder},
+    };
+
+    #[test]
+    fn export_layers() {
+        let mut b = GridDBBuilder::new();
+        let gate = b.primitive(PrimitiveType::And(2), 0, 0, Rotation::ROT0);
+        let assertion = PrimitiveType::Assertion {
+            condition: AssertionType::OneHot,
+            n_inputs: 2,
+        };
+        let assertion = b.primitive(assertion, 10, 0, Rotation::ROT0);
+        b.connect((gate, 2), (assertion, 0));
+        b.text("internal note", 0, 10);
+        let db = b.build();
+        let ids = db.get_component_ids();
+        assert_eq!(ExportLayer::filter(&db, ids.clone(), &[]), ids);
+        let filtered = ExportLayer::filter(&db, ids.clone(), &[ExportLayer::Notes]);
+        assert_eq!(filtered, [gate, assertion]);
+        let filtered = ExportLayer::filter(&db, ids, &ExportLayer::ALL);
+        assert_eq!(filtered, [gate]);
+        // Nets to the excluded components are left out too:
+        assert!(db.extract_fragment(&filtered).get_net_ids().is_empty());
+        assert!(!ExportLayer::Notes.contains(db.get_component(&gate).unwrap()));
+    }
+
+    #[test]
+    fn export_grid() {
+        let db = half_adder();
+        let export = |grid| {
+            let mut job = SvgExportJob::new(&db, Theme::Light, 20.0, Default::default(), vec![])
+                .and_then(|job| job.with_grid(grid))
+                .unwrap();
+            while !job.step(&db, 16).unwrap() {}
+            String::from_utf8(job.finish(Some(&db)).unwrap()).unwrap()
+        };
+        assert!(!export(ExportGrid::NONE).contains("pattern"));
+        let grid = ExportGrid {
+            grid_type: GridType::Dots,
+            opacity: 0.25,
+        };
+        let svg = export(grid);
+        assert!(svg.contains(r#"x="-10" y="-10" width="20" height="20""#));
+        assert!(svg.contains(r#"opacity="0.25""#));
+        // The grid is drawn under the schematic:
+        assert!(svg.lines().nth(2).unwrap().starts_with("<defs><pattern"));
+        let loaded = GridDB::load_from_svg(&svg).unwrap().unwrap();
+        assert_eq!(loaded.get_component_ids(), db.get_component_ids());
+    }
+}
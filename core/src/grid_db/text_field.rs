@@ -0,0 +1,279 @@
+use std::{borrow::Cow, collections::HashMap};
+
+use crate::{
+    canvas::Canvas,
+    field_state::FieldState,
+    grid_db::{
+        ComponentAction, ComponentColor, GridPos, Id, Rotation, SharedStr, SvgColor, Theme,
+        layout_math_text,
+    },
+};
+use epaint::{FontId, Pos2, Rect, Shape, TextShape, emath::Align2, pos2, vec2};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct TextField {
+    pub text: SharedStr,
+    pub size: (i32, i32),
+    pub pos: GridPos,
+    /// Component the annotation is attached to, it follows the component when it's moved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub anchor: Option<Id>,
+}
+
+impl TextField {
+    pub const ACTIONS: &'static [ComponentAction] =
+        &[ComponentAction::EditText, ComponentAction::Remove];
+    pub const FONT_SCALE: f32 = 0.5;
+
+    /// Updates the anchor by the new IDs of the components, it's dropped if it's not there
+    pub fn remap_anchor(&mut self, ids: &HashMap<Id, Id>) {
+        self.anchor = self.anchor.and_then(|id| ids.get(&id).copied());
+    }
+
+    pub fn display(&self, state: &FieldState, painter: &impl Canvas) {
+        self.display_text(&self.text, state, painter);
+    }
+
+    /// Displays `text` instead of the own one, e.g. with expanded template variables
+    pub fn display_text(&self, text: &str, state: &FieldState, painter: &impl Canvas) {
+        let screen_pos = state.grid_to_screen(&self.pos);
+        let (w, h) = self.size;
+        let rect = Rect::from_min_size(
+            screen_pos,
+            vec2(state.grid_size * w as f32, state.grid_size * h as f32),
+        );
+        if !painter.clip_rect().intersects(rect) {
+            return;
+        }
+        if let Some(math) = layout_math_text(text, state.grid_size * Self::FONT_SCALE) {
+            let color = painter.theme().get_text_color();
+            math.paint(&painter.with_clip_rect(rect), screen_pos, color);
+            return;
+        }
+        show_text_with_debounce(
+            screen_pos,
+            text.to_string(),
+            state,
+            &painter.with_clip_rect(rect),
+            Some(w as f32 * state.grid_size),
+            Rotation::ROT0,
+            Align2::LEFT_TOP,
+        );
+    }
+
+    pub fn get_svg(&self, offset: GridPos, scale: f32, theme: Theme) -> String {
+        self.get_text_svg(&self.text, offset, scale, theme)
+    }
+
+    pub fn get_text_svg(&self, text: &str, offset: GridPos, scale: f32, theme: Theme) -> String {
+        // TODO: Add text wrapping!!!
+        let color = theme.get_text_color().to_svg_hex();
+        let GridPos { x, y } = self.pos + offset;
+        let x = x as f32 * scale;
+        let y = y as f32 * scale;
+        let font_size = Self::FONT_SCALE * scale;
+        // Formulas are exported as outlines, the source is kept in the title
+        if let Some(math) = layout_math_text(text, font_size) {
+            let d = math.to_svg_path(pos2(x, y));
+            let title = html_escape::encode_text(text);
+            return format!(r#"<path d="{d}" fill="{color}"><title>{title}</title></path>"#);
+        }
+        let body = text
+            .split("\n")
+            .enumerate()
+            .map(|(i, line)| {
+                let encoded_text = html_escape::encode_text(line);
+                let dy = if i == 0 { 0.0 } else { font_size };
+                format!(r#"<tspan x="{x}" dy="{dy}">{encoded_text}</tspan>"#)
+            })
+            .collect::<Vec<String>>()
+            .join("");
+        format!(
+            r#"<text x="{x}" y="{y}" font-family="monospace" font-size="{font_size}" fill="{color}" text-anchor="start" dominant-baseline="hanging">{body}</text>"#
+        )
+    }
+}
+
+/// Replaces `{{name}}` placeholders with the values given by `lookup`, unknown ones are kept
+pub fn expand_template(text: &str, lookup: impl Fn(&str) -> Option<String>) -> Cow<'_, str> {
+    if !text.contains("{{") {
+        return Cow::Borrowed(text);
+    }
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        let name = &rest[start + 2..start + 2 + len];
+        result.push_str(&rest[..start]);
+        match lookup(name.trim()) {
+            Some(value) => result.push_str(&value),
+            None => result.push_str(&rest[start..start + len + 4]),
+        }
+        rest = &rest[start + len + 4..];
+    }
+    result.push_str(rest);
+    Cow::Owned(result)
+}
+
+/// Current UTC date as `YYYY-MM-DD`
+pub fn current_date() -> String {
+    let secs = web_time::SystemTime::now()
+        .duration_since(web_time::SystemTime::UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs());
+    format_date((secs / 86400) as i64)
+}
+
+/// Date of the day number since 1970-01-01, the proleptic Gregorian calendar
+fn format_date(days: i64) -> String {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{year:04}-{month:02}-{day:02}")
+}
+
+/// Bounds of the text laid out with `font_size` at `pos`, for any rotation and anchor.
+/// They are estimated without the layout, which is the slowest part of the drawing.
+fn get_text_bounds(pos: Pos2, text: &str, font_size: f32, wrap_width: Option<f32>) -> Rect {
+    // Upper bound of the glyph width and the row height in font sizes
+    const GLYPH_BOUND: f32 = 1.5;
+    let lines = text.lines().count().max(1);
+    let longest = text.lines().map(|l| l.chars().count()).max().unwrap_or(0);
+    let (width, height) = match wrap_width {
+        Some(wrap) => (wrap, (text.chars().count() + lines) as f32),
+        None => (longest as f32 * GLYPH_BOUND * font_size, lines as f32),
+    };
+    let extent = width.max(height * GLYPH_BOUND * font_size);
+    Rect::from_center_size(pos, vec2(2.0 * extent, 2.0 * extent))
+}
+
+pub fn show_text_with_debounce(
+    pos: Pos2,
+    text: String,
+    state: &FieldState,
+    painter: &impl Canvas,
+    wrap_width: Option<f32>,
+    rotation: Rotation,
+    anchor: Align2,
+) {
+    let font_size = state.grid_size * TextField::FONT_SCALE;
+    // Labels outside the field are skipped before their layout:
+    if !painter
+        .clip_rect()
+        .intersects(get_text_bounds(pos, &text, font_size, wrap_width))
+    {
+        return;
+    }
+    let theme = painter.theme();
+    let color = theme.get_text_color();
+
+    if state.debounce {
+        let prev_font_size = 64.0;
+        let scale = state.grid_size * TextField::FONT_SCALE / prev_font_size;
+
+        let galley = painter.fonts(|fonts| {
+            if let Some(wrap) = wrap_width {
+                let scaled_wrap = wrap / scale;
+                fonts.layout(
+                    text.clone(),
+                    FontId::monospace(prev_font_size),
+                    color,
+                    scaled_wrap,
+                )
+            } else {
+                fonts.layout_no_wrap(text.clone(), FontId::monospace(prev_font_size), color)
+            }
+        });
+
+        let final_size = galley.size() * scale;
+        let offset = rotation.get_text_anchor_offset(anchor, final_size);
+        let aligned_pos = pos - offset;
+
+        let mut shape = Shape::Text(
+            TextShape::new(pos2(0.0, 0.0), galley, color).with_angle(rotation.to_radians()),
+        );
+        shape.scale(scale);
+        shape.translate(aligned_pos.to_vec2());
+        painter.add(shape);
+        painter.request_repaint();
+    } else {
+        let galley = painter.fonts(|fonts| {
+            if let Some(wrap) = wrap_width {
+                fonts.layout(text.clone(), FontId::monospace(font_size), color, wrap)
+            } else {
+                fonts.layout_no_wrap(text.clone(), FontId::monospace(font_size), color)
+            }
+        });
+
+        let offset = rotation.get_text_anchor_offset(anchor, galley.size());
+        let aligned_pos = pos - offset;
+
+        let shape = Shape::Text(
+            TextShape::new(aligned_pos, galley, color).with_angle(rotation.to_radians()),
+        );
+        painter.add(shape);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use epaint::{
+        AlphaFromCoverage, FontId, Rect, emath::Align2, pos2,
+        text::{FontDefinitions, Fonts},
+    };
+
+    use super::{expand_template, format_date, get_text_bounds};
+
+    #[test]
+    fn template_variables() {
+        let lookup = |name: &str| (name == "rev").then(|| "B".to_owned());
+        assert_eq!(
+            expand_template("Rev. {{rev}}/{{ rev }}", lookup),
+            "Rev. B/B"
+        );
+        assert_eq!(
+            expand_template("{{sheet}} {{rev", lookup),
+            "{{sheet}} {{rev"
+        );
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(20454), "2026-01-01");
+        assert_eq!(format_date(11016), "2000-02-29");
+    }
+
+    #[test]
+    fn text_bounds() {
+        let fonts = Fonts::new(
+            1.0,
+            2048,
+            AlphaFromCoverage::default(),
+            FontDefinitions::default(),
+        );
+        let text = "Q\nQ_out ⇅";
+        let size = fonts
+            .layout_no_wrap(text.into(), FontId::monospace(20.0), Default::default())
+            .size();
+        let pos = pos2(100.0, 100.0);
+        let bounds = get_text_bounds(pos, text, 20.0, None);
+        for anchor in [
+            Align2::LEFT_TOP,
+            Align2::CENTER_CENTER,
+            Align2::RIGHT_BOTTOM,
+        ] {
+            let rect = anchor.anchor_size(pos, size);
+            assert!(bounds.contains_rect(rect));
+            // Rotated by 90 degrees:
+            let rect = anchor.anchor_size(pos, size.yx());
+            assert!(bounds.contains_rect(rect));
+        }
+        let far = Rect::from_min_size(pos2(1000.0, 0.0), size);
+        assert!(!far.intersects(bounds));
+    }
+}
@@ -0,0 +1,365 @@
+use std::collections::{HashMap, VecDeque};
+
+use serde_json::Value;
+
+use crate::grid_db::{
+    Component, DFFParams, GridDB, GridDBConnectionPoint, GridPos, Id, Net, Parameter, Port,
+    PrimitiveComponent, PrimitiveType, Rotation, TextField, Unit, grid_pos, simplify_path,
+};
+
+/// Horizontal distance between columns of the imported components
+const COLUMN_WIDTH: i32 = 12;
+/// Vertical gap between the imported components
+const ROW_GAP: i32 = 2;
+
+struct Node {
+    component: Component,
+    label: Option<String>,
+    // (connection ID, signal key, is output)
+    connections: Vec<(Id, String, bool)>,
+}
+
+/// Driver and load of an imported signal
+pub type YosysConnection = (GridDBConnectionPoint, GridDBConnectionPoint);
+
+/// Returns true if the JSON looks like a netlist written by Yosys `write_json`
+pub fn is_yosys_json(value: &Value) -> bool {
+    value.get("modules").is_some_and(|m| m.is_object())
+}
+
+fn find_connection(component: &Component, name: &str) -> Option<Id> {
+    (0..component.get_connections_number())
+        .find(|id| component.get_connection_name(*id).as_deref() == Some(name))
+}
+
+fn primitive(typ: PrimitiveType) -> Component {
+    Component::Primitive(PrimitiveComponent {
+        typ,
+        pos: grid_pos(0, 0),
+        rotation: Rotation::ROT0,
+        name: Default::default(),
+        source: None,
+    })
+}
+
+/// Maps a Yosys cell type to a primitive, None for unsupported cells
+fn map_cell_type(typ: &str) -> Option<PrimitiveType> {
+    let typ = match typ {
+        "$and" | "$_AND_" | "$logic_and" => PrimitiveType::And(2),
+        "$or" | "$_OR_" | "$logic_or" => PrimitiveType::Or(2),
+        "$xor" | "$_XOR_" => PrimitiveType::Xor(2),
+        "$_NAND_" => PrimitiveType::Nand(2),
+        "$not" | "$_NOT_" | "$logic_not" => PrimitiveType::Not,
+        "$mux" | "$_MUX_" => PrimitiveType::Mux(2),
+        "$add" => PrimitiveType::Adder {
+            cin: false,
+            cout: false,
+        },
+        "$eq" => PrimitiveType::Comparator(crate::grid_db::ComparisonType::EQ),
+        "$lt" => PrimitiveType::Comparator(crate::grid_db::ComparisonType::LT),
+        "$le" => PrimitiveType::Comparator(crate::grid_db::ComparisonType::LTE),
+        "$gt" => PrimitiveType::Comparator(crate::grid_db::ComparisonType::GT),
+        "$ge" => PrimitiveType::Comparator(crate::grid_db::ComparisonType::GTE),
+        "$dff" | "$_DFF_P_" => PrimitiveType::DFF(DFFParams {
+            has_enable: false,
+            has_async_reset: false,
+            has_sync_reset: false,
+            async_reset_inverted: false,
+            sync_reset_inverted: false,
+        }),
+        _ => return None,
+    };
+    Some(typ)
+}
+
+/// Maps a Yosys port name to the name of the primitive connection
+fn map_port_name(name: &str) -> &str {
+    match name {
+        "A" => "in0",
+        "B" => "in1",
+        "Y" => "out",
+        "S" => "sel",
+        "D" => "d",
+        "Q" => "q",
+        "C" | "CLK" => "clk",
+        _ => name,
+    }
+}
+
+/// Unknown cells become units with inputs on the left side and outputs on the right one
+fn make_unit(directions: &[(String, bool)], parameters: Vec<Parameter>) -> Component {
+    let mut ports = vec![];
+    let (mut n_inputs, mut n_outputs) = (0, 0);
+    for (name, is_output) in directions {
+        let (offset, align) = if *is_output {
+            n_outputs += 1;
+            (n_outputs, Rotation::ROT180)
+        } else {
+            n_inputs += 1;
+            (n_inputs, Rotation::ROT0)
+        };
+        ports.push(Port {
+            offset,
+            align,
+            name: name.into(),
+            bundle: None,
+        });
+    }
+    Component::Unit(Unit {
+        pos: grid_pos(0, 0),
+        width: 4,
+        height: n_inputs.max(n_outputs) + 2,
+        ports,
+        parameters,
+        description: String::new(),
+        name: Default::default(),
+        source: None,
+        schematic: None,
+    })
+}
+
+/// Cell parameters, Yosys writes numbers as strings of binary digits
+fn parse_parameters(cell: &Value) -> Vec<Parameter> {
+    let Some(parameters) = cell.get("parameters").and_then(|p| p.as_object()) else {
+        return vec![];
+    };
+    parameters
+        .iter()
+        .map(|(name, value)| {
+            let value = match value {
+                Value::String(s) if s.len() <= 64 && s.chars().all(|c| c == '0' || c == '1') => {
+                    u64::from_str_radix(s, 2).map_or(s.clone(), |v| v.to_string())
+                }
+                Value::String(s) => s.clone(),
+                other => other.to_string(),
+            };
+            Parameter {
+                name: name.into(),
+                value: value.into(),
+            }
+        })
+        .collect()
+}
+
+fn signal_key(bits: &Value) -> String {
+    bits.to_string()
+}
+
+fn parse_cell(name: &str, cell: &Value) -> Option<Node> {
+    let typ = cell.get("type")?.as_str()?;
+    let connections = cell.get("connections")?.as_object()?;
+    let directions: Vec<(String, bool)> = connections
+        .keys()
+        .map(|port| {
+            let is_output = cell
+                .get("port_directions")
+                .and_then(|d| d.get(port))
+                .and_then(|d| d.as_str())
+                == Some("output");
+            (port.clone(), is_output)
+        })
+        .collect();
+
+    let mapped = map_cell_type(typ).map(primitive).filter(|component| {
+        connections
+            .keys()
+            .all(|port| find_connection(component, map_port_name(port)).is_some())
+    });
+    let (component, label) = match mapped {
+        Some(component) => (component, None),
+        None => (
+            make_unit(&directions, parse_parameters(cell)),
+            Some(format!("{} ({})", typ.trim_start_matches('$'), name)),
+        ),
+    };
+    let connections = directions
+        .iter()
+        .filter_map(|(port, is_output)| {
+            let id = match &component {
+                Component::Unit(_) => find_connection(&component, port)?,
+                _ => find_connection(&component, map_port_name(port))?,
+            };
+            Some((id, signal_key(connections.get(port)?), *is_output))
+        })
+        .collect();
+    Some(Node {
+        component,
+        label,
+        connections,
+    })
+}
+
+fn parse_module(module: &Value) -> Vec<Node> {
+    let mut nodes = vec![];
+    if let Some(ports) = module.get("ports").and_then(|p| p.as_object()) {
+        for (name, port) in ports {
+            let is_input = port.get("direction").and_then(|d| d.as_str()) == Some("input");
+            let Some(bits) = port.get("bits") else {
+                continue;
+            };
+            nodes.push(Node {
+                component: primitive(if is_input {
+                    PrimitiveType::Input
+                } else {
+                    PrimitiveType::Output
+                }),
+                label: Some(name.clone()),
+                // Module input drives the signal
+                connections: vec![(0, signal_key(bits), is_input)],
+            });
+        }
+    }
+    if let Some(cells) = module.get("cells").and_then(|c| c.as_object()) {
+        for (name, cell) in cells {
+            if let Some(node) = parse_cell(name, cell) {
+                nodes.push(node);
+            }
+        }
+    }
+    nodes
+}
+
+/// Column of every node: distance from module inputs through the signals
+fn get_levels(nodes: &[Node]) -> Vec<i32> {
+    let mut loads: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, node) in nodes.iter().enumerate() {
+        for (_, key, is_output) in &node.connections {
+            if !is_output {
+                loads.entry(key).or_default().push(i);
+            }
+        }
+    }
+    let is_module_port = |node: &Node| match &node.component {
+        Component::Primitive(p) => matches!(p.typ, PrimitiveType::Input | PrimitiveType::Output),
+        _ => false,
+    };
+    let mut levels: Vec<Option<i32>> = vec![None; nodes.len()];
+    let mut queue = VecDeque::new();
+    for (i, node) in nodes.iter().enumerate() {
+        if is_module_port(node) && node.connections.iter().any(|c| c.2) {
+            levels[i] = Some(0);
+            queue.push_back(i);
+        }
+    }
+    while let Some(i) = queue.pop_front() {
+        for (_, key, is_output) in &nodes[i].connections {
+            if !is_output {
+                continue;
+            }
+            for load in loads.get(key.as_str()).into_iter().flatten() {
+                if levels[*load].is_none() && !is_module_port(&nodes[*load]) {
+                    levels[*load] = Some(levels[i].unwrap() + 1);
+                    queue.push_back(*load);
+                }
+            }
+        }
+    }
+    let max_level = levels.iter().flatten().max().copied().unwrap_or(0);
+    nodes
+        .iter()
+        .zip(levels)
+        .map(|(node, level)| match level {
+            Some(level) => level,
+            None if is_module_port(node) => max_level + 1,
+            None => 1,
+        })
+        .collect()
+}
+
+impl GridDB {
+    /// Imports the top module of a Yosys JSON netlist.
+    /// Components are placed in columns by their distance from the inputs.
+    pub fn load_from_yosys_json(value: &Value) -> Option<Self> {
+        let (mut result, connections) = Self::place_yosys_json(value)?;
+        result.route_connections(&connections);
+        Some(result)
+    }
+
+    /// Places the components of the Yosys JSON netlist without nets.
+    /// Returns the connections between the drivers and the loads to be routed.
+    pub fn place_yosys_json(value: &Value) -> Option<(Self, Vec<YosysConnection>)> {
+        let modules = value.get("modules")?.as_object()?;
+        let module = modules
+            .values()
+            .find(|m| {
+                m.get("attributes")
+                    .and_then(|a| a.get("top"))
+                    .is_some_and(|top| top.as_str().is_some_and(|s| s.ends_with('1')))
+            })
+            .or_else(|| modules.values().next())?;
+
+        let mut nodes = parse_module(module);
+        let levels = get_levels(&nodes);
+        let mut column_heights: HashMap<i32, i32> = HashMap::new();
+        let mut result = Self::new();
+        let mut drivers: HashMap<String, GridDBConnectionPoint> = HashMap::new();
+        let mut loads: Vec<(String, GridDBConnectionPoint)> = vec![];
+
+        for (node, level) in nodes.iter_mut().zip(levels) {
+            let y = column_heights.entry(level).or_insert(1);
+            let pos = grid_pos(level * COLUMN_WIDTH, *y + 1);
+            let (width, height) = node.component.get_dimension();
+            *y += height + ROW_GAP + 1;
+            node.component.set_pos(pos);
+
+            if let Some(label) = &node.label {
+                let id = result.allocate_component();
+                let text_width = (label.chars().count() as i32 / 2 + 1).max(width);
+                result.insert_component(
+                    id,
+                    Component::TextField(TextField {
+                        text: label.into(),
+                        size: (text_width, 1),
+                        pos: pos - grid_pos(0, 1),
+                        anchor: None,
+                    }),
+                );
+            }
+            let id = result.allocate_component();
+            for (connection_id, key, is_output) in &node.connections {
+                let point = GridDBConnectionPoint {
+                    component_id: id,
+                    connection_id: *connection_id,
+                };
+                if *is_output {
+                    drivers.insert(key.clone(), point);
+                } else {
+                    loads.push((key.clone(), point));
+                }
+            }
+            result.insert_component(id, node.component.clone());
+        }
+
+        let connections = loads
+            .into_iter()
+            .filter_map(|(key, end_point)| Some((*drivers.get(&key)?, end_point)))
+            .collect();
+        Some((result, connections))
+    }
+
+    /// Adds the nets of the imported connections, it is the slowest part of the import
+    pub fn route_connections(&mut self, connections: &[YosysConnection]) {
+        for (start_point, end_point) in connections.iter().copied() {
+            let dock_cell = |p: &GridDBConnectionPoint| -> Option<GridPos> {
+                self.get_component(&p.component_id)?
+                    .get_connection_dock_cell(p.connection_id)
+            };
+            let (Some(a), Some(b)) = (dock_cell(&start_point), dock_cell(&end_point)) else {
+                continue;
+            };
+            let mut points = vec![a];
+            points.extend(self.find_net_path(a, b));
+            points.push(b);
+            let id = self.allocate_net();
+            self.insert_net(
+                id,
+                Net {
+                    start_point,
+                    end_point,
+                    points: simplify_path(points),
+                    class: Default::default(),
+                },
+            );
+        }
+    }
+}
@@ -0,0 +1,694 @@
+//! Hierarchical units: extraction of the selected components into a unit
+//! with an inner schematic and flattening of the unit back
+
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+use crate::{
+    grid_db::{
+        AllocatedFragment, Component, GridDB, GridDBConnectionPoint, GridPos, Id, Port,
+        PrimitiveComponent, PrimitiveType, Rotation, SharedStr, Unit, grid_pos,
+    },
+    netlist::{NetIndex, Netlist, extract_netlist},
+};
+
+/// Cells between the inner components and the inputs and outputs added for the ports
+const PORT_IO_GAP: i32 = 4;
+
+/// Crossing of the selection boundary, it becomes a port of the extracted unit
+struct BoundaryNet {
+    name: String,
+    is_output: bool,
+    inside: BTreeSet<GridDBConnectionPoint>,
+    outside: BTreeSet<GridDBConnectionPoint>,
+}
+
+/// Unit replacing the selected components, with nets from its ports to the rest of the schematic
+pub struct UnitExtraction {
+    pub id: Id,
+    pub unit: Unit,
+    pub nets: AllocatedFragment,
+}
+
+impl UnitExtraction {
+    /// The unit and its nets to be inserted into the database
+    pub fn into_fragment(self) -> AllocatedFragment {
+        let mut fragment = self.nets;
+        fragment
+            .components
+            .push((self.id, Component::Unit(self.unit)));
+        fragment
+    }
+}
+
+fn point(component_id: Id, connection_id: Id) -> GridDBConnectionPoint {
+    GridDBConnectionPoint {
+        component_id,
+        connection_id,
+    }
+}
+
+fn get_dock_cell(db: &GridDB, p: &GridDBConnectionPoint) -> Option<GridPos> {
+    db.get_component(&p.component_id)?
+        .get_connection_dock_cell(p.connection_id)
+}
+
+/// Cells occupied by the components: (min, max), inclusive
+fn get_components_rect(db: &GridDB, ids: &BTreeSet<Id>) -> (GridPos, GridPos) {
+    let rects: Vec<(GridPos, GridPos)> = ids
+        .iter()
+        .filter_map(|id| db.get_component(id))
+        .map(|comp| {
+            let (w, h) = comp.get_dimension();
+            let pos = comp.get_position();
+            (pos, pos + grid_pos(w - 1, h - 1))
+        })
+        .collect();
+    let min = grid_pos(
+        rects.iter().map(|r| r.0.x).min().unwrap_or(0),
+        rects.iter().map(|r| r.0.y).min().unwrap_or(0),
+    );
+    let max = grid_pos(
+        rects.iter().map(|r| r.1.x).max().unwrap_or(0),
+        rects.iter().map(|r| r.1.y).max().unwrap_or(0),
+    );
+    (min, max)
+}
+
+/// Port named by the first inner pin of the net, like `and2_3_out`
+fn get_port_name(
+    netlist: &Netlist,
+    net: NetIndex,
+    inside: &BTreeSet<GridDBConnectionPoint>,
+) -> String {
+    inside
+        .iter()
+        .find_map(|p| {
+            let instance = netlist.get_instance(p.component_id)?;
+            let pin = instance.pins.get(p.connection_id)?;
+            Some(match pin.name.is_empty() {
+                true => instance.name.clone(),
+                false => format!("{}_{}", instance.name, pin.name.to_lowercase()),
+            })
+        })
+        .unwrap_or_else(|| netlist.nets[net].name.clone())
+}
+
+fn find_boundary_nets(db: &GridDB, selected: &BTreeSet<Id>) -> Vec<BoundaryNet> {
+    let netlist = extract_netlist(db);
+    let mut crossings: BTreeMap<NetIndex, (BTreeSet<_>, BTreeSet<_>)> = BTreeMap::new();
+    for wire_id in db.get_net_ids() {
+        let wire = db.get_net(&wire_id).unwrap();
+        let (start, end) = (wire.start_point, wire.end_point);
+        let (inside, outside) = match (
+            selected.contains(&start.component_id),
+            selected.contains(&end.component_id),
+        ) {
+            (true, false) => (start, end),
+            (false, true) => (end, start),
+            _ => continue,
+        };
+        let Some(net) = netlist.get_wire_net(wire_id) else {
+            continue;
+        };
+        let crossing = crossings.entry(net).or_default();
+        crossing.0.insert(inside);
+        crossing.1.insert(outside);
+    }
+    let mut names = HashSet::new();
+    crossings
+        .into_iter()
+        .map(|(net, (inside, outside))| {
+            let base = get_port_name(&netlist, net, &inside);
+            let mut name = base.clone();
+            for i in 1.. {
+                if names.insert(name.clone()) {
+                    break;
+                }
+                name = format!("{base}_{i}");
+            }
+            BoundaryNet {
+                name,
+                is_output: netlist
+                    .get_drivers(net)
+                    .iter()
+                    .any(|p| selected.contains(&p.component_id)),
+                inside,
+                outside,
+            }
+        })
+        .collect()
+}
+
+/// Inner schematic of the extracted unit: the selected components
+/// with inputs and outputs in place of the ports, connected to the boundary pins
+fn build_inner_schematic(db: &GridDB, selected: &BTreeSet<Id>, ports: &[BoundaryNet]) -> GridDB {
+    let ids: Vec<Id> = selected.iter().copied().collect();
+    let mut inner = db.extract_fragment(&ids);
+    let (min, max) = get_components_rect(db, selected);
+    let (mut inputs, mut outputs) = (0, 0);
+    for port in ports {
+        let (typ, x, row) = match port.is_output {
+            true => (PrimitiveType::Output, max.x + PORT_IO_GAP, &mut outputs),
+            false => (PrimitiveType::Input, min.x - PORT_IO_GAP - 2, &mut inputs),
+        };
+        let io_id = inner.allocate_component();
+        inner.insert_component(
+            io_id,
+            Component::Primitive(PrimitiveComponent {
+                typ,
+                pos: grid_pos(x, min.y + 2 * *row),
+                rotation: Rotation::ROT0,
+                name: port.name.as_str().into(),
+                source: None,
+            }),
+        );
+        *row += 1;
+        let io = point(io_id, 0);
+        let io_cell = get_dock_cell(&inner, &io).unwrap();
+        for p in &port.inside {
+            let Some(cell) = get_dock_cell(&inner, p) else {
+                continue;
+            };
+            let net = match port.is_output {
+                true => inner.route_net(*p, cell, io, io_cell),
+                false => inner.route_net(io, io_cell, *p, cell),
+            };
+            let net_id = inner.allocate_net();
+            inner.insert_net(net_id, net);
+        }
+    }
+    inner
+}
+
+/// Unit with the selected components as its inner schematic, placed instead of them.
+/// Nets crossing the selection become ports: inputs on the left and outputs on the right.
+/// IDs of the unit and the new nets are allocated in `db`.
+/// None if nothing is selected or the unit doesn't fit.
+pub fn extract_unit(db: &mut GridDB, ids: &[Id]) -> Option<UnitExtraction> {
+    let selected: BTreeSet<Id> = ids
+        .iter()
+        .copied()
+        .filter(|id| db.get_component(id).is_some())
+        .collect();
+    if selected.is_empty() {
+        return None;
+    }
+    let ports = find_boundary_nets(db, &selected);
+    let inner = build_inner_schematic(db, &selected, &ports);
+    let (min, _) = get_components_rect(db, &selected);
+    let mut unit_ports = vec![];
+    let (mut inputs, mut outputs) = (0, 0);
+    for port in &ports {
+        let (align, row) = match port.is_output {
+            true => (Rotation::ROT180, &mut outputs),
+            false => (Rotation::ROT0, &mut inputs),
+        };
+        *row += 1;
+        unit_ports.push(Port {
+            offset: *row,
+            align,
+            name: port.name.as_str().into(),
+            bundle: None,
+        });
+    }
+    let unit = Unit {
+        pos: min,
+        width: 4,
+        height: inputs.max(outputs).max(1) + 1,
+        ports: unit_ports,
+        parameters: vec![],
+        description: String::new(),
+        name: Default::default(),
+        source: None,
+        schematic: Some(Box::new(inner.to_schematic())),
+    };
+    // Any selected component is replaced, but overlap-only ones like texts check less cells
+    let replaced = *selected
+        .iter()
+        .find(|id| !db.get_component(id).unwrap().is_overlap_only())
+        .or(selected.first())?;
+    if !db.is_available_location_except(
+        unit.pos,
+        (unit.width, unit.height),
+        replaced,
+        &selected.iter().copied().collect(),
+    ) {
+        return None;
+    }
+
+    let id = db.allocate_component();
+    let component = Component::Unit(unit);
+    let mut nets = vec![];
+    for (i, port) in ports.iter().enumerate() {
+        let port_point = point(id, i);
+        let port_cell = component.get_connection_dock_cell(i).unwrap();
+        for p in &port.outside {
+            let Some(cell) = get_dock_cell(db, p) else {
+                continue;
+            };
+            let net = match port.is_output {
+                true => db.route_net(port_point, port_cell, *p, cell),
+                false => db.route_net(*p, cell, port_point, port_cell),
+            };
+            nets.push((db.allocate_net(), net));
+        }
+    }
+    let Component::Unit(unit) = component else {
+        unreachable!()
+    };
+    Some(UnitExtraction {
+        id,
+        unit,
+        nets: AllocatedFragment {
+            components: vec![],
+            nets,
+        },
+    })
+}
+
+/// Inputs and outputs of the inner schematic connected to the unit ports, by port index
+fn get_port_io(unit: &Unit, inner: &GridDB) -> HashMap<Id, Id> {
+    let mut result = HashMap::new();
+    for id in inner.get_component_ids() {
+        let Some(Component::Primitive(p)) = inner.get_component(&id) else {
+            continue;
+        };
+        if !matches!(p.typ, PrimitiveType::Input | PrimitiveType::Output) {
+            continue;
+        }
+        if let Some(port) = unit.ports.iter().position(|port| port.name == p.name) {
+            result.entry(port).or_insert(id);
+        }
+    }
+    result
+}
+
+/// Pins of the inner schematic connected to the input or output by wires
+fn get_io_loads(inner: &GridDB, io: Id) -> Vec<GridDBConnectionPoint> {
+    let io_point = point(io, 0);
+    let mut result: Vec<_> = inner
+        .get_point_nets(&io_point)
+        .filter_map(|net_id| inner.get_net(&net_id))
+        .map(|net| match net.start_point == io_point {
+            true => net.end_point,
+            false => net.start_point,
+        })
+        .collect();
+    result.sort_unstable();
+    result.dedup();
+    result
+}
+
+/// Inner components and nets of the hierarchical unit placed instead of it,
+/// the content is moved to the unit position. Inputs and outputs of the ports are dropped,
+/// the outer nets of the ports are connected to the pins they were wired to inside.
+/// IDs are allocated in `db`. None if the unit has no schematic or its content doesn't fit.
+pub fn flatten_unit(db: &mut GridDB, id: Id) -> Option<AllocatedFragment> {
+    let Some(Component::Unit(unit)) = db.get_component(&id) else {
+        return None;
+    };
+    let inner = GridDB::from_schematic(unit.schematic.as_ref()?);
+    let port_io = get_port_io(unit, &inner);
+    let content: BTreeSet<Id> = inner
+        .get_component_ids()
+        .into_iter()
+        .filter(|id| !port_io.values().any(|io| io == id))
+        .collect();
+    let offset = unit.pos - get_components_rect(&inner, &content).0;
+    let replaced = HashSet::from([id]);
+    let fits = content.iter().all(|inner_id| {
+        let comp = inner.get_component(inner_id).unwrap();
+        db.is_available_location_except(
+            comp.get_position() + offset,
+            comp.get_dimension(),
+            id,
+            &replaced,
+        )
+    });
+    if !fits {
+        return None;
+    }
+    // Outer pins by port index:
+    let mut outer_pins: BTreeMap<Id, Vec<GridDBConnectionPoint>> = BTreeMap::new();
+    for net_id in db.get_connected_nets(&id) {
+        let net = db.get_net(&net_id).unwrap();
+        let (port, outer) = match net.start_point.component_id == id {
+            true => (net.start_point, net.end_point),
+            false => (net.end_point, net.start_point),
+        };
+        outer_pins
+            .entry(port.connection_id)
+            .or_default()
+            .push(outer);
+    }
+
+    let mut ids = HashMap::new();
+    let mut components = vec![];
+    for inner_id in &content {
+        let mut comp = inner.get_component(inner_id).unwrap().clone();
+        comp.set_pos(comp.get_position() + offset);
+        let new_id = db.allocate_component();
+        ids.insert(*inner_id, new_id);
+        components.push((new_id, comp));
+    }
+    let moved = |p: &GridDBConnectionPoint| point(ids[&p.component_id], p.connection_id);
+    let mut nets = vec![];
+    for net_id in inner.get_net_ids() {
+        let mut net = inner.get_net(&net_id).unwrap().clone();
+        if !ids.contains_key(&net.start_point.component_id)
+            || !ids.contains_key(&net.end_point.component_id)
+        {
+            continue;
+        }
+        net.start_point = moved(&net.start_point);
+        net.end_point = moved(&net.end_point);
+        for p in net.points.iter_mut() {
+            *p += offset;
+        }
+        nets.push((db.allocate_net(), net));
+    }
+    let dock_cells: HashMap<GridDBConnectionPoint, GridPos> = components
+        .iter()
+        .flat_map(|(id, comp)| {
+            (0..comp.get_connections_number())
+                .filter_map(|i| Some((point(*id, i), comp.get_connection_dock_cell(i)?)))
+        })
+        .collect();
+    for (port, pins) in outer_pins {
+        let Some(io) = port_io.get(&port) else {
+            continue;
+        };
+        for inner_pin in get_io_loads(&inner, *io) {
+            let Some(inner_pin) = ids
+                .contains_key(&inner_pin.component_id)
+                .then(|| moved(&inner_pin))
+            else {
+                continue;
+            };
+            for outer in &pins {
+                let Some(outer_cell) = get_dock_cell(db, outer) else {
+                    continue;
+                };
+                let net = db.route_net(*outer, outer_cell, inner_pin, dock_cells[&inner_pin]);
+                nets.push((db.allocate_net(), net));
+            }
+        }
+    }
+    Some(AllocatedFragment { components, nets })
+}
+
+/// Difference between the ports of the unit symbol and the inputs and outputs of its schematic
+#[derive(Clone, PartialEq, Debug)]
+pub enum PortMismatch {
+    /// Input or output of the schematic without a port
+    Missing { name: String, is_output: bool },
+    /// Port without an input or output of the schematic, by port index
+    Extra(Id),
+    /// Port on the side of the other direction: inputs are on the left and outputs on the right
+    WrongSide(Id),
+}
+
+/// Names of the inputs and outputs of the schematic and whether they are outputs,
+/// from top to bottom
+fn get_schematic_io(inner: &GridDB) -> Vec<(String, bool)> {
+    let mut io: Vec<_> = inner
+        .get_component_ids()
+        .into_iter()
+        .filter_map(|id| match inner.get_component(&id) {
+            Some(Component::Primitive(p))
+                if matches!(p.typ, PrimitiveType::Input | PrimitiveType::Output) =>
+            {
+                Some((p.pos, p.name.to_string(), p.typ == PrimitiveType::Output))
+            }
+            _ => None,
+        })
+        .collect();
+    io.sort_by_key(|(pos, _, _)| (pos.y, pos.x));
+    io.into_iter()
+        .map(|(_, name, is_output)| (name, is_output))
+        .collect()
+}
+
+/// Compares the ports of the hierarchical unit with its schematic by name, direction and count.
+/// Ports on the top and bottom sides may have any direction. Empty if the unit has no schematic.
+pub fn check_unit_ports(unit: &Unit) -> Vec<PortMismatch> {
+    let Some(schematic) = &unit.schematic else {
+        return vec![];
+    };
+    let mut io = get_schematic_io(&GridDB::from_schematic(schematic));
+    let mut result = vec![];
+    for (i, port) in unit.ports.iter().enumerate() {
+        let Some(j) = io.iter().position(|(name, _)| *name == *port.name) else {
+            result.push(PortMismatch::Extra(i));
+            continue;
+        };
+        let (_, is_output) = io.remove(j);
+        let expected = match is_output {
+            true => Rotation::ROT180,
+            false => Rotation::ROT0,
+        };
+        if matches!(port.align, Rotation::ROT0 | Rotation::ROT180) && port.align != expected {
+            result.push(PortMismatch::WrongSide(i));
+        }
+    }
+    result.extend(
+        io.into_iter()
+            .map(|(name, is_output)| PortMismatch::Missing { name, is_output }),
+    );
+    result
+}
+
+/// Unit with the ports matching its schematic: extra ports are removed, ports of the wrong
+/// direction are moved to their side and missing ports are added below the others.
+/// Also returns the new indices of the changed ports, None for the removed ones.
+/// None if the ports already match.
+pub fn sync_unit_ports(unit: &Unit) -> Option<(Unit, HashMap<Id, Option<Id>>)> {
+    let mismatches = check_unit_ports(unit);
+    if mismatches.is_empty() {
+        return None;
+    }
+    let mut synced = unit.clone();
+    synced.ports.clear();
+    let mut connections = HashMap::new();
+    let mut moved = vec![];
+    for (i, port) in unit.ports.iter().enumerate() {
+        if mismatches.contains(&PortMismatch::Extra(i)) {
+            connections.insert(i, None);
+        } else if mismatches.contains(&PortMismatch::WrongSide(i)) {
+            moved.push(i);
+        } else {
+            if synced.ports.len() != i {
+                connections.insert(i, Some(synced.ports.len()));
+            }
+            synced.ports.push(port.clone());
+        }
+    }
+    let last_row = |align| {
+        synced
+            .ports
+            .iter()
+            .filter(|p| p.align == align)
+            .map(|p| p.offset)
+            .max()
+            .unwrap_or(0)
+    };
+    let (mut left, mut right) = (last_row(Rotation::ROT0), last_row(Rotation::ROT180));
+    let mut new_port = |name: SharedStr, is_output: bool| {
+        let (align, row) = match is_output {
+            true => (Rotation::ROT180, &mut right),
+            false => (Rotation::ROT0, &mut left),
+        };
+        *row += 1;
+        Port {
+            offset: *row,
+            align,
+            name,
+            bundle: None,
+        }
+    };
+    for i in moved {
+        let port = &unit.ports[i];
+        connections.insert(i, Some(synced.ports.len()));
+        synced.ports.push(Port {
+            bundle: port.bundle.clone(),
+            ..new_port(port.name.clone(), port.align == Rotation::ROT0)
+        });
+    }
+    for mismatch in mismatches {
+        if let PortMismatch::Missing { name, is_output } = mismatch {
+            synced.ports.push(new_port(name.as_str().into(), is_output));
+        }
+    }
+    synced.height = synced.height.max(left.max(right) + 1);
+    Some((synced, connections))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PortMismatch, check_unit_ports, extract_unit, flatten_unit, sync_unit_ports};
+    use crate::{
+        grid_db::{AllocatedFragment, Component, GridDB, Id, PrimitiveType, Rotation},
+        netlist::{NetlistItem, extract_netlist, find_hierarchy_path},
+        testing::{GridDBBuilder, half_adder},
+        truth_table::build_truth_table,
+    };
+
+    /// Applies the edit like `InteractionManager::replace_fragment` does
+    fn replace(db: &mut GridDB, removed: &[Id], fragment: AllocatedFragment) {
+        for id in removed {
+            for net in db.get_connected_nets(id) {
+                db.remove_net(&net);
+            }
+            db.remove_component(id);
+        }
+        for (id, comp) in fragment.components {
+            db.insert_component(id, comp);
+        }
+        for (id, net) in fragment.nets {
+            db.insert_net(id, net);
+        }
+        db.check_indices().unwrap();
+    }
+
+    #[test]
+    fn half_adder_extraction() {
+        let mut db = half_adder();
+        // XOR and AND gates:
+        let extraction = extract_unit(&mut db, &[2, 3]).unwrap();
+        let names: Vec<_> = extraction
+            .unit
+            .ports
+            .iter()
+            .map(|p| p.name.to_string())
+            .collect();
+        assert_eq!(
+            names,
+            ["xor2_2_in0", "xor2_2_in1", "xor2_2_out", "and2_3_out"]
+        );
+        assert_eq!(
+            extraction
+                .unit
+                .ports
+                .iter()
+                .map(|p| p.align)
+                .collect::<Vec<_>>(),
+            [
+                Rotation::ROT0,
+                Rotation::ROT0,
+                Rotation::ROT180,
+                Rotation::ROT180
+            ]
+        );
+        let inner = GridDB::from_schematic(extraction.unit.schematic.as_ref().unwrap());
+        let inner_netlist = extract_netlist(&inner);
+        assert_eq!(inner_netlist.instances.len(), 6);
+        // Each input drives both gates, each gate drives an output:
+        assert_eq!(inner.get_net_ids().len(), 6);
+        assert_eq!(extraction.nets.nets.len(), 4);
+
+        let unit = extraction.id;
+        replace(&mut db, &[2, 3], extraction.into_fragment());
+        assert!(matches!(
+            db.get_component(&unit),
+            Some(Component::Unit(unit)) if unit.schematic.is_some()
+        ));
+
+        // Doesn't fit between the neighbours:
+        let mut b = GridDBBuilder::new();
+        let not = b.primitive(PrimitiveType::Not, 0, 0, Rotation::ROT0);
+        let input = b.primitive(PrimitiveType::Input, -3, 0, Rotation::ROT0);
+        b.primitive(PrimitiveType::Output, 0, 1, Rotation::ROT0);
+        b.connect((input, 0), (not, 1));
+        assert!(extract_unit(&mut b.build(), &[not]).is_none());
+    }
+
+    #[test]
+    fn hierarchical_paths() {
+        let mut db = half_adder();
+        let extraction = extract_unit(&mut db, &[2, 3]).unwrap();
+        let unit = extraction.id;
+        replace(&mut db, &[2, 3], extraction.into_fragment());
+        let unit_name = extract_netlist(&db)
+            .get_instance(unit)
+            .unwrap()
+            .name
+            .clone();
+        let unit_pos = db.get_component(&unit).unwrap().get_position();
+
+        let item = find_hierarchy_path(&db, &format!("top.{unit_name}.xor2_2")).unwrap();
+        assert_eq!(item.units, [unit]);
+        assert!(matches!(item.item, NetlistItem::Instance(_)));
+        assert_eq!(item.pos, unit_pos);
+        let item = find_hierarchy_path(&db, &format!("top.{unit_name}")).unwrap();
+        assert!(item.units.is_empty());
+        assert_eq!(item.item, NetlistItem::Instance(unit));
+        assert!(find_hierarchy_path(&db, "top.xor2_2").is_none());
+        assert!(find_hierarchy_path(&db, &format!("top.{unit_name}.missing")).is_none());
+    }
+
+    #[test]
+    fn flattening_restores_logic() {
+        let mut db = half_adder();
+        let [inputs, outputs] = [[0, 1], [4, 5]];
+        let expected = build_truth_table(&db, &inputs, &outputs).unwrap().rows;
+        let extraction = extract_unit(&mut db, &[2, 3]).unwrap();
+        let unit = extraction.id;
+        replace(&mut db, &[2, 3], extraction.into_fragment());
+        assert!(flatten_unit(&mut db, 0).is_none());
+
+        let fragment = flatten_unit(&mut db, unit).unwrap();
+        assert_eq!(fragment.components.len(), 2);
+        replace(&mut db, &[unit], fragment);
+        assert_eq!(db.get_component_ids().len(), 7);
+        let table = build_truth_table(&db, &inputs, &outputs).unwrap();
+        assert_eq!(table.rows, expected);
+    }
+
+    #[test]
+    fn port_sync() {
+        let mut db = half_adder();
+        let mut unit = extract_unit(&mut db, &[2, 3]).unwrap().unit;
+        assert!(check_unit_ports(&unit).is_empty());
+        assert!(sync_unit_ports(&unit).is_none());
+        // Ports: xor2_2_in0, xor2_2_in1, xor2_2_out, and2_3_out
+        unit.ports.remove(0);
+        unit.ports[0].name = "renamed".into();
+        unit.ports[1].align = Rotation::ROT0;
+        assert_eq!(
+            check_unit_ports(&unit),
+            [
+                PortMismatch::Extra(0),
+                PortMismatch::WrongSide(1),
+                PortMismatch::Missing {
+                    name: "xor2_2_in0".into(),
+                    is_output: false
+                },
+                PortMismatch::Missing {
+                    name: "xor2_2_in1".into(),
+                    is_output: false
+                },
+            ]
+        );
+
+        let (synced, connections) = sync_unit_ports(&unit).unwrap();
+        assert!(check_unit_ports(&synced).is_empty());
+        let ports: Vec<_> = synced
+            .ports
+            .iter()
+            .map(|p| (p.name.to_string(), p.align, p.offset))
+            .collect();
+        assert_eq!(
+            ports,
+            [
+                ("and2_3_out".into(), Rotation::ROT180, 2),
+                ("xor2_2_out".into(), Rotation::ROT180, 3),
+                ("xor2_2_in0".into(), Rotation::ROT0, 1),
+                ("xor2_2_in1".into(), Rotation::ROT0, 2),
+            ]
+        );
+        assert_eq!(synced.height, 4);
+        let mut connections: Vec<_> = connections.into_iter().collect();
+        connections.sort();
+        assert_eq!(connections, [(0, None), (1, Some(1)), (2, Some(0))]);
+    }
+}
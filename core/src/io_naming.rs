@@ -0,0 +1,204 @@
+//! Naming the unnamed inputs and outputs of the schematic before they get into the netlist
+
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    grid_db::{Component, GridDB, GridDBConnectionPoint, Id, PrimitiveType},
+    netlist::{Netlist, extract_netlist},
+    rename::order_by_position,
+};
+
+/// Row of the naming table
+#[derive(Clone, PartialEq, Debug)]
+pub struct IoName {
+    pub id: Id,
+    /// Chosen name, the input or output stays unnamed if it's empty
+    pub name: String,
+}
+
+/// Input or output without a name, it gets a generated one in the netlist
+pub fn is_unnamed_io(comp: &Component) -> bool {
+    matches!(
+        comp,
+        Component::Primitive(p)
+            if matches!(p.typ, PrimitiveType::Input | PrimitiveType::Output) && p.name.is_empty()
+    )
+}
+
+/// Labels of the connected net, then names of the connected unit ports without bus ranges
+fn get_suggestions(db: &GridDB, netlist: &Netlist, id: Id) -> Vec<String> {
+    let Some(net) = netlist.get_net(&GridDBConnectionPoint {
+        component_id: id,
+        connection_id: 0,
+    }) else {
+        return vec![];
+    };
+    let ports = netlist.nets[net]
+        .pins
+        .iter()
+        .filter(|p| matches!(db.get_component(&p.component_id), Some(Component::Unit(_))))
+        .filter_map(|p| {
+            let pin = netlist
+                .get_instance(p.component_id)?
+                .pins
+                .get(p.connection_id)?;
+            Some(
+                pin.name
+                    .split('[')
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_owned(),
+            )
+        });
+    netlist
+        .get_labels(net)
+        .into_iter()
+        .map(str::to_owned)
+        .chain(ports)
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Unnamed inputs and outputs ordered top-to-bottom, then left-to-right.
+/// Names are suggested by the connected net labels and unit ports,
+/// the ones used by other instances are skipped.
+pub fn find_unnamed_io(db: &GridDB) -> Vec<IoName> {
+    let netlist = extract_netlist(db);
+    let mut used: HashSet<String> = netlist.instances.iter().map(|i| i.name.clone()).collect();
+    let ids: Vec<Id> = db
+        .get_component_ids()
+        .into_iter()
+        .filter(|id| db.get_component(id).is_some_and(is_unnamed_io))
+        .collect();
+    order_by_position(db, &ids)
+        .into_iter()
+        .map(|id| {
+            let name = get_suggestions(db, &netlist, id)
+                .into_iter()
+                .find(|name| !used.contains(name))
+                .unwrap_or_default();
+            if !name.is_empty() {
+                used.insert(name.clone());
+            }
+            IoName { id, name }
+        })
+        .collect()
+}
+
+/// Indices of the rows whose names are used twice or by other instances.
+/// Junctions and connectors are not instances, so their names are not taken.
+pub fn find_name_conflicts(db: &GridDB, names: &[IoName]) -> HashSet<usize> {
+    let ids: HashSet<Id> = names.iter().map(|row| row.id).collect();
+    let used: HashSet<String> = db
+        .get_component_ids()
+        .into_iter()
+        .filter(|id| !ids.contains(id))
+        .filter_map(|id| match db.get_component(&id)? {
+            Component::Primitive(p)
+                if matches!(p.typ, PrimitiveType::Point | PrimitiveType::Connector) =>
+            {
+                None
+            }
+            comp => comp.get_instance_name(id),
+        })
+        .collect();
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for row in names {
+        *counts.entry(row.name.trim()).or_default() += 1;
+    }
+    names
+        .iter()
+        .enumerate()
+        .filter(|(_, row)| {
+            let name = row.name.trim();
+            !name.is_empty() && (used.contains(name) || counts[name] > 1)
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// Inputs and outputs with the chosen names, the ones left empty are skipped
+pub fn apply_io_names(db: &GridDB, names: &[IoName]) -> Vec<(Id, Component)> {
+    names
+        .iter()
+        .filter(|row| !row.name.trim().is_empty())
+        .filter_map(|row| {
+            let mut comp = db.get_component(&row.id)?.clone();
+            *comp.get_name_mut()? = row.name.trim().into();
+            Some((row.id, comp))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IoName, apply_io_names, find_name_conflicts, find_unnamed_io};
+    use crate::{
+        grid_db::{Component, GridDB, Port, PrimitiveType, Rotation, Unit, grid_pos},
+        netlist::extract_netlist,
+        testing::GridDBBuilder,
+    };
+
+    fn set_name(db: &mut GridDB, id: usize, name: &str) {
+        let mut comp = db.remove_component(&id).unwrap();
+        *comp.get_name_mut().unwrap() = name.into();
+        db.insert_component(id, comp);
+    }
+
+    #[test]
+    fn io_naming() {
+        let mut b = GridDBBuilder::new();
+        let clk = b.primitive(PrimitiveType::Input, 0, 0, Rotation::ROT0);
+        let connector = b.primitive(PrimitiveType::Connector, 6, 0, Rotation::ROT0);
+        let data = b.primitive(PrimitiveType::Input, 0, 10, Rotation::ROT0);
+        let spare = b.primitive(PrimitiveType::Input, 0, 20, Rotation::ROT0);
+        let named = b.primitive(PrimitiveType::Output, 30, 0, Rotation::ROT0);
+        let unit = b.component(Component::Unit(Unit {
+            pos: grid_pos(10, 8),
+            width: 4,
+            height: 4,
+            ports: vec![Port {
+                offset: 2,
+                align: Rotation::ROT0,
+                name: "data[7:0]".into(),
+                bundle: None,
+            }],
+            parameters: vec![],
+            description: String::new(),
+            name: Default::default(),
+            source: None,
+            schematic: None,
+        }));
+        b.connect((clk, 0), (connector, 0));
+        b.connect((data, 0), (unit, 0));
+        let mut db = b.build();
+        set_name(&mut db, connector, "clk");
+        set_name(&mut db, named, "data");
+
+        let mut names = find_unnamed_io(&db);
+        // The unit port name is used by the output:
+        let row = |id, name: &str| IoName {
+            id,
+            name: name.into(),
+        };
+        assert_eq!(names, [row(clk, "clk"), row(data, ""), row(spare, "")]);
+        names[1].name = "data".into();
+        names[2].name = "clk".into();
+        assert_eq!(find_name_conflicts(&db, &names).len(), 3);
+        names[1].name = " din ".into();
+        names[2].name = String::new();
+        assert!(find_name_conflicts(&db, &names).is_empty());
+
+        let named = apply_io_names(&db, &names);
+        assert_eq!(named.len(), 2);
+        for (id, comp) in named {
+            db.remove_component(&id);
+            db.insert_component(id, comp);
+        }
+        let netlist = extract_netlist(&db);
+        assert!(netlist.find_net("din").is_some());
+        assert_eq!(netlist.get_instance(clk).unwrap().name, "clk");
+        assert_eq!(find_unnamed_io(&db), [row(spare, "")]);
+    }
+}
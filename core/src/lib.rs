@@ -0,0 +1,49 @@
+//! Core of the schematic editor: the schematic database, symbol geometry, exporters
+//! and design checks. It has no UI, the `editor` package is the egui frontend over it.
+//!
+//! Schematics can be generated and analyzed without the UI:
+//!
+//! ```
+//! use editor_core::grid_db::{
+//!     Component, GridDB, PrimitiveComponent, PrimitiveType, Rotation, NetStyle, Theme, grid_pos,
+//! };
+//!
+//! let mut db = GridDB::new();
+//! let id = db.allocate_component();
+//! db.insert_component(
+//!     id,
+//!     Component::Primitive(PrimitiveComponent {
+//!         typ: PrimitiveType::And(2),
+//!         pos: grid_pos(0, 0),
+//!         rotation: Rotation::ROT0,
+//!         name: Default::default(),
+//!         source: None,
+//!     }),
+//! );
+//! let json = db.dump_to_json().unwrap();
+//! let db = GridDB::load_from_json(json).unwrap();
+//! let svg = db.dump_to_svg(Theme::Light, 20.0, &NetStyle::DEFAULT);
+//! assert!(svg.contains("<polygon"));
+//! assert!(editor_core::problems::collect_problems(&db).len() > 0); // Unconnected inputs
+//! ```
+
+pub mod auto_wiring;
+pub mod canvas;
+pub mod datasheet;
+pub mod field_state;
+pub mod grid_db;
+pub mod hierarchy;
+pub mod io_naming;
+pub mod macros;
+pub mod netlist;
+pub mod parallel;
+pub mod problems;
+pub mod rename;
+pub mod search;
+pub mod simulation;
+pub mod templates;
+pub mod truth_table;
+pub mod worker;
+
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
@@ -0,0 +1,329 @@
+//! Recorded sequences of edits replayed at another place of the schematic.
+//! Positions are stored relative to the first edited component or net.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::grid_db::{Component, GridDB, GridPos, Id, Net};
+
+/// Component or net referenced by the macro
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+enum MacroRef {
+    /// Created by the macro
+    New,
+    /// Existed before the macro, found by the position relative to the anchor:
+    /// the component position or the first point of the net
+    Existing(GridPos),
+}
+
+/// New state of the component or net, IDs are local to the macro
+#[derive(Clone, Serialize, Deserialize)]
+enum MacroStep {
+    Component {
+        id: Id,
+        component: Option<Component>,
+    },
+    Net {
+        id: Id,
+        net: Option<Net>,
+    },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Macro {
+    pub name: String,
+    /// Components and nets of the steps by their local IDs
+    components: Vec<MacroRef>,
+    nets: Vec<MacroRef>,
+    steps: Vec<MacroStep>,
+}
+
+/// Edit made by the replayed macro
+pub enum MacroEdit {
+    Component {
+        id: Id,
+        component: Option<Component>,
+    },
+    Net {
+        id: Id,
+        net: Option<Net>,
+    },
+}
+
+/// Collects the edits, they are grouped by undo steps
+#[derive(Default)]
+pub struct MacroRecorder {
+    anchor: Option<GridPos>,
+    component_ids: HashMap<Id, Id>,
+    net_ids: HashMap<Id, Id>,
+    components: Vec<MacroRef>,
+    nets: Vec<MacroRef>,
+    groups: Vec<Vec<MacroStep>>,
+}
+
+impl MacroRecorder {
+    /// Following edits are a new undo step
+    pub fn start_group(&mut self) {
+        self.groups.push(vec![]);
+    }
+
+    /// Forgets the edits of the undone step
+    pub fn pop_group(&mut self) {
+        self.groups.pop();
+    }
+
+    /// Joins the last `n` steps, which are undone together
+    pub fn merge_last_groups(&mut self, n: usize) {
+        let merged = self.groups.split_off(self.groups.len().saturating_sub(n));
+        self.groups.push(merged.into_iter().flatten().collect());
+    }
+
+    fn get_anchor(&mut self, pos: GridPos) -> GridPos {
+        *self.anchor.get_or_insert(pos)
+    }
+
+    fn get_component_id(&mut self, db: &GridDB, id: Id) -> Id {
+        if let Some(local) = self.component_ids.get(&id) {
+            return *local;
+        }
+        let reference = match db.get_component(&id) {
+            Some(comp) => {
+                MacroRef::Existing(comp.get_position() - self.get_anchor(comp.get_position()))
+            }
+            None => MacroRef::New,
+        };
+        self.components.push(reference);
+        self.component_ids.insert(id, self.components.len() - 1);
+        self.components.len() - 1
+    }
+
+    fn get_net_id(&mut self, db: &GridDB, id: Id) -> Id {
+        if let Some(local) = self.net_ids.get(&id) {
+            return *local;
+        }
+        let reference = match db.get_net(&id).and_then(|net| net.points.first()) {
+            Some(pos) => MacroRef::Existing(*pos - self.get_anchor(*pos)),
+            None => MacroRef::New,
+        };
+        self.nets.push(reference);
+        self.net_ids.insert(id, self.nets.len() - 1);
+        self.nets.len() - 1
+    }
+
+    fn push(&mut self, step: MacroStep) {
+        match self.groups.last_mut() {
+            Some(group) => group.push(step),
+            None => self.groups.push(vec![step]),
+        }
+    }
+
+    /// Records the new state of the component, before it is applied to `db`
+    pub fn record_component(&mut self, db: &GridDB, id: Id, new: Option<&Component>) {
+        if let Some(new) = new {
+            self.get_anchor(new.get_position());
+        }
+        let id = self.get_component_id(db, id);
+        let component = new.map(|comp| {
+            let mut comp = comp.clone();
+            comp.set_pos(comp.get_position() - self.anchor.unwrap());
+            comp
+        });
+        self.push(MacroStep::Component { id, component });
+    }
+
+    /// Records the new state of the net, before it is applied to `db`
+    pub fn record_net(&mut self, db: &GridDB, id: Id, new: Option<&Net>) {
+        if let Some(pos) = new.and_then(|net| net.points.first()) {
+            self.get_anchor(*pos);
+        }
+        let id = self.get_net_id(db, id);
+        let net = new.map(|net| {
+            let mut net = net.clone();
+            for point in [&mut net.start_point, &mut net.end_point] {
+                point.component_id = self.get_component_id(db, point.component_id);
+            }
+            let anchor = self.anchor.unwrap();
+            for p in net.points.iter_mut() {
+                *p = *p - anchor;
+            }
+            net
+        });
+        self.push(MacroStep::Net { id, net });
+    }
+
+    /// None if nothing was recorded
+    pub fn finish(self, name: &str) -> Option<Macro> {
+        let steps: Vec<MacroStep> = self.groups.into_iter().flatten().collect();
+        (!steps.is_empty()).then(|| Macro {
+            name: name.to_owned(),
+            components: self.components,
+            nets: self.nets,
+            steps,
+        })
+    }
+}
+
+impl Macro {
+    /// Edits of the macro replayed at `anchor`. New components and nets get IDs allocated in `db`,
+    /// steps of the existing ones not found at their places are skipped.
+    /// None if a component doesn't fit.
+    pub fn replay(&self, db: &mut GridDB, anchor: GridPos) -> Option<Vec<MacroEdit>> {
+        let find_component = |db: &GridDB, pos: GridPos| {
+            db.get_component_ids()
+                .into_iter()
+                .find(|id| db.get_component(id).unwrap().get_position() == anchor + pos)
+        };
+        let find_net = |db: &GridDB, pos: GridPos| {
+            db.get_net_ids()
+                .into_iter()
+                .find(|id| db.get_net(id).unwrap().points.first() == Some(&(anchor + pos)))
+        };
+        let component_ids: Vec<Option<Id>> = self
+            .components
+            .iter()
+            .map(|r| match r {
+                MacroRef::New => Some(db.allocate_component()),
+                MacroRef::Existing(pos) => find_component(db, *pos),
+            })
+            .collect();
+        let net_ids: Vec<Option<Id>> = self
+            .nets
+            .iter()
+            .map(|r| match r {
+                MacroRef::New => Some(db.allocate_net()),
+                MacroRef::Existing(pos) => find_net(db, *pos),
+            })
+            .collect();
+        let mut edits = vec![];
+        for step in &self.steps {
+            match step {
+                MacroStep::Component { id, component } => {
+                    let Some(id) = component_ids[*id] else {
+                        continue;
+                    };
+                    let component = component.clone().map(|mut comp| {
+                        comp.set_pos(comp.get_position() + anchor);
+                        comp
+                    });
+                    if let Some(comp) = &component {
+                        let (pos, dim) = (comp.get_position(), comp.get_dimension());
+                        let fits = match db.get_component(&id) {
+                            Some(_) => db.is_available_location(pos, dim, id),
+                            None => db.is_free_location(pos, dim, comp.is_overlap_only()),
+                        };
+                        if !fits {
+                            return None;
+                        }
+                    }
+                    edits.push(MacroEdit::Component { id, component });
+                }
+                MacroStep::Net { id, net } => {
+                    let Some(id) = net_ids[*id] else {
+                        continue;
+                    };
+                    let net = match net.clone() {
+                        Some(mut net) => {
+                            let (Some(start), Some(end)) = (
+                                component_ids[net.start_point.component_id],
+                                component_ids[net.end_point.component_id],
+                            ) else {
+                                continue;
+                            };
+                            net.start_point.component_id = start;
+                            net.end_point.component_id = end;
+                            for p in net.points.iter_mut() {
+                                *p += anchor;
+                            }
+                            Some(net)
+                        }
+                        None => None,
+                    };
+                    edits.push(MacroEdit::Net { id, net });
+                }
+            }
+        }
+        Some(edits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Macro, MacroEdit, MacroRecorder};
+    use crate::{
+        grid_db::{Component, GridDB, PrimitiveType, Rotation, grid_pos},
+        testing::{GridDBBuilder, json_snapshot},
+    };
+
+    fn apply(db: &mut GridDB, edits: Vec<MacroEdit>) {
+        for edit in edits {
+            match edit {
+                MacroEdit::Component { id, component } => {
+                    db.remove_component(&id);
+                    if let Some(component) = component {
+                        db.insert_component(id, component);
+                    }
+                }
+                MacroEdit::Net { id, net } => {
+                    db.remove_net(&id);
+                    if let Some(net) = net {
+                        db.insert_net(id, net);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn macro_replay() {
+        // Inverter wired to the existing input and a removed text:
+        let mut b = GridDBBuilder::new();
+        let input = b.primitive(PrimitiveType::Input, 0, 0, Rotation::ROT0);
+        let text = b.text("old", 0, 4);
+        let other_input = b.primitive(PrimitiveType::Input, 20, 20, Rotation::ROT0);
+        let other_text = b.text("old", 20, 24);
+        let mut db = b.build();
+        let mut fragment = GridDBBuilder::new();
+        let source = fragment.primitive(PrimitiveType::Input, 0, 0, Rotation::ROT0);
+        let not = fragment.primitive(PrimitiveType::Not, 6, 0, Rotation::ROT0);
+        fragment.connect((source, 0), (not, 0));
+        let fragment = fragment.build();
+
+        let mut recorder = MacroRecorder::default();
+        recorder.start_group();
+        let not_id = db.allocate_component();
+        let not_comp = fragment.get_component(&not).unwrap().clone();
+        recorder.record_component(&db, input, db.get_component(&input));
+        recorder.record_component(&db, not_id, Some(&not_comp));
+        db.insert_component(not_id, not_comp);
+        let mut net = fragment.get_net(&0).unwrap().clone();
+        net.start_point.component_id = input;
+        net.end_point.component_id = not_id;
+        let net_id = db.allocate_net();
+        recorder.record_net(&db, net_id, Some(&net));
+        db.insert_net(net_id, net);
+        recorder.start_group();
+        recorder.record_component(&db, text, None);
+        db.remove_component(&text);
+        let recorded = recorder.finish("inverter").unwrap();
+        let json = serde_json::to_string(&recorded).unwrap();
+        let recorded: Macro = serde_json::from_str(&json).unwrap();
+
+        let edits = recorded.replay(&mut db, grid_pos(20, 20)).unwrap();
+        assert_eq!(edits.len(), 4);
+        apply(&mut db, edits);
+        db.check_indices().unwrap();
+        assert!(db.get_component(&other_text).is_none());
+        let nets: Vec<_> = db.get_connected_nets(&other_input).into_iter().collect();
+        let net = db.get_net(&nets[0]).unwrap();
+        assert!(matches!(
+            db.get_component(&net.end_point.component_id),
+            Some(Component::Primitive(p)) if p.typ == PrimitiveType::Not
+                && p.pos == grid_pos(26, 20)
+        ));
+        // The new inverter doesn't fit over the replayed one:
+        let before = json_snapshot(&db);
+        assert!(recorded.replay(&mut db, grid_pos(20, 20)).is_none());
+        assert_eq!(before, json_snapshot(&db));
+    }
+}
@@ -0,0 +1,777 @@
+//! Connectivity of the schematic: named nets, component instances and their pin maps.
+//! Simulation, design checks and exporters read connectivity from the netlist
+//! instead of walking wires of the [`GridDB`].
+
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use serde_with::serde_as;
+
+use crate::grid_db::{
+    Change, Component, GridDB, GridDBConnectionPoint, GridPos, Id, Parameter, PrimitiveType,
+    Revision,
+};
+
+/// Index of a net in [`Netlist::nets`]
+pub type NetIndex = usize;
+
+/// Name of the top level schematic in hierarchical names
+pub const TOP_SCOPE: &str = "top";
+
+/// Instance or net found by the hierarchical name
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NetlistItem {
+    Instance(Id),
+    Net(NetIndex),
+}
+
+/// Instance or net of the unit schematics found by the hierarchical name
+#[derive(Clone, Debug, PartialEq)]
+pub struct HierarchyItem {
+    /// Units from the top schematic down to the one containing the item, empty for the top items
+    pub units: Vec<Id>,
+    pub item: NetlistItem,
+    /// Cell of the top schematic to focus on: the item itself or the top unit containing it
+    pub pos: GridPos,
+}
+
+/// Electrically connected pins
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NetlistNet {
+    pub name: String,
+    /// Wires (nets of the `GridDB`) forming the net, sorted
+    pub wires: Vec<Id>,
+    /// Pins of the instances, sorted. Junctions are not included.
+    pub pins: Vec<GridDBConnectionPoint>,
+    junctions: Vec<GridDBConnectionPoint>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InstancePin {
+    pub name: String,
+    /// True for inputs and false for outputs, None if direction is unknown
+    pub is_input: Option<bool>,
+    /// None if the pin is unconnected
+    pub net: Option<NetIndex>,
+}
+
+/// Component with pins, indexed by connection ID
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Instance {
+    pub id: Id,
+    /// Name given by the user or generated one like `and2_5`
+    pub name: String,
+    pub type_name: String,
+    pub pins: Vec<InstancePin>,
+    /// Generic parameters of units, passed to exporters
+    pub parameters: Vec<Parameter>,
+}
+
+// Sent from the Web Worker as JSON, so the maps are stored as lists of pairs
+#[serde_as]
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Netlist {
+    pub nets: Vec<NetlistNet>,
+    /// Instances sorted by ID, text fields and junctions are not included
+    pub instances: Vec<Instance>,
+    /// Pins without wires and touching pins, including unconnected junctions
+    pub unconnected: Vec<GridDBConnectionPoint>,
+    #[serde_as(as = "Vec<(_, _)>")]
+    instance_index: HashMap<Id, usize>,
+    // Including junctions
+    #[serde_as(as = "Vec<(_, _)>")]
+    point_net: HashMap<GridDBConnectionPoint, NetIndex>,
+    #[serde_as(as = "Vec<(_, _)>")]
+    wire_net: HashMap<Id, NetIndex>,
+    // Named connectors by the signal name and their names
+    connectors: HashMap<String, BTreeSet<Id>>,
+    connector_signals: HashMap<Id, String>,
+}
+
+impl Netlist {
+    pub fn get_instance(&self, id: Id) -> Option<&Instance> {
+        self.instances.get(*self.instance_index.get(&id)?)
+    }
+
+    /// Net connected to the pin
+    pub fn get_net(&self, point: &GridDBConnectionPoint) -> Option<NetIndex> {
+        self.point_net.get(point).copied()
+    }
+
+    /// Net formed by the wire (net of the `GridDB`)
+    pub fn get_wire_net(&self, wire_id: Id) -> Option<NetIndex> {
+        self.wire_net.get(&wire_id).copied()
+    }
+
+    pub fn find_net(&self, name: &str) -> Option<NetIndex> {
+        self.nets.iter().position(|net| net.name == name)
+    }
+
+    /// Hierarchical name of the instance like `top.u_alu.and2_5`,
+    /// `scope` is the hierarchical name of the schematic containing the netlist
+    pub fn get_instance_path(&self, scope: &str, id: Id) -> Option<String> {
+        Some(format!("{scope}.{}", self.get_instance(id)?.name))
+    }
+
+    /// Hierarchical name of the net like `top.u_alu.n3`
+    pub fn get_net_path(&self, scope: &str, net: NetIndex) -> String {
+        format!("{scope}.{}", self.nets[net].name)
+    }
+
+    /// Finds the instance or the net by the hierarchical name, used for cross-probing
+    pub fn find_path(&self, scope: &str, path: &str) -> Option<NetlistItem> {
+        let name = path.strip_prefix(scope)?.strip_prefix('.')?;
+        if let Some(inst) = self.instances.iter().find(|inst| inst.name == name) {
+            return Some(NetlistItem::Instance(inst.id));
+        }
+        self.find_net(name).map(NetlistItem::Net)
+    }
+
+    /// Connectors of the signal, sorted by ID
+    pub fn get_connectors(&self, signal: &str) -> Vec<Id> {
+        self.connectors
+            .get(signal)
+            .map_or(vec![], |ids| ids.iter().copied().collect())
+    }
+
+    /// Signal names of the connectors on the net, sorted
+    pub fn get_labels(&self, net: NetIndex) -> Vec<&str> {
+        let mut labels: Vec<&str> = self.nets[net]
+            .junctions
+            .iter()
+            .filter_map(|p| self.connector_signals.get(&p.component_id))
+            .map(String::as_str)
+            .collect();
+        labels.sort_unstable();
+        labels.dedup();
+        labels
+    }
+
+    fn get_pin(&self, point: &GridDBConnectionPoint) -> Option<&InstancePin> {
+        self.get_instance(point.component_id)?
+            .pins
+            .get(point.connection_id)
+    }
+
+    /// Output pins connected to the net
+    pub fn get_drivers(&self, net: NetIndex) -> Vec<GridDBConnectionPoint> {
+        self.nets[net]
+            .pins
+            .iter()
+            .filter(|p| {
+                self.get_pin(p)
+                    .is_some_and(|pin| pin.is_input == Some(false))
+            })
+            .copied()
+            .collect()
+    }
+
+    /// Input pins connected to the net
+    pub fn get_loads(&self, net: NetIndex) -> Vec<GridDBConnectionPoint> {
+        self.nets[net]
+            .pins
+            .iter()
+            .filter(|p| {
+                self.get_pin(p)
+                    .is_some_and(|pin| pin.is_input == Some(true))
+            })
+            .copied()
+            .collect()
+    }
+}
+
+fn point(component_id: Id, connection_id: Id) -> GridDBConnectionPoint {
+    GridDBConnectionPoint {
+        component_id,
+        connection_id,
+    }
+}
+
+/// Junctions and connectors join wires, they are not instances
+fn is_junction(component: &Component) -> bool {
+    matches!(
+        component,
+        Component::Primitive(p) if matches!(p.typ, PrimitiveType::Point | PrimitiveType::Connector)
+    )
+}
+
+/// Signal name of the off-sheet connector, None for other components and unnamed connectors
+pub fn get_connector_signal(component: &Component) -> Option<&str> {
+    match component {
+        Component::Primitive(p) if p.typ == PrimitiveType::Connector && !p.name.is_empty() => {
+            Some(&p.name)
+        }
+        _ => None,
+    }
+}
+
+fn is_existing_point(db: &GridDB, p: &GridDBConnectionPoint) -> bool {
+    db.get_component(&p.component_id)
+        .is_some_and(|comp| p.connection_id < comp.get_connections_number())
+}
+
+/// Name of the net: I/O names for nets with inputs or outputs of the schematic, `n<index>` otherwise.
+/// Unnamed inputs and outputs give names like `in_<id>`.
+fn get_net_name(db: &GridDB, pins: &[GridDBConnectionPoint], index: NetIndex) -> String {
+    for p in pins {
+        if let Some(Component::Primitive(prim)) = db.get_component(&p.component_id) {
+            match prim.typ {
+                PrimitiveType::Input | PrimitiveType::Output if !prim.name.is_empty() => {
+                    return prim.name.to_string();
+                }
+                PrimitiveType::Input => return format!("in_{}", p.component_id),
+                PrimitiveType::Output => return format!("out_{}", p.component_id),
+                _ => {}
+            }
+        }
+    }
+    format!("n{index}")
+}
+
+/// Nets are ordered by their first connection points
+fn get_sort_key(net: &NetlistNet) -> Option<(Id, Id)> {
+    net.pins
+        .iter()
+        .chain(&net.junctions)
+        .map(|p| (p.component_id, p.connection_id))
+        .min()
+}
+
+fn get_instance(db: &GridDB, id: Id) -> Option<Instance> {
+    let comp = db.get_component(&id)?;
+    if matches!(comp, Component::TextField(_)) || is_junction(comp) {
+        return None;
+    }
+    let pins = (0..comp.get_connections_number())
+        .map(|connection_id| InstancePin {
+            name: comp.get_connection_name(connection_id).unwrap_or_default(),
+            is_input: comp.is_input_connection(connection_id),
+            net: None,
+        })
+        .collect();
+    let parameters = match comp {
+        Component::Unit(unit) => unit.parameters.clone(),
+        _ => vec![],
+    };
+    let type_name = comp.get_type_name();
+    Some(Instance {
+        id,
+        name: comp.get_instance_name(id)?,
+        type_name,
+        pins,
+        parameters,
+    })
+}
+
+impl Netlist {
+    /// Updates the netlist after `changes` of `db`.
+    /// Only connectivity around the changed components and nets is traced again.
+    pub fn update(&mut self, db: &GridDB, changes: impl IntoIterator<Item = Change>) {
+        let mut seeds = vec![];
+        let mut removed_nets = HashSet::new();
+        let mut changed_components = HashSet::new();
+        for change in changes {
+            match change {
+                Change::Component(id) => {
+                    if !changed_components.insert(id) {
+                        continue;
+                    }
+                    // Junctions and text fields are not instances and have at most one pin:
+                    let old_pins = self.get_instance(id).map_or(1, |inst| inst.pins.len());
+                    for connection_id in 0..old_pins {
+                        if let Some(net) = self.point_net.get(&point(id, connection_id)) {
+                            removed_nets.insert(*net);
+                        }
+                    }
+                    if let Some(comp) = db.get_component(&id) {
+                        seeds.extend((0..comp.get_connections_number()).map(|i| point(id, i)));
+                    }
+                    self.update_connector(id, db.get_component(&id));
+                }
+                Change::Net(id) => {
+                    if let Some(net) = self.wire_net.get(&id) {
+                        removed_nets.insert(*net);
+                    }
+                    if let Some(net) = db.get_net(&id) {
+                        seeds.extend([net.start_point, net.end_point]);
+                    }
+                }
+            }
+        }
+        for net in &removed_nets {
+            let net = &self.nets[*net];
+            seeds.extend(net.pins.iter().chain(&net.junctions));
+        }
+        seeds.sort_by_key(|p| (p.component_id, p.connection_id));
+        seeds.dedup();
+
+        let mut visited = HashSet::new();
+        let mut new_nets = vec![];
+        let mut new_unconnected = vec![];
+        for seed in seeds {
+            if visited.contains(&seed) || !is_existing_point(db, &seed) {
+                continue;
+            }
+            match self.trace(db, seed, &mut visited, &mut removed_nets) {
+                Some(net) => new_nets.push(net),
+                None => new_unconnected.push(seed),
+            }
+        }
+
+        // Nets:
+        let mut nets: Vec<NetlistNet> = std::mem::take(&mut self.nets)
+            .into_iter()
+            .enumerate()
+            .filter(|(i, _)| !removed_nets.contains(i))
+            .map(|(_, net)| net)
+            .chain(new_nets)
+            .collect();
+        nets.sort_by_key(get_sort_key);
+        self.point_net.clear();
+        self.wire_net.clear();
+        for (index, net) in nets.iter_mut().enumerate() {
+            net.name = get_net_name(db, &net.pins, index);
+            for p in net.pins.iter().chain(&net.junctions) {
+                self.point_net.insert(*p, index);
+            }
+            for wire in &net.wires {
+                self.wire_net.insert(*wire, index);
+            }
+        }
+        self.nets = nets;
+
+        // Unconnected pins:
+        self.unconnected
+            .retain(|p| !changed_components.contains(&p.component_id) && !visited.contains(p));
+        self.unconnected.extend(new_unconnected);
+        self.unconnected
+            .sort_by_key(|p| (p.component_id, p.connection_id));
+
+        // Instances:
+        self.instances
+            .retain(|inst| !changed_components.contains(&inst.id));
+        self.instances.extend(
+            changed_components
+                .iter()
+                .filter_map(|id| get_instance(db, *id)),
+        );
+        self.instances.sort_by_key(|inst| inst.id);
+        self.instance_index.clear();
+        for (index, inst) in self.instances.iter_mut().enumerate() {
+            self.instance_index.insert(inst.id, index);
+            for (connection_id, pin) in inst.pins.iter_mut().enumerate() {
+                pin.net = self.point_net.get(&point(inst.id, connection_id)).copied();
+            }
+        }
+    }
+
+    fn update_connector(&mut self, id: Id, comp: Option<&Component>) {
+        if let Some(signal) = self.connector_signals.remove(&id)
+            && let Some(ids) = self.connectors.get_mut(&signal)
+        {
+            ids.remove(&id);
+            if ids.is_empty() {
+                self.connectors.remove(&signal);
+            }
+        }
+        if let Some(signal) = comp.and_then(get_connector_signal) {
+            self.connectors
+                .entry(signal.to_owned())
+                .or_default()
+                .insert(id);
+            self.connector_signals.insert(id, signal.to_owned());
+        }
+    }
+
+    /// Collects the net containing `seed`, None if the pin is unconnected.
+    /// Previous nets met on the way are added to `removed_nets`.
+    fn trace(
+        &self,
+        db: &GridDB,
+        seed: GridDBConnectionPoint,
+        visited: &mut HashSet<GridDBConnectionPoint>,
+        removed_nets: &mut HashSet<NetIndex>,
+    ) -> Option<NetlistNet> {
+        let mut net = NetlistNet {
+            name: String::new(),
+            wires: vec![],
+            pins: vec![],
+            junctions: vec![],
+        };
+        let mut stack = vec![seed];
+        visited.insert(seed);
+        while let Some(p) = stack.pop() {
+            let comp = db.get_component(&p.component_id).unwrap();
+            if is_junction(comp) {
+                net.junctions.push(p);
+            } else {
+                net.pins.push(p);
+            }
+            if let Some(old_net) = self.point_net.get(&p) {
+                removed_nets.insert(*old_net);
+            }
+            let mut next = vec![];
+            for wire_id in db.get_point_nets(&p) {
+                let wire = db.get_net(&wire_id).unwrap();
+                if is_existing_point(db, &wire.start_point)
+                    && is_existing_point(db, &wire.end_point)
+                {
+                    net.wires.push(wire_id);
+                    next.extend([wire.start_point, wire.end_point]);
+                }
+            }
+            // Pins touching each other:
+            if let Some(cell) = comp.get_connection_dock_cell(p.connection_id) {
+                next.extend(
+                    db.get_cell_connections(&cell)
+                        .filter(|q| q.component_id != p.component_id),
+                );
+            }
+            // Connectors of the same signal:
+            if let Some(ids) = self
+                .connector_signals
+                .get(&p.component_id)
+                .and_then(|signal| self.connectors.get(signal))
+            {
+                next.extend(ids.iter().map(|id| point(*id, 0)));
+            }
+            for q in next {
+                if visited.insert(q) {
+                    stack.push(q);
+                }
+            }
+        }
+        if net.wires.is_empty() && net.pins.len() + net.junctions.len() == 1 {
+            return None;
+        }
+        net.wires.sort_unstable();
+        net.wires.dedup();
+        net.pins.sort_by_key(|p| (p.component_id, p.connection_id));
+        net.junctions
+            .sort_by_key(|p| (p.component_id, p.connection_id));
+        Some(net)
+    }
+}
+
+/// Builds the netlist. Pins are connected by wires, by junction points,
+/// by connectors of the same signal and by touching each other in the same cell.
+pub fn extract_netlist(db: &GridDB) -> Netlist {
+    let mut netlist = Netlist::default();
+    netlist.update(
+        db,
+        db.get_component_ids().into_iter().map(Change::Component),
+    );
+    netlist
+}
+
+/// Cell of the instance or of the first wire of the net
+fn get_item_pos(db: &GridDB, netlist: &Netlist, item: NetlistItem) -> Option<GridPos> {
+    match item {
+        NetlistItem::Instance(id) => Some(db.get_component(&id)?.get_position()),
+        NetlistItem::Net(net) => {
+            let wire = netlist.nets.get(net)?.wires.first()?;
+            db.get_net(wire)?.points.first().copied()
+        }
+    }
+}
+
+/// Looks for the path in the schematic named `scope` and in the schematics of its units,
+/// pushes the units containing the item to `units`
+fn find_in_scope(
+    db: &GridDB,
+    scope: &str,
+    path: &str,
+    units: &mut Vec<Id>,
+) -> Option<(NetlistItem, Option<GridPos>)> {
+    let netlist = extract_netlist(db);
+    if let Some(item) = netlist.find_path(scope, path) {
+        return Some((item, get_item_pos(db, &netlist, item)));
+    }
+    let rest = path.strip_prefix(scope)?.strip_prefix('.')?;
+    for inst in &netlist.instances {
+        if !rest
+            .strip_prefix(&*inst.name)
+            .is_some_and(|r| r.starts_with('.'))
+        {
+            continue;
+        }
+        let Some(Component::Unit(unit)) = db.get_component(&inst.id) else {
+            continue;
+        };
+        let Some(schematic) = &unit.schematic else {
+            continue;
+        };
+        units.push(inst.id);
+        let inner = GridDB::from_schematic(schematic);
+        if let Some((item, _)) =
+            find_in_scope(&inner, &format!("{scope}.{}", inst.name), path, units)
+        {
+            return Some((item, Some(unit.pos)));
+        }
+        units.pop();
+    }
+    None
+}
+
+/// Finds the instance or the net by the hierarchical name like `top.u_alu.and2_5`,
+/// descending into the schematics of the units. Used for cross-probing.
+pub fn find_hierarchy_path(db: &GridDB, path: &str) -> Option<HierarchyItem> {
+    let mut units = vec![];
+    let (item, pos) = find_in_scope(db, TOP_SCOPE, path, &mut units)?;
+    Some(HierarchyItem {
+        units,
+        item,
+        pos: pos?,
+    })
+}
+
+/// Next connector of the same signal after `id` in the order of IDs, used for navigation
+pub fn get_next_connector(db: &GridDB, id: Id) -> Option<Id> {
+    let signal = get_connector_signal(db.get_component(&id)?)?;
+    let ids: Vec<Id> = db
+        .get_component_ids()
+        .into_iter()
+        .filter(|other| db.get_component(other).and_then(get_connector_signal) == Some(signal))
+        .collect();
+    ids.iter()
+        .find(|other| **other > id)
+        .or(ids.first())
+        .copied()
+        .filter(|next| *next != id)
+}
+
+/// Netlist kept up to date with the edits of a database.
+/// After small edits only the affected nets are traced again.
+#[derive(Default)]
+pub struct NetlistCache {
+    netlist: Netlist,
+    revision: Option<Revision>,
+}
+
+impl NetlistCache {
+    /// True if the netlist can't be updated from the change log and is extracted again
+    pub fn needs_extraction(&self, db: &GridDB) -> bool {
+        self.revision.is_none_or(|revision| {
+            revision != db.get_revision() && db.get_changes_since(revision).is_none()
+        })
+    }
+
+    /// Uses the netlist extracted elsewhere, like in the Web Worker, for the `revision`
+    pub fn set(&mut self, netlist: Netlist, revision: Revision) {
+        self.netlist = netlist;
+        self.revision = Some(revision);
+    }
+
+    pub fn get(&mut self, db: &GridDB) -> &Netlist {
+        let revision = db.get_revision();
+        if self.revision != Some(revision) {
+            match self.revision.and_then(|r| db.get_changes_since(r)) {
+                Some(changes) => self.netlist.update(db, changes.iter().copied()),
+                None => self.netlist = extract_netlist(db),
+            }
+            self.revision = Some(revision);
+        }
+        &self.netlist
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        NetlistCache, NetlistItem, TOP_SCOPE, extract_netlist, find_hierarchy_path,
+        get_next_connector,
+    };
+    use crate::{
+        grid_db::{
+            Component, GridDB, GridDBConnectionPoint, Parameter, PrimitiveType, Rotation, Unit,
+            grid_pos,
+        },
+        testing::{GridDBBuilder, Rng, half_adder},
+    };
+
+    fn pin(component_id: usize, connection_id: usize) -> GridDBConnectionPoint {
+        GridDBConnectionPoint {
+            component_id,
+            connection_id,
+        }
+    }
+
+    #[test]
+    fn half_adder_netlist() {
+        let db = half_adder();
+        let netlist = extract_netlist(&db);
+        // a, b, sum and carry:
+        assert_eq!(netlist.nets.len(), 4);
+        assert!(netlist.unconnected.is_empty());
+        let a = netlist.get_net(&pin(0, 0)).unwrap();
+        assert_eq!(netlist.nets[a].name, "in_0");
+        assert_eq!(netlist.get_drivers(a), vec![pin(0, 0)]);
+        assert_eq!(netlist.get_loads(a), vec![pin(2, 1), pin(3, 1)]);
+        let sum = netlist.find_net("out_4").unwrap();
+        assert_eq!(netlist.get_drivers(sum), vec![pin(2, 0)]);
+        // Text field is not an instance:
+        assert_eq!(netlist.instances.len(), 6);
+    }
+
+    #[test]
+    fn junctions_merge_nets() {
+        let mut b = GridDBBuilder::new();
+        let input = b.primitive(PrimitiveType::Input, 0, 0, Rotation::ROT0);
+        let point = b.primitive(PrimitiveType::Point, 6, 0, Rotation::ROT0);
+        let not1 = b.primitive(PrimitiveType::Not, 10, -3, Rotation::ROT0);
+        let not2 = b.primitive(PrimitiveType::Not, 10, 3, Rotation::ROT0);
+        b.connect((input, 0), (point, 0));
+        b.connect((point, 0), (not1, 0));
+        b.connect((point, 0), (not2, 0));
+        let netlist = extract_netlist(&b.build());
+        let net = netlist.get_net(&pin(input, 0)).unwrap();
+        assert_eq!(netlist.nets[net].wires, vec![0, 1, 2]);
+        assert_eq!(
+            netlist.nets[net].pins,
+            vec![pin(input, 0), pin(not1, 0), pin(not2, 0)]
+        );
+        assert!(netlist.get_instance(point).is_none());
+        assert_eq!(netlist.unconnected, vec![pin(not1, 1), pin(not2, 1)]);
+    }
+
+    #[test]
+    fn incremental_update_matches_extraction() {
+        for seed in 1..=20 {
+            let mut rng = Rng::new(seed);
+            let mut db = half_adder();
+            let mut cache = NetlistCache::default();
+            cache.get(&db);
+            for step in 0..50 {
+                let component_ids = db.get_component_ids();
+                let net_ids = db.get_net_ids();
+                match rng.below(5) {
+                    0 => {
+                        let id = db.allocate_component();
+                        db.insert_component(id, rng.component());
+                    }
+                    1 => {
+                        if let Some(net) = rng.net(&db) {
+                            let id = db.allocate_net();
+                            db.insert_net(id, net);
+                        }
+                    }
+                    2 if !component_ids.is_empty() => {
+                        db.remove_component(&rng.pick(&component_ids));
+                    }
+                    3 if !component_ids.is_empty() => {
+                        // Move, nets stay attached:
+                        let id = rng.pick(&component_ids);
+                        let mut comp = db.remove_component(&id).unwrap();
+                        comp.set_pos(grid_pos(rng.range(-30, 30), rng.range(-30, 30)));
+                        db.insert_component(id, comp);
+                    }
+                    _ if !net_ids.is_empty() => {
+                        db.remove_net(&rng.pick(&net_ids));
+                    }
+                    _ => {}
+                }
+                assert!(
+                    *cache.get(&db) == extract_netlist(&db),
+                    "seed {seed}, step {step}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unit_parameters() {
+        let mut b = GridDBBuilder::new();
+        let parameter = |name: &str, value: &str| Parameter {
+            name: name.into(),
+            value: value.into(),
+        };
+        let parameters = vec![
+            parameter("WIDTH", "8"),
+            parameter("INIT", "0xFF"),
+            parameter("MASK", "4'b1010"),
+            parameter("NAME", "\"fifo\""),
+        ];
+        let unit = b.component(Component::Unit(Unit {
+            pos: grid_pos(0, 0),
+            width: 4,
+            height: 4,
+            ports: vec![],
+            parameters: parameters.clone(),
+            description: String::new(),
+            name: Default::default(),
+            source: None,
+            schematic: None,
+        }));
+        let netlist = extract_netlist(&b.build());
+        let instance = netlist.get_instance(unit).unwrap();
+        assert_eq!(instance.parameters, parameters);
+        let numbers: Vec<_> = parameters.iter().map(Parameter::get_number).collect();
+        assert_eq!(numbers, [Some(8), Some(0xFF), Some(0b1010), None]);
+    }
+
+    #[test]
+    fn hierarchical_names() {
+        let netlist = extract_netlist(&half_adder());
+        let xor = netlist.get_instance_path(TOP_SCOPE, 2).unwrap();
+        assert_eq!(xor, "top.xor2_2");
+        assert_eq!(
+            netlist.find_path(TOP_SCOPE, &xor),
+            Some(NetlistItem::Instance(2))
+        );
+        let sum = netlist.find_net("out_4").unwrap();
+        assert_eq!(netlist.get_net_path(TOP_SCOPE, sum), "top.out_4");
+        assert_eq!(
+            netlist.find_path(TOP_SCOPE, "top.out_4"),
+            Some(NetlistItem::Net(sum))
+        );
+        assert_eq!(netlist.find_path(TOP_SCOPE, "out_4"), None);
+    }
+
+    #[test]
+    fn hierarchy_path_position() {
+        let db = half_adder();
+        let found = find_hierarchy_path(&db, "top.xor2_2").unwrap();
+        assert!(found.units.is_empty());
+        assert_eq!(found.item, NetlistItem::Instance(2));
+        assert_eq!(found.pos, db.get_component(&2).unwrap().get_position());
+        assert_eq!(find_hierarchy_path(&db, "top.missing"), None);
+    }
+
+    #[test]
+    fn connectors_merge_nets() {
+        let mut b = GridDBBuilder::new();
+        let input = b.primitive(PrimitiveType::Input, 0, 0, Rotation::ROT0);
+        let out_connector = b.primitive(PrimitiveType::Connector, 6, 0, Rotation::ROT0);
+        let in_connector = b.primitive(PrimitiveType::Connector, 0, 10, Rotation::ROT180);
+        let not = b.primitive(PrimitiveType::Not, 6, 9, Rotation::ROT0);
+        b.connect((input, 0), (out_connector, 0));
+        b.connect((in_connector, 0), (not, 0));
+        let mut db = b.build();
+        let mut cache = NetlistCache::default();
+        let set_signal = |db: &mut GridDB, id, signal: &str| {
+            let mut comp = db.remove_component(&id).unwrap();
+            *comp.get_name_mut().unwrap() = signal.into();
+            db.insert_component(id, comp);
+        };
+        set_signal(&mut db, out_connector, "data");
+        set_signal(&mut db, in_connector, "data");
+        let netlist = cache.get(&db);
+        let net = netlist.get_net(&pin(input, 0)).unwrap();
+        assert_eq!(netlist.nets[net].pins, [pin(input, 0), pin(not, 0)]);
+        assert_eq!(
+            netlist.get_connectors("data"),
+            [out_connector, in_connector]
+        );
+        assert!(netlist.get_instance(in_connector).is_none());
+        assert_eq!(get_next_connector(&db, in_connector), Some(out_connector));
+
+        set_signal(&mut db, in_connector, "ctrl");
+        let netlist = cache.get(&db);
+        assert_ne!(
+            netlist.get_net(&pin(input, 0)),
+            netlist.get_net(&pin(not, 0))
+        );
+        assert_eq!(*netlist, extract_netlist(&db));
+        assert_eq!(get_next_connector(&db, in_connector), None);
+    }
+}
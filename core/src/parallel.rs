@@ -0,0 +1,33 @@
+//! Data-parallel helpers: rayon on native with the `parallel` feature, sequential otherwise
+
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+use rayon::prelude::*;
+
+/// Maps every item, keeping the order of the results
+pub fn map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync + Send) -> Vec<R> {
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    return items.par_iter().map(f).collect();
+    #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+    return items.iter().map(f).collect();
+}
+
+/// Maps every item to several results, keeping the order
+pub fn flat_map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> Vec<R> + Sync + Send) -> Vec<R> {
+    map(items, f).into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn order_is_kept() {
+        let items: Vec<usize> = (0..10_000).collect();
+        assert_eq!(
+            super::map(&items, |i| i * 2),
+            (0..20_000).step_by(2).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            super::flat_map(&items[..3], |i| vec![*i; *i]),
+            vec![1, 2, 2]
+        );
+    }
+}
@@ -0,0 +1,517 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::{
+    grid_db::{
+        Component, GridDB, GridPos, Id, Net, PrimitiveType, Severity, grid_pos, grid_rect,
+        segment_cells, simplify_path,
+    },
+    hierarchy::{PortMismatch, check_unit_ports},
+    netlist::{Netlist, TOP_SCOPE, extract_netlist, get_connector_signal},
+    parallel,
+};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ProblemKind {
+    UnconnectedPin,
+    /// Net runs along a net of another signal, so they look connected
+    NetOverlap,
+    /// Net runs through the body of a component
+    NetThroughComponent,
+    /// Off-sheet connector without a name or without other connectors of its signal
+    UnmatchedConnector,
+    /// Net runs through a keep-out region
+    NetThroughKeepOut,
+    /// Net of the loaded file to a missing component or pin, it was dropped
+    DanglingNet,
+    /// Component referred by the editor, but missing in the schematic
+    MissingComponent,
+    /// Input or output of the unit schematic without a port on the symbol
+    MissingUnitPort,
+    /// Port of the unit symbol without an input or output in the schematic
+    ExtraUnitPort,
+    /// Port of the unit symbol on the side of the other direction
+    UnitPortSide,
+}
+
+pub struct Problem {
+    pub kind: ProblemKind,
+    pub pos: GridPos,
+    pub description: String,
+    /// Net, which can be rerouted to fix the problem
+    pub net_id: Option<Id>,
+    /// Unit, whose ports can be synced with its schematic to fix the problem
+    pub unit_id: Option<Id>,
+    /// Set by the class of the net, problems of ignored classes are not reported
+    pub severity: Severity,
+}
+
+/// Checks the schematic and returns found problems
+pub fn collect_problems(db: &GridDB) -> Vec<Problem> {
+    collect_netlist_problems(db, &extract_netlist(db))
+}
+
+/// Checks the schematic with already extracted `netlist`
+pub fn collect_netlist_problems(db: &GridDB, netlist: &Netlist) -> Vec<Problem> {
+    let mut result: Vec<Problem> = db
+        .get_dangling_nets()
+        .iter()
+        .map(|net| Problem {
+            kind: ProblemKind::DanglingNet,
+            pos: net.pos,
+            description: format!("#{}", net.id),
+            net_id: None,
+            unit_id: None,
+            severity: Severity::Error,
+        })
+        .collect();
+    for id in db.get_missing_components() {
+        result.push(Problem {
+            kind: ProblemKind::MissingComponent,
+            pos: grid_pos(0, 0),
+            description: format!("#{id}"),
+            net_id: None,
+            unit_id: None,
+            severity: Severity::Error,
+        });
+    }
+    for point in netlist.unconnected.iter().copied() {
+        let Some(comp) = db.get_component(&point.component_id) else {
+            continue;
+        };
+        let pin_name = comp
+            .get_connection_name(point.connection_id)
+            .filter(|name| !name.is_empty())
+            .unwrap_or_else(|| format!("#{}", point.connection_id));
+        result.push(Problem {
+            kind: ProblemKind::UnconnectedPin,
+            pos: comp
+                .get_connection_dock_cell(point.connection_id)
+                .unwrap_or(comp.get_position()),
+            description: format!(
+                "{}: {}",
+                netlist
+                    .get_instance_path(TOP_SCOPE, point.component_id)
+                    .unwrap_or_else(|| format!("{} #{}", comp.get_type_name(), point.component_id)),
+                pin_name
+            ),
+            net_id: None,
+            unit_id: None,
+            severity: Severity::Error,
+        });
+    }
+    for id in db.get_component_ids() {
+        let comp = db.get_component(&id).unwrap();
+        if !matches!(comp, Component::Primitive(p) if p.typ == PrimitiveType::Connector) {
+            continue;
+        }
+        let signal = get_connector_signal(comp);
+        if signal.is_some_and(|signal| netlist.get_connectors(signal).len() > 1) {
+            continue;
+        }
+        result.push(Problem {
+            kind: ProblemKind::UnmatchedConnector,
+            pos: comp.get_position(),
+            description: signal.map_or_else(|| format!("#{id}"), str::to_owned),
+            net_id: None,
+            unit_id: None,
+            severity: Severity::Error,
+        });
+    }
+    for id in db.get_component_ids() {
+        let Some(Component::Unit(unit)) = db.get_component(&id) else {
+            continue;
+        };
+        let unit_name = netlist
+            .get_instance_path(TOP_SCOPE, id)
+            .unwrap_or_else(|| format!("Unit #{id}"));
+        for mismatch in check_unit_ports(unit) {
+            let port_name = |port: Id| unit.ports[port].name.to_string();
+            let (kind, name, port) = match mismatch {
+                PortMismatch::Missing { name, .. } => (ProblemKind::MissingUnitPort, name, None),
+                PortMismatch::Extra(port) => {
+                    (ProblemKind::ExtraUnitPort, port_name(port), Some(port))
+                }
+                PortMismatch::WrongSide(port) => {
+                    (ProblemKind::UnitPortSide, port_name(port), Some(port))
+                }
+            };
+            result.push(Problem {
+                kind,
+                pos: port
+                    .and_then(|port| db.get_component(&id)?.get_connection_dock_cell(port))
+                    .unwrap_or(unit.pos),
+                description: format!("{unit_name}: {name}"),
+                net_id: None,
+                unit_id: Some(id),
+                severity: Severity::Error,
+            });
+        }
+    }
+    let net_ids = db.get_net_ids();
+    result.extend(parallel::flat_map(&net_ids, |net_id| {
+        let net = db.get_net(net_id).unwrap();
+        let severity = db
+            .get_net_class(net)
+            .map_or(Severity::Error, |class| class.severity);
+        if severity == Severity::Ignore {
+            return vec![];
+        }
+        let Some((kind, pos, other)) = check_net_path(db, netlist, *net_id, &net.points) else {
+            return vec![];
+        };
+        let wire_path = get_wire_path(netlist, *net_id);
+        let description = match kind {
+            ProblemKind::NetOverlap => format!("{wire_path}: {}", get_wire_path(netlist, other)),
+            ProblemKind::NetThroughKeepOut => wire_path,
+            _ => format!(
+                "{wire_path}: {}",
+                netlist
+                    .get_instance_path(TOP_SCOPE, other)
+                    .unwrap_or_else(|| format!("#{other}"))
+            ),
+        };
+        vec![Problem {
+            kind,
+            pos,
+            description,
+            net_id: Some(*net_id),
+            unit_id: None,
+            severity,
+        }]
+    }));
+    result
+}
+
+fn get_wire_path(netlist: &Netlist, wire_id: Id) -> String {
+    netlist.get_wire_net(wire_id).map_or_else(
+        || format!("#{wire_id}"),
+        |net| netlist.get_net_path(TOP_SCOPE, net),
+    )
+}
+
+/// Checks the path of the wire `net_id`.
+/// Returns the first found problem: its kind, position and ID of the other net or component.
+fn check_net_path(
+    db: &GridDB,
+    netlist: &Netlist,
+    net_id: Id,
+    points: &[GridPos],
+) -> Option<(ProblemKind, GridPos, Id)> {
+    if let Some(pos) = db.find_keep_out_crossing(points) {
+        return Some((ProblemKind::NetThroughKeepOut, pos, net_id));
+    }
+    let signal = netlist.get_wire_net(net_id);
+    for (a, b) in points.iter().zip(points.iter().skip(1)) {
+        let (min, max) = (
+            grid_pos(a.x.min(b.x), a.y.min(b.y)),
+            grid_pos(a.x.max(b.x), a.y.max(b.y)),
+        );
+        let rect = grid_rect(0, min, max);
+        for segment in db.get_visible_net_segments(&rect) {
+            if segment.net_id == net_id
+                || signal.is_some() && netlist.get_wire_net(segment.net_id) == signal
+            {
+                continue;
+            }
+            let (s_min, s_max) = (
+                grid_pos(
+                    segment.pos1.x.min(segment.pos2.x),
+                    segment.pos1.y.min(segment.pos2.y),
+                ),
+                grid_pos(
+                    segment.pos1.x.max(segment.pos2.x),
+                    segment.pos1.y.max(segment.pos2.y),
+                ),
+            );
+            // Parallel segments on the same line sharing more than one cell:
+            let overlap = if a.y == b.y && s_min.y == s_max.y && a.y == s_min.y {
+                (min.x.max(s_min.x) < max.x.min(s_max.x)).then(|| grid_pos(min.x.max(s_min.x), a.y))
+            } else if a.x == b.x && s_min.x == s_max.x && a.x == s_min.x {
+                (min.y.max(s_min.y) < max.y.min(s_max.y)).then(|| grid_pos(a.x, min.y.max(s_min.y)))
+            } else {
+                None
+            };
+            if let Some(pos) = overlap {
+                return Some((ProblemKind::NetOverlap, pos, segment.net_id));
+            }
+        }
+        for id in db.get_visible_component_ids(&rect) {
+            let comp = db.get_component(&id).unwrap();
+            if comp.is_overlap_only() {
+                continue;
+            }
+            // Pins are docked inside the component, wires may end there:
+            let body = comp.get_grid_rect(id);
+            let docks = comp.get_connection_dock_cells();
+            if let Some(pos) =
+                segment_cells(*a, *b).find(|cell| body.contains(*cell) && !docks.contains(cell))
+            {
+                return Some((ProblemKind::NetThroughComponent, pos, id));
+            }
+        }
+    }
+    None
+}
+
+/// Path with the fewest corners from `a` to `b` going around other nets and components,
+/// searched over the cells around them. The path is found cell by cell, so it's slower
+/// than trying the paths with two corners.
+fn find_free_path(
+    db: &GridDB,
+    netlist: &Netlist,
+    net_id: Id,
+    a: GridPos,
+    b: GridPos,
+) -> Option<Vec<GridPos>> {
+    const MARGIN: i32 = 64;
+    // Cells of a longer straight path are preferred over a corner:
+    const CORNER_COST: u32 = 8;
+    const DIRECTIONS: [(i32, i32); 4] = [(1, 0), (0, 1), (-1, 0), (0, -1)];
+    let (min, max) = (
+        grid_pos(a.x.min(b.x) - MARGIN, a.y.min(b.y) - MARGIN),
+        grid_pos(a.x.max(b.x) + MARGIN, a.y.max(b.y) + MARGIN),
+    );
+    let mut is_free: HashMap<(GridPos, GridPos), bool> = HashMap::new();
+    let mut costs: HashMap<(GridPos, usize), u32> = HashMap::new();
+    let mut previous: HashMap<(GridPos, usize), (GridPos, usize)> = HashMap::new();
+    let mut queue = BinaryHeap::new();
+    for dir in 0..DIRECTIONS.len() {
+        costs.insert((a, dir), 0);
+        queue.push(Reverse((0, a.x, a.y, dir)));
+    }
+    while let Some(Reverse((cost, x, y, dir))) = queue.pop() {
+        let pos = grid_pos(x, y);
+        if pos == b {
+            let mut points = vec![b];
+            let mut state = (pos, dir);
+            while let Some(prev) = previous.get(&state) {
+                points.push(prev.0);
+                state = *prev;
+            }
+            points.reverse();
+            return Some(simplify_path(points));
+        }
+        if costs.get(&(pos, dir)).is_some_and(|c| *c < cost) {
+            continue;
+        }
+        for (next_dir, (dx, dy)) in DIRECTIONS.iter().enumerate() {
+            // No turning back:
+            if next_dir == (dir + 2) % DIRECTIONS.len() {
+                continue;
+            }
+            let next = grid_pos(x + dx, y + dy);
+            if next.x < min.x || next.x > max.x || next.y < min.y || next.y > max.y {
+                continue;
+            }
+            let next_cost = cost + 1 + if next_dir == dir { 0 } else { CORNER_COST };
+            if costs
+                .get(&(next, next_dir))
+                .is_some_and(|c| *c <= next_cost)
+            {
+                continue;
+            }
+            // Steps between the same cells are checked once for both directions:
+            let step = match (pos.x, pos.y) < (next.x, next.y) {
+                true => (pos, next),
+                false => (next, pos),
+            };
+            let free = *is_free
+                .entry(step)
+                .or_insert_with(|| check_net_path(db, netlist, net_id, &[pos, next]).is_none());
+            if free {
+                costs.insert((next, next_dir), next_cost);
+                previous.insert((next, next_dir), (pos, dir));
+                queue.push(Reverse((next_cost, next.x, next.y, next_dir)));
+            }
+        }
+    }
+    None
+}
+
+/// Finds a path of the wire avoiding other nets and components bodies.
+/// Paths with up to two corners are tried first, then the path is searched around the obstacles.
+/// Returns None if there is no such path.
+pub fn reroute_net(db: &GridDB, netlist: &Netlist, net_id: Id) -> Option<Net> {
+    const MAX_DETOUR: i32 = 32;
+    let net = db.get_net(&net_id)?;
+    let (a, b) = (*net.points.first()?, *net.points.last()?);
+    let (mid_x, mid_y) = ((a.x + b.x) / 2, (a.y + b.y) / 2);
+    (0..=MAX_DETOUR)
+        .flat_map(|detour| [detour, -detour])
+        .flat_map(|detour| {
+            [
+                vec![
+                    a,
+                    grid_pos(mid_x + detour, a.y),
+                    grid_pos(mid_x + detour, b.y),
+                    b,
+                ],
+                vec![
+                    a,
+                    grid_pos(a.x, mid_y + detour),
+                    grid_pos(b.x, mid_y + detour),
+                    b,
+                ],
+            ]
+        })
+        .map(simplify_path)
+        .find(|points| check_net_path(db, netlist, net_id, points).is_none())
+        .or_else(|| find_free_path(db, netlist, net_id, a, b))
+        .map(|points| Net {
+            start_point: net.start_point,
+            end_point: net.end_point,
+            points,
+            class: net.class.clone(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProblemKind, collect_problems, reroute_net};
+    use crate::{
+        grid_db::{Component, GridDB, Id, PrimitiveType, Rotation, Severity, Unit, grid_pos},
+        netlist::extract_netlist,
+        testing::{GridDBBuilder, half_adder},
+    };
+
+    fn net_problems(db: &GridDB) -> Vec<(ProblemKind, Id)> {
+        let mut result: Vec<_> = collect_problems(db)
+            .into_iter()
+            .filter_map(|p| Some((p.kind, p.net_id?)))
+            .collect();
+        result.sort_by_key(|(_, id)| *id);
+        result
+    }
+
+    fn reroute(db: &mut GridDB, net_id: Id) {
+        let net = reroute_net(db, &extract_netlist(db), net_id).unwrap();
+        db.remove_net(&net_id);
+        db.insert_net(net_id, net);
+    }
+
+    #[test]
+    fn reroute_around_wall() {
+        let mut b = GridDBBuilder::new();
+        let input = b.primitive(PrimitiveType::Input, 0, 0, Rotation::ROT0);
+        let output = b.primitive(PrimitiveType::Output, 20, 0, Rotation::ROT0);
+        // Taller than the detours of the paths with two corners:
+        b.component(Component::Unit(Unit {
+            pos: grid_pos(8, -40),
+            width: 2,
+            height: 80,
+            ports: vec![],
+            parameters: vec![],
+            description: String::new(),
+            name: Default::default(),
+            source: None,
+            schematic: None,
+        }));
+        let net = b.connect((input, 0), (output, 0));
+        let mut db = b.build();
+        assert!(!net_problems(&db).is_empty());
+        reroute(&mut db, net);
+        assert!(net_problems(&db).is_empty());
+        // Found by the search around the obstacles:
+        let points = &db.get_net(&net).unwrap().points;
+        assert!(points.iter().any(|p| p.y < -40 || p.y >= 40));
+    }
+
+    #[test]
+    fn net_overlap() {
+        let mut db = half_adder();
+        // Vertical parts of `b -> xor` and `a -> and` are on the same line:
+        assert_eq!(
+            net_problems(&db),
+            [(ProblemKind::NetOverlap, 1), (ProblemKind::NetOverlap, 2)]
+        );
+        reroute(&mut db, 1);
+        assert!(net_problems(&db).is_empty());
+        assert!(collect_problems(&db).is_empty());
+    }
+
+    #[test]
+    fn net_through_component() {
+        let mut b = GridDBBuilder::new();
+        let input = b.primitive(PrimitiveType::Input, 0, 0, Rotation::ROT0);
+        let output = b.primitive(PrimitiveType::Output, 20, 0, Rotation::ROT0);
+        b.primitive(PrimitiveType::And(2), 8, -1, Rotation::ROT0);
+        let net = b.connect((input, 0), (output, 0));
+        let mut db = b.build();
+        let problems = collect_problems(&db);
+        let problem = problems
+            .iter()
+            .find(|p| p.kind == ProblemKind::NetThroughComponent)
+            .unwrap();
+        assert_eq!((problem.pos, problem.net_id), (grid_pos(8, 0), Some(net)));
+        reroute(&mut db, net);
+        assert!(net_problems(&db).is_empty());
+    }
+
+    #[test]
+    fn net_class_severity() {
+        let mut db = half_adder();
+        let set_class = |db: &mut GridDB, net_id, class: &str| {
+            let mut net = db.get_net(&net_id).unwrap().clone();
+            net.class = class.into();
+            db.remove_net(&net_id);
+            db.insert_net(net_id, net);
+        };
+        set_class(&mut db, 1, "debug");
+        set_class(&mut db, 2, "data");
+        let problems = collect_problems(&db);
+        let severities: Vec<_> = problems.iter().map(|p| (p.net_id, p.severity)).collect();
+        assert_eq!(severities, [(Some(2), Severity::Warning)]);
+
+        // Classes are saved with the project:
+        db.get_net_classes_mut()[3].severity = Severity::Error;
+        let loaded = GridDB::load_from_json(db.dump_to_json().unwrap()).unwrap();
+        assert_eq!(loaded.get_net_classes(), db.get_net_classes());
+        assert_eq!(net_problems(&loaded).len(), 2);
+        assert!(!half_adder().dump_to_json().unwrap().contains("net_classes"));
+    }
+
+    #[test]
+    fn dangling_nets_are_dropped() {
+        let mut json: serde_json::Value =
+            serde_json::from_str(&half_adder().dump_to_json().unwrap()).unwrap();
+        // The second input is lost, and a pin of XOR doesn't exist:
+        json["components"].as_object_mut().unwrap().remove("1");
+        json["nets"]["0"]["end_point"]["connection_id"] = 7.into();
+        let db = GridDB::load_from_json(json.to_string()).unwrap();
+        db.check_indices().unwrap();
+        let dangling: Vec<_> = db.get_dangling_nets().iter().map(|net| net.id).collect();
+        assert_eq!(dangling, [0, 1, 3]);
+        assert_eq!(db.get_net_ids(), [2, 4, 5]);
+        let problems = collect_problems(&db);
+        assert_eq!(
+            problems
+                .iter()
+                .filter(|p| p.kind == ProblemKind::DanglingNet)
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn missing_components() {
+        let mut db = half_adder();
+        let missing = |db: &GridDB| -> Vec<String> {
+            collect_problems(db)
+                .into_iter()
+                .filter(|p| p.kind == ProblemKind::MissingComponent)
+                .map(|p| p.description)
+                .collect()
+        };
+        let comp = db.remove_component(&0).unwrap();
+        db.report_missing_component(0);
+        db.report_missing_component(0);
+        // Existing components are not reported:
+        db.report_missing_component(1);
+        assert_eq!(missing(&db), ["#0"]);
+        db.insert_component(0, comp);
+        assert!(missing(&db).is_empty());
+    }
+}
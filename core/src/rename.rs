@@ -0,0 +1,149 @@
+//! Batch renaming of the selected instances with numbered names like `reg_%02d`
+
+use crate::grid_db::{Component, GridDB, Id};
+
+/// Pattern piece: literal text or the counter with the minimal width
+enum Token<'a> {
+    Text(&'a str),
+    Counter { width: usize, zero_pad: bool },
+}
+
+/// Splits printf-like pattern, `%d`, `%3d` and `%03d` are counters, `%%` is the percent sign
+fn tokenize(pattern: &str) -> Vec<Token<'_>> {
+    let mut tokens = vec![];
+    let mut rest = pattern;
+    while let Some(start) = rest.find('%') {
+        tokens.push(Token::Text(&rest[..start]));
+        let spec = &rest[start + 1..];
+        if let Some(spec) = spec.strip_prefix('%') {
+            tokens.push(Token::Text("%"));
+            rest = spec;
+            continue;
+        }
+        let digits = spec.len() - spec.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        if spec[digits..].starts_with('d') {
+            tokens.push(Token::Counter {
+                width: spec[..digits].parse().unwrap_or(0),
+                zero_pad: spec.starts_with('0'),
+            });
+            rest = &spec[digits + 1..];
+        } else {
+            tokens.push(Token::Text("%"));
+            rest = spec;
+        }
+    }
+    tokens.push(Token::Text(rest));
+    tokens
+}
+
+pub fn has_counter(pattern: &str) -> bool {
+    tokenize(pattern)
+        .iter()
+        .any(|t| matches!(t, Token::Counter { .. }))
+}
+
+/// Name with the counters of `pattern` replaced by `n`
+pub fn format_name(pattern: &str, n: usize) -> String {
+    tokenize(pattern)
+        .into_iter()
+        .map(|token| match token {
+            Token::Text(text) => text.to_owned(),
+            Token::Counter {
+                width,
+                zero_pad: true,
+            } => format!("{n:0width$}"),
+            Token::Counter { width, .. } => format!("{n:width$}"),
+        })
+        .collect()
+}
+
+/// Instances among `ids` ordered top-to-bottom, then left-to-right
+pub fn order_by_position(db: &GridDB, ids: &[Id]) -> Vec<Id> {
+    let mut ids: Vec<Id> = ids
+        .iter()
+        .copied()
+        .filter(|id| {
+            db.get_component(id)
+                .is_some_and(|c| !matches!(c, Component::TextField(_)))
+        })
+        .collect();
+    ids.sort_by_key(|id| {
+        let pos = db.get_component(id).unwrap().get_position();
+        (pos.y, pos.x, *id)
+    });
+    ids
+}
+
+/// Components among `ids` renamed by the pattern, numbered from `start` with `step`
+pub fn rename_instances(
+    db: &GridDB,
+    ids: &[Id],
+    pattern: &str,
+    start: usize,
+    step: usize,
+) -> Vec<(Id, Component)> {
+    order_by_position(db, ids)
+        .into_iter()
+        .enumerate()
+        .map(|(i, id)| {
+            let mut comp = db.get_component(&id).unwrap().clone();
+            if let Some(name) = comp.get_name_mut() {
+                *name = format_name(pattern, start + i * step).into();
+            }
+            (id, comp)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_name, has_counter, rename_instances};
+    use crate::{
+        grid_db::{GridDB, PrimitiveType, Rotation},
+        netlist::extract_netlist,
+        testing::GridDBBuilder,
+    };
+
+    #[test]
+    fn numbering_patterns() {
+        assert_eq!(format_name("reg_%02d", 7), "reg_07");
+        assert_eq!(format_name("r%d_%3d", 12), "r12_ 12");
+        assert_eq!(format_name("a%%b%x", 1), "a%b%x");
+        assert!(has_counter("u%d"));
+        assert!(!has_counter("u%%d"));
+    }
+
+    #[test]
+    fn rename_by_position() {
+        let mut b = GridDBBuilder::new();
+        let bottom = b.primitive(PrimitiveType::Not, 0, 10, Rotation::ROT0);
+        let top_right = b.primitive(PrimitiveType::Not, 10, 0, Rotation::ROT0);
+        let top_left = b.primitive(PrimitiveType::Not, 0, 0, Rotation::ROT0);
+        let label = b.text("regs", 0, -3);
+        let db = b.build();
+        let renamed =
+            rename_instances(&db, &[bottom, top_right, top_left, label], "reg_%02d", 1, 2);
+        let names: Vec<_> = renamed
+            .iter()
+            .map(|(id, comp)| (*id, comp.get_instance_name(*id).unwrap()))
+            .collect();
+        assert_eq!(
+            names,
+            [
+                (top_left, "reg_01".to_owned()),
+                (top_right, "reg_03".to_owned()),
+                (bottom, "reg_05".to_owned())
+            ]
+        );
+
+        let mut db = db;
+        for (id, comp) in renamed {
+            db.remove_component(&id);
+            db.insert_component(id, comp);
+        }
+        let netlist = extract_netlist(&db);
+        assert_eq!(netlist.get_instance(bottom).unwrap().name, "reg_05");
+        let loaded = GridDB::load_from_json(db.dump_to_json().unwrap()).unwrap();
+        assert_eq!(extract_netlist(&loaded), netlist);
+    }
+}
@@ -0,0 +1,230 @@
+//! Find and replace over instance names, port names, unit parameters and text fields
+
+use regex_lite::Regex;
+
+use crate::{
+    grid_db::{Component, GridDB, GridPos, Id, SharedStr},
+    netlist::{HierarchyItem, TOP_SCOPE, extract_netlist, find_hierarchy_path},
+};
+
+/// Kind of the searched text
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SearchField {
+    InstanceName,
+    PortName,
+    ParameterName,
+    ParameterValue,
+    Text,
+}
+
+#[derive(Clone, Default, PartialEq)]
+pub struct SearchQuery {
+    pub pattern: String,
+    pub replacement: String,
+    /// `pattern` is a regular expression, `replacement` may refer to its groups as `$1`
+    pub regex: bool,
+}
+
+/// Text found by the query with its replacement
+pub struct SearchMatch {
+    pub id: Id,
+    /// Hierarchical name of the instance like `top.and2_5`, or `#id` for text fields
+    pub name: String,
+    pub field: SearchField,
+    pub pos: GridPos,
+    pub text: String,
+    pub replaced: String,
+}
+
+/// Found matches and the components with all of them replaced
+pub struct SearchResult {
+    /// Item of the unit hierarchy named by the plain text pattern, like `top.u_alu.and2_5`
+    pub path: Option<(String, HierarchyItem)>,
+    pub matches: Vec<SearchMatch>,
+    pub replaced: Vec<(Id, Component)>,
+}
+
+/// Calls `f` for every searchable text of the component
+fn for_each_text(comp: &mut Component, mut f: impl FnMut(SearchField, &mut SharedStr)) {
+    // Generated names are not stored, so they can't be replaced
+    if let Some(name) = comp.get_name_mut()
+        && !name.is_empty()
+    {
+        f(SearchField::InstanceName, name);
+    }
+    match comp {
+        Component::Unit(unit) => {
+            for port in &mut unit.ports {
+                f(SearchField::PortName, &mut port.name);
+            }
+            for param in &mut unit.parameters {
+                f(SearchField::ParameterName, &mut param.name);
+                f(SearchField::ParameterValue, &mut param.value);
+            }
+        }
+        Component::TextField(field) => f(SearchField::Text, &mut field.text),
+        Component::Primitive(_) => {}
+    }
+}
+
+impl SearchQuery {
+    /// Regular expression of the pattern, None for the plain text search
+    pub fn compile(&self) -> Result<Option<Regex>, regex_lite::Error> {
+        match self.regex && !self.pattern.is_empty() {
+            true => Regex::new(&self.pattern).map(Some),
+            false => Ok(None),
+        }
+    }
+
+    /// Finds the matches in all components, sorted by component ID
+    pub fn search(&self, db: &GridDB) -> Result<SearchResult, regex_lite::Error> {
+        Ok(self.search_compiled(db, self.compile()?.as_ref()))
+    }
+
+    /// Same as `search` with the regular expression made by `compile`
+    pub fn search_compiled(&self, db: &GridDB, regex: Option<&Regex>) -> SearchResult {
+        let mut result = SearchResult {
+            path: None,
+            matches: vec![],
+            replaced: vec![],
+        };
+        if self.pattern.is_empty() {
+            return result;
+        }
+        if regex.is_none() {
+            result.path =
+                find_hierarchy_path(db, &self.pattern).map(|item| (self.pattern.clone(), item));
+        }
+        let netlist = extract_netlist(db);
+        let replace = |text: &str| -> Option<String> {
+            match regex {
+                Some(regex) if regex.is_match(text) => Some(
+                    regex
+                        .replace_all(text, self.replacement.as_str())
+                        .into_owned(),
+                ),
+                None if text.contains(&self.pattern) => {
+                    Some(text.replace(&self.pattern, &self.replacement))
+                }
+                _ => None,
+            }
+        };
+        for id in db.get_component_ids() {
+            let mut comp = db.get_component(&id).unwrap().clone();
+            let pos = comp.get_position();
+            let mut changed = false;
+            let name = netlist
+                .get_instance_path(TOP_SCOPE, id)
+                .unwrap_or_else(|| format!("#{id}"));
+            for_each_text(&mut comp, |field, text| {
+                if let Some(replaced) = replace(text) {
+                    result.matches.push(SearchMatch {
+                        id,
+                        name: name.clone(),
+                        field,
+                        pos,
+                        text: text.to_string(),
+                        replaced: replaced.clone(),
+                    });
+                    *text = replaced.into();
+                    changed = true;
+                }
+            });
+            if changed {
+                result.replaced.push((id, comp));
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SearchField, SearchQuery};
+    use crate::{
+        grid_db::{Component, Port, Rotation, Unit, grid_pos},
+        testing::{GridDBBuilder, half_adder},
+    };
+
+    #[test]
+    fn find_and_replace() {
+        let mut b = GridDBBuilder::new();
+        let port = |offset, name: &str| Port {
+            offset,
+            align: Rotation::ROT0,
+            name: name.into(),
+            bundle: None,
+        };
+        let unit = b.component(Component::Unit(Unit {
+            pos: grid_pos(0, 0),
+            width: 4,
+            height: 4,
+            ports: vec![port(1, "data_in0"), port(2, "data_in1"), port(3, "clk")],
+            parameters: vec![],
+            description: String::new(),
+            name: Default::default(),
+            source: None,
+            schematic: None,
+        }));
+        let text = b.text("data bus", 0, 6);
+        let db = b.build();
+        let query = |pattern: &str, replacement: &str, regex| SearchQuery {
+            pattern: pattern.into(),
+            replacement: replacement.into(),
+            regex,
+        };
+
+        let result = query("data", "d", false).search(&db).unwrap();
+        let found: Vec<_> = result.matches.iter().map(|m| (m.id, m.field)).collect();
+        assert_eq!(
+            found,
+            [
+                (unit, SearchField::PortName),
+                (unit, SearchField::PortName),
+                (text, SearchField::Text)
+            ]
+        );
+        assert_eq!(result.replaced.len(), 2);
+        let Component::Unit(replaced) = &result.replaced[0].1 else {
+            panic!("Unit expected");
+        };
+        assert_eq!(&*replaced.ports[1].name, "d_in1");
+
+        let result = query(r"^data_in(\d)$", "din[$1]", true)
+            .search(&db)
+            .unwrap();
+        let replaced: Vec<_> = result.matches.iter().map(|m| m.replaced.as_str()).collect();
+        assert_eq!(replaced, ["din[0]", "din[1]"]);
+
+        assert!(query("(", "", true).search(&db).is_err());
+        assert!(
+            query("", "x", false)
+                .search(&db)
+                .unwrap()
+                .matches
+                .is_empty()
+        );
+        assert!(
+            query("adder", "", false)
+                .search(&half_adder())
+                .unwrap()
+                .matches
+                .len()
+                == 1
+        );
+        let adder = half_adder();
+        let (_, item) = query("top.xor2_2", "", false)
+            .search(&adder)
+            .unwrap()
+            .path
+            .unwrap();
+        assert_eq!(item.pos, adder.get_component(&2).unwrap().get_position());
+        assert!(
+            query("top.xor2_2", "", true)
+                .search(&adder)
+                .unwrap()
+                .path
+                .is_none()
+        );
+    }
+}
@@ -0,0 +1,737 @@
+//! Logic simulation over the netlist
+
+use std::collections::{BTreeMap, HashMap};
+
+use epaint::{Color32, Pos2, Rect, Stroke, vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    canvas::Canvas,
+    field_state::{FieldState, filled_cells},
+    grid_db::{
+        Change, Component, GridDB, Id, Logic, NetColorMode, PrimitiveState, PrimitiveType, Revision,
+        grid_rect,
+    },
+    netlist::NetlistCache,
+};
+
+/// Four-valued (0, 1, X, Z) unit delay simulator:
+/// every step evaluates all primitives with the net values of the previous step
+#[derive(Default)]
+pub struct Simulator {
+    netlist: NetlistCache,
+    revision: Option<Revision>,
+    // Value of every net of the netlist
+    values: Vec<Logic>,
+    // Number of 0 ↔ 1 transitions of every net since the last reset or schematic change
+    toggles: Vec<u64>,
+    states: HashMap<Id, PrimitiveState>,
+    steps: u64,
+    // Interactive input held by the pointer
+    pressed: Option<Id>,
+}
+
+/// Saved simulation state, stored in a file next to the project
+#[derive(Serialize, Deserialize)]
+struct SimulationSnapshot {
+    steps: u64,
+    /// Values of flip-flops, inputs and generators
+    states: BTreeMap<Id, PrimitiveState>,
+    /// Values of the driven wires
+    wires: BTreeMap<Id, Logic>,
+}
+
+impl Simulator {
+    /// Limit for circuits which never become stable, like ring oscillators
+    pub const MAX_SETTLE_STEPS: usize = 1000;
+
+    /// Simulation time in steps
+    pub fn get_steps(&self) -> u64 {
+        self.steps
+    }
+
+    pub fn reset(&mut self) {
+        self.values.clear();
+        self.toggles.clear();
+        self.states.clear();
+        self.revision = None;
+        self.steps = 0;
+    }
+
+    /// Simulation state as JSON, restored by `load_state`
+    pub fn dump_state(&mut self, db: &GridDB) -> Option<String> {
+        self.sync(db);
+        let netlist = self.netlist.get(db);
+        let snapshot = SimulationSnapshot {
+            steps: self.steps,
+            states: self
+                .states
+                .iter()
+                .map(|(id, state)| (*id, *state))
+                .collect(),
+            wires: netlist
+                .nets
+                .iter()
+                .zip(&self.values)
+                .filter(|(_, value)| **value != Logic::Z)
+                .flat_map(|(net, value)| net.wires.iter().map(|wire| (*wire, *value)))
+                .collect(),
+        };
+        serde_json::to_string_pretty(&snapshot).ok()
+    }
+
+    /// Restores the state saved by `dump_state`, components missing in `db` are skipped
+    pub fn load_state(&mut self, db: &GridDB, json: &str) -> Result<(), serde_json::Error> {
+        let snapshot: SimulationSnapshot = serde_json::from_str(json)?;
+        self.reset();
+        // Settled before the values are replaced, so the restored state is not re-evaluated:
+        self.sync(db);
+        self.steps = snapshot.steps;
+        self.states = snapshot
+            .states
+            .into_iter()
+            .filter(|(id, _)| matches!(db.get_component(id), Some(Component::Primitive(_))))
+            .collect();
+        let netlist = self.netlist.get(db);
+        self.values.fill(Logic::Z);
+        for (wire, value) in snapshot.wires {
+            if let Some(net) = netlist.get_wire_net(wire) {
+                self.values[net] = value;
+            }
+        }
+        Ok(())
+    }
+
+    /// Frees the netlist and the values of the background project.
+    /// Returns the state for `load_state`, None if the simulation was not started.
+    pub fn unload(&mut self, db: &GridDB) -> Option<String> {
+        let state = self
+            .revision
+            .is_some()
+            .then(|| self.dump_state(db))
+            .flatten();
+        *self = Self::default();
+        state
+    }
+
+    /// Value of the net (wire) of the database
+    pub fn get_wire_value(&mut self, db: &GridDB, wire_id: Id) -> Logic {
+        self.sync(db);
+        self.netlist
+            .get(db)
+            .get_wire_net(wire_id)
+            .map_or(Logic::Z, |net| self.values[net])
+    }
+
+    /// Values of all wires of the database, used for the back-annotated SVG export
+    pub fn get_wire_values(&mut self, db: &GridDB) -> HashMap<Id, Logic> {
+        db.get_net_ids()
+            .into_iter()
+            .map(|id| (id, self.get_wire_value(db, id)))
+            .collect()
+    }
+
+    /// Number of 0 ↔ 1 transitions of the net (wire) since the last reset or schematic change
+    pub fn get_wire_toggles(&mut self, db: &GridDB, wire_id: Id) -> u64 {
+        self.sync(db);
+        self.netlist
+            .get(db)
+            .get_wire_net(wire_id)
+            .map_or(0, |net| self.toggles[net])
+    }
+
+    /// Value of the component connection
+    pub fn get_pin_value(&mut self, db: &GridDB, component_id: Id, connection_id: Id) -> Logic {
+        self.sync(db);
+        self.netlist
+            .get(db)
+            .get_net(&crate::grid_db::GridDBConnectionPoint {
+                component_id,
+                connection_id,
+            })
+            .map_or(Logic::Z, |net| self.values[net])
+    }
+
+    /// Recalculates the values after the schematic is changed
+    fn sync(&mut self, db: &GridDB) {
+        let revision = db.get_revision();
+        if self.revision != Some(revision) {
+            if let Some(old) = self.revision {
+                match db.get_changes_since(old) {
+                    // Removed components, their Ids may be reused by others:
+                    Some(changes) => {
+                        for change in changes {
+                            if let Change::Component(id) = change
+                                && !matches!(db.get_component(id), Some(Component::Primitive(_)))
+                            {
+                                self.states.remove(id);
+                            }
+                        }
+                    }
+                    // Another project or too many changes, the Ids may belong to anything:
+                    None => self.states.clear(),
+                }
+            }
+            self.revision = Some(revision);
+            self.values = vec![Logic::Z; self.netlist.get(db).nets.len()];
+            for _ in 0..Self::MAX_SETTLE_STEPS {
+                if !self.evaluate(db) {
+                    break;
+                }
+            }
+            self.toggles = vec![0; self.values.len()];
+        }
+    }
+
+    // Returns true if any value is changed
+    fn evaluate(&mut self, db: &GridDB) -> bool {
+        let netlist = self.netlist.get(db);
+        let mut values = vec![Logic::Z; netlist.nets.len()];
+        for inst in &netlist.instances {
+            let Some(Component::Primitive(prim)) = db.get_component(&inst.id) else {
+                continue;
+            };
+            let pins: Vec<Logic> = inst
+                .pins
+                .iter()
+                .map(|pin| pin.net.map_or(Logic::Z, |net| self.values[net]))
+                .collect();
+            let state = self.states.entry(inst.id).or_default();
+            for (connection_id, value) in prim.typ.evaluate(&pins, state) {
+                // Conflicting drivers of the same net give X:
+                if let Some(net) = inst.pins[connection_id].net {
+                    values[net] = values[net].resolve(value);
+                }
+            }
+        }
+        let changed = values != self.values;
+        if changed && self.toggles.len() == values.len() {
+            for ((old, new), toggles) in self.values.iter().zip(&values).zip(&mut self.toggles) {
+                if matches!(
+                    (old, new),
+                    (Logic::Low, Logic::High) | (Logic::High, Logic::Low)
+                ) {
+                    *toggles += 1;
+                }
+            }
+        }
+        self.values = values;
+        changed
+    }
+
+    /// Single propagation step, returns true if any value is changed
+    pub fn step(&mut self, db: &GridDB) -> bool {
+        self.sync(db);
+        self.steps += 1;
+        self.evaluate(db)
+    }
+
+    /// Steps until the values stop changing, returns false if the circuit is not stable
+    pub fn run_until_stable(&mut self, db: &GridDB) -> bool {
+        (0..Self::MAX_SETTLE_STEPS).any(|_| !self.step(db))
+    }
+
+    /// Sets the value driven by the input, switch or another source
+    pub fn set_source_value(&mut self, id: Id, value: bool) {
+        self.states.entry(id).or_default().value = value;
+    }
+
+    /// Inverts outputs of all clock primitives
+    pub fn toggle_clocks(&mut self, db: &GridDB) {
+        // Settled first, so the flip-flops don't see the edge before the initial values:
+        self.sync(db);
+        for inst in &self.netlist.get(db).instances {
+            if let Some(Component::Primitive(prim)) = db.get_component(&inst.id)
+                && prim.typ == PrimitiveType::Clock
+            {
+                let state = self.states.entry(inst.id).or_default();
+                state.value = !state.value;
+            }
+        }
+    }
+
+    /// Assertions with the violated condition
+    pub fn get_violations(&mut self, db: &GridDB) -> Vec<Id> {
+        self.sync(db);
+        self.netlist
+            .get(db)
+            .instances
+            .iter()
+            .filter(|inst| {
+                matches!(
+                    db.get_component(&inst.id),
+                    Some(Component::Primitive(prim)) if matches!(prim.typ, PrimitiveType::Assertion { .. })
+                ) && self.states.get(&inst.id).is_some_and(|state| state.value)
+            })
+            .map(|inst| inst.id)
+            .collect()
+    }
+
+    /// Clicks the interactive inputs under the pointer, returns true if any input is changed
+    pub fn handle_pointer(
+        &mut self,
+        db: &GridDB,
+        state: &FieldState,
+        pos: Option<Pos2>,
+        pressed: bool,
+        released: bool,
+    ) -> bool {
+        if released && let Some(id) = self.pressed.take() {
+            return self.click(db, id, None, false);
+        }
+        let Some(pos) = pos.filter(|pos| pressed && state.rect.contains(*pos)) else {
+            return false;
+        };
+        let cell = state.screen_to_grid(pos);
+        let Some(id) = db
+            .get_visible_component_ids(&grid_rect(0, cell, cell))
+            .into_iter()
+            .find(|id| {
+                matches!(db.get_component(id), Some(Component::Primitive(p)) if p.typ.is_interactive())
+            })
+        else {
+            return false;
+        };
+        let grid_pos = ((pos - state.rect.min - state.offset) / state.grid_size).to_pos2();
+        self.pressed = Some(id);
+        self.click(db, id, Some(grid_pos), true)
+    }
+
+    fn click(&mut self, db: &GridDB, id: Id, pos: Option<Pos2>, pressed: bool) -> bool {
+        let Some(Component::Primitive(prim)) = db.get_component(&id) else {
+            return false;
+        };
+        let raw = pos.map_or(Pos2::ZERO, |pos| prim.grid_to_raw(pos));
+        self.sync(db);
+        prim.typ
+            .click(raw, pressed, self.states.entry(id).or_default())
+    }
+
+    /// Highlight of the value, low values are not highlighted
+    fn get_value_color(value: Logic) -> Option<Color32> {
+        match value {
+            Logic::Low => None,
+            Logic::High => Some(Color32::from_rgba_unmultiplied(0, 220, 0, 140)),
+            Logic::X => Some(Color32::from_rgba_unmultiplied(255, 140, 0, 160)),
+            Logic::Z => Some(Color32::from_rgba_unmultiplied(60, 120, 255, 120)),
+        }
+    }
+
+    /// Colors nets from blue (never toggled) to red (the most active one)
+    pub fn draw_activity(&mut self, db: &GridDB, state: &FieldState, painter: &impl Canvas) {
+        self.sync(db);
+        let rect = grid_rect(
+            0,
+            state.screen_to_grid(state.rect.min),
+            state.screen_to_grid(state.rect.max),
+        );
+        let max_toggles = self.toggles.iter().copied().max().unwrap_or(0).max(1);
+        let ofs = vec2(0.5, 0.5) * state.grid_size;
+        for segment in db.get_visible_net_segments(&rect) {
+            let toggles = self.get_wire_toggles(db, segment.net_id);
+            let color = if toggles == 0 {
+                Color32::from_rgba_unmultiplied(60, 120, 255, 120)
+            } else {
+                let t = toggles as f32 / max_toggles as f32;
+                Color32::from_rgba_unmultiplied(255, (255.0 * (1.0 - t)) as u8, 0, 140)
+            };
+            painter.line_segment(
+                [
+                    state.grid_to_screen(&segment.pos1) + ofs,
+                    state.grid_to_screen(&segment.pos2) + ofs,
+                ],
+                Stroke::new((state.grid_size * 0.3).max(1.0), color),
+            );
+        }
+    }
+
+    /// Highlights nets with high, unknown and floating values and lights up the outputs
+    pub fn draw(&mut self, db: &GridDB, state: &FieldState, painter: &impl Canvas) {
+        let rect = grid_rect(
+            0,
+            state.screen_to_grid(state.rect.min),
+            state.screen_to_grid(state.rect.max),
+        );
+        let high_color = Self::get_value_color(Logic::High).unwrap();
+        let violation_color = Color32::from_rgba_unmultiplied(230, 0, 0, 140);
+        let ofs = vec2(0.5, 0.5) * state.grid_size;
+        // The values may be shown by the colors of the nets themselves:
+        let segments = match state.net_style.color_mode {
+            NetColorMode::SimulationValues => vec![],
+            _ => db.get_visible_net_segments(&rect),
+        };
+        for segment in segments {
+            if let Some(color) = Self::get_value_color(self.get_wire_value(db, segment.net_id)) {
+                painter.line_segment(
+                    [
+                        state.grid_to_screen(&segment.pos1) + ofs,
+                        state.grid_to_screen(&segment.pos2) + ofs,
+                    ],
+                    Stroke::new((state.grid_size * 0.25).max(1.0), color),
+                );
+            }
+        }
+        for id in db.get_visible_component_ids(&rect) {
+            let comp = db.get_component(&id).unwrap();
+            if let Component::Primitive(prim) = comp
+                && let Some(prim_state) = self.states.get(&id)
+            {
+                for area in prim.typ.get_active_areas(prim_state) {
+                    let to_screen = |p: Pos2| {
+                        state.rect.min
+                            + state.offset
+                            + prim.raw_to_grid(p).to_vec2() * state.grid_size
+                    };
+                    painter.rect_filled(
+                        Rect::from_two_pos(to_screen(area.min), to_screen(area.max)),
+                        0.0,
+                        high_color,
+                    );
+                }
+            }
+            let color = match comp {
+                Component::Primitive(prim) if prim.typ == PrimitiveType::Output => {
+                    match Self::get_value_color(self.get_pin_value(db, id, 0)) {
+                        Some(color) => color,
+                        None => continue,
+                    }
+                }
+                Component::Primitive(prim)
+                    if matches!(prim.typ, PrimitiveType::Assertion { .. })
+                        && self.states.get(&id).is_some_and(|state| state.value) =>
+                {
+                    violation_color
+                }
+                _ => continue,
+            };
+            let (w, h) = comp.get_dimension();
+            painter.add(filled_cells(
+                state,
+                &comp.get_position(),
+                w,
+                h,
+                color,
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use epaint::pos2;
+
+    use super::Simulator;
+    use crate::{
+        grid_db::{
+            AssertionType, Component, DFFParams, ExportGrid, GridDB, Id, Logic, PrimitiveState,
+            PrimitiveType, Rotation, Theme,
+        },
+        testing::{GridDBBuilder, half_adder},
+    };
+
+    #[test]
+    fn half_adder_outputs() {
+        let db = half_adder();
+        let mut sim = Simulator::default();
+        for (a, b) in [(false, false), (false, true), (true, false), (true, true)] {
+            sim.states.entry(0).or_default().value = a;
+            sim.states.entry(1).or_default().value = b;
+            assert!(sim.run_until_stable(&db));
+            // Sum and carry outputs:
+            assert_eq!(sim.get_pin_value(&db, 4, 0), Logic::from(a ^ b));
+            assert_eq!(sim.get_pin_value(&db, 5, 0), Logic::from(a && b));
+        }
+    }
+
+    #[test]
+    fn states_of_another_project() {
+        let mut sim = Simulator::default();
+        let mut b = GridDBBuilder::new();
+        let input = b.primitive(PrimitiveType::Input, 0, 0, Rotation::ROT0);
+        let out = b.primitive(PrimitiveType::Output, 8, 0, Rotation::ROT0);
+        b.connect((input, 0), (out, 0));
+        let db = b.build();
+        sim.states.entry(input).or_default().value = true;
+        assert_eq!(sim.get_pin_value(&db, input, 0), Logic::High);
+        // The opened project has another input with the same Id:
+        let mut b = GridDBBuilder::new();
+        let other_input = b.primitive(PrimitiveType::Input, 4, 4, Rotation::ROT0);
+        let out = b.primitive(PrimitiveType::Output, 12, 4, Rotation::ROT0);
+        b.connect((other_input, 0), (out, 0));
+        let other = b.build();
+        assert_eq!(other_input, input);
+        assert_eq!(sim.get_pin_value(&other, other_input, 0), Logic::Low);
+    }
+
+    #[test]
+    fn back_annotated_svg() {
+        let db = half_adder();
+        let mut sim = Simulator::default();
+        sim.states.entry(0).or_default().value = true;
+        assert!(sim.run_until_stable(&db));
+        let values = sim.get_wire_values(&db);
+        assert_eq!(values.len(), db.get_net_ids().len());
+        let svg = db.dump_to_annotated_svg(
+            Theme::Light,
+            20.0,
+            &Default::default(),
+            values,
+            ExportGrid::NONE,
+        );
+        // Both wires of `a` and the sum are high, `b` and the carry are low:
+        assert_eq!(svg.matches(">1</text>").count(), 3);
+        assert_eq!(svg.matches(">0</text>").count(), 3);
+        assert_eq!(svg.matches("stroke=\"#00aa00\"").count(), 3);
+    }
+
+    /// DFF with the inverted output connected to the input, returns the DFF ID
+    fn clock_divider_db() -> (GridDB, Id) {
+        let mut b = GridDBBuilder::new();
+        let clock = b.primitive(PrimitiveType::Clock, 0, 0, Rotation::ROT0);
+        let dff = b.primitive(
+            PrimitiveType::DFF(DFFParams {
+                has_enable: false,
+                has_async_reset: false,
+                has_sync_reset: false,
+                async_reset_inverted: false,
+                sync_reset_inverted: false,
+            }),
+            6,
+            0,
+            Rotation::ROT0,
+        );
+        let not = b.primitive(PrimitiveType::Not, 6, 8, Rotation::ROT0);
+        b.connect((clock, 0), (dff, 0));
+        b.connect((dff, 2), (not, 0));
+        b.connect((not, 1), (dff, 1));
+        (b.build(), dff)
+    }
+
+    #[test]
+    fn clock_divider() {
+        let (db, dff) = clock_divider_db();
+        let mut sim = Simulator::default();
+        let mut outputs = vec![];
+        for _ in 0..8 {
+            sim.toggle_clocks(&db);
+            assert!(sim.run_until_stable(&db));
+            outputs.push(sim.get_pin_value(&db, dff, 2));
+        }
+        // Q toggles on every rising edge:
+        assert_eq!(
+            outputs,
+            [true, true, false, false, true, true, false, false].map(Logic::from)
+        );
+        let wire_ids = db.get_net_ids();
+        let toggles: Vec<_> = wire_ids
+            .iter()
+            .map(|id| sim.get_wire_toggles(&db, *id))
+            .collect();
+        // Clock, Q and its inversion:
+        assert_eq!(toggles, [8, 4, 4]);
+        sim.reset();
+        assert_eq!(sim.get_wire_toggles(&db, wire_ids[0]), 0);
+    }
+
+    #[test]
+    fn keypad_click() {
+        let mut b = GridDBBuilder::new();
+        let keypad = b.primitive(PrimitiveType::HexKeypad, 3, 2, Rotation::ROT90);
+        let db = b.build();
+        let Some(Component::Primitive(prim)) = db.get_component(&keypad) else {
+            unreachable!()
+        };
+        // Center of the "A" key:
+        let key = prim.raw_to_grid(pos2(2.5, 2.5));
+        assert_eq!(prim.grid_to_raw(key), pos2(2.5, 2.5));
+        let mut sim = Simulator::default();
+        assert!(sim.click(&db, keypad, Some(key), true));
+        assert_eq!(sim.states[&keypad].bits, 0xA);
+        assert!(sim.states[&keypad].value);
+        assert!(sim.click(&db, keypad, None, false));
+        assert!(!sim.states[&keypad].value);
+    }
+
+    #[test]
+    fn empty_dip_switch_click() {
+        let mut state = PrimitiveState::default();
+        assert!(!PrimitiveType::DipSwitch(0).click(pos2(1.0, 0.5), true, &mut state));
+        assert!(PrimitiveType::DipSwitch(2).click(pos2(1.0, 5.0), true, &mut state));
+        assert_eq!(state.bits, 0b10);
+    }
+
+    #[test]
+    fn stimulus_generators() {
+        let (pattern, length) = PrimitiveType::pattern_from_str("1101").unwrap();
+        assert_eq!(PrimitiveType::pattern_to_string(pattern, length), "1101");
+        let mut b = GridDBBuilder::new();
+        let clock = b.primitive(PrimitiveType::Clock, 0, 0, Rotation::ROT0);
+        let pat = b.primitive(
+            PrimitiveType::Pattern { pattern, length },
+            6,
+            0,
+            Rotation::ROT0,
+        );
+        let rnd = b.primitive(
+            PrimitiveType::Random { width: 4, seed: 7 },
+            6,
+            6,
+            Rotation::ROT0,
+        );
+        b.connect((clock, 0), (pat, 0));
+        b.connect((clock, 0), (rnd, 0));
+        // Unconnected pins have no value:
+        let out = b.primitive(PrimitiveType::Output, 16, 0, Rotation::ROT0);
+        b.connect((pat, 1), (out, 0));
+        for i in 1..=4 {
+            let out = b.primitive(PrimitiveType::Output, 16, 4 + 2 * i as i32, Rotation::ROT0);
+            b.connect((rnd, i), (out, 0));
+        }
+        let db = b.build();
+        let mut sim = Simulator::default();
+        let run = |sim: &mut Simulator| {
+            let mut bits = String::new();
+            let mut values = vec![];
+            for _ in 0..8 {
+                bits.push(if sim.get_pin_value(&db, pat, 1) == Logic::High {
+                    '1'
+                } else {
+                    '0'
+                });
+                values.push(
+                    (1..=4)
+                        .map(|i| sim.get_pin_value(&db, rnd, i))
+                        .collect::<Vec<_>>(),
+                );
+                // Full clock period:
+                for _ in 0..2 {
+                    sim.toggle_clocks(&db);
+                    assert!(sim.run_until_stable(&db));
+                }
+            }
+            (bits, values)
+        };
+        let (bits, values) = run(&mut sim);
+        assert_eq!(bits, "11011101");
+        assert!(values.windows(2).any(|w| w[0] != w[1]));
+        // The same seed gives the same sequence:
+        sim.reset();
+        assert_eq!(run(&mut sim), (bits, values));
+    }
+
+    #[test]
+    fn stale_pattern_state() {
+        let typ = PrimitiveType::Pattern {
+            pattern: 0b10,
+            length: 2,
+        };
+        // E.g. left by a Random generator with the same Id:
+        let mut state = PrimitiveState {
+            bits: u64::MAX,
+            ..Default::default()
+        };
+        assert_eq!(typ.evaluate(&[Logic::Low], &mut state), [(1, Logic::High)]);
+        assert_eq!(typ.evaluate(&[Logic::High], &mut state), [(1, Logic::Low)]);
+        assert_eq!(state.bits, 0);
+    }
+
+    #[test]
+    fn assertion_violations() {
+        let mut b = GridDBBuilder::new();
+        let a = b.primitive(PrimitiveType::Switch, 0, 0, Rotation::ROT0);
+        let c = b.primitive(PrimitiveType::Switch, 0, 4, Rotation::ROT0);
+        let check = b.primitive(
+            PrimitiveType::Assertion {
+                condition: AssertionType::NotAll,
+                n_inputs: 2,
+            },
+            6,
+            1,
+            Rotation::ROT0,
+        );
+        b.connect((a, 0), (check, 0));
+        b.connect((c, 0), (check, 1));
+        let db = b.build();
+        let mut sim = Simulator::default();
+        for (va, vc) in [(false, false), (true, false), (true, true), (false, true)] {
+            sim.states.entry(a).or_default().value = va;
+            sim.states.entry(c).or_default().value = vc;
+            assert!(sim.run_until_stable(&db));
+            let expected = if va && vc { vec![check] } else { vec![] };
+            assert_eq!(sim.get_violations(&db), expected);
+        }
+    }
+
+    #[test]
+    fn state_save_restore() {
+        let (db, dff) = clock_divider_db();
+        let mut sim = Simulator::default();
+        for _ in 0..3 {
+            sim.toggle_clocks(&db);
+            assert!(sim.run_until_stable(&db));
+        }
+        let json = sim.dump_state(&db).unwrap();
+        let mut restored = Simulator::default();
+        restored.load_state(&db, &json).unwrap();
+        assert_eq!(restored.get_steps(), sim.get_steps());
+        for _ in 0..4 {
+            assert_eq!(
+                restored.get_pin_value(&db, dff, 2),
+                sim.get_pin_value(&db, dff, 2)
+            );
+            for sim in [&mut sim, &mut restored] {
+                sim.toggle_clocks(&db);
+                assert!(sim.run_until_stable(&db));
+            }
+        }
+        assert!(restored.load_state(&db, "{}").is_err());
+
+        let state = sim.unload(&db).unwrap();
+        assert_eq!(sim.get_steps(), 0);
+        assert!(sim.unload(&db).is_none());
+        sim.load_state(&db, &state).unwrap();
+        assert_eq!(sim.get_steps(), restored.get_steps());
+    }
+
+    #[test]
+    fn unknown_and_floating_values() {
+        let mut b = GridDBBuilder::new();
+        let data_a = b.primitive(PrimitiveType::Switch, 0, 0, Rotation::ROT0);
+        let en_a = b.primitive(PrimitiveType::Switch, 0, 4, Rotation::ROT0);
+        let data_b = b.primitive(PrimitiveType::Switch, 0, 10, Rotation::ROT0);
+        let en_b = b.primitive(PrimitiveType::Switch, 0, 14, Rotation::ROT0);
+        let buf_a = b.primitive(PrimitiveType::TriState, 6, -1, Rotation::ROT0);
+        let buf_b = b.primitive(PrimitiveType::TriState, 6, 9, Rotation::ROT0);
+        let and = b.primitive(PrimitiveType::And(2), 16, 4, Rotation::ROT0);
+        let out = b.primitive(PrimitiveType::Output, 24, 4, Rotation::ROT0);
+        b.connect((and, 0), (out, 0));
+        b.connect((data_a, 0), (buf_a, 0));
+        b.connect((en_a, 0), (buf_a, 2));
+        b.connect((data_b, 0), (buf_b, 0));
+        b.connect((en_b, 0), (buf_b, 2));
+        // Shared bus, the second input of the AND gate is floating:
+        b.connect((buf_a, 1), (and, 1));
+        b.connect((buf_b, 1), (and, 1));
+        let db = b.build();
+        let mut sim = Simulator::default();
+        for (a, en_a_value, b, en_b_value, bus, out) in [
+            (true, false, false, false, Logic::Z, Logic::X),
+            (true, true, false, false, Logic::High, Logic::X),
+            (true, false, false, true, Logic::Low, Logic::Low),
+            (true, true, false, true, Logic::X, Logic::X),
+            (true, true, true, true, Logic::High, Logic::X),
+        ] {
+            for (id, value) in [
+                (data_a, a),
+                (en_a, en_a_value),
+                (data_b, b),
+                (en_b, en_b_value),
+            ] {
+                sim.states.entry(id).or_default().value = value;
+            }
+            assert!(sim.run_until_stable(&db));
+            assert_eq!(sim.get_pin_value(&db, buf_a, 1), bus);
+            assert_eq!(sim.get_pin_value(&db, and, 0), out);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! Parametric templates of schematic fragments, like an N-stage pipeline block.
+//! The fragment is a stage repeated by the count parameter from left to right,
+//! `{{name}}` placeholders in the instance names and texts are replaced by the parameter values.
+
+use serde::{Deserialize, Serialize};
+
+use crate::grid_db::{Component, GridDB, Schematic, expand_template, grid_pos};
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct TemplateParameter {
+    pub name: String,
+    pub default: i64,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Template {
+    pub name: String,
+    pub parameters: Vec<TemplateParameter>,
+    /// Parameter with the number of stages, the fragment is placed once if it's empty
+    #[serde(default)]
+    pub repeat: String,
+    pub schematic: Schematic,
+}
+
+impl Template {
+    pub const MAX_STAGES: i64 = 64;
+    /// Free columns between the stages
+    pub const STAGE_GAP: i32 = 2;
+
+    pub fn new(name: &str, fragment: &GridDB) -> Self {
+        Self {
+            name: name.to_owned(),
+            parameters: vec![],
+            repeat: String::new(),
+            schematic: fragment.to_schematic(),
+        }
+    }
+
+    pub fn get_defaults(&self) -> Vec<i64> {
+        self.parameters.iter().map(|p| p.default).collect()
+    }
+
+    fn get_stages(&self, values: &[i64]) -> i64 {
+        self.parameters
+            .iter()
+            .zip(values)
+            .find(|(p, _)| !self.repeat.is_empty() && p.name == self.repeat)
+            .map_or(1, |(_, value)| (*value).clamp(1, Self::MAX_STAGES))
+    }
+
+    /// Fragment for the parameter values. Besides the parameters,
+    /// `{{i}}`, `{{prev}}` and `{{next}}` are the indices of the stage and its neighbors,
+    /// so connectors named like `d{{i}}` and `d{{prev}}` chain the stages.
+    pub fn expand(&self, values: &[i64]) -> GridDB {
+        let stage = GridDB::from_schematic(&self.schematic);
+        let (min, max) = stage.get_bounds();
+        let step = max.x - min.x + Self::STAGE_GAP;
+        let mut result = GridDB::new();
+        for i in 0..self.get_stages(values) {
+            let lookup = |name: &str| {
+                let value = match name {
+                    "i" => i,
+                    "prev" => i - 1,
+                    "next" => i + 1,
+                    _ => {
+                        let index = self.parameters.iter().position(|p| p.name == name)?;
+                        *values.get(index)?
+                    }
+                };
+                Some(value.to_string())
+            };
+            let fragment = result.allocate_fragment(&stage, grid_pos(step * i as i32, 0));
+            for (id, mut component) in fragment.components {
+                if let Component::TextField(text_field) = &mut component {
+                    text_field.text = expand_template(&text_field.text, lookup).as_ref().into();
+                } else if let Some(name) = component.get_name_mut() {
+                    *name = expand_template(name, lookup).as_ref().into();
+                }
+                result.insert_component(id, component);
+            }
+            for (id, net) in fragment.nets {
+                result.insert_net(id, net);
+            }
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Template, TemplateParameter};
+    use crate::{
+        grid_db::{Component, GridDB, PrimitiveType, Rotation},
+        netlist::extract_netlist,
+        testing::GridDBBuilder,
+    };
+
+    #[test]
+    fn template_stages() {
+        let mut b = GridDBBuilder::new();
+        let input = b.primitive(PrimitiveType::Connector, 0, 0, Rotation::ROT180);
+        let inverter = b.primitive(PrimitiveType::Not, 3, 0, Rotation::ROT0);
+        let output = b.primitive(PrimitiveType::Connector, 8, 0, Rotation::ROT0);
+        let label = b.text("stage {{i}} of {{N}}", 0, -3);
+        b.connect((input, 0), (inverter, 0));
+        b.connect((inverter, 1), (output, 0));
+        let mut db = b.build();
+        for (id, name) in [
+            (input, "d{{prev}}"),
+            (inverter, "inv{{i}}"),
+            (output, "d{{i}}"),
+        ] {
+            let mut comp = db.remove_component(&id).unwrap();
+            *comp.get_name_mut().unwrap() = name.into();
+            db.insert_component(id, comp);
+        }
+        let mut template = Template::new(
+            "pipeline",
+            &db.extract_fragment(&[input, inverter, output, label]),
+        );
+        template.parameters.push(TemplateParameter {
+            name: "N".into(),
+            default: 2,
+        });
+        template.repeat = "N".into();
+
+        let json = serde_json::to_string(&template).unwrap();
+        let template: Template = serde_json::from_str(&json).unwrap();
+        let expanded = template.expand(&template.get_defaults());
+        assert_eq!(expanded.get_component_ids().len(), 8);
+        assert_eq!(expanded.get_net_ids().len(), 4);
+        let texts: Vec<_> = expanded
+            .get_component_ids()
+            .into_iter()
+            .filter_map(|id| match expanded.get_component(&id) {
+                Some(Component::TextField(t)) => Some(t.text.to_string()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts, ["stage 0 of 2", "stage 1 of 2"]);
+        // Connectors chain the stages:
+        let netlist = extract_netlist(&expanded);
+        let inv1 = netlist.instances.iter().find(|i| i.name == "inv1").unwrap();
+        let inv0 = netlist.instances.iter().find(|i| i.name == "inv0").unwrap();
+        assert_eq!(inv1.pins[0].net, inv0.pins[1].net);
+        assert!(inv1.pins[0].net.is_some());
+        assert_eq!(template.expand(&[1000]).get_component_ids().len(), 4 * 64);
+
+        let single = Template::new("single", &GridDB::new());
+        assert!(single.expand(&[]).get_component_ids().is_empty());
+    }
+}
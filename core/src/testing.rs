@@ -0,0 +1,358 @@
+//! Test support: deterministic schematic builders, golden snapshots and random edits generation
+
+use std::{
+    path::PathBuf,
+    sync::{Mutex, MutexGuard, PoisonError},
+};
+
+use crate::grid_db::{
+    ComparisonType, Component, DFFParams, GridDB, GridDBConnectionPoint, Id, Net, NetStyle, Port,
+    PrimitiveComponent, PrimitiveType, Rotation, TextField, Theme, Unit, grid_pos,
+};
+
+/// Builds a schematic with IDs allocated in the call order
+#[derive(Default)]
+pub struct GridDBBuilder {
+    db: GridDB,
+}
+
+impl GridDBBuilder {
+    pub fn new() -> Self {
+        Self { db: GridDB::new() }
+    }
+
+    pub fn component(&mut self, component: Component) -> Id {
+        let id = self.db.allocate_component();
+        self.db.insert_component(id, component);
+        id
+    }
+
+    pub fn primitive(&mut self, typ: PrimitiveType, x: i32, y: i32, rotation: Rotation) -> Id {
+        self.component(Component::Primitive(PrimitiveComponent {
+            typ,
+            pos: grid_pos(x, y),
+            rotation,
+            name: Default::default(),
+            source: None,
+        }))
+    }
+
+    pub fn text(&mut self, text: &str, x: i32, y: i32) -> Id {
+        self.component(Component::TextField(TextField {
+            text: text.into(),
+            size: (text.len() as i32 / 2 + 1, 1),
+            pos: grid_pos(x, y),
+            anchor: None,
+        }))
+    }
+
+    /// Connects `(component ID, connection ID)` pairs with a routed net
+    pub fn connect(&mut self, from: (Id, Id), to: (Id, Id)) -> Id {
+        let start_point = GridDBConnectionPoint {
+            component_id: from.0,
+            connection_id: from.1,
+        };
+        let end_point = GridDBConnectionPoint {
+            component_id: to.0,
+            connection_id: to.1,
+        };
+        let dock_cell = |p: &GridDBConnectionPoint| {
+            self.db
+                .get_component(&p.component_id)
+                .and_then(|c| c.get_connection_dock_cell(p.connection_id))
+                .expect("Connection must exist")
+        };
+        let net = self.db.route_net(
+            start_point,
+            dock_cell(&start_point),
+            end_point,
+            dock_cell(&end_point),
+        );
+        let id = self.db.allocate_net();
+        self.db.insert_net(id, net);
+        id
+    }
+
+    pub fn build(self) -> GridDB {
+        self.db
+    }
+}
+
+/// Half adder: two inputs, XOR and AND gates, two outputs
+pub fn half_adder() -> GridDB {
+    let mut b = GridDBBuilder::new();
+    let a = b.primitive(PrimitiveType::Input, 0, 0, Rotation::ROT0);
+    let c = b.primitive(PrimitiveType::Input, 0, 6, Rotation::ROT0);
+    let xor = b.primitive(PrimitiveType::Xor(2), 8, 0, Rotation::ROT0);
+    let and = b.primitive(PrimitiveType::And(2), 8, 6, Rotation::ROT0);
+    let sum = b.primitive(PrimitiveType::Output, 16, 1, Rotation::ROT0);
+    let carry = b.primitive(PrimitiveType::Output, 16, 7, Rotation::ROT0);
+    b.text("half adder", 0, -3);
+    b.connect((a, 0), (xor, 1));
+    b.connect((c, 0), (xor, 2));
+    b.connect((a, 0), (and, 1));
+    b.connect((c, 0), (and, 2));
+    b.connect((xor, 0), (sum, 0));
+    b.connect((and, 0), (carry, 0));
+    b.build()
+}
+
+/// Serializes the tests installing user libraries, the installed ones are shared by all tests
+pub fn lock_user_libraries() -> MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Unit with ports on every side and labeled primitives in all rotations
+pub fn rotated_symbols() -> GridDB {
+    let mut b = GridDBBuilder::new();
+    let port = |offset, align, name: &str| Port {
+        offset,
+        align,
+        name: name.into(),
+        bundle: None,
+    };
+    b.component(Component::Unit(Unit {
+        pos: grid_pos(0, 0),
+        width: 6,
+        height: 5,
+        ports: vec![
+            port(1, Rotation::ROT0, "clk"),
+            port(1, Rotation::ROT180, "q"),
+            port(2, Rotation::ROT90, "addr[3:0]"),
+            port(3, Rotation::ROT270, "rst"),
+        ],
+        parameters: vec![],
+        description: String::new(),
+        name: Default::default(),
+        source: None,
+        schematic: None,
+    }));
+    let dff = PrimitiveType::DFF(DFFParams {
+        has_enable: true,
+        has_async_reset: false,
+        has_sync_reset: false,
+        async_reset_inverted: false,
+        sync_reset_inverted: false,
+    });
+    let rotations = [
+        Rotation::ROT0,
+        Rotation::ROT90,
+        Rotation::ROT180,
+        Rotation::ROT270,
+    ];
+    for (i, rotation) in rotations.into_iter().enumerate() {
+        let x = 10 * i as i32 + 10;
+        b.primitive(dff, x, 0, rotation);
+        b.component(Component::Primitive(PrimitiveComponent {
+            typ: PrimitiveType::Connector,
+            pos: grid_pos(x, 10),
+            rotation,
+            name: "bus".into(),
+            source: None,
+        }));
+    }
+    b.build()
+}
+
+/// Project JSON with sorted keys, independent of the hash maps order
+pub fn json_snapshot(db: &GridDB) -> String {
+    let value: serde_json::Value =
+        serde_json::from_str(&db.dump_to_json().expect("Project must be serializable")).unwrap();
+    serde_json::to_string_pretty(&value).unwrap()
+}
+
+/// SVG export with the elements sorted, independent of the hash maps order
+pub fn svg_snapshot(db: &GridDB, theme: Theme) -> String {
+    let svg = db.dump_to_svg(theme, 20.0, &NetStyle::DEFAULT);
+    let lines: Vec<&str> = svg.lines().filter(|line| !line.trim().is_empty()).collect();
+    // Keep the header and the closing tag in place:
+    let body_end = lines.len() - 1;
+    // Elements written on several lines, like `<rect`, are sorted as a whole:
+    let mut elements: Vec<Vec<&str>> = vec![];
+    for line in &lines[2..body_end] {
+        match elements.last_mut() {
+            Some(element) if !element.last().unwrap().trim_end().ends_with('>') => {
+                element.push(line)
+            }
+            _ => elements.push(vec![line]),
+        }
+    }
+    elements.sort();
+    [&lines[..2], &elements.concat(), &lines[body_end..]]
+        .concat()
+        .join("\n")
+}
+
+/// Compares `actual` with `tests/golden/<name>`.
+/// Run with `UPDATE_GOLDEN=1` to write the current output as the new golden file.
+pub fn assert_golden(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(name);
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("No golden file {path:?}, run with UPDATE_GOLDEN=1"));
+    if expected != actual {
+        let line = expected
+            .lines()
+            .zip(actual.lines())
+            .position(|(e, a)| e != a)
+            .unwrap_or(expected.lines().count().min(actual.lines().count()));
+        panic!(
+            "{name} differs from the golden file at line {}:\nexpected: {:?}\nactual:   {:?}",
+            line + 1,
+            expected.lines().nth(line),
+            actual.lines().nth(line)
+        );
+    }
+}
+
+/// Small seeded generator (xorshift64*), so failures can be reproduced by the seed
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Random number in `0..n`
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    pub fn range(&mut self, min: i32, max: i32) -> i32 {
+        min + self.below((max - min) as usize) as i32
+    }
+
+    pub fn chance(&mut self, percent: usize) -> bool {
+        self.below(100) < percent
+    }
+
+    pub fn pick<T: Clone>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())].clone()
+    }
+
+    pub fn rotation(&mut self) -> Rotation {
+        self.pick(&[
+            Rotation::ROT0,
+            Rotation::ROT90,
+            Rotation::ROT180,
+            Rotation::ROT270,
+        ])
+    }
+
+    pub fn primitive_type(&mut self) -> PrimitiveType {
+        let n_inputs = self.range(2, 5) as usize;
+        match self.below(10) {
+            0 => PrimitiveType::And(n_inputs),
+            1 => PrimitiveType::Or(n_inputs),
+            2 => PrimitiveType::Xor(n_inputs),
+            3 => PrimitiveType::Nand(n_inputs),
+            4 => PrimitiveType::Not,
+            5 => PrimitiveType::Mux(n_inputs),
+            6 => PrimitiveType::Input,
+            7 => PrimitiveType::Output,
+            8 => PrimitiveType::DFF(DFFParams {
+                has_enable: self.chance(50),
+                has_async_reset: self.chance(50),
+                has_sync_reset: self.chance(50),
+                async_reset_inverted: self.chance(50),
+                sync_reset_inverted: self.chance(50),
+            }),
+            _ => PrimitiveType::Comparator(self.pick(&[ComparisonType::EQ, ComparisonType::LT])),
+        }
+    }
+
+    pub fn component(&mut self) -> Component {
+        let pos = grid_pos(self.range(-30, 30), self.range(-30, 30));
+        match self.below(10) {
+            0 => Component::TextField(TextField {
+                text: format!("label {}", self.below(100)).into(),
+                size: (self.range(1, 6), self.range(1, 3)),
+                pos,
+                anchor: None,
+            }),
+            1 => {
+                let height = self.range(2, 6);
+                Component::Unit(Unit {
+                    pos,
+                    width: self.range(2, 6),
+                    height,
+                    ports: (0..self.below(4))
+                        .map(|i| Port {
+                            offset: self.range(1, height),
+                            align: self.pick(&[Rotation::ROT0, Rotation::ROT180]),
+                            name: format!("p{i}").into(),
+                            bundle: None,
+                        })
+                        .collect(),
+                    parameters: vec![],
+                    description: String::new(),
+                    name: Default::default(),
+                    source: None,
+                    schematic: None,
+                })
+            }
+            2 => Component::Primitive(PrimitiveComponent {
+                typ: PrimitiveType::Connector,
+                pos,
+                rotation: self.rotation(),
+                name: self.pick(&["a", "b", ""]).into(),
+                source: None,
+            }),
+            _ => Component::Primitive(PrimitiveComponent {
+                typ: self.primitive_type(),
+                pos,
+                rotation: self.rotation(),
+                name: Default::default(),
+                source: None,
+            }),
+        }
+    }
+
+    /// Net between random connections of `db`, None if there are no connections
+    pub fn net(&mut self, db: &GridDB) -> Option<Net> {
+        let points: Vec<GridDBConnectionPoint> = db
+            .get_component_ids()
+            .into_iter()
+            .flat_map(|id| {
+                let n = db.get_component(&id).unwrap().get_connections_number();
+                (0..n).map(move |connection_id| GridDBConnectionPoint {
+                    component_id: id,
+                    connection_id,
+                })
+            })
+            .collect();
+        if points.is_empty() {
+            return None;
+        }
+        let (start_point, end_point) = (self.pick(&points), self.pick(&points));
+        let mut path = vec![grid_pos(self.range(-30, 30), self.range(-30, 30))];
+        for _ in 0..self.below(4) + 1 {
+            let last = *path.last().unwrap();
+            path.push(if self.chance(50) {
+                grid_pos(self.range(-30, 30), last.y)
+            } else {
+                grid_pos(last.x, self.range(-30, 30))
+            });
+        }
+        Some(Net {
+            start_point,
+            end_point,
+            points: path,
+            class: Default::default(),
+        })
+    }
+}
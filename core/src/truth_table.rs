@@ -0,0 +1,138 @@
+//! Truth table of the logic between the chosen inputs and outputs, evaluated by the simulator
+
+use crate::{
+    grid_db::{Component, GridDB, Id, Logic, PrimitiveType},
+    simulation::Simulator,
+};
+
+/// The table has `2^n` rows, so bigger numbers of inputs are refused
+pub const MAX_TRUTH_TABLE_INPUTS: usize = 16;
+
+pub struct TruthTable {
+    pub inputs: Vec<String>,
+    pub outputs: Vec<String>,
+    /// Output values for every combination of the inputs,
+    /// the first input is the most significant bit of the row index
+    pub rows: Vec<Vec<Logic>>,
+}
+
+impl TruthTable {
+    /// Value of the input in the row
+    pub fn get_input(&self, row: usize, input: usize) -> bool {
+        (row >> (self.inputs.len() - 1 - input)) & 1 == 1
+    }
+
+    pub fn to_csv(&self) -> String {
+        let escape = |name: &String| {
+            if name.contains([',', '"', '\n']) {
+                format!("\"{}\"", name.replace('"', "\"\""))
+            } else {
+                name.clone()
+            }
+        };
+        let header: Vec<String> = self
+            .inputs
+            .iter()
+            .chain(&self.outputs)
+            .map(escape)
+            .collect();
+        let mut out = header.join(",") + "\n";
+        for (row, outputs) in self.rows.iter().enumerate() {
+            let values: Vec<&str> = (0..self.inputs.len())
+                .map(|input| Logic::from(self.get_input(row, input)).to_str())
+                .chain(outputs.iter().map(|value| value.to_str()))
+                .collect();
+            out += &values.join(",");
+            out += "\n";
+        }
+        out
+    }
+}
+
+/// Sources which can be set by the table, like inputs and switches
+fn is_table_input(comp: &Component) -> bool {
+    matches!(
+        comp,
+        Component::Primitive(p)
+            if matches!(p.typ, PrimitiveType::Input | PrimitiveType::Switch | PrimitiveType::Button)
+    )
+}
+
+fn is_table_output(comp: &Component) -> bool {
+    matches!(comp, Component::Primitive(p) if p.typ == PrimitiveType::Output)
+}
+
+/// Inputs and outputs of the schematic, sorted by component ID
+pub fn get_table_io(db: &GridDB) -> (Vec<Id>, Vec<Id>) {
+    let ids = db.get_component_ids();
+    let filter = |f: fn(&Component) -> bool| -> Vec<Id> {
+        ids.iter()
+            .copied()
+            .filter(|id| db.get_component(id).is_some_and(f))
+            .collect()
+    };
+    (filter(is_table_input), filter(is_table_output))
+}
+
+/// Evaluates the outputs over all combinations of the inputs,
+/// None if there are more than [`MAX_TRUTH_TABLE_INPUTS`] inputs.
+/// Outputs of circuits with memory depend on the previous rows.
+pub fn build_truth_table(db: &GridDB, inputs: &[Id], outputs: &[Id]) -> Option<TruthTable> {
+    if inputs.len() > MAX_TRUTH_TABLE_INPUTS {
+        return None;
+    }
+    let name = |id: &Id| {
+        db.get_component(id)
+            .and_then(|comp| comp.get_instance_name(*id))
+            .unwrap_or_else(|| format!("#{id}"))
+    };
+    let mut table = TruthTable {
+        inputs: inputs.iter().map(name).collect(),
+        outputs: outputs.iter().map(name).collect(),
+        rows: vec![],
+    };
+    let mut sim = Simulator::default();
+    for row in 0..1usize << inputs.len() {
+        for (i, id) in inputs.iter().enumerate() {
+            sim.set_source_value(*id, table.get_input(row, i));
+        }
+        sim.run_until_stable(db);
+        table.rows.push(
+            outputs
+                .iter()
+                .map(|id| sim.get_pin_value(db, *id, 0))
+                .collect(),
+        );
+    }
+    Some(table)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_truth_table, get_table_io};
+    use crate::{
+        grid_db::{Logic, PrimitiveType, Rotation},
+        testing::{GridDBBuilder, half_adder},
+    };
+
+    #[test]
+    fn half_adder_truth_table() {
+        let db = half_adder();
+        let (inputs, outputs) = get_table_io(&db);
+        assert_eq!((inputs.len(), outputs.len()), (2, 2));
+        let table = build_truth_table(&db, &inputs, &outputs).unwrap();
+        let [l, h] = [Logic::Low, Logic::High];
+        assert_eq!(table.rows, [[l, l], [h, l], [h, l], [l, h]]);
+        assert_eq!(
+            table.to_csv(),
+            "input_0,input_1,output_4,output_5\n\
+             0,0,0,0\n0,1,1,0\n1,0,1,0\n1,1,0,1\n"
+        );
+
+        let mut b = GridDBBuilder::new();
+        let inputs: Vec<_> = (0..17)
+            .map(|i| b.primitive(PrimitiveType::Input, 0, 3 * i, Rotation::ROT0))
+            .collect();
+        assert!(build_truth_table(&b.build(), &inputs, &[]).is_none());
+    }
+}
@@ -0,0 +1,220 @@
+//! Heavy operations, which are offloaded to a Web Worker on wasm to keep the UI responsive.
+//! The worker (`src/bin/worker.rs` of the editor) and the page exchange JSON encoded
+//! requests and responses.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    grid_db::{
+        DocumentSize, ExportGrid, GridDB, Id, Logic, NetStyle, SvgExportJob, Theme,
+        YosysConnection,
+    },
+    netlist::{Netlist, extract_netlist},
+};
+
+#[derive(Serialize, Deserialize)]
+pub enum WorkerRequest {
+    /// SVG export of the project JSON with the embedded project data
+    ExportSvg {
+        project: String,
+        dark_theme: bool,
+        cell_size: f32,
+        net_style: NetStyle,
+        /// Back-annotated simulation values of nets
+        #[serde(default)]
+        net_values: HashMap<Id, Logic>,
+        #[serde(default)]
+        size: Option<DocumentSize>,
+        #[serde(default)]
+        grid: ExportGrid,
+    },
+    /// Routing of the nets of the imported netlist
+    Route {
+        project: String,
+        connections: Vec<YosysConnection>,
+    },
+    /// Netlist extraction for the design checks
+    ExtractNetlist { project: String },
+}
+
+#[derive(Serialize, Deserialize)]
+pub enum WorkerResponse {
+    Svg(String),
+    /// Project JSON with the routed nets
+    Project(String),
+    Netlist(Box<Netlist>),
+    Error(String),
+}
+
+impl WorkerRequest {
+    pub fn export_svg(
+        db: &GridDB,
+        theme: Theme,
+        cell_size: f32,
+        net_style: NetStyle,
+        net_values: HashMap<Id, Logic>,
+        size: Option<DocumentSize>,
+        grid: ExportGrid,
+    ) -> Option<Self> {
+        Some(Self::ExportSvg {
+            project: db.dump_to_json()?,
+            dark_theme: theme == Theme::Dark,
+            cell_size,
+            net_style,
+            net_values,
+            size,
+            grid,
+        })
+    }
+
+    pub fn route(db: &GridDB, connections: Vec<YosysConnection>) -> Option<Self> {
+        Some(Self::Route {
+            project: db.dump_to_json()?,
+            connections,
+        })
+    }
+
+    pub fn extract_netlist(db: &GridDB) -> Option<Self> {
+        Some(Self::ExtractNetlist {
+            project: db.dump_to_json()?,
+        })
+    }
+
+    /// Executes the request, called inside the worker
+    pub fn handle(self) -> WorkerResponse {
+        match self {
+            Self::ExportSvg {
+                project,
+                dark_theme,
+                cell_size,
+                net_style,
+                net_values,
+                size,
+                grid,
+            } => {
+                let db = match GridDB::load_from_json(project) {
+                    Ok(db) => db,
+                    Err(err) => return WorkerResponse::Error(err.to_string()),
+                };
+                let theme = if dark_theme {
+                    Theme::Dark
+                } else {
+                    Theme::Light
+                };
+                let result =
+                    SvgExportJob::new_with_size(&db, theme, cell_size, net_style, size, vec![])
+                        .and_then(|job| job.with_grid(grid))
+                        .map(|job| job.with_net_values(net_values))
+                        .and_then(|mut job| {
+                            while !job.step(&db, 1024)? {}
+                            job.finish(Some(&db))
+                        });
+                match result.map(String::from_utf8) {
+                    Ok(Ok(svg)) => WorkerResponse::Svg(svg),
+                    Ok(Err(err)) => WorkerResponse::Error(err.to_string()),
+                    Err(err) => WorkerResponse::Error(err.to_string()),
+                }
+            }
+            Self::Route {
+                project,
+                connections,
+            } => {
+                let mut db = match GridDB::load_from_json(project) {
+                    Ok(db) => db,
+                    Err(err) => return WorkerResponse::Error(err.to_string()),
+                };
+                db.route_connections(&connections);
+                match db.dump_to_json() {
+                    Some(project) => WorkerResponse::Project(project),
+                    None => WorkerResponse::Error("Project serialization failed".into()),
+                }
+            }
+            Self::ExtractNetlist { project } => match GridDB::load_from_json(project) {
+                Ok(db) => WorkerResponse::Netlist(Box::new(extract_netlist(&db))),
+                Err(err) => WorkerResponse::Error(err.to_string()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{WorkerRequest, WorkerResponse};
+    use crate::{
+        grid_db::{DocumentSize, ExportGrid, GridDB, Theme},
+        netlist::extract_netlist,
+        testing::half_adder,
+    };
+
+    #[test]
+    fn export_svg_request() {
+        let db = half_adder();
+        let size = DocumentSize {
+            width: 100.0,
+            height: 50.0,
+            millimeters: true,
+        };
+        let request = WorkerRequest::export_svg(
+            &db,
+            Theme::Dark,
+            20.0,
+            Default::default(),
+            HashMap::new(),
+            Some(size),
+            ExportGrid::NONE,
+        )
+        .unwrap();
+        // Requests are passed to the worker as JSON:
+        let request: WorkerRequest =
+            serde_json::from_str(&serde_json::to_string(&request).unwrap()).unwrap();
+        let WorkerResponse::Svg(svg) = request.handle() else {
+            panic!("Export failed");
+        };
+        assert!(svg.contains(r#"width="100mm" height="50mm""#));
+        let loaded = GridDB::load_from_svg(&svg).unwrap().unwrap();
+        assert_eq!(loaded.get_component_ids(), db.get_component_ids());
+    }
+
+    #[test]
+    fn route_request() {
+        let mut db = half_adder();
+        let mut connections = vec![];
+        for id in db.get_net_ids() {
+            let net = db.get_net(&id).unwrap();
+            connections.push((net.start_point, net.end_point));
+            db.remove_net(&id);
+        }
+        let request = WorkerRequest::route(&db, connections.clone()).unwrap();
+        let request: WorkerRequest =
+            serde_json::from_str(&serde_json::to_string(&request).unwrap()).unwrap();
+        let WorkerResponse::Project(project) = request.handle() else {
+            panic!("Routing failed");
+        };
+        let routed = GridDB::load_from_json(project).unwrap();
+        let routed_connections: Vec<_> = routed
+            .get_net_ids()
+            .iter()
+            .map(|id| routed.get_net(id).unwrap())
+            .map(|net| (net.start_point, net.end_point))
+            .collect();
+        assert_eq!(routed_connections, connections);
+    }
+
+    #[test]
+    fn extract_netlist_request() {
+        let db = half_adder();
+        let request = WorkerRequest::extract_netlist(&db).unwrap();
+        let request: WorkerRequest =
+            serde_json::from_str(&serde_json::to_string(&request).unwrap()).unwrap();
+        // The response is passed back as JSON too:
+        let response = serde_json::to_string(&request.handle()).unwrap();
+        let WorkerResponse::Netlist(netlist) = serde_json::from_str(&response).unwrap() else {
+            panic!("Extraction failed");
+        };
+        assert_eq!(*netlist, extract_netlist(&db));
+    }
+}
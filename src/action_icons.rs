@@ -0,0 +1,252 @@
+use std::f32::consts::PI;
+
+use egui::{
+    Align2, Color32, FontId, Painter, Pos2, Rect, Shape, Stroke, Vec2, Visuals,
+    epaint::{PathShape, PathStroke},
+    vec2,
+};
+
+use crate::grid_db::{ComponentAction, NetAction};
+
+/// Icon of the action shown in the panel over the field
+pub trait ActionIcon {
+    fn draw(&self, rect: &Rect, painter: &Painter, selected: bool, visuals: &Visuals);
+}
+
+fn draw_rotation_arrow(
+    painter: &Painter,
+    center: Pos2,
+    radius: f32,
+    clockwise: bool,
+    stroke: Stroke,
+) {
+    let sweep_angle = if clockwise { 1.7 * PI } else { -1.7 * PI };
+    let start_angle = if clockwise { 0.0 } else { PI };
+    let eps = if clockwise { 0.2 } else { -0.2 };
+
+    let num_segments = 30;
+    let mut points = Vec::with_capacity(num_segments + 1);
+
+    for i in 0..=num_segments {
+        let t = i as f32 / num_segments as f32;
+        let angle = start_angle + t * sweep_angle;
+        points.push(center + radius * Vec2::angled(angle));
+    }
+
+    painter.add(Shape::Path(PathShape {
+        points,
+        closed: false,
+        fill: Color32::TRANSPARENT,
+        stroke: PathStroke::new(stroke.width, stroke.color),
+    }));
+
+    let r_vec = radius * Vec2::angled(start_angle + sweep_angle - eps);
+    let p1 = center + r_vec + r_vec.normalized() * stroke.width;
+    let p2 = center + r_vec - r_vec.normalized() * stroke.width;
+    let p3 = if clockwise {
+        ((p2 - p1).rot90() + (p1.to_vec2() + p2.to_vec2()) / 2.0).to_pos2()
+    } else {
+        ((p1 - p2).rot90() + (p1.to_vec2() + p2.to_vec2()) / 2.0).to_pos2()
+    };
+
+    painter.add(Shape::convex_polygon(
+        vec![p1, p2, p3],
+        stroke.color,
+        Stroke::NONE,
+    ));
+}
+
+fn draw_connection_icon(center: Pos2, radius: f32, painter: &Painter, stroke: Stroke) {
+    let num_segments = 30;
+    let mut points = Vec::with_capacity(num_segments + 1);
+    let start_angle = PI * 0.25;
+    let sweep_angle = 1.5 * PI;
+    for i in 0..=num_segments {
+        let t = i as f32 / num_segments as f32;
+        let angle = start_angle + t * sweep_angle;
+        points.push(center + radius * Vec2::angled(angle));
+    }
+    painter.add(Shape::Path(PathShape {
+        points,
+        closed: false,
+        fill: Color32::TRANSPARENT,
+        stroke: PathStroke::new(stroke.width, stroke.color),
+    }));
+    painter.line_segment(
+        [
+            center - vec2(radius, 0.0),
+            center - vec2(radius * 1.75, 0.0),
+        ],
+        stroke,
+    );
+    painter.line_segment([center, center + vec2(radius * 1.0, 0.0)], stroke);
+    painter.circle_filled(center, stroke.width, stroke.color);
+}
+
+impl ActionIcon for ComponentAction {
+    fn draw(&self, rect: &Rect, painter: &Painter, selected: bool, visuals: &Visuals) {
+        let stroke = if selected {
+            Stroke::new(rect.height() / 8.0, visuals.strong_text_color())
+        } else {
+            Stroke::new(rect.height() / 8.0, visuals.text_color())
+        };
+        match self {
+            Self::RotateDown => {
+                draw_rotation_arrow(
+                    &painter,
+                    rect.center(),
+                    rect.height() * 0.3,
+                    false,
+                    stroke,
+                );
+            }
+            Self::RotateUp => {
+                draw_rotation_arrow(
+                    &painter,
+                    rect.center(),
+                    rect.height() * 0.3,
+                    true,
+                    stroke,
+                );
+            }
+            Self::Remove => {
+                let scaled = rect.scale_from_center(0.6);
+                painter.line_segment([scaled.left_top(), scaled.right_bottom()], stroke);
+                painter.line_segment([scaled.left_bottom(), scaled.right_top()], stroke);
+            }
+            Self::AddPort => {
+                painter.text(
+                    rect.min + vec2(rect.height() * 0.05, rect.height() * 0.05),
+                    Align2::LEFT_TOP,
+                    "+",
+                    FontId::monospace(rect.height() * 0.5),
+                    stroke.color,
+                );
+                let stroke2 = Stroke {
+                    color: stroke.color,
+                    width: stroke.width * 0.75,
+                };
+                draw_connection_icon(
+                    rect.center() + vec2(rect.height() * 0.1, rect.height() * 0.1),
+                    rect.height() * 0.3 * 0.75,
+                    painter,
+                    stroke2,
+                );
+            }
+            Self::RemovePort => {
+                painter.text(
+                    rect.min + vec2(rect.height() * 0.05, rect.height() * 0.05),
+                    Align2::LEFT_TOP,
+                    "×",
+                    FontId::monospace(rect.height() * 0.5),
+                    stroke.color,
+                );
+                let stroke2 = Stroke {
+                    color: stroke.color,
+                    width: stroke.width * 0.75,
+                };
+                draw_connection_icon(
+                    rect.center() + vec2(rect.height() * 0.1, rect.height() * 0.1),
+                    rect.height() * 0.3 * 0.75,
+                    painter,
+                    stroke2,
+                );
+            }
+            Self::EditPort => {
+                painter.text(
+                    rect.min + vec2(rect.height() * 0.05, rect.height() * 0.05),
+                    Align2::LEFT_TOP,
+                    "📝",
+                    FontId::monospace(rect.height() * 0.5),
+                    stroke.color,
+                );
+                let stroke2 = Stroke {
+                    color: stroke.color,
+                    width: stroke.width * 0.75,
+                };
+                draw_connection_icon(
+                    rect.center() + vec2(rect.height() * 0.1, rect.height() * 0.1),
+                    rect.height() * 0.3 * 0.75,
+                    painter,
+                    stroke2,
+                );
+            }
+            Self::EditText => {
+                painter.text(
+                    rect.center(),
+                    Align2::CENTER_CENTER,
+                    "📝",
+                    FontId::monospace(rect.height()),
+                    stroke.color,
+                );
+            }
+            Self::Customize => {
+                painter.text(
+                    rect.center(),
+                    Align2::CENTER_CENTER,
+                    "⚙",
+                    FontId::monospace(rect.height()),
+                    stroke.color,
+                );
+            }
+            Self::SwapPins => {
+                painter.text(
+                    rect.min + vec2(rect.height() * 0.05, rect.height() * 0.05),
+                    Align2::LEFT_TOP,
+                    "⇅",
+                    FontId::monospace(rect.height() * 0.5),
+                    stroke.color,
+                );
+                let stroke2 = Stroke {
+                    color: stroke.color,
+                    width: stroke.width * 0.75,
+                };
+                draw_connection_icon(
+                    rect.center() + vec2(rect.height() * 0.1, rect.height() * 0.1),
+                    rect.height() * 0.3 * 0.75,
+                    painter,
+                    stroke2,
+                );
+            }
+            Self::Replace => {
+                painter.text(
+                    rect.center(),
+                    Align2::CENTER_CENTER,
+                    "🔄",
+                    FontId::monospace(rect.height() * 0.8),
+                    stroke.color,
+                );
+            }
+            Self::JumpToConnector => {
+                painter.text(
+                    rect.center(),
+                    Align2::CENTER_CENTER,
+                    "➡",
+                    FontId::monospace(rect.height() * 0.8),
+                    stroke.color,
+                );
+            }
+            _ => {}
+        }
+    }
+}
+
+impl ActionIcon for NetAction {
+    fn draw(&self, rect: &Rect, painter: &Painter, selected: bool, visuals: &Visuals) {
+        let stroke = if selected {
+            Stroke::new(rect.height() / 8.0, visuals.strong_text_color())
+        } else {
+            Stroke::new(rect.height() / 8.0, visuals.text_color())
+        };
+        let scaled = rect.scale_from_center(0.6);
+        match self {
+            Self::RemoveNet => {
+                painter.line_segment([scaled.left_top(), scaled.right_bottom()], stroke);
+                painter.line_segment([scaled.left_bottom(), scaled.right_top()], stroke);
+            },
+            Self::InsertPoint => {
+                painter.circle_filled(scaled.center(), stroke.width * 1.3, stroke.color);
+                painter.line_segment([scaled.left_center(), scaled.right_center()], stroke);            }
+        }
+    }
+}
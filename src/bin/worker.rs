@@ -0,0 +1,33 @@
+//! Web Worker executing `WorkerRequest`s sent by the editor page.
+//! Built by trunk as a separate wasm module, see `index.html`.
+
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    use editor_core::worker::{WorkerRequest, WorkerResponse};
+    use eframe::wasm_bindgen::{JsCast, JsValue, closure::Closure};
+
+    let scope: web_sys::DedicatedWorkerGlobalScope = js_sys::global().unchecked_into();
+    let reply_scope = scope.clone();
+    let on_message =
+        Closure::<dyn FnMut(web_sys::MessageEvent)>::new(move |event: web_sys::MessageEvent| {
+            let response = event
+                .data()
+                .as_string()
+                .and_then(|data| serde_json::from_str::<WorkerRequest>(&data).ok())
+                .map_or(
+                    WorkerResponse::Error("Invalid worker request".into()),
+                    |request| request.handle(),
+                );
+            if let Ok(response) = serde_json::to_string(&response) {
+                reply_scope.post_message(&JsValue::from_str(&response)).ok();
+            }
+        });
+    scope.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+    // The worker lives until it is terminated by the page
+    on_message.forget();
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    eprintln!("The worker is used only by the web version");
+}
@@ -0,0 +1,49 @@
+use egui::{Painter, Rect, Shape, epaint::Fonts};
+
+use crate::grid_db::Theme;
+
+pub use editor_core::canvas::Canvas;
+
+/// Canvas of the core over the egui painter
+#[derive(Clone)]
+pub struct PainterCanvas(pub Painter);
+
+impl Canvas for PainterCanvas {
+    fn add(&self, shape: impl Into<Shape>) {
+        self.0.add(shape);
+    }
+
+    fn fonts<R>(&self, reader: impl FnOnce(&Fonts) -> R) -> R {
+        self.0.fonts(reader)
+    }
+
+    fn clip_rect(&self) -> Rect {
+        self.0.clip_rect()
+    }
+
+    fn with_clip_rect(&self, rect: Rect) -> Self {
+        Self(self.0.with_clip_rect(rect))
+    }
+
+    fn theme(&self) -> Theme {
+        self.0.ctx().theme().schematic()
+    }
+
+    fn request_repaint(&self) {
+        self.0.ctx().request_repaint();
+    }
+}
+
+pub trait SchematicTheme {
+    /// Theme of the schematic drawn with the egui theme
+    fn schematic(self) -> Theme;
+}
+
+impl SchematicTheme for egui::Theme {
+    fn schematic(self) -> Theme {
+        match self {
+            Self::Dark => Theme::Dark,
+            Self::Light => Theme::Light,
+        }
+    }
+}
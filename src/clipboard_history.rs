@@ -0,0 +1,140 @@
+//! Recently copied fragments, they can be pasted again from the history window
+
+use std::collections::VecDeque;
+
+use crate::{grid_db::GridDB, locale::Locale, thumbnail::encode_thumbnail};
+
+struct ClipboardEntry {
+    /// Fragment as project JSON, like in the clipboard
+    json: String,
+    components: usize,
+    /// URI of the thumbnail image
+    uri: String,
+    thumbnail: Option<egui::load::Bytes>,
+}
+
+#[derive(Default)]
+pub struct ClipboardHistory {
+    pub is_open: bool,
+    /// The last copied fragment is first
+    entries: VecDeque<ClipboardEntry>,
+    /// Images of the removed entries, to be forgotten by the context
+    removed: Vec<String>,
+    next_serial: u64,
+}
+
+impl ClipboardHistory {
+    pub const MAX_ENTRIES: usize = 10;
+    const THUMBNAIL_SIZE: f32 = 64.0;
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Adds the copied fragment, the same fragment copied again is moved to the top
+    pub fn push(&mut self, fragment: &GridDB) {
+        let Some(json) = fragment.dump_to_json() else {
+            return;
+        };
+        if let Some(i) = self.entries.iter().position(|e| e.json == json) {
+            let entry = self.entries.remove(i).unwrap();
+            self.entries.push_front(entry);
+            return;
+        }
+        self.entries.push_front(ClipboardEntry {
+            json,
+            components: fragment.get_component_ids().len(),
+            uri: format!("bytes://clipboard_history/{}.png", self.next_serial),
+            thumbnail: encode_thumbnail(fragment).map(Into::into),
+        });
+        self.next_serial += 1;
+        while self.entries.len() > Self::MAX_ENTRIES {
+            let entry = self.entries.pop_back().unwrap();
+            self.removed.push(entry.uri);
+        }
+    }
+
+    /// Fragment copied `index` copies ago
+    pub fn get(&self, index: usize) -> Option<GridDB> {
+        GridDB::load_from_json(self.entries.get(index)?.json.clone()).ok()
+    }
+
+    /// Shows the history, returns the fragment chosen to be pasted
+    pub fn show(&mut self, ctx: &egui::Context, locale: &'static Locale) -> Option<GridDB> {
+        for uri in self.removed.drain(..) {
+            ctx.forget_image(&uri);
+        }
+        let mut chosen = None;
+        let mut is_open = self.is_open;
+        egui::Window::new(locale.paste_from_history)
+            .id("clipboard_history".into())
+            .open(&mut is_open)
+            .default_width(300.0)
+            .show(ctx, |ui| {
+                if self.entries.is_empty() {
+                    ui.label(locale.clipboard_history_is_empty);
+                    return;
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        for (i, entry) in self.entries.iter().enumerate() {
+                            let text = format!("{}: {}", locale.components, entry.components);
+                            let size = egui::Vec2::splat(Self::THUMBNAIL_SIZE);
+                            let button = match &entry.thumbnail {
+                                Some(png) => egui::Button::image_and_text(
+                                    egui::Image::from_bytes(entry.uri.clone(), png.clone())
+                                        .fit_to_exact_size(size),
+                                    text,
+                                ),
+                                None => egui::Button::new(text),
+                            };
+                            if ui.add(button).clicked() {
+                                chosen = Some(i);
+                            }
+                        }
+                    });
+            });
+        let result = chosen.and_then(|i| self.get(i));
+        self.is_open = is_open && result.is_none();
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ClipboardHistory;
+    use crate::{
+        grid_db::{PrimitiveType, Rotation},
+        testing::{GridDBBuilder, json_snapshot},
+    };
+
+    #[test]
+    fn clipboard_history() {
+        let mut history = ClipboardHistory::default();
+        let fragments: Vec<_> = (0..ClipboardHistory::MAX_ENTRIES + 2)
+            .map(|i| {
+                let mut b = GridDBBuilder::new();
+                b.primitive(PrimitiveType::Not, i as i32 * 4, 0, Rotation::ROT0);
+                b.build()
+            })
+            .collect();
+        for fragment in &fragments {
+            history.push(fragment);
+        }
+        assert_eq!(history.entries.len(), ClipboardHistory::MAX_ENTRIES);
+        let last = fragments.last().unwrap();
+        assert_eq!(json_snapshot(&history.get(0).unwrap()), json_snapshot(last));
+        // The oldest fragments are dropped:
+        assert_eq!(history.removed.len(), 2);
+        // Copying again moves the fragment to the top:
+        history.push(&fragments[5]);
+        assert_eq!(history.entries.len(), ClipboardHistory::MAX_ENTRIES);
+        assert_eq!(
+            json_snapshot(&history.get(0).unwrap()),
+            json_snapshot(&fragments[5])
+        );
+        assert_eq!(json_snapshot(&history.get(1).unwrap()), json_snapshot(last));
+        assert!(history.get(ClipboardHistory::MAX_ENTRIES).is_none());
+    }
+}
@@ -1,7 +1,11 @@
+use std::{collections::BTreeSet, mem::discriminant, sync::RwLock};
+
+use serde::{Deserialize, Serialize};
+
 use crate::{
     grid_db::{
-        Component, DFFParams, Port, PrimitiveComponent, PrimitiveType, Rotation, TextField, Unit,
-        grid_pos,
+        Component, DFFParams, LibrarySource, Parameter, Port, PrimitiveComponent, PrimitiveType,
+        Rotation, TextField, Unit, grid_pos,
     },
     locale::Locale,
 };
@@ -12,6 +16,242 @@ pub struct ComponentLibEntry {
     pub component: Component,
 }
 
+/// File name suffix of the component library files
+pub const LIBRARY_SUFFIX: &str = ".lib.json";
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UserLibraryEntry {
+    pub name: String,
+    pub component: Component,
+}
+
+/// Component library installed by the user from a `.lib.json` file
+#[derive(Clone, Serialize, Deserialize)]
+pub struct UserLibrary {
+    pub name: String,
+    /// Increased by the library author after changes, placed components offer the update
+    #[serde(default)]
+    pub version: u32,
+    pub components: Vec<UserLibraryEntry>,
+}
+
+struct InstalledLibrary {
+    name: &'static str,
+    entries: Vec<ComponentLibEntry>,
+    source: UserLibrary,
+}
+
+static USER_LIBRARIES: RwLock<Vec<InstalledLibrary>> = RwLock::new(Vec::new());
+
+/// Names of the installed libraries, their entries and the entries with the default parameters,
+/// like `AND3`. Each one is leaked only once.
+static NAMES: RwLock<BTreeSet<&'static str>> = RwLock::new(BTreeSet::new());
+
+/// Built-in component group: its entries and localized name
+type BuiltinGroup = (fn() -> Vec<ComponentLibEntry>, fn(&Locale) -> &'static str);
+
+/// Built-in component groups, the user libraries follow them
+const BUILTIN_GROUP_LIST: &[BuiltinGroup] = &[
+    (get_gates, |locale| locale.logic_gates),
+    (get_muxes, |locale| locale.muxes),
+    (get_arithmetic, |locale| locale.arithmetic_primitives),
+    (get_io, |locale| locale.input_outputs),
+    (get_units_examples, |locale| locale.custom_units),
+    (get_flip_flops, |locale| locale.flip_flops),
+    (get_text_labels, |locale| locale.text_labels),
+];
+/// User library receiving the units extracted from the selection
+pub const EXTRACTED_UNITS_LIBRARY: &str = "Extracted units";
+
+/// Number of built-in component groups
+const BUILTIN_GROUPS: usize = BUILTIN_GROUP_LIST.len();
+
+/// Returns the static copy of the name, so reinstalling a library doesn't leak it again
+fn intern_name(name: &str) -> &'static str {
+    if let Some(interned) = NAMES.read().unwrap().get(name) {
+        return interned;
+    }
+    let mut names = NAMES.write().unwrap();
+    match names.get(name) {
+        Some(interned) => interned,
+        None => {
+            let interned: &'static str = name.to_string().leak();
+            names.insert(interned);
+            interned
+        }
+    }
+}
+
+/// Parameters of the new primitives set by the user, one per type, like DFF with async reset
+static COMPONENT_DEFAULTS: RwLock<Vec<PrimitiveType>> = RwLock::new(Vec::new());
+
+/// Installs the library, replacing the installed one with the same name
+pub fn install_library(library: UserLibrary) {
+    let installed = InstalledLibrary {
+        name: intern_name(&library.name),
+        entries: library
+            .components
+            .iter()
+            .map(|entry| {
+                let mut component = entry.component.clone();
+                component.set_library_source(LibrarySource {
+                    library: library.name.as_str().into(),
+                    entry: entry.name.as_str().into(),
+                    version: library.version,
+                });
+                ComponentLibEntry {
+                    name: intern_name(&entry.name),
+                    component,
+                }
+            })
+            .collect(),
+        source: library,
+    };
+    let mut libraries = USER_LIBRARIES.write().unwrap();
+    match libraries.iter_mut().find(|lib| lib.name == installed.name) {
+        Some(lib) => *lib = installed,
+        None => libraries.push(installed),
+    }
+}
+
+/// Adds the part to the installed library, replacing the entry with the same name.
+/// The library is created if it's not installed.
+/// Returns the source to be set on the placed instances of the part.
+pub fn add_library_entry(library: &str, entry: UserLibraryEntry) -> LibrarySource {
+    let mut source = USER_LIBRARIES
+        .read()
+        .unwrap()
+        .iter()
+        .find(|lib| lib.name == library)
+        .map(|lib| lib.source.clone())
+        .unwrap_or_else(|| UserLibrary {
+            name: library.to_owned(),
+            version: 0,
+            components: vec![],
+        });
+    let result = LibrarySource {
+        library: library.into(),
+        entry: entry.name.as_str().into(),
+        version: source.version,
+    };
+    match source.components.iter_mut().find(|e| e.name == entry.name) {
+        Some(e) => *e = entry,
+        None => source.components.push(entry),
+    }
+    install_library(source);
+    result
+}
+
+pub fn uninstall_library(group_id: usize) {
+    let mut libraries = USER_LIBRARIES.write().unwrap();
+    if group_id >= BUILTIN_GROUPS && group_id - BUILTIN_GROUPS < libraries.len() {
+        libraries.remove(group_id - BUILTIN_GROUPS);
+    }
+}
+
+pub fn is_user_library(group_id: usize) -> bool {
+    group_id >= BUILTIN_GROUPS
+}
+
+/// Newer version of the library part the component was placed from: the version and the part
+pub fn get_library_update(source: &LibrarySource) -> Option<(u32, Component)> {
+    let libraries = USER_LIBRARIES.read().unwrap();
+    let library = libraries.iter().find(|lib| *lib.name == *source.library)?;
+    if library.source.version <= source.version {
+        return None;
+    }
+    let entry = library
+        .entries
+        .iter()
+        .find(|entry| *entry.name == *source.entry)?;
+    Some((library.source.version, entry.component.clone()))
+}
+
+/// Newer version of the library part placed instead of `component`,
+/// with its position, rotation and instance name
+pub fn get_updated_component(component: &Component) -> Option<Component> {
+    let mut source = component.get_library_source()?.clone();
+    let (version, mut updated) = get_library_update(&source)?;
+    source.version = version;
+    updated.set_library_source(source);
+    updated.set_pos(component.get_position());
+    if let (Component::Primitive(new_p), Component::Primitive(old_p)) = (&mut updated, component) {
+        new_p.rotation = old_p.rotation;
+        new_p.name = old_p.name.clone();
+    }
+    if let (Component::Unit(new_unit), Component::Unit(old_unit)) = (&mut updated, component) {
+        new_unit.name = old_unit.name.clone();
+    }
+    Some(updated)
+}
+
+/// Replaces the default parameters of the new primitives, stored in the settings
+pub fn set_component_defaults(defaults: Vec<PrimitiveType>) {
+    *COMPONENT_DEFAULTS.write().unwrap() = defaults;
+}
+
+pub fn get_component_defaults() -> Vec<PrimitiveType> {
+    COMPONENT_DEFAULTS.read().unwrap().clone()
+}
+
+/// Sets the parameters of the new primitives of the same type as `typ`
+pub fn set_component_default(typ: PrimitiveType) {
+    let mut defaults = COMPONENT_DEFAULTS.write().unwrap();
+    match defaults
+        .iter_mut()
+        .find(|t| discriminant(*t) == discriminant(&typ))
+    {
+        Some(t) => *t = typ,
+        None => defaults.push(typ),
+    }
+}
+
+/// Built-in entries with the default parameters of their types.
+/// Entries named by the type, like `AND2`, are renamed.
+fn apply_component_defaults(
+    mut entries: Vec<ComponentLibEntry>,
+    defaults: &[PrimitiveType],
+) -> Vec<ComponentLibEntry> {
+    for entry in &mut entries {
+        let Component::Primitive(p) = &mut entry.component else {
+            continue;
+        };
+        let Some(typ) = defaults
+            .iter()
+            .find(|t| discriminant(*t) == discriminant(&p.typ))
+        else {
+            continue;
+        };
+        if entry.name == p.typ.get_type_name() {
+            entry.name = intern_name(&typ.get_type_name());
+        }
+        p.typ = *typ;
+    }
+    entries
+}
+
+/// Customizable built-in primitives with the parameters of the new instances
+pub fn get_customizable_types() -> Vec<PrimitiveType> {
+    get_builtin_groups()
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| match entry.component {
+            Component::Primitive(p) if p.typ.is_customizable() => Some(p.typ),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Installed libraries, to be stored in the settings
+pub fn get_user_libraries() -> Vec<UserLibrary> {
+    USER_LIBRARIES
+        .read()
+        .unwrap()
+        .iter()
+        .map(|lib| lib.source.clone())
+        .collect()
+}
+
 fn get_io() -> Vec<ComponentLibEntry> {
     vec![
         ComponentLibEntry {
@@ -20,6 +260,8 @@ fn get_io() -> Vec<ComponentLibEntry> {
                 typ: PrimitiveType::Input,
                 pos: grid_pos(1, 1), // Default preview pos
                 rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
             }),
         },
         ComponentLibEntry {
@@ -28,6 +270,104 @@ fn get_io() -> Vec<ComponentLibEntry> {
                 typ: PrimitiveType::Output,
                 pos: grid_pos(1, 1), // Default preview pos
                 rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
+            }),
+        },
+        ComponentLibEntry {
+            name: "CONNECTOR",
+            component: Component::Primitive(PrimitiveComponent {
+                typ: PrimitiveType::Connector,
+                pos: grid_pos(1, 1), // Default preview pos
+                rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
+            }),
+        },
+        ComponentLibEntry {
+            name: "CLOCK",
+            component: Component::Primitive(PrimitiveComponent {
+                typ: PrimitiveType::Clock,
+                pos: grid_pos(1, 1), // Default preview pos
+                rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
+            }),
+        },
+        ComponentLibEntry {
+            name: "BUTTON",
+            component: Component::Primitive(PrimitiveComponent {
+                typ: PrimitiveType::Button,
+                pos: grid_pos(1, 1), // Default preview pos
+                rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
+            }),
+        },
+        ComponentLibEntry {
+            name: "SWITCH",
+            component: Component::Primitive(PrimitiveComponent {
+                typ: PrimitiveType::Switch,
+                pos: grid_pos(1, 1), // Default preview pos
+                rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
+            }),
+        },
+        ComponentLibEntry {
+            name: "DIP4",
+            component: Component::Primitive(PrimitiveComponent {
+                typ: PrimitiveType::DipSwitch(4),
+                pos: grid_pos(1, 1), // Default preview pos
+                rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
+            }),
+        },
+        ComponentLibEntry {
+            name: "KEYPAD",
+            component: Component::Primitive(PrimitiveComponent {
+                typ: PrimitiveType::HexKeypad,
+                pos: grid_pos(1, 1), // Default preview pos
+                rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
+            }),
+        },
+        ComponentLibEntry {
+            name: "PATTERN",
+            component: Component::Primitive(PrimitiveComponent {
+                typ: PrimitiveType::Pattern {
+                    pattern: 0b0110,
+                    length: 4,
+                },
+                pos: grid_pos(1, 1), // Default preview pos
+                rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
+            }),
+        },
+        ComponentLibEntry {
+            name: "RANDOM",
+            component: Component::Primitive(PrimitiveComponent {
+                typ: PrimitiveType::Random { width: 1, seed: 1 },
+                pos: grid_pos(1, 1), // Default preview pos
+                rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
+            }),
+        },
+        ComponentLibEntry {
+            name: "ASSERT",
+            component: Component::Primitive(PrimitiveComponent {
+                typ: PrimitiveType::Assertion {
+                    condition: crate::grid_db::AssertionType::NotAll,
+                    n_inputs: 2,
+                },
+                pos: grid_pos(1, 1), // Default preview pos
+                rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
             }),
         },
         ComponentLibEntry {
@@ -36,6 +376,8 @@ fn get_io() -> Vec<ComponentLibEntry> {
                 typ: PrimitiveType::Point,
                 pos: grid_pos(1, 1), // Default preview pos
                 rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
             }),
         },
     ]
@@ -48,6 +390,8 @@ fn get_muxes() -> Vec<ComponentLibEntry> {
             typ: PrimitiveType::Mux(2),
             pos: grid_pos(1, 1), // Default preview pos
             rotation: crate::grid_db::Rotation::ROT0,
+            name: Default::default(),
+            source: None,
         }),
     }]
 }
@@ -60,6 +404,8 @@ fn get_gates() -> Vec<ComponentLibEntry> {
                 typ: PrimitiveType::And(2),
                 pos: grid_pos(1, 1), // Default preview pos
                 rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
             }),
         },
         ComponentLibEntry {
@@ -68,6 +414,8 @@ fn get_gates() -> Vec<ComponentLibEntry> {
                 typ: PrimitiveType::Or(2),
                 pos: grid_pos(1, 1), // Default preview pos
                 rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
             }),
         },
         ComponentLibEntry {
@@ -76,6 +424,8 @@ fn get_gates() -> Vec<ComponentLibEntry> {
                 typ: PrimitiveType::Xor(2),
                 pos: grid_pos(1, 1), // Default preview pos
                 rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
             }),
         },
         ComponentLibEntry {
@@ -84,6 +434,8 @@ fn get_gates() -> Vec<ComponentLibEntry> {
                 typ: PrimitiveType::Nand(2),
                 pos: grid_pos(1, 1), // Default preview pos
                 rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
             }),
         },
         ComponentLibEntry {
@@ -92,6 +444,18 @@ fn get_gates() -> Vec<ComponentLibEntry> {
                 typ: PrimitiveType::Not,
                 pos: grid_pos(1, 1), // Default preview pos
                 rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
+            }),
+        },
+        ComponentLibEntry {
+            name: "TRIBUF",
+            component: Component::Primitive(PrimitiveComponent {
+                typ: PrimitiveType::TriState,
+                pos: grid_pos(1, 1), // Default preview pos
+                rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
             }),
         },
     ]
@@ -106,6 +470,11 @@ fn get_units_examples() -> Vec<ComponentLibEntry> {
                 width: 5,
                 height: 5,
                 ports: vec![],
+                parameters: vec![],
+                description: String::new(),
+                name: Default::default(),
+                source: None,
+                schematic: None,
             }),
         },
         ComponentLibEntry {
@@ -118,44 +487,60 @@ fn get_units_examples() -> Vec<ComponentLibEntry> {
                     Port {
                         offset: 3,
                         align: Rotation::ROT0,
-                        name: "vld".to_owned(),
+                        name: "vld".into(),
+                        bundle: None,
                     },
                     Port {
                         offset: 4,
                         align: Rotation::ROT0,
-                        name: "data1".to_owned(),
+                        name: "data1".into(),
+                        bundle: None,
                     },
                     Port {
                         offset: 5,
                         align: Rotation::ROT0,
-                        name: "data2".to_owned(),
+                        name: "data2".into(),
+                        bundle: None,
                     },
                     Port {
                         offset: 1,
                         align: Rotation::ROT180,
-                        name: "vld".to_owned(),
+                        name: "vld".into(),
+                        bundle: None,
                     },
                     Port {
                         offset: 2,
                         align: Rotation::ROT180,
-                        name: "data1".to_owned(),
+                        name: "data1".into(),
+                        bundle: None,
                     },
                     Port {
                         offset: 3,
                         align: Rotation::ROT180,
-                        name: "data2".to_owned(),
+                        name: "data2".into(),
+                        bundle: None,
                     },
                     Port {
                         offset: 2,
                         align: Rotation::ROT90,
-                        name: "error".to_owned(),
+                        name: "error".into(),
+                        bundle: None,
                     },
                     Port {
                         offset: 2,
                         align: Rotation::ROT270,
-                        name: "clk".to_owned(),
+                        name: "clk".into(),
+                        bundle: None,
                     },
                 ],
+                parameters: vec![Parameter {
+                    name: "WIDTH".into(),
+                    value: "8".into(),
+                }],
+                description: "Example block with inputs, outputs and a clock".into(),
+                name: Default::default(),
+                source: None,
+                schematic: None,
             }),
         },
     ]
@@ -169,6 +554,8 @@ fn get_arithmetic() -> Vec<ComponentLibEntry> {
                 typ: PrimitiveType::Comparator(crate::grid_db::ComparisonType::EQ),
                 pos: grid_pos(1, 1), // Default preview pos
                 rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
             }),
         },
         ComponentLibEntry {
@@ -180,6 +567,8 @@ fn get_arithmetic() -> Vec<ComponentLibEntry> {
                 },
                 pos: grid_pos(1, 1), // Default preview pos
                 rotation: crate::grid_db::Rotation::ROT0,
+                name: Default::default(),
+                source: None,
             }),
         },
     ]
@@ -198,6 +587,8 @@ fn get_flip_flops() -> Vec<ComponentLibEntry> {
             }),
             pos: grid_pos(1, 1), // Default preview pos
             rotation: crate::grid_db::Rotation::ROT0,
+            name: Default::default(),
+            source: None,
         }),
     }]
 }
@@ -209,20 +600,30 @@ fn get_text_labels() -> Vec<ComponentLibEntry> {
             pos: grid_pos(1, 1), // Default preview pos
             size: (4, 1),
             text: "Some text".into(),
+            anchor: None,
         }),
     }]
 }
 
+fn get_builtin_groups() -> Vec<Vec<ComponentLibEntry>> {
+    let defaults = COMPONENT_DEFAULTS.read().unwrap();
+    BUILTIN_GROUP_LIST
+        .iter()
+        .map(|(group, _)| apply_component_defaults(group(), &defaults))
+        .collect()
+}
+
 pub fn get_component_lib() -> Vec<Vec<ComponentLibEntry>> {
-    vec![
-        get_gates(),
-        get_muxes(),
-        get_arithmetic(),
-        get_io(),
-        get_units_examples(),
-        get_flip_flops(),
-        get_text_labels(),
-    ]
+    get_builtin_groups()
+        .into_iter()
+        .chain(
+            USER_LIBRARIES
+                .read()
+                .unwrap()
+                .iter()
+                .map(|lib| lib.entries.clone()),
+        )
+        .collect()
 }
 
 pub fn get_component_lib_with_query(query: &String) -> Vec<Vec<ComponentLibEntry>> {
@@ -248,14 +649,103 @@ pub fn get_component_lib_with_query(query: &String) -> Vec<Vec<ComponentLibEntry
 }
 
 pub fn get_group_name(group_id: usize, locale: &Locale) -> &'static str {
-    match group_id {
-        0 => locale.logic_gates,
-        1 => locale.muxes,
-        2 => locale.arithmetic_primitives,
-        3 => locale.input_outputs,
-        4 => locale.custom_units,
-        5 => locale.flip_flops,
-        6 => locale.text_labels,
-        _ => "",
+    match BUILTIN_GROUP_LIST.get(group_id) {
+        Some((_, name)) => name(locale),
+        None => USER_LIBRARIES
+            .read()
+            .unwrap()
+            .get(group_id - BUILTIN_GROUPS)
+            .map_or("", |lib| lib.name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        BUILTIN_GROUPS, UserLibrary, UserLibraryEntry, apply_component_defaults, get_component_lib,
+        get_flip_flops, get_gates, get_library_update, get_user_libraries, install_library,
+        uninstall_library,
+    };
+    use crate::{
+        grid_db::{Component, DFFParams, PrimitiveType, Unit, grid_pos},
+        testing::lock_user_libraries,
+    };
+
+    fn library(version: u32, width: i32) -> UserLibrary {
+        UserLibrary {
+            name: "pinning_test".into(),
+            version,
+            components: vec![UserLibraryEntry {
+                name: "ZZ_REG".into(),
+                component: Component::Unit(Unit {
+                    pos: grid_pos(1, 1),
+                    width,
+                    height: 3,
+                    ports: vec![],
+                    parameters: vec![],
+                    description: String::new(),
+                    name: Default::default(),
+                    source: None,
+                    schematic: None,
+                }),
+            }],
+        }
+    }
+
+    #[test]
+    fn library_revision_pinning() {
+        let _lock = lock_user_libraries();
+        install_library(library(1, 3));
+        let placed = get_component_lib()
+            .into_iter()
+            .flatten()
+            .find(|entry| entry.name == "ZZ_REG")
+            .unwrap()
+            .component;
+        let source = placed.get_library_source().unwrap().clone();
+        assert_eq!(
+            (&*source.library, &*source.entry, source.version),
+            ("pinning_test", "ZZ_REG", 1)
+        );
+        assert!(get_library_update(&source).is_none());
+
+        install_library(library(2, 5));
+        let (version, updated) = get_library_update(&source).unwrap();
+        assert_eq!(version, 2);
+        assert_eq!(updated.get_dimension(), (5, 3));
+
+        let group = get_user_libraries()
+            .iter()
+            .position(|lib| lib.name == "pinning_test")
+            .unwrap();
+        uninstall_library(BUILTIN_GROUPS + group);
+        assert!(get_library_update(&source).is_none());
+    }
+
+    #[test]
+    fn component_defaults() {
+        let dff = DFFParams {
+            has_enable: false,
+            has_async_reset: true,
+            has_sync_reset: false,
+            async_reset_inverted: true,
+            sync_reset_inverted: false,
+        };
+        let defaults = [PrimitiveType::And(3), PrimitiveType::DFF(dff)];
+        let get_types = |entries: Vec<super::ComponentLibEntry>| -> Vec<_> {
+            entries
+                .into_iter()
+                .filter_map(|entry| match entry.component {
+                    Component::Primitive(p) => Some((entry.name, p.typ)),
+                    _ => None,
+                })
+                .collect()
+        };
+        let gates = get_types(apply_component_defaults(get_gates(), &defaults));
+        assert!(gates[0] == ("AND3", PrimitiveType::And(3)));
+        // Other gates are not changed:
+        assert!(gates[1] == ("OR2", PrimitiveType::Or(2)));
+        let flip_flops = get_types(apply_component_defaults(get_flip_flops(), &defaults));
+        assert!(flip_flops == [("DFF", PrimitiveType::DFF(dff))]);
     }
 }
@@ -1,17 +1,58 @@
-use egui::{CursorIcon, LayerId, Pos2, Rect, RichText, Sense, Vec2, vec2};
+use std::collections::BTreeSet;
+
+use egui::collapsing_header::CollapsingState;
+use egui::{Color32, CursorIcon, LayerId, Pos2, Rect, RichText, Sense, Vec2, vec2};
+use serde::{Deserialize, Serialize};
 
 use crate::{
+    canvas::{PainterCanvas, SchematicTheme},
     component_lib::{
-        ComponentLibEntry, get_component_lib, get_component_lib_with_query, get_group_name,
+        ComponentLibEntry, get_component_lib, get_component_lib_with_query, get_customizable_types,
+        get_group_name, is_user_library, set_component_default, set_component_defaults,
+        uninstall_library,
     },
+    customization::CustomizationPanel,
     field::Field,
     grid_db::Component,
     locale::{EN_LOCALE, Locale},
 };
 
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum PaletteView {
+    /// Thumbnails of the components with names above them
+    #[default]
+    Grid,
+    /// Rows with small icons and names
+    List,
+}
+
+/// Appearance of the components panel, saved in the app settings
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaletteSettings {
+    pub view: PaletteView,
+    pub thumbnail_size: f32,
+    /// English names of the groups in the order set by dragging
+    pub group_order: Vec<String>,
+    /// English names of the expanded groups
+    pub open_groups: BTreeSet<String>,
+}
+
+impl Default for PaletteSettings {
+    fn default() -> Self {
+        Self {
+            view: PaletteView::Grid,
+            thumbnail_size: 160.0,
+            group_order: vec![],
+            open_groups: BTreeSet::new(),
+        }
+    }
+}
+
 pub struct ComponentsPanel {
     drag_vec: Vec2,
     pub is_expanded: bool,
+    pub settings: PaletteSettings,
     component_lib: Vec<Vec<ComponentLibEntry>>,
     query: String,
 }
@@ -35,16 +76,59 @@ impl Default for DragComponentResponse {
     }
 }
 
+impl Default for ComponentsPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl ComponentsPanel {
+    /// Narrow panel truncates the names, see `item_label`
+    const MIN_WIDTH: f32 = 100.0;
+    const DEFAULT_WIDTH: f32 = 180.0;
+    const DRAWER_HANDLE_HEIGHT: f32 = 24.0;
+    const MIN_THUMBNAIL_SIZE: f32 = 48.0;
+    const MAX_THUMBNAIL_SIZE: f32 = 240.0;
+    const LIST_ROW_HEIGHT: f32 = 28.0;
+    /// Part of the screen height taken by the opened drawer
+    const DRAWER_HEIGHT_RATIO: f32 = 0.4;
+    /// Vertical drag distance per frame that opens or closes the drawer
+    const MIN_SWIPE: f32 = 2.0;
+
     pub fn new() -> Self {
         Self {
             is_expanded: true,
+            settings: PaletteSettings::default(),
             drag_vec: vec2(0.0, 0.0),
             component_lib: get_component_lib(),
             query: String::new(),
         }
     }
 
+    /// Reloads components after the installed libraries change
+    pub fn reload(&mut self) {
+        self.component_lib = get_component_lib_with_query(&self.query);
+    }
+
+    /// Parameters of the new primitives of every customizable type
+    pub fn show_component_defaults(&mut self, ui: &mut egui::Ui, locale: &'static Locale) {
+        for (i, typ) in get_customizable_types().into_iter().enumerate() {
+            let mut edited = typ;
+            egui::CollapsingHeader::new(typ.get_type_name())
+                .id_salt(("component_default", i))
+                .show(ui, |ui| edited.show_customization_panel(ui, locale));
+            if edited != typ {
+                set_component_default(edited);
+                self.reload();
+            }
+        }
+        ui.separator();
+        if ui.button(locale.reset).clicked() {
+            set_component_defaults(vec![]);
+            self.reload();
+        }
+    }
+
     pub fn component_preview(
         &mut self,
         ui: &mut egui::Ui,
@@ -52,15 +136,34 @@ impl ComponentsPanel {
         field_scale: f32,
         group_id: usize,
         item_id: usize,
+        size: Vec2,
     ) -> DragComponentResponse {
-        let comp = &self.component_lib[group_id][item_id].component;
+        let entry = &self.component_lib[group_id][item_id];
+        let comp = &entry.component;
         let mut drag_response = DragComponentResponse::None;
-        let mut rect = ui.available_rect_before_wrap();
-        rect.set_height(ui.available_width()); // TODO: optimize it
-        let response = ui.allocate_rect(rect, Sense::all());
+        let (rect, response) = ui.allocate_exact_size(size, Sense::all());
         let painter = ui.painter().with_clip_rect(rect);
-        let comp = comp;
-        comp.draw_preview(&rect, &painter, ui.ctx().theme());
+        let canvas = PainterCanvas(painter.clone());
+        let theme = ui.ctx().theme().schematic();
+        if self.settings.view == PaletteView::List {
+            // Square icon and the name to the right of it:
+            let icon_rect = Rect::from_min_size(rect.min, vec2(rect.height(), rect.height()));
+            comp.draw_preview(&icon_rect, &canvas, theme);
+            painter.text(
+                icon_rect.right_center() + vec2(6.0, 0.0),
+                egui::Align2::LEFT_CENTER,
+                entry.name,
+                egui::TextStyle::Body.resolve(ui.style()),
+                ui.visuals().text_color(),
+            );
+        } else {
+            comp.draw_preview(&rect, &canvas, theme);
+        }
+        let response = if self.settings.view == PaletteView::List && !response.dragged() {
+            response.on_hover_ui(|ui| entry_tooltip(ui, entry))
+        } else {
+            response
+        };
         let field_grid_size = field_scale * Field::BASE_GRID_SIZE;
         if let Some(hover_pos) = response.hover_pos() {
             if response.dragged() {
@@ -73,7 +176,7 @@ impl ComponentsPanel {
                     (h + 2) as f32 * field_grid_size,
                 );
                 let rect2 = Rect::from_center_size(hover_pos, rect_size);
-                comp.draw_preview(&rect2, &painter, ui.ctx().theme());
+                comp.draw_preview(&rect2, &PainterCanvas(painter), ui.ctx().theme().schematic());
                 if !rect.contains(hover_pos) {
                     let ofs_vec = vec2(field_grid_size, field_grid_size);
                     drag_response = DragComponentResponse::Dragged {
@@ -119,84 +222,314 @@ impl ComponentsPanel {
         locale: &'static Locale,
     ) -> DragComponentResponse {
         let mut drag_response = DragComponentResponse::None;
-        let mut collapse_all_groups = false;
-        let mut expand_all_groups = false;
-
         egui::SidePanel::left("left_panel")
             .resizable(true)
-            .min_width(180.0) // FIXME
+            .min_width(Self::MIN_WIDTH)
+            .default_width(Self::DEFAULT_WIDTH)
             .show_animated(ctx, self.is_expanded, |ui| {
+                drag_response = self.show_contents(ui, foreground, field_scale, locale);
+            });
+        drag_response
+    }
+
+    /// Components panel at the bottom of the touch layout.
+    /// Swiping the handle up opens it, swiping it down closes it.
+    pub fn show_drawer(
+        &mut self,
+        ctx: &egui::Context,
+        foreground: LayerId,
+        field_scale: f32,
+        locale: &'static Locale,
+    ) -> DragComponentResponse {
+        let mut drag_response = DragComponentResponse::None;
+        egui::TopBottomPanel::bottom("components_drawer").show(ctx, |ui| {
+            let (rect, resp) = ui.allocate_exact_size(
+                vec2(ui.available_width(), Self::DRAWER_HANDLE_HEIGHT),
+                Sense::click_and_drag(),
+            );
+            ui.painter().rect_filled(
+                Rect::from_center_size(rect.center(), vec2(40.0, 5.0)),
+                2.5,
+                ui.visuals().weak_text_color(),
+            );
+            let swipe = resp.drag_delta().y;
+            if resp.clicked() {
+                self.is_expanded = !self.is_expanded;
+            } else if swipe < -Self::MIN_SWIPE {
+                self.is_expanded = true;
+            } else if swipe > Self::MIN_SWIPE {
+                self.is_expanded = false;
+            }
+            if self.is_expanded {
+                let height = ctx.screen_rect().height() * Self::DRAWER_HEIGHT_RATIO;
+                ui.allocate_ui(vec2(ui.available_width(), height), |ui| {
+                    drag_response = self.show_contents(ui, foreground, field_scale, locale);
+                });
+            }
+        });
+        drag_response
+    }
+
+    /// Thumbnails of the group entries, wrapped to the panel width
+    fn show_grid(
+        &mut self,
+        ui: &mut egui::Ui,
+        foreground: LayerId,
+        field_scale: f32,
+        group_id: usize,
+    ) -> Option<DragComponentResponse> {
+        let mut drag_response = None;
+        // Thumbnail can't be wider than the panel:
+        let size = self
+            .settings
+            .thumbnail_size
+            .min(ui.available_width() - 22.0)
+            .max(1.0);
+        ui.horizontal_wrapped(|ui| {
+            for item_id in 0..self.component_lib[group_id].len() {
+                ui.vertical(|ui| {
+                    ui.set_width(size + 22.0);
+                    item_label(ui, &self.component_lib[group_id][item_id]);
+                    egui::Frame::default()
+                        .stroke(ui.visuals().window_stroke)
+                        .corner_radius(5.0)
+                        .inner_margin(10.0)
+                        .show(ui, |ui| {
+                            let resp = self.component_preview(
+                                ui,
+                                foreground,
+                                field_scale,
+                                group_id,
+                                item_id,
+                                vec2(size, size),
+                            );
+                            if !matches!(resp, DragComponentResponse::None) {
+                                drag_response = Some(resp);
+                            }
+                        });
+                });
+            }
+        });
+        drag_response
+    }
+
+    /// Rows with icons and names of the group entries
+    fn show_list(
+        &mut self,
+        ui: &mut egui::Ui,
+        foreground: LayerId,
+        field_scale: f32,
+        group_id: usize,
+    ) -> Option<DragComponentResponse> {
+        let mut drag_response = None;
+        for item_id in 0..self.component_lib[group_id].len() {
+            let size = vec2(ui.available_width(), Self::LIST_ROW_HEIGHT);
+            let resp = self.component_preview(ui, foreground, field_scale, group_id, item_id, size);
+            if !matches!(resp, DragComponentResponse::None) {
+                drag_response = Some(resp);
+            }
+        }
+        drag_response
+    }
+
+    fn show_contents(
+        &mut self,
+        ui: &mut egui::Ui,
+        foreground: LayerId,
+        field_scale: f32,
+        locale: &'static Locale,
+    ) -> DragComponentResponse {
+        let mut drag_response = DragComponentResponse::None;
+        let mut removed_library = None;
+        // (dragged group, group it was dropped on)
+        let mut moved_group = None;
+
+        ui.add(
+            egui::Label::new(RichText::new(locale.components).heading().strong()).selectable(false),
+        );
+        ui.separator();
+
+        // Filtering:
+        ui.horizontal(|ui| {
+            ui.add(egui::Label::new(locale.filter).selectable(false));
+            if ui
+                .add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .background_color(ui.visuals().faint_bg_color)
+                        .desired_width(ui.available_width()),
+                )
+                .changed()
+            {
+                self.component_lib = get_component_lib_with_query(&self.query);
+            }
+        });
+        ui.horizontal(|ui| {
+            let settings = &mut self.settings;
+            ui.selectable_value(&mut settings.view, PaletteView::Grid, "⊞");
+            ui.selectable_value(&mut settings.view, PaletteView::List, "☰");
+            if settings.view == PaletteView::Grid {
                 ui.add(
-                    egui::Label::new(RichText::new(locale.components).heading().strong())
-                        .selectable(false),
+                    egui::Slider::new(
+                        &mut settings.thumbnail_size,
+                        Self::MIN_THUMBNAIL_SIZE..=Self::MAX_THUMBNAIL_SIZE,
+                    )
+                    .show_value(false),
                 );
-                ui.separator();
-
-                // Filtering:
-                ui.horizontal(|ui| {
-                    ui.add(egui::Label::new(locale.filter).selectable(false));
-                    if ui
-                        .add(
-                            egui::TextEdit::singleline(&mut self.query)
-                                .background_color(ui.visuals().faint_bg_color)
-                                .desired_width(ui.available_width()),
-                        )
-                        .changed()
-                    {
-                        self.component_lib = get_component_lib_with_query(&self.query);
-                        collapse_all_groups = self.query == "";
-                        expand_all_groups = self.query != "";
+            }
+        });
+        ui.separator();
+
+        // Previews:
+        egui::ScrollArea::vertical()
+            .max_width(ui.available_width())
+            .show(ui, |ui| {
+                let keys: Vec<&str> = (0..self.component_lib.len())
+                    .map(|group_id| get_group_name(group_id, &EN_LOCALE))
+                    .collect();
+                let order = get_group_order(&keys, &self.settings.group_order);
+                for &group_id in &order {
+                    if self.component_lib[group_id].is_empty() {
+                        continue;
                     }
-                });
-                ui.separator();
-
-                // Previews:
-                egui::ScrollArea::vertical()
-                    .max_width(ui.available_width())
-                    .show(ui, |ui| {
-                        for group_id in 0..self.component_lib.len() {
-                            if self.component_lib[group_id].is_empty() {
-                                continue;
+                    let key = keys[group_id];
+                    let id = ui.make_persistent_id(("components_group", key));
+                    let mut state = CollapsingState::load_with_default_open(ui.ctx(), id, false);
+                    // All groups with matches are opened while filtering:
+                    let is_open = !self.query.is_empty() || self.settings.open_groups.contains(key);
+                    state.set_open(is_open);
+                    let (toggle_resp, header, _) = state
+                        .show_header(ui, |ui| {
+                            let handle = ui
+                                .add(egui::Label::new("≡").selectable(false).sense(Sense::drag()))
+                                .on_hover_cursor(CursorIcon::Grab);
+                            handle.dnd_set_drag_payload(group_id);
+                            let name = RichText::new(get_group_name(group_id, locale));
+                            ui.add(
+                                egui::Label::new(name)
+                                    .selectable(false)
+                                    .sense(Sense::click()),
+                            )
+                        })
+                        .body(|ui| {
+                            let resp = match self.settings.view {
+                                PaletteView::Grid => {
+                                    self.show_grid(ui, foreground, field_scale, group_id)
+                                }
+                                PaletteView::List => {
+                                    self.show_list(ui, foreground, field_scale, group_id)
+                                }
+                            };
+                            if let Some(resp) = resp {
+                                drag_response = resp;
                             }
-                            egui::CollapsingHeader::new(get_group_name(group_id, locale))
-                                .id_salt(get_group_name(group_id, &EN_LOCALE))
-                                .open(if expand_all_groups {
-                                    Some(true)
-                                } else if collapse_all_groups {
-                                    Some(false)
-                                } else {
-                                    None
-                                })
-                                .show(ui, |ui| {
-                                    for item_id in 0..self.component_lib[group_id].len() {
-                                        ui.add(
-                                            egui::Label::new(
-                                                self.component_lib[group_id][item_id].name,
-                                            )
-                                            .selectable(false),
-                                        );
-                                        egui::Frame::default()
-                                            .stroke(ui.visuals().window_stroke)
-                                            .corner_radius(5.0)
-                                            .inner_margin(10.0)
-                                            .show(ui, |ui: &mut egui::Ui| {
-                                                let resp = self.component_preview(
-                                                    ui,
-                                                    foreground,
-                                                    field_scale,
-                                                    group_id,
-                                                    item_id,
-                                                );
-                                                match resp {
-                                                    DragComponentResponse::None => {}
-                                                    _ => drag_response = resp,
-                                                }
-                                            });
-                                    }
-                                });
+                        });
+                    if (toggle_resp.clicked() || header.inner.clicked()) && self.query.is_empty() {
+                        if is_open {
+                            self.settings.open_groups.remove(key);
+                        } else {
+                            self.settings.open_groups.insert(key.to_owned());
                         }
-                    });
+                    }
+                    let row = header.response;
+                    if row.dnd_hover_payload::<usize>().is_some() {
+                        let stroke = ui.visuals().selection.stroke;
+                        ui.painter()
+                            .hline(row.rect.x_range(), row.rect.top(), stroke);
+                    }
+                    if let Some(dragged) = row.dnd_release_payload::<usize>() {
+                        moved_group = Some((*dragged, group_id));
+                    }
+                    if is_user_library(group_id) {
+                        header.inner.context_menu(|ui| {
+                            if ui.button(locale.remove_library).clicked() {
+                                removed_library = Some(group_id);
+                                ui.close();
+                            }
+                        });
+                    }
+                }
+                if let Some((dragged, target)) = moved_group {
+                    self.settings.group_order = move_group(&order, dragged, target)
+                        .into_iter()
+                        .map(|group_id| keys[group_id].to_owned())
+                        .collect();
+                }
             });
-        return drag_response;
+        if let Some(group_id) = removed_library {
+            uninstall_library(group_id);
+            self.reload();
+        }
+        drag_response
+    }
+}
+
+/// Group IDs in the saved order, groups missing in `order` follow in the library order
+fn get_group_order(keys: &[&str], order: &[String]) -> Vec<usize> {
+    let saved = order
+        .iter()
+        .filter_map(|key| keys.iter().position(|k| k == key));
+    let mut groups = vec![];
+    for group_id in saved.chain(0..keys.len()) {
+        if !groups.contains(&group_id) {
+            groups.push(group_id);
+        }
+    }
+    groups
+}
+
+/// Order with the dragged group placed before the target group
+fn move_group(order: &[usize], dragged: usize, target: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = order.iter().copied().filter(|g| *g != dragged).collect();
+    let pos = order
+        .iter()
+        .position(|g| *g == target)
+        .unwrap_or(order.len());
+    order.insert(pos, dragged);
+    order
+}
+
+/// Full name and the symbol of the library entry
+fn entry_tooltip(ui: &mut egui::Ui, entry: &ComponentLibEntry) {
+    ui.label(RichText::new(entry.name).strong());
+    let (rect, _) = ui.allocate_exact_size(vec2(96.0, 96.0), Sense::hover());
+    let canvas = PainterCanvas(ui.painter().clone());
+    entry
+        .component
+        .draw_preview(&rect, &canvas, ui.ctx().theme().schematic());
+}
+
+/// Name of the library entry, truncated to the panel width.
+/// Truncated names show the full name and the symbol on hover.
+fn item_label(ui: &mut egui::Ui, entry: &ComponentLibEntry) {
+    let font = egui::TextStyle::Body.resolve(ui.style());
+    let text_width = ui
+        .fonts(|f| f.layout_no_wrap(entry.name.to_owned(), font, Color32::PLACEHOLDER))
+        .size()
+        .x;
+    let is_truncated = text_width > ui.available_width();
+    let resp = ui.add(
+        egui::Label::new(entry.name)
+            .selectable(false)
+            .truncate()
+            .show_tooltip_when_elided(false),
+    );
+    if is_truncated {
+        resp.on_hover_ui(|ui| entry_tooltip(ui, entry));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{get_group_order, move_group};
+
+    #[test]
+    fn group_order() {
+        let keys = ["Gates", "Muxes", "I/O", "My library"];
+        // Unknown names are skipped, new groups are appended:
+        let saved = ["I/O".to_owned(), "Removed".to_owned(), "Gates".to_owned()];
+        let order = get_group_order(&keys, &saved);
+        assert_eq!(order, [2, 0, 1, 3]);
+        assert_eq!(move_group(&order, 3, 2), [3, 2, 0, 1]);
+        assert_eq!(move_group(&order, 2, 1), [0, 2, 1, 3]);
     }
 }
@@ -0,0 +1,176 @@
+use std::sync::Mutex;
+
+use egui::RichText;
+use serde::{Deserialize, Serialize};
+use web_time::{Duration, Instant};
+
+use crate::{
+    grid_db::{GridDB, Revision},
+    locale::Locale,
+};
+
+/// File in the app data directory, or the local storage key on the web
+const REPORT_NAME: &str = "crash_report.json";
+
+/// Project JSON written into the report, kept up to date by [`CrashGuard`]
+static PROJECT_COPY: Mutex<Option<String>> = Mutex::new(None);
+
+/// Bug report written by the panic hook, offered for export on the next start
+#[derive(Serialize, Deserialize)]
+pub struct CrashReport {
+    pub version: String,
+    pub message: String,
+    pub backtrace: String,
+    /// Emergency copy of the opened project
+    pub project: Option<String>,
+}
+
+impl CrashReport {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn get_path() -> Option<std::path::PathBuf> {
+        eframe::storage_dir(crate::APP_ID).map(|dir| dir.join(REPORT_NAME))
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn get_storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok().flatten()
+    }
+
+    fn write(&self) {
+        let Ok(json) = serde_json::to_string(self) else {
+            return;
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = Self::get_path() {
+            if let Some(dir) = path.parent() {
+                _ = std::fs::create_dir_all(dir);
+            }
+            _ = std::fs::write(path, json);
+        }
+        #[cfg(target_arch = "wasm32")]
+        if let Some(storage) = Self::get_storage() {
+            _ = storage.set_item(REPORT_NAME, &json);
+        }
+    }
+
+    /// Report of the previous run, it is removed from the storage
+    pub fn take() -> Option<Self> {
+        #[cfg(not(target_arch = "wasm32"))]
+        let json = {
+            let path = Self::get_path()?;
+            let json = std::fs::read_to_string(&path).ok()?;
+            _ = std::fs::remove_file(path);
+            json
+        };
+        #[cfg(target_arch = "wasm32")]
+        let json = {
+            let storage = Self::get_storage()?;
+            let json = storage.get_item(REPORT_NAME).ok().flatten()?;
+            _ = storage.remove_item(REPORT_NAME);
+            json
+        };
+        serde_json::from_str(&json).ok()
+    }
+}
+
+/// Writes the crash report before the default panic output
+pub fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // The copy is lost if the panic happened while it was updated:
+        let project = PROJECT_COPY.try_lock().ok().and_then(|copy| copy.clone());
+        CrashReport {
+            version: env!("CARGO_PKG_VERSION").to_owned(),
+            message: info.to_string(),
+            backtrace: std::backtrace::Backtrace::force_capture().to_string(),
+            project,
+        }
+        .write();
+        default_hook(info);
+    }));
+}
+
+/// Keeps the emergency copy of the project up to date
+pub struct CrashGuard {
+    revision: Option<Revision>,
+    last_copy: Instant,
+}
+
+impl CrashGuard {
+    /// Min time between the copies, dumping a big design takes a while
+    const COPY_INTERVAL: Duration = Duration::from_secs(5);
+
+    pub fn new() -> Self {
+        Self {
+            revision: None,
+            last_copy: Instant::now(),
+        }
+    }
+
+    pub fn update(&mut self, db: &GridDB) {
+        let revision = db.get_revision();
+        if self.revision == Some(revision) || self.last_copy.elapsed() < Self::COPY_INTERVAL {
+            return;
+        }
+        let json = db.dump_to_json();
+        *PROJECT_COPY.lock().unwrap_or_else(|e| e.into_inner()) = json;
+        self.revision = Some(revision);
+        self.last_copy = Instant::now();
+    }
+}
+
+pub enum CrashReportAction {
+    /// Save the report as a file to be attached to the bug report
+    Export(String),
+    /// Open the emergency copy of the project
+    Restore(String),
+}
+
+/// Dialog shown on start after a crash
+pub struct CrashReportWindow {
+    report: Option<CrashReport>,
+}
+
+impl CrashReportWindow {
+    pub fn new(report: Option<CrashReport>) -> Self {
+        Self { report }
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        locale: &'static Locale,
+    ) -> Option<CrashReportAction> {
+        let report = self.report.as_ref()?;
+        let mut action = None;
+        let mut close = false;
+        egui::modal::Modal::new("crash_report".into()).show(ctx, |ui| {
+            ui.heading(locale.crash_report);
+            ui.label(locale.crash_report_message);
+            egui::ScrollArea::vertical()
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    ui.label(RichText::new(&report.message).monospace())
+                });
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button(locale.export_bug_report).clicked() {
+                    action = serde_json::to_string_pretty(report)
+                        .ok()
+                        .map(CrashReportAction::Export);
+                }
+                if let Some(project) = &report.project
+                    && ui.button(locale.restore_project).clicked()
+                {
+                    action = Some(CrashReportAction::Restore(project.clone()));
+                    close = true;
+                }
+                close |= ui.button("OK").clicked();
+            });
+        });
+        if close {
+            self.report = None;
+        }
+        action
+    }
+}
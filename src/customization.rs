@@ -0,0 +1,197 @@
+use egui::{Grid, RichText, TextEdit, TextWrapMode, Ui};
+
+use crate::{
+    grid_db::{AssertionType, ComparisonType, Component, Parameter, PrimitiveType, Unit},
+    locale::Locale,
+};
+
+pub trait CustomizationPanel {
+    fn show_customization_panel(&mut self, ui: &mut Ui, locale: &'static Locale);
+}
+
+impl CustomizationPanel for Component {
+    fn show_customization_panel(&mut self, ui: &mut Ui, locale: &'static Locale) {
+        match self {
+            Self::Primitive(p) => {
+                if p.typ == PrimitiveType::Connector {
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}:", locale.signal_name));
+                        let mut name = p.name.to_string();
+                        if ui.text_edit_singleline(&mut name).changed() {
+                            p.name = name.into();
+                        }
+                    });
+                }
+                p.typ.show_customization_panel(ui, locale);
+            }
+            Self::Unit(u) => {
+                u.show_customization_panel(ui, locale);
+            }
+            _ => panic!(),
+        }
+    }
+}
+
+impl CustomizationPanel for PrimitiveType {
+    fn show_customization_panel(&mut self, ui: &mut Ui, locale: &'static Locale) {
+        ui.style_mut().wrap_mode = Some(TextWrapMode::Extend);
+        match self {
+            Self::And(n_inputs)
+            | Self::Or(n_inputs)
+            | Self::Xor(n_inputs)
+            | Self::Nand(n_inputs)
+            | Self::Mux(n_inputs) => {
+                let mut buffer = n_inputs.to_string();
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}:", locale.inputs_number));
+
+                    if ui
+                        .add(TextEdit::singleline(&mut buffer).desired_width(50.0))
+                        .changed()
+                    {
+                        match buffer.parse::<usize>() {
+                            Ok(num) => {
+                                if num <= Self::get_max_gate_inputs() && num >= 2 {
+                                    *n_inputs = num
+                                }
+                            }
+                            _ => {
+                                if buffer.is_empty() {
+                                    *n_inputs = 2
+                                }
+                            }
+                        }
+                    }
+                    if ui.button(RichText::new("+").monospace()).clicked()
+                        && *n_inputs < Self::get_max_gate_inputs()
+                    {
+                        *n_inputs += 1;
+                    }
+                    if ui.button(RichText::new("-").monospace()).clicked() && *n_inputs > 2 {
+                        *n_inputs -= 1;
+                    }
+                });
+            }
+            Self::DFF(params) => {
+                ui.checkbox(&mut params.has_sync_reset, locale.sync_reset);
+                if params.has_sync_reset {
+                    ui.checkbox(&mut params.sync_reset_inverted, locale.sync_reset_inverted);
+                }
+                ui.checkbox(&mut params.has_async_reset, locale.async_reset);
+                if params.has_async_reset {
+                    ui.checkbox(
+                        &mut params.async_reset_inverted,
+                        locale.async_reset_inverted,
+                    );
+                }
+                ui.checkbox(&mut params.has_enable, locale.enable_signal);
+            }
+            Self::Adder { cin, cout } => {
+                ui.checkbox(cin, "cin");
+                ui.checkbox(cout, "cout");
+            }
+            Self::DipSwitch(n) => {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}:", locale.switches_number));
+                    ui.add(egui::DragValue::new(n).range(1..=Self::MAX_DIP_SWITCHES));
+                });
+            }
+            Self::Pattern { pattern, length } => {
+                let id = ui.id().with("pattern");
+                let mut buffer = ui
+                    .data(|data| data.get_temp::<String>(id))
+                    .unwrap_or_else(|| Self::pattern_to_string(*pattern, *length));
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}:", locale.pattern));
+                    let response = ui.add(
+                        TextEdit::singleline(&mut buffer)
+                            .char_limit(Self::MAX_PATTERN_LENGTH)
+                            .hint_text("0110")
+                            .desired_width(120.0),
+                    );
+                    if let Some((p, l)) = Self::pattern_from_str(&buffer) {
+                        (*pattern, *length) = (p, l);
+                    }
+                    if response.has_focus() {
+                        ui.data_mut(|data| data.insert_temp(id, buffer));
+                    } else {
+                        ui.data_mut(|data| data.remove::<String>(id));
+                    }
+                });
+            }
+            Self::Random { width, seed } => {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}:", locale.outputs_number));
+                    ui.add(egui::DragValue::new(width).range(1..=Self::MAX_RANDOM_WIDTH));
+                });
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}:", locale.seed));
+                    ui.add(egui::DragValue::new(seed));
+                });
+            }
+            Self::Assertion {
+                condition,
+                n_inputs,
+            } => {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}:", locale.inputs_number));
+                    ui.add(egui::DragValue::new(n_inputs).range(1..=Self::MAX_ASSERTION_INPUTS));
+                });
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}:", locale.condition));
+                    ui.menu_button(condition.to_str(), |ui: &mut Ui| {
+                        for typ in AssertionType::TYPES {
+                            ui.selectable_value(condition, *typ, typ.to_str());
+                        }
+                    });
+                });
+            }
+            Self::Comparator(curr_typ) => {
+                ui.horizontal(|ui| {
+                    ui.label(format!("{}:", locale.type_));
+                    ui.menu_button(curr_typ.to_str(), |ui: &mut Ui| {
+                        for typ in ComparisonType::TYPES {
+                            ui.selectable_value(curr_typ, *typ, typ.to_str());
+                        }
+                    });
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+impl CustomizationPanel for Unit {
+    fn show_customization_panel(&mut self, ui: &mut Ui, locale: &'static Locale) {
+        ui.label(format!("{}:", locale.description));
+        ui.add(TextEdit::multiline(&mut self.description).desired_rows(2));
+        ui.label(format!("{}:", locale.parameters));
+        let mut removed = None;
+        Grid::new("unit_parameters").num_columns(3).show(ui, |ui| {
+            for (i, param) in self.parameters.iter_mut().enumerate() {
+                for text in [&mut param.name, &mut param.value] {
+                    let mut buffer = text.to_string();
+                    if ui
+                        .add(TextEdit::singleline(&mut buffer).desired_width(100.0))
+                        .changed()
+                    {
+                        *text = buffer.into();
+                    }
+                }
+                if ui.button(RichText::new("-").monospace()).clicked() {
+                    removed = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+        if let Some(i) = removed {
+            self.parameters.remove(i);
+        }
+        if ui.button(RichText::new("+").monospace()).clicked() {
+            self.parameters.push(Parameter {
+                name: format!("P{}", self.parameters.len()).into(),
+                value: "0".into(),
+            });
+        }
+    }
+}
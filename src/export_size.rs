@@ -0,0 +1,216 @@
+//! Size of the exported images: by the cell size, by exact pixel dimensions or by physical size
+
+use image::{Rgba, RgbaImage, imageops};
+
+use crate::{grid_db::DocumentSize, locale::Locale};
+
+pub const MM_PER_INCH: f32 = 25.4;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum ExportSizeMode {
+    CellSize,
+    Pixels,
+    Millimeters,
+}
+
+impl ExportSizeMode {
+    pub const ALL: [Self; 3] = [Self::CellSize, Self::Pixels, Self::Millimeters];
+
+    pub fn get_name(&self, locale: &'static Locale) -> &'static str {
+        match self {
+            Self::CellSize => locale.cell_size,
+            Self::Pixels => locale.pixels,
+            Self::Millimeters => locale.millimeters,
+        }
+    }
+}
+
+/// Requested size of the exported image
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct ExportSize {
+    pub mode: ExportSizeMode,
+    /// Pixels per cell in the cell size mode
+    pub cell_size: f32,
+    /// Width and height in pixels or millimeters, depending on the mode
+    pub width: f32,
+    pub height: f32,
+    /// If set, the height follows the width by the aspect ratio of the drawing.
+    /// Otherwise the drawing is fit into the image and centered.
+    pub keep_aspect: bool,
+    /// Pixels per inch of the physical size, also written into PNG
+    pub dpi: f32,
+}
+
+impl Default for ExportSize {
+    fn default() -> Self {
+        Self {
+            mode: ExportSizeMode::CellSize,
+            cell_size: 40.0,
+            width: 1920.0,
+            height: 1080.0,
+            keep_aspect: true,
+            dpi: 96.0,
+        }
+    }
+}
+
+impl ExportSize {
+    /// Bigger images are refused, they don't fit into memory of the web version
+    pub const MAX_PIXELS: f32 = 16384.0;
+
+    /// Size of the image in pixels for the drawing of `cells` size
+    fn get_pixel_box(&self, (cols, rows): (f32, f32)) -> (f32, f32) {
+        match self.mode {
+            ExportSizeMode::CellSize => (cols * self.cell_size, rows * self.cell_size),
+            ExportSizeMode::Pixels => (self.width, self.height),
+            ExportSizeMode::Millimeters => (
+                self.width / MM_PER_INCH * self.dpi,
+                self.height / MM_PER_INCH * self.dpi,
+            ),
+        }
+    }
+
+    /// Pixels per cell fitting the drawing of `cells` size into the image
+    pub fn get_cell_size(&self, cells: (f32, f32)) -> f32 {
+        let (w, h) = self.get_pixel_box(cells);
+        (w / cells.0).min(h / cells.1)
+    }
+
+    /// Size of the image in whole pixels
+    pub fn get_pixel_size(&self, cells: (f32, f32)) -> (u32, u32) {
+        let (w, h) = self.get_pixel_box(cells);
+        (w.round().max(1.0) as u32, h.round().max(1.0) as u32)
+    }
+
+    /// Size of the SVG document, None if it's the size of the drawing
+    pub fn get_document_size(&self) -> Option<DocumentSize> {
+        let millimeters = match self.mode {
+            ExportSizeMode::CellSize => return None,
+            ExportSizeMode::Pixels => false,
+            ExportSizeMode::Millimeters => true,
+        };
+        Some(DocumentSize {
+            width: self.width,
+            height: self.height,
+            millimeters,
+        })
+    }
+
+    /// Updates the height by the width, if the aspect ratio is kept
+    pub fn fit_height(&mut self, (cols, rows): (f32, f32)) {
+        if self.keep_aspect {
+            self.height = (self.width * rows / cols).round();
+        }
+    }
+
+    /// Updates the width by the height, if the aspect ratio is kept
+    pub fn fit_width(&mut self, (cols, rows): (f32, f32)) {
+        if self.keep_aspect {
+            self.width = (self.height * cols / rows).round();
+        }
+    }
+
+    /// False for empty or too big images
+    pub fn is_valid(&self, cells: (f32, f32)) -> bool {
+        let (w, h) = self.get_pixel_box(cells);
+        let cell_size = self.get_cell_size(cells);
+        cell_size.is_finite() && cell_size > 0.0 && w <= Self::MAX_PIXELS && h <= Self::MAX_PIXELS
+    }
+}
+
+/// Scales the image to fit into `size` and centers it on the background
+pub fn fit_image(
+    image: &RgbaImage,
+    (width, height): (u32, u32),
+    background: Rgba<u8>,
+) -> RgbaImage {
+    let scale = (width as f32 / image.width() as f32).min(height as f32 / image.height() as f32);
+    let scaled_w = ((image.width() as f32 * scale).round() as u32).clamp(1, width);
+    let scaled_h = ((image.height() as f32 * scale).round() as u32).clamp(1, height);
+    let scaled = imageops::resize(image, scaled_w, scaled_h, imageops::FilterType::Lanczos3);
+    let mut result = RgbaImage::from_pixel(width, height, background);
+    let x = (width - scaled_w) / 2;
+    let y = (height - scaled_h) / 2;
+    imageops::overlay(&mut result, &scaled, x.into(), y.into());
+    result
+}
+
+/// Sets the pixel density of the encoded PNG by inserting the `pHYs` chunk after the header.
+/// None if the data is not PNG.
+pub fn set_png_dpi(png: &[u8], dpi: f32) -> Option<Vec<u8>> {
+    // Signature, then the header chunk: length, type, 13 bytes of data and CRC
+    const HEADER_END: usize = 8 + 4 + 4 + 13 + 4;
+    if png.len() < HEADER_END || &png[12..16] != b"IHDR" {
+        return None;
+    }
+    let pixels_per_meter = (dpi / MM_PER_INCH * 1000.0).round() as u32;
+    let mut chunk = b"pHYs".to_vec();
+    chunk.extend(pixels_per_meter.to_be_bytes());
+    chunk.extend(pixels_per_meter.to_be_bytes());
+    chunk.push(1); // The unit is meter
+    let mut crc = flate2::Crc::new();
+    crc.update(&chunk);
+    let mut result = png[..HEADER_END].to_vec();
+    result.extend(9u32.to_be_bytes());
+    result.extend(chunk);
+    result.extend(crc.sum().to_be_bytes());
+    result.extend(&png[HEADER_END..]);
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{Rgba, RgbaImage};
+
+    use super::{ExportSize, ExportSizeMode, fit_image, set_png_dpi};
+
+    #[test]
+    fn export_sizes() {
+        let cells = (20.0, 10.0);
+        let mut size = ExportSize::default();
+        assert_eq!(size.get_pixel_size(cells), (800, 400));
+        assert_eq!(size.get_document_size(), None);
+
+        size.mode = ExportSizeMode::Pixels;
+        size.width = 1000.0;
+        size.fit_height(cells);
+        assert_eq!(size.get_pixel_size(cells), (1000, 500));
+        assert_eq!(size.get_cell_size(cells), 50.0);
+        // Fit into the box:
+        size.height = 200.0;
+        assert_eq!(size.get_cell_size(cells), 20.0);
+
+        size.mode = ExportSizeMode::Millimeters;
+        (size.width, size.height, size.dpi) = (254.0, 127.0, 100.0);
+        assert_eq!(size.get_pixel_size(cells), (1000, 500));
+        assert!(size.get_document_size().unwrap().millimeters);
+        size.width = 1e6;
+        size.fit_height(cells);
+        assert!(!size.is_valid(cells));
+
+        let image = RgbaImage::from_pixel(20, 10, Rgba([0, 0, 0, 255]));
+        let white = Rgba([255, 255, 255, 255]);
+        let fitted = fit_image(&image, (40, 40), white);
+        assert_eq!(fitted.dimensions(), (40, 40));
+        assert_eq!(
+            (fitted[(0, 0)], fitted[(20, 20)]),
+            (white, Rgba([0, 0, 0, 255]))
+        );
+    }
+
+    #[test]
+    fn png_dpi() {
+        let mut png = vec![];
+        image::DynamicImage::ImageRgba8(RgbaImage::new(2, 2))
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .unwrap();
+        let with_dpi = set_png_dpi(&png, 300.0).unwrap();
+        assert_eq!(with_dpi.len(), png.len() + 21);
+        let chunk = with_dpi.windows(4).position(|w| w == b"pHYs").unwrap();
+        // 300 DPI is 11811 pixels per meter:
+        assert_eq!(with_dpi[chunk + 4..chunk + 8], 11811u32.to_be_bytes());
+        let decoded = image::load_from_memory(&with_dpi).unwrap();
+        assert_eq!((decoded.width(), decoded.height()), (2, 2));
+        assert!(set_png_dpi(b"GIF89a", 300.0).is_none());
+    }
+}
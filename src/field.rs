@@ -1,126 +1,67 @@
 use egui::{
-    Color32, CursorIcon, FontId, Painter, Pos2, Rect, Response, Sense, Shape, Stroke, StrokeKind,
-    Vec2, pos2, vec2,
+    Color32, CursorIcon, Event, FontId, LayerId, Order, Painter, Pos2, Rect, Response, Sense,
+    Shape, Stroke, StrokeKind, Vec2, pos2, vec2,
 };
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 
 use crate::{
+    canvas::{PainterCanvas, SchematicTheme},
+    component_lib::get_library_update,
     components_panel::DragComponentResponse,
-    grid_db::{GridDB, GridPos, LodLevel, grid_pos, grid_rect},
+    grid_db::{
+        Component, ComponentColor, ExportGrid, GridDB, GridDBConnectionPoint, GridPos, GridRect, Id,
+        Logic, NetBundleCache, NetColorMode, NetSegment, NetStyle, ProjectView, Revision, Theme,
+        grid_pos, grid_rect,
+    },
     interaction_manager::{InteractionManager, draw_component_drag_preview},
     locale::Locale,
+    quick_add::{QuickAdd, QuickAddResponse},
 };
 
 use web_time::{Duration, Instant};
 
-#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
-pub enum GridType {
-    Dots,
-    Cells,
-    None,
-}
+pub use editor_core::field_state::{FieldState, GridType, filled_cells};
 
 pub const SUPPORTED_GRID_TYPES: &[GridType] = &[GridType::Cells, GridType::Dots, GridType::None];
-impl GridType {
+/// Action of the mouse wheel without modifiers, Ctrl + wheel always zooms
+#[derive(Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum WheelMode {
+    #[default]
+    Scroll,
+    Zoom,
+}
+
+pub const SUPPORTED_WHEEL_MODES: &[WheelMode] = &[WheelMode::Scroll, WheelMode::Zoom];
+impl WheelMode {
     pub fn get_name(&self, locale: &'static Locale) -> &'static str {
         match self {
-            Self::Cells => locale.cells,
-            Self::Dots => locale.dots,
-            Self::None => locale.empty,
+            Self::Scroll => locale.wheel_scrolls,
+            Self::Zoom => locale.wheel_zooms,
         }
     }
 }
 
-pub struct FieldState {
-    pub scale: f32,
-    pub offset: Vec2,
-    pub grid_size: f32,
-    pub rect: Rect,
-    pub label_font: FontId,
-    pub label_visible: bool,
-    pub cursor_pos: Option<Pos2>,
-    pub debounce: bool,
-    pub debounce_scale: f32,
-}
+/// Zoom levels offered in the menus, relative to [`Field::DEFAULT_SCALE`]
+pub const ZOOM_PRESETS: &[f32] = &[0.25, 0.5, 1.0, 2.0];
 
-// Dummy state parameters used to generate SVG
-pub const SVG_DUMMY_STATE: FieldState = FieldState {
-    scale: 1.0 / Field::BASE_GRID_SIZE,
-    offset: vec2(0.0, 0.0),
-    grid_size: 1.0,
-    cursor_pos: None,
-    label_font: FontId {
-        size: 0.8,
-        family: egui::FontFamily::Monospace,
-    },
-    label_visible: true,
-    rect: Rect::from_min_max(pos2(0.0, 0.0), pos2(0.0, 0.0)),
-    debounce: false,
-    debounce_scale: 1.0,
-};
-
-impl FieldState {
-    pub fn grid_to_screen(&self, grid_pos: &GridPos) -> Pos2 {
-        return pos2(
-            self.rect.left() + self.offset.x + grid_pos.x as f32 * self.grid_size,
-            self.rect.top() + self.offset.y + grid_pos.y as f32 * self.grid_size,
-        );
-    }
-
-    pub fn screen_to_grid(&self, screen_pos: Pos2) -> GridPos {
-        let grid_x = (screen_pos.x - self.rect.left() - self.offset.x) / self.grid_size;
-        let grid_y = (screen_pos.y - self.rect.top() - self.offset.y) / self.grid_size;
-
-        GridPos {
-            x: grid_x.floor() as i32,
-            y: grid_y.floor() as i32,
-        }
-    }
-
-    pub fn lod_level(&self) -> LodLevel {
-        if self.scale <= Field::LOD_LEVEL_MIN_SCALE {
-            LodLevel::Min
-        } else if self.scale <= Field::LOD_LEVEL_MID_SCALE {
-            LodLevel::Mid
-        } else {
-            LodLevel::Max
-        }
-    }
-}
-
-pub fn filled_cells(
-    state: &FieldState,
-    grid_pos: &GridPos,
-    width: i32,
-    height: i32,
-    color: Color32,
-) -> Shape {
-    let rect = Rect::from_min_size(
-        state.grid_to_screen(&grid_pos),
-        vec2(
-            state.grid_size * width as f32,
-            state.grid_size * height as f32,
-        ),
-    );
-    Shape::rect_filled(rect, 0.0, color)
-}
 
+/// Hatched cell, the hatches of the neighbouring cells are joined,
+/// so blocked areas are seen without colors, e.g. in grayscale screenshots
 pub fn blocked_cell(state: &FieldState, pos: &GridPos) -> Vec<Shape> {
     let mut result = vec![];
     let base_p = state.grid_to_screen(&pos);
-    let p1 = base_p + vec2(state.grid_size * 0.25, state.grid_size * 0.25);
-    let p2 = base_p + vec2(state.grid_size * 0.75, state.grid_size * 0.75);
-    let p3 = base_p + vec2(state.grid_size * 0.25, state.grid_size * 0.75);
-    let p4 = base_p + vec2(state.grid_size * 0.75, state.grid_size * 0.25);
-    result.push(Shape::line_segment(
-        [p1, p2],
-        Stroke::new(1.0, Color32::RED),
-    ));
-    result.push(Shape::line_segment(
-        [p3, p4],
-        Stroke::new(1.0, Color32::RED),
-    ));
+    let stroke = Stroke::new((state.grid_size * 0.05).max(1.0), Color32::RED);
+    for (a, b) in [
+        (vec2(0.0, 0.5), vec2(0.5, 0.0)),
+        (vec2(0.0, 1.0), vec2(1.0, 0.0)),
+        (vec2(0.5, 1.0), vec2(1.0, 0.5)),
+    ] {
+        result.push(Shape::line_segment(
+            [base_p + a * state.grid_size, base_p + b * state.grid_size],
+            stroke,
+        ));
+    }
     result.push(filled_cells(
         &state,
         &pos,
@@ -135,26 +76,79 @@ pub struct Field {
     pub state: FieldState,
     pub grid_type: GridType,
     pub grid_db: GridDB,
+    pub unconnected_visible: bool,
+    pub bundle_nets: bool,
+    bundle_cache: NetBundleCache,
+    pub length_heatmap_visible: bool,
+    /// Simulated values of the wires, shown in [`NetColorMode::SimulationValues`]
+    pub net_values: Option<HashMap<Id, Logic>>,
+    /// Colors of the current net color mode are explained in the corner of the field
+    pub net_legend_visible: bool,
+    /// Grid of the PNG export being captured, drawn instead of the grid of the field
+    pub capture_grid: Option<ExportGrid>,
+    /// LOD thresholds follow the measured drawing time, keeping slow machines responsive
+    pub adaptive_lod: bool,
+    /// Drawing time of the frames since the last LOD adaptation
+    frame_times: (Duration, u32),
+    /// Dropped components are moved to the nearest free location instead of being refused
+    pub auto_place: bool,
+    pub wheel_mode: WheelMode,
+    /// Viewport and selection are saved with the project
+    pub save_view: bool,
     external_drag_resp: DragComponentResponse,
     pub interaction_manager: InteractionManager,
     debounce_inst: Instant,
+    quick_add: Option<QuickAdd>,
+    /// Fragment copied in the last frame, for the clipboard history
+    copied: Option<GridDB>,
+    /// Database shown in the last frame, a new project is rendered progressively
+    shown_revision: Option<Revision>,
+    /// Visible components drawn in full detail while the project is rendered progressively,
+    /// the rest are drawn as boxes
+    refined_components: Option<usize>,
+}
+
+impl Default for Field {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Field {
     // TODO: Move to settings
-    pub const BASE_GRID_SIZE: f32 = 10.0;
+    pub const BASE_GRID_SIZE: f32 = FieldState::BASE_GRID_SIZE;
     pub const MIN_SCALE: f32 = 0.1;
     pub const MAX_SCALE: f32 = 100.0;
-    pub const MAX_FONT_SIZE: f32 = 32.0;
+    /// Scale shown as 100%
+    pub const DEFAULT_SCALE: f32 = 2.5;
+    /// Zoom factor of one scrolled point in [`WheelMode::Zoom`]
+    const WHEEL_ZOOM_SPEED: f32 = 1.0 / 200.0;
+    /// Part of the field taken by the project zoomed to fit
+    const FIT_MARGIN: f32 = 0.9;
+    pub const MAX_FONT_SIZE: f32 = FieldState::MAX_FONT_SIZE;
     pub const POINT_MIN_SCALE: f32 = 2.0;
     pub const GRID_MIN_SCALE: f32 = 0.6;
-    pub const MIN_DISPLAY_TEXT_SIZE: f32 = 3.0;
-    pub const LOD_LEVEL_MID_SCALE: f32 = 1.0; // ??
-    pub const LOD_LEVEL_MIN_SCALE: f32 = 0.5;
+    pub const MIN_DISPLAY_TEXT_SIZE: f32 = FieldState::MIN_DISPLAY_TEXT_SIZE;
     pub const DEBOUNCE_DURATION: Duration = Duration::from_millis(300);
+    /// Drawing time of a frame, the LOD thresholds are raised when it's exceeded on average
+    const TARGET_FRAME_TIME: Duration = Duration::from_millis(16);
+    /// Frames averaged before the LOD thresholds are changed
+    const LOD_ADAPT_FRAMES: u32 = 30;
+    const LOD_BIAS_STEP: f32 = 1.25;
+    const MAX_LOD_BIAS: f32 = 4.0;
+    /// Visible components of a new project, starting from which it is rendered progressively
+    const PROGRESSIVE_MIN_COMPONENTS: usize = 5000;
+    /// Components refined in a frame of the progressive rendering
+    const PROGRESSIVE_STEP: usize = 2000;
+    /// Magnifier is shown around the cursor while the key is held
+    const MAGNIFIER_KEY: egui::Key = egui::Key::Space;
+    const MAGNIFIER_ZOOM: f32 = 4.0;
+    const MAGNIFIER_RADIUS: f32 = 80.0;
+    /// Corners of the polygon the magnifier is clipped by
+    const MAGNIFIER_CORNERS: usize = 64;
 
     pub fn new() -> Self {
-        let scale = (Self::MAX_SCALE / 40.0).max(Self::MIN_SCALE);
+        let scale = Self::DEFAULT_SCALE;
         let db = GridDB::new();
         Self {
             state: FieldState {
@@ -172,12 +166,30 @@ impl Field {
                 cursor_pos: None,
                 debounce: false,
                 debounce_scale: scale,
+                net_style: NetStyle::DEFAULT,
+                lod_bias: 1.0,
             },
             grid_type: GridType::Cells,
             grid_db: db,
+            unconnected_visible: false,
+            bundle_nets: false,
+            bundle_cache: NetBundleCache::default(),
+            length_heatmap_visible: false,
+            net_values: None,
+            net_legend_visible: false,
+            capture_grid: None,
+            adaptive_lod: true,
+            frame_times: (Duration::ZERO, 0),
+            auto_place: true,
+            wheel_mode: WheelMode::Scroll,
+            save_view: true,
             external_drag_resp: DragComponentResponse::None,
             interaction_manager: InteractionManager::new(),
             debounce_inst: Instant::now(),
+            quick_add: None,
+            copied: None,
+            shown_revision: None,
+            refined_components: None,
         }
     }
 
@@ -193,10 +205,18 @@ impl Field {
             self.state.grid_size - (self.state.offset.y.abs() % self.state.grid_size)
         };
 
-        let stroke = Stroke::new(1.0, ui.visuals().strong_text_color().gamma_multiply(0.1));
+        let grid = self.capture_grid.unwrap_or(ExportGrid {
+            grid_type: self.grid_type,
+            opacity: ExportGrid::DEFAULT_OPACITY,
+        });
+        let color = ui
+            .visuals()
+            .strong_text_color()
+            .gamma_multiply(grid.opacity);
+        let stroke = Stroke::new(1.0, color);
         let mut shapes = vec![];
 
-        match self.grid_type {
+        match grid.grid_type {
             GridType::Cells => {
                 if Self::GRID_MIN_SCALE < self.state.scale {
                     for i in
@@ -272,7 +292,12 @@ impl Field {
             self.interaction_manager
                 .refresh(&mut self.grid_db, &self.state, response, ui, locale);
         if response.hovered() {
-            let zoom_delta = ui.input(|i| i.zoom_delta());
+            let (mut zoom_delta, scroll_delta) =
+                ui.input(|i| (i.zoom_delta(), i.smooth_scroll_delta));
+            match self.wheel_mode {
+                WheelMode::Zoom => zoom_delta *= (scroll_delta.y * Self::WHEEL_ZOOM_SPEED).exp(),
+                WheelMode::Scroll => self.state.offset += scroll_delta,
+            }
             let new_scale = (self.state.scale * zoom_delta).clamp(Self::MIN_SCALE, Self::MAX_SCALE);
             let zoom_factor = new_scale / self.state.scale;
 
@@ -294,10 +319,7 @@ impl Field {
 
             self.state.scale = new_scale;
             if zoom_delta != 1.0 {
-                self.state.grid_size = Self::BASE_GRID_SIZE * self.state.scale;
-                let label_text_size = self.state.grid_size * 0.5;
-                self.state.label_visible = label_text_size > Self::MIN_DISPLAY_TEXT_SIZE;
-                self.state.label_font = FontId::monospace(label_text_size);
+                self.update_grid_size();
             }
             if !ongoing_interaction {
                 if response.dragged() {
@@ -329,68 +351,941 @@ impl Field {
                     fill_color,
                     only_overlap,
                 );
+                // Outline of the location the component slides to:
+                let cell = self.state.screen_to_grid(pos);
+                if let Some(location) = self.get_drop_location(cell, dim, only_overlap)
+                    && location != cell
+                {
+                    painter.rect_stroke(
+                        Rect::from_min_size(
+                            self.state.grid_to_screen(&location),
+                            vec2(dim.0 as f32, dim.1 as f32) * self.state.grid_size,
+                        ),
+                        0.0,
+                        Stroke::new(
+                            (self.state.grid_size * 0.1).max(1.0),
+                            fill_color.gamma_multiply(4.0),
+                        ),
+                        StrokeKind::Inside,
+                    );
+                }
             }
-            DragComponentResponse::Released { pos, mut component } => {
-                component.set_pos(self.state.screen_to_grid(pos));
-                let dim = component.get_dimension();
-                let p0 = component.get_position();
-                for x in 0..dim.0 {
-                    for y in 0..dim.1 {
-                        if !self
-                            .grid_db
-                            .is_free_cell(p0 + grid_pos(x, y), component.is_overlap_only())
-                        {
-                            return;
-                        }
+            DragComponentResponse::Released { pos, component } => {
+                self.place_component(component, self.state.screen_to_grid(pos));
+            }
+            _ => {}
+        }
+    }
+
+    /// Location where the new component dropped at `pos` is placed, None if it is occupied
+    fn get_drop_location(
+        &self,
+        pos: GridPos,
+        dim: (i32, i32),
+        overlap_only: bool,
+    ) -> Option<GridPos> {
+        if self.grid_db.is_free_location(pos, dim, overlap_only) {
+            Some(pos)
+        } else if self.auto_place {
+            self.grid_db.find_free_location(pos, dim, overlap_only)
+        } else {
+            None
+        }
+    }
+
+    /// Adds the component at `pos` or the nearest free location
+    fn place_component(&mut self, mut component: Component, pos: GridPos) {
+        let Some(pos) =
+            self.get_drop_location(pos, component.get_dimension(), component.is_overlap_only())
+        else {
+            return;
+        };
+        component.set_pos(pos);
+        self.interaction_manager
+            .add_new_component(component, &mut self.grid_db);
+    }
+
+    /// Fragment copied since the last call
+    pub fn take_copied(&mut self) -> Option<GridDB> {
+        self.copied.take()
+    }
+
+    /// Copies the selected components as project JSON,
+    /// pasted project content is attached to the cursor to be placed by click
+    fn handle_clipboard(&mut self, ui: &egui::Ui, response: &Response) {
+        if ui.memory(|m| m.focused().is_some()) {
+            return;
+        }
+        for event in ui.input(|i| i.events.clone()) {
+            match event {
+                Event::Copy | Event::Cut => {
+                    let Some(fragment) = self.interaction_manager.copy_selected(&self.grid_db)
+                    else {
+                        continue;
+                    };
+                    let Some(json) = fragment.dump_to_json() else {
+                        continue;
+                    };
+                    ui.ctx().copy_text(json);
+                    self.copied = Some(fragment);
+                    if event == Event::Cut {
+                        self.interaction_manager.remove_selected(&mut self.grid_db);
                     }
                 }
-                self.interaction_manager
-                    .add_new_component(component, &mut self.grid_db);
+                Event::Paste(text) if response.hovered() => {
+                    if let Ok(fragment) = GridDB::load_from_json(text) {
+                        self.interaction_manager.start_placing(fragment);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// Opens the quick add popup when user types over the idle field
+    fn handle_quick_add(&mut self, ui: &egui::Ui, response: &Response) {
+        if self.quick_add.is_none()
+            && response.hovered()
+            && self.interaction_manager.is_idle()
+            && ui.memory(|m| m.focused().is_none())
+            && let Some(cursor) = self.state.cursor_pos
+        {
+            self.quick_add = QuickAdd::start(ui, self.state.screen_to_grid(cursor));
+        }
+        let Some(quick_add) = &mut self.quick_add else {
+            return;
+        };
+        let pos = quick_add.pos;
+        match quick_add.show(ui.ctx(), self.state.grid_to_screen(&pos)) {
+            QuickAddResponse::Open => {}
+            QuickAddResponse::Closed => self.quick_add = None,
+            QuickAddResponse::Chosen(component) => {
+                self.quick_add = None;
+                self.place_component(component, pos);
             }
-            _ => {}
         }
     }
 
     pub fn show(&mut self, ui: &mut egui::Ui, locale: &'static Locale) {
-        let theme = ui.ctx().theme();
+        let frame_start = Instant::now();
+        let theme = ui.ctx().theme().schematic();
         let allocated_rect = ui.available_rect_before_wrap();
         let response = ui.allocate_rect(self.state.rect, Sense::drag().union(Sense::all()));
         self.refresh(ui, &response, allocated_rect, locale);
+        if let Some(view) = self.grid_db.take_view() {
+            self.restore_view(view);
+        }
+        if !self
+            .shown_revision
+            .is_some_and(|revision| self.grid_db.owns_revision(revision))
+        {
+            self.refined_components = Some(0);
+        }
+        self.shown_revision = Some(self.grid_db.get_revision());
         self.display_grid(ui);
-        let grid_rect = grid_rect(
-            0,
-            self.state.screen_to_grid(self.state.rect.min),
-            self.state.screen_to_grid(self.state.rect.max),
-        );
+        let grid_rect = self.get_visible_grid_rect();
         let painter: Painter = ui.painter().with_clip_rect(self.state.rect);
+        let canvas = PainterCanvas(painter.clone());
 
-        // Display components:
-        self.grid_db
-            .get_visible_components(&grid_rect)
-            .iter()
-            .for_each(|u| {
-                u.display(&self.state, &painter, theme);
-            });
+        self.display_keep_out(&grid_rect, &painter, ui.visuals().error_fg_color);
+        self.display_origin(&painter, ui.visuals().weak_text_color());
+
+        // Display components, a big new project is refined over several frames:
+        let components = self.grid_db.get_visible_components(&grid_rect);
+        let refined = match self.refined_components {
+            Some(refined) if components.len() > Self::PROGRESSIVE_MIN_COMPONENTS => refined,
+            _ => usize::MAX,
+        };
+        let is_coarse = refined < components.len();
+        for (i, u) in components.iter().enumerate() {
+            match u {
+                _ if i >= refined => {
+                    let (width, height) = u.get_dimension();
+                    let color = theme.get_fill_color();
+                    let pos = u.get_position();
+                    painter.add(filled_cells(&self.state, &pos, width, height, color));
+                }
+                Component::TextField(f) => f.display_text(
+                    &self.grid_db.expand_variables(&f.text),
+                    &self.state,
+                    &canvas,
+                ),
+                _ => u.display(&self.state, &canvas, theme),
+            }
+        }
+        self.refined_components = is_coarse.then_some(refined + Self::PROGRESSIVE_STEP);
 
-        // Display nets:
+        // Display nets, as plain lines until the components are refined:
         let net_segments = self.grid_db.get_visible_net_segments(&grid_rect);
         painter.extend(net_segments.iter().map(|segment| {
+            if is_coarse {
+                let half_cell = vec2(0.5, 0.5) * self.state.grid_size;
+                let points = [segment.pos1, segment.pos2]
+                    .map(|pos| self.state.grid_to_screen(&pos) + half_cell);
+                return Shape::line_segment(points, Stroke::new(1.0, theme.get_stroke_color()));
+            }
+            let value = self
+                .net_values
+                .as_ref()
+                .and_then(|values| values.get(&segment.net_id).copied());
             Shape::Mesh(Arc::new(segment.get_mesh(
                 &self.grid_db,
                 &self.state,
                 theme,
+                value,
             )))
         }));
 
+        if self.bundle_nets {
+            let color = theme.get_stroke_color();
+            let bundles = self.bundle_cache.get(&self.grid_db);
+            for bundle in bundles.iter().filter(|b| b.is_visible(&grid_rect)) {
+                bundle.draw(&self.state, &canvas, color);
+            }
+        }
+
+        if self.length_heatmap_visible {
+            self.display_length_heatmap(&net_segments, &painter);
+        }
+
+        if self.unconnected_visible {
+            self.display_unconnected(&self.state, &grid_rect, &painter);
+        }
+
+        self.display_library_updates(&grid_rect, &painter, ui.visuals().warn_fg_color);
+
+        if self.net_legend_visible {
+            self.display_net_legend(&painter, ui.visuals(), theme);
+        }
+
+        if is_coarse {
+            painter.text(
+                self.state.rect.right_top() + vec2(-6.0, 6.0),
+                egui::Align2::RIGHT_TOP,
+                locale.rendering,
+                FontId::proportional(12.0),
+                ui.visuals().weak_text_color(),
+            );
+            ui.ctx().request_repaint();
+        }
+
         self.handle_drag_resp(
             &ui.painter().with_clip_rect(self.state.rect),
             ui.visuals().strong_text_color().gamma_multiply(0.08),
         );
         self.interaction_manager
             .draw(&mut self.grid_db, &self.state, &painter, ui);
+        if let Some(pos) = self.interaction_manager.take_focus_request() {
+            self.focus_on(pos);
+        }
+        if let Some(cursor) = self.state.cursor_pos
+            && ui.memory(|m| m.focused().is_none())
+            && ui.input(|i| i.key_down(Self::MAGNIFIER_KEY) && i.modifiers.is_none())
+        {
+            self.display_magnifier(ui, cursor, theme);
+        }
+        self.handle_quick_add(ui, &response);
+        self.handle_clipboard(ui, &response);
+        self.adapt_lod(frame_start.elapsed());
+    }
+
+    /// Raises the LOD thresholds when the drawing is slow, and restores them when it's fast again
+    fn adapt_lod(&mut self, frame_time: Duration) {
+        if !self.adaptive_lod {
+            self.state.lod_bias = 1.0;
+            return;
+        }
+        let (total, frames) = &mut self.frame_times;
+        *total += frame_time;
+        *frames += 1;
+        if *frames < Self::LOD_ADAPT_FRAMES {
+            return;
+        }
+        let average = *total / *frames;
+        self.frame_times = (Duration::ZERO, 0);
+        let bias = self.state.lod_bias;
+        self.state.lod_bias = if average > Self::TARGET_FRAME_TIME {
+            (bias * Self::LOD_BIAS_STEP).min(Self::MAX_LOD_BIAS)
+        } else if average < Self::TARGET_FRAME_TIME / 2 {
+            (bias / Self::LOD_BIAS_STEP).max(1.0)
+        } else {
+            bias
+        };
+        self.update_grid_size();
+    }
+
+    /// State of the field zoomed around `center`, which stays in place
+    fn get_magnifier_state(&self, center: Pos2) -> FieldState {
+        let mut state = self.state.clone();
+        let local_pos = center - self.state.rect.min;
+        state.offset = (self.state.offset - local_pos) * Self::MAGNIFIER_ZOOM + local_pos;
+        state.scale *= Self::MAGNIFIER_ZOOM;
+        state.debounce = false;
+        state.update_grid_size();
+        state
+    }
+
+    /// Circular inset of the zoomed field around the cursor, the viewport is not changed
+    fn display_magnifier(&self, ui: &egui::Ui, center: Pos2, theme: Theme) {
+        let state = self.get_magnifier_state(center);
+        let radius = Self::MAGNIFIER_RADIUS;
+        let bounds = Rect::from_center_size(center, Vec2::splat(radius * 2.0));
+        let layer = LayerId::new(Order::Foreground, ui.id().with("magnifier"));
+        let painter = Painter::new(ui.ctx().clone(), layer, bounds.intersect(self.state.rect));
+        let canvas = PainterCanvas(painter.clone());
+        painter.circle_filled(center, radius, ui.visuals().panel_fill);
+        let grid_rect = grid_rect(
+            0,
+            state.screen_to_grid(bounds.min),
+            state.screen_to_grid(bounds.max),
+        );
+        for u in self.grid_db.get_visible_components(&grid_rect) {
+            match u {
+                Component::TextField(f) => {
+                    f.display_text(&self.grid_db.expand_variables(&f.text), &state, &canvas)
+                }
+                _ => u.display(&state, &canvas, theme),
+            }
+        }
+        painter.extend(
+            (self.grid_db.get_visible_net_segments(&grid_rect).iter()).map(|segment| {
+                let value = (self.net_values.as_ref())
+                    .and_then(|values| values.get(&segment.net_id).copied());
+                Shape::Mesh(Arc::new(segment.get_mesh(
+                    &self.grid_db,
+                    &state,
+                    theme,
+                    value,
+                )))
+            }),
+        );
+        if self.unconnected_visible {
+            self.display_unconnected(&state, &grid_rect, &painter);
+        }
+        // Shapes are clipped only by rectangles, so the tessellated inset is cut by the circle:
+        let shapes = ui.ctx().graphics_mut(|graphics| {
+            std::mem::take(graphics.entry(layer))
+                .all_entries()
+                .cloned()
+                .collect()
+        });
+        let circle: Vec<Pos2> = (0..Self::MAGNIFIER_CORNERS)
+            .map(|i| {
+                let angle = std::f32::consts::TAU * i as f32 / Self::MAGNIFIER_CORNERS as f32;
+                center + Vec2::angled(angle) * radius
+            })
+            .collect();
+        for clipped in ui.ctx().tessellate(shapes, ui.ctx().pixels_per_point()) {
+            if let egui::epaint::Primitive::Mesh(mesh) = clipped.primitive {
+                painter.add(clip_mesh(&mesh, &circle));
+            }
+        }
+        painter.circle_stroke(center, radius, ui.visuals().window_stroke);
+    }
+
+    // Colors of the nets in the current color mode, in the bottom left corner
+    fn display_net_legend(&self, painter: &Painter, visuals: &egui::Visuals, theme: Theme) {
+        const PADDING: f32 = 6.0;
+        const ROW_HEIGHT: f32 = 16.0;
+        const SAMPLE_WIDTH: f32 = 24.0;
+        let mode = self.state.net_style.color_mode;
+        let mut entries: Vec<(String, Color32)> = match mode {
+            NetColorMode::Uniform => return,
+            NetColorMode::NetClass => vec![],
+            NetColorMode::SimulationValues => [Logic::Low, Logic::High, Logic::X, Logic::Z]
+                .into_iter()
+                .map(|value| {
+                    let color = mode.get_net_color(None, Some(value), theme);
+                    (value.to_str().to_owned(), color)
+                })
+                .collect(),
+        };
+        // Nets without values are colored by their classes in the simulation mode too:
+        entries.extend(
+            self.grid_db
+                .get_net_classes()
+                .iter()
+                .filter(|class| class.color.is_some())
+                .map(|class| (class.name.clone(), class.get_color(theme))),
+        );
+        if entries.is_empty() {
+            return;
+        }
+        let font = FontId::proportional(12.0);
+        let galleys: Vec<_> = entries
+            .into_iter()
+            .map(|(name, color)| {
+                let galley = painter.layout_no_wrap(name, font.clone(), visuals.text_color());
+                (galley, color)
+            })
+            .collect();
+        let text_width = galleys.iter().map(|(g, _)| g.size().x).fold(0.0, f32::max);
+        let size = vec2(
+            text_width + SAMPLE_WIDTH + 3.0 * PADDING,
+            galleys.len() as f32 * ROW_HEIGHT + 2.0 * PADDING,
+        );
+        let rect = Rect::from_min_size(
+            self.state.rect.left_bottom() + vec2(PADDING, -PADDING - size.y),
+            size,
+        );
+        painter.rect(
+            rect,
+            4.0,
+            visuals.window_fill.gamma_multiply(0.9),
+            visuals.window_stroke,
+            StrokeKind::Inside,
+        );
+        for (i, (galley, color)) in galleys.into_iter().enumerate() {
+            let x = rect.min.x + PADDING;
+            let y = rect.min.y + PADDING + (i as f32 + 0.5) * ROW_HEIGHT;
+            painter.line_segment(
+                [pos2(x, y), pos2(x + SAMPLE_WIDTH, y)],
+                Stroke::new(3.0, color),
+            );
+            let text_pos = pos2(x + SAMPLE_WIDTH + PADDING, y - galley.size().y / 2.0);
+            painter.galley(text_pos, galley, visuals.text_color());
+        }
+    }
+
+    // Marks unconnected connection points with hollow red squares
+    fn display_unconnected(&self, state: &FieldState, grid_rect: &GridRect, painter: &Painter) {
+        let size = vec2(0.5, 0.5) * state.grid_size;
+        let stroke = Stroke::new(state.grid_size * 0.1, Color32::RED);
+        for id in self.grid_db.get_visible_component_ids(grid_rect) {
+            let comp = self.grid_db.get_component(&id).unwrap();
+            for connection_id in 0..comp.get_connections_number() {
+                let point = GridDBConnectionPoint {
+                    component_id: id,
+                    connection_id,
+                };
+                if self.grid_db.is_connected(&point) {
+                    continue;
+                }
+                if let Some(center) = comp.get_connection_position(connection_id, state) {
+                    painter.rect_stroke(
+                        Rect::from_center_size(center, size),
+                        0.0,
+                        stroke,
+                        StrokeKind::Middle,
+                    );
+                }
+            }
+        }
+    }
+
+    // Crosshair at the center of the origin cell
+    fn display_origin(&self, painter: &Painter, color: Color32) {
+        let center = self.state.grid_to_screen(&self.grid_db.get_origin())
+            + vec2(0.5, 0.5) * self.state.grid_size;
+        let size = self.state.grid_size;
+        let stroke = Stroke::new((size * 0.05).max(1.0), color);
+        painter.line_segment([center - vec2(size, 0.0), center + vec2(size, 0.0)], stroke);
+        painter.line_segment([center - vec2(0.0, size), center + vec2(0.0, size)], stroke);
+        painter.circle_stroke(center, size * 0.3, stroke);
+    }
+
+    // Hatches keep-out regions
+    fn display_keep_out(&self, grid_rect: &GridRect, painter: &Painter, color: Color32) {
+        let stroke = Stroke::new((self.state.grid_size * 0.05).max(1.0), color);
+        for region in self.grid_db.get_keep_out_regions() {
+            if region.max.x < grid_rect.min.x
+                || region.max.y < grid_rect.min.y
+                || region.min.x > grid_rect.max.x
+                || region.min.y > grid_rect.max.y
+            {
+                continue;
+            }
+            let rect = Rect::from_min_max(
+                self.state.grid_to_screen(&region.min),
+                self.state.grid_to_screen(&(region.max + grid_pos(1, 1))),
+            );
+            painter.rect(
+                rect,
+                0.0,
+                color.gamma_multiply(0.08),
+                stroke,
+                StrokeKind::Inside,
+            );
+            let hatch_painter = painter.with_clip_rect(rect.intersect(painter.clip_rect()));
+            let step = self.state.grid_size.max(4.0);
+            let mut x = rect.min.x - rect.height();
+            while x < rect.max.x {
+                hatch_painter.line_segment(
+                    [pos2(x, rect.max.y), pos2(x + rect.height(), rect.min.y)],
+                    Stroke::new(stroke.width, color.gamma_multiply(0.3)),
+                );
+                x += step;
+            }
+        }
+    }
+
+    // Marks library parts having a newer version with a badge in the top right corner
+    fn display_library_updates(&self, grid_rect: &GridRect, painter: &Painter, color: Color32) {
+        if !self.state.label_visible {
+            return;
+        }
+        for id in self.grid_db.get_visible_component_ids(grid_rect) {
+            let comp = self.grid_db.get_component(&id).unwrap();
+            if comp
+                .get_library_source()
+                .and_then(get_library_update)
+                .is_none()
+            {
+                continue;
+            }
+            let pos = comp.get_position();
+            let (width, _) = comp.get_dimension();
+            painter.text(
+                self.state.grid_to_screen(&grid_pos(pos.x + width, pos.y)),
+                egui::Align2::CENTER_CENTER,
+                "⟳",
+                self.state.label_font.clone(),
+                color,
+            );
+        }
+    }
+
+    // Colors nets from green (short) to red (the longest one)
+    fn display_length_heatmap(&self, segments: &[&NetSegment], painter: &Painter) {
+        let max_length = self
+            .grid_db
+            .nets
+            .values()
+            .map(|net| net.get_length())
+            .max()
+            .unwrap_or(0)
+            .max(1);
+        let ofs = vec2(0.5, 0.5) * self.state.grid_size;
+        for segment in segments {
+            let Some(net) = self.grid_db.get_net(&segment.net_id) else {
+                continue;
+            };
+            let t = net.get_length() as f32 / max_length as f32;
+            let color = Color32::from_rgba_unmultiplied(
+                (255.0 * t) as u8,
+                (255.0 * (1.0 - t)) as u8,
+                0,
+                120,
+            );
+            painter.line_segment(
+                [
+                    self.state.grid_to_screen(&segment.pos1) + ofs,
+                    self.state.grid_to_screen(&segment.pos2) + ofs,
+                ],
+                Stroke::new((self.state.grid_size * 0.3).max(1.0), color),
+            );
+        }
+    }
+
+    /// Shows information about the cursor and the selected net
+    pub fn show_status(&mut self, ui: &mut egui::Ui, locale: &'static Locale) {
+        if self.interaction_manager.is_drawing_keep_out() {
+            ui.label(locale.keep_out_hint);
+            ui.separator();
+        }
+        if self.interaction_manager.is_picking_origin() {
+            ui.label(locale.pick_origin_hint);
+            ui.separator();
+        }
+        if self.interaction_manager.is_placing_fragment() {
+            ui.label(locale.place_fragment_hint);
+            ui.separator();
+        }
+        ui.menu_button("⌖", |ui| {
+            if ui.button(locale.set_origin).clicked() {
+                self.interaction_manager.start_origin_picking();
+                ui.close();
+            }
+            if ui.button(locale.reset_origin).clicked() {
+                self.grid_db.set_user_origin(None);
+                ui.close();
+            }
+        })
+        .response
+        .on_hover_text(locale.origin);
+        if let Some(pos) = self.state.cursor_pos {
+            let GridPos { x, y } = self.state.screen_to_grid(pos) - self.grid_db.get_origin();
+            ui.label(format!("x: {x}, y: {y}"));
+        }
+        if let [id] = self.interaction_manager.get_selected_components()[..]
+            && let Some(name) = self
+                .grid_db
+                .get_component(&id)
+                .and_then(|c| c.get_instance_name(id))
+        {
+            ui.separator();
+            ui.label(name);
+            if let Some(source) = self
+                .grid_db
+                .get_component(&id)
+                .and_then(|c| c.get_library_source())
+                && let Some((version, _)) = get_library_update(source)
+            {
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    format!(
+                        "⟳ {}: {} v{} → v{version}",
+                        locale.update_available, source.library, source.version
+                    ),
+                );
+                if ui.button(locale.update).clicked() {
+                    self.interaction_manager
+                        .update_from_library(id, &mut self.grid_db, locale);
+                } else if ui.button(locale.keep).clicked() {
+                    self.interaction_manager
+                        .keep_library_version(id, &mut self.grid_db);
+                }
+            }
+        }
+        if let Some(net_id) = self.interaction_manager.get_selected_net()
+            && let Some(net) = self.grid_db.get_net(&net_id)
+        {
+            ui.separator();
+            ui.label(format!(
+                "{} #{}: {} {}, {} {}",
+                locale.net,
+                net_id,
+                locale.net_length,
+                net.get_length(),
+                locale.manhattan_distance,
+                net.get_manhattan_distance()
+            ));
+            let class = net.class.clone();
+            let mut selected = class.clone();
+            egui::ComboBox::from_id_salt("selected_net_class")
+                .selected_text(if class.is_empty() {
+                    locale.no_net_class
+                } else {
+                    &class
+                })
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut selected, Default::default(), locale.no_net_class);
+                    for name in self.grid_db.get_net_classes().iter().map(|c| &c.name) {
+                        ui.selectable_value(&mut selected, name.as_str().into(), name);
+                    }
+                })
+                .response
+                .on_hover_text(locale.net_class);
+            if selected != class {
+                self.interaction_manager
+                    .set_net_class(net_id, selected, &mut self.grid_db);
+            }
+        }
+        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+            ui.menu_button(format!("{:.0}%", self.get_zoom() * 100.0), |ui| {
+                self.show_zoom_presets(ui, locale)
+            })
+            .response
+            .on_hover_text(format!("{}\n{}", locale.zoom, locale.magnifier_hint));
+            ui.separator();
+            let filter = &mut self.interaction_manager.selection_filter;
+            ui.toggle_value(&mut filter.annotations, locale.text_labels);
+            ui.toggle_value(&mut filter.nets, locale.nets);
+            ui.toggle_value(&mut filter.components, locale.components);
+            ui.label(locale.selection_filter);
+        });
+    }
+
+    fn update_grid_size(&mut self) {
+        self.state.update_grid_size();
+    }
+
+    /// Zooms around the center of the field, used by the zoom buttons
+    /// Cells visible in the field
+    pub fn get_visible_grid_rect(&self) -> GridRect {
+        grid_rect(
+            0,
+            self.state.screen_to_grid(self.state.rect.min),
+            self.state.screen_to_grid(self.state.rect.max),
+        )
+    }
+
+    pub fn zoom_by(&mut self, zoom_factor: f32) {
+        let new_scale = (self.state.scale * zoom_factor).clamp(Self::MIN_SCALE, Self::MAX_SCALE);
+        let center = self.state.rect.size() * 0.5;
+        self.state.offset = (self.state.offset - center) * (new_scale / self.state.scale) + center;
+        self.state.scale = new_scale;
+        self.update_grid_size();
+    }
+
+    /// Current zoom relative to [`Self::DEFAULT_SCALE`], 1.0 is 100%
+    pub fn get_zoom(&self) -> f32 {
+        self.state.scale / Self::DEFAULT_SCALE
+    }
+
+    pub fn set_zoom(&mut self, zoom: f32) {
+        self.zoom_by(zoom * Self::DEFAULT_SCALE / self.state.scale);
+    }
+
+    /// Zooms and moves view to show the whole project
+    pub fn zoom_to_fit(&mut self) {
+        if self.grid_db.get_component_ids().is_empty() {
+            self.set_zoom(1.0);
+            self.focus_on(grid_pos(0, 0));
+            return;
+        }
+        let (min, max) = self.grid_db.get_bounds();
+        let size = vec2((max.x - min.x + 1) as f32, (max.y - min.y + 1) as f32);
+        let available = self.state.rect.size() * Self::FIT_MARGIN / Self::BASE_GRID_SIZE;
+        self.state.scale = (available.x / size.x)
+            .min(available.y / size.y)
+            .clamp(Self::MIN_SCALE, Self::MAX_SCALE);
+        self.update_grid_size();
+        let center = vec2(min.x as f32, min.y as f32) + size * 0.5;
+        self.state.offset = self.state.rect.size() * 0.5 - center * self.state.grid_size;
+    }
+
+    /// Zoom presets and zoom to fit, shown in the View menu and in the status bar
+    pub fn show_zoom_presets(&mut self, ui: &mut egui::Ui, locale: &'static Locale) {
+        for zoom in ZOOM_PRESETS {
+            if ui.button(format!("{:.0}%", zoom * 100.0)).clicked() {
+                self.set_zoom(*zoom);
+                ui.close();
+            }
+        }
+        if ui.button(locale.zoom_to_fit).clicked() {
+            self.zoom_to_fit();
+            ui.close();
+        }
+    }
+
+    /// Frees the caches of the project moved to a background tab, they are rebuilt when needed
+    pub fn unload_caches(&mut self) {
+        self.interaction_manager.unload_caches();
+        self.quick_add = None;
+        self.grid_db.clear_change_log();
+    }
+
+    /// Takes the view settings of the other field, they are shared by the project tabs
+    pub fn copy_settings(&mut self, other: &Field) {
+        self.grid_type = other.grid_type;
+        self.adaptive_lod = other.adaptive_lod;
+        self.state.net_style = other.state.net_style;
+        self.unconnected_visible = other.unconnected_visible;
+        self.bundle_nets = other.bundle_nets;
+        self.length_heatmap_visible = other.length_heatmap_visible;
+        self.net_legend_visible = other.net_legend_visible;
+        self.auto_place = other.auto_place;
+        self.interaction_manager.snap_annotations = other.interaction_manager.snap_annotations;
+        self.wheel_mode = other.wheel_mode;
+        self.save_view = other.save_view;
+    }
+
+    /// Writes the current view into the project before saving, if it is enabled
+    pub fn store_view(&mut self) {
+        let view = self.save_view.then(|| {
+            let center = (self.state.rect.size() * 0.5 - self.state.offset) / self.state.grid_size;
+            ProjectView {
+                center: [center.x, center.y],
+                scale: self.state.scale,
+                selection: self.interaction_manager.get_selected_components(),
+            }
+        });
+        self.grid_db.set_view(view);
+    }
+
+    fn restore_view(&mut self, view: ProjectView) {
+        self.state.scale = view.scale.clamp(Self::MIN_SCALE, Self::MAX_SCALE);
+        self.update_grid_size();
+        self.state.offset =
+            self.state.rect.size() * 0.5 - Vec2::from(view.center) * self.state.grid_size;
+        self.interaction_manager
+            .select_components(&view.selection, &self.grid_db);
+    }
+
+    /// Moves view so that `pos` is in the center of the field
+    pub fn focus_on(&mut self, pos: GridPos) {
+        self.state.offset = self.state.rect.size() * 0.5
+            - vec2(pos.x as f32 + 0.5, pos.y as f32 + 0.5) * self.state.grid_size;
     }
 
     pub fn set_external_drag_resp(&mut self, resp: DragComponentResponse) {
         self.external_drag_resp = resp;
     }
 }
+
+/// Part of the mesh inside the convex `polygon`, the cut triangles are split into fans
+fn clip_mesh(mesh: &egui::Mesh, polygon: &[Pos2]) -> egui::Mesh {
+    let mut result = egui::Mesh::with_texture(mesh.texture_id);
+    let centroid =
+        polygon.iter().fold(Vec2::ZERO, |sum, p| sum + p.to_vec2()) / polygon.len() as f32;
+    let edges: Vec<(Pos2, Vec2)> = (0..polygon.len())
+        .map(|i| {
+            let (a, b) = (polygon[i], polygon[(i + 1) % polygon.len()]);
+            // The normal is turned inside, whatever the winding of the polygon:
+            let normal = (b - a).rot90();
+            let sign = normal.dot(centroid.to_pos2() - a).signum();
+            (a, normal * sign)
+        })
+        .collect();
+    for triangle in mesh.indices.chunks_exact(3) {
+        let mut vertices: Vec<egui::epaint::Vertex> = triangle
+            .iter()
+            .map(|&i| mesh.vertices[i as usize])
+            .collect();
+        for &(a, normal) in &edges {
+            let distance = |v: &egui::epaint::Vertex| normal.dot(v.pos - a);
+            if vertices.iter().all(|v| distance(v) >= 0.0) {
+                continue;
+            }
+            let mut inside = Vec::with_capacity(vertices.len() + 1);
+            for (i, v) in vertices.iter().enumerate() {
+                let next = &vertices[(i + 1) % vertices.len()];
+                let (d, next_d) = (distance(v), distance(next));
+                if d >= 0.0 {
+                    inside.push(*v);
+                }
+                if (d >= 0.0) != (next_d >= 0.0) {
+                    let t = d / (d - next_d);
+                    inside.push(egui::epaint::Vertex {
+                        pos: v.pos.lerp(next.pos, t),
+                        uv: v.uv.lerp(next.uv, t),
+                        color: v.color.lerp_to_gamma(next.color, t),
+                    });
+                }
+            }
+            vertices = inside;
+            if vertices.is_empty() {
+                break;
+            }
+        }
+        if vertices.len() < 3 {
+            continue;
+        }
+        let first = result.vertices.len() as u32;
+        result.vertices.extend(vertices.iter().copied());
+        for i in 1..vertices.len() as u32 - 1 {
+            result.add_triangle(first, first + i, first + i + 1);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use egui::{Color32, Rect, pos2, vec2};
+
+    use web_time::Duration;
+
+    use super::{Field, clip_mesh};
+    use crate::{
+        grid_db::{GridDB, LodLevel},
+        testing::half_adder,
+    };
+
+    #[test]
+    fn zoom_presets_and_fit() {
+        let mut field = Field::new();
+        field.state.rect = Rect::from_min_max(pos2(0.0, 0.0), pos2(800.0, 600.0));
+        assert_eq!(field.get_zoom(), 1.0);
+        field.set_zoom(0.5);
+        assert_eq!(field.get_zoom(), 0.5);
+
+        field.grid_db = half_adder();
+        field.zoom_to_fit();
+        let (min, max) = field.grid_db.get_bounds();
+        let rect = field.state.rect;
+        assert!(rect.contains(field.state.grid_to_screen(&min)));
+        assert!(rect.contains(field.state.grid_to_screen(&max)));
+        // Bigger zoom would not fit:
+        let size = field.state.grid_to_screen(&max) - field.state.grid_to_screen(&min);
+        assert!(size.x > rect.width() * 0.8 || size.y > rect.height() * 0.8);
+    }
+
+    #[test]
+    fn adaptive_lod() {
+        let mut field = Field::new();
+        assert!(field.state.lod_level() == LodLevel::Max);
+        for _ in 0..10 * Field::LOD_ADAPT_FRAMES {
+            field.adapt_lod(Duration::from_millis(50));
+        }
+        assert_eq!(field.state.lod_bias, Field::MAX_LOD_BIAS);
+        assert!(field.state.lod_level() == LodLevel::Mid);
+        // Restored when the frames are fast again:
+        for _ in 0..10 * Field::LOD_ADAPT_FRAMES {
+            field.adapt_lod(Duration::from_millis(1));
+        }
+        assert_eq!(field.state.lod_bias, 1.0);
+        assert!(field.state.lod_level() == LodLevel::Max);
+    }
+
+    #[test]
+    fn magnifier_state() {
+        let mut field = Field::new();
+        field.state.rect = Rect::from_min_max(pos2(100.0, 50.0), pos2(900.0, 650.0));
+        field.state.offset = vec2(-35.0, 12.0);
+        let center = pos2(412.0, 301.0);
+        let state = field.get_magnifier_state(center);
+        assert_eq!(
+            state.grid_size,
+            field.state.grid_size * Field::MAGNIFIER_ZOOM
+        );
+        // The cell under the cursor stays in place:
+        assert_eq!(
+            state.screen_to_grid(center),
+            field.state.screen_to_grid(center)
+        );
+        let pos = field.state.screen_to_grid(center);
+        let offset = center - field.state.grid_to_screen(&pos);
+        assert!(
+            (center - state.grid_to_screen(&pos) - offset * Field::MAGNIFIER_ZOOM).length() < 1e-3
+        );
+    }
+
+    #[test]
+    fn mesh_clipped_by_polygon() {
+        let square = [
+            pos2(0.0, 0.0),
+            pos2(10.0, 0.0),
+            pos2(10.0, 10.0),
+            pos2(0.0, 10.0),
+        ];
+        let mut mesh = egui::Mesh::default();
+        mesh.colored_vertex(pos2(2.0, 2.0), Color32::RED);
+        mesh.colored_vertex(pos2(8.0, 2.0), Color32::RED);
+        mesh.colored_vertex(pos2(2.0, 8.0), Color32::RED);
+        mesh.add_triangle(0, 1, 2);
+        // Inside triangle is kept as is:
+        let clipped = clip_mesh(&mesh, &square);
+        assert_eq!(clipped.indices.len(), 3);
+        assert_eq!(clipped.calc_bounds(), mesh.calc_bounds());
+        // The corner sticking out is cut off:
+        mesh.vertices[1].pos = pos2(20.0, 2.0);
+        let clipped = clip_mesh(&mesh, &square);
+        assert_eq!(clipped.indices.len(), 6);
+        assert_eq!(clipped.calc_bounds().max.x, 10.0);
+        // Outside triangle is dropped:
+        mesh.translate(vec2(30.0, 0.0));
+        assert!(clip_mesh(&mesh, &square).is_empty());
+    }
+
+    #[test]
+    fn project_view_round_trip() {
+        let rect = Rect::from_min_max(pos2(0.0, 0.0), pos2(800.0, 600.0));
+        let mut field = Field::new();
+        field.state.rect = rect;
+        field.grid_db = half_adder();
+        field.zoom_to_fit();
+        let ids = field.grid_db.get_component_ids();
+        field
+            .interaction_manager
+            .select_components(&[ids[1], ids[3], 100], &field.grid_db);
+        field.store_view();
+
+        let mut opened = Field::new();
+        opened.state.rect = rect;
+        opened.grid_db = GridDB::load_from_json(field.grid_db.dump_to_json().unwrap()).unwrap();
+        let view = opened.grid_db.take_view().unwrap();
+        opened.restore_view(view);
+        assert_eq!(opened.state.scale, field.state.scale);
+        assert!((opened.state.offset - field.state.offset).length() < 1e-3);
+        assert_eq!(
+            opened.interaction_manager.get_selected_components(),
+            [ids[1], ids[3]]
+        );
+
+        field.save_view = false;
+        field.store_view();
+        assert_eq!(field.grid_db.dump_to_json(), half_adder().dump_to_json());
+    }
+}
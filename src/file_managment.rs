@@ -1,32 +1,216 @@
-use std::sync::{Arc, atomic::AtomicBool};
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize},
+    },
+};
 
 #[cfg(not(target_arch = "wasm32"))]
-use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use egui::{ColorImage, Rect, mutex::Mutex};
+use web_time::{Duration, Instant};
+
+use editor_core::datasheet::DatasheetFormat;
+#[cfg(target_arch = "wasm32")]
+use editor_core::worker::{WorkerRequest, WorkerResponse};
+
+use crate::{
+    canvas::SchematicTheme,
+    component_lib::{LIBRARY_SUFFIX, UserLibrary},
+    export_size::{ExportSize, ExportSizeMode, fit_image, set_png_dpi},
+    field::{GridType, SUPPORTED_GRID_TYPES},
+    grid_db::{
+        ComponentColor, DocumentSize, ExportGrid, ExportLayer, GridDB, GridRect, Id, Logic,
+        NetStyle, SvgExportJob, Theme, YosysConnection, get_svg_drawing_cells, is_yosys_json,
+    },
+    locale::Locale,
+    settings::GetName,
+    thumbnail::encode_thumbnail,
+};
+#[cfg(target_arch = "wasm32")]
+use crate::worker::WorkerTask;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+pub const SIMULATION_SUFFIX: &str = ".sim.json";
+/// Thumbnails are saved next to the projects on native, and in the local storage on web
+pub const THUMBNAIL_SUFFIX: &str = ".thumb.png";
+/// Extensions of the project files, the longer ones are first
+const PROJECT_SUFFIXES: [&str; 4] = [".json.gz", ".gz", ".json", ".svg"];
+const MAX_RECENT_PROJECTS: usize = 10;
+
+/// Name of the project file without the extension, and the extension
+pub fn split_project_suffix(file_name: &str) -> (&str, &str) {
+    PROJECT_SUFFIXES
+        .iter()
+        .find_map(|suffix| Some((file_name.strip_suffix(suffix)?, *suffix)))
+        .unwrap_or((file_name, ""))
+}
 
+// SVG is written directly to the file on native, and downloaded from memory on web
 #[cfg(not(target_arch = "wasm32"))]
-use std::fs::File;
+type SvgExportWriter = std::io::BufWriter<std::fs::File>;
+#[cfg(target_arch = "wasm32")]
+type SvgExportWriter = Vec<u8>;
+
+/// Part of the design written into the exported file
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum ExportScope {
+    Design,
+    Selection,
+    /// Components visible in the editor
+    Viewport,
+}
+
+impl ExportScope {
+    const ALL: [Self; 3] = [Self::Design, Self::Selection, Self::Viewport];
+
+    fn get_name(&self, locale: &'static Locale) -> &'static str {
+        match self {
+            Self::Design => locale.whole_design,
+            Self::Selection => locale.selection,
+            Self::Viewport => locale.viewport,
+        }
+    }
+}
 
-use egui::{Theme, mutex::Mutex};
+struct SvgExport {
+    theme: Theme,
+    pending_themes: Vec<Theme>, // Exported to separate files after the current theme
+    themed_names: bool,         // Names of the files are suffixed by the theme
+    cell_size: f32,
+    document_size: Option<DocumentSize>,
+    scoped_db: Option<GridDB>, // Exported instead of the design, if only its part is chosen
+    job: Option<SvgExportJob<SvgExportWriter>>,
+    #[cfg(target_arch = "wasm32")]
+    file_name: String, // Without the extension
+    #[cfg(not(target_arch = "wasm32"))]
+    path: Arc<Mutex<Option<Option<PathBuf>>>>, // None while the file dialog is open
+    #[cfg(target_arch = "wasm32")]
+    worker: Option<WorkerTask>, // Export in the Web Worker, used instead of `job` when available
+}
+
+impl SvgExport {
+    /// Name of the file of the current theme, like `name_dark.svg`
+    fn get_file_name(&self, name: &str) -> String {
+        match (self.themed_names, self.theme) {
+            (false, _) => format!("{name}.svg"),
+            (true, Theme::Light) => format!("{name}_light.svg"),
+            (true, Theme::Dark) => format!("{name}_dark.svg"),
+        }
+    }
 
-use crate::{grid_db::GridDB, locale::Locale};
+    /// Path of the file of the current theme next to the picked one
+    #[cfg(not(target_arch = "wasm32"))]
+    fn get_path(&self, picked: PathBuf) -> PathBuf {
+        let name = picked.file_stem().unwrap_or_default().to_string_lossy();
+        picked.with_file_name(self.get_file_name(&name))
+    }
+
+    /// Starts the export of the current theme in the worker, if it is available,
+    /// otherwise the export is done in steps
+    #[cfg(target_arch = "wasm32")]
+    fn start_wasm(
+        &mut self,
+        ctx: &egui::Context,
+        design: &GridDB,
+        net_style: NetStyle,
+        net_values: &HashMap<Id, Logic>,
+        grid: ExportGrid,
+    ) {
+        let db = self.scoped_db.as_ref().unwrap_or(design);
+        self.worker = WorkerRequest::export_svg(
+            db,
+            self.theme,
+            self.cell_size,
+            net_style,
+            net_values.clone(),
+            self.document_size,
+            grid,
+        )
+        .and_then(|request| WorkerTask::start(ctx, &request));
+        self.job = match self.worker {
+            Some(_) => None,
+            None => SvgExportJob::new_with_size(
+                db,
+                self.theme,
+                self.cell_size,
+                net_style,
+                self.document_size,
+                vec![],
+            )
+            .and_then(|job| job.with_grid(grid))
+            .ok()
+            .map(|job| job.with_net_values(net_values.clone())),
+        };
+    }
+}
 
 #[derive(PartialEq, Debug)]
 enum FileManagerState {
     OpenFile,
+    /// Loading a project to be placed into the opened one
+    ImportFile,
     SaveFile,
     ExportSVGDialog {
         export_theme: Theme,
-        cell_size: String,
+        both_themes: bool, // Light and dark variants are exported to separate files
+        size: ExportSize,
+        back_annotation: bool,
+        scope: ExportScope,
+        excluded: Vec<ExportLayer>,
     },
     ExportSVG,
+    ExportPNGDialog {
+        rect: Rect,
+        size: ExportSize,
+        /// Size of the captured cells in points
+        grid_size: f32,
+    },
+    ExportPNGCapture {
+        rect: Rect,
+        size: ExportSize,
+        grid_size: f32,
+        requested: Instant,
+    },
+    ExportPNG,
+    #[cfg(target_arch = "wasm32")]
+    SaveAsDialog {
+        file_name: String,
+    },
     None,
     Error(&'static str),
 }
 
+/// Time to wait for the screenshot before the PNG export is aborted
+const SCREENSHOT_TIMEOUT: Duration = Duration::from_secs(3);
+
 pub struct FileManager {
     state: FileManagerState,
     done: Arc<AtomicBool>, // For async action status checking
     loaded_data: Arc<Mutex<Result<(GridDB, String), &'static str>>>,
+    net_style: NetStyle, // Style of nets for the ongoing export
+    simulation_values: Option<HashMap<Id, Logic>>, // Values of nets, if the simulation is open
+    net_values: HashMap<Id, Logic>, // Values written over the nets in the ongoing export
+    export_grid: ExportGrid, // Grid drawn under the ongoing export
+    #[cfg(not(target_arch = "wasm32"))]
+    file_path: Arc<Mutex<Option<PathBuf>>>, // Path used by "Save" without the dialog
+    errors: Arc<Mutex<Vec<String>>>, // Errors of async actions, shown as toasts
+    loaded_libraries: Arc<Mutex<Vec<UserLibrary>>>, // Dropped libraries to be installed
+    loaded_simulation: Arc<Mutex<Option<String>>>, // Simulation state to be restored
+    imported: Option<GridDB>, // Loaded by the import, to be placed by the user
+    svg_export: Option<SvgExport>,
+    export_cancelled: Arc<AtomicBool>,
+    export_selection: Vec<Id>, // Selected components when the export is started
+    export_viewport: Option<GridRect>, // Visible part of the field when the export is started
+    scoped_db: Option<GridDB>, // Part of the design chosen in the export dialog
+    pub thumbnails: bool,      // Thumbnails are saved with the projects
+    recent: Arc<Mutex<Vec<String>>>, // Paths of the recent projects, file names on web
+    thumbnail_cache: HashMap<String, Option<egui::load::Bytes>>,
+    cached_revision: usize,
+    thumbnail_revision: Arc<AtomicUsize>, // Changed by saving, so the thumbnails are reloaded
+    saved: Arc<AtomicBool>, // Set when a project is saved, taken by the app to mark it unmodified
 }
 
 impl FileManager {
@@ -35,70 +219,273 @@ impl FileManager {
             state: FileManagerState::None,
             done: Arc::new(AtomicBool::new(false)),
             loaded_data: Arc::new(Mutex::new(Err(&""))), // Dummy value
+            net_style: NetStyle::DEFAULT,
+            simulation_values: None,
+            net_values: HashMap::new(),
+            export_grid: ExportGrid::NONE,
+            #[cfg(not(target_arch = "wasm32"))]
+            file_path: Arc::new(Mutex::new(None)),
+            errors: Arc::new(Mutex::new(vec![])),
+            loaded_libraries: Arc::new(Mutex::new(vec![])),
+            loaded_simulation: Arc::new(Mutex::new(None)),
+            imported: None,
+            svg_export: None,
+            export_cancelled: Arc::new(AtomicBool::new(false)),
+            export_selection: vec![],
+            export_viewport: None,
+            scoped_db: None,
+            thumbnails: true,
+            recent: Arc::new(Mutex::new(vec![])),
+            thumbnail_cache: HashMap::new(),
+            cached_revision: 0,
+            thumbnail_revision: Arc::new(AtomicUsize::new(0)),
+            saved: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Path written by "Save" without the dialog, it's always None on web
+    pub fn get_file_path(&self) -> Option<String> {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self
+            .file_path
+            .lock()
+            .as_ref()
+            .map(|path| path.to_string_lossy().into_owned());
+        #[cfg(target_arch = "wasm32")]
+        None
+    }
+
+    pub fn set_file_path(&mut self, _path: Option<String>) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            *self.file_path.lock() = _path.map(PathBuf::from);
+        }
+    }
+
+    pub fn set_recent(&mut self, recent: Vec<String>) {
+        *self.recent.lock() = recent;
+    }
+
+    /// Recently opened or saved projects, the last one is first
+    pub fn get_recent(&self) -> Vec<String> {
+        self.recent.lock().clone()
+    }
+
+    /// Follows the renamed project file, returns true if it's the opened project
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn project_renamed(&mut self, from: &str, to: &str) -> bool {
+        for name in self.recent.lock().iter_mut() {
+            if name == from {
+                *name = to.to_owned();
+            }
+        }
+        let mut file_path = self.file_path.lock();
+        let is_opened = file_path.as_ref().is_some_and(|path| path == Path::new(from));
+        if is_opened {
+            *file_path = Some(PathBuf::from(to));
+        }
+        is_opened
+    }
+
+    fn add_recent(recent: &Mutex<Vec<String>>, name: String) {
+        let mut recent = recent.lock();
+        recent.retain(|r| *r != name);
+        recent.insert(0, name);
+        recent.truncate(MAX_RECENT_PROJECTS);
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn get_storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok().flatten()
+    }
+
+    /// Writes the thumbnail of the project saved to `name`
+    fn write_thumbnail(name: &str, png: &[u8]) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            _ = std::fs::write(format!("{name}{THUMBNAIL_SUFFIX}"), png);
+        }
+        #[cfg(target_arch = "wasm32")]
+        if let Some(storage) = Self::get_storage() {
+            let hex: String = png.iter().map(|b| format!("{b:02x}")).collect();
+            _ = storage.set_item(&format!("{name}{THUMBNAIL_SUFFIX}"), &hex);
+        }
+    }
+
+    fn read_thumbnail(name: &str) -> Option<Vec<u8>> {
+        #[cfg(not(target_arch = "wasm32"))]
+        return std::fs::read(format!("{name}{THUMBNAIL_SUFFIX}")).ok();
+        #[cfg(target_arch = "wasm32")]
+        {
+            let hex = Self::get_storage()?
+                .get_item(&format!("{name}{THUMBNAIL_SUFFIX}"))
+                .ok()
+                .flatten()?;
+            (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+                .collect()
+        }
+    }
+
+    fn thumbnail_uri(name: &str) -> String {
+        format!("bytes://thumbnail/{name}.png")
+    }
+
+    /// Image of the recent project, None if it was saved without the thumbnail
+    pub fn get_thumbnail(
+        &mut self,
+        ctx: &egui::Context,
+        name: &str,
+    ) -> Option<egui::Image<'static>> {
+        let revision = self
+            .thumbnail_revision
+            .load(std::sync::atomic::Ordering::Relaxed);
+        if revision != self.cached_revision {
+            // The URIs are reused, so the loaded images are replaced by the saved ones:
+            for name in self.thumbnail_cache.keys() {
+                ctx.forget_image(&Self::thumbnail_uri(name));
+            }
+            self.thumbnail_cache.clear();
+            self.cached_revision = revision;
+        }
+        let bytes = self
+            .thumbnail_cache
+            .entry(name.to_owned())
+            .or_insert_with(|| Self::read_thumbnail(name).map(Into::into))
+            .clone()?;
+        Some(egui::Image::from_bytes(Self::thumbnail_uri(name), bytes))
+    }
+
+    /// Remembers the saved project and writes its thumbnail
+    fn project_saved(
+        name: String,
+        thumbnail: Option<Vec<u8>>,
+        recent: &Mutex<Vec<String>>,
+        revision: &AtomicUsize,
+        saved: &AtomicBool,
+    ) {
+        if let Some(png) = thumbnail {
+            Self::write_thumbnail(&name, &png);
+            revision.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        }
+        Self::add_recent(recent, name);
+        saved.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+
+    /// True if a project was saved since the last call
+    pub fn take_saved(&self) -> bool {
+        self.saved.swap(false, std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Returns libraries loaded since the last call
+    pub fn take_libraries(&self) -> Vec<UserLibrary> {
+        std::mem::take(&mut *self.loaded_libraries.lock())
+    }
+
+    /// Returns the simulation state file loaded since the last call
+    pub fn take_simulation(&self) -> Option<String> {
+        self.loaded_simulation.lock().take()
+    }
+
+    /// Returns errors occurred since the last call
+    pub fn take_errors(&self) -> Vec<String> {
+        std::mem::take(&mut *self.errors.lock())
+    }
+
+    fn report_error(
+        errors: &Mutex<Vec<String>>,
+        message: &'static str,
+        err: impl std::fmt::Display,
+    ) {
+        errors.lock().push(format!("{message}: {err}"));
+    }
+
+    fn dropped_file_name(file: &egui::DroppedFile) -> String {
+        #[cfg(not(target_arch = "wasm32"))]
+        if file.name.is_empty()
+            && let Some(name) = file.path.as_ref().and_then(|path| path.file_name())
+        {
+            return name.to_string_lossy().into_owned();
+        }
+        file.name.clone()
+    }
+
+    fn read_dropped_file(file: &egui::DroppedFile) -> Option<Vec<u8>> {
+        if let Some(bytes) = &file.bytes {
+            return Some(bytes.to_vec());
         }
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = &file.path {
+            return std::fs::read(path).ok();
+        }
+        None
     }
 
+    /// The first dropped project is opened, the other ones are appended to it.
+    /// Dropped `.lib.json` files are installed as component libraries.
     fn check_dropping_files(&mut self, ctx: &egui::Context, locale: &'static Locale) {
         if ctx.input(|input_state| !input_state.raw.hovered_files.is_empty()) {
             egui::modal::Modal::new("FileManager".into())
                 .show(ctx, |ui| ui.label(locale.file_hovered_message));
         }
 
-        let file_read_err = ctx.input(|input_state| {
-            if !input_state.raw.dropped_files.is_empty() {
-                if let Some(file) = input_state.raw.dropped_files.first() {
-                    let resp = self.loaded_data.clone();
-                    if let Some(bytes) = file.bytes.clone() {
-                        let file_name = file.name.clone();
-                        self.state = FileManagerState::OpenFile;
-                        let status = self.done.clone().clone();
-                        Self::execute(async move {
-                            let data = bytes.to_vec();
-                            let mut receiver = resp.lock();
-                            *receiver = Self::load_data(data, locale, file_name);
-                            status.store(true, std::sync::atomic::Ordering::Relaxed);
-                        });
-                        return false;
-                    } else {
-                        #[cfg(not(target_arch = "wasm32"))]
-                        {
-                            if let Some(path) = file.path.clone() {
-                                let file_name = file.name.clone();
-                                self.state = FileManagerState::OpenFile;
-                                let status = self.done.clone().clone();
-
-                                Self::execute(async move {
-                                    let mut receiver = resp.lock();
-                                    if let Ok(mut file) = File::open(path) {
-                                        let mut bytes = vec![];
-                                        if let Ok(_size) = file.read_to_end(&mut bytes) {
-                                            *receiver = Self::load_data(bytes, locale, file_name);
-                                            status
-                                                .store(true, std::sync::atomic::Ordering::Relaxed);
-                                        } else {
-                                            *receiver = Err(locale.file_load_error);
-                                            status
-                                                .store(true, std::sync::atomic::Ordering::Relaxed);
-                                        }
-                                    } else {
-                                        *receiver = Err(locale.file_load_error);
-                                        status.store(true, std::sync::atomic::Ordering::Relaxed);
-                                    }
-                                });
-
-                                return true;
-                            }
-                        }
+        let dropped = ctx.input(|input_state| input_state.raw.dropped_files.clone());
+        if dropped.is_empty() {
+            return;
+        }
+        let is_library =
+            |file: &egui::DroppedFile| Self::dropped_file_name(file).ends_with(LIBRARY_SUFFIX);
+        if let Some(_project) = dropped.iter().find(|file| !is_library(file)) {
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                *self.file_path.lock() = _project.path.clone();
+            }
+            self.state = FileManagerState::OpenFile;
+        }
+
+        let resp = self.loaded_data.clone();
+        let status = self.done.clone();
+        let errors = self.errors.clone();
+        let libraries = self.loaded_libraries.clone();
+        Self::execute(async move {
+            let mut result: Option<Result<(GridDB, String), &'static str>> = None;
+            for file in dropped.iter() {
+                let file_name = Self::dropped_file_name(file);
+                let bytes = Self::read_dropped_file(file);
+                if is_library(file) {
+                    let Some(bytes) = bytes else {
+                        Self::report_error(&errors, locale.file_load_error, &file_name);
+                        continue;
+                    };
+                    match serde_json::from_slice::<UserLibrary>(&bytes) {
+                        Ok(library) => libraries.lock().push(library),
+                        Err(err) => Self::report_error(&errors, locale.library_load_error, err),
                     }
+                    continue;
                 }
-                true
-            } else {
-                false
+                // A failed project is shown as the error of opening, the appended ones are reported:
+                let loaded = match bytes {
+                    Some(bytes) => Self::load_data(bytes, locale, file_name.clone()).await,
+                    None => Err(locale.file_load_error),
+                };
+                result = match (result, loaded) {
+                    (Some(Ok((mut db, name))), Ok((other, _))) => {
+                        db.append(other);
+                        Some(Ok((db, name)))
+                    }
+                    (Some(Ok(first)), Err(err)) => {
+                        Self::report_error(&errors, err, &file_name);
+                        Some(Ok(first))
+                    }
+                    (_, loaded) => Some(loaded),
+                };
+            }
+            if let Some(result) = result {
+                *resp.lock() = result;
+                status.store(true, std::sync::atomic::Ordering::Relaxed);
             }
         });
-        if file_read_err {
-            self.state = FileManagerState::Error(locale.file_load_error);
-        }
     }
 
     pub fn update(
@@ -108,7 +495,37 @@ impl FileManager {
         db: &mut GridDB,
         file_name: &mut String,
     ) {
+        if let FileManagerState::ExportPNGCapture {
+            rect,
+            size,
+            grid_size,
+            requested,
+        } = self.state
+        {
+            // The modal is not shown, so it is not captured
+            let image = ctx.input(|input| {
+                input.raw.events.iter().find_map(|event| match event {
+                    egui::Event::Screenshot { image, .. } => Some(image.clone()),
+                    _ => None,
+                })
+            });
+            if let Some(image) = image {
+                let image = image.region(&rect, Some(ctx.pixels_per_point()));
+                let cells = (rect.width() / grid_size, rect.height() / grid_size);
+                let background = ctx.theme().schematic().get_bg_color();
+                self.export_to_png(&image, size, cells, background, file_name, locale);
+                return;
+            } else if requested.elapsed() < SCREENSHOT_TIMEOUT {
+                ctx.request_repaint();
+                return;
+            }
+            // The backend may never deliver the screenshot
+            self.state = FileManagerState::Error(locale.png_capture_error);
+        }
         if self.state != FileManagerState::None {
+            let mut export_cancelled = false;
+            #[cfg(target_arch = "wasm32")]
+            let mut save_as = None;
             // Display state modal
             egui::modal::Modal::new("FileManager".into()).show(ctx, |ui| {
                 ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
@@ -116,7 +533,7 @@ impl FileManager {
                     FileManagerState::SaveFile => {
                         ui.label(locale.saving_file);
                     }
-                    FileManagerState::OpenFile => {
+                    FileManagerState::OpenFile | FileManagerState::ImportFile => {
                         ui.label(locale.opening_file);
                     }
                     FileManagerState::Error(err) => {
@@ -129,16 +546,54 @@ impl FileManager {
                     }
                     FileManagerState::ExportSVG => {
                         ui.label(locale.ongoing_export_to_svg);
+                        if let Some(export) = &self.svg_export {
+                            #[cfg(target_arch = "wasm32")]
+                            if export.worker.is_some() {
+                                ui.spinner();
+                            }
+                            if let Some(job) = &export.job {
+                                ui.add(egui::ProgressBar::new(job.progress()).show_percentage());
+                            }
+                            export_cancelled = ui.button(locale.cancel).clicked();
+                        }
                     }
-                    FileManagerState::ExportSVGDialog {
-                        export_theme: _,
-                        cell_size: _,
-                    } => {
+                    FileManagerState::ExportPNG => {
+                        ui.label(locale.ongoing_export_to_png);
+                        ui.spinner();
+                        export_cancelled = ui.button(locale.cancel).clicked();
+                    }
+                    #[cfg(target_arch = "wasm32")]
+                    FileManagerState::SaveAsDialog { file_name: new_name } => {
+                        ui.text_edit_singleline(new_name);
+                        ui.horizontal(|ui| {
+                            if ui.button("OK").clicked() {
+                                *file_name = new_name.clone();
+                                save_as = Some(true);
+                            }
+                            if ui.button(locale.cancel).clicked() {
+                                save_as = Some(false);
+                            }
+                        });
+                    }
+                    FileManagerState::ExportSVGDialog { .. } => {
                         self.export_file_dialog(ui, locale, db, file_name);
                     }
+                    FileManagerState::ExportPNGDialog { .. } => {
+                        self.export_png_dialog(ui, locale);
+                    }
                     _ => {}
                 }
             });
+            #[cfg(target_arch = "wasm32")]
+            match save_as {
+                Some(true) => self.save_file_as(db, file_name, locale),
+                Some(false) => self.state = FileManagerState::None,
+                None => {}
+            }
+            if export_cancelled {
+                self.cancel_export();
+            }
+            self.continue_svg_export(ctx, db, locale);
             match self.state {
                 FileManagerState::OpenFile => {
                     if self.done.load(std::sync::atomic::Ordering::Relaxed) {
@@ -156,6 +611,18 @@ impl FileManager {
                         }
                     }
                 }
+                FileManagerState::ImportFile => {
+                    if self.done.load(std::sync::atomic::Ordering::Relaxed) {
+                        match &mut *self.loaded_data.lock() {
+                            Ok((new_db, _)) => {
+                                self.imported = Some(std::mem::take(new_db));
+                                self.state = FileManagerState::None;
+                            }
+                            Err(err) => self.state = FileManagerState::Error(err),
+                        }
+                        self.done.store(false, std::sync::atomic::Ordering::Relaxed);
+                    }
+                }
                 _ => {
                     if self.done.load(std::sync::atomic::Ordering::Relaxed) {
                         self.state = FileManagerState::None;
@@ -169,9 +636,16 @@ impl FileManager {
     }
 
     #[cfg(target_arch = "wasm32")]
-    fn show_preview_wasm(db: &mut GridDB, grid_size: f32, theme: Theme) {
+    fn show_preview_wasm(
+        db: &GridDB,
+        grid_size: f32,
+        theme: Theme,
+        style: &NetStyle,
+        net_values: &HashMap<Id, Logic>,
+        grid: ExportGrid,
+    ) {
         {
-            let data = db.dump_to_svg(theme, grid_size);
+            let data = db.dump_to_annotated_svg(theme, grid_size, style, net_values.clone(), grid);
             use eframe::wasm_bindgen::JsCast;
             use eframe::wasm_bindgen::prelude::Closure;
             use web_sys::{Blob, BlobPropertyBag, Url};
@@ -221,102 +695,302 @@ impl FileManager {
         db: &mut GridDB,
         file_name: &String,
     ) {
-        let (export_theme, cell_size) = match &mut self.state {
-            FileManagerState::ExportSVGDialog {
-                export_theme,
-                cell_size,
-            } => (export_theme, cell_size),
-            _ => panic!(),
-        };
+        let (export_theme, both_themes, size, back_annotation, scope, excluded) =
+            match &mut self.state {
+                FileManagerState::ExportSVGDialog {
+                    export_theme,
+                    both_themes,
+                    size,
+                    back_annotation,
+                    scope,
+                    excluded,
+                } => (
+                    export_theme,
+                    both_themes,
+                    size,
+                    back_annotation,
+                    scope,
+                    excluded,
+                ),
+                _ => panic!(),
+            };
+        let annotation_changed = ui
+            .add_enabled(
+                self.simulation_values.is_some(),
+                egui::Checkbox::new(back_annotation, locale.simulation_values),
+            )
+            .changed();
+        if annotation_changed {
+            self.net_values = match &self.simulation_values {
+                Some(values) if *back_annotation => values.clone(),
+                _ => HashMap::new(),
+            };
+        }
 
-        #[cfg(not(target_arch = "wasm32"))]
-        {
-            let egui::Vec2 { x, y } = ui.ctx().available_rect().size();
-            ui.set_min_size(egui::vec2(x.min(y), x.min(y)) * 0.5);
-            ui.set_max_size(egui::vec2(x.min(y), x.min(y)) * 0.5);
-            let mut preview_valid = true;
-            ui.horizontal(|ui| {
-                ui.label(locale.theme);
-                let change0 = ui
-                    .radio_value(export_theme, Theme::Dark, locale.theme_dark)
-                    .changed();
-                let change1 = ui
-                    .radio_value(export_theme, Theme::Light, locale.theme_light)
-                    .changed();
-                if change0 || change1 {
-                    Self::reload_preview(ui.ctx(), db, *export_theme);
-                    preview_valid = false;
+        let egui::Vec2 { x, y } = ui.ctx().available_rect().size();
+        ui.set_min_size(egui::vec2(x.min(y), x.min(y)) * 0.5);
+        ui.set_max_size(egui::vec2(x.min(y), x.min(y)) * 0.5);
+        let mut options_changed = annotation_changed;
+        ui.horizontal(|ui| {
+            ui.label(locale.theme);
+            options_changed |= ui
+                .radio_value(export_theme, Theme::Dark, locale.theme_dark)
+                .changed();
+            options_changed |= ui
+                .radio_value(export_theme, Theme::Light, locale.theme_light)
+                .changed();
+            ui.checkbox(both_themes, locale.both_themes)
+                .on_hover_text(locale.both_themes_hint);
+        });
+        ui.horizontal(|ui| {
+            ui.label(locale.export_scope);
+            for value in ExportScope::ALL {
+                let enabled = match value {
+                    ExportScope::Design => true,
+                    ExportScope::Selection => !self.export_selection.is_empty(),
+                    ExportScope::Viewport => self.export_viewport.is_some(),
+                };
+                let radio = egui::RadioButton::new(*scope == value, value.get_name(locale));
+                if ui.add_enabled(enabled, radio).clicked() && *scope != value {
+                    *scope = value;
+                    options_changed = true;
                 }
-            });
-            ui.horizontal(|ui| {
-                ui.label(locale.cell_size);
-                ui.add(egui::TextEdit::singleline(cell_size).desired_width(30.0))
-            });
-            if preview_valid {
-                ui.add(egui::Image::new(egui::ImageSource::Uri(
-                    "bytes://preview.svg".into(),
-                )));
-            }
-            ui.add_space((ui.available_height() - 20.0).max(0.0));
-            let theme = export_theme.clone();
-            if ui.button("OK").clicked() {
-                match cell_size.parse::<f32>() {
-                    Ok(cell_size) => self.export_to_svg(db, file_name, theme, cell_size),
-                    Err(_) => self.state = FileManagerState::Error(locale.illegal_cell_size),
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(locale.include_layers);
+            for layer in ExportLayer::ALL {
+                let mut included = !excluded.contains(&layer);
+                if ui.checkbox(&mut included, layer.get_name(locale)).changed() {
+                    match included {
+                        true => excluded.retain(|l| *l != layer),
+                        false => excluded.push(layer),
+                    }
+                    options_changed = true;
                 }
             }
+        });
+        options_changed |= Self::export_grid_ui(ui, &mut self.export_grid, locale);
+        let cells = get_svg_drawing_cells(self.scoped_db.as_ref().unwrap_or(db));
+        let size_valid = Self::export_size_ui(ui, size, cells, false, locale);
+        if options_changed {
+            let ids = match scope {
+                ExportScope::Design => db.get_component_ids(),
+                ExportScope::Selection => self.export_selection.clone(),
+                ExportScope::Viewport => self
+                    .export_viewport
+                    .as_ref()
+                    .map(|rect| db.get_visible_component_ids(rect))
+                    .unwrap_or_default(),
+            };
+            // The whole design is exported as is
+            self.scoped_db = (*scope != ExportScope::Design || !excluded.is_empty())
+                .then(|| db.extract_fragment(&ExportLayer::filter(db, ids, excluded)));
+            Self::reload_preview(
+                ui.ctx(),
+                db,
+                self.scoped_db.as_ref(),
+                *export_theme,
+                &self.net_style,
+                &self.net_values,
+                self.export_grid,
+            );
+        } else {
+            // Not shown in the frame of the reload
+            ui.add(egui::Image::new(egui::ImageSource::Uri(
+                "bytes://preview.svg".into(),
+            )));
         }
-        #[cfg(target_arch = "wasm32")]
-        {
-            ui.horizontal(|ui| {
-                ui.label(locale.theme);
-                ui.radio_value(export_theme, Theme::Dark, locale.theme_dark)
-                    .changed();
-                ui.radio_value(export_theme, Theme::Light, locale.theme_light)
+        let is_empty = self
+            .scoped_db
+            .as_ref()
+            .is_some_and(|scoped| scoped.get_component_ids().is_empty());
+        if is_empty {
+            ui.colored_label(ui.visuals().error_fg_color, locale.nothing_to_export);
+        }
+        ui.add_space((ui.available_height() - 20.0).max(0.0));
+        let (theme, both_themes, size) = (*export_theme, *both_themes, *size);
+        ui.horizontal(|ui| {
+            let button = egui::Button::new("OK");
+            if ui.add_enabled(!is_empty && size_valid, button).clicked() {
+                self.export_to_svg(ui.ctx(), db, file_name, theme, both_themes, size);
+            }
+            // Full-size preview in a new tab
+            #[cfg(target_arch = "wasm32")]
+            if ui.button(locale.preview).clicked() {
+                let db = self.scoped_db.as_ref().unwrap_or(&*db);
+                let (style, values) = (&self.net_style, &self.net_values);
+                Self::show_preview_wasm(db, 100.0, theme, style, values, self.export_grid);
+            }
+        });
+    }
+
+    /// Options of the grid drawn under the exported image, returns true if they are changed
+    fn export_grid_ui(ui: &mut egui::Ui, grid: &mut ExportGrid, locale: &'static Locale) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label(locale.export_grid);
+            for grid_type in SUPPORTED_GRID_TYPES {
+                changed |= ui
+                    .radio_value(&mut grid.grid_type, *grid_type, grid_type.get_name(locale))
                     .changed();
-            });
-            let parse_result = cell_size.parse::<f32>();
+            }
+        });
+        ui.add_enabled_ui(grid.grid_type != GridType::None, |ui| {
+            let slider = egui::Slider::new(&mut grid.opacity, 0.0..=1.0).text(locale.grid_opacity);
+            changed |= ui.add(slider).changed();
+        });
+        changed
+    }
 
-            ui.horizontal(|ui| {
+    /// Options of the exported image size for the drawing of `cells` size,
+    /// returns false if the size is invalid
+    fn export_size_ui(
+        ui: &mut egui::Ui,
+        size: &mut ExportSize,
+        cells: (f32, f32),
+        with_dpi: bool,
+        locale: &'static Locale,
+    ) -> bool {
+        ui.horizontal(|ui| {
+            ui.label(locale.image_size);
+            for mode in ExportSizeMode::ALL {
+                if ui
+                    .radio_value(&mut size.mode, mode, mode.get_name(locale))
+                    .changed()
+                {
+                    size.fit_height(cells);
+                }
+            }
+        });
+        ui.horizontal(|ui| match size.mode {
+            ExportSizeMode::CellSize => {
                 ui.label(locale.cell_size);
-                ui.add(egui::TextEdit::singleline(cell_size).desired_width(30.0));
-                if parse_result.is_err() {
-                    ui.label("⚠");
+                ui.add(egui::DragValue::new(&mut size.cell_size).range(1.0..=1000.0));
+            }
+            ExportSizeMode::Pixels | ExportSizeMode::Millimeters => {
+                let max = ExportSize::MAX_PIXELS;
+                ui.label(locale.width);
+                if ui
+                    .add(egui::DragValue::new(&mut size.width).range(1.0..=max))
+                    .changed()
+                {
+                    size.fit_height(cells);
                 }
-            });
-            let theme = export_theme.clone();
-            ui.horizontal(|ui| {
-                if ui.button("OK").clicked() {
-                    match parse_result {
-                        Ok(cell_size) => self.export_to_svg(db, file_name, theme, cell_size),
-                        Err(_) => self.state = FileManagerState::Error(locale.illegal_cell_size),
-                    }
+                ui.label(locale.height);
+                if ui
+                    .add(egui::DragValue::new(&mut size.height).range(1.0..=max))
+                    .changed()
+                {
+                    size.fit_width(cells);
                 }
-                if ui.button(locale.preview).clicked() {
-                    Self::show_preview_wasm(db, 100.0, theme);
+                if ui
+                    .checkbox(&mut size.keep_aspect, locale.keep_aspect_ratio)
+                    .changed()
+                {
+                    size.fit_height(cells);
                 }
+            }
+        });
+        if with_dpi || size.mode == ExportSizeMode::Millimeters {
+            ui.horizontal(|ui| {
+                ui.label(locale.dpi);
+                ui.add(egui::DragValue::new(&mut size.dpi).range(10.0..=2400.0));
             });
         }
+        let is_valid = size.is_valid(cells);
+        if is_valid {
+            let (w, h) = size.get_pixel_size(cells);
+            ui.label(format!("{w} × {h} px"));
+        } else {
+            ui.colored_label(ui.visuals().error_fg_color, locale.image_too_big);
+        }
+        is_valid
     }
 
-    fn load_data(
+    async fn load_data(
         data: Vec<u8>,
         locale: &'static Locale,
         file_name: String,
     ) -> Result<(GridDB, String), &'static str> {
-        if let Ok(json) = String::from_utf8(data) {
-            if let Ok(new_db) = GridDB::load_from_json(json) {
-                let striped_name = file_name
-                    .strip_suffix(".json")
-                    .unwrap_or(&file_name)
-                    .to_string();
-                return Ok((new_db, striped_name));
-            } else {
-                Err(locale.file_wrong_format)
-            }
+        let (mut db, connections) = Self::load_db(data, locale)?;
+        // Routing is the slowest part of the netlist import, on web it is done in the worker
+        #[cfg(target_arch = "wasm32")]
+        Self::route_wasm(&mut db, connections).await;
+        #[cfg(not(target_arch = "wasm32"))]
+        db.route_connections(&connections);
+        let striped_name = split_project_suffix(&file_name).0.to_string();
+        Ok((db, striped_name))
+    }
+
+    /// Detects the file format by the content: project JSON, gzip-compressed project,
+    /// Yosys JSON netlist or SVG exported with the project data.
+    /// Returns the connections of the imported netlist, which are not routed yet.
+    fn load_db(
+        data: Vec<u8>,
+        locale: &'static Locale,
+    ) -> Result<(GridDB, Vec<YosysConnection>), &'static str> {
+        if data.starts_with(&GZIP_MAGIC) {
+            use std::io::Read as _;
+            let mut decoded = vec![];
+            flate2::read::GzDecoder::new(data.as_slice())
+                .read_to_end(&mut decoded)
+                .map_err(|_| locale.file_wrong_format)?;
+            return Self::load_db(decoded, locale);
+        }
+        let text = String::from_utf8(data).map_err(|_| locale.file_wrong_format)?;
+        if text.trim_start().starts_with('<') {
+            return match GridDB::load_from_svg(&text) {
+                Some(result) => result
+                    .map(|db| (db, vec![]))
+                    .map_err(|_| locale.file_wrong_format),
+                None => Err(locale.file_no_project_data),
+            };
+        }
+        let value: serde_json::Value =
+            serde_json::from_str(&text).map_err(|_| locale.file_wrong_format)?;
+        if is_yosys_json(&value) {
+            GridDB::place_yosys_json(&value).ok_or(locale.file_wrong_format)
+        } else {
+            GridDB::load_from_json(text)
+                .map(|db| (db, vec![]))
+                .map_err(|_| locale.file_wrong_format)
+        }
+    }
+
+    /// Routes the nets of the imported netlist in the worker, or in the page if it failed
+    #[cfg(target_arch = "wasm32")]
+    async fn route_wasm(db: &mut GridDB, connections: Vec<YosysConnection>) {
+        if connections.is_empty() {
+            return;
+        }
+        let routed = match WorkerRequest::route(db, connections.clone()) {
+            Some(request) => WorkerTask::run(&request).await,
+            None => None,
+        };
+        if let Some(WorkerResponse::Project(project)) = routed
+            && let Ok(routed) = GridDB::load_from_json(project)
+        {
+            *db = routed;
         } else {
-            Err(locale.file_wrong_format)
+            db.route_connections(&connections);
+        }
+    }
+
+    /// Project files named `*.gz` are compressed
+    #[cfg(not(target_arch = "wasm32"))]
+    fn encode_project(data: String, file_name: &str) -> Vec<u8> {
+        use std::io::Write as _;
+        if file_name.ends_with(".gz") {
+            let mut encoder =
+                flate2::write::GzEncoder::new(vec![], flate2::Compression::default());
+            if encoder.write_all(data.as_bytes()).is_ok()
+                && let Ok(bytes) = encoder.finish()
+            {
+                return bytes;
+            }
         }
+        data.into_bytes()
     }
 
     #[cfg(not(target_arch = "wasm32"))]
@@ -331,15 +1005,76 @@ impl FileManager {
 
     pub fn open_file(&mut self, locale: &'static Locale) {
         self.state = FileManagerState::OpenFile;
+        self.load_picked_file(locale, true);
+    }
+
+    /// Opens the recent project, on web the file is picked again
+    pub fn open_recent(&mut self, name: &str, locale: &'static Locale) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            self.state = FileManagerState::OpenFile;
+            let path = PathBuf::from(name);
+            let status = self.done.clone();
+            let resp = self.loaded_data.clone();
+            let file_path = self.file_path.clone();
+            let recent = self.recent.clone();
+            Self::execute(async move {
+                let file_name = path.file_name().unwrap_or_default();
+                let result = match std::fs::read(&path) {
+                    Ok(data) => {
+                        Self::load_data(data, locale, file_name.to_string_lossy().into()).await
+                    }
+                    Err(_) => Err(locale.file_load_error),
+                };
+                if result.is_ok() {
+                    Self::add_recent(&recent, path.to_string_lossy().into_owned());
+                    *file_path.lock() = Some(path);
+                }
+                *resp.lock() = result;
+                status.store(true, std::sync::atomic::Ordering::Relaxed);
+            });
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            _ = name;
+            self.open_file(locale);
+        }
+    }
+
+    /// Loads a project or a Yosys netlist to be placed into the opened project
+    pub fn import_file(&mut self, locale: &'static Locale) {
+        self.state = FileManagerState::ImportFile;
+        self.load_picked_file(locale, false);
+    }
+
+    pub fn take_imported(&mut self) -> Option<GridDB> {
+        self.imported.take()
+    }
+
+    /// `remember_path` makes "Save" write to the picked file
+    fn load_picked_file(&mut self, locale: &'static Locale, _remember_path: bool) {
         {
             let status = self.done.clone().clone();
             let resp = self.loaded_data.clone();
+            #[cfg(not(target_arch = "wasm32"))]
+            let file_path = self.file_path.clone();
+            let recent = self.recent.clone();
 
             Self::execute(async move {
                 if let Some(file) = rfd::AsyncFileDialog::new().pick_file().await {
+                    #[cfg(not(target_arch = "wasm32"))]
+                    if _remember_path {
+                        *file_path.lock() = Some(file.path().to_path_buf());
+                        Self::add_recent(&recent, file.path().to_string_lossy().into_owned());
+                    }
+                    #[cfg(target_arch = "wasm32")]
+                    if _remember_path {
+                        Self::add_recent(&recent, file.file_name());
+                    }
                     let data = file.read().await;
+                    let loaded = Self::load_data(data, locale, file.file_name()).await;
                     let mut receiver = resp.lock();
-                    *receiver = Self::load_data(data, locale, file.file_name());
+                    *receiver = loaded;
                 } else {
                     let mut receiver = resp.lock();
                     *receiver = Err(locale.file_load_error);
@@ -350,15 +1085,16 @@ impl FileManager {
     }
 
     #[cfg(target_arch = "wasm32")]
-    fn save_file_wasm(default_file_name: String, content: String) {
+    fn save_file_wasm(default_file_name: String, content: &[u8]) {
         #[cfg(target_arch = "wasm32")]
         {
             use eframe::wasm_bindgen::JsCast;
             use web_sys::{Blob, Url};
 
-            let blob =
-                Blob::new_with_str_sequence(&js_sys::Array::of1(&js_sys::JsString::from(content)))
-                    .unwrap();
+            let blob = Blob::new_with_u8_array_sequence(&js_sys::Array::of1(
+                &js_sys::Uint8Array::from(content),
+            ))
+            .unwrap();
 
             let url = Url::create_object_url_with_blob(&blob).unwrap();
 
@@ -382,41 +1118,200 @@ impl FileManager {
         }
     }
 
-    pub fn save_file(&mut self, db: &GridDB, file_name: &String) {
+    /// Saves the file to the previously used path, if there is one
+    pub fn save_file(&mut self, db: &GridDB, file_name: &String, locale: &'static Locale) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let path = self.file_path.lock().clone();
+            if let Some(path) = path {
+                if let Some(data) = db.dump_to_json() {
+                    self.state = FileManagerState::SaveFile;
+                    let arc = self.done.clone().clone();
+                    let errors = self.errors.clone();
+                    let thumbnail = self.thumbnails.then(|| encode_thumbnail(db)).flatten();
+                    let recent = self.recent.clone();
+                    let revision = self.thumbnail_revision.clone();
+                    let saved = self.saved.clone();
+                    Self::execute(async move {
+                        let name = path.to_string_lossy().into_owned();
+                        let data = Self::encode_project(data, &name);
+                        match std::fs::write(&path, data) {
+                            Ok(()) => {
+                                Self::project_saved(name, thumbnail, &recent, &revision, &saved)
+                            }
+                            Err(err) => Self::report_error(&errors, locale.file_save_error, err),
+                        }
+                        arc.store(true, std::sync::atomic::Ordering::Relaxed);
+                    });
+                } else {
+                    self.errors.lock().push(locale.file_save_error.into());
+                }
+                return;
+            }
+        }
+        self.save_file_as(db, file_name, locale);
+    }
+
+    /// Saves the file under a new name, always asking for it
+    pub fn save_file_as(&mut self, db: &GridDB, file_name: &String, locale: &'static Locale) {
         if let Some(data) = db.dump_to_json() {
             self.state = FileManagerState::SaveFile;
             let default_file_name = format!("{file_name}.json");
+            let thumbnail = self.thumbnails.then(|| encode_thumbnail(db)).flatten();
+            let recent = self.recent.clone();
+            let revision = self.thumbnail_revision.clone();
+            let saved = self.saved.clone();
             #[cfg(not(target_arch = "wasm32"))]
             {
                 let arc = self.done.clone().clone();
+                let file_path = self.file_path.clone();
+                let errors = self.errors.clone();
                 Self::execute(async move {
                     if let Some(file) = rfd::AsyncFileDialog::new()
                         .set_file_name(default_file_name)
                         .save_file()
                         .await
                     {
-                        file.write(data.as_bytes()).await.ok();
-                        //errors.lock().push(error_msg.into());
+                        let data = Self::encode_project(data, &file.file_name());
+                        match file.write(&data).await {
+                            Ok(()) => {
+                                *file_path.lock() = Some(file.path().to_path_buf());
+                                let name = file.path().to_string_lossy().into_owned();
+                                Self::project_saved(name, thumbnail, &recent, &revision, &saved);
+                            }
+                            Err(err) => Self::report_error(&errors, locale.file_save_error, err),
+                        }
                     }
                     arc.store(true, std::sync::atomic::Ordering::Relaxed);
                 });
             }
             #[cfg(target_arch = "wasm32")]
             {
-                Self::save_file_wasm(default_file_name, data);
+                Self::save_file_wasm(default_file_name.clone(), data.as_bytes());
+                Self::project_saved(default_file_name, thumbnail, &recent, &revision, &saved);
                 self.done.store(true, std::sync::atomic::Ordering::Relaxed);
             }
         } else {
-            // self.errors.lock().push(error_msg.into());
+            self.errors.lock().push(locale.file_save_error.into());
+        }
+    }
+
+    /// Saves the simulation state, `{file_name}.sim.json` is suggested
+    pub fn save_simulation(&mut self, data: String, file_name: &String, locale: &'static Locale) {
+        self.save_text(data, format!("{file_name}{SIMULATION_SUFFIX}"), locale);
+    }
+
+    /// Saves the documentation generated for the project units
+    pub fn save_datasheets(
+        &mut self,
+        data: String,
+        file_name: &String,
+        format: DatasheetFormat,
+        locale: &'static Locale,
+    ) {
+        self.save_text(
+            data,
+            format!("{file_name}.{}", format.get_extension()),
+            locale,
+        );
+    }
+
+    pub fn save_truth_table(&mut self, data: String, file_name: &String, locale: &'static Locale) {
+        self.save_text(data, format!("{file_name}.csv"), locale);
+    }
+
+    /// Saves the crash report to be attached to the bug report
+    pub fn save_bug_report(&mut self, data: String, locale: &'static Locale) {
+        self.save_text(data, "bug_report.json".to_owned(), locale);
+    }
+
+    fn save_text(&self, data: String, default_file_name: String, locale: &'static Locale) {
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let errors = self.errors.clone();
+            Self::execute(async move {
+                if let Some(file) = rfd::AsyncFileDialog::new()
+                    .set_file_name(default_file_name)
+                    .save_file()
+                    .await
+                    && let Err(err) = file.write(data.as_bytes()).await
+                {
+                    Self::report_error(&errors, locale.file_save_error, err);
+                }
+            });
+        }
+        #[cfg(target_arch = "wasm32")]
+        {
+            _ = locale;
+            Self::save_file_wasm(default_file_name, data.as_bytes());
+        }
+    }
+
+    /// Picks the simulation state file, it is returned by `take_simulation`
+    pub fn open_simulation(&mut self, locale: &'static Locale) {
+        let loaded = self.loaded_simulation.clone();
+        let errors = self.errors.clone();
+        Self::execute(async move {
+            if let Some(file) = rfd::AsyncFileDialog::new()
+                .add_filter("JSON", &["json"])
+                .pick_file()
+                .await
+            {
+                match String::from_utf8(file.read().await) {
+                    Ok(data) => *loaded.lock() = Some(data),
+                    Err(err) => Self::report_error(&errors, locale.file_load_error, err),
+                }
+            }
+        });
+    }
+
+    /// Asks for a new file name, then saves the file
+    pub fn start_save_file_as(&mut self, db: &GridDB, file_name: &String, locale: &'static Locale) {
+        #[cfg(not(target_arch = "wasm32"))]
+        self.save_file_as(db, file_name, locale);
+        #[cfg(target_arch = "wasm32")]
+        {
+            _ = (db, locale);
+            self.state = FileManagerState::SaveAsDialog {
+                file_name: file_name.clone(),
+            };
         }
     }
 
     #[cfg(not(target_arch = "wasm32"))]
-    fn reload_preview(ctx: &egui::Context, db: &GridDB, theme: Theme) {
+    /// Preview of the whole design, the part chosen for the export is highlighted
+    fn reload_preview(
+        ctx: &egui::Context,
+        db: &GridDB,
+        scoped_db: Option<&GridDB>,
+        theme: Theme,
+        style: &NetStyle,
+        net_values: &HashMap<Id, Logic>,
+        grid: ExportGrid,
+    ) {
+        const PREVIEW_CELL_SIZE: f32 = 100.0;
         ctx.loaders().bytes.lock().iter().for_each(|loader| {
             loader.forget("bytes://preview.svg");
         });
-        let svg = db.dump_to_svg(theme, 100.0);
+        let mut svg =
+            db.dump_to_annotated_svg(theme, PREVIEW_CELL_SIZE, style, net_values.clone(), grid);
+        if let Some(scoped_db) = scoped_db
+            && !scoped_db.get_component_ids().is_empty()
+            && let Some(end) = svg.rfind("</svg>")
+        {
+            // The same offset as in the export of the whole design:
+            let (min, _) = db.get_bounds();
+            let (scoped_min, scoped_max) = scoped_db.get_bounds();
+            let x = (scoped_min.x - min.x + 1) as f32 * PREVIEW_CELL_SIZE;
+            let y = (scoped_min.y - min.y + 1) as f32 * PREVIEW_CELL_SIZE;
+            let w = (scoped_max.x - scoped_min.x + 1) as f32 * PREVIEW_CELL_SIZE;
+            let h = (scoped_max.y - scoped_min.y + 1) as f32 * PREVIEW_CELL_SIZE;
+            let highlight = format!(
+                r##"<rect x="{x}" y="{y}" width="{w}" height="{h}" fill="#3080ff" fill-opacity="0.2" stroke="#3080ff" stroke-width="{}"/>"##,
+                0.1 * PREVIEW_CELL_SIZE
+            );
+            svg.insert_str(end, &highlight);
+        }
         let bytes = svg.as_bytes();
         _ = egui::ImageSource::Bytes {
             uri: format!("bytes://preview.svg").into(),
@@ -429,39 +1324,422 @@ impl FileManager {
         );
     }
 
-    #[allow(unused_variables)]
-    pub fn start_export_svg(&mut self, ctx: &egui::Context, db: &GridDB, default_theme: Theme) {
-        #[cfg(not(target_arch = "wasm32"))]
-        Self::reload_preview(ctx, db, default_theme);
+    /// Grid to be drawn by the field while the PNG export is captured
+    pub fn get_capture_grid(&self) -> Option<ExportGrid> {
+        match self.state {
+            FileManagerState::ExportPNGCapture { .. } => Some(self.export_grid),
+            _ => None,
+        }
+    }
+
+    /// Selection and viewport offered in the export dialog instead of the whole design
+    pub fn set_export_scopes(&mut self, selection: Vec<Id>, viewport: GridRect) {
+        self.export_selection = selection;
+        self.export_viewport = Some(viewport);
+    }
+
+    pub fn start_export_svg(
+        &mut self,
+        ctx: &egui::Context,
+        db: &GridDB,
+        default_theme: Theme,
+        net_style: NetStyle,
+        simulation_values: Option<HashMap<Id, Logic>>,
+    ) {
+        self.net_style = net_style;
+        self.simulation_values = simulation_values;
+        self.net_values = HashMap::new();
+        self.export_grid = ExportGrid::NONE;
+        self.scoped_db = None;
+        Self::reload_preview(
+            ctx,
+            db,
+            None,
+            default_theme,
+            &self.net_style,
+            &self.net_values,
+            self.export_grid,
+        );
 
         self.state = FileManagerState::ExportSVGDialog {
             export_theme: default_theme,
-            cell_size: "40".into(),
+            both_themes: false,
+            size: ExportSize::default(),
+            back_annotation: false,
+            scope: ExportScope::Design,
+            excluded: vec![],
         };
     }
 
-    fn export_to_svg(&mut self, db: &GridDB, file_name: &String, theme: Theme, grid_size: f32) {
+    fn export_to_svg(
+        &mut self,
+        ctx: &egui::Context,
+        db: &GridDB,
+        file_name: &String,
+        theme: Theme,
+        both_themes: bool,
+        size: ExportSize,
+    ) {
         self.state = FileManagerState::ExportSVG;
-        let default_file_name = format!("{file_name}.svg");
-        let data = db.dump_to_svg(theme, grid_size);
+        let scoped_db = self.scoped_db.take();
+        let grid_size = size.get_cell_size(get_svg_drawing_cells(scoped_db.as_ref().unwrap_or(db)));
+        // The light variant is exported first
+        let (theme, pending_themes) = match both_themes {
+            true => (Theme::Light, vec![Theme::Dark]),
+            false => (theme, vec![]),
+        };
+        let export = SvgExport {
+            theme,
+            pending_themes,
+            themed_names: both_themes,
+            cell_size: grid_size,
+            document_size: size.get_document_size(),
+            scoped_db,
+            job: None,
+            #[cfg(target_arch = "wasm32")]
+            file_name: file_name.clone(),
+            #[cfg(not(target_arch = "wasm32"))]
+            path: Arc::new(Mutex::new(None)),
+            #[cfg(target_arch = "wasm32")]
+            worker: None,
+        };
+        // On native the job starts when the file is picked
         #[cfg(not(target_arch = "wasm32"))]
         {
-            let arc = self.done.clone().clone();
+            _ = (ctx, db);
+            let path = export.path.clone();
+            let default_file_name = format!("{file_name}.svg");
             Self::execute(async move {
-                if let Some(file) = rfd::AsyncFileDialog::new()
+                let file = rfd::AsyncFileDialog::new()
                     .set_file_name(default_file_name)
                     .save_file()
-                    .await
-                {
-                    file.write(data.as_bytes()).await.ok();
-                }
-                arc.store(true, std::sync::atomic::Ordering::Relaxed);
+                    .await;
+                *path.lock() = Some(file.map(|file| file.path().to_path_buf()));
             });
         }
+        self.svg_export = Some(export);
         #[cfg(target_arch = "wasm32")]
+        if let Some(export) = &mut self.svg_export {
+            export.start_wasm(ctx, db, self.net_style, &self.net_values, self.export_grid);
+        }
+    }
+
+    /// Starts the export of the next theme, or finishes the export
+    fn next_svg_export(&mut self, ctx: &egui::Context, design: &GridDB, locale: &'static Locale) {
+        let Some(export) = &mut self.svg_export else {
+            return;
+        };
+        let Some(theme) = export.pending_themes.pop() else {
+            return self.finish_svg_export(None, locale);
+        };
+        export.theme = theme;
+        #[cfg(target_arch = "wasm32")]
+        export.start_wasm(
+            ctx,
+            design,
+            self.net_style,
+            &self.net_values,
+            self.export_grid,
+        );
+        #[cfg(not(target_arch = "wasm32"))]
+        let _ = design;
+        ctx.request_repaint();
+    }
+
+    fn finish_svg_export(&mut self, error: Option<std::io::Error>, locale: &'static Locale) {
+        if let Some(err) = error {
+            Self::report_error(&self.errors, locale.file_save_error, err);
+        }
+        self.svg_export = None;
+        self.state = FileManagerState::None;
+    }
+
+    /// Exports the next part of SVG, the time of a frame is limited
+    fn continue_svg_export(
+        &mut self,
+        ctx: &egui::Context,
+        design: &GridDB,
+        locale: &'static Locale,
+    ) {
+        const FRAME_BUDGET: web_time::Duration = web_time::Duration::from_millis(10);
+        const ITEMS_PER_STEP: usize = 64;
+        let Some(export) = &mut self.svg_export else {
+            return;
+        };
+        let db = export.scoped_db.as_ref().unwrap_or(design);
+        #[cfg(not(target_arch = "wasm32"))]
+        if export.job.is_none() {
+            let picked = export.path.lock().clone();
+            match picked {
+                None => {
+                    // Waiting for the file dialog
+                    ctx.request_repaint_after(web_time::Duration::from_millis(100));
+                    return;
+                }
+                Some(None) => return self.finish_svg_export(None, locale),
+                Some(Some(path)) => {
+                    let job = std::fs::File::create(export.get_path(path)).and_then(|file| {
+                        SvgExportJob::new_with_size(
+                            db,
+                            export.theme,
+                            export.cell_size,
+                            self.net_style,
+                            export.document_size,
+                            std::io::BufWriter::new(file),
+                        )
+                    });
+                    let job = job
+                        .and_then(|job| job.with_grid(self.export_grid))
+                        .map(|job| job.with_net_values(self.net_values.clone()));
+                    match job {
+                        Ok(job) => export.job = Some(job),
+                        Err(err) => return self.finish_svg_export(Some(err), locale),
+                    }
+                }
+            }
+        }
+        #[cfg(target_arch = "wasm32")]
+        if let Some(worker) = &export.worker {
+            if worker.is_failed() {
+                // Continued in steps in the page:
+                export.worker = None;
+                export.job =
+                    SvgExportJob::new(db, export.theme, export.cell_size, self.net_style, vec![])
+                        .ok();
+                ctx.request_repaint();
+                return;
+            }
+            match worker.take_response() {
+                Some(WorkerResponse::Svg(svg)) => {
+                    Self::save_file_wasm(export.get_file_name(&export.file_name), svg.as_bytes());
+                    self.next_svg_export(ctx, design, locale);
+                }
+                Some(WorkerResponse::Error(err)) => {
+                    Self::report_error(&self.errors, locale.file_save_error, err);
+                    self.finish_svg_export(None, locale);
+                }
+                None => {} // Repaint is requested by the worker
+            }
+            return;
+        }
+        let Some(job) = &mut export.job else {
+            return self.finish_svg_export(None, locale);
+        };
+        let start = web_time::Instant::now();
+        let mut done = false;
+        while !done && start.elapsed() < FRAME_BUDGET {
+            match job.step(db, ITEMS_PER_STEP) {
+                Ok(finished) => done = finished,
+                Err(err) => return self.finish_svg_export(Some(err), locale),
+            }
+        }
+        if done && let Some(job) = export.job.take() {
+            match job.finish(Some(db)) {
+                Ok(_writer) => {
+                    #[cfg(target_arch = "wasm32")]
+                    Self::save_file_wasm(export.get_file_name(&export.file_name), &_writer);
+                    self.next_svg_export(ctx, design, locale);
+                }
+                Err(err) => self.finish_svg_export(Some(err), locale),
+            }
+            return;
+        }
+        ctx.request_repaint();
+    }
+
+    fn cancel_export(&mut self) {
+        if let Some(_export) = self.svg_export.take() {
+            // Remove the partially written file
+            #[cfg(not(target_arch = "wasm32"))]
+            if _export.job.is_some()
+                && let Some(Some(path)) = _export.path.lock().clone()
+            {
+                let path = _export.get_path(path);
+                drop(_export.job);
+                std::fs::remove_file(path).ok();
+            }
+            self.state = FileManagerState::None;
+        } else {
+            self.export_cancelled
+                .store(true, std::sync::atomic::Ordering::Relaxed);
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn write_export(
+        data: Vec<u8>,
+        default_file_name: String,
+        errors: &Mutex<Vec<String>>,
+        locale: &'static Locale,
+    ) {
+        if let Some(file) = rfd::AsyncFileDialog::new()
+            .set_file_name(default_file_name)
+            .save_file()
+            .await
+            && let Err(err) = file.write(&data).await
         {
-            Self::save_file_wasm(default_file_name, data);
-            self.done.store(true, std::sync::atomic::Ordering::Relaxed);
+            Self::report_error(errors, locale.file_save_error, err);
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    async fn write_export(
+        data: Vec<u8>,
+        default_file_name: String,
+        _errors: &Mutex<Vec<String>>,
+        _locale: &'static Locale,
+    ) {
+        Self::save_file_wasm(default_file_name, &data);
+    }
+
+    /// Opens the size options of the PNG export of the field area,
+    /// `grid_size` is the size of the visible cells in points, the grid is shown as on the screen
+    pub fn start_export_png(
+        &mut self,
+        ctx: &egui::Context,
+        rect: Rect,
+        grid_size: f32,
+        grid_type: GridType,
+    ) {
+        let size = ExportSize {
+            // The screen resolution by default
+            cell_size: grid_size * ctx.pixels_per_point(),
+            ..Default::default()
+        };
+        self.export_grid = ExportGrid {
+            grid_type,
+            opacity: ExportGrid::DEFAULT_OPACITY,
+        };
+        self.state = FileManagerState::ExportPNGDialog {
+            rect,
+            size,
+            grid_size,
+        };
+    }
+
+    fn export_png_dialog(&mut self, ui: &mut egui::Ui, locale: &'static Locale) {
+        let FileManagerState::ExportPNGDialog {
+            rect,
+            size,
+            grid_size,
+        } = &mut self.state
+        else {
+            return;
+        };
+        let cells = (rect.width() / *grid_size, rect.height() / *grid_size);
+        Self::export_grid_ui(ui, &mut self.export_grid, locale);
+        let is_valid = Self::export_size_ui(ui, size, cells, true, locale);
+        let (rect, size, grid_size) = (*rect, *size, *grid_size);
+        ui.horizontal(|ui| {
+            if ui.add_enabled(is_valid, egui::Button::new("OK")).clicked() {
+                // Captures the field area from the next frame
+                self.state = FileManagerState::ExportPNGCapture {
+                    rect,
+                    size,
+                    grid_size,
+                    requested: Instant::now(),
+                };
+                ui.ctx()
+                    .send_viewport_cmd(egui::ViewportCommand::Screenshot(Default::default()));
+            }
+            if ui.button(locale.cancel).clicked() {
+                self.state = FileManagerState::None;
+            }
+        });
+    }
+
+    /// Scales the captured image to the requested size and exports it to PNG
+    fn export_to_png(
+        &mut self,
+        image: &ColorImage,
+        export_size: ExportSize,
+        cells: (f32, f32),
+        background: egui::Color32,
+        file_name: &String,
+        locale: &'static Locale,
+    ) {
+        self.state = FileManagerState::ExportPNG;
+        self.export_cancelled
+            .store(false, std::sync::atomic::Ordering::Relaxed);
+        let default_file_name = format!("{file_name}.png");
+        let size = [image.width() as u32, image.height() as u32];
+        let pixels = image.as_raw().to_vec();
+        let target = export_size.get_pixel_size(cells);
+        let background = image::Rgba(background.to_array());
+        let arc = self.done.clone();
+        let errors = self.errors.clone();
+        let cancelled = self.export_cancelled.clone();
+        Self::execute(async move {
+            let mut data = vec![];
+            let buffer = image::RgbaImage::from_raw(size[0], size[1], pixels).map(|buffer| {
+                match buffer.dimensions() == target {
+                    true => buffer,
+                    false => fit_image(&buffer, target, background),
+                }
+            });
+            let encoded = match buffer {
+                Some(buffer) => image::DynamicImage::ImageRgba8(buffer)
+                    .write_to(
+                        &mut std::io::Cursor::new(&mut data),
+                        image::ImageFormat::Png,
+                    )
+                    .map_err(|err| Self::report_error(&errors, locale.file_save_error, err))
+                    .is_ok(),
+                None => false,
+            };
+            if encoded && !cancelled.load(std::sync::atomic::Ordering::Relaxed) {
+                let data = set_png_dpi(&data, export_size.dpi).unwrap_or(data);
+                Self::write_export(data, default_file_name, &errors, locale).await;
+            }
+            arc.store(true, std::sync::atomic::Ordering::Relaxed);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, sync::Arc};
+
+    use crate::{grid_db::GridDB, locale::EN_LOCALE};
+
+    use super::{FileManager, FileManagerState};
+
+    /// Drops the file on the field and waits for the opening to finish
+    fn drop_file(file: egui::DroppedFile) -> FileManager {
+        let ctx = egui::Context::default();
+        let mut manager = FileManager::new();
+        let mut db = GridDB::new();
+        let mut file_name = String::new();
+        let mut input = egui::RawInput::default();
+        input.dropped_files.push(file);
+        for _ in 0..500 {
+            let _ = ctx.run(std::mem::take(&mut input), |ctx| {
+                manager.update(ctx, &EN_LOCALE, &mut db, &mut file_name)
+            });
+            if manager.state != FileManagerState::OpenFile {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
         }
+        manager
+    }
+
+    #[test]
+    fn dropping_broken_files() {
+        let malformed = drop_file(egui::DroppedFile {
+            name: "broken.json".into(),
+            bytes: Some(Arc::from(b"{\"components\": [".as_slice())),
+            ..Default::default()
+        });
+        assert!(matches!(malformed.state, FileManagerState::Error(_)));
+        let unreadable = drop_file(egui::DroppedFile {
+            name: "missing.json".into(),
+            path: Some(PathBuf::from("/nonexistent/missing.json")),
+            ..Default::default()
+        });
+        assert_eq!(
+            unreadable.state,
+            FileManagerState::Error(EN_LOCALE.file_load_error)
+        );
     }
 }
@@ -1,13 +0,0 @@
-mod components;
-mod graphics;
-mod grid_db;
-mod primitives;
-mod text_field;
-mod net;
-
-pub use components::*;
-pub use graphics::*;
-pub use grid_db::*;
-pub use primitives::*;
-pub use text_field::*;
-pub use net::*;
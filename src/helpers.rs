@@ -4,11 +4,25 @@ use egui::{ImageSource, SizeHint, TextureOptions, load::Bytes};
 use egui_commonmark::{CommonMarkCache, CommonMarkViewer};
 use include_dir::{Dir, include_dir};
 
-use crate::locale::LocaleType;
+use crate::{
+    grid_db::{Component, GridDB, Id, Interface, InterfacePort, NetClass, Severity},
+    locale::{Locale, LocaleType},
+    settings::GetName,
+};
 
 pub struct Helpers {
     cache: CommonMarkCache,
     pub about_showed: bool,
+    pub variables_showed: bool,
+    pub net_classes_showed: bool,
+    pub interfaces_showed: bool,
+    /// Interface edited in the interfaces window
+    interface_index: usize,
+    /// Instance name and side of the interface added to units
+    bundle_name: String,
+    bundle_mirrored: bool,
+    /// Project name editor used instead of the menu bar field on narrow screens
+    pub project_name_showed: bool,
 }
 
 static ASSETS_DIR: Dir = include_dir!("$CARGO_MANIFEST_DIR/assets/common");
@@ -28,6 +42,13 @@ impl Helpers {
         Self {
             cache: CommonMarkCache::default(),
             about_showed: false,
+            variables_showed: false,
+            net_classes_showed: false,
+            interfaces_showed: false,
+            interface_index: 0,
+            bundle_name: String::new(),
+            bundle_mirrored: false,
+            project_name_showed: false,
         }
     }
 
@@ -48,4 +69,292 @@ impl Helpers {
                 });
             });
     }
+
+    pub fn show_project_name_window(
+        &mut self,
+        ctx: &egui::Context,
+        project_name: &mut String,
+        locale: &'static Locale,
+    ) {
+        let mut done = false;
+        egui::Window::new(locale.project_name)
+            .id("project_name".into())
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 40.0))
+            .open(&mut self.project_name_showed)
+            .show(ctx, |ui| {
+                let resp = ui.add(
+                    egui::TextEdit::singleline(project_name).hint_text(locale.project_name),
+                );
+                let entered = resp.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                done = ui.button(locale.ok).clicked() || entered;
+            });
+        if done {
+            self.project_name_showed = false;
+        }
+    }
+
+    /// Editor of the project variables used in `{{name}}` placeholders of text fields
+    pub fn show_variables_window(
+        &mut self,
+        ctx: &egui::Context,
+        db: &mut GridDB,
+        locale: &'static Locale,
+    ) {
+        egui::Window::new(locale.project_variables)
+            .id("project_variables".into())
+            .collapsible(false)
+            .open(&mut self.variables_showed)
+            .show(ctx, |ui| {
+                ui.label(locale.project_variables_hint);
+                let mut variables: Vec<(String, String)> = db
+                    .get_variables()
+                    .iter()
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect();
+                let mut changed = false;
+                let mut removed = None;
+                egui::Grid::new("project_variables_grid")
+                    .num_columns(3)
+                    .show(ui, |ui| {
+                        for (i, (name, value)) in variables.iter_mut().enumerate() {
+                            for text in [name, value] {
+                                changed |= ui
+                                    .add(egui::TextEdit::singleline(text).desired_width(120.0))
+                                    .changed();
+                            }
+                            if ui.button(egui::RichText::new("-").monospace()).clicked() {
+                                removed = Some(i);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if let Some(i) = removed {
+                    variables.remove(i);
+                    changed = true;
+                }
+                if ui.button(egui::RichText::new("+").monospace()).clicked() {
+                    let name = (0..)
+                        .map(|i| format!("var{i}"))
+                        .find(|name| !db.get_variables().contains_key(name))
+                        .unwrap();
+                    variables.push((name, String::new()));
+                    changed = true;
+                }
+                if changed {
+                    *db.get_variables_mut() = variables.into_iter().collect();
+                }
+            });
+    }
+
+    /// Editor of the project net classes
+    pub fn show_net_classes_window(
+        &mut self,
+        ctx: &egui::Context,
+        db: &mut GridDB,
+        locale: &'static Locale,
+    ) {
+        egui::Window::new(locale.net_classes)
+            .id("net_classes".into())
+            .collapsible(false)
+            .open(&mut self.net_classes_showed)
+            .show(ctx, |ui| {
+                let mut classes = db.get_net_classes().to_vec();
+                let mut removed = None;
+                egui::Grid::new("net_classes_grid")
+                    .num_columns(5)
+                    .show(ui, |ui| {
+                        ui.label(locale.name);
+                        ui.label(locale.color);
+                        ui.label(locale.line_width);
+                        ui.label(locale.severity);
+                        ui.end_row();
+                        for (i, class) in classes.iter_mut().enumerate() {
+                            ui.add(egui::TextEdit::singleline(&mut class.name).desired_width(80.0));
+                            ui.horizontal(|ui| {
+                                let mut custom = class.color.is_some();
+                                if ui.checkbox(&mut custom, "").changed() {
+                                    class.color = custom.then_some([0x80, 0x80, 0x80]);
+                                }
+                                if let Some(color) = &mut class.color {
+                                    ui.color_edit_button_srgb(color);
+                                }
+                            });
+                            ui.add(
+                                egui::DragValue::new(&mut class.width)
+                                    .range(NetClass::MIN_WIDTH..=NetClass::MAX_WIDTH)
+                                    .speed(0.05),
+                            );
+                            egui::ComboBox::from_id_salt(("net_class_severity", i))
+                                .selected_text(class.severity.get_name(locale))
+                                .show_ui(ui, |ui| {
+                                    for severity in Severity::ALL {
+                                        ui.selectable_value(
+                                            &mut class.severity,
+                                            severity,
+                                            severity.get_name(locale),
+                                        );
+                                    }
+                                });
+                            if ui.button(egui::RichText::new("-").monospace()).clicked() {
+                                removed = Some(i);
+                            }
+                            ui.end_row();
+                        }
+                    });
+                if let Some(i) = removed {
+                    classes.remove(i);
+                }
+                if ui.button(egui::RichText::new("+").monospace()).clicked() {
+                    let name = (0..)
+                        .map(|i| format!("class{i}"))
+                        .find(|name| !classes.iter().any(|c| c.name == *name))
+                        .unwrap();
+                    classes.push(NetClass {
+                        name,
+                        color: None,
+                        width: 1.0,
+                        severity: Severity::Warning,
+                    });
+                }
+                if classes != db.get_net_classes() {
+                    // Nets follow the renamed classes:
+                    let old_classes = db.get_net_classes().to_vec();
+                    if old_classes.len() == classes.len() {
+                        for (old, new) in old_classes.iter().zip(&classes) {
+                            if old.name != new.name {
+                                for net in db.nets.values_mut() {
+                                    if *net.class == *old.name {
+                                        net.class = new.name.as_str().into();
+                                    }
+                                }
+                            }
+                        }
+                    }
+                    *db.get_net_classes_mut() = classes;
+                }
+            });
+    }
+
+    /// Editor of the project interfaces, returns the selected units with the interface added
+    pub fn show_interfaces_window(
+        &mut self,
+        ctx: &egui::Context,
+        db: &mut GridDB,
+        selected: &[Id],
+        locale: &'static Locale,
+    ) -> Option<Vec<(Id, Component)>> {
+        let mut result = None;
+        egui::Window::new(locale.interfaces)
+            .id("interfaces".into())
+            .collapsible(false)
+            .open(&mut self.interfaces_showed)
+            .show(ctx, |ui| {
+                let mut interfaces = db.get_interfaces().to_vec();
+                ui.horizontal(|ui| {
+                    let selected_text = interfaces
+                        .get(self.interface_index)
+                        .map(|i| i.name.as_str())
+                        .unwrap_or_default();
+                    egui::ComboBox::from_id_salt("interface")
+                        .selected_text(selected_text)
+                        .show_ui(ui, |ui| {
+                            for (i, interface) in interfaces.iter().enumerate() {
+                                ui.selectable_value(&mut self.interface_index, i, &interface.name);
+                            }
+                        });
+                    if ui.button(egui::RichText::new("+").monospace()).clicked() {
+                        let name = (0..)
+                            .map(|i| format!("if{i}"))
+                            .find(|name| !interfaces.iter().any(|i| i.name == *name))
+                            .unwrap();
+                        interfaces.push(Interface {
+                            name,
+                            ports: vec![],
+                        });
+                        self.interface_index = interfaces.len() - 1;
+                    }
+                    if self.interface_index < interfaces.len()
+                        && ui.button(egui::RichText::new("-").monospace()).clicked()
+                    {
+                        interfaces.remove(self.interface_index);
+                    }
+                });
+                if let Some(interface) = interfaces.get_mut(self.interface_index) {
+                    ui.horizontal(|ui| {
+                        ui.label(locale.name);
+                        ui.text_edit_singleline(&mut interface.name);
+                    });
+                    ui.label(locale.interface_ports);
+                    let mut removed = None;
+                    egui::Grid::new("interface_ports_grid")
+                        .num_columns(4)
+                        .show(ui, |ui| {
+                            ui.label(locale.name);
+                            ui.label(locale.is_input);
+                            ui.label(locale.width);
+                            ui.end_row();
+                            for (i, port) in interface.ports.iter_mut().enumerate() {
+                                ui.add(
+                                    egui::TextEdit::singleline(&mut port.name).desired_width(80.0),
+                                );
+                                ui.checkbox(&mut port.is_input, "");
+                                ui.add(
+                                    egui::DragValue::new(&mut port.width)
+                                        .range(1..=Interface::MAX_WIDTH),
+                                );
+                                if ui.button(egui::RichText::new("-").monospace()).clicked() {
+                                    removed = Some(i);
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    if let Some(i) = removed {
+                        interface.ports.remove(i);
+                    }
+                    if ui.button(egui::RichText::new("+").monospace()).clicked() {
+                        let name = (0..)
+                            .map(|i| format!("port{i}"))
+                            .find(|name| !interface.ports.iter().any(|p| p.name == *name))
+                            .unwrap();
+                        interface.ports.push(InterfacePort {
+                            name,
+                            is_input: true,
+                            width: 1,
+                        });
+                    }
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        ui.label(locale.instance_name);
+                        ui.text_edit_singleline(&mut self.bundle_name);
+                    });
+                    ui.checkbox(&mut self.bundle_mirrored, locale.mirrored);
+                    let units: Vec<(Id, Component)> = selected
+                        .iter()
+                        .filter_map(|id| match db.get_component(id) {
+                            Some(Component::Unit(unit)) => {
+                                let mut unit = unit.clone();
+                                let bundle = self.bundle_name.trim();
+                                unit.add_interface(interface, bundle, self.bundle_mirrored);
+                                Some((*id, Component::Unit(unit)))
+                            }
+                            _ => None,
+                        })
+                        .collect();
+                    let enabled = !units.is_empty()
+                        && !interface.ports.is_empty()
+                        && !self.bundle_name.trim().is_empty();
+                    let button = egui::Button::new(locale.add_to_selected_units);
+                    if ui.add_enabled(enabled, button).clicked() {
+                        result = Some(units);
+                    }
+                }
+                if interfaces != db.get_interfaces() {
+                    *db.get_interfaces_mut() = interfaces;
+                }
+            });
+        result
+    }
+
 }
@@ -1,15 +1,32 @@
-use std::{collections::LinkedList};
+use std::{
+    collections::{BTreeSet, HashMap, HashSet, LinkedList},
+    mem::discriminant,
+    sync::Arc,
+};
+
+use editor_core::{
+    macros::{Macro, MacroEdit, MacroRecorder},
+    netlist::{NetlistCache, extract_netlist, get_next_connector},
+};
 
 use crate::{
+    action_icons::ActionIcon,
+    canvas::{PainterCanvas, SchematicTheme},
+    component_lib::{get_component_lib_with_query, get_library_update, get_updated_component},
+    customization::CustomizationPanel,
     field::{blocked_cell, filled_cells, FieldState},
     grid_db::{
-        grid_pos, show_text_edit, Component, ComponentAction, ComponentColor, GridDB, GridDBConnectionPoint, GridPos, Id, Net, NetAction, Port, PrimitiveComponent, RotationDirection
+        AllocatedFragment, Component, ComponentAction, ComponentColor, GridDB,
+        GridDBConnectionPoint, GridPos, GridRect, Id, KeepOutRegion, Net, NetAction, NetSegment,
+        Port, PrimitiveComponent, PrimitiveType, RotationDirection, SharedStr, TextField, Unit,
+        draw_dashed_rect, grid_pos, grid_rect, simplify_path,
     },
     locale::Locale,
 };
 use egui::{
-    epaint::TextShape, vec2, Align2, Color32, CursorIcon, FontId, KeyboardShortcut, Modifiers, Painter, Pos2, Rect, Response, Shape, Stroke, StrokeKind, Ui, Vec2
+    epaint::TextShape, vec2, Align2, Color32, CursorIcon, FontId, KeyboardShortcut, Modifiers, Painter, Pos2, Rect, Response, Shape, Stroke, StrokeKind, TextEdit, Ui, UiBuilder, Vec2
 };
+use web_time::{Duration, Instant};
 
 pub fn draw_component_drag_preview(
     db: &GridDB,
@@ -41,17 +58,89 @@ pub fn draw_component_drag_preview(
     painter.extend(result);
 }
 
+fn show_text_edit(
+    text_edit_rect: Rect,
+    single_line: bool,
+    edit_buffer: &mut String,
+    state: &FieldState,
+    ui: &mut Ui,
+    painter: &Painter,
+) {
+    if !single_line {
+        painter.rect_filled(
+            text_edit_rect,
+            state.grid_size * 0.1,
+            ui.ctx().theme().schematic().get_stroke_color().gamma_multiply_u8(127),
+        );
+    }
+
+    let style = if state.debounce {
+        let mut style = (*ui.ctx().style()).clone();
+        style.visuals.selection.bg_fill = Color32::TRANSPARENT;
+        style.visuals.selection.stroke.color = Color32::TRANSPARENT;
+        style.visuals.text_cursor.blink = false;
+        style.visuals.text_cursor.stroke.color = Color32::TRANSPARENT;
+        Arc::new(style)
+    } else {
+        ui.ctx().style().clone()
+    };
+    let ui_builder = UiBuilder::new().max_rect(text_edit_rect).style(style);
+    let bg_color = if state.debounce {
+        Color32::TRANSPARENT
+    } else {
+        ui.ctx().theme().schematic().get_bg_color()
+    };
+    let font_size = state.grid_size * TextField::FONT_SCALE;
+    ui.scope_builder(ui_builder, |ui| {
+        egui::ScrollArea::vertical()
+            .auto_shrink(true)
+            .show(ui, |ui| {
+                if single_line {
+                    TextEdit::singleline(edit_buffer)
+                } else {
+                    TextEdit::multiline(edit_buffer)
+                }
+                .background_color(bg_color)
+                .desired_width(text_edit_rect.width())
+                .desired_rows(1)
+                .text_color(if state.debounce {
+                    Color32::TRANSPARENT
+                } else {
+                    ui.ctx().theme().schematic().get_text_color()
+                })
+                .font(egui::FontId::monospace(font_size))
+                .show(ui);
+            });
+        if state.debounce {
+            ui.ctx().request_repaint();
+        }
+    });
+}
+
 enum InteractionState {
     Idle,
     NetDragged {
         net_id: Id,
         segment_id: Id,
     },
+    /// Alt-drag of the segment, which inserts new corners at `split_pos`
+    NetSplitting {
+        net_id: Id,
+        segment_id: Id,
+        split_pos: GridPos,
+    },
     ComponentSelected(Id),
     ComponentDragged {
         id: Id,
         grab_ofs: Vec2,
     },
+    /// Several components selected with Shift+click
+    ComponentsSelected(HashSet<Id>),
+    /// Selected components are moved together, nets between them are kept rigid
+    ComponentsDragged {
+        ids: HashSet<Id>,
+        grab_pos: GridPos,
+    },
     Resizing {
         id: Id,
         direction: ResizeDirection,
@@ -69,28 +158,133 @@ enum InteractionState {
         id: Id,
         buffer: Component,
     },
-    NetSelected{net_id: Id, segment_id: Id, pos: GridPos},
+    /// Customizing the selected primitives of the same type together
+    CustomizeComponents {
+        ids: Vec<Id>,
+        buffer: Component,
+    },
+    NetSelected {
+        net_id: Id,
+        segment_id: Id,
+        pos: GridPos,
+    },
+    /// Choosing two equivalent pins to swap their nets
+    SwappingPins {
+        id: Id,
+        first: Option<Id>,
+    },
+    /// Choosing a library entry to replace the component with
+    ReplacingComponent {
+        id: Id,
+        query: String,
+    },
+    /// Shows problems of the replacement, e.g. connections that were lost
+    ReplaceReport {
+        message: &'static str,
+        items: Vec<String>,
+    },
+    /// Dragging draws keep-out regions, clicking removes them
+    DrawingKeepOut {
+        start: Option<GridPos>,
+    },
+    /// Clicked cell becomes the user origin
+    PickingOrigin,
+    /// Pasted or imported content follows the cursor until it is placed by click
+    PlacingFragment {
+        fragment: Box<GridDB>,
+        /// Top left cell of the fragment, placed at the cursor
+        anchor: GridPos,
+    },
+    /// Every click replays the macro at the clicked cell
+    PlayingMacro(Box<Macro>),
+}
+
+/// Identifies the logical edit a transaction belongs to.
+/// Consecutive transactions with the same key are merged into one undo step.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CoalesceKey {
+    MoveComponent(Id),
+    MoveNetSegment { net_id: Id, segment_id: Id },
+    EditText { id: Id, text_edit_id: Id },
+}
+
+/// Kinds of objects that can be hovered and grabbed on the field
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct SelectionFilter {
+    pub components: bool,
+    pub nets: bool,
+    /// Text fields
+    pub annotations: bool,
+}
+
+impl Default for SelectionFilter {
+    fn default() -> Self {
+        Self {
+            components: true,
+            nets: true,
+            annotations: true,
+        }
+    }
+}
+
+impl SelectionFilter {
+    pub fn accepts(&self, comp: &Component) -> bool {
+        match comp {
+            Component::TextField(_) => self.annotations,
+            _ => self.components,
+        }
+    }
+}
+
+/// Loads of the traced net, cycled by the jumps
+struct SignalTrace {
+    loads: Vec<GridDBConnectionPoint>,
+    current: usize,
 }
 
 pub struct InteractionManager {
+    pub selection_filter: SelectionFilter,
+    /// Moved text fields are attached to the adjacent components
+    pub snap_annotations: bool,
     state: InteractionState,
     drag_delta: Vec2,
     applied_transactions: LinkedList<Transaction>,
     reverted_transactions: LinkedList<Transaction>,
     connection_builder: ConnectionBuilder,
+    last_coalesce: Option<(CoalesceKey, Instant)>,
+    // Position to be shown by the field, like the connector selected by the jump
+    focus_request: Option<GridPos>,
+    macro_recorder: Option<MacroRecorder>,
+    signal_trace: Option<SignalTrace>,
+    netlist: NetlistCache,
+}
+
+impl Default for InteractionManager {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl InteractionManager {
     const UNDO_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::CTRL, egui::Key::Z);
     const REDO_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::CTRL, egui::Key::Y);
+    /// Max pause between two edits of the same kind that are still undone together
+    const COALESCE_WINDOW: Duration = Duration::from_millis(1000);
 
     pub fn new() -> Self {
         Self {
+            selection_filter: SelectionFilter::default(),
+            snap_annotations: true,
             state: InteractionState::Idle,
             drag_delta: vec2(0.0, 0.0),
             applied_transactions: LinkedList::new(),
             reverted_transactions: LinkedList::new(),
             connection_builder: ConnectionBuilder::new(),
+            last_coalesce: None,
+            focus_request: None,
+            macro_recorder: None,
+            signal_trace: None,
+            netlist: NetlistCache::default(),
         }
     }
 
@@ -103,6 +297,286 @@ impl InteractionManager {
         self.reset_state();
         self.applied_transactions.clear();
         self.reverted_transactions.clear();
+        self.last_coalesce = None;
+    }
+
+    /// Position of the newly selected component outside of the view, if any
+    pub fn take_focus_request(&mut self) -> Option<GridPos> {
+        self.focus_request.take()
+    }
+
+    /// Nothing is selected, dragged or edited now
+    pub fn is_idle(&self) -> bool {
+        matches!(self.state, InteractionState::Idle)
+            && matches!(self.connection_builder.state, ConnectionBuilderState::IDLE)
+    }
+
+    /// Switches to drawing keep-out regions until Escape is pressed
+    pub fn start_keep_out_drawing(&mut self) {
+        self.reset_state();
+        self.state = InteractionState::DrawingKeepOut { start: None };
+    }
+
+    pub fn is_drawing_keep_out(&self) -> bool {
+        matches!(self.state, InteractionState::DrawingKeepOut { .. })
+    }
+
+    /// Attaches the pasted or imported content to the cursor
+    pub fn start_placing(&mut self, fragment: GridDB) {
+        if fragment.get_component_ids().is_empty() {
+            return;
+        }
+        self.reset_state();
+        self.state = InteractionState::PlacingFragment {
+            anchor: fragment.get_bounds().0,
+            fragment: Box::new(fragment),
+        };
+    }
+
+    pub fn is_placing_fragment(&self) -> bool {
+        matches!(self.state, InteractionState::PlacingFragment { .. })
+    }
+
+    /// Selected components with nets between them
+    pub fn copy_selected(&self, db: &GridDB) -> Option<GridDB> {
+        let ids = self.get_selected_components();
+        (!ids.is_empty()).then(|| db.extract_fragment(&ids))
+    }
+
+    /// Inserts the fragment as one edit and selects the inserted components
+    fn place_fragment(&mut self, fragment: &GridDB, offset: GridPos, db: &mut GridDB) {
+        let allocated = db.allocate_fragment(fragment, offset);
+        let ids: HashSet<Id> = allocated.components.iter().map(|(id, _)| *id).collect();
+        let transactions = allocated
+            .components
+            .into_iter()
+            .map(|(comp_id, comp)| Transaction::ChangeComponent {
+                comp_id,
+                old_comp: None,
+                new_comp: Some(comp),
+            })
+            .chain(allocated.nets.into_iter().map(|(net_id, net)| Transaction::ChangeNet {
+                net_id,
+                old_net: None,
+                new_net: Some(net),
+            }))
+            .collect();
+        self.apply_new_transaction(Transaction::CombinedTransaction(transactions), db);
+        self.state = InteractionState::ComponentsSelected(ids);
+    }
+
+    pub fn start_origin_picking(&mut self) {
+        self.reset_state();
+        self.state = InteractionState::PickingOrigin;
+    }
+
+    pub fn is_picking_origin(&self) -> bool {
+        matches!(self.state, InteractionState::PickingOrigin)
+    }
+
+    /// Returns ID of the net, which is selected or dragged now
+    pub fn get_selected_net(&self) -> Option<Id> {
+        match &self.state {
+            InteractionState::NetSelected { net_id, .. }
+            | InteractionState::NetDragged { net_id, .. }
+            | InteractionState::NetSplitting { net_id, .. } => Some(*net_id),
+            _ => None,
+        }
+    }
+
+    /// Hovered component, if its kind passes the selection filter
+    fn get_hovered_component_id(&self, db: &GridDB, state: &FieldState) -> Option<Id> {
+        let id = *db.get_hovered_component_id(state)?;
+        self.selection_filter
+            .accepts(db.get_component(&id)?)
+            .then_some(id)
+    }
+
+    fn get_hovered_segment<'a>(&self, db: &'a GridDB, state: &FieldState) -> Option<&'a NetSegment> {
+        if self.selection_filter.nets {
+            db.get_hovered_segment(state)
+        } else {
+            None
+        }
+    }
+
+    /// Reverts the last edit, or cancels the current interaction
+    pub fn undo(&mut self, db: &mut GridDB) {
+        match self.state {
+            InteractionState::Idle => {
+                if let Some(mut trans) = self.applied_transactions.pop_back() {
+                    trans.revert(db);
+                    self.reverted_transactions.push_front(trans);
+                    if let Some(recorder) = &mut self.macro_recorder {
+                        recorder.pop_group();
+                    }
+                }
+                self.last_coalesce = None;
+            }
+            _ => {
+                self.state = InteractionState::Idle;
+            }
+        }
+    }
+
+    pub fn redo(&mut self, db: &mut GridDB) {
+        if let InteractionState::Idle = self.state {
+            if let Some(mut trans) = self.reverted_transactions.pop_front() {
+                self.record(&trans, db, true);
+                trans.apply(db);
+                self.applied_transactions.push_back(trans);
+            }
+            self.last_coalesce = None;
+        }
+    }
+
+    /// Removes the selected components or net, like the Delete key
+    pub fn remove_selected(&mut self, db: &mut GridDB) {
+        match &self.state {
+            InteractionState::ComponentSelected(id) => self.remove_component(db, *id),
+            InteractionState::ComponentsSelected(ids) => {
+                let ids: BTreeSet<Id> = ids.iter().copied().collect();
+                // Nets between the removed components are removed once:
+                let nets: BTreeSet<Id> =
+                    ids.iter().flat_map(|id| db.get_connected_nets(id)).collect();
+                let transactions = nets
+                    .into_iter()
+                    .map(|net_id| Transaction::ChangeNet {
+                        net_id,
+                        old_net: None,
+                        new_net: None,
+                    })
+                    .chain(ids.into_iter().map(|comp_id| Transaction::ChangeComponent {
+                        comp_id,
+                        old_comp: None,
+                        new_comp: None,
+                    }))
+                    .collect();
+                self.apply_new_transaction(Transaction::CombinedTransaction(transactions), db);
+            }
+            InteractionState::NetSelected { net_id, .. } => {
+                let net_id = *net_id;
+                self.apply_new_transaction(
+                    Transaction::ChangeNet {
+                        net_id,
+                        old_net: None,
+                        new_net: None,
+                    },
+                    db,
+                );
+            }
+            _ => return,
+        }
+        self.reset_state();
+    }
+
+    /// Replaces the components with the fragment as one undoable edit, e.g. by a unit
+    /// extracted from them. Nets of the removed components are removed too.
+    /// The inserted components are selected.
+    pub fn replace_fragment(
+        &mut self,
+        removed: &[Id],
+        fragment: AllocatedFragment,
+        db: &mut GridDB,
+    ) {
+        self.reset_state();
+        let nets: BTreeSet<Id> = removed
+            .iter()
+            .flat_map(|id| db.get_connected_nets(id))
+            .collect();
+        let ids: Vec<Id> = fragment.components.iter().map(|(id, _)| *id).collect();
+        let transactions = nets
+            .into_iter()
+            .map(|net_id| Transaction::ChangeNet {
+                net_id,
+                old_net: None,
+                new_net: None,
+            })
+            .chain(removed.iter().map(|comp_id| Transaction::ChangeComponent {
+                comp_id: *comp_id,
+                old_comp: None,
+                new_comp: None,
+            }))
+            .chain(fragment.components.into_iter().map(|(comp_id, comp)| {
+                Transaction::ChangeComponent {
+                    comp_id,
+                    old_comp: None,
+                    new_comp: Some(comp),
+                }
+            }))
+            .chain(
+                fragment
+                    .nets
+                    .into_iter()
+                    .map(|(net_id, net)| Transaction::ChangeNet {
+                        net_id,
+                        old_net: None,
+                        new_net: Some(net),
+                    }),
+            )
+            .collect();
+        self.apply_new_transaction(Transaction::CombinedTransaction(transactions), db);
+        self.select_components(&ids, db);
+    }
+
+    /// Inserts the new nets as one undoable edit, e.g. made by the auto-wiring
+    pub fn add_nets(&mut self, nets: Vec<(Id, Net)>, db: &mut GridDB) {
+        let transactions = nets
+            .into_iter()
+            .map(|(net_id, net)| Transaction::ChangeNet {
+                net_id,
+                old_net: None,
+                new_net: Some(net),
+            })
+            .collect();
+        self.apply_new_transaction(Transaction::CombinedTransaction(transactions), db);
+    }
+
+    /// Selects the existing ones of the components, e.g. restored with the project view
+    pub fn select_components(&mut self, ids: &[Id], db: &GridDB) {
+        let ids: HashSet<Id> =
+            ids.iter().copied().filter(|id| db.get_component(id).is_some()).collect();
+        self.reset_state();
+        self.state = match ids.len() {
+            0 => InteractionState::Idle,
+            1 => InteractionState::ComponentSelected(*ids.iter().next().unwrap()),
+            _ => InteractionState::ComponentsSelected(ids),
+        };
+    }
+
+    /// Returns IDs of the selected components in ascending order
+    pub fn get_selected_components(&self) -> Vec<Id> {
+        let mut ids: Vec<Id> = match &self.state {
+            InteractionState::ComponentSelected(id) => vec![*id],
+            InteractionState::ComponentsSelected(ids) => ids.iter().copied().collect(),
+            _ => vec![],
+        };
+        ids.sort_unstable();
+        ids
+    }
+
+    /// First of the selected components, if all of them are primitives of the same type
+    pub fn get_homogeneous_selection(&self, db: &GridDB) -> Option<Component> {
+        let ids = self.get_selected_components();
+        let typ = |id| match db.get_component(id)? {
+            Component::Primitive(p) => Some(discriminant(&p.typ)),
+            _ => None,
+        };
+        let first = typ(ids.first()?)?;
+        if ids.iter().any(|id| typ(id) != Some(first)) {
+            return None;
+        }
+        db.get_component(&ids[0]).cloned()
+    }
+
+    /// Opens one customization panel for all the selected primitives of the same type
+    pub fn customize_selected(&mut self, db: &GridDB) {
+        let Some(buffer) = self.get_homogeneous_selection(db) else {
+            return;
+        };
+        let ids = self.get_selected_components();
+        self.reset_state();
+        self.state = InteractionState::CustomizeComponents { ids, buffer };
     }
 
     pub fn add_new_component(&mut self, component: Component, db: &mut GridDB) {
@@ -116,10 +590,301 @@ impl InteractionManager {
         );
     }
 
-    fn apply_new_transaction(&mut self, mut transaction: Transaction, db: &mut GridDB) {
+    /// Assigns the class to the net keeping it selected, empty `class` makes the net unclassified
+    pub fn set_net_class(&mut self, net_id: Id, class: SharedStr, db: &mut GridDB) {
+        let Some(net) = db.get_net(&net_id) else {
+            return;
+        };
+        let mut net = net.clone();
+        net.class = class;
+        self.apply_new_transaction(
+            Transaction::ChangeNet {
+                net_id,
+                old_net: None,
+                new_net: Some(net),
+            },
+            db,
+        );
+    }
+
+    /// Replaces the net, e.g. rerouted by the problems fix, the change can be undone
+    pub fn replace_net(&mut self, net_id: Id, net: Net, db: &mut GridDB) {
+        self.reset_state();
+        self.apply_new_transaction(
+            Transaction::ChangeNet {
+                net_id,
+                old_net: None,
+                new_net: Some(net),
+            },
+            db,
+        );
+    }
+
+    /// Replaces the components as one undoable edit
+    pub fn replace_components(&mut self, components: Vec<(Id, Component)>, db: &mut GridDB) {
+        self.reset_state();
+        let transactions = components
+            .into_iter()
+            .map(|(comp_id, comp)| Transaction::ChangeComponent {
+                comp_id,
+                old_comp: None,
+                new_comp: Some(comp),
+            })
+            .collect();
+        self.apply_new_transaction(Transaction::CombinedTransaction(transactions), db);
+    }
+
+    /// Replaces the library part by its newer version, keeping the instance name
+    pub fn update_from_library(&mut self, id: Id, db: &mut GridDB, locale: &'static Locale) {
+        let Some(new_comp) = db.get_component(&id).and_then(get_updated_component) else {
+            return;
+        };
+        self.reset_state();
+        self.replace_in_place(db, id, new_comp, locale);
+    }
+
+    /// Updates the library parts to their newer versions as one undoable edit,
+    /// skips the ones that don't fit
+    pub fn update_components(&mut self, components: Vec<(Id, Component)>, db: &mut GridDB) {
+        self.reset_state();
+        let mut applied = 0;
+        for (id, new_comp) in components {
+            if !db.is_available_location(new_comp.get_position(), new_comp.get_dimension(), id) {
+                continue;
+            }
+            self.apply_replacement(db, id, new_comp);
+            applied += 1;
+        }
+        self.merge_last_transactions(applied);
+    }
+
+    /// Marks the library part as up to date without changing it
+    pub fn keep_library_version(&mut self, id: Id, db: &mut GridDB) {
+        let Some(mut comp) = db.get_component(&id).cloned() else {
+            return;
+        };
+        let Some(version) = comp
+            .get_library_source()
+            .and_then(get_library_update)
+            .map(|(version, _)| version)
+        else {
+            return;
+        };
+        let mut source = comp.get_library_source().unwrap().clone();
+        source.version = version;
+        comp.set_library_source(source);
+        self.replace_components(vec![(id, comp)], db);
+    }
+
+    /// Replaces the unit by the one with changed ports, nets of the ports are remapped
+    /// by `connections` and removed for the ports mapped to None.
+    /// Returns false if the new unit doesn't fit.
+    pub fn change_unit_ports(
+        &mut self,
+        id: Id,
+        unit: Unit,
+        connections: HashMap<Id, Option<Id>>,
+        db: &mut GridDB,
+    ) -> bool {
+        if !db.is_available_location(unit.pos, (unit.width, unit.height), id) {
+            return false;
+        }
+        self.reset_state();
+        self.apply_component_change(db, id, Component::Unit(unit), connections);
+        true
+    }
+
+    fn change_keep_out(&mut self, db: &mut GridDB, f: impl FnOnce(&mut Vec<KeepOutRegion>)) {
+        let mut regions = db.get_keep_out_regions().to_vec();
+        f(&mut regions);
+        self.apply_new_transaction(Transaction::ChangeKeepOut(regions), db);
+    }
+
+    pub fn start_macro_recording(&mut self) {
+        self.macro_recorder = Some(MacroRecorder::default());
+    }
+
+    pub fn is_recording_macro(&self) -> bool {
+        self.macro_recorder.is_some()
+    }
+
+    /// Stops the recording, None if nothing was recorded
+    pub fn stop_macro_recording(&mut self, name: &str) -> Option<Macro> {
+        self.macro_recorder.take()?.finish(name)
+    }
+
+    pub fn start_macro_playback(&mut self, recorded: Macro) {
+        self.reset_state();
+        self.state = InteractionState::PlayingMacro(Box::new(recorded));
+    }
+
+    /// Applies the edits of the macro relative to `anchor` as one undoable edit,
+    /// false if they don't fit
+    fn play_macro(&mut self, recorded: &Macro, anchor: GridPos, db: &mut GridDB) -> bool {
+        let Some(edits) = recorded.replay(db, anchor) else {
+            return false;
+        };
+        let transactions = edits
+            .into_iter()
+            .map(|edit| match edit {
+                MacroEdit::Component { id, component } => Transaction::ChangeComponent {
+                    comp_id: id,
+                    old_comp: None,
+                    new_comp: component,
+                },
+                MacroEdit::Net { id, net } => Transaction::ChangeNet {
+                    net_id: id,
+                    old_net: None,
+                    new_net: net,
+                },
+            })
+            .collect();
+        self.apply_new_transaction(Transaction::CombinedTransaction(transactions), db);
+        true
+    }
+
+    /// Adds the transaction to the recorded macro before it's applied,
+    /// `new_group` is false for transactions merged with the previous undo step
+    fn record(&mut self, transaction: &Transaction, db: &GridDB, new_group: bool) {
+        if let Some(recorder) = &mut self.macro_recorder {
+            if new_group {
+                recorder.start_group();
+            }
+            transaction.record(recorder, db);
+        }
+    }
+
+    /// Merges the last `n` applied transactions into one undo step
+    fn merge_last_transactions(&mut self, n: usize) {
+        if n > 1 {
+            let edits = self.applied_transactions.split_off(self.applied_transactions.len() - n);
+            self.applied_transactions.push_back(Transaction::CombinedTransaction(edits));
+            if let Some(recorder) = &mut self.macro_recorder {
+                recorder.merge_last_groups(n);
+            }
+        }
+    }
+
+    /// Component adjacent to the text field, the nearest one if there are several
+    fn find_anchor(db: &GridDB, id: Id, text: &TextField) -> Option<Id> {
+        let rect = Component::TextField(text.clone()).get_grid_rect(id);
+        let around = grid_rect(id, rect.min - grid_pos(1, 1), rect.max + grid_pos(1, 1));
+        let center = |r: &GridRect| r.min + r.max;
+        db.get_visible_component_ids(&around)
+            .into_iter()
+            .filter(|other| *other != id)
+            .filter_map(|other| match db.get_component(&other)? {
+                Component::TextField(_) => None,
+                comp => Some((other, comp.get_grid_rect(other))),
+            })
+            .min_by_key(|(other, r)| {
+                let d = center(r) - center(&rect);
+                (d.x * d.x + d.y * d.y, *other)
+            })
+            .map(|(other, _)| other)
+    }
+
+    /// Attaches the moved text fields to the adjacent components, if snapping is enabled.
+    /// Text fields attached to the moved components are moved along with them.
+    fn attach_annotations(&self, mut transaction: Transaction, db: &GridDB) -> Transaction {
+        let mut changed = HashSet::new();
+        transaction.for_each_component(&mut |id, _| {
+            changed.insert(id);
+        });
+        let mut moved = HashMap::new();
+        transaction.for_each_component(&mut |id, new_comp| {
+            let old_pos = db.get_component(&id).map(|comp| comp.get_position());
+            match new_comp {
+                Some(Component::TextField(text)) if old_pos != Some(text.pos) => {
+                    // Moved together with its component, or placed with a pasted one:
+                    if text.anchor.is_some_and(|anchor| changed.contains(&anchor)) {
+                        return;
+                    }
+                    text.anchor = match self.snap_annotations {
+                        true => Self::find_anchor(db, id, text),
+                        false => None,
+                    };
+                }
+                Some(comp) if old_pos.is_some_and(|pos| pos != comp.get_position()) => {
+                    moved.insert(id, comp.get_position() - old_pos.unwrap());
+                }
+                _ => {}
+            }
+        });
+        if moved.is_empty() {
+            return transaction;
+        }
+        let mut sequence = LinkedList::from([transaction]);
+        for id in db.get_component_ids() {
+            if changed.contains(&id) {
+                continue;
+            }
+            let Some(Component::TextField(text)) = db.get_component(&id) else {
+                continue;
+            };
+            let Some(delta) = text.anchor.and_then(|anchor| moved.get(&anchor)) else {
+                continue;
+            };
+            let mut text = text.clone();
+            text.pos += *delta;
+            if db.is_available_location(text.pos, text.size, id) {
+                sequence.push_back(Transaction::ChangeComponent {
+                    comp_id: id,
+                    old_comp: None,
+                    new_comp: Some(Component::TextField(text)),
+                });
+            }
+        }
+        Transaction::CombinedTransaction(sequence)
+    }
+
+    fn apply_new_transaction(&mut self, transaction: Transaction, db: &mut GridDB) {
+        let mut transaction = self.attach_annotations(transaction, db);
+        self.record(&transaction, db, true);
         transaction.apply(db);
         self.applied_transactions.push_back(transaction);
         self.reverted_transactions.clear();
+        self.last_coalesce = None;
+    }
+
+    /// Applies transaction and merges it with the previous one,
+    /// if both belong to the same logical edit made within `COALESCE_WINDOW`.
+    fn apply_coalescing_transaction(
+        &mut self,
+        transaction: Transaction,
+        key: CoalesceKey,
+        db: &mut GridDB,
+    ) {
+        let mut transaction = self.attach_annotations(transaction, db);
+        let now = Instant::now();
+        let mergeable = self.last_coalesce.is_some_and(|(last_key, last_time)| {
+            last_key == key && now.duration_since(last_time) < Self::COALESCE_WINDOW
+        });
+        match self.applied_transactions.back_mut() {
+            Some(last) if mergeable => {
+                if let Some(recorder) = &mut self.macro_recorder {
+                    transaction.record(recorder, db);
+                }
+                transaction.apply(db);
+                // Reverting a combined transaction goes backwards, so appending keeps it consistent:
+                match last {
+                    Transaction::CombinedTransaction(sequence) => sequence.push_back(transaction),
+                    _ => {
+                        let prev = std::mem::replace(
+                            last,
+                            Transaction::CombinedTransaction(LinkedList::new()),
+                        );
+                        *last = Transaction::CombinedTransaction(LinkedList::from([
+                            prev,
+                            transaction,
+                        ]));
+                    }
+                }
+                self.reverted_transactions.clear();
+            }
+            _ => self.apply_new_transaction(transaction, db),
+        }
+        self.last_coalesce = Some((key, now));
     }
 
     fn move_net_segment(
@@ -129,30 +894,35 @@ impl InteractionManager {
         cursor_grid_pos: &GridPos,
         db: &mut GridDB,
     ) {
-        let GridPos { x, y } = cursor_grid_pos;
         let mut net = db.get_net(&net_id).unwrap().clone();
-        let p1: GridPos = net.points[segment_id];
-        let p2 = net.points[segment_id + 1];
-        if p1.y == p2.y {
-            net.points[segment_id] = grid_pos(p1.x, *y);
-            net.points[segment_id + 1] = grid_pos(p2.x, *y);
-        } else {
-            net.points[segment_id] = grid_pos(*x, p1.y);
-            net.points[segment_id + 1] = grid_pos(*x, p2.y);
-        }
-        if net.points.len() > 0 && (segment_id == net.points.len() - 2) {
-            net.points.push(p2);
-        }
-        if segment_id == 0 {
-            net.points.insert(0, p1);
-        }
-        net.points = simplify_path(net.points);
-        self.apply_new_transaction(
+        net.points = get_moved_segment_path(net.points, segment_id, cursor_grid_pos);
+        self.apply_coalescing_transaction(
             Transaction::ChangeNet {
                 net_id: net_id,
                 old_net: None,
                 new_net: Some(net),
             },
+            CoalesceKey::MoveNetSegment { net_id, segment_id },
+            db,
+        );
+    }
+
+    fn split_net_segment(
+        &mut self,
+        net_id: Id,
+        segment_id: Id,
+        split_pos: &GridPos,
+        cursor_grid_pos: &GridPos,
+        db: &mut GridDB,
+    ) {
+        let mut net = db.get_net(&net_id).unwrap().clone();
+        net.points = get_split_segment_path(net.points, segment_id, split_pos, cursor_grid_pos);
+        self.apply_new_transaction(
+            Transaction::ChangeNet {
+                net_id,
+                old_net: None,
+                new_net: Some(net),
+            },
             db,
         );
     }
@@ -246,7 +1016,9 @@ impl InteractionManager {
     }
 
     fn move_component(&mut self, comp_id: Id, db: &mut GridDB, new_pos: GridPos) {
-        let comp = db.get_component(&comp_id).unwrap();
+        let Some(comp) = db.get_component(&comp_id) else {
+            return;
+        };
 
         if db.is_available_location(new_pos, comp.get_dimension(), comp_id) {
             let old_pos = comp.get_position();
@@ -282,10 +1054,224 @@ impl InteractionManager {
                 old_comp: None,
                 new_comp: Some(new_comp),
             });
-            self.apply_new_transaction(Transaction::CombinedTransaction(transactions), db);
+            self.apply_coalescing_transaction(
+                Transaction::CombinedTransaction(transactions),
+                CoalesceKey::MoveComponent(comp_id),
+                db,
+            );
+        }
+    }
+
+    /// Moves selected component by one cell in response to arrow keys
+    fn nudge_component(&mut self, comp_id: Id, db: &mut GridDB, ui: &egui::Ui) -> bool {
+        let delta = ui.input(|i| {
+            if i.key_pressed(egui::Key::ArrowLeft) {
+                Some(grid_pos(-1, 0))
+            } else if i.key_pressed(egui::Key::ArrowRight) {
+                Some(grid_pos(1, 0))
+            } else if i.key_pressed(egui::Key::ArrowUp) {
+                Some(grid_pos(0, -1))
+            } else if i.key_pressed(egui::Key::ArrowDown) {
+                Some(grid_pos(0, 1))
+            } else {
+                None
+            }
+        });
+        if let Some(delta) = delta
+            && let Some(comp) = db.get_component(&comp_id)
+        {
+            let new_pos = comp.get_position() + delta;
+            self.move_component(comp_id, db, new_pos);
+            return true;
+        }
+        false
+    }
+
+    /// Signal tracing: `D` jumps to the driver of the selected net or the hovered pin,
+    /// `L` cycles through the loads of the net. The reached component is selected.
+    fn trace_signal(&mut self, db: &GridDB, state: &FieldState, ui: &egui::Ui) -> bool {
+        if ui.ctx().wants_keyboard_input() {
+            return false;
+        }
+        let (driver, load) = ui.input(|i| {
+            let plain = !(i.modifiers.command || i.modifiers.ctrl || i.modifiers.alt);
+            (plain && i.key_pressed(egui::Key::D), plain && i.key_pressed(egui::Key::L))
+        });
+        if !driver && !load {
+            return false;
+        }
+        // The next load of the traced net, if the previous one is still selected:
+        if load
+            && let Some(trace) = &mut self.signal_trace
+            && matches!(self.state, InteractionState::ComponentSelected(id)
+                if id == trace.loads[trace.current].component_id)
+        {
+            trace.current = (trace.current + 1) % trace.loads.len();
+            let target = trace.loads[trace.current];
+            self.jump_to_pin(db, target);
+            return true;
+        }
+        let netlist = extract_netlist(db);
+        let net = match &self.state {
+            InteractionState::NetSelected { net_id, .. } => netlist.get_wire_net(*net_id),
+            _ => db.get_hovered_connection(state).and_then(|p| netlist.get_net(&p)),
+        };
+        let Some(net) = net else {
+            return false;
+        };
+        let targets = match driver {
+            true => netlist.get_drivers(net),
+            false => netlist.get_loads(net),
+        };
+        let Some(target) = targets.first().copied() else {
+            return false;
+        };
+        self.signal_trace = load.then_some(SignalTrace { loads: targets, current: 0 });
+        self.jump_to_pin(db, target);
+        true
+    }
+
+    /// Frees the caches of the project moved to a background tab
+    pub fn unload_caches(&mut self) {
+        self.netlist = NetlistCache::default();
+        self.signal_trace = None;
+    }
+
+    /// Translucent lines from the hovered unit port to the pins it's connected to,
+    /// across junctions and connectors
+    fn draw_connectivity_ghost(&mut self, db: &GridDB, state: &FieldState, painter: &Painter) {
+        let Some(hovered) = db.get_hovered_connection(state) else {
+            return;
+        };
+        let Some(comp @ Component::Unit(_)) = db.get_component(&hovered.component_id) else {
+            return;
+        };
+        let netlist = self.netlist.get(db);
+        let (Some(net), Some(start)) = (
+            netlist.get_net(&hovered),
+            comp.get_connection_position(hovered.connection_id, state),
+        ) else {
+            return;
+        };
+        let color = painter.ctx().theme().schematic().get_stroke_color().gamma_multiply(0.3);
+        for pin in &netlist.nets[net].pins {
+            if *pin == hovered {
+                continue;
+            }
+            let Some(end) = db
+                .get_component(&pin.component_id)
+                .and_then(|comp| comp.get_connection_position(pin.connection_id, state))
+            else {
+                continue;
+            };
+            painter.line_segment([start, end], Stroke::new(state.grid_size * 0.2, color));
+            painter.circle_filled(end, state.grid_size * 0.3, color);
         }
     }
 
+    /// Selects the component of the pin and shows the pin
+    fn jump_to_pin(&mut self, db: &GridDB, pin: GridDBConnectionPoint) {
+        self.focus_request = db
+            .get_component(&pin.component_id)
+            .and_then(|comp| comp.get_connection_dock_cell(pin.connection_id));
+        self.reset_state();
+        self.state = InteractionState::ComponentSelected(pin.component_id);
+    }
+
+    /// Changes parameters of the selected primitive without the modal:
+    /// `+` and `-` change the number of inputs, `T` cycles the operation of comparators
+    fn adjust_parameters(&mut self, comp_id: Id, db: &mut GridDB, ui: &egui::Ui) -> bool {
+        if ui.ctx().wants_keyboard_input() {
+            return false;
+        }
+        let Some(Component::Primitive(p)) = db.get_component(&comp_id) else {
+            return false;
+        };
+        let typ = ui.input(|i| {
+            if i.modifiers.command || i.modifiers.ctrl {
+                None
+            } else if i.key_pressed(egui::Key::Plus) || i.key_pressed(egui::Key::Equals) {
+                p.typ.with_inputs_delta(1, PrimitiveType::get_max_gate_inputs())
+            } else if i.key_pressed(egui::Key::Minus) {
+                p.typ.with_inputs_delta(-1, PrimitiveType::get_max_gate_inputs())
+            } else if i.key_pressed(egui::Key::T) {
+                p.typ.with_next_operation()
+            } else {
+                None
+            }
+        });
+        let Some(typ) = typ else {
+            return false;
+        };
+        let new_comp = Component::Primitive(PrimitiveComponent { typ, ..p.clone() });
+        if !db.is_available_location(new_comp.get_position(), new_comp.get_dimension(), comp_id) {
+            return false;
+        }
+        self.apply_customization(db, comp_id, new_comp);
+        true
+    }
+
+    /// Moves group of components by `delta`.
+    /// Nets between the moved components are translated as is, boundary nets are stretched.
+    fn move_components(&mut self, ids: &HashSet<Id>, db: &mut GridDB, delta: GridPos) {
+        if delta == grid_pos(0, 0) {
+            return;
+        }
+        for id in ids {
+            let Some(comp) = db.get_component(id) else {
+                return;
+            };
+            if !db.is_available_location_except(
+                comp.get_position() + delta,
+                comp.get_dimension(),
+                *id,
+                ids,
+            ) {
+                return;
+            }
+        }
+
+        let mut transactions = LinkedList::new();
+        let mut nets = HashSet::new();
+        for id in ids {
+            nets.extend(db.get_connected_nets(id));
+        }
+        for net_id in nets {
+            let net = db.get_net(&net_id).unwrap();
+            let trans = Self::get_net_connection_move_transaction(
+                net_id,
+                db,
+                if ids.contains(&net.start_point.component_id) {
+                    (delta.x, delta.y)
+                } else {
+                    (0, 0)
+                },
+                if ids.contains(&net.end_point.component_id) {
+                    (delta.x, delta.y)
+                } else {
+                    (0, 0)
+                },
+            );
+            if let Some(t) = trans {
+                transactions.push_back(t);
+            }
+        }
+        for id in ids {
+            let Some(comp) = db.get_component(id) else {
+                db.report_missing_component(*id);
+                continue;
+            };
+            let mut new_comp = comp.clone();
+            new_comp.set_pos(new_comp.get_position() + delta);
+            transactions.push_back(Transaction::ChangeComponent {
+                comp_id: *id,
+                old_comp: None,
+                new_comp: Some(new_comp),
+            });
+        }
+        self.apply_new_transaction(Transaction::CombinedTransaction(transactions), db);
+    }
+
     fn get_net_rotation_transaction(
         net_id: Id,
         db: &GridDB,
@@ -317,7 +1303,9 @@ impl InteractionManager {
     }
 
     fn rotate_component(&mut self, comp_id: Id, db: &mut GridDB, dir: RotationDirection) {
-        let comp = db.get_component(&comp_id).unwrap().clone();
+        let Some(comp) = db.get_component(&comp_id).cloned() else {
+            return;
+        };
         let mut rotated_comp = comp.clone();
         rotated_comp.rotate(dir);
 
@@ -390,7 +1378,9 @@ impl InteractionManager {
     }
 
     fn apply_resize(&mut self, db: &mut GridDB, comp_id: Id, new_size: (i32, i32)) {
-        let comp = db.get_component(&comp_id).unwrap();
+        let Some(comp) = db.get_component(&comp_id) else {
+            return;
+        };
 
         if db.is_available_location(comp.get_position(), new_size, comp_id) {
             let mut transactions = LinkedList::new();
@@ -471,16 +1461,19 @@ impl InteractionManager {
         let mut points1 = net.points[segment_id+1..net.points.len()].to_vec();
         points0.push(pos);
         points1.insert(0, pos);
-        let net0 = Net {start_point: net.start_point, end_point: GridDBConnectionPoint { component_id: point_id, connection_id: 0 }, points: points0};
-        let net1 = Net {start_point: GridDBConnectionPoint { component_id: point_id, connection_id: 0 }, end_point: net.end_point, points: points1};
+        let net0 = Net {start_point: net.start_point, end_point: GridDBConnectionPoint { component_id: point_id, connection_id: 0 }, points: points0, class: net.class.clone()};
+        let net1 = Net {start_point: GridDBConnectionPoint { component_id: point_id, connection_id: 0 }, end_point: net.end_point, points: points1, class: net.class.clone()};
         let mut transactions = LinkedList::new();
-        transactions.push_back(Transaction::ChangeComponent { comp_id: point_id, old_comp: None, new_comp: Some(Component::Primitive(PrimitiveComponent {pos, typ: crate::grid_db::PrimitiveType::Point, rotation: crate::grid_db::Rotation::ROT0})) });
+        transactions.push_back(Transaction::ChangeComponent { comp_id: point_id, old_comp: None, new_comp: Some(Component::Primitive(PrimitiveComponent {pos, typ: crate::grid_db::PrimitiveType::Point, rotation: crate::grid_db::Rotation::ROT0, name: Default::default(), source: None})) });
         transactions.push_back(Transaction::ChangeNet { net_id: net_id, old_net: None, new_net: Some(net0) });
         transactions.push_back(Transaction::ChangeNet { net_id: new_net_id, old_net: None, new_net: Some(net1) });
         self.apply_new_transaction(Transaction::CombinedTransaction(transactions), db);
     }
 
     fn remove_port(&mut self, db: &mut GridDB, comp_id: Id, port_id: Id) {
+        let Some(mut new_comp) = db.get_component(&comp_id).cloned() else {
+            return;
+        };
         let mut transactions = LinkedList::new();
         // Refresh connected net:
         for net_id in db.get_connected_nets(&comp_id) {
@@ -511,7 +1504,6 @@ impl InteractionManager {
                 });
             }
         }
-        let mut new_comp = db.get_component(&comp_id).unwrap().clone();
         new_comp.remove_port(port_id);
         transactions.push_back(Transaction::ChangeComponent {
             comp_id: comp_id,
@@ -522,8 +1514,110 @@ impl InteractionManager {
     }
 
     fn apply_customization(&mut self, db: &mut GridDB, comp_id: Id, customized_comp: Component) {
-        let old_comp = db.get_component(&comp_id).unwrap();
-        let connections_diff = old_comp.get_connections_diff(&customized_comp);
+        let Some(comp) = db.get_component(&comp_id) else {
+            return;
+        };
+        let connections_diff = comp.get_connections_diff(&customized_comp);
+        self.apply_component_change(db, comp_id, customized_comp, connections_diff);
+    }
+
+    /// Gives the type of `customized` to all the primitives as one undoable edit,
+    /// skips the ones that don't fit
+    fn apply_bulk_customization(&mut self, db: &mut GridDB, ids: &[Id], customized: Component) {
+        let Component::Primitive(customized) = customized else {
+            return;
+        };
+        let mut applied = 0;
+        for id in ids {
+            let Some(Component::Primitive(p)) = db.get_component(id) else {
+                continue;
+            };
+            let new_comp = Component::Primitive(PrimitiveComponent {
+                typ: customized.typ,
+                ..p.clone()
+            });
+            if !db.is_available_location(new_comp.get_position(), new_comp.get_dimension(), *id) {
+                continue;
+            }
+            self.apply_customization(db, *id, new_comp);
+            applied += 1;
+        }
+        self.merge_last_transactions(applied);
+    }
+
+    /// Exchanges nets connected to two equivalent pins of the component
+    fn swap_pins(&mut self, db: &mut GridDB, comp_id: Id, a: Id, b: Id) {
+        let Some(comp) = db.get_component(&comp_id).cloned() else {
+            return;
+        };
+        self.apply_component_change(
+            db,
+            comp_id,
+            comp,
+            HashMap::from([(a, Some(b)), (b, Some(a))]),
+        );
+    }
+
+    /// Replaces component keeping its position and rotation,
+    /// reports lost connections or that the new component does not fit
+    fn replace_in_place(
+        &mut self,
+        db: &mut GridDB,
+        id: Id,
+        mut new_comp: Component,
+        locale: &'static Locale,
+    ) {
+        let Some(old_comp) = db.get_component(&id) else {
+            return;
+        };
+        new_comp.set_pos(old_comp.get_position());
+        if let (Component::Primitive(new_p), Component::Primitive(old_p)) =
+            (&mut new_comp, old_comp)
+        {
+            new_p.rotation = old_p.rotation;
+        }
+        if db.is_available_location(new_comp.get_position(), new_comp.get_dimension(), id) {
+            let lost = self.apply_replacement(db, id, new_comp);
+            self.state = if lost.is_empty() {
+                InteractionState::Idle
+            } else {
+                InteractionState::ReplaceReport {
+                    message: locale.lost_connections,
+                    items: lost,
+                }
+            };
+        } else {
+            self.state = InteractionState::ReplaceReport {
+                message: locale.replacement_does_not_fit,
+                items: vec![],
+            };
+        }
+    }
+
+    /// Replaces component by another one from the library.
+    /// Returns names of connected pins that could not be preserved.
+    fn apply_replacement(&mut self, db: &mut GridDB, comp_id: Id, new_comp: Component) -> Vec<String> {
+        let lost = db.get_lost_connections(comp_id, &new_comp);
+        let connections_map = db
+            .get_component(&comp_id)
+            .unwrap()
+            .get_replacement_connections(&new_comp);
+        self.apply_component_change(db, comp_id, new_comp, connections_map);
+        lost
+    }
+
+    /// Replaces component, remaps connected nets according to `connections_diff`
+    /// and removes nets whose connections are mapped to None.
+    fn apply_component_change(
+        &mut self,
+        db: &mut GridDB,
+        comp_id: Id,
+        customized_comp: Component,
+        connections_diff: HashMap<Id, Option<Id>>,
+    ) {
+        let Some(old_comp) = db.get_component(&comp_id) else {
+            return;
+        };
         let mut transactions = LinkedList::new();
 
         // Rebuild connected nets:
@@ -645,29 +1739,11 @@ impl InteractionManager {
             } => {}
             _ => {
                 if ui.input_mut(|i| i.consume_shortcut(&Self::UNDO_SHORTCUT)) {
-                    // Undo:
-                    match self.state {
-                        InteractionState::Idle => {
-                            if let Some(mut trans) = self.applied_transactions.pop_back() {
-                                trans.revert(db);
-                                self.reverted_transactions.push_front(trans);
-                            }
-                        }
-                        _ => {
-                            self.state = InteractionState::Idle;
-                        }
-                    }
+                    self.undo(db);
                 } else if ui.input_mut(|i| i.consume_shortcut(&Self::REDO_SHORTCUT)) {
-                    // Redo:
-                    match self.state {
-                        InteractionState::Idle => {
-                            if let Some(mut trans) = self.reverted_transactions.pop_front() {
-                                trans.apply(db);
-                                self.applied_transactions.push_back(trans);
-                            }
-                        }
-                        _ => {} // ???
-                    }
+                    self.redo(db);
+                } else if self.trace_signal(db, state, ui) {
+                    return true;
                 }
             }
         }
@@ -703,8 +1779,36 @@ impl InteractionManager {
                     }
                 }
             }
+            InteractionState::NetSplitting {
+                net_id,
+                segment_id,
+                split_pos,
+            } => {
+                if let Some(hover_pos) = state.cursor_pos {
+                    ui.ctx().output_mut(|o| o.cursor_icon = CursorIcon::Move);
+                    if response.is_pointer_button_down_on() {
+                        self.drag_delta += response.drag_delta();
+                        return true;
+                    } else {
+                        self.drag_delta = vec2(0.0, 0.0);
+                        self.split_net_segment(
+                            *net_id,
+                            *segment_id,
+                            &split_pos.clone(),
+                            &state.screen_to_grid(hover_pos),
+                            db,
+                        );
+                        self.state = InteractionState::Idle
+                    }
+                }
+            }
             InteractionState::Idle => {
-                if let Some(resp) = self.connection_builder.update(db, state, &response) {
+                let resp = if self.selection_filter.nets {
+                    self.connection_builder.update(db, state, response)
+                } else {
+                    None
+                };
+                if let Some(resp) = resp {
                     match resp {
                         ConnectionBuilderResponse::Toggled => {
                             self.state = InteractionState::CreatingNet;
@@ -715,7 +1819,7 @@ impl InteractionManager {
                             panic!("Unexpected complete of building connection")
                         }
                     }
-                } else if let Some(segment) = db.get_hovered_segment(state) {
+                } else if let Some(segment) = self.get_hovered_segment(db, state) {
                     if segment.is_horizontal() {
                         ui.ctx()
                             .output_mut(|o| o.cursor_icon = CursorIcon::ResizeVertical);
@@ -731,23 +1835,54 @@ impl InteractionManager {
                     } else if ui.input(|state| {state.pointer.button_pressed(egui::PointerButton::Primary)}) {
                         // Do no use dragged() or drag_started()
                         self.drag_delta += response.drag_delta();
-                        self.state = InteractionState::NetDragged {
-                            net_id: segment.net_id,
-                            segment_id: segment.inner_id,
-                        };
+                        if ui.input(|i| i.modifiers.alt) {
+                            self.state = InteractionState::NetSplitting {
+                                net_id: segment.net_id,
+                                segment_id: segment.inner_id,
+                                split_pos: state.screen_to_grid(state.cursor_pos.unwrap()),
+                            };
+                        } else {
+                            self.state = InteractionState::NetDragged {
+                                net_id: segment.net_id,
+                                segment_id: segment.inner_id,
+                            };
+                        }
                         return true;
                     }
-                } else if let Some(id) = db.get_hovered_component_id(state) {
+                } else if let Some(id) = self.get_hovered_component_id(db, state) {
                     ui.ctx()
                         .output_mut(|o| o.cursor_icon = CursorIcon::Crosshair);
                     if response.clicked() {
-                        self.state = InteractionState::ComponentSelected(*id);
+                        if ui.input(|i| i.modifiers.shift) {
+                            self.state = InteractionState::ComponentsSelected(HashSet::from([id]));
+                        } else {
+                            self.state = InteractionState::ComponentSelected(id);
+                        }
                         return true;
                     }
                 }
-            }
-            InteractionState::ComponentSelected(id) => {
-                let comp = db.get_component(&id).unwrap();
+            }
+            InteractionState::ComponentSelected(id) => {
+                let id = *id;
+                if self.nudge_component(id, db, ui) || self.adjust_parameters(id, db, ui) {
+                    return true;
+                }
+                if response.clicked()
+                    && ui.input(|i| i.modifiers.shift)
+                    && let Some(other_id) = self.get_hovered_component_id(db, state)
+                {
+                    let mut ids = HashSet::from([id]);
+                    if !ids.remove(&other_id) {
+                        ids.insert(other_id);
+                    }
+                    self.state = InteractionState::ComponentsSelected(ids);
+                    return true;
+                }
+                let Some(comp) = db.get_component(&id) else {
+                    db.report_missing_component(id);
+                    self.reset_state();
+                    return true;
+                };
                 let resizable = comp.is_resizable();
                 let right_border_hovered =
                     Self::is_right_selection_border_hovered(state.cursor_pos, state, comp);
@@ -757,52 +1892,82 @@ impl InteractionManager {
                 // Check actions:
                 let action = Self::get_action(comp, state);
                 if ui.input(|i| i.key_pressed(egui::Key::Delete)) {
-                    self.remove_component(db, *id);
+                    self.remove_component(db, id);
                     self.state = InteractionState::Idle;
                     return true;
                 }
                 if response.clicked() && action != ComponentAction::None {
                     match action {
                         ComponentAction::RotateUp => {
-                            self.rotate_component(*id, db, RotationDirection::Up);
+                            self.rotate_component(id, db, RotationDirection::Up);
                             self.state = InteractionState::Idle;
                         }
                         ComponentAction::RotateDown => {
-                            self.rotate_component(*id, db, RotationDirection::Down);
+                            self.rotate_component(id, db, RotationDirection::Down);
                             self.state = InteractionState::Idle;
                         }
                         ComponentAction::Remove => {
-                            self.remove_component(db, *id);
+                            self.remove_component(db, id);
                             self.state = InteractionState::Idle;
                             return true;
                         }
                         ComponentAction::AddPort => {
-                            self.state = InteractionState::AddingPort(*id);
+                            self.state = InteractionState::AddingPort(id);
                             return true;
                         }
                         ComponentAction::RemovePort => {
-                            self.state = InteractionState::RemovingPort(*id);
+                            self.state = InteractionState::RemovingPort(id);
                             return true;
                         }
                         ComponentAction::EditPort => {
-                            self.state = InteractionState::EditingPort(*id);
+                            self.state = InteractionState::EditingPort(id);
                             return true;
                         }
                         ComponentAction::EditText => {
                             self.state = InteractionState::EditingText {
-                                id: *id,
+                                id,
                                 text_edit_id: 0,
-                                text_buffer: comp.get_text_edit(0).unwrap().clone(),
+                                text_buffer: comp.get_text_edit(0).unwrap().to_string(),
                             };
                             return true;
                         }
                         ComponentAction::Customize => {
+                            let Some(comp) = db.get_component(&id) else {
+                                db.report_missing_component(id);
+                                self.reset_state();
+                                return true;
+                            };
                             self.state = InteractionState::CustomizeComponent {
-                                id: *id,
-                                buffer: db.get_component(id).unwrap().clone(),
+                                id,
+                                buffer: comp.clone(),
+                            };
+                            return true;
+                        }
+                        ComponentAction::SwapPins => {
+                            self.state = InteractionState::SwappingPins {
+                                id,
+                                first: None,
+                            };
+                            return true;
+                        }
+                        ComponentAction::Replace => {
+                            self.state = InteractionState::ReplacingComponent {
+                                id,
+                                query: String::new(),
                             };
                             return true;
                         }
+                        ComponentAction::JumpToConnector => {
+                            if let Some(next) = get_next_connector(db, id) {
+                                let Some(next_comp) = db.get_component(&next) else {
+                                    db.report_missing_component(next);
+                                    return true;
+                                };
+                                self.focus_request = Some(next_comp.get_position());
+                                self.state = InteractionState::ComponentSelected(next);
+                            }
+                            return true;
+                        }
                         _ => {}
                     }
                     return true;
@@ -813,7 +1978,7 @@ impl InteractionManager {
                     if response.dragged() {
                         if let Some(hovepos) = response.hover_pos() {
                             self.state = InteractionState::ComponentDragged {
-                                id: *id,
+                                id,
                                 grab_ofs: hovepos.to_vec2()
                                     - state.grid_to_screen(&comp.get_position()).to_vec2(),
                             };
@@ -825,7 +1990,7 @@ impl InteractionManager {
                         .output_mut(|o| o.cursor_icon = CursorIcon::ResizeHorizontal);
                     if response.is_pointer_button_down_on() {
                         self.state = InteractionState::Resizing {
-                            id: *id,
+                            id,
                             direction: ResizeDirection::Right,
                         };
                         return true;
@@ -835,7 +2000,7 @@ impl InteractionManager {
                         .output_mut(|o| o.cursor_icon = CursorIcon::ResizeVertical);
                     if response.is_pointer_button_down_on() {
                         self.state = InteractionState::Resizing {
-                            id: *id,
+                            id,
                             direction: ResizeDirection::Down,
                         };
                         return true;
@@ -856,6 +2021,54 @@ impl InteractionManager {
                 }
                 return true;
             }
+            InteractionState::ComponentsSelected(ids) => {
+                let hovered_id = self.get_hovered_component_id(db, state);
+                if response.clicked() {
+                    match hovered_id {
+                        Some(id) if ui.input(|i| i.modifiers.shift) => {
+                            let mut ids = ids.clone();
+                            if !ids.remove(&id) {
+                                ids.insert(id);
+                            }
+                            self.state = if ids.is_empty() {
+                                InteractionState::Idle
+                            } else {
+                                InteractionState::ComponentsSelected(ids)
+                            };
+                        }
+                        _ => self.state = InteractionState::Idle,
+                    }
+                    return true;
+                }
+                if let Some(id) = hovered_id
+                    && ids.contains(&id)
+                {
+                    ui.ctx().output_mut(|o| o.cursor_icon = CursorIcon::Grab);
+                    if response.dragged()
+                        && let Some(hovepos) = response.hover_pos()
+                    {
+                        self.state = InteractionState::ComponentsDragged {
+                            ids: ids.clone(),
+                            grab_pos: state.screen_to_grid(hovepos),
+                        };
+                    }
+                    return true;
+                }
+            }
+            InteractionState::ComponentsDragged { ids, grab_pos } => {
+                if response.dragged() {
+                    ui.ctx()
+                        .output_mut(|o| o.cursor_icon = CursorIcon::Grabbing);
+                } else {
+                    let ids = ids.clone();
+                    if let Some(pos) = state.cursor_pos {
+                        let delta = state.screen_to_grid(pos) - *grab_pos;
+                        self.move_components(&ids, db, delta);
+                    }
+                    self.state = InteractionState::ComponentsSelected(ids);
+                }
+                return true;
+            }
             InteractionState::Resizing { id, direction } => {
                 if response.is_pointer_button_down_on() {
                     ui.ctx().output_mut(|o| {
@@ -865,7 +2078,11 @@ impl InteractionManager {
                         }
                     });
                 } else {
-                    let comp = db.get_component(&id).unwrap();
+                    let Some(comp) = db.get_component(id) else {
+                        db.report_missing_component(*id);
+                        self.reset_state();
+                        return true;
+                    };
                     if let Some(new_size) = Self::get_new_size(comp, state, *direction) {
                         self.apply_resize(db, *id, new_size);
                     }
@@ -878,7 +2095,11 @@ impl InteractionManager {
                 text_edit_id,
                 text_buffer,
             } => {
-                let comp = db.get_component(&id).unwrap();
+                let Some(comp) = db.get_component(id) else {
+                    db.report_missing_component(*id);
+                    self.reset_state();
+                    return true;
+                };
                 let text_edit_rect = comp.get_text_edit_rect(*text_edit_id, state).unwrap();
 
                 if response.clicked() {
@@ -887,13 +2108,17 @@ impl InteractionManager {
                         if !text_edit_rect.contains(cursor_pos) {
                             let mut new_comp = comp.clone();
                             *(new_comp.get_text_edit_mut(*text_edit_id).unwrap()) =
-                                text_buffer.clone();
-                            self.apply_new_transaction(
+                                text_buffer.into();
+                            self.apply_coalescing_transaction(
                                 Transaction::ChangeComponent {
                                     comp_id: *id,
                                     old_comp: None,
                                     new_comp: Some(new_comp),
                                 },
+                                CoalesceKey::EditText {
+                                    id: *id,
+                                    text_edit_id: *text_edit_id,
+                                },
                                 db,
                             );
                             self.state = InteractionState::Idle;
@@ -906,7 +2131,7 @@ impl InteractionManager {
                 if let Some(resp) = self.connection_builder.update(db, state, response) {
                     match resp {
                         ConnectionBuilderResponse::Complete(t) => {
-                            self.apply_new_transaction(t, db);
+                            self.apply_new_transaction(*t, db);
                             debug_assert!(!self.connection_builder.is_active());
                             self.state = InteractionState::Idle;
                             return true;
@@ -917,7 +2142,11 @@ impl InteractionManager {
                 }
             }
             InteractionState::AddingPort(id) => {
-                let comp = db.get_component(id).unwrap();
+                let Some(comp) = db.get_component(id) else {
+                    db.report_missing_component(*id);
+                    self.reset_state();
+                    return true;
+                };
                 if response.clicked() && !comp.is_hovered(state) {
                     self.state = InteractionState::Idle;
                     return true;
@@ -928,6 +2157,7 @@ impl InteractionManager {
                             offset: offset,
                             align: rotation,
                             name: "...".into(),
+                            bundle: None,
                         });
                         self.apply_new_transaction(
                             Transaction::ChangeComponent {
@@ -942,7 +2172,11 @@ impl InteractionManager {
             }
             InteractionState::RemovingPort(id) => {
                 // TODO
-                let comp = db.get_component(id).unwrap();
+                let Some(comp) = db.get_component(id) else {
+                    db.report_missing_component(*id);
+                    self.reset_state();
+                    return true;
+                };
                 if response.clicked() && !comp.is_hovered(state) {
                     self.state = InteractionState::Idle;
                     return true;
@@ -953,7 +2187,11 @@ impl InteractionManager {
                 }
             }
             InteractionState::EditingPort(id) => {
-                let comp = db.get_component(id).unwrap();
+                let Some(comp) = db.get_component(id) else {
+                    db.report_missing_component(*id);
+                    self.reset_state();
+                    return true;
+                };
                 if response.clicked() && !comp.is_hovered(state) {
                     self.state = InteractionState::Idle;
                     return true;
@@ -963,18 +2201,49 @@ impl InteractionManager {
                         self.state = InteractionState::EditingText {
                             id: *id,
                             text_edit_id: port_id,
-                            text_buffer: comp.get_text_edit(port_id).unwrap().clone(),
+                            text_buffer: comp.get_text_edit(port_id).unwrap().to_string(),
                         };
                         return true;
                     }
                 }
             }
+            InteractionState::SwappingPins { id, first } => {
+                let Some(comp) = db.get_component(id) else {
+                    db.report_missing_component(*id);
+                    self.reset_state();
+                    return true;
+                };
+                if response.clicked() {
+                    let (id, first) = (*id, *first);
+                    match (comp.get_hovered_swappable_connection(state), first) {
+                        (None, _) => self.state = InteractionState::ComponentSelected(id),
+                        (Some(pin), None) => {
+                            self.state = InteractionState::SwappingPins {
+                                id,
+                                first: Some(pin),
+                            }
+                        }
+                        (Some(pin), Some(first)) if pin == first => {
+                            self.state = InteractionState::SwappingPins { id, first: None }
+                        }
+                        (Some(pin), Some(first)) => {
+                            if comp.is_swappable_pair(pin, first) {
+                                self.swap_pins(db, id, pin, first);
+                            }
+                            self.state = InteractionState::ComponentSelected(id);
+                        }
+                    }
+                    return true;
+                }
+            }
             InteractionState::CustomizeComponent { id: _, buffer: _ } => {
                 let done = if let InteractionState::CustomizeComponent { id: _, buffer } =
                     &mut self.state
                 {
+                    let GridPos { x, y } = buffer.get_position() - db.get_origin();
                     egui::modal::Modal::new("customizing".into())
                         .show(ui.ctx(), |ui| {
+                            ui.label(format!("x: {x}, y: {y}"));
                             buffer.show_customization_panel(ui, locale);
                             ui.button("Ok").clicked()
                         })
@@ -994,6 +2263,134 @@ impl InteractionManager {
                     }
                 }
             }
+            InteractionState::CustomizeComponents { ids: _, buffer: _ } => {
+                let InteractionState::CustomizeComponents { ids, buffer } = &mut self.state else {
+                    panic!()
+                };
+                let count = ids.len();
+                let done = egui::modal::Modal::new("customizing".into())
+                    .show(ui.ctx(), |ui| {
+                        ui.label(format!("{}: {count}", locale.selected_components));
+                        buffer.show_customization_panel(ui, locale);
+                        ui.button("Ok").clicked()
+                    })
+                    .inner;
+                if done
+                    && let InteractionState::CustomizeComponents { ids, buffer } =
+                        std::mem::replace(&mut self.state, InteractionState::Idle)
+                {
+                    self.apply_bulk_customization(db, &ids, buffer);
+                    return true;
+                }
+            }
+            InteractionState::ReplacingComponent { id, query: _ } => {
+                let id = *id;
+                let mut cancel = false;
+                let chosen = if let InteractionState::ReplacingComponent { id: _, query } =
+                    &mut self.state
+                {
+                    egui::modal::Modal::new("replacing".into())
+                        .show(ui.ctx(), |ui| {
+                            ui.label(locale.replace_with);
+                            ui.add(egui::TextEdit::singleline(query).hint_text(locale.filter));
+                            let mut chosen = None;
+                            egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                                for entry in get_component_lib_with_query(query).iter().flatten() {
+                                    if ui.button(entry.name).clicked() {
+                                        chosen = Some(entry.component.clone());
+                                    }
+                                }
+                            });
+                            cancel = ui.button(locale.cancel).clicked();
+                            chosen
+                        })
+                        .inner
+                } else {
+                    panic!()
+                };
+
+                if let Some(new_comp) = chosen {
+                    self.replace_in_place(db, id, new_comp, locale);
+                    return true;
+                } else if cancel {
+                    self.state = InteractionState::Idle;
+                    return true;
+                }
+            }
+            InteractionState::PlacingFragment { fragment, anchor } => {
+                ui.ctx().set_cursor_icon(CursorIcon::Move);
+                if response.clicked()
+                    && let Some(pos) = state.cursor_pos
+                {
+                    let offset = state.screen_to_grid(pos) - *anchor;
+                    if db.is_free_fragment_location(fragment, offset)
+                        && let InteractionState::PlacingFragment { fragment, .. } =
+                            std::mem::replace(&mut self.state, InteractionState::Idle)
+                    {
+                        self.place_fragment(&fragment, offset, db);
+                    }
+                    return true;
+                }
+            }
+            InteractionState::PlayingMacro(recorded) => {
+                ui.ctx().set_cursor_icon(CursorIcon::Crosshair);
+                if response.clicked()
+                    && let Some(pos) = state.cursor_pos
+                {
+                    let recorded = recorded.clone();
+                    self.play_macro(&recorded, state.screen_to_grid(pos), db);
+                    return true;
+                }
+            }
+            InteractionState::PickingOrigin => {
+                ui.ctx().set_cursor_icon(CursorIcon::Crosshair);
+                if response.clicked()
+                    && let Some(pos) = state.cursor_pos
+                {
+                    db.set_user_origin(Some(state.screen_to_grid(pos)));
+                    self.state = InteractionState::Idle;
+                }
+                return true;
+            }
+            InteractionState::DrawingKeepOut { start } => {
+                let start = *start;
+                ui.ctx().set_cursor_icon(CursorIcon::Crosshair);
+                if response.drag_started() {
+                    let origin = ui.input(|i| i.pointer.press_origin());
+                    self.state = InteractionState::DrawingKeepOut {
+                        start: origin.map(|pos| state.screen_to_grid(pos)),
+                    };
+                } else if response.drag_stopped()
+                    && let (Some(start), Some(pos)) = (start, state.cursor_pos)
+                {
+                    let region = KeepOutRegion::new(start, state.screen_to_grid(pos));
+                    self.change_keep_out(db, |regions| regions.push(region));
+                    self.state = InteractionState::DrawingKeepOut { start: None };
+                } else if response.clicked()
+                    && let Some(pos) = state.cursor_pos
+                {
+                    let cell = state.screen_to_grid(pos);
+                    if db.is_keep_out(cell) {
+                        self.change_keep_out(db, |regions| regions.retain(|r| !r.contains(cell)));
+                    }
+                }
+                return true;
+            }
+            InteractionState::ReplaceReport { message, items } => {
+                let done = egui::modal::Modal::new("replace_report".into())
+                    .show(ui.ctx(), |ui| {
+                        ui.label(*message);
+                        for name in items {
+                            ui.label(format!("• {}", name));
+                        }
+                        ui.button("Ok").clicked()
+                    })
+                    .inner;
+                if done {
+                    self.state = InteractionState::Idle;
+                    return true;
+                }
+            }
             InteractionState::NetSelected { net_id, segment_id, pos } => {
                 if ui.input(|i| i.key_pressed(egui::Key::Delete)) {
                     self.apply_new_transaction(Transaction::ChangeNet { net_id: *net_id, old_net: None, new_net: None}, db);
@@ -1019,6 +2416,65 @@ impl InteractionManager {
         false
     }
 
+    /// Colored outline with the dashed one over it, which is seen without colors
+    fn draw_selection_outline(rect: Rect, state: &FieldState, painter: &Painter) {
+        let width = state.grid_size * 0.15;
+        painter.rect_stroke(
+            rect,
+            state.grid_size * 0.1,
+            Stroke::new(width, Color32::from_rgba_unmultiplied(100, 100, 0, 100)),
+            StrokeKind::Outside,
+        );
+        draw_dashed_rect(
+            &PainterCanvas(painter.clone()),
+            rect.expand(width * 0.5),
+            painter.ctx().theme().schematic().get_text_color(),
+            (state.grid_size * 0.05).max(1.0),
+            state.grid_size * 0.4,
+            state.grid_size * 0.2,
+        );
+    }
+
+    /// Draws semi-transparent fragment moved by `offset`, marks the occupied cells under it
+    fn draw_ghost(
+        fragment: &GridDB,
+        offset: GridPos,
+        db: &GridDB,
+        state: &FieldState,
+        painter: &Painter,
+    ) {
+        let theme = painter.ctx().theme().schematic();
+        let mut ghost_state = state.clone();
+        ghost_state.offset += vec2(offset.x as f32, offset.y as f32) * state.grid_size;
+        let mut ghost_painter = painter.clone();
+        ghost_painter.multiply_opacity(0.5);
+        let ghost_canvas = PainterCanvas(ghost_painter.clone());
+        for id in fragment.get_component_ids() {
+            let comp = fragment.get_component(&id).unwrap();
+            match comp {
+                Component::TextField(f) => {
+                    f.display_text(&db.expand_variables(&f.text), &ghost_state, &ghost_canvas)
+                }
+                _ => comp.display(&ghost_state, &ghost_canvas, theme),
+            }
+            let (pos, dim) = (comp.get_position() + offset, comp.get_dimension());
+            for x in 0..dim.0 {
+                for y in 0..dim.1 {
+                    let cell = pos + grid_pos(x, y);
+                    if !db.is_free_cell(cell, comp.is_overlap_only()) {
+                        painter.extend(blocked_cell(state, &cell));
+                    }
+                }
+            }
+        }
+        for id in fragment.get_net_ids() {
+            for segment in fragment.get_net(&id).unwrap().get_segments(id) {
+                let mesh = segment.get_mesh(fragment, &ghost_state, theme, None);
+                ghost_painter.add(Shape::Mesh(Arc::new(mesh)));
+            }
+        }
+    }
+
     pub fn draw(&mut self, db: &mut GridDB, state: &FieldState, painter: &Painter, ui: &mut Ui) {
         match &mut self.state {
             InteractionState::NetDragged { net_id, segment_id } => {
@@ -1072,43 +2528,111 @@ impl InteractionManager {
                     );
                 }
             }
+            InteractionState::NetSplitting {
+                net_id,
+                segment_id,
+                split_pos,
+            } => {
+                let ofs = vec2(0.5, 0.5) * state.grid_size;
+                if let Some(pos) = state.cursor_pos {
+                    let points = get_split_segment_path(
+                        db.get_net(net_id).unwrap().points.clone(),
+                        *segment_id,
+                        split_pos,
+                        &state.screen_to_grid(pos),
+                    );
+                    painter.line(
+                        points
+                            .iter()
+                            .map(|p| state.grid_to_screen(p) + ofs)
+                            .collect(),
+                        Stroke::new(
+                            state.grid_size * 0.1,
+                            Color32::from_rgba_unmultiplied(100, 100, 0, 100),
+                        ),
+                    );
+                }
+            }
             InteractionState::Idle => {
-                if !self.connection_builder.draw(db, state, painter) {
-                    if let Some(seg) = db.get_hovered_segment(state) {
-                        seg.highlight(state, &painter);
+                if !self.selection_filter.nets || !self.connection_builder.draw(db, state, painter) {
+                    if let Some(seg) = self.get_hovered_segment(db, state) {
+                        seg.highlight(state, &PainterCanvas(painter.clone()));
                     }
+                } else if !self.connection_builder.is_active() {
+                    self.draw_connectivity_ghost(db, state, painter);
                 }
             }
             InteractionState::ComponentSelected(id) => {
                 if let Some(comp) = db.get_component(&id) {
                     let rect = Self::get_selection_rect(comp, state);
-                    painter.rect_stroke(
-                        rect,
-                        state.grid_size * 0.1,
-                        Stroke::new(
-                            state.grid_size * 0.15,
-                            Color32::from_rgba_unmultiplied(100, 100, 0, 100),
-                        ),
-                        StrokeKind::Outside,
-                    );
+                    Self::draw_selection_outline(rect, state, painter);
+                    // Link of the annotation to its component:
+                    if let Component::TextField(TextField { anchor: Some(anchor), .. }) = comp
+                        && let Some(target) = db.get_component(anchor)
+                    {
+                        painter.line_segment(
+                            [rect.center(), Self::get_selection_rect(target, state).center()],
+                            Stroke::new(
+                                state.grid_size * 0.05,
+                                Color32::from_rgba_unmultiplied(100, 100, 0, 100),
+                            ),
+                        );
+                    }
                     Self::draw_actions_panel(comp, state, ui, painter);
                 }
             }
             InteractionState::ComponentDragged { id, grab_ofs } => {
                 if let Some(pos) = state.cursor_pos {
-                    let comp = db.get_component(&id).unwrap().is_overlap_only();
+                    let Some(comp) = db.get_component(id) else {
+                        db.report_missing_component(*id);
+                        return;
+                    };
                     draw_component_drag_preview(
                         db,
                         state,
-                        db.get_component(&id).unwrap().get_dimension(),
+                        comp.get_dimension(),
                         painter,
                         pos - *grab_ofs,
                         Some(*id),
                         ui.visuals().strong_text_color().gamma_multiply(0.08),
-                        comp,
+                        comp.is_overlap_only(),
                     );
                 }
             }
+            InteractionState::ComponentsSelected(ids) => {
+                for id in ids.iter() {
+                    if let Some(comp) = db.get_component(id) {
+                        let rect = Self::get_selection_rect(comp, state);
+                        Self::draw_selection_outline(rect, state, painter);
+                    }
+                }
+            }
+            InteractionState::ComponentsDragged { ids, grab_pos } => {
+                if let Some(pos) = state.cursor_pos {
+                    let delta = state.screen_to_grid(pos) - *grab_pos;
+                    let fill_color = ui.visuals().strong_text_color().gamma_multiply(0.08);
+                    let mut result = vec![];
+                    for id in ids.iter() {
+                        let Some(comp) = db.get_component(id) else {
+                            db.report_missing_component(*id);
+                            continue;
+                        };
+                        let (w, h) = comp.get_dimension();
+                        let p0 = comp.get_position() + delta;
+                        for x in 0..w {
+                            for y in 0..h {
+                                let cell = p0 + grid_pos(x, y);
+                                if db.is_available_cell_except(cell, *id, ids) {
+                                    result.push(filled_cells(state, &cell, 1, 1, fill_color));
+                                } else {
+                                    result.extend(blocked_cell(state, &cell));
+                                }
+                            }
+                        }
+                    }
+                    painter.extend(result);
+                }
+            }
             InteractionState::Resizing { id, direction } => {
                 if let Some(comp) = db.get_component(&id) {
                     if let Some(resize_rect) =
@@ -1131,7 +2655,10 @@ impl InteractionManager {
                 text_edit_id,
                 text_buffer,
             } => {
-                let comp = db.get_component_mut(&id).unwrap();
+                let Some(comp) = db.get_component_mut(id) else {
+                    db.report_missing_component(*id);
+                    return;
+                };
                 let text_edit_rect = comp.get_text_edit_rect(*text_edit_id, state).unwrap();
                 show_text_edit(
                     text_edit_rect,
@@ -1143,7 +2670,10 @@ impl InteractionManager {
                 );
             }
             InteractionState::AddingPort(id) => {
-                let comp = db.get_component(id).unwrap();
+                let Some(comp) = db.get_component(id) else {
+                    db.report_missing_component(*id);
+                    return;
+                };
                 let rect = Self::get_selection_rect(comp, state);
                 painter.rect_stroke(
                     rect,
@@ -1157,6 +2687,7 @@ impl InteractionManager {
                         align: rotation,
                         offset: offset,
                         name: "".into(),
+                        bundle: None,
                     }
                     .center(&comp.get_position(), comp.get_dimension(), state);
                     painter.text(
@@ -1169,7 +2700,10 @@ impl InteractionManager {
                 }
             }
             InteractionState::EditingPort(id) => {
-                let comp = db.get_component(id).unwrap();
+                let Some(comp) = db.get_component(id) else {
+                    db.report_missing_component(*id);
+                    return;
+                };
 
                 let rect = Self::get_selection_rect(comp, state);
                 painter.rect_stroke(
@@ -1185,6 +2719,7 @@ impl InteractionManager {
                         align: rotation,
                         offset: offset,
                         name: "".into(),
+                        bundle: None,
                     }
                     .center(&comp.get_position(), comp.get_dimension(), state);
                     painter.circle_filled(
@@ -1192,7 +2727,7 @@ impl InteractionManager {
                         state.grid_size * 0.3,
                         Color32::BLUE.gamma_multiply(0.5),
                     );
-                    let theme = painter.ctx().theme();
+                    let theme = painter.ctx().theme().schematic();
                     let galley = painter.fonts(|fonts| {
                         fonts.layout_no_wrap(
                             "📝".into(),
@@ -1216,8 +2751,30 @@ impl InteractionManager {
                 }
             }
 
+            InteractionState::SwappingPins { id, first } => {
+                let Some(comp) = db.get_component(id) else {
+                    db.report_missing_component(*id);
+                    return;
+                };
+                let rect = Self::get_selection_rect(comp, state);
+                painter.rect_stroke(
+                    rect,
+                    state.grid_size * 0.1,
+                    Stroke::new(state.grid_size * 0.15, Color32::BLUE.gamma_multiply(0.25)),
+                    StrokeKind::Outside,
+                );
+                if let Some(first) = first {
+                    comp.highlight_connection(*first, state, &PainterCanvas(painter.clone()));
+                }
+                if let Some(hovered) = comp.get_hovered_swappable_connection(state) {
+                    comp.highlight_connection(hovered, state, &PainterCanvas(painter.clone()));
+                }
+            }
             InteractionState::RemovingPort(id) => {
-                let comp = db.get_component(id).unwrap();
+                let Some(comp) = db.get_component(id) else {
+                    db.report_missing_component(*id);
+                    return;
+                };
 
                 let rect = Self::get_selection_rect(comp, state);
                 painter.rect_stroke(
@@ -1232,6 +2789,7 @@ impl InteractionManager {
                         align: rotation,
                         offset: offset,
                         name: "".into(),
+                        bundle: None,
                     }
                     .center(&comp.get_position(), comp.get_dimension(), state);
                     painter.text(
@@ -1247,10 +2805,29 @@ impl InteractionManager {
                 ui.ctx().output_mut(|o| o.cursor_icon = CursorIcon::Crosshair);
                 self.connection_builder.draw(db, state, painter);
             }
+            InteractionState::PlacingFragment { fragment, anchor } => {
+                if let Some(pos) = state.cursor_pos {
+                    let offset = state.screen_to_grid(pos) - *anchor;
+                    Self::draw_ghost(fragment, offset, db, state, painter);
+                }
+            }
+            InteractionState::DrawingKeepOut { start: Some(start) } => {
+                if let Some(pos) = state.cursor_pos {
+                    let region = KeepOutRegion::new(*start, state.screen_to_grid(pos));
+                    let size = region.max - region.min + grid_pos(1, 1);
+                    painter.add(filled_cells(
+                        state,
+                        &region.min,
+                        size.x,
+                        size.y,
+                        Color32::RED.gamma_multiply(0.25),
+                    ));
+                }
+            }
             InteractionState::NetSelected { net_id, segment_id: _, pos } => {
                 let segments = db.get_net(net_id).unwrap().get_segments(*net_id);
                 for seg in segments {
-                    seg.highlight(state, painter);
+                    seg.highlight_selected(state, &PainterCanvas(painter.clone()));
                 }
                 Self::draw_net_action_panel(painter, pos, state);
             }
@@ -1281,7 +2858,7 @@ impl InteractionManager {
             } else {
                 false
             };
-            action.draw(painter, rect, selected);
+            action.draw(&rect, painter, selected, visuals);
         });
     }
 
@@ -1427,36 +3004,60 @@ enum ConnectionBuilderResponse {
     Hovered,
     Toggled,
     /// Connection building is complete
-    Complete(Transaction),
+    Complete(Box<Transaction>),
 }
 
 pub struct ConnectionBuilder {
     state: ConnectionBuilderState,
 }
 
-fn simplify_path(mut path: Vec<GridPos>) -> Vec<GridPos> {
-    loop {
-        let prev_size = path.len();
-        let mut i = 1;
-        while i < (path.len() - 1) {
-            let prev = path[i - 1];
-            let curr = path[i];
-            let next = path[i + 1];
-
-            let same_x = prev.x == curr.x && curr.x == next.x;
-            let same_y = prev.y == curr.y && curr.y == next.y;
+/// Moves segment of the path to the cursor line, keeping the end points in place
+fn get_moved_segment_path(
+    mut points: Vec<GridPos>,
+    segment_id: Id,
+    cursor_grid_pos: &GridPos,
+) -> Vec<GridPos> {
+    let GridPos { x, y } = cursor_grid_pos;
+    let p1: GridPos = points[segment_id];
+    let p2 = points[segment_id + 1];
+    if p1.y == p2.y {
+        points[segment_id] = grid_pos(p1.x, *y);
+        points[segment_id + 1] = grid_pos(p2.x, *y);
+    } else {
+        points[segment_id] = grid_pos(*x, p1.y);
+        points[segment_id + 1] = grid_pos(*x, p2.y);
+    }
+    if !points.is_empty() && (segment_id == points.len() - 2) {
+        points.push(p2);
+    }
+    if segment_id == 0 {
+        points.insert(0, p1);
+    }
+    simplify_path(points)
+}
 
-            if same_x || same_y {
-                path.remove(i);
-            } else {
-                i += 1;
-            }
-        }
-        if prev_size == path.len() {
-            break;
-        }
+/// Splits segment at `split_pos` into three: the untouched head, a new jog
+/// and the tail moved to the cursor line.
+fn get_split_segment_path(
+    mut points: Vec<GridPos>,
+    segment_id: Id,
+    split_pos: &GridPos,
+    cursor_grid_pos: &GridPos,
+) -> Vec<GridPos> {
+    let p1 = points[segment_id];
+    let p2 = points[segment_id + 1];
+    // Keep jog orthogonal to the segment:
+    let split_pos = if p1.y == p2.y {
+        grid_pos(split_pos.x, p1.y)
+    } else {
+        grid_pos(p1.x, split_pos.y)
+    };
+    if split_pos == p1 || split_pos == p2 {
+        return get_moved_segment_path(points, segment_id, cursor_grid_pos);
     }
-    path
+    points.insert(segment_id + 1, split_pos);
+    points.insert(segment_id + 1, split_pos);
+    get_moved_segment_path(points, segment_id + 2, cursor_grid_pos)
 }
 
 impl ConnectionBuilder {
@@ -1473,10 +3074,9 @@ impl ConnectionBuilder {
                     result.extend(db.find_net_path(result.last().unwrap().clone(), a.clone())); // !!!
                     result.push(a.clone());
                 });
-                let target_comp = db.get_component(&target.component_id).unwrap();
-                let target_pos = target_comp
-                    .get_connection_dock_cell(target.connection_id)
-                    .unwrap();
+                let target_pos = db
+                    .get_component(&target.component_id)?
+                    .get_connection_dock_cell(target.connection_id)?;
                 result.extend(db.find_net_path(result.last().unwrap().clone(), target_pos.clone())); // !!!
                 result.push(target_pos);
                 Some(simplify_path(result))
@@ -1500,7 +3100,7 @@ impl ConnectionBuilder {
         if let Some(con) = db.get_hovered_connection(&state) {
             if response.clicked() {
                 if let Some(t) = self.toggle(db, con) {
-                    return Some(ConnectionBuilderResponse::Complete(t));
+                    return Some(ConnectionBuilderResponse::Complete(Box::new(t)));
                 } else {
                     return Some(ConnectionBuilderResponse::Toggled);
                 }
@@ -1537,6 +3137,7 @@ impl ConnectionBuilder {
                                 start_point: point,
                                 end_point: target_point,
                                 points: points,
+                                class: Default::default(),
                             }),
                         })
                     } else {
@@ -1568,7 +3169,7 @@ impl ConnectionBuilder {
                     let r2 = r1.scale_from_center(0.5);
                     let stroke = Stroke::new(
                         state.grid_size * 0.1,
-                        painter.ctx().theme().get_anchor_color(),
+                        painter.ctx().theme().schematic().get_anchor_color(),
                     );
                     painter.line_segment([r1.left_top(), r2.left_top()], stroke);
                     painter.line_segment([r1.left_bottom(), r2.left_bottom()], stroke);
@@ -1585,7 +3186,7 @@ impl ConnectionBuilder {
         let result = if let Some(con) = db.get_hovered_connection(&state) {
             db.get_component(&con.component_id)
                 .unwrap()
-                .highlight_connection(con.connection_id, state, painter);
+                .highlight_connection(con.connection_id, state, &PainterCanvas(painter.clone()));
             true
         } else {
             false
@@ -1637,13 +3238,13 @@ impl ConnectionBuilder {
                             painter.circle_filled(
                                 points[i],
                                 state.grid_size * 0.15,
-                                painter.ctx().theme().get_stroke_color(),
+                                painter.ctx().theme().schematic().get_stroke_color(),
                             );
                             painter.line_segment(
                                 [points[i - 1], points[i]],
                                 Stroke::new(
                                     state.grid_size * 0.3,
-                                    painter.ctx().theme().get_stroke_color(),
+                                    painter.ctx().theme().schematic().get_stroke_color(),
                                 ),
                             );
                         }
@@ -1676,6 +3277,8 @@ enum Transaction {
         new_net: Option<Net>,
     },
     CombinedTransaction(LinkedList<Transaction>),
+    /// Holds the other list of keep-out regions, applying and reverting swaps them
+    ChangeKeepOut(Vec<KeepOutRegion>),
 }
 
 impl Transaction {
@@ -1686,13 +3289,16 @@ impl Transaction {
                     t.apply(db);
                 }
             }
+            Transaction::ChangeKeepOut(regions) => {
+                std::mem::swap(db.get_keep_out_regions_mut(), regions);
+            }
             Transaction::ChangeComponent {
                 comp_id: id,
                 old_comp,
                 new_comp,
             } => {
                 *old_comp = db.remove_component(&id);
-                if let Some(inserting_comp) = std::mem::replace(new_comp, None) {
+                if let Some(inserting_comp) = new_comp.take() {
                     db.insert_component(*id, inserting_comp);
                 }
             }
@@ -1702,11 +3308,49 @@ impl Transaction {
                 old_net,
                 new_net,
             } => {
-                *old_net = db.remove_net(&net_id);
-                if let Some(inserting_net) = std::mem::replace(new_net, None) {
-                    db.insert_net(*net_id, inserting_net);
+                // The rerouted net keeps its unchanged segments in the R-tree:
+                *old_net = match new_net.take() {
+                    Some(inserting_net) => db.replace_net(*net_id, inserting_net),
+                    None => db.remove_net(net_id),
+                };
+            }
+        }
+    }
+
+    /// Calls `f` with the new states of the changed components, before it's applied
+    fn for_each_component(&mut self, f: &mut impl FnMut(Id, &mut Option<Component>)) {
+        match self {
+            Transaction::CombinedTransaction(sequence) => {
+                for t in sequence {
+                    t.for_each_component(f);
+                }
+            }
+            Transaction::ChangeComponent {
+                comp_id, new_comp, ..
+            } => f(*comp_id, new_comp),
+            Transaction::ChangeNet { .. } | Transaction::ChangeKeepOut(_) => {}
+        }
+    }
+
+    /// Adds the new states of the components and nets to the macro, before it's applied
+    fn record(&self, recorder: &mut MacroRecorder, db: &GridDB) {
+        match self {
+            Transaction::CombinedTransaction(sequence) => {
+                for t in sequence {
+                    t.record(recorder, db);
                 }
             }
+            Transaction::ChangeKeepOut(_) => {}
+            Transaction::ChangeComponent {
+                comp_id,
+                new_comp,
+                ..
+            } => recorder.record_component(db, *comp_id, new_comp.as_ref()),
+            Transaction::ChangeNet {
+                net_id,
+                new_net,
+                ..
+            } => recorder.record_net(db, *net_id, new_net.as_ref()),
         }
     }
 
@@ -1717,13 +3361,16 @@ impl Transaction {
                     t.revert(db);
                 }
             }
+            Transaction::ChangeKeepOut(regions) => {
+                std::mem::swap(db.get_keep_out_regions_mut(), regions);
+            }
             Transaction::ChangeComponent {
                 comp_id: id,
                 old_comp,
                 new_comp,
             } => {
                 *new_comp = db.remove_component(&id);
-                if let Some(inserting_comp) = std::mem::replace(old_comp, None) {
+                if let Some(inserting_comp) = old_comp.take() {
                     db.insert_component(*id, inserting_comp);
                 }
             }
@@ -1732,11 +3379,176 @@ impl Transaction {
                 old_net,
                 new_net,
             } => {
-                *new_net = db.remove_net(&net_id);
-                if let Some(inserting_net) = std::mem::replace(old_net, None) {
-                    db.insert_net(*net_id, inserting_net);
-                }
+                // The rerouted net keeps its unchanged segments in the R-tree:
+                *new_net = match old_net.take() {
+                    Some(inserting_net) => db.replace_net(*net_id, inserting_net),
+                    None => db.remove_net(net_id),
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::LinkedList;
+
+    use super::{InteractionManager, Transaction};
+    use crate::{
+        grid_db::{Component, GridDB, PrimitiveComponent, PrimitiveType, Rotation, grid_pos},
+        testing::{GridDBBuilder, Rng, half_adder, json_snapshot},
+    };
+
+    fn random_transaction(rng: &mut Rng, db: &GridDB, depth: usize) -> Transaction {
+        let ids = db.get_component_ids();
+        let net_ids = db.get_net_ids();
+        match rng.below(if depth < 2 { 5 } else { 4 }) {
+            0 | 1 => Transaction::ChangeComponent {
+                comp_id: match ids.is_empty() || rng.chance(30) {
+                    true => ids.iter().max().map_or(0, |id| id + 1) + rng.below(3),
+                    false => rng.pick(&ids),
+                },
+                old_comp: None,
+                new_comp: (!rng.chance(20)).then(|| rng.component()),
+            },
+            2 | 3 => Transaction::ChangeNet {
+                net_id: match net_ids.is_empty() || rng.chance(30) {
+                    true => net_ids.iter().max().map_or(0, |id| id + 1) + rng.below(3),
+                    false => rng.pick(&net_ids),
+                },
+                old_net: None,
+                new_net: if rng.chance(20) { None } else { rng.net(db) },
+            },
+            _ => Transaction::CombinedTransaction(
+                (0..rng.below(4))
+                    .map(|_| random_transaction(rng, db, depth + 1))
+                    .collect::<LinkedList<_>>(),
+            ),
+        }
+    }
+
+    #[test]
+    fn transaction_revert_restores_db() {
+        for seed in 1..=100 {
+            let mut rng = Rng::new(seed);
+            let mut db = half_adder();
+            for step in 0..20 {
+                let before = json_snapshot(&db);
+                let mut transaction = random_transaction(&mut rng, &db, 0);
+                transaction.apply(&mut db);
+                db.check_indices()
+                    .unwrap_or_else(|e| panic!("seed {seed}, step {step}, apply: {e}"));
+                let after = json_snapshot(&db);
+
+                transaction.revert(&mut db);
+                db.check_indices()
+                    .unwrap_or_else(|e| panic!("seed {seed}, step {step}, revert: {e}"));
+                assert_eq!(before, json_snapshot(&db), "seed {seed}, step {step}");
+
+                transaction.apply(&mut db);
+                assert_eq!(after, json_snapshot(&db), "seed {seed}, step {step}");
+            }
+        }
+    }
+
+    #[test]
+    fn undo_history_restores_db() {
+        for seed in 1..=50 {
+            let mut rng = Rng::new(seed);
+            let mut db = half_adder();
+            let initial = json_snapshot(&db);
+            let mut history = vec![];
+            for _ in 0..20 {
+                let mut transaction = random_transaction(&mut rng, &db, 0);
+                transaction.apply(&mut db);
+                history.push(transaction);
+            }
+            let last = json_snapshot(&db);
+            for transaction in history.iter_mut().rev() {
+                transaction.revert(&mut db);
             }
+            db.check_indices()
+                .unwrap_or_else(|e| panic!("seed {seed}: {e}"));
+            assert_eq!(initial, json_snapshot(&db), "seed {seed}");
+            for transaction in history.iter_mut() {
+                transaction.apply(&mut db);
+            }
+            assert_eq!(last, json_snapshot(&db), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn bulk_customization() {
+        let mut b = GridDBBuilder::new();
+        let input = b.primitive(PrimitiveType::Input, 0, 0, Rotation::ROT0);
+        let first = b.primitive(PrimitiveType::And(2), 8, 0, Rotation::ROT0);
+        let second = b.primitive(PrimitiveType::And(2), 8, 10, Rotation::ROT90);
+        b.connect((input, 0), (first, 2));
+        b.connect((input, 0), (second, 1));
+        let mut db = b.build();
+        let initial = json_snapshot(&db);
+        let mut manager = InteractionManager::new();
+        manager.select_components(&[first, input], &db);
+        assert!(manager.get_homogeneous_selection(&db).is_none());
+        manager.select_components(&[first, second], &db);
+        let Some(Component::Primitive(buffer)) = manager.get_homogeneous_selection(&db) else {
+            panic!()
+        };
+        let customized = Component::Primitive(PrimitiveComponent {
+            typ: PrimitiveType::And(3),
+            ..buffer
+        });
+        manager.apply_bulk_customization(&mut db, &[first, second], customized);
+        for (id, rotation) in [(first, Rotation::ROT0), (second, Rotation::ROT90)] {
+            let Some(Component::Primitive(p)) = db.get_component(&id) else {
+                panic!()
+            };
+            assert!(p.typ == PrimitiveType::And(3) && p.rotation == rotation);
+            assert_eq!(db.get_connected_nets(&id).len(), 1);
         }
+        db.check_indices().unwrap();
+        // Both changes are undone at once:
+        manager.reset_state();
+        manager.undo(&mut db);
+        assert_eq!(initial, json_snapshot(&db));
+    }
+
+    #[test]
+    fn annotation_anchors() {
+        let mut b = GridDBBuilder::new();
+        let not = b.primitive(PrimitiveType::Not, 0, 0, Rotation::ROT0);
+        let other = b.primitive(PrimitiveType::Not, 20, 0, Rotation::ROT0);
+        let text = b.text("inverter", 10, 10);
+        let mut db = b.build();
+        let anchor = |db: &GridDB| match db.get_component(&text) {
+            Some(Component::TextField(t)) => t.anchor,
+            _ => panic!(),
+        };
+        let mut manager = InteractionManager::new();
+        // Dropped next to the component:
+        manager.move_component(text, &mut db, grid_pos(0, -1));
+        assert_eq!(anchor(&db), Some(not));
+        manager.move_component(not, &mut db, grid_pos(0, 5));
+        assert_eq!(db.get_component(&text).unwrap().get_position(), grid_pos(0, 4));
+        // Moved with the other component, and undone at once:
+        manager.move_component(other, &mut db, grid_pos(20, 5));
+        assert_eq!(db.get_component(&text).unwrap().get_position(), grid_pos(0, 4));
+        manager.undo(&mut db);
+        manager.undo(&mut db);
+        assert_eq!(db.get_component(&text).unwrap().get_position(), grid_pos(0, -1));
+        // Anchors follow the new IDs of the copies:
+        let mut copy = GridDB::new();
+        copy.allocate_component();
+        copy.append(db.extract_fragment(&[not, text]));
+        let ids = copy.get_component_ids();
+        assert!(matches!(
+            copy.get_component(&ids[1]),
+            Some(Component::TextField(t)) if t.anchor == Some(ids[0])
+        ));
+        // Detached when dropped away:
+        manager.snap_annotations = false;
+        manager.move_component(text, &mut db, grid_pos(0, -3));
+        assert_eq!(anchor(&db), None);
+        db.check_indices().unwrap();
     }
 }
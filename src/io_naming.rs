@@ -0,0 +1,85 @@
+//! Naming table window of the unnamed inputs and outputs
+
+use editor_core::io_naming::{
+    IoName, apply_io_names, find_name_conflicts, find_unnamed_io, is_unnamed_io,
+};
+use egui::RichText;
+
+use crate::{
+    grid_db::{Component, GridDB, Id},
+    locale::Locale,
+};
+
+#[derive(Default)]
+pub struct IoNamingWizard {
+    pub is_open: bool,
+    names: Vec<IoName>,
+}
+
+impl IoNamingWizard {
+    /// Opens the table of the unnamed inputs and outputs with the suggested names
+    pub fn open(&mut self, db: &GridDB) {
+        self.names = find_unnamed_io(db);
+        self.is_open = true;
+    }
+
+    /// Shows the table, returns the named components when the user applies the names
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        db: &GridDB,
+        locale: &'static Locale,
+    ) -> Option<Vec<(Id, Component)>> {
+        let mut result = None;
+        let mut is_open = self.is_open;
+        egui::Window::new(locale.io_names)
+            .id("io_names".into())
+            .open(&mut is_open)
+            .default_width(300.0)
+            .show(ctx, |ui| {
+                self.names
+                    .retain(|row| db.get_component(&row.id).is_some_and(is_unnamed_io));
+                if self.names.is_empty() {
+                    ui.label(locale.all_io_named);
+                    return;
+                }
+                ui.label(locale.io_names_hint);
+                let conflicts = find_name_conflicts(db, &self.names);
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("io_names_table")
+                            .num_columns(3)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for (i, row) in self.names.iter_mut().enumerate() {
+                                    // Generated name, kept if the row is left empty:
+                                    let generated = db
+                                        .get_component(&row.id)
+                                        .and_then(|comp| comp.get_instance_name(row.id))
+                                        .unwrap_or_default();
+                                    ui.label(RichText::new(&generated).monospace());
+                                    let edit = ui.add(
+                                        egui::TextEdit::singleline(&mut row.name)
+                                            .hint_text(generated),
+                                    );
+                                    if conflicts.contains(&i) {
+                                        edit.on_hover_text(locale.name_is_used);
+                                        ui.colored_label(ui.visuals().error_fg_color, "⚠");
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+                ui.separator();
+                if ui
+                    .add_enabled(conflicts.is_empty(), egui::Button::new(locale.apply))
+                    .clicked()
+                {
+                    result = Some(apply_io_names(db, &self.names));
+                }
+            });
+        self.is_open = is_open && result.is_none();
+        result
+    }
+}
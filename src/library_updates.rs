@@ -0,0 +1,261 @@
+//! Bulk update of the placed library parts whose library got a newer version
+
+use std::collections::HashSet;
+
+use egui::RichText;
+
+use crate::{
+    component_lib::get_updated_component,
+    grid_db::{Component, GridDB, GridPos, Id},
+    locale::Locale,
+};
+
+/// Placed library part with a newer version available
+pub struct LibraryUpdate {
+    pub id: Id,
+    pub pos: GridPos,
+    pub name: String,
+    pub library: String,
+    pub from: u32,
+    pub to: u32,
+    pub added_ports: Vec<String>,
+    pub removed_ports: Vec<String>,
+    /// Connected pins missing in the new version, their nets will be removed
+    pub lost_connections: Vec<String>,
+    /// New version doesn't fit between the neighbours
+    pub blocked: bool,
+    /// New version placed instead of the instance
+    pub component: Component,
+}
+
+fn get_port_names(comp: &Component) -> Vec<String> {
+    (0..comp.get_connections_number())
+        .filter_map(|id| comp.get_connection_name(id))
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Names of `ports` missing in `other`, ignoring case like the replacement does
+fn get_missing_ports(ports: &[String], other: &[String]) -> Vec<String> {
+    ports
+        .iter()
+        .filter(|name| !other.iter().any(|o| o.eq_ignore_ascii_case(name)))
+        .cloned()
+        .collect()
+}
+
+/// All placed components with updates, sorted by component ID
+pub fn find_library_updates(db: &GridDB) -> Vec<LibraryUpdate> {
+    let mut result = vec![];
+    for id in db.get_component_ids() {
+        let comp = db.get_component(&id).unwrap();
+        let (Some(source), Some(updated)) =
+            (comp.get_library_source(), get_updated_component(comp))
+        else {
+            continue;
+        };
+        let old_ports = get_port_names(comp);
+        let new_ports = get_port_names(&updated);
+        result.push(LibraryUpdate {
+            id,
+            pos: comp.get_position(),
+            name: comp.get_instance_name(id).unwrap_or_default(),
+            library: source.library.to_string(),
+            from: source.version,
+            to: updated.get_library_source().unwrap().version,
+            added_ports: get_missing_ports(&new_ports, &old_ports),
+            removed_ports: get_missing_ports(&old_ports, &new_ports),
+            lost_connections: db.get_lost_connections(id, &updated),
+            blocked: !db.is_available_location(updated.get_position(), updated.get_dimension(), id),
+            component: updated,
+        });
+    }
+    result
+}
+
+/// Action chosen by user in the library updates window
+pub enum LibraryUpdateAction {
+    Focus(GridPos),
+    /// Update the components, all together as one edit
+    Update(Vec<(Id, Component)>),
+}
+
+pub struct LibraryUpdatesWindow {
+    pub is_open: bool,
+    /// Instances opted out of the update
+    skipped: HashSet<Id>,
+}
+
+impl Default for LibraryUpdatesWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LibraryUpdatesWindow {
+    pub fn new() -> Self {
+        Self {
+            is_open: false,
+            skipped: HashSet::new(),
+        }
+    }
+
+    /// Shows the changed parts with the preview of the changes
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        db: &GridDB,
+        locale: &'static Locale,
+    ) -> Option<LibraryUpdateAction> {
+        let mut action = None;
+        let mut is_open = self.is_open;
+        egui::Window::new(locale.library_updates)
+            .id("library_updates".into())
+            .open(&mut is_open)
+            .default_width(300.0)
+            .show(ctx, |ui| {
+                let updates = find_library_updates(db);
+                if updates.is_empty() {
+                    ui.label(locale.no_library_updates);
+                    return;
+                }
+                let error_color = ui.visuals().error_fg_color;
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        for update in &updates {
+                            let mut checked = !update.blocked && !self.skipped.contains(&update.id);
+                            ui.horizontal(|ui| {
+                                let checkbox = ui.add_enabled(
+                                    !update.blocked,
+                                    egui::Checkbox::without_text(&mut checked),
+                                );
+                                if checkbox.changed() && !checked {
+                                    self.skipped.insert(update.id);
+                                } else if checkbox.changed() {
+                                    self.skipped.remove(&update.id);
+                                }
+                                let text = format!(
+                                    "{}: {} v{} → v{}",
+                                    update.name, update.library, update.from, update.to
+                                );
+                                if ui
+                                    .add(
+                                        egui::Label::new(RichText::new(text).monospace())
+                                            .selectable(false)
+                                            .sense(egui::Sense::click()),
+                                    )
+                                    .clicked()
+                                {
+                                    action = Some(LibraryUpdateAction::Focus(update.pos));
+                                }
+                            });
+                            ui.indent(update.id, |ui| {
+                                for port in &update.added_ports {
+                                    ui.label(format!("+ {} {port}", locale.port));
+                                }
+                                for port in &update.removed_ports {
+                                    ui.label(format!("− {} {port}", locale.port));
+                                }
+                                for pin in &update.lost_connections {
+                                    ui.colored_label(
+                                        error_color,
+                                        format!("{}: {pin}", locale.lost_connections),
+                                    );
+                                }
+                                if update.blocked {
+                                    ui.colored_label(error_color, locale.replacement_does_not_fit);
+                                }
+                            });
+                        }
+                    });
+                ui.separator();
+                let chosen: Vec<_> = updates
+                    .into_iter()
+                    .filter(|u| !u.blocked && !self.skipped.contains(&u.id))
+                    .map(|u| (u.id, u.component))
+                    .collect();
+                if ui
+                    .add_enabled(
+                        !chosen.is_empty(),
+                        egui::Button::new(format!("{} ({})", locale.update, chosen.len())),
+                    )
+                    .clicked()
+                {
+                    action = Some(LibraryUpdateAction::Update(chosen));
+                }
+            });
+        self.is_open = is_open;
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find_library_updates;
+    use crate::{
+        component_lib::{UserLibrary, UserLibraryEntry, get_component_lib, install_library},
+        grid_db::{Component, Port, PrimitiveType, Rotation, Unit, grid_pos},
+        testing::{GridDBBuilder, lock_user_libraries},
+    };
+
+    fn library(version: u32, ports: &[(i32, &str)]) -> UserLibrary {
+        UserLibrary {
+            name: "bulk_update_test".into(),
+            version,
+            components: vec![UserLibraryEntry {
+                name: "ZZ_FIFO".into(),
+                component: Component::Unit(Unit {
+                    pos: grid_pos(1, 1),
+                    width: 3,
+                    height: 4,
+                    ports: ports
+                        .iter()
+                        .map(|(offset, name)| Port {
+                            offset: *offset,
+                            align: Rotation::ROT0,
+                            name: (*name).into(),
+                            bundle: None,
+                        })
+                        .collect(),
+                    parameters: vec![],
+                    description: String::new(),
+                    name: Default::default(),
+                    source: None,
+                    schematic: None,
+                }),
+            }],
+        }
+    }
+
+    #[test]
+    fn library_update_preview() {
+        // Other tests don't look for this library, so it stays installed
+        let _lock = lock_user_libraries();
+        install_library(library(1, &[(1, "din"), (2, "push")]));
+        let mut fifo = get_component_lib()
+            .into_iter()
+            .flatten()
+            .find(|entry| entry.name == "ZZ_FIFO")
+            .unwrap()
+            .component;
+        fifo.set_pos(grid_pos(0, 0));
+        let mut b = GridDBBuilder::new();
+        let fifo = b.component(fifo);
+        let input = b.primitive(PrimitiveType::Input, -6, 2, Rotation::ROT0);
+        b.connect((input, 0), (fifo, 1));
+        let db = b.build();
+        assert!(find_library_updates(&db).is_empty());
+
+        // `push` is replaced by `wr_en` on another side:
+        install_library(library(2, &[(1, "din"), (3, "wr_en")]));
+        let updates = find_library_updates(&db);
+        assert_eq!(updates.len(), 1);
+        let update = &updates[0];
+        assert_eq!((update.id, update.from, update.to), (fifo, 1, 2));
+        assert_eq!(update.added_ports, ["wr_en"]);
+        assert_eq!(update.removed_ports, ["push"]);
+        assert_eq!(update.lost_connections, ["push"]);
+        assert!(!update.blocked);
+    }
+}
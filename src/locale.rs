@@ -56,6 +56,319 @@ pub struct Locale {
     pub sync_reset_inverted: &'static str,
     pub async_reset_inverted: &'static str,
     pub enable_signal: &'static str,
+
+    // Replace component:
+    pub replace_with: &'static str,
+    pub lost_connections: &'static str,
+    pub replacement_does_not_fit: &'static str,
+    pub cancel: &'static str,
+    pub discard: &'static str,
+    pub save_changes_before_closing: &'static str,
+
+    // Problems:
+    pub problems: &'static str,
+    pub unconnected_pin: &'static str,
+    pub unconnected_only: &'static str,
+    pub no_problems: &'static str,
+    pub highlight_unconnected: &'static str,
+
+    // Net style:
+    pub net_style: &'static str,
+    pub junction_size: &'static str,
+    pub square_corners: &'static str,
+    pub round_corners: &'static str,
+    pub net_arrows: &'static str,
+    pub bundle_nets: &'static str,
+
+    // Status bar:
+    pub net: &'static str,
+    pub net_length: &'static str,
+    pub manhattan_distance: &'static str,
+    pub net_length_heatmap: &'static str,
+    pub save_as: &'static str,
+    pub export_to_png: &'static str,
+    pub ongoing_export_to_png: &'static str,
+    pub png_capture_error: &'static str,
+    pub file_no_project_data: &'static str,
+    pub remove_library: &'static str,
+    pub library_load_error: &'static str,
+    pub compact_project: &'static str,
+
+    // Simulation:
+    pub simulation: &'static str,
+    pub sim_run: &'static str,
+    pub sim_pause: &'static str,
+    pub sim_step: &'static str,
+    pub run_until_stable: &'static str,
+    pub sim_reset: &'static str,
+    pub steps_per_second: &'static str,
+    pub real_time_clock: &'static str,
+    pub sim_steps: &'static str,
+    pub switches_number: &'static str,
+    pub pattern: &'static str,
+    pub outputs_number: &'static str,
+    pub seed: &'static str,
+    pub condition: &'static str,
+    pub assertion_failed: &'static str,
+    pub save_sim_state: &'static str,
+    pub load_sim_state: &'static str,
+    pub sim_state_load_error: &'static str,
+    pub parameters: &'static str,
+    pub simulation_values: &'static str,
+    pub net_overlap: &'static str,
+    pub net_through_component: &'static str,
+    pub reroute: &'static str,
+    pub reroute_failed: &'static str,
+    pub description: &'static str,
+    pub export_datasheets: &'static str,
+    pub project_variables: &'static str,
+    pub project_variables_hint: &'static str,
+
+    // Find and replace:
+    pub edit: &'static str,
+    pub replace: &'static str,
+    pub find: &'static str,
+    pub regular_expression: &'static str,
+    pub hierarchical_name: &'static str,
+    pub replace_all: &'static str,
+    pub port: &'static str,
+    pub text: &'static str,
+
+    // Batch rename:
+    pub instance_name: &'static str,
+    pub rename_instances: &'static str,
+    pub name_pattern: &'static str,
+    pub start_number: &'static str,
+    pub step: &'static str,
+    pub apply: &'static str,
+    pub select_instances_hint: &'static str,
+    pub pattern_without_counter: &'static str,
+    pub name_is_used: &'static str,
+    pub nets: &'static str,
+    pub selection_filter: &'static str,
+    pub menu_bar: &'static str,
+    pub menu_auto: &'static str,
+    pub menu_full: &'static str,
+    pub menu_compact: &'static str,
+    pub ok: &'static str,
+    pub layout: &'static str,
+    pub layout_desktop: &'static str,
+    pub layout_touch: &'static str,
+    pub undo: &'static str,
+    pub redo: &'static str,
+    pub delete: &'static str,
+    pub zoom_in: &'static str,
+    pub zoom_out: &'static str,
+
+    // Net classes:
+    pub net_classes: &'static str,
+    pub net_class: &'static str,
+    pub no_net_class: &'static str,
+    pub name: &'static str,
+    pub color: &'static str,
+    pub line_width: &'static str,
+    pub severity: &'static str,
+    pub severity_ignore: &'static str,
+    pub severity_warning: &'static str,
+    pub severity_error: &'static str,
+
+    // Off-sheet connectors:
+    pub signal_name: &'static str,
+    pub unmatched_connector: &'static str,
+
+    // Library parts:
+    pub update_available: &'static str,
+    pub update: &'static str,
+    pub keep: &'static str,
+    pub library_updates: &'static str,
+    pub no_library_updates: &'static str,
+
+    // Keep-out regions:
+    pub keep_out_regions: &'static str,
+    pub keep_out_hint: &'static str,
+    pub net_through_keep_out: &'static str,
+
+    // Origin:
+    pub origin: &'static str,
+    pub set_origin: &'static str,
+    pub reset_origin: &'static str,
+    pub pick_origin_hint: &'static str,
+
+    // Auto-placement:
+    pub auto_place: &'static str,
+    pub import: &'static str,
+    pub place_fragment_hint: &'static str,
+    pub zoom: &'static str,
+    pub zoom_to_fit: &'static str,
+    pub mouse_wheel: &'static str,
+    pub wheel_scrolls: &'static str,
+    pub wheel_zooms: &'static str,
+    pub save_view: &'static str,
+    pub project_compacted: &'static str,
+    pub orphaned_nets: &'static str,
+    pub index_entries: &'static str,
+    pub duplicate_net_classes: &'static str,
+    pub crash_report: &'static str,
+    pub crash_report_message: &'static str,
+    pub export_bug_report: &'static str,
+    pub restore_project: &'static str,
+    pub dangling_net: &'static str,
+    pub missing_component: &'static str,
+    pub usage_statistics: &'static str,
+    pub collect_statistics: &'static str,
+    pub statistics_are_local: &'static str,
+    pub time_in_app: &'static str,
+    pub sessions: &'static str,
+    pub largest_design: &'static str,
+    pub copy: &'static str,
+    pub reset: &'static str,
+    pub activity_heatmap: &'static str,
+
+    // Truth table:
+    pub truth_table: &'static str,
+    pub inputs: &'static str,
+    pub outputs: &'static str,
+    pub evaluate: &'static str,
+    pub export_csv: &'static str,
+    pub too_many_inputs: &'static str,
+
+    // Hierarchy:
+    pub extract_to_unit: &'static str,
+    pub library_entry_name: &'static str,
+    pub extract: &'static str,
+    pub unit_does_not_fit: &'static str,
+    pub flatten_unit: &'static str,
+    pub unit_content_does_not_fit: &'static str,
+    pub connect_same_named_ports: &'static str,
+    pub connected_ports: &'static str,
+
+    // Interfaces:
+    pub interfaces: &'static str,
+    pub interface_ports: &'static str,
+    pub is_input: &'static str,
+    pub width: &'static str,
+    pub mirrored: &'static str,
+    pub add_to_selected_units: &'static str,
+    pub interface_does_not_fit: &'static str,
+
+    // Export scope:
+    pub export_scope: &'static str,
+    pub whole_design: &'static str,
+    pub selection: &'static str,
+    pub viewport: &'static str,
+    pub nothing_to_export: &'static str,
+
+    // Export size:
+    pub pixels: &'static str,
+    pub millimeters: &'static str,
+    pub height: &'static str,
+    pub keep_aspect_ratio: &'static str,
+    pub dpi: &'static str,
+    pub image_size: &'static str,
+    pub image_too_big: &'static str,
+
+    // SVG export of both themes:
+    pub both_themes: &'static str,
+    pub both_themes_hint: &'static str,
+
+    // Layers of the exported components:
+    pub include_layers: &'static str,
+    pub notes: &'static str,
+    pub assertions: &'static str,
+
+    // Default parameters of the new components:
+    pub component_defaults: &'static str,
+
+    // Bulk customization:
+    pub customize_selected: &'static str,
+    pub selected_components: &'static str,
+
+    // Naming inputs and outputs:
+    pub io_names: &'static str,
+    pub io_names_hint: &'static str,
+    pub all_io_named: &'static str,
+
+    // Templates:
+    pub templates: &'static str,
+    pub place: &'static str,
+    pub add_parameter: &'static str,
+    pub stages_count: &'static str,
+    pub save_selection_as_template: &'static str,
+
+    // Macros:
+    pub macros: &'static str,
+    pub start_recording: &'static str,
+    pub stop_recording: &'static str,
+    pub macro_name: &'static str,
+    pub macro_default_name: &'static str,
+    pub macro_is_empty: &'static str,
+    pub macro_playback_hint: &'static str,
+
+    // Recent projects:
+    pub open_recent: &'static str,
+    pub save_thumbnails: &'static str,
+    pub new_tab: &'static str,
+
+    // Clipboard history:
+    pub paste_from_history: &'static str,
+    pub clipboard_history_is_empty: &'static str,
+    pub snap_annotations: &'static str,
+
+    // Unit ports:
+    pub sync_ports: &'static str,
+    pub missing_unit_port: &'static str,
+    pub extra_unit_port: &'static str,
+    pub unit_port_side: &'static str,
+    pub synced_unit_does_not_fit: &'static str,
+    pub generate_symbol: &'static str,
+    pub generated_symbol_does_not_fit: &'static str,
+    pub net_colors: &'static str,
+    pub net_class_colors: &'static str,
+    pub uniform_net_color: &'static str,
+    pub net_legend: &'static str,
+
+    // Grid of the exported image:
+    pub export_grid: &'static str,
+    pub grid_opacity: &'static str,
+
+    // Progressive rendering:
+    pub rendering: &'static str,
+
+    // Profiler:
+    pub profiler: &'static str,
+    pub frame_time: &'static str,
+    pub mesh_cache: &'static str,
+    pub cache_hits: &'static str,
+    pub cache_misses: &'static str,
+    pub cache_evictions: &'static str,
+    pub cache_budget: &'static str,
+    pub geometry_cache: &'static str,
+    pub cache_entries: &'static str,
+
+    // Adaptive LOD:
+    pub adaptive_lod: &'static str,
+    pub adaptive_lod_hint: &'static str,
+    pub max_gate_inputs: &'static str,
+
+    // What's new:
+    pub whats_new: &'static str,
+    pub version: &'static str,
+    pub newer_version_available: &'static str,
+    pub check_updates: &'static str,
+    pub check_updates_hint: &'static str,
+
+    // Workspace:
+    pub workspace: &'static str,
+    pub add_folder: &'static str,
+    pub remove_folder: &'static str,
+    pub refresh: &'static str,
+    pub no_workspace_folders: &'static str,
+    pub no_projects_in_folder: &'static str,
+    pub rename: &'static str,
+    pub duplicate: &'static str,
+    pub rename_failed: &'static str,
+    pub duplicate_failed: &'static str,
+    pub magnifier_hint: &'static str,
 }
 
 pub const RU_LOCALE: Locale = Locale {
@@ -102,6 +415,251 @@ pub const RU_LOCALE: Locale = Locale {
     enable_signal: "Имеет вход сигнала включения (enable)",
     preview: "Предпросмотр",
     type_: "Тип",
+    replace_with: "Заменить на…",
+    lost_connections: "Не удалось сохранить подключения:",
+    replacement_does_not_fit: "Новый компонент не помещается на месте старого",
+    cancel: "Отмена",
+    discard: "Не сохранять",
+    save_changes_before_closing: "Сохранить изменения проекта перед закрытием?",
+    problems: "Проблемы",
+    unconnected_pin: "Неподключенный вывод",
+    unconnected_only: "Только неподключенные выводы",
+    no_problems: "Проблем не найдено",
+    highlight_unconnected: "Подсвечивать неподключенные выводы",
+    net_style: "Стиль цепей",
+    junction_size: "Размер точки соединения",
+    square_corners: "Прямые углы",
+    round_corners: "Скругленные углы",
+    net_arrows: "Стрелки на концах цепей",
+    bundle_nets: "Объединять параллельные цепи в шину",
+    net: "Цепь",
+    net_length: "длина",
+    manhattan_distance: "манхэттенское расстояние",
+    net_length_heatmap: "Тепловая карта длины цепей",
+    save_as: "Сохранить как...",
+    export_to_png: "Экспорт в PNG",
+    ongoing_export_to_png: "Идет экспорт в PNG...",
+    png_capture_error: "Не удалось получить снимок поля",
+    file_no_project_data: "SVG файл не содержит данных проекта",
+    remove_library: "Удалить библиотеку",
+    library_load_error: "Ошибка загрузки библиотеки",
+    compact_project: "Сжать проект",
+    simulation: "Симуляция",
+    sim_run: "Запуск",
+    sim_pause: "Пауза",
+    sim_step: "Шаг",
+    run_until_stable: "До установления",
+    sim_reset: "Сброс",
+    steps_per_second: "шагов/с",
+    real_time_clock: "Тактирование в реальном времени",
+    sim_steps: "Шагов",
+    switches_number: "Количество переключателей",
+    pattern: "Шаблон",
+    outputs_number: "Количество выходов",
+    seed: "Начальное значение",
+    condition: "Условие",
+    assertion_failed: "Нарушено утверждение",
+    save_sim_state: "Сохранить состояние",
+    load_sim_state: "Загрузить состояние",
+    sim_state_load_error: "Не удалось загрузить состояние симуляции",
+    parameters: "Параметры",
+    simulation_values: "Значения симуляции",
+    net_overlap: "Наложение цепей",
+    net_through_component: "Цепь через компонент",
+    reroute: "Перепроложить",
+    reroute_failed: "Не найден свободный путь для цепи",
+    description: "Описание",
+    export_datasheets: "Экспорт описаний блоков",
+    project_variables: "Переменные проекта",
+    project_variables_hint: "Используются в текстовых полях как {{имя}}, также доступны {{project}} и {{date}}",
+    edit: "Правка",
+    replace: "Заменить",
+    find: "Найти",
+    regular_expression: "Регулярное выражение",
+    hierarchical_name: "Иерархическое имя",
+    replace_all: "Заменить все",
+    port: "Порт",
+    text: "Текст",
+    instance_name: "Имя экземпляра",
+    rename_instances: "Переименовать экземпляры",
+    name_pattern: "Шаблон имени",
+    start_number: "Начальный номер",
+    step: "Шаг",
+    apply: "Применить",
+    select_instances_hint: "Выделите компоненты с помощью Shift+клик",
+    pattern_without_counter: "Шаблон должен содержать счетчик, например %02d",
+    name_is_used: "Имя уже используется",
+    nets: "Цепи",
+    selection_filter: "Фильтр выделения",
+    menu_bar: "Строка меню",
+    menu_auto: "Автоматически",
+    menu_full: "Полная",
+    menu_compact: "Свернутая",
+    ok: "ОК",
+    layout: "Раскладка",
+    layout_desktop: "Настольная",
+    layout_touch: "Сенсорная",
+    undo: "Отменить",
+    redo: "Повторить",
+    delete: "Удалить",
+    zoom_in: "Приблизить",
+    zoom_out: "Отдалить",
+    net_classes: "Классы цепей",
+    net_class: "Класс цепи",
+    no_net_class: "Без класса",
+    name: "Имя",
+    color: "Цвет",
+    line_width: "Толщина",
+    severity: "Важность",
+    severity_ignore: "Игнорировать",
+    severity_warning: "Предупреждение",
+    severity_error: "Ошибка",
+    signal_name: "Сигнал",
+    unmatched_connector: "Соединитель без пары",
+    update_available: "Доступно обновление",
+    update: "Обновить",
+    keep: "Оставить",
+    library_updates: "Обновления библиотек",
+    no_library_updates: "Все компоненты библиотек актуальны",
+    keep_out_regions: "Запретные области",
+    keep_out_hint: "Перетащите, чтобы зарезервировать область, щелкните по области, чтобы удалить ее, Esc — завершить",
+    net_through_keep_out: "Цепь проходит через запретную область",
+    origin: "Начало координат",
+    set_origin: "Задать начало координат",
+    reset_origin: "Сбросить начало координат",
+    pick_origin_hint: "Щелкните по клетке, чтобы сделать ее началом координат, Esc — отмена",
+    auto_place: "Сдвигать добавляемые компоненты на свободное место",
+    import: "Импорт",
+    place_fragment_hint: "Щелчок — разместить, Esc — отмена",
+    zoom: "Масштаб",
+    zoom_to_fit: "Показать всё",
+    mouse_wheel: "Колесо мыши",
+    wheel_scrolls: "Прокрутка (Ctrl — масштаб)",
+    wheel_zooms: "Масштаб",
+    save_view: "Сохранять вид в проекте",
+    project_compacted: "Проект сжат",
+    orphaned_nets: "потерянные цепи",
+    index_entries: "записи индекса",
+    duplicate_net_classes: "повторные классы цепей",
+    crash_report: "Сбой редактора",
+    crash_report_message: "Редактор неожиданно завершился в прошлый раз. Отчет об ошибке можно сохранить и приложить к сообщению об ошибке.",
+    export_bug_report: "Сохранить отчет",
+    restore_project: "Восстановить проект",
+    dangling_net: "Цепь к отсутствующему компоненту удалена",
+    missing_component: "Ссылка на отсутствующий компонент",
+    usage_statistics: "Статистика использования",
+    collect_statistics: "Собирать статистику",
+    statistics_are_local: "Статистика хранится только на этом устройстве и никуда не отправляется",
+    time_in_app: "Время работы",
+    sessions: "Запуски",
+    largest_design: "Наибольший проект, компонентов",
+    copy: "Копировать",
+    reset: "Сбросить",
+    activity_heatmap: "Активность цепей",
+    truth_table: "Таблица истинности",
+    inputs: "Входы",
+    outputs: "Выходы",
+    evaluate: "Вычислить",
+    export_csv: "Экспорт CSV",
+    too_many_inputs: "Слишком много входов, максимум",
+    extract_to_unit: "Выделить в блок",
+    library_entry_name: "Имя в библиотеке",
+    extract: "Выделить",
+    unit_does_not_fit: "Блок не помещается на месте выделения",
+    flatten_unit: "Развернуть блок",
+    unit_content_does_not_fit: "Содержимое блока не помещается на его месте",
+    connect_same_named_ports: "Соединить одноимённые порты",
+    connected_ports: "Соединено портов",
+    interfaces: "Интерфейсы",
+    interface_ports: "Порты интерфейса",
+    is_input: "Вход",
+    width: "Ширина",
+    mirrored: "Зеркальный (ведомый)",
+    add_to_selected_units: "Добавить выбранным модулям",
+    interface_does_not_fit: "Модуль с интерфейсом не помещается",
+    export_scope: "Экспортировать",
+    whole_design: "Всю схему",
+    selection: "Выделение",
+    viewport: "Видимую область",
+    nothing_to_export: "Нечего экспортировать",
+    pixels: "Пиксели",
+    millimeters: "Миллиметры",
+    height: "Высота",
+    keep_aspect_ratio: "Сохранять пропорции",
+    dpi: "DPI",
+    image_size: "Размер изображения",
+    image_too_big: "Изображение слишком большое",
+    both_themes: "Обе темы",
+    both_themes_hint: "Экспорт в файлы name_light.svg и name_dark.svg",
+    include_layers: "Включить",
+    notes: "Заметки",
+    assertions: "Утверждения",
+    component_defaults: "Параметры новых компонентов",
+    customize_selected: "Настроить выбранные",
+    selected_components: "Выбрано компонентов",
+    io_names: "Имена входов и выходов",
+    io_names_hint: "Имена попадут в нетлист, пустые строки оставят сгенерированные имена",
+    all_io_named: "Все входы и выходы названы",
+    templates: "Шаблоны",
+    place: "Разместить",
+    add_parameter: "Добавить параметр",
+    stages_count: "Число ступеней",
+    save_selection_as_template: "Сохранить выделение как шаблон",
+    macros: "Макросы",
+    start_recording: "Начать запись",
+    stop_recording: "Остановить запись",
+    macro_name: "Имя макроса",
+    macro_default_name: "Макрос",
+    macro_is_empty: "Макрос пуст: правки не записаны",
+    macro_playback_hint: "Щелчок по полю повторяет макрос от этой клетки",
+    open_recent: "Открыть недавние",
+    save_thumbnails: "Сохранять миниатюры проектов",
+    new_tab: "Новая вкладка",
+    paste_from_history: "Вставить из истории",
+    clipboard_history_is_empty: "Скопированных фрагментов нет",
+    snap_annotations: "Прикреплять надписи к компонентам",
+    sync_ports: "Синхронизировать порты",
+    missing_unit_port: "Нет порта для входа или выхода схемы блока",
+    extra_unit_port: "Порт блока отсутствует в его схеме",
+    unit_port_side: "Порт блока на стороне другого направления",
+    synced_unit_does_not_fit: "Блок с новыми портами не помещается",
+    generate_symbol: "Сгенерировать символ",
+    generated_symbol_does_not_fit: "Сгенерированный символ не помещается",
+    net_colors: "Цвета цепей",
+    net_class_colors: "Цвета классов цепей",
+    uniform_net_color: "Цвет темы",
+    net_legend: "Легенда",
+    export_grid: "Сетка",
+    grid_opacity: "Прозрачность сетки",
+    rendering: "Отрисовка…",
+    profiler: "Профилировщик",
+    frame_time: "Время кадра",
+    mesh_cache: "Кэш сеток символов",
+    cache_hits: "Попадания",
+    cache_misses: "Промахи",
+    cache_evictions: "Вытеснения",
+    cache_budget: "Размер кэша сеток",
+    geometry_cache: "Кэш геометрии символов",
+    cache_entries: "Записи",
+    adaptive_lod: "Адаптивная детализация",
+    adaptive_lod_hint: "Детализация снижается, когда отрисовка медленная",
+    max_gate_inputs: "Макс. число входов",
+    whats_new: "Что нового",
+    version: "Версия",
+    newer_version_available: "Доступна новая версия",
+    check_updates: "Проверять обновления",
+    check_updates_hint: "При запуске запрашивается последний релиз на GitHub",
+    workspace: "Рабочая область",
+    add_folder: "Добавить папку",
+    remove_folder: "Убрать папку из рабочей области",
+    refresh: "Обновить",
+    no_workspace_folders: "Добавьте папку с проектами",
+    no_projects_in_folder: "В папке нет проектов",
+    rename: "Переименовать",
+    duplicate: "Дублировать",
+    rename_failed: "Не удалось переименовать проект",
+    duplicate_failed: "Не удалось дублировать проект",
+    magnifier_hint: "Удерживайте пробел, чтобы увеличить область под курсором",
 };
 
 pub const EN_LOCALE: Locale = Locale {
@@ -148,6 +706,251 @@ pub const EN_LOCALE: Locale = Locale {
     enable_signal: "Enable signal",
     preview: "Preview",
     type_: "Type",
+    replace_with: "Replace with…",
+    lost_connections: "Connections that could not be preserved:",
+    replacement_does_not_fit: "The new component does not fit in place of the old one",
+    cancel: "Cancel",
+    discard: "Discard",
+    save_changes_before_closing: "Save changes to the project before closing?",
+    problems: "Problems",
+    unconnected_pin: "Unconnected pin",
+    unconnected_only: "Unconnected pins only",
+    no_problems: "No problems found",
+    highlight_unconnected: "Highlight unconnected pins",
+    net_style: "Net style",
+    junction_size: "Junction size",
+    square_corners: "Square corners",
+    round_corners: "Rounded corners",
+    net_arrows: "Endpoint arrows",
+    bundle_nets: "Bundle parallel nets",
+    net: "Net",
+    net_length: "length",
+    manhattan_distance: "Manhattan distance",
+    net_length_heatmap: "Net length heat map",
+    save_as: "Save as...",
+    export_to_png: "Export to PNG",
+    ongoing_export_to_png: "Exporting to png...",
+    png_capture_error: "Failed to capture the field image",
+    file_no_project_data: "SVG file contains no project data",
+    remove_library: "Remove library",
+    library_load_error: "Library load error",
+    compact_project: "Compact project",
+    simulation: "Simulation",
+    sim_run: "Run",
+    sim_pause: "Pause",
+    sim_step: "Step",
+    run_until_stable: "Run until stable",
+    sim_reset: "Reset",
+    steps_per_second: "steps/s",
+    real_time_clock: "Real-time clock",
+    sim_steps: "Steps",
+    switches_number: "Number of switches",
+    pattern: "Pattern",
+    outputs_number: "Number of outputs",
+    seed: "Seed",
+    condition: "Condition",
+    assertion_failed: "Assertion failed",
+    save_sim_state: "Save state",
+    load_sim_state: "Load state",
+    sim_state_load_error: "Cannot load the simulation state",
+    parameters: "Parameters",
+    simulation_values: "Simulation values",
+    net_overlap: "Overlapping nets",
+    net_through_component: "Net through component",
+    reroute: "Reroute",
+    reroute_failed: "No free path for the net was found",
+    description: "Description",
+    export_datasheets: "Export unit datasheets",
+    project_variables: "Project variables",
+    project_variables_hint: "Used in text fields as {{name}}, {{project}} and {{date}} are also available",
+    edit: "Edit",
+    replace: "Replace",
+    find: "Find",
+    regular_expression: "Regular expression",
+    hierarchical_name: "Hierarchical name",
+    replace_all: "Replace all",
+    port: "Port",
+    text: "Text",
+    instance_name: "Instance name",
+    rename_instances: "Rename instances",
+    name_pattern: "Name pattern",
+    start_number: "Start number",
+    step: "Step",
+    apply: "Apply",
+    select_instances_hint: "Select components with Shift+click",
+    pattern_without_counter: "Pattern must contain a counter like %02d",
+    name_is_used: "Name is already used",
+    nets: "Nets",
+    selection_filter: "Selection filter",
+    menu_bar: "Menu bar",
+    menu_auto: "Automatic",
+    menu_full: "Full",
+    menu_compact: "Collapsed",
+    ok: "OK",
+    layout: "Layout",
+    layout_desktop: "Desktop",
+    layout_touch: "Touch",
+    undo: "Undo",
+    redo: "Redo",
+    delete: "Delete",
+    zoom_in: "Zoom in",
+    zoom_out: "Zoom out",
+    net_classes: "Net classes",
+    net_class: "Net class",
+    no_net_class: "No class",
+    name: "Name",
+    color: "Color",
+    line_width: "Width",
+    severity: "Severity",
+    severity_ignore: "Ignore",
+    severity_warning: "Warning",
+    severity_error: "Error",
+    signal_name: "Signal",
+    unmatched_connector: "Connector without counterpart",
+    update_available: "Update available",
+    update: "Update",
+    keep: "Keep",
+    library_updates: "Library updates",
+    no_library_updates: "All library parts are up to date",
+    keep_out_regions: "Keep-out regions",
+    keep_out_hint: "Drag to reserve an area, click a region to remove it, Esc to finish",
+    net_through_keep_out: "Net through keep-out region",
+    origin: "Origin",
+    set_origin: "Set origin",
+    reset_origin: "Reset origin",
+    pick_origin_hint: "Click a cell to make it the origin, Esc to cancel",
+    auto_place: "Move dropped components to free space",
+    import: "Import",
+    place_fragment_hint: "Click to place, Esc to cancel",
+    zoom: "Zoom",
+    zoom_to_fit: "Fit",
+    mouse_wheel: "Mouse wheel",
+    wheel_scrolls: "Scroll (Ctrl to zoom)",
+    wheel_zooms: "Zoom",
+    save_view: "Save view in project",
+    project_compacted: "Project compacted",
+    orphaned_nets: "orphaned nets",
+    index_entries: "index entries",
+    duplicate_net_classes: "duplicate net classes",
+    crash_report: "Editor crashed",
+    crash_report_message: "The editor stopped unexpectedly last time. Save the report and attach it to the bug report.",
+    export_bug_report: "Export bug report",
+    restore_project: "Restore project",
+    dangling_net: "Net to a missing component was dropped",
+    missing_component: "Reference to a missing component",
+    usage_statistics: "Usage statistics",
+    collect_statistics: "Collect statistics",
+    statistics_are_local: "Statistics are kept only on this device and never sent anywhere",
+    time_in_app: "Time in app",
+    sessions: "Sessions",
+    largest_design: "Largest design, components",
+    copy: "Copy",
+    reset: "Reset",
+    activity_heatmap: "Activity heatmap",
+    truth_table: "Truth table",
+    inputs: "Inputs",
+    outputs: "Outputs",
+    evaluate: "Evaluate",
+    export_csv: "Export CSV",
+    too_many_inputs: "Too many inputs, the maximum is",
+    extract_to_unit: "Extract to unit",
+    library_entry_name: "Library entry name",
+    extract: "Extract",
+    unit_does_not_fit: "The unit doesn't fit in place of the selection",
+    flatten_unit: "Flatten unit",
+    unit_content_does_not_fit: "The unit content doesn't fit in its place",
+    connect_same_named_ports: "Connect same-named ports",
+    connected_ports: "Connected ports",
+    interfaces: "Interfaces",
+    interface_ports: "Interface ports",
+    is_input: "Input",
+    width: "Width",
+    mirrored: "Mirrored (slave)",
+    add_to_selected_units: "Add to selected units",
+    interface_does_not_fit: "Unit with the interface does not fit",
+    export_scope: "Include",
+    whole_design: "Whole design",
+    selection: "Selection",
+    viewport: "Viewport",
+    nothing_to_export: "Nothing to export",
+    pixels: "Pixels",
+    millimeters: "Millimeters",
+    height: "Height",
+    keep_aspect_ratio: "Keep aspect ratio",
+    dpi: "DPI",
+    image_size: "Image size",
+    image_too_big: "The image is too big",
+    both_themes: "Both themes",
+    both_themes_hint: "Export to name_light.svg and name_dark.svg",
+    include_layers: "Include",
+    notes: "Notes",
+    assertions: "Assertions",
+    component_defaults: "Component defaults",
+    customize_selected: "Customize selected",
+    selected_components: "Selected components",
+    io_names: "Input/output names",
+    io_names_hint: "The names go into the netlist, empty rows keep the generated names",
+    all_io_named: "All inputs and outputs are named",
+    templates: "Templates",
+    place: "Place",
+    add_parameter: "Add parameter",
+    stages_count: "Number of stages",
+    save_selection_as_template: "Save selection as template",
+    macros: "Macros",
+    start_recording: "Start recording",
+    stop_recording: "Stop recording",
+    macro_name: "Macro name",
+    macro_default_name: "Macro",
+    macro_is_empty: "The macro is empty: no edits were recorded",
+    macro_playback_hint: "Clicks on the field replay the macro from the clicked cell",
+    open_recent: "Open recent",
+    save_thumbnails: "Save project thumbnails",
+    new_tab: "New tab",
+    paste_from_history: "Paste from history",
+    clipboard_history_is_empty: "Nothing has been copied yet",
+    snap_annotations: "Attach text to components",
+    sync_ports: "Sync ports",
+    missing_unit_port: "No port for an input or output of the unit schematic",
+    extra_unit_port: "Unit port is missing in its schematic",
+    unit_port_side: "Unit port is on the side of the other direction",
+    synced_unit_does_not_fit: "The unit with the synced ports doesn't fit",
+    generate_symbol: "Generate symbol",
+    generated_symbol_does_not_fit: "The generated symbol doesn't fit",
+    net_colors: "Net colors",
+    net_class_colors: "Net class colors",
+    uniform_net_color: "Uniform theme color",
+    net_legend: "Legend",
+    export_grid: "Grid",
+    grid_opacity: "Grid opacity",
+    rendering: "Rendering…",
+    profiler: "Profiler",
+    frame_time: "Frame time",
+    mesh_cache: "Symbol mesh cache",
+    cache_hits: "Hits",
+    cache_misses: "Misses",
+    cache_evictions: "Evictions",
+    cache_budget: "Mesh cache budget",
+    geometry_cache: "Symbol geometry cache",
+    cache_entries: "Entries",
+    adaptive_lod: "Adaptive detail level",
+    adaptive_lod_hint: "Detail is reduced while the drawing is slow",
+    max_gate_inputs: "Max gate inputs",
+    whats_new: "What's New",
+    version: "Version",
+    newer_version_available: "New version available",
+    check_updates: "Check for updates",
+    check_updates_hint: "The latest release is requested from GitHub on start",
+    workspace: "Workspace",
+    add_folder: "Add folder",
+    remove_folder: "Remove folder from workspace",
+    refresh: "Refresh",
+    no_workspace_folders: "Add a folder with projects",
+    no_projects_in_folder: "No projects in the folder",
+    rename: "Rename",
+    duplicate: "Duplicate",
+    rename_failed: "Failed to rename the project",
+    duplicate_failed: "Failed to duplicate the project",
+    magnifier_hint: "Hold Space to magnify the area under the cursor",
 };
 
 #[cfg(feature = "unifont")]
@@ -195,6 +998,251 @@ pub const ZH_LOCALE: Locale = Locale {
     enable_signal: "使能信号",
     preview: "预览",
     type_: "类型",
+    replace_with: "替换为…",
+    lost_connections: "无法保留的连接:",
+    replacement_does_not_fit: "新组件无法放置在原组件的位置",
+    cancel: "取消",
+    discard: "不保存",
+    save_changes_before_closing: "关闭前保存项目的更改吗？",
+    problems: "问题",
+    unconnected_pin: "未连接的引脚",
+    unconnected_only: "仅显示未连接的引脚",
+    no_problems: "未发现问题",
+    highlight_unconnected: "高亮未连接的引脚",
+    net_style: "连线样式",
+    junction_size: "连接点大小",
+    square_corners: "直角",
+    round_corners: "圆角",
+    net_arrows: "端点箭头",
+    bundle_nets: "将平行连线绘制为总线",
+    net: "连线",
+    net_length: "长度",
+    manhattan_distance: "曼哈顿距离",
+    net_length_heatmap: "连线长度热力图",
+    save_as: "另存为...",
+    export_to_png: "导出为PNG",
+    ongoing_export_to_png: "正在导出为PNG...",
+    png_capture_error: "无法截取画布图像",
+    file_no_project_data: "SVG文件不包含项目数据",
+    remove_library: "删除库",
+    library_load_error: "库加载错误",
+    compact_project: "压缩项目",
+    simulation: "仿真",
+    sim_run: "运行",
+    sim_pause: "暂停",
+    sim_step: "单步",
+    run_until_stable: "运行至稳定",
+    sim_reset: "复位",
+    steps_per_second: "步/秒",
+    real_time_clock: "实时时钟",
+    sim_steps: "步数",
+    switches_number: "开关数量",
+    pattern: "模式",
+    outputs_number: "输出数量",
+    seed: "种子",
+    condition: "条件",
+    assertion_failed: "断言失败",
+    save_sim_state: "保存状态",
+    load_sim_state: "加载状态",
+    sim_state_load_error: "无法加载仿真状态",
+    parameters: "参数",
+    simulation_values: "仿真值",
+    net_overlap: "网络重叠",
+    net_through_component: "网络穿过元件",
+    reroute: "重新布线",
+    reroute_failed: "未找到网络的空闲路径",
+    description: "描述",
+    export_datasheets: "导出模块说明",
+    project_variables: "项目变量",
+    project_variables_hint: "在文本框中以 {{名称}} 使用，另外可用 {{project}} 和 {{date}}",
+    edit: "编辑",
+    replace: "替换",
+    find: "查找",
+    regular_expression: "正则表达式",
+    hierarchical_name: "层次化名称",
+    replace_all: "全部替换",
+    port: "端口",
+    text: "文本",
+    instance_name: "实例名称",
+    rename_instances: "重命名实例",
+    name_pattern: "名称模板",
+    start_number: "起始编号",
+    step: "步长",
+    apply: "应用",
+    select_instances_hint: "按住 Shift 单击以选择组件",
+    pattern_without_counter: "模板必须包含计数器，例如 %02d",
+    name_is_used: "名称已被使用",
+    nets: "连线",
+    selection_filter: "选择过滤器",
+    menu_bar: "菜单栏",
+    menu_auto: "自动",
+    menu_full: "完整",
+    menu_compact: "折叠",
+    ok: "确定",
+    layout: "布局",
+    layout_desktop: "桌面",
+    layout_touch: "触控",
+    undo: "撤销",
+    redo: "重做",
+    delete: "删除",
+    zoom_in: "放大",
+    zoom_out: "缩小",
+    net_classes: "网络类别",
+    net_class: "网络类别",
+    no_net_class: "无类别",
+    name: "名称",
+    color: "颜色",
+    line_width: "线宽",
+    severity: "严重性",
+    severity_ignore: "忽略",
+    severity_warning: "警告",
+    severity_error: "错误",
+    signal_name: "信号",
+    unmatched_connector: "连接器缺少对应项",
+    update_available: "有可用更新",
+    update: "更新",
+    keep: "保留",
+    library_updates: "库更新",
+    no_library_updates: "所有库元件均为最新",
+    keep_out_regions: "禁布区",
+    keep_out_hint: "拖动以保留区域，单击区域将其删除，按 Esc 结束",
+    net_through_keep_out: "网络穿过禁布区",
+    origin: "原点",
+    set_origin: "设置原点",
+    reset_origin: "重置原点",
+    pick_origin_hint: "单击单元格将其设为原点，按 Esc 取消",
+    auto_place: "将放置的元件移到空闲位置",
+    import: "导入",
+    place_fragment_hint: "单击放置，Esc 取消",
+    zoom: "缩放",
+    zoom_to_fit: "适应窗口",
+    mouse_wheel: "鼠标滚轮",
+    wheel_scrolls: "滚动（Ctrl 缩放）",
+    wheel_zooms: "缩放",
+    save_view: "在项目中保存视图",
+    project_compacted: "项目已压缩",
+    orphaned_nets: "孤立连线",
+    index_entries: "索引条目",
+    duplicate_net_classes: "重复的连线类别",
+    crash_report: "编辑器崩溃",
+    crash_report_message: "编辑器上次意外退出。可以保存报告并附加到错误报告中。",
+    export_bug_report: "导出错误报告",
+    restore_project: "恢复项目",
+    dangling_net: "已删除连接到缺失组件的连线",
+    missing_component: "引用了缺失的组件",
+    usage_statistics: "使用统计",
+    collect_statistics: "收集统计",
+    statistics_are_local: "统计信息仅保存在本设备上，不会发送到任何地方",
+    time_in_app: "使用时间",
+    sessions: "启动次数",
+    largest_design: "最大设计（组件数）",
+    copy: "复制",
+    reset: "重置",
+    activity_heatmap: "活动热图",
+    truth_table: "真值表",
+    inputs: "输入",
+    outputs: "输出",
+    evaluate: "计算",
+    export_csv: "导出 CSV",
+    too_many_inputs: "输入过多，最大值为",
+    extract_to_unit: "提取为单元",
+    library_entry_name: "库条目名称",
+    extract: "提取",
+    unit_does_not_fit: "单元无法放在所选位置",
+    flatten_unit: "展开单元",
+    unit_content_does_not_fit: "单元内容无法放在其位置",
+    connect_same_named_ports: "连接同名端口",
+    connected_ports: "已连接端口",
+    interfaces: "接口",
+    interface_ports: "接口端口",
+    is_input: "输入",
+    width: "宽度",
+    mirrored: "镜像（从端）",
+    add_to_selected_units: "添加到所选单元",
+    interface_does_not_fit: "带接口的单元放不下",
+    export_scope: "导出范围",
+    whole_design: "整个设计",
+    selection: "选区",
+    viewport: "视口",
+    nothing_to_export: "没有可导出的内容",
+    pixels: "像素",
+    millimeters: "毫米",
+    height: "高度",
+    keep_aspect_ratio: "保持纵横比",
+    dpi: "DPI",
+    image_size: "图像尺寸",
+    image_too_big: "图像太大",
+    both_themes: "两种主题",
+    both_themes_hint: "导出为 name_light.svg 和 name_dark.svg",
+    include_layers: "包含",
+    notes: "注释",
+    assertions: "断言",
+    component_defaults: "组件默认参数",
+    customize_selected: "自定义所选组件",
+    selected_components: "所选组件数",
+    io_names: "输入/输出名称",
+    io_names_hint: "名称将写入网表，空行保留自动生成的名称",
+    all_io_named: "所有输入和输出均已命名",
+    templates: "模板",
+    place: "放置",
+    add_parameter: "添加参数",
+    stages_count: "级数",
+    save_selection_as_template: "将所选内容保存为模板",
+    macros: "宏",
+    start_recording: "开始录制",
+    stop_recording: "停止录制",
+    macro_name: "宏名称",
+    macro_default_name: "宏",
+    macro_is_empty: "宏为空：未录制任何编辑",
+    macro_playback_hint: "在画布上单击即从该单元格重放宏",
+    open_recent: "打开最近的项目",
+    save_thumbnails: "保存项目缩略图",
+    new_tab: "新标签页",
+    paste_from_history: "从历史记录粘贴",
+    clipboard_history_is_empty: "尚未复制任何内容",
+    snap_annotations: "将文本附加到组件",
+    sync_ports: "同步端口",
+    missing_unit_port: "单元原理图的输入或输出没有端口",
+    extra_unit_port: "单元端口在其原理图中不存在",
+    unit_port_side: "单元端口位于相反方向的一侧",
+    synced_unit_does_not_fit: "同步端口后的单元放不下",
+    generate_symbol: "生成符号",
+    generated_symbol_does_not_fit: "生成的符号放不下",
+    net_colors: "连线颜色",
+    net_class_colors: "连线类别颜色",
+    uniform_net_color: "统一主题颜色",
+    net_legend: "图例",
+    export_grid: "网格",
+    grid_opacity: "网格不透明度",
+    rendering: "正在渲染…",
+    profiler: "性能分析器",
+    frame_time: "帧时间",
+    mesh_cache: "符号网格缓存",
+    cache_hits: "命中",
+    cache_misses: "未命中",
+    cache_evictions: "淘汰",
+    cache_budget: "网格缓存上限",
+    geometry_cache: "符号几何缓存",
+    cache_entries: "条目",
+    adaptive_lod: "自适应细节级别",
+    adaptive_lod_hint: "绘制缓慢时降低细节",
+    max_gate_inputs: "最大门输入数",
+    whats_new: "新功能",
+    version: "版本",
+    newer_version_available: "有新版本可用",
+    check_updates: "检查更新",
+    check_updates_hint: "启动时从 GitHub 获取最新版本",
+    workspace: "工作区",
+    add_folder: "添加文件夹",
+    remove_folder: "从工作区移除文件夹",
+    refresh: "刷新",
+    no_workspace_folders: "添加包含项目的文件夹",
+    no_projects_in_folder: "文件夹中没有项目",
+    rename: "重命名",
+    duplicate: "复制",
+    rename_failed: "无法重命名项目",
+    duplicate_failed: "无法复制项目",
+    magnifier_hint: "按住空格键放大光标下的区域",
 };
 
 pub fn get_system_default_locale() -> LocaleType {
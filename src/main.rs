@@ -1,28 +1,91 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 use eframe::{Storage, egui};
 
-use egui::{
-    CursorIcon, Id, KeyboardShortcut, LayerId, Modifiers, Rect, Sense, Stroke, Theme, vec2,
+use egui::{CursorIcon, Id, LayerId, Rect, Sense, Stroke, Theme, vec2};
+
+use editor_core::{
+    auto_wiring::auto_wire,
+    datasheet::{SUPPORTED_DATASHEET_FORMATS, generate_datasheets},
+    grid_db,
+    hierarchy::{check_unit_ports, extract_unit, flatten_unit, sync_unit_ports},
+    macros::Macro,
 };
 
 use crate::{
+    canvas::SchematicTheme,
+    clipboard_history::ClipboardHistory,
+    component_lib::{
+        EXTRACTED_UNITS_LIBRARY, UserLibraryEntry, add_library_entry, get_component_defaults,
+        get_user_libraries, install_library, set_component_defaults,
+    },
     components_panel::ComponentsPanel,
-    field::{Field, SUPPORTED_GRID_TYPES},
+    crash_report::{CrashGuard, CrashReport, CrashReportAction, CrashReportWindow},
+    field::{Field, SUPPORTED_GRID_TYPES, SUPPORTED_WHEEL_MODES},
     file_managment::FileManager,
+    grid_db::{
+        Component, GridDB, NetColorMode, NetStyle, PrimitiveType, Revision, Unit,
+        get_mesh_cache_stats, grid_pos, set_max_gate_inputs, set_mesh_cache_budget,
+    },
     helpers::Helpers,
+    io_naming::IoNamingWizard,
+    library_updates::{LibraryUpdateAction, LibraryUpdatesWindow, find_library_updates},
     locale::{LocaleType, SUPPORTED_LOCALES},
-    settings::{AppSettings, GetName, SUPPORTED_THEMES},
+    problems::{ProblemAction, ProblemsPanel},
+    profiler::Profiler,
+    rename::RenameWizard,
+    search::{SearchAction, SearchPanel},
+    settings::{
+        AppSettings, GetName, LayoutMode, MenuLayout, SUPPORTED_CORNER_STYLES,
+        SUPPORTED_LAYOUT_MODES, SUPPORTED_MENU_LAYOUTS, SUPPORTED_THEMES,
+    },
+    shortcuts::{ShortcutAction, ShortcutMap},
+    simulation::{SimulationFileAction, SimulationPanel},
+    statistics::StatisticsWindow,
+    tabs::{ProjectTab, Session, TabAction, Tabs, UNTITLED},
+    templates::TemplatesWindow,
+    toasts::Toasts,
+    truth_table::{TruthTableAction, TruthTableWindow},
+    whats_new::WhatsNew,
 };
+#[cfg(not(target_arch = "wasm32"))]
+use crate::workspace::{WorkspaceAction, WorkspaceWindow};
 
+mod action_icons;
+mod canvas;
+mod clipboard_history;
 mod component_lib;
 mod components_panel;
+mod crash_report;
+mod customization;
+mod export_size;
 mod field;
 mod file_managment;
-mod grid_db;
 mod helpers;
 mod interaction_manager;
+mod io_naming;
+mod library_updates;
 mod locale;
+mod problems;
+mod profiler;
+mod quick_add;
+mod rename;
+mod search;
 mod settings;
+mod shortcuts;
+mod simulation;
+mod statistics;
+mod tabs;
+mod templates;
+mod thumbnail;
+#[cfg(test)]
+mod testing;
+mod toasts;
+mod truth_table;
+mod whats_new;
+#[cfg(target_arch = "wasm32")]
+mod worker;
+#[cfg(not(target_arch = "wasm32"))]
+mod workspace;
 
 #[cfg(not(target_arch = "wasm32"))]
 fn main() {
@@ -40,8 +103,9 @@ fn main() {
             .with_icon(Arc::new(icon_data)),
         ..Default::default()
     };
+    crash_report::install_panic_hook();
     _ = eframe::run_native(
-        "Editor",
+        APP_ID,
         options,
         Box::new(|cc| {
             #[cfg(feature = "unifont")]
@@ -81,6 +145,7 @@ fn main() {
     // Redirect `log` message to `console.log` and friends:
     //eframe::WebLogger::init(log::LevelFilter::Debug).ok();
 
+    crash_report::install_panic_hook();
     let web_options = eframe::WebOptions {
         dithering: false,
         ..Default::default()
@@ -134,8 +199,44 @@ struct EditorApp {
     locale: locale::LocaleType,
     file_manager: FileManager,
     helpers: Helpers,
+    problems_panel: ProblemsPanel,
+    search_panel: SearchPanel,
+    rename_wizard: RenameWizard,
+    io_naming: IoNamingWizard,
+    templates: TemplatesWindow,
+    // Copies of all tabs
+    clipboard_history: ClipboardHistory,
+    library_updates: LibraryUpdatesWindow,
+    simulation_panel: SimulationPanel,
+    truth_table: TruthTableWindow,
+    // Library entry name of the next unit extracted from the selection
+    unit_name: String,
+    macros: Vec<Macro>,
+    macro_name: String,
     file_name: String,
+    // Revision of the active project when it was opened or saved, None if it was never saved
+    saved_revision: Option<Revision>,
+    // Revision being saved, it becomes the saved one when the save is finished
+    saving_revision: Option<Revision>,
+    // Projects opened besides the active one
+    tabs: Tabs,
+    // Modified tab asked to be closed, waiting for the user to save or discard it
+    close_prompt: Option<usize>,
+    // The active tab is closed when its save is finished
+    close_after_save: bool,
     theme: Theme,
+    shortcuts: ShortcutMap,
+    toasts: Toasts,
+    crash_guard: CrashGuard,
+    statistics: StatisticsWindow,
+    profiler: Profiler,
+    whats_new: WhatsNew,
+    #[cfg(not(target_arch = "wasm32"))]
+    workspace: WorkspaceWindow,
+    crash_report: CrashReportWindow,
+    menu_layout: MenuLayout,
+    layout_mode: LayoutMode,
+    touch_detected: bool,
 }
 
 impl EditorApp {
@@ -146,92 +247,827 @@ impl EditorApp {
             .and_then(|json| serde_json::from_str(&json).ok())
             .unwrap_or_default();
 
+        settings
+            .libraries
+            .iter()
+            .cloned()
+            .for_each(install_library);
+        set_component_defaults(settings.component_defaults.clone());
+        set_mesh_cache_budget(settings.mesh_cache_budget);
+        set_max_gate_inputs(settings.max_gate_inputs);
+
+        let mut preview_window = ComponentsPanel::new();
+        preview_window.settings = settings.palette;
+
         let mut field = Field::new();
         field.grid_type = settings.grid_type;
+        field.unconnected_visible = settings.unconnected_visible;
+        field.state.net_style = settings.net_style;
+        field.bundle_nets = settings.bundle_nets;
+        field.length_heatmap_visible = settings.length_heatmap_visible;
+        field.net_legend_visible = settings.net_legend_visible;
+        field.auto_place = settings.auto_place;
+        field.interaction_manager.snap_annotations = settings.snap_annotations;
+        field.wheel_mode = settings.wheel_mode;
+        field.save_view = settings.save_view;
+        field.adaptive_lod = settings.adaptive_lod;
+
+        let mut file_manager = FileManager::new();
+        file_manager.thumbnails = settings.thumbnails;
+        file_manager.set_recent(settings.recent_projects.clone());
 
-        EditorApp {
+        let session: Option<Session> = cc
+            .storage
+            .and_then(|s| s.get_string("session"))
+            .and_then(|json| serde_json::from_str(&json).ok());
+
+        let mut app = EditorApp {
             field: field,
-            preview_window: ComponentsPanel::new(),
+            preview_window,
             locale: if settings.locale.is_supported() {
                 settings.locale
             } else {
                 LocaleType::En
             },
-            file_manager: FileManager::new(),
+            file_manager,
             helpers: Helpers::new(cc),
-            file_name: "Untitled".into(),
+            problems_panel: ProblemsPanel::new(),
+            search_panel: SearchPanel::new(),
+            rename_wizard: RenameWizard::new(),
+            io_naming: IoNamingWizard::default(),
+            templates: TemplatesWindow::new(settings.templates),
+            clipboard_history: ClipboardHistory::default(),
+            library_updates: LibraryUpdatesWindow::new(),
+            simulation_panel: SimulationPanel::new(),
+            truth_table: TruthTableWindow::new(),
+            unit_name: String::new(),
+            macros: settings.macros,
+            macro_name: String::new(),
+            file_name: UNTITLED.into(),
+            saved_revision: None,
+            saving_revision: None,
+            tabs: Tabs::default(),
+            close_prompt: None,
+            close_after_save: false,
             theme: settings.theme.into(),
+            shortcuts: settings.shortcuts,
+            toasts: Toasts::new(),
+            crash_guard: CrashGuard::new(),
+            statistics: StatisticsWindow::new(settings.statistics),
+            profiler: Profiler::default(),
+            whats_new: WhatsNew::new(&settings.last_seen_version, settings.check_updates),
+            #[cfg(not(target_arch = "wasm32"))]
+            workspace: WorkspaceWindow::new(settings.workspace),
+            crash_report: CrashReportWindow::new(CrashReport::take()),
+            menu_layout: settings.menu_layout,
+            layout_mode: settings.layout_mode,
+            touch_detected: false,
+        };
+        app.saved_revision = Some(app.field.grid_db.get_revision());
+        if let Some((tabs, active)) = session.and_then(Tabs::restore_session) {
+            app.tabs = tabs;
+            app.set_active_tab(active);
+        }
+        app
+    }
+
+    /// Active project as a tab, it's replaced by an empty project
+    fn take_active_tab(&mut self) -> ProjectTab {
+        let mut field = Field::new();
+        field.copy_settings(&self.field);
+        ProjectTab {
+            field: std::mem::replace(&mut self.field, field),
+            file_name: std::mem::take(&mut self.file_name),
+            file_path: self.file_manager.get_file_path(),
+            simulation_panel: std::mem::take(&mut self.simulation_panel),
+            problems_panel: std::mem::take(&mut self.problems_panel),
+            search_panel: std::mem::take(&mut self.search_panel),
+            truth_table: std::mem::take(&mut self.truth_table),
+            saved_revision: self.saved_revision.take(),
+        }
+    }
+
+    fn set_active_tab(&mut self, mut tab: ProjectTab) {
+        tab.field.copy_settings(&self.field);
+        self.field = tab.field;
+        self.file_name = tab.file_name;
+        self.file_manager.set_file_path(tab.file_path);
+        self.simulation_panel = tab.simulation_panel;
+        self.problems_panel = tab.problems_panel;
+        self.search_panel = tab.search_panel;
+        self.truth_table = tab.truth_table;
+        self.saved_revision = tab.saved_revision;
+        // The pending save belongs to the previous tab:
+        self.saving_revision = None;
+        self.close_after_save = false;
+    }
+
+    /// The active project was changed after it was opened or saved
+    fn is_modified(&self) -> bool {
+        self.saved_revision != Some(self.field.grid_db.get_revision())
+    }
+
+    fn execute_tab_action(&mut self, action: TabAction) {
+        match action {
+            TabAction::Select(index) => {
+                let active = self.take_active_tab();
+                let tab = self.tabs.select(index, active);
+                self.set_active_tab(tab);
+            }
+            TabAction::Close(index) => {
+                let modified = match self.tabs.get_background(index) {
+                    Some(tab) => tab.is_modified(),
+                    None => self.is_modified(),
+                };
+                if modified {
+                    self.close_prompt = Some(index);
+                } else if let Some(tab) = self.tabs.close(index) {
+                    self.set_active_tab(tab);
+                }
+            }
+            TabAction::New => {
+                let active = self.take_active_tab();
+                self.tabs.push(active);
+                self.set_active_tab(ProjectTab::new());
+            }
+        }
+    }
+
+    /// Asks to save the modified tab before closing it
+    fn show_close_prompt(&mut self, ctx: &egui::Context, locale: &'static locale::Locale) {
+        let Some(index) = self.close_prompt else {
+            return;
+        };
+        let mut save = None;
+        let mut cancel = false;
+        egui::modal::Modal::new("close_tab".into()).show(ctx, |ui| {
+            ui.label(locale.save_changes_before_closing);
+            ui.horizontal(|ui| {
+                if ui.button(locale.save).clicked() {
+                    save = Some(true);
+                }
+                if ui.button(locale.discard).clicked() {
+                    save = Some(false);
+                }
+                cancel = ui.button(locale.cancel).clicked();
+            });
+        });
+        match save {
+            Some(true) => {
+                self.close_prompt = None;
+                if index != self.tabs.get_active() {
+                    self.execute_tab_action(TabAction::Select(index));
+                }
+                self.execute_shortcut_action(ctx, ShortcutAction::Save);
+                self.close_after_save = true;
+            }
+            Some(false) => {
+                self.close_prompt = None;
+                if let Some(tab) = self.tabs.close(index) {
+                    self.set_active_tab(tab);
+                }
+            }
+            None if cancel => self.close_prompt = None,
+            None => {}
+        }
+    }
+
+    fn execute_shortcut_action(&mut self, ctx: &egui::Context, action: ShortcutAction) {
+        let locale = self.locale.locale();
+        self.statistics.record_command(&format!("{action:?}"));
+        match action {
+            ShortcutAction::Save => {
+                self.field.store_view();
+                self.saving_revision = Some(self.field.grid_db.get_revision());
+                self.close_after_save = false;
+                self.file_manager
+                    .save_file(&self.field.grid_db, &self.file_name, locale)
+            }
+            ShortcutAction::SaveAs => {
+                self.field.store_view();
+                self.saving_revision = Some(self.field.grid_db.get_revision());
+                self.close_after_save = false;
+                self.file_manager
+                    .start_save_file_as(&self.field.grid_db, &self.file_name, locale)
+            }
+            ShortcutAction::ExportSVG => {
+                let simulation_values = self.simulation_panel.is_open.then(|| {
+                    self.simulation_panel
+                        .simulator
+                        .get_wire_values(&self.field.grid_db)
+                });
+                self.file_manager.set_export_scopes(
+                    self.field.interaction_manager.get_selected_components(),
+                    self.field.get_visible_grid_rect(),
+                );
+                self.file_manager.start_export_svg(
+                    ctx,
+                    &self.field.grid_db,
+                    self.theme.schematic(),
+                    self.field.state.net_style,
+                    simulation_values,
+                )
+            }
+            ShortcutAction::ExportPNG => self.file_manager.start_export_png(
+                ctx,
+                self.field.state.rect,
+                self.field.state.grid_size,
+                self.field.grid_type,
+            ),
+        }
+    }
+
+    fn show_macros_menu(&mut self, ui: &mut egui::Ui, locale: &'static locale::Locale) {
+        let manager = &mut self.field.interaction_manager;
+        if manager.is_recording_macro() {
+            ui.label(locale.macro_name);
+            ui.text_edit_singleline(&mut self.macro_name);
+            if ui.button(locale.stop_recording).clicked() {
+                let name = match self.macro_name.trim() {
+                    "" => format!("{} {}", locale.macro_default_name, self.macros.len() + 1),
+                    name => name.to_owned(),
+                };
+                match manager.stop_macro_recording(&name) {
+                    Some(recorded) => self.macros.push(recorded),
+                    None => self.toasts.push(locale.macro_is_empty.to_owned()),
+                }
+                self.macro_name.clear();
+                ui.close();
+            }
+        } else if ui.button(locale.start_recording).clicked() {
+            manager.start_macro_recording();
+            ui.close();
+        }
+        if !self.macros.is_empty() {
+            ui.separator();
+            ui.label(locale.macro_playback_hint);
+        }
+        let mut removed = None;
+        for (i, recorded) in self.macros.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.button(&recorded.name).clicked() {
+                    manager.start_macro_playback(recorded.clone());
+                    ui.close();
+                }
+                if ui.button("🗑").clicked() {
+                    removed = Some(i);
+                }
+            });
+        }
+        if let Some(i) = removed {
+            self.macros.remove(i);
+        }
+    }
+
+    /// Replaces the selected components by a unit with them inside,
+    /// the unit is added to the user library
+    fn extract_selected_unit(&mut self, locale: &'static locale::Locale) {
+        let ids = self.field.interaction_manager.get_selected_components();
+        let Some(mut extraction) = extract_unit(&mut self.field.grid_db, &ids) else {
+            self.toasts.push(locale.unit_does_not_fit.into());
+            return;
+        };
+        let entry = UserLibraryEntry {
+            name: self.unit_name.clone(),
+            component: Component::Unit(Unit {
+                pos: grid_pos(1, 1), // Default preview pos
+                ..extraction.unit.clone()
+            }),
+        };
+        extraction.unit.source = Some(add_library_entry(EXTRACTED_UNITS_LIBRARY, entry));
+        self.field.interaction_manager.replace_fragment(
+            &ids,
+            extraction.into_fragment(),
+            &mut self.field.grid_db,
+        );
+    }
+
+    /// Makes the ports of the hierarchical unit match its schematic
+    fn sync_ports(&mut self, id: editor_core::grid_db::Id, locale: &'static locale::Locale) {
+        let Some(Component::Unit(unit)) = self.field.grid_db.get_component(&id) else {
+            return;
+        };
+        let Some((unit, connections)) = sync_unit_ports(unit) else {
+            return;
+        };
+        if !self.field.interaction_manager.change_unit_ports(
+            id,
+            unit,
+            connections,
+            &mut self.field.grid_db,
+        ) {
+            self.toasts.push(locale.synced_unit_does_not_fit.into());
+        }
+    }
+
+    /// Lays out the ports of the unit like a generated symbol
+    fn generate_symbol(&mut self, id: editor_core::grid_db::Id, locale: &'static locale::Locale) {
+        let Some(Component::Unit(unit)) = self.field.grid_db.get_component(&id) else {
+            return;
+        };
+        let mut unit = unit.clone();
+        unit.generate_symbol();
+        let connections = (0..unit.ports.len()).map(|i| (i, Some(i))).collect();
+        if !self.field.interaction_manager.change_unit_ports(
+            id,
+            unit,
+            connections,
+            &mut self.field.grid_db,
+        ) {
+            self.toasts.push(locale.generated_symbol_does_not_fit.into());
+        }
+    }
+
+    /// Large buttons at the bottom of the touch layout
+    fn show_touch_toolbar(&mut self, ctx: &egui::Context, locale: &'static locale::Locale) {
+        egui::TopBottomPanel::bottom("touch_toolbar").show(ctx, |ui| {
+            ui.horizontal_centered(|ui| {
+                let size = vec2(TOUCH_BUTTON_SIZE, TOUCH_BUTTON_SIZE);
+                let icon = |icon: &str| egui::RichText::new(icon).size(TOUCH_ICON_SIZE);
+                let db = &mut self.field.grid_db;
+                let manager = &mut self.field.interaction_manager;
+                let button = |icon| egui::Button::new(icon).min_size(size);
+                if ui.add(button(icon("⟲"))).on_hover_text(locale.undo).clicked() {
+                    manager.undo(db);
+                }
+                if ui.add(button(icon("⟳"))).on_hover_text(locale.redo).clicked() {
+                    manager.redo(db);
+                }
+                if ui.add(button(icon("🗑"))).on_hover_text(locale.delete).clicked() {
+                    manager.remove_selected(db);
+                }
+                ui.separator();
+                for (is_open, text, hint) in [
+                    (&mut self.preview_window.is_expanded, "➕", locale.components),
+                    (&mut self.simulation_panel.is_open, "▶", locale.simulation),
+                ] {
+                    let toggle = egui::Button::selectable(*is_open, icon(text)).min_size(size);
+                    if ui.add(toggle).on_hover_text(hint).clicked() {
+                        *is_open = !*is_open;
+                    }
+                }
+            });
+        });
+    }
+
+    /// Zoom buttons floating in the bottom right corner of the field
+    fn show_zoom_buttons(&mut self, ui: &mut egui::Ui, locale: &'static locale::Locale) {
+        let size = vec2(TOUCH_BUTTON_SIZE, TOUCH_BUTTON_SIZE);
+        let corner = ui.max_rect().right_bottom() - vec2(10.0, 10.0);
+        let buttons = [("+", locale.zoom_in, 1.25), ("−", locale.zoom_out, 0.8)];
+        for (i, (icon, text, factor)) in buttons.into_iter().enumerate() {
+            // Stacked from the corner up:
+            let min = corner - vec2(size.x, (2 - i) as f32 * (size.y + 10.0) - 10.0);
+            let button = egui::Button::new(egui::RichText::new(icon).size(TOUCH_ICON_SIZE));
+            if ui
+                .put(Rect::from_min_size(min, size), button)
+                .on_hover_text(text)
+                .clicked()
+            {
+                self.field.zoom_by(factor);
+            }
         }
     }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn show_workspace(&mut self, ctx: &egui::Context, locale: &'static locale::Locale) {
+        match self.workspace.show(ctx, &mut self.file_manager, locale) {
+            Some(WorkspaceAction::Open(path)) => {
+                self.field.interaction_manager.reset();
+                self.file_manager.open_recent(&path, locale);
+                self.statistics.record_command("OpenFromWorkspace");
+            }
+            Some(WorkspaceAction::Renamed(from, to))
+                if self.file_manager.project_renamed(&from, &to) =>
+            {
+                let file_name = to.rsplit(['/', '\\']).next().unwrap_or(&to);
+                self.file_name = file_managment::split_project_suffix(file_name).0.to_owned();
+            }
+            Some(WorkspaceAction::Error(error)) => self.toasts.push(error.into()),
+            Some(WorkspaceAction::Renamed(..)) | None => {}
+        }
+    }
+
+    /// Recent projects with their thumbnails
+    fn show_recent_menu(&mut self, ui: &mut egui::Ui, locale: &'static locale::Locale) {
+        for name in self.file_manager.get_recent() {
+            let file_name = name.rsplit(['/', '\\']).next().unwrap_or(&name).to_owned();
+            let thumbnail = self.file_manager.get_thumbnail(ui.ctx(), &name);
+            let button = match thumbnail {
+                Some(image) => egui::Button::image_and_text(
+                    image.fit_to_exact_size(vec2(RECENT_THUMBNAIL_SIZE, RECENT_THUMBNAIL_SIZE)),
+                    file_name,
+                ),
+                None => egui::Button::new(file_name),
+            };
+            if ui.add(button).on_hover_text(&name).clicked() {
+                self.field.interaction_manager.reset();
+                self.file_manager.open_recent(&name, locale);
+                self.statistics.record_command("OpenRecent");
+                ui.close();
+            }
+        }
+    }
+
+    /// File, Edit, View and Help menus, shown in the menu bar or in the collapsed menu
+    fn show_menus(
+        &mut self,
+        ctx: &egui::Context,
+        ui: &mut egui::Ui,
+        locale: &'static locale::Locale,
+    ) {
+        ui.menu_button(locale.file, |ui| {
+            ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+            if ui.button(locale.new_tab).clicked() {
+                self.execute_tab_action(TabAction::New);
+                ui.close();
+            }
+            if ui.button(locale.open).clicked() {
+                self.field.interaction_manager.reset(); // TODO: refactor it
+                self.file_manager.open_file(locale);
+                self.statistics.record_command("Open");
+                ui.close();
+            }
+            ui.add_enabled_ui(!self.file_manager.get_recent().is_empty(), |ui| {
+                ui.menu_button(locale.open_recent, |ui| self.show_recent_menu(ui, locale));
+            });
+            #[cfg(not(target_arch = "wasm32"))]
+            if ui.button(locale.workspace).clicked() {
+                self.workspace.is_open = true;
+                ui.close();
+            }
+            if ui.button(locale.import).clicked() {
+                self.file_manager.import_file(locale);
+                self.statistics.record_command("Import");
+                ui.close();
+            }
+            let shortcuts = &self.shortcuts;
+            let mut menu_action = None;
+            for (action, text) in [
+                (ShortcutAction::Save, locale.save),
+                (ShortcutAction::SaveAs, locale.save_as),
+                (ShortcutAction::ExportSVG, locale.export_to_svg),
+                (ShortcutAction::ExportPNG, locale.export_to_png),
+            ] {
+                if ui
+                    .add(
+                        egui::Button::new(text)
+                            .shortcut_text(shortcuts.format(ctx, action)),
+                    )
+                    .clicked()
+                {
+                    menu_action = Some(action);
+                    ui.close();
+                }
+            }
+            if let Some(action) = menu_action {
+                self.execute_shortcut_action(ctx, action);
+            }
+            ui.checkbox(&mut self.field.save_view, locale.save_view);
+            ui.checkbox(&mut self.file_manager.thumbnails, locale.save_thumbnails);
+            ui.menu_button(locale.export_datasheets, |ui| {
+                for format in SUPPORTED_DATASHEET_FORMATS {
+                    if ui.button(format.get_name()).clicked() {
+                        let data = generate_datasheets(
+                            &self.field.grid_db,
+                            &self.file_name,
+                            format,
+                            self.theme.schematic(),
+                        );
+                        self.file_manager.save_datasheets(
+                            data,
+                            &self.file_name,
+                            format,
+                            locale,
+                        );
+                        ui.close();
+                    }
+                }
+            });
+            ui.separator();
+            if ui.button(locale.project_variables).clicked() {
+                self.helpers.variables_showed = true;
+                ui.close();
+            }
+            if ui.button(locale.net_classes).clicked() {
+                self.helpers.net_classes_showed = true;
+                ui.close();
+            }
+            if ui.button(locale.interfaces).clicked() {
+                self.helpers.interfaces_showed = true;
+                ui.close();
+            }
+            if ui.button(locale.compact_project).clicked() {
+                let report = self.field.grid_db.compact();
+                // Undo history refers to the old IDs
+                if report.changed {
+                    self.field.interaction_manager.reset();
+                }
+                self.toasts.push(format!(
+                    "{}: {} → {} B, {}: {}, {}: {}, {}: {}",
+                    locale.project_compacted,
+                    report.size_before,
+                    report.size_after,
+                    locale.orphaned_nets,
+                    report.orphaned_nets,
+                    locale.index_entries,
+                    report.index_entries,
+                    locale.duplicate_net_classes,
+                    report.duplicate_classes,
+                ));
+                ui.close();
+            }
+        });
+        ui.menu_button(locale.edit, |ui| {
+            ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+            if ui.button(locale.replace).clicked() {
+                self.search_panel.is_open = true;
+                ui.close();
+            }
+            if ui.button(locale.rename_instances).clicked() {
+                self.rename_wizard.is_open = true;
+                ui.close();
+            }
+            if ui.button(locale.io_names).clicked() {
+                self.io_naming.open(&self.field.grid_db);
+                ui.close();
+            }
+            let history = egui::Button::new(locale.paste_from_history);
+            if ui.add_enabled(!self.clipboard_history.is_empty(), history).clicked() {
+                self.clipboard_history.is_open = true;
+                ui.close();
+            }
+            if ui.button(locale.templates).clicked() {
+                self.templates.is_open = true;
+                ui.close();
+            }
+            ui.menu_button(locale.macros, |ui| self.show_macros_menu(ui, locale));
+            ui.checkbox(&mut self.field.auto_place, locale.auto_place);
+            ui.checkbox(
+                &mut self.field.interaction_manager.snap_annotations,
+                locale.snap_annotations,
+            );
+            ui.menu_button(locale.component_defaults, |ui| {
+                ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+                self.preview_window.show_component_defaults(ui, locale);
+            });
+            ui.horizontal(|ui| {
+                ui.label(locale.max_gate_inputs);
+                let mut max_inputs = PrimitiveType::get_max_gate_inputs();
+                let range = 2..=PrimitiveType::MAX_GATE_INPUTS_LIMIT;
+                if ui.add(egui::DragValue::new(&mut max_inputs).range(range)).changed() {
+                    set_max_gate_inputs(max_inputs);
+                }
+            });
+            if ui.button(locale.keep_out_regions).clicked() {
+                self.field.interaction_manager.start_keep_out_drawing();
+                ui.close();
+            }
+            if ui.button(locale.library_updates).clicked() {
+                self.library_updates.is_open = true;
+                ui.close();
+            }
+            ui.menu_button(locale.extract_to_unit, |ui| {
+                let selected = self.field.interaction_manager.get_selected_components();
+                ui.label(locale.library_entry_name);
+                ui.text_edit_singleline(&mut self.unit_name);
+                if ui
+                    .add_enabled(
+                        !selected.is_empty() && !self.unit_name.trim().is_empty(),
+                        egui::Button::new(locale.extract),
+                    )
+                    .clicked()
+                {
+                    self.extract_selected_unit(locale);
+                    ui.close();
+                }
+            });
+            let homogeneous = self
+                .field
+                .interaction_manager
+                .get_homogeneous_selection(&self.field.grid_db)
+                .is_some();
+            let customize = egui::Button::new(locale.customize_selected);
+            if ui.add_enabled(homogeneous, customize).clicked() {
+                self.field.interaction_manager.customize_selected(&self.field.grid_db);
+                ui.close();
+            }
+            let selected = self.field.interaction_manager.get_selected_components();
+            let auto_wiring = egui::Button::new(locale.connect_same_named_ports);
+            if ui.add_enabled(selected.len() == 2, auto_wiring).clicked() {
+                let nets = auto_wire(&mut self.field.grid_db, selected[0], selected[1]);
+                self.toasts.push(format!("{}: {}", locale.connected_ports, nets.len()));
+                if !nets.is_empty() {
+                    self.field.interaction_manager.add_nets(nets, &mut self.field.grid_db);
+                }
+                ui.close();
+            }
+            let hierarchical_unit = match selected[..] {
+                [id] => matches!(
+                    self.field.grid_db.get_component(&id),
+                    Some(Component::Unit(unit)) if unit.schematic.is_some()
+                )
+                .then_some(id),
+                _ => None,
+            };
+            if ui
+                .add_enabled(hierarchical_unit.is_some(), egui::Button::new(locale.flatten_unit))
+                .clicked()
+                && let Some(id) = hierarchical_unit
+            {
+                match flatten_unit(&mut self.field.grid_db, id) {
+                    Some(fragment) => self.field.interaction_manager.replace_fragment(
+                        &[id],
+                        fragment,
+                        &mut self.field.grid_db,
+                    ),
+                    None => self.toasts.push(locale.unit_content_does_not_fit.into()),
+                }
+                ui.close();
+            }
+            let unit = match selected[..] {
+                [id] => matches!(self.field.grid_db.get_component(&id), Some(Component::Unit(_)))
+                    .then_some(id),
+                _ => None,
+            };
+            if ui
+                .add_enabled(unit.is_some(), egui::Button::new(locale.generate_symbol))
+                .clicked()
+                && let Some(id) = unit
+            {
+                self.generate_symbol(id, locale);
+                ui.close();
+            }
+            let mismatched_unit = hierarchical_unit.filter(|id| {
+                matches!(
+                    self.field.grid_db.get_component(id),
+                    Some(Component::Unit(unit)) if !check_unit_ports(unit).is_empty()
+                )
+            });
+            if ui
+                .add_enabled(mismatched_unit.is_some(), egui::Button::new(locale.sync_ports))
+                .clicked()
+                && let Some(id) = mismatched_unit
+            {
+                self.sync_ports(id, locale);
+                ui.close();
+            }
+        });
+        ui.menu_button(locale.view, |ui| {
+            ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+            ui.menu_button(locale.zoom, |ui| {
+                ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+                self.field.show_zoom_presets(ui, locale);
+            });
+            ui.menu_button(locale.mouse_wheel, |ui| {
+                ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+                SUPPORTED_WHEEL_MODES.iter().for_each(|mode| {
+                    ui.radio_value(&mut self.field.wheel_mode, *mode, mode.get_name(locale));
+                });
+            });
+            ui.menu_button(locale.grid, |ui| {
+                ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+                SUPPORTED_GRID_TYPES.iter().for_each(|grid_type| {
+                    ui.radio_value(
+                        &mut self.field.grid_type,
+                        *grid_type,
+                        grid_type.get_name(locale),
+                    );
+                });
+            });
+            ui.menu_button(locale.language, |ui| {
+                ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+                SUPPORTED_LOCALES.iter().for_each(|locale| {
+                    ui.add_enabled_ui(locale.is_supported(), |ui| {
+                        ui.radio_value(&mut self.locale, *locale, locale.get_name());
+                    });
+                });
+            });
+            ui.menu_button(locale.theme, |ui| {
+                ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+                SUPPORTED_THEMES.iter().for_each(|theme| {
+                    ui.radio_value(&mut self.theme, *theme, theme.get_name(locale));
+                });
+            });
+            ui.menu_button(locale.layout, |ui| {
+                ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+                SUPPORTED_LAYOUT_MODES.iter().for_each(|mode| {
+                    ui.radio_value(&mut self.layout_mode, *mode, mode.get_name(locale));
+                });
+            });
+            ui.menu_button(locale.menu_bar, |ui| {
+                ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+                SUPPORTED_MENU_LAYOUTS.iter().for_each(|layout| {
+                    ui.radio_value(&mut self.menu_layout, *layout, layout.get_name(locale));
+                });
+            });
+            ui.menu_button(locale.net_style, |ui| {
+                ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+                let style = &mut self.field.state.net_style;
+                ui.add(
+                    egui::Slider::new(
+                        &mut style.junction_size,
+                        NetStyle::MIN_JUNCTION_SIZE..=NetStyle::MAX_JUNCTION_SIZE,
+                    )
+                    .text(locale.junction_size),
+                );
+                SUPPORTED_CORNER_STYLES.iter().for_each(|corners| {
+                    ui.radio_value(
+                        &mut style.corners,
+                        *corners,
+                        corners.get_name(locale),
+                    );
+                });
+                ui.checkbox(&mut style.arrows, locale.net_arrows);
+                ui.checkbox(&mut self.field.bundle_nets, locale.bundle_nets);
+            });
+            ui.menu_button(locale.net_colors, |ui| {
+                ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+                let style = &mut self.field.state.net_style;
+                NetColorMode::ALL.iter().for_each(|mode| {
+                    ui.radio_value(&mut style.color_mode, *mode, mode.get_name(locale));
+                });
+                ui.separator();
+                ui.checkbox(&mut self.field.net_legend_visible, locale.net_legend);
+            });
+            ui.checkbox(
+                &mut self.field.unconnected_visible,
+                locale.highlight_unconnected,
+            );
+            ui.checkbox(
+                &mut self.field.length_heatmap_visible,
+                locale.net_length_heatmap,
+            );
+            ui.checkbox(&mut self.problems_panel.is_open, locale.problems);
+            ui.checkbox(&mut self.simulation_panel.is_open, locale.simulation);
+            ui.checkbox(&mut self.truth_table.is_open, locale.truth_table);
+            ui.checkbox(&mut self.field.adaptive_lod, locale.adaptive_lod)
+                .on_hover_text(locale.adaptive_lod_hint);
+            ui.checkbox(&mut self.profiler.is_open, locale.profiler);
+        });
+        ui.menu_button(locale.help, |ui| {
+            ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
+            if ui.button(locale.about).clicked() {
+                self.helpers.about_showed = true;
+                ui.close();
+            }
+            if ui.button(locale.whats_new).clicked() {
+                self.whats_new.is_open = true;
+                ui.close();
+            }
+            if ui.button(locale.usage_statistics).clicked() {
+                self.statistics.is_open = true;
+                ui.close();
+            }
+        });
+    }
 }
 
 impl eframe::App for EditorApp {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let frame_start = web_time::Instant::now();
         ctx.set_theme(self.theme);
         let locale: &'static locale::Locale = self.locale.locale();
         let foreground: LayerId = LayerId::new(egui::Order::Foreground, Id::new("foreground"));
+        let revision = self.field.grid_db.get_revision();
         self.file_manager
             .update(ctx, locale, &mut self.field.grid_db, &mut self.file_name);
+        if self.field.grid_db.get_revision() != revision {
+            // Another project is opened:
+            self.saved_revision = Some(self.field.grid_db.get_revision());
+        }
+        if self.file_manager.take_saved() {
+            if let Some(revision) = self.saving_revision.take() {
+                self.saved_revision = Some(revision);
+            }
+            if std::mem::take(&mut self.close_after_save)
+                && let Some(tab) = self.tabs.close(self.tabs.get_active())
+            {
+                self.set_active_tab(tab);
+            }
+        }
+        self.field.grid_db.set_project_name(&self.file_name);
         ctx.tessellation_options_mut(|options| options.feathering = false);
         egui::TopBottomPanel::top("menu_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
                 egui::MenuBar::new().ui(ui, |ui| {
-                    ui.menu_button(locale.file, |ui| {
-                        ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
-                        if ui.button(locale.open).clicked() {
-                            self.field.interaction_manager.reset(); // TODO: refactor it
-                            self.file_manager.open_file(locale);
-                            ui.close();
-                        }
-                        if ui.button(locale.save).clicked() {
-                            self.file_manager
-                                .save_file(&self.field.grid_db, &self.file_name);
-                            ui.close();
-                        }
-                        if ui.button(locale.export_to_svg).clicked() {
-                            self.file_manager.start_export_svg(
-                                ctx,
-                                &self.field.grid_db,
-                                self.theme,
-                            );
-                            ui.close();
-                        }
-                    });
-                    ui.menu_button(locale.view, |ui| {
-                        ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
-                        ui.menu_button(locale.grid, |ui| {
-                            ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
-                            SUPPORTED_GRID_TYPES.iter().for_each(|grid_type| {
-                                ui.radio_value(
-                                    &mut self.field.grid_type,
-                                    *grid_type,
-                                    grid_type.get_name(locale),
-                                );
-                            });
+                    let compact = match self.menu_layout {
+                        MenuLayout::Auto => ui.available_width() < COMPACT_MENU_WIDTH,
+                        MenuLayout::Full => false,
+                        MenuLayout::Compact => true,
+                    };
+                    if compact {
+                        ui.menu_button("☰", |ui| {
+                            self.show_menus(ctx, ui, locale);
+                            if ui.button(locale.project_name).clicked() {
+                                self.helpers.project_name_showed = true;
+                                ui.close();
+                            }
                         });
-                        ui.menu_button(locale.language, |ui| {
-                            ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
-                            SUPPORTED_LOCALES.iter().for_each(|locale| {
-                                ui.add_enabled_ui(locale.is_supported(), |ui| {
-                                    ui.radio_value(&mut self.locale, *locale, locale.get_name());
-                                });
-                            });
-                        });
-                        ui.menu_button(locale.theme, |ui| {
-                            ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
-                            SUPPORTED_THEMES.iter().for_each(|theme| {
-                                ui.radio_value(&mut self.theme, *theme, theme.get_name(locale));
-                            });
-                        });
-                    });
-                    ui.menu_button(locale.help, |ui| {
-                        ui.style_mut().wrap_mode = Some(egui::TextWrapMode::Extend);
-                        if ui.button(locale.about).clicked() {
-                            self.helpers.about_showed = true;
-                            ui.close();
-                        }
-                    });
-                    if ui.available_width() >= ui.available_height() * 2.5 + 40.0 {
+                    } else {
+                        self.show_menus(ctx, ui, locale);
+                    }
+                    if !compact && ui.available_width() >= ui.available_height() * 2.5 + 40.0 {
                         ui.add_space(10.0);
                         ui.add(
                             egui::Label::new(locale.project_name.to_string() + &":")
@@ -253,24 +1089,209 @@ impl eframe::App for EditorApp {
             });
         });
 
-        self.field.set_external_drag_resp(self.preview_window.show(
-            ctx,
-            foreground,
-            self.field.state.scale,
-            locale,
-        ));
+        if self.tabs.len() > 1 {
+            let action = egui::TopBottomPanel::top("tabs_panel")
+                .show(ctx, |ui| self.tabs.show(ui, &self.file_name, locale))
+                .inner;
+            if let Some(action) = action {
+                self.execute_tab_action(action);
+            }
+        }
+        self.show_close_prompt(ctx, locale);
+
+        match self.simulation_panel.show(ctx, &self.field.grid_db, locale) {
+            Some(SimulationFileAction::Save(data)) => {
+                self.file_manager
+                    .save_simulation(data, &self.file_name, locale)
+            }
+            Some(SimulationFileAction::Load) => self.file_manager.open_simulation(locale),
+            None => {}
+        }
+        if let Some(data) = self.file_manager.take_simulation()
+            && self
+                .simulation_panel
+                .simulator
+                .load_state(&self.field.grid_db, &data)
+                .is_err()
+        {
+            self.toasts.push(locale.sim_state_load_error.into());
+        }
+        self.touch_detected |= ctx.input(|i| i.any_touches());
+        let touch_layout = match self.layout_mode {
+            LayoutMode::Auto => self.touch_detected,
+            LayoutMode::Desktop => false,
+            LayoutMode::Touch => true,
+        };
+        if touch_layout {
+            self.show_touch_toolbar(ctx, locale);
+            self.field.set_external_drag_resp(self.preview_window.show_drawer(
+                ctx,
+                foreground,
+                self.field.state.scale,
+                locale,
+            ));
+        } else {
+            self.field.set_external_drag_resp(self.preview_window.show(
+                ctx,
+                foreground,
+                self.field.state.scale,
+                locale,
+            ));
+        }
+        egui::TopBottomPanel::bottom("status_panel").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                self.field.show_status(ui, locale);
+            });
+        });
         egui::CentralPanel::default().show(ctx, |ui| {
+            self.field.net_values = (self.simulation_panel.is_open
+                && self.field.state.net_style.color_mode == NetColorMode::SimulationValues)
+                .then(|| {
+                    self.simulation_panel
+                        .simulator
+                        .get_wire_values(&self.field.grid_db)
+                });
+            self.field.capture_grid = self.file_manager.get_capture_grid();
             self.field.show(ui, locale);
+            self.simulation_panel
+                .show_on_field(&self.field.grid_db, &self.field.state, ui);
+            if touch_layout {
+                self.show_zoom_buttons(ui, locale);
+            }
         });
         self.helpers.show(ctx, self.locale);
+        self.helpers
+            .show_variables_window(ctx, &mut self.field.grid_db, locale);
+        self.helpers
+            .show_net_classes_window(ctx, &mut self.field.grid_db, locale);
+        let selected = self.field.interaction_manager.get_selected_components();
+        if let Some(units) =
+            self.helpers
+                .show_interfaces_window(ctx, &mut self.field.grid_db, &selected, locale)
+        {
+            let db = &self.field.grid_db;
+            let fits = units.iter().all(|(id, unit)| {
+                db.is_available_location(unit.get_position(), unit.get_dimension(), *id)
+            });
+            if !fits {
+                self.toasts.push(locale.interface_does_not_fit.to_owned());
+            }
+            self.field
+                .interaction_manager
+                .update_components(units, &mut self.field.grid_db);
+        }
+        self.helpers
+            .show_project_name_window(ctx, &mut self.file_name, locale);
+        match self
+            .problems_panel
+            .show(ctx, &self.field.grid_db, locale)
+        {
+            Some(ProblemAction::Focus(pos)) => self.field.focus_on(pos),
+            Some(ProblemAction::Reroute(net_id, net)) => self
+                .field
+                .interaction_manager
+                .replace_net(net_id, net, &mut self.field.grid_db),
+            Some(ProblemAction::RerouteFailed(net_id)) => self
+                .toasts
+                .push(format!("{}: #{net_id}", locale.reroute_failed)),
+            Some(ProblemAction::SyncPorts(id)) => self.sync_ports(id, locale),
+            None => {}
+        }
+        match self.search_panel.show(ctx, &self.field.grid_db, locale) {
+            Some(SearchAction::Focus(pos)) => self.field.focus_on(pos),
+            Some(SearchAction::Replace(components)) => self
+                .field
+                .interaction_manager
+                .replace_components(components, &mut self.field.grid_db),
+            None => {}
+        }
+        let selected = self.field.interaction_manager.get_selected_components();
+        if let Some(components) =
+            self.rename_wizard
+                .show(ctx, &self.field.grid_db, &selected, locale)
+        {
+            self.field
+                .interaction_manager
+                .replace_components(components, &mut self.field.grid_db);
+        }
+        if let Some(components) = self.io_naming.show(ctx, &self.field.grid_db, locale) {
+            self.field
+                .interaction_manager
+                .replace_components(components, &mut self.field.grid_db);
+        }
+        let selected = self.field.interaction_manager.get_selected_components();
+        if let Some(fragment) = self.templates.show(ctx, &self.field.grid_db, &selected, locale) {
+            self.field.interaction_manager.start_placing(fragment);
+        }
+        if let Some(fragment) = self.field.take_copied() {
+            self.clipboard_history.push(&fragment);
+        }
+        if let Some(fragment) = self.clipboard_history.show(ctx, locale) {
+            self.field.interaction_manager.start_placing(fragment);
+        }
+        if let Some(TruthTableAction::ExportCsv(data)) =
+            self.truth_table.show(ctx, &self.field.grid_db, locale)
+        {
+            self.file_manager.save_truth_table(data, &self.file_name, locale);
+        }
+        match self.library_updates.show(ctx, &self.field.grid_db, locale) {
+            Some(LibraryUpdateAction::Focus(pos)) => self.field.focus_on(pos),
+            Some(LibraryUpdateAction::Update(components)) => self
+                .field
+                .interaction_manager
+                .update_components(components, &mut self.field.grid_db),
+            None => {}
+        }
 
-        // Check Ctrl+S:
-        if ctx.input_mut(|state| {
-            state.consume_shortcut(&KeyboardShortcut::new(Modifiers::CTRL, egui::Key::S))
-        }) {
-            self.file_manager
-                .save_file(&self.field.grid_db, &self.file_name);
+        if let Some(action) = self.shortcuts.consume(ctx) {
+            self.execute_shortcut_action(ctx, action);
+        }
+
+        let libraries = self.file_manager.take_libraries();
+        if !libraries.is_empty() {
+            libraries.into_iter().for_each(install_library);
+            self.preview_window.reload();
+            if !find_library_updates(&self.field.grid_db).is_empty() {
+                self.library_updates.is_open = true;
+            }
+        }
+        if let Some(fragment) = self.file_manager.take_imported() {
+            self.field.interaction_manager.start_placing(fragment);
         }
+        self.crash_guard.update(&self.field.grid_db);
+        self.statistics.update(&self.field.grid_db);
+        self.statistics.show(ctx, locale);
+        match self.crash_report.show(ctx, locale) {
+            Some(CrashReportAction::Export(data)) => {
+                self.file_manager.save_bug_report(data, locale)
+            }
+            Some(CrashReportAction::Restore(project)) => match GridDB::load_from_json(project) {
+                Ok(db) => {
+                    self.field.interaction_manager.reset();
+                    self.field.grid_db = db;
+                }
+                Err(err) => self.toasts.push(format!("{}: {err}", locale.file_load_error)),
+            },
+            None => {}
+        }
+        for error in self.file_manager.take_errors() {
+            self.toasts.push(error);
+        }
+        if let Some(release) = self.whats_new.take_notification() {
+            self.toasts.push(format!("{}: {release}", locale.newer_version_available));
+        }
+        if self.whats_new.is_open {
+            self.whats_new.show(ctx, locale);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.workspace.is_open {
+            self.show_workspace(ctx, locale);
+        }
+        self.toasts.show(ctx);
+        if self.profiler.is_open {
+            self.profiler.show(ctx, locale);
+        }
+        self.profiler.record_frame(frame_start.elapsed());
     }
 
     fn save(&mut self, storage: &mut dyn Storage) {
@@ -278,12 +1299,56 @@ impl eframe::App for EditorApp {
             grid_type: self.field.grid_type,
             locale: self.locale,
             theme: self.theme.into(),
+            unconnected_visible: self.field.unconnected_visible,
+            net_style: self.field.state.net_style,
+            bundle_nets: self.field.bundle_nets,
+            length_heatmap_visible: self.field.length_heatmap_visible,
+            net_legend_visible: self.field.net_legend_visible,
+            auto_place: self.field.auto_place,
+            snap_annotations: self.field.interaction_manager.snap_annotations,
+            wheel_mode: self.field.wheel_mode,
+            save_view: self.field.save_view,
+            shortcuts: self.shortcuts.clone(),
+            libraries: get_user_libraries(),
+            menu_layout: self.menu_layout,
+            layout_mode: self.layout_mode,
+            palette: self.preview_window.settings.clone(),
+            statistics: self.statistics.statistics.clone(),
+            component_defaults: get_component_defaults(),
+            templates: self.templates.templates.clone(),
+            macros: self.macros.clone(),
+            thumbnails: self.file_manager.thumbnails,
+            recent_projects: self.file_manager.get_recent(),
+            adaptive_lod: self.field.adaptive_lod,
+            mesh_cache_budget: get_mesh_cache_stats().budget,
+            max_gate_inputs: PrimitiveType::get_max_gate_inputs(),
+            last_seen_version: whats_new::VERSION.into(),
+            check_updates: self.whats_new.check_updates,
+            #[cfg(not(target_arch = "wasm32"))]
+            workspace: self.workspace.get_settings(),
         }) {
             storage.set_string("settings", value);
         }
+        let mut active = self.take_active_tab();
+        let session = self.tabs.save_session(&mut active);
+        self.set_active_tab(active);
+        if let Ok(value) = serde_json::to_string(&session) {
+            storage.set_string("session", value);
+        }
     }
 }
 
+/// Name of the app data directory
+const APP_ID: &str = "Editor";
+
+const TOUCH_BUTTON_SIZE: f32 = 48.0;
+const TOUCH_ICON_SIZE: f32 = 24.0;
+/// Thumbnails in the list of the recent projects
+const RECENT_THUMBNAIL_SIZE: f32 = 48.0;
+
+/// Menu bar width, below which the menus are collapsed in the automatic layout
+const COMPACT_MENU_WIDTH: f32 = 500.0;
+
 fn panel_left_switch(ui: &mut egui::Ui, is_expanded: &mut bool) {
     let h = ui.available_height();
     ui.add_space((ui.available_width() - h * 2.0).max(0.0));
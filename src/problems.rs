@@ -0,0 +1,212 @@
+use editor_core::{
+    netlist::NetlistCache,
+    problems::{Problem, ProblemKind, collect_netlist_problems, reroute_net},
+};
+#[cfg(target_arch = "wasm32")]
+use editor_core::{
+    netlist::{Netlist, extract_netlist},
+    worker::{WorkerRequest, WorkerResponse},
+};
+use egui::RichText;
+
+#[cfg(target_arch = "wasm32")]
+use crate::worker::WorkerTask;
+use crate::{
+    grid_db::{GridDB, GridPos, Id, KeepOutRegion, Net, NetClass, Revision, Severity},
+    locale::Locale,
+    settings::GetName,
+};
+
+impl GetName for ProblemKind {
+    fn get_name(&self, locale: &'static Locale) -> &'static str {
+        match self {
+            Self::UnconnectedPin => locale.unconnected_pin,
+            Self::NetOverlap => locale.net_overlap,
+            Self::NetThroughComponent => locale.net_through_component,
+            Self::UnmatchedConnector => locale.unmatched_connector,
+            Self::NetThroughKeepOut => locale.net_through_keep_out,
+            Self::DanglingNet => locale.dangling_net,
+            Self::MissingComponent => locale.missing_component,
+            Self::MissingUnitPort => locale.missing_unit_port,
+            Self::ExtraUnitPort => locale.extra_unit_port,
+            Self::UnitPortSide => locale.unit_port_side,
+        }
+    }
+}
+
+/// Action chosen by user in the problems window
+pub enum ProblemAction {
+    Focus(GridPos),
+    /// Replace the net with the rerouted one
+    Reroute(Id, Net),
+    /// No free path was found for the net
+    RerouteFailed(Id),
+    /// Sync the ports of the unit with its schematic
+    SyncPorts(Id),
+}
+
+/// Found problems with the state of the schematic they were found in
+struct CheckedState {
+    revision: Revision,
+    // Not logged as changes, but change the problems:
+    net_classes: Vec<NetClass>,
+    keep_out: Vec<KeepOutRegion>,
+    missing_components: usize,
+    problems: Vec<Problem>,
+}
+
+pub struct ProblemsPanel {
+    pub is_open: bool,
+    unconnected_only: bool,
+    netlist: NetlistCache,
+    // Problems are checked again only after the schematic is changed
+    checked: Option<CheckedState>,
+    #[cfg(target_arch = "wasm32")]
+    extraction: Option<(Revision, WorkerTask)>, // Netlist extraction in the Web Worker
+}
+
+impl Default for ProblemsPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProblemsPanel {
+    pub fn new() -> Self {
+        Self {
+            is_open: false,
+            unconnected_only: false,
+            netlist: NetlistCache::default(),
+            checked: None,
+            #[cfg(target_arch = "wasm32")]
+            extraction: None,
+        }
+    }
+
+    fn update_problems(&mut self, ctx: &egui::Context, db: &GridDB) {
+        let revision = db.get_revision();
+        if self.checked.as_ref().is_some_and(|checked| {
+            checked.revision == revision
+                && checked.net_classes == db.get_net_classes()
+                && checked.keep_out == db.get_keep_out_regions()
+                && checked.missing_components == db.get_missing_components().len()
+        }) {
+            return;
+        }
+        // Small changes are applied to the netlist in the page, the whole one is extracted
+        // in the worker:
+        #[cfg(target_arch = "wasm32")]
+        if self.netlist.needs_extraction(db) {
+            let Some(netlist) = self.extract_netlist_wasm(ctx, db) else {
+                return; // The previous problems are shown until the extraction is finished
+            };
+            self.netlist.set(netlist, revision);
+        }
+        #[cfg(not(target_arch = "wasm32"))]
+        let _ = ctx;
+        let netlist = self.netlist.get(db);
+        self.checked = Some(CheckedState {
+            revision,
+            net_classes: db.get_net_classes().to_vec(),
+            keep_out: db.get_keep_out_regions().to_vec(),
+            missing_components: db.get_missing_components().len(),
+            problems: collect_netlist_problems(db, netlist),
+        });
+    }
+
+    /// Extracts the netlist in the worker, or in the page if the worker is not available.
+    /// Returns None while the worker is running.
+    #[cfg(target_arch = "wasm32")]
+    fn extract_netlist_wasm(&mut self, ctx: &egui::Context, db: &GridDB) -> Option<Netlist> {
+        let revision = db.get_revision();
+        if self
+            .extraction
+            .as_ref()
+            .is_none_or(|(started, _)| *started != revision)
+        {
+            // The result for the changed schematic is outdated:
+            self.extraction = WorkerRequest::extract_netlist(db)
+                .and_then(|request| WorkerTask::start(ctx, &request))
+                .map(|task| (revision, task));
+        }
+        let Some((_, task)) = &self.extraction else {
+            return Some(extract_netlist(db));
+        };
+        let response = match task.take_response() {
+            None if !task.is_failed() => return None,
+            response => response,
+        };
+        self.extraction = None;
+        match response {
+            Some(WorkerResponse::Netlist(netlist)) => Some(*netlist),
+            _ => Some(extract_netlist(db)),
+        }
+    }
+
+    /// Shows problems window.
+    /// Returns position of the problem selected by user or the net fix.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        db: &GridDB,
+        locale: &'static Locale,
+    ) -> Option<ProblemAction> {
+        let mut selected = None;
+        let mut is_open = self.is_open;
+        egui::Window::new(locale.problems)
+            .id("problems".into())
+            .open(&mut is_open)
+            .default_width(250.0)
+            .show(ctx, |ui| {
+                ui.checkbox(&mut self.unconnected_only, locale.unconnected_only);
+                ui.separator();
+                self.update_problems(ctx, db);
+                let problems: Vec<&Problem> = (self.checked.iter())
+                    .flat_map(|checked| &checked.problems)
+                    .filter(|p| !self.unconnected_only || p.kind == ProblemKind::UnconnectedPin)
+                    .collect();
+                if problems.is_empty() {
+                    ui.label(locale.no_problems);
+                    return;
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for problem in problems {
+                        let text =
+                            format!("{}: {}", problem.kind.get_name(locale), problem.description);
+                        ui.horizontal(|ui| {
+                            if let Some(net_id) = problem.net_id
+                                && ui.small_button(locale.reroute).clicked()
+                            {
+                                selected =
+                                    Some(match reroute_net(db, self.netlist.get(db), net_id) {
+                                        Some(net) => ProblemAction::Reroute(net_id, net),
+                                        None => ProblemAction::RerouteFailed(net_id),
+                                    });
+                            }
+                            if let Some(unit_id) = problem.unit_id
+                                && ui.small_button(locale.sync_ports).clicked()
+                            {
+                                selected = Some(ProblemAction::SyncPorts(unit_id));
+                            }
+                            let color = match problem.severity {
+                                Severity::Warning => ui.visuals().warn_fg_color,
+                                _ => egui::Color32::RED,
+                            };
+                            if ui
+                                .add(
+                                    egui::Label::new(RichText::new(text).color(color))
+                                        .selectable(false)
+                                        .sense(egui::Sense::click()),
+                                )
+                                .clicked()
+                            {
+                                selected = Some(ProblemAction::Focus(problem.pos));
+                            }
+                        });
+                    }
+                });
+            });
+        self.is_open = is_open;
+        selected
+    }
+}
@@ -0,0 +1,82 @@
+//! Overlay with the frame time and the cache statistics, for finding slow frames
+
+use std::collections::VecDeque;
+
+use web_time::Duration;
+
+use crate::{
+    grid_db::{get_geometry_cache_stats, get_mesh_cache_stats, set_mesh_cache_budget},
+    locale::Locale,
+};
+
+#[derive(Default)]
+pub struct Profiler {
+    pub is_open: bool,
+    /// Times of the recent frames in seconds
+    frame_times: VecDeque<f32>,
+}
+
+impl Profiler {
+    /// Frames averaged in the shown frame time
+    const FRAMES: usize = 60;
+    const MAX_CACHE_BUDGET: usize = 65536;
+
+    pub fn record_frame(&mut self, time: Duration) {
+        self.frame_times.push_back(time.as_secs_f32());
+        if self.frame_times.len() > Self::FRAMES {
+            self.frame_times.pop_front();
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, locale: &'static Locale) {
+        let frame_time =
+            self.frame_times.iter().sum::<f32>() / self.frame_times.len().max(1) as f32;
+        let mesh_cache = get_mesh_cache_stats();
+        let caches = [
+            (locale.mesh_cache, mesh_cache),
+            (locale.geometry_cache, get_geometry_cache_stats()),
+        ];
+        egui::Window::new(locale.profiler)
+            .id("profiler".into())
+            .open(&mut self.is_open)
+            .anchor(egui::Align2::RIGHT_TOP, [-10.0, 40.0])
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "{}: {:.1} ms",
+                    locale.frame_time,
+                    frame_time * 1000.0
+                ));
+                egui::Grid::new("profiler_grid")
+                    .num_columns(5)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.label("");
+                        ui.strong(locale.cache_entries);
+                        ui.strong(locale.cache_hits);
+                        ui.strong(locale.cache_misses);
+                        ui.strong(locale.cache_evictions);
+                        ui.end_row();
+                        for (name, stats) in caches {
+                            ui.label(name);
+                            ui.label(format!("{} / {}", stats.entries, stats.budget));
+                            ui.label(stats.hits.to_string());
+                            ui.label(stats.misses.to_string());
+                            ui.label(stats.evictions.to_string());
+                            ui.end_row();
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    ui.label(locale.cache_budget);
+                    let mut budget = mesh_cache.budget;
+                    let range = 1..=Self::MAX_CACHE_BUDGET;
+                    if ui
+                        .add(egui::DragValue::new(&mut budget).range(range))
+                        .changed()
+                    {
+                        set_mesh_cache_budget(budget);
+                    }
+                });
+            });
+    }
+}
@@ -0,0 +1,175 @@
+//! Adding components by typing their names over the field, like `and3`
+
+use egui::{Event, Key, Modifiers, Pos2};
+
+use crate::{
+    component_lib::get_component_lib,
+    grid_db::{Component, GridPos, PrimitiveType},
+};
+
+/// Max number of shown completions
+const MAX_COMPLETIONS: usize = 8;
+
+/// Gate or mux with `n` inputs, None for other components and unsupported numbers
+fn with_inputs_number(component: &Component, n: usize) -> Option<Component> {
+    let Component::Primitive(primitive) = component else {
+        return None;
+    };
+    let typ = match primitive.typ {
+        PrimitiveType::And(_) => PrimitiveType::And(n),
+        PrimitiveType::Or(_) => PrimitiveType::Or(n),
+        PrimitiveType::Xor(_) => PrimitiveType::Xor(n),
+        PrimitiveType::Nand(_) => PrimitiveType::Nand(n),
+        PrimitiveType::Mux(_) => PrimitiveType::Mux(n),
+        _ => return None,
+    };
+    // Same limits as in the customization dialog:
+    if !(2..=PrimitiveType::get_max_gate_inputs()).contains(&n) {
+        return None;
+    }
+    let mut primitive = primitive.clone();
+    primitive.typ = typ;
+    Some(Component::Primitive(primitive))
+}
+
+/// Library entries matching the typed text, names starting with it go first.
+/// Trailing number sets the inputs number of gates and muxes: `and3` is AND with 3 inputs.
+pub fn get_completions(query: &str) -> Vec<(String, Component)> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return vec![];
+    }
+    let base = query.trim_end_matches(|c: char| c.is_ascii_digit());
+    let number = query[base.len()..].parse::<usize>().ok();
+    let mut prefix_matches = vec![];
+    let mut other_matches = vec![];
+    for entry in get_component_lib().into_iter().flatten() {
+        let name = entry.name.to_lowercase();
+        let entry_base = name.trim_end_matches(|c: char| c.is_ascii_digit());
+        if let Some(n) = number
+            && !base.is_empty()
+            && entry_base.starts_with(base)
+            && let Some(component) = with_inputs_number(&entry.component, n)
+        {
+            prefix_matches.push((format!("{}{n}", entry_base.to_uppercase()), component));
+        } else if name.starts_with(&query) {
+            prefix_matches.push((entry.name.to_owned(), entry.component));
+        } else if name.contains(&query) {
+            other_matches.push((entry.name.to_owned(), entry.component));
+        }
+    }
+    prefix_matches.extend(other_matches);
+    prefix_matches.truncate(MAX_COMPLETIONS);
+    prefix_matches
+}
+
+pub enum QuickAddResponse {
+    Open,
+    Closed,
+    /// Component to be placed at [`QuickAdd::pos`]
+    Chosen(Component),
+}
+
+/// Popup with the typed name and the completions
+pub struct QuickAdd {
+    query: String,
+    /// Cell under the cursor when the typing started
+    pub pos: GridPos,
+    selected: usize,
+}
+
+impl QuickAdd {
+    /// Opens the popup if a letter or a digit was typed
+    pub fn start(ui: &egui::Ui, pos: GridPos) -> Option<Self> {
+        let text = ui.input(|i| {
+            i.events.iter().find_map(|e| match e {
+                Event::Text(text) if text.chars().all(char::is_alphanumeric) => Some(text.clone()),
+                _ => None,
+            })
+        })?;
+        Some(Self {
+            query: text,
+            pos,
+            selected: 0,
+        })
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, screen_pos: Pos2) -> QuickAddResponse {
+        let mut response = QuickAddResponse::Open;
+        egui::Area::new("quick_add".into())
+            .fixed_pos(screen_pos)
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    // Arrows select the completion instead of moving the text cursor:
+                    let (up, down) = ui.input_mut(|i| {
+                        (
+                            i.consume_key(Modifiers::NONE, Key::ArrowUp),
+                            i.consume_key(Modifiers::NONE, Key::ArrowDown),
+                        )
+                    });
+                    let edit = ui.add(
+                        egui::TextEdit::singleline(&mut self.query)
+                            .desired_width(150.0)
+                            .font(egui::TextStyle::Monospace),
+                    );
+                    edit.request_focus();
+                    let completions = get_completions(&self.query);
+                    if down {
+                        self.selected += 1;
+                    }
+                    if up {
+                        self.selected = self.selected.saturating_sub(1);
+                    }
+                    self.selected = self.selected.min(completions.len().saturating_sub(1));
+                    let mut chosen = None;
+                    for (i, (name, _)) in completions.iter().enumerate() {
+                        if ui.selectable_label(i == self.selected, name).clicked() {
+                            chosen = Some(i);
+                        }
+                    }
+                    if ui.input(|i| i.key_pressed(Key::Enter)) && !completions.is_empty() {
+                        chosen = Some(self.selected);
+                    }
+                    if let Some(i) = chosen {
+                        response = QuickAddResponse::Chosen(completions[i].1.clone());
+                    } else if self.query.is_empty()
+                        || ui.input(|i| i.key_pressed(Key::Escape))
+                        || (edit.lost_focus() && !ui.input(|i| i.key_pressed(Key::Enter)))
+                    {
+                        response = QuickAddResponse::Closed;
+                    }
+                });
+            });
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::get_completions;
+    use crate::grid_db::{Component, PrimitiveType};
+
+    fn names(query: &str) -> Vec<String> {
+        get_completions(query)
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    #[test]
+    fn quick_add_completions() {
+        let completions = get_completions("and3");
+        assert_eq!(completions[0].0, "AND3");
+        assert!(matches!(
+            &completions[0].1,
+            Component::Primitive(p) if p.typ == PrimitiveType::And(3)
+        ));
+        assert_eq!(names("mux4"), ["MUX4"]);
+        assert_eq!(names("NOT"), ["NOT"]);
+        // Prefix matches go before other ones:
+        assert_eq!(names("in")[0], "INPUT");
+        assert!(names("and1").is_empty());
+        assert!(names("").is_empty());
+    }
+}
@@ -0,0 +1,112 @@
+//! Batch rename window
+
+use std::collections::HashSet;
+
+use editor_core::rename::{has_counter, rename_instances};
+use egui::RichText;
+
+use crate::{
+    grid_db::{Component, GridDB, Id},
+    locale::Locale,
+};
+
+pub struct RenameWizard {
+    pub is_open: bool,
+    pattern: String,
+    start: usize,
+    step: usize,
+}
+
+impl Default for RenameWizard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RenameWizard {
+    pub fn new() -> Self {
+        Self {
+            is_open: false,
+            pattern: "reg_%02d".into(),
+            start: 0,
+            step: 1,
+        }
+    }
+
+    /// Shows the wizard for the selected components,
+    /// returns the renamed ones when the user applies the pattern
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        db: &GridDB,
+        selected: &[Id],
+        locale: &'static Locale,
+    ) -> Option<Vec<(Id, Component)>> {
+        let mut result = None;
+        let mut is_open = self.is_open;
+        egui::Window::new(locale.rename_instances)
+            .id("rename_instances".into())
+            .open(&mut is_open)
+            .default_width(250.0)
+            .show(ctx, |ui| {
+                egui::Grid::new("rename_instances_pattern")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label(locale.name_pattern);
+                        ui.text_edit_singleline(&mut self.pattern);
+                        ui.end_row();
+                        ui.label(locale.start_number);
+                        ui.add(egui::DragValue::new(&mut self.start));
+                        ui.end_row();
+                        ui.label(locale.step);
+                        ui.add(egui::DragValue::new(&mut self.step).range(1..=1000));
+                        ui.end_row();
+                    });
+                ui.separator();
+                let renamed = rename_instances(db, selected, &self.pattern, self.start, self.step);
+                if renamed.is_empty() {
+                    ui.label(locale.select_instances_hint);
+                    return;
+                }
+                let is_valid = has_counter(&self.pattern);
+                if !is_valid {
+                    ui.colored_label(ui.visuals().error_fg_color, locale.pattern_without_counter);
+                }
+                // Names of the instances that keep them:
+                let used: HashSet<String> = db
+                    .get_component_ids()
+                    .into_iter()
+                    .filter(|id| !selected.contains(id))
+                    .filter_map(|id| db.get_component(&id)?.get_instance_name(id))
+                    .collect();
+                let mut has_conflicts = false;
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        for (id, comp) in &renamed {
+                            let old = db.get_component(id).and_then(|c| c.get_instance_name(*id));
+                            let new = comp.get_instance_name(*id).unwrap_or_default();
+                            let text =
+                                RichText::new(format!("{} → {new}", old.unwrap_or_default()))
+                                    .monospace();
+                            if used.contains(&new) {
+                                has_conflicts = true;
+                                ui.label(text.color(ui.visuals().error_fg_color))
+                                    .on_hover_text(locale.name_is_used);
+                            } else {
+                                ui.label(text);
+                            }
+                        }
+                    });
+                ui.separator();
+                if ui
+                    .add_enabled(is_valid && !has_conflicts, egui::Button::new(locale.apply))
+                    .clicked()
+                {
+                    result = Some(renamed);
+                }
+            });
+        self.is_open = is_open && result.is_none();
+        result
+    }
+}
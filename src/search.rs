@@ -0,0 +1,197 @@
+//! Find and replace window
+
+use editor_core::search::{SearchField, SearchQuery, SearchResult};
+use egui::RichText;
+use regex_lite::Regex;
+
+use crate::{
+    grid_db::{Component, GridDB, GridPos, Id, Revision},
+    locale::Locale,
+    settings::GetName,
+};
+
+impl GetName for SearchField {
+    fn get_name(&self, locale: &'static Locale) -> &'static str {
+        match self {
+            Self::InstanceName => locale.instance_name,
+            Self::PortName => locale.port,
+            Self::ParameterName | Self::ParameterValue => locale.parameters,
+            Self::Text => locale.text,
+        }
+    }
+}
+
+/// Action chosen by user in the replace window
+pub enum SearchAction {
+    Focus(GridPos),
+    /// Replace the components, all together as one edit
+    Replace(Vec<(Id, Component)>),
+}
+
+pub struct SearchPanel {
+    pub is_open: bool,
+    query: SearchQuery,
+    // Compiled pattern with the query it was made of, kept until the pattern is edited
+    compiled: Option<(SearchQuery, Result<Option<Regex>, String>)>,
+    // Matches of the query in the schematic revision, not searched again every frame
+    result: Option<(SearchQuery, Revision, SearchResult)>,
+}
+
+impl Default for SearchPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SearchPanel {
+    pub fn new() -> Self {
+        Self {
+            is_open: false,
+            query: SearchQuery::default(),
+            compiled: None,
+            result: None,
+        }
+    }
+
+    /// Matches of the query, searched again after the query or the schematic is changed
+    fn get_result(&mut self, db: &GridDB) -> Result<&SearchResult, String> {
+        let query = &self.query;
+        if !(self.compiled.as_ref())
+            .is_some_and(|(q, _)| q.pattern == query.pattern && q.regex == query.regex)
+        {
+            self.compiled = None;
+        }
+        let (_, regex) = self.compiled.get_or_insert_with(|| {
+            let regex = query.compile().map_err(|err| err.to_string());
+            (query.clone(), regex)
+        });
+        let regex = regex.as_ref().map_err(Clone::clone)?;
+        let revision = db.get_revision();
+        if !(self.result.as_ref()).is_some_and(|(q, r, _)| q == query && *r == revision) {
+            self.result = None;
+        }
+        let (_, _, result) = self.result.get_or_insert_with(|| {
+            let result = query.search_compiled(db, regex.as_ref());
+            (query.clone(), revision, result)
+        });
+        Ok(result)
+    }
+
+    /// Shows the replace window with the preview of the matches
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        db: &GridDB,
+        locale: &'static Locale,
+    ) -> Option<SearchAction> {
+        let mut action = None;
+        let mut is_open = self.is_open;
+        egui::Window::new(locale.replace)
+            .id("search_replace".into())
+            .open(&mut is_open)
+            .default_width(300.0)
+            .show(ctx, |ui| {
+                egui::Grid::new("search_replace_query")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label(locale.find);
+                        ui.text_edit_singleline(&mut self.query.pattern);
+                        ui.end_row();
+                        ui.label(locale.replace_with);
+                        ui.text_edit_singleline(&mut self.query.replacement);
+                        ui.end_row();
+                    });
+                ui.checkbox(&mut self.query.regex, locale.regular_expression);
+                ui.separator();
+                let result = match self.get_result(db) {
+                    Ok(result) => result,
+                    Err(err) => {
+                        ui.colored_label(ui.visuals().error_fg_color, err);
+                        return;
+                    }
+                };
+                if ui
+                    .add_enabled(
+                        !result.matches.is_empty(),
+                        egui::Button::new(format!(
+                            "{} ({})",
+                            locale.replace_all,
+                            result.matches.len()
+                        )),
+                    )
+                    .clicked()
+                {
+                    action = Some(SearchAction::Replace(result.replaced.clone()));
+                    return;
+                }
+                if let Some((name, item)) = &result.path {
+                    let text = format!("{}: {name}", locale.hierarchical_name);
+                    if ui
+                        .add(
+                            egui::Label::new(RichText::new(text).monospace())
+                                .selectable(false)
+                                .sense(egui::Sense::click()),
+                        )
+                        .clicked()
+                    {
+                        action = Some(SearchAction::Focus(item.pos));
+                    }
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for m in &result.matches {
+                        let text = format!(
+                            "{} {}: {} → {}",
+                            m.name,
+                            m.field.get_name(locale),
+                            m.text,
+                            m.replaced
+                        );
+                        if ui
+                            .add(
+                                egui::Label::new(RichText::new(text).monospace())
+                                    .selectable(false)
+                                    .sense(egui::Sense::click()),
+                            )
+                            .clicked()
+                        {
+                            action = Some(SearchAction::Focus(m.pos));
+                        }
+                    }
+                });
+            });
+        self.is_open = is_open;
+        action
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SearchPanel;
+    use crate::{
+        grid_db::{Component, TextField, grid_pos},
+        testing::half_adder,
+    };
+
+    #[test]
+    fn cached_search_result() {
+        let mut panel = SearchPanel::new();
+        panel.query.pattern = "adder".into();
+        let mut db = half_adder();
+        assert_eq!(panel.get_result(&db).unwrap().matches.len(), 1);
+        let id = db.allocate_component();
+        db.insert_component(
+            id,
+            Component::TextField(TextField {
+                pos: grid_pos(0, 20),
+                size: (4, 1),
+                text: "full adder".into(),
+                anchor: None,
+            }),
+        );
+        // Searched again in the changed schematic:
+        assert_eq!(panel.get_result(&db).unwrap().matches.len(), 2);
+        panel.query.regex = true;
+        panel.query.pattern = "(".into();
+        assert!(panel.get_result(&db).is_err());
+    }
+}
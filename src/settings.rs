@@ -1,9 +1,20 @@
+use editor_core::{macros::Macro, templates::Template};
 use egui::Theme;
 use serde::{Deserialize, Serialize};
 
+#[cfg(not(target_arch = "wasm32"))]
+use crate::workspace::WorkspaceSettings;
 use crate::{
-    field::GridType,
+    component_lib::UserLibrary,
+    components_panel::PaletteSettings,
+    field::{GridType, WheelMode},
+    grid_db::{
+        CornerStyle, DEFAULT_MESH_CACHE_BUDGET, ExportLayer, NetColorMode, NetStyle, PrimitiveType,
+        Severity,
+    },
     locale::{Locale, LocaleType, get_system_default_locale},
+    shortcuts::ShortcutMap,
+    statistics::UsageStatistics,
 };
 
 #[derive(Clone, Copy, Serialize, Deserialize)]
@@ -30,11 +41,119 @@ impl Into<Theme> for ThemeWrapper {
     }
 }
 
+/// Menu bar is collapsed into one menu on narrow screens
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum MenuLayout {
+    #[default]
+    Auto,
+    Full,
+    Compact,
+}
+
+/// Touch layout has a bottom toolbar, zoom buttons and the components drawer
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum LayoutMode {
+    /// Touch layout after the first touch event
+    #[default]
+    Auto,
+    Desktop,
+    Touch,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct AppSettings {
     pub theme: ThemeWrapper,
     pub grid_type: GridType,
     pub locale: LocaleType,
+    #[serde(default)]
+    pub unconnected_visible: bool,
+    #[serde(default)]
+    pub net_style: NetStyle,
+    #[serde(default)]
+    pub bundle_nets: bool,
+    #[serde(default)]
+    pub length_heatmap_visible: bool,
+    #[serde(default)]
+    pub net_legend_visible: bool,
+    #[serde(default = "default_auto_place")]
+    pub auto_place: bool,
+    /// Texts are attached to the nearby components and moved along with them
+    #[serde(default = "default_snap_annotations")]
+    pub snap_annotations: bool,
+    #[serde(default)]
+    pub wheel_mode: WheelMode,
+    #[serde(default = "default_save_view")]
+    pub save_view: bool,
+    #[serde(default)]
+    pub shortcuts: ShortcutMap,
+    #[serde(default)]
+    pub libraries: Vec<UserLibrary>,
+    #[serde(default)]
+    pub menu_layout: MenuLayout,
+    #[serde(default)]
+    pub layout_mode: LayoutMode,
+    #[serde(default)]
+    pub palette: PaletteSettings,
+    #[serde(default)]
+    pub statistics: UsageStatistics,
+    /// Parameters of the new primitives, one per type
+    #[serde(default)]
+    pub component_defaults: Vec<PrimitiveType>,
+    #[serde(default)]
+    pub templates: Vec<Template>,
+    #[serde(default)]
+    pub macros: Vec<Macro>,
+    /// Thumbnails are saved with the projects for the list of the recent projects
+    #[serde(default = "default_thumbnails")]
+    pub thumbnails: bool,
+    #[serde(default)]
+    pub recent_projects: Vec<String>,
+    /// LOD thresholds follow the drawing time
+    #[serde(default = "default_adaptive_lod")]
+    pub adaptive_lod: bool,
+    /// Max number of cached symbol meshes
+    #[serde(default = "default_mesh_cache_budget")]
+    pub mesh_cache_budget: usize,
+    /// Inputs limit of gates and muxes
+    #[serde(default = "default_max_gate_inputs")]
+    pub max_gate_inputs: usize,
+    /// Version of the last run, the changelog is shown when it changes
+    #[serde(default)]
+    pub last_seen_version: String,
+    /// Opt-in check of the newer releases
+    #[serde(default)]
+    pub check_updates: bool,
+    #[cfg(not(target_arch = "wasm32"))]
+    #[serde(default)]
+    pub workspace: WorkspaceSettings,
+}
+
+fn default_auto_place() -> bool {
+    true
+}
+
+fn default_snap_annotations() -> bool {
+    true
+}
+
+fn default_save_view() -> bool {
+    true
+}
+
+fn default_thumbnails() -> bool {
+    true
+}
+
+fn default_adaptive_lod() -> bool {
+    true
+}
+
+fn default_mesh_cache_budget() -> usize {
+    DEFAULT_MESH_CACHE_BUDGET
+}
+
+fn default_max_gate_inputs() -> usize {
+    PrimitiveType::DEFAULT_MAX_GATE_INPUTS
 }
 
 impl Default for AppSettings {
@@ -43,6 +162,33 @@ impl Default for AppSettings {
             locale: get_system_default_locale(),
             theme: ThemeWrapper::Dark,
             grid_type: GridType::Cells,
+            unconnected_visible: false,
+            net_style: NetStyle::DEFAULT,
+            bundle_nets: false,
+            length_heatmap_visible: false,
+            net_legend_visible: false,
+            auto_place: default_auto_place(),
+            snap_annotations: default_snap_annotations(),
+            wheel_mode: WheelMode::default(),
+            save_view: default_save_view(),
+            shortcuts: ShortcutMap::default(),
+            libraries: vec![],
+            menu_layout: MenuLayout::Auto,
+            layout_mode: LayoutMode::Auto,
+            palette: PaletteSettings::default(),
+            statistics: UsageStatistics::default(),
+            component_defaults: vec![],
+            templates: vec![],
+            macros: vec![],
+            thumbnails: default_thumbnails(),
+            recent_projects: vec![],
+            adaptive_lod: default_adaptive_lod(),
+            mesh_cache_budget: default_mesh_cache_budget(),
+            max_gate_inputs: default_max_gate_inputs(),
+            last_seen_version: String::new(),
+            check_updates: false,
+            #[cfg(not(target_arch = "wasm32"))]
+            workspace: WorkspaceSettings::default(),
         }
     }
 }
@@ -61,3 +207,79 @@ impl GetName for Theme {
         }
     }
 }
+
+impl GetName for CornerStyle {
+    fn get_name(&self, locale: &'static Locale) -> &'static str {
+        match self {
+            Self::Square => locale.square_corners,
+            Self::Round => locale.round_corners,
+        }
+    }
+}
+
+impl GetName for NetColorMode {
+    fn get_name(&self, locale: &'static Locale) -> &'static str {
+        match self {
+            Self::NetClass => locale.net_class_colors,
+            Self::Uniform => locale.uniform_net_color,
+            Self::SimulationValues => locale.simulation_values,
+        }
+    }
+}
+
+impl GetName for Severity {
+    fn get_name(&self, locale: &'static Locale) -> &'static str {
+        match self {
+            Self::Ignore => locale.severity_ignore,
+            Self::Warning => locale.severity_warning,
+            Self::Error => locale.severity_error,
+        }
+    }
+}
+
+impl GetName for ExportLayer {
+    fn get_name(&self, locale: &'static Locale) -> &'static str {
+        match self {
+            Self::Notes => locale.notes,
+            Self::Assertions => locale.assertions,
+        }
+    }
+}
+
+impl GetName for GridType {
+    fn get_name(&self, locale: &'static Locale) -> &'static str {
+        match self {
+            Self::Cells => locale.cells,
+            Self::Dots => locale.dots,
+            Self::None => locale.empty,
+        }
+    }
+}
+
+pub const SUPPORTED_CORNER_STYLES: &[CornerStyle] = &[CornerStyle::Square, CornerStyle::Round];
+
+impl GetName for MenuLayout {
+    fn get_name(&self, locale: &'static Locale) -> &'static str {
+        match self {
+            Self::Auto => locale.menu_auto,
+            Self::Full => locale.menu_full,
+            Self::Compact => locale.menu_compact,
+        }
+    }
+}
+
+pub const SUPPORTED_MENU_LAYOUTS: &[MenuLayout] =
+    &[MenuLayout::Auto, MenuLayout::Full, MenuLayout::Compact];
+
+impl GetName for LayoutMode {
+    fn get_name(&self, locale: &'static Locale) -> &'static str {
+        match self {
+            Self::Auto => locale.menu_auto,
+            Self::Desktop => locale.layout_desktop,
+            Self::Touch => locale.layout_touch,
+        }
+    }
+}
+
+pub const SUPPORTED_LAYOUT_MODES: &[LayoutMode] =
+    &[LayoutMode::Auto, LayoutMode::Desktop, LayoutMode::Touch];
@@ -0,0 +1,74 @@
+use egui::{Key, KeyboardShortcut, Modifiers};
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum ShortcutAction {
+    Save,
+    SaveAs,
+    ExportSVG,
+    ExportPNG,
+}
+
+/// Keyboard shortcuts of the application actions, stored in the settings
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ShortcutMap {
+    shortcuts: Vec<(ShortcutAction, KeyboardShortcut)>,
+}
+
+impl Default for ShortcutMap {
+    fn default() -> Self {
+        let ctrl_shift = Modifiers::CTRL.plus(Modifiers::SHIFT);
+        Self {
+            shortcuts: vec![
+                (
+                    ShortcutAction::Save,
+                    KeyboardShortcut::new(Modifiers::CTRL, Key::S),
+                ),
+                (
+                    ShortcutAction::SaveAs,
+                    KeyboardShortcut::new(ctrl_shift, Key::S),
+                ),
+                (
+                    ShortcutAction::ExportSVG,
+                    KeyboardShortcut::new(Modifiers::CTRL, Key::E),
+                ),
+                (
+                    ShortcutAction::ExportPNG,
+                    KeyboardShortcut::new(ctrl_shift, Key::E),
+                ),
+            ],
+        }
+    }
+}
+
+impl ShortcutMap {
+    pub fn get(&self, action: ShortcutAction) -> Option<&KeyboardShortcut> {
+        self.shortcuts
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, shortcut)| shortcut)
+    }
+
+    /// Text of the shortcut for menu buttons
+    pub fn format(&self, ctx: &egui::Context, action: ShortcutAction) -> String {
+        self.get(action)
+            .map(|shortcut| ctx.format_shortcut(shortcut))
+            .unwrap_or_default()
+    }
+
+    /// Consumes the pressed shortcut and returns its action
+    pub fn consume(&self, ctx: &egui::Context) -> Option<ShortcutAction> {
+        // Shortcuts with more modifiers go first: Ctrl+S also matches pressed Ctrl+Shift+S
+        let mut shortcuts: Vec<_> = self.shortcuts.iter().collect();
+        shortcuts.sort_by_key(|(_, shortcut)| {
+            let m = shortcut.modifiers;
+            std::cmp::Reverse(m.alt as u8 + m.ctrl as u8 + m.shift as u8 + m.command as u8)
+        });
+        ctx.input_mut(|input| {
+            shortcuts
+                .iter()
+                .find(|(_, shortcut)| input.consume_shortcut(shortcut))
+                .map(|(action, _)| *action)
+        })
+    }
+}
@@ -0,0 +1,215 @@
+//! Simulation toolbar
+
+use editor_core::simulation::Simulator;
+use web_time::Instant;
+
+use crate::{canvas::PainterCanvas, field::FieldState, grid_db::GridDB, locale::Locale};
+
+/// Request of the toolbar to save or load the simulation state file
+pub enum SimulationFileAction {
+    Save(String),
+    Load,
+}
+
+/// Toolbar controlling the simulation
+pub struct SimulationPanel {
+    pub is_open: bool,
+    pub simulator: Simulator,
+    running: bool,
+    /// Steps per second while running
+    speed: f32,
+    /// Clocks are toggled by the wall clock instead of each time the circuit becomes stable
+    real_time_clock: bool,
+    /// Clock frequency in the real-time mode, Hz
+    clock_frequency: f32,
+    /// Nets are colored by the number of toggles instead of the values
+    activity_heatmap: bool,
+    last_update: Instant,
+    // Fractions of steps and clock half-periods left from the previous frames
+    pending_steps: f32,
+    pending_clock: f32,
+    /// State of the unloaded simulation of the background project
+    unloaded_state: Option<String>,
+}
+
+impl Default for SimulationPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimulationPanel {
+    const MAX_SPEED: f32 = 10000.0;
+    const MAX_CLOCK_FREQUENCY: f32 = 50.0;
+
+    pub fn new() -> Self {
+        Self {
+            is_open: false,
+            simulator: Simulator::default(),
+            running: false,
+            speed: 10.0,
+            real_time_clock: false,
+            clock_frequency: 1.0,
+            activity_heatmap: false,
+            last_update: Instant::now(),
+            pending_steps: 0.0,
+            pending_clock: 0.0,
+            unloaded_state: None,
+        }
+    }
+
+    /// Frees the simulation when the project is moved to a background tab
+    pub fn unload(&mut self, db: &GridDB) {
+        self.unloaded_state = self.simulator.unload(db);
+    }
+
+    /// Restores the unloaded simulation when the project becomes active
+    pub fn reload(&mut self, db: &GridDB) {
+        if let Some(state) = self.unloaded_state.take() {
+            _ = self.simulator.load_state(db, &state);
+        }
+        self.last_update = Instant::now();
+    }
+
+    /// Draws the simulation state over the field and handles clicks on the interactive inputs
+    pub fn show_on_field(&mut self, db: &GridDB, state: &FieldState, ui: &egui::Ui) {
+        if !self.is_open {
+            return;
+        }
+        let (pos, pressed, released) = ui.input(|i| {
+            (
+                i.pointer.interact_pos(),
+                i.pointer.primary_pressed(),
+                i.pointer.primary_released(),
+            )
+        });
+        if self
+            .simulator
+            .handle_pointer(db, state, pos, pressed, released)
+            && !self.running
+        {
+            self.simulator.run_until_stable(db);
+        }
+        let canvas = PainterCanvas(ui.painter().with_clip_rect(state.rect));
+        if self.activity_heatmap {
+            self.simulator.draw_activity(db, state, &canvas);
+        } else {
+            self.simulator.draw(db, state, &canvas);
+        }
+    }
+
+    /// Shows the toolbar and advances the running simulation.
+    /// Must be called before the central panel.
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        db: &GridDB,
+        locale: &'static Locale,
+    ) -> Option<SimulationFileAction> {
+        if !self.is_open {
+            self.running = false;
+            return None;
+        }
+        let mut action = None;
+        egui::TopBottomPanel::top("simulation_panel").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                let text = if self.running {
+                    locale.sim_pause
+                } else {
+                    locale.sim_run
+                };
+                if ui.button(text).clicked() {
+                    self.running = !self.running;
+                    self.last_update = Instant::now();
+                }
+                ui.add_enabled_ui(!self.running, |ui| {
+                    if ui.button(locale.sim_step).clicked() {
+                        self.simulator.step(db);
+                    }
+                    if ui.button(locale.run_until_stable).clicked() {
+                        self.simulator.run_until_stable(db);
+                    }
+                });
+                if ui.button(locale.sim_reset).clicked() {
+                    self.simulator.reset();
+                }
+                ui.separator();
+                if ui.button(locale.save_sim_state).clicked() {
+                    action = self
+                        .simulator
+                        .dump_state(db)
+                        .map(SimulationFileAction::Save);
+                }
+                if ui.button(locale.load_sim_state).clicked() {
+                    action = Some(SimulationFileAction::Load);
+                }
+                ui.separator();
+                ui.add(
+                    egui::Slider::new(&mut self.speed, 1.0..=Self::MAX_SPEED)
+                        .logarithmic(true)
+                        .integer()
+                        .text(locale.steps_per_second),
+                );
+                ui.checkbox(&mut self.real_time_clock, locale.real_time_clock);
+                ui.add_enabled(
+                    self.real_time_clock,
+                    egui::DragValue::new(&mut self.clock_frequency)
+                        .range(0.1..=Self::MAX_CLOCK_FREQUENCY)
+                        .speed(0.1)
+                        .suffix(" Hz"),
+                );
+                ui.separator();
+                ui.checkbox(&mut self.activity_heatmap, locale.activity_heatmap);
+                ui.separator();
+                ui.label(format!(
+                    "{}: {}",
+                    locale.sim_steps,
+                    self.simulator.get_steps()
+                ));
+                let violations = self.simulator.get_violations(db).len();
+                if violations > 0 {
+                    ui.separator();
+                    ui.colored_label(
+                        ui.visuals().error_fg_color,
+                        format!("{}: {}", locale.assertion_failed, violations),
+                    );
+                }
+            });
+        });
+        self.update(ctx, db);
+        action
+    }
+
+    fn update(&mut self, ctx: &egui::Context, db: &GridDB) {
+        let now = Instant::now();
+        let dt = (now - self.last_update).as_secs_f32();
+        self.last_update = now;
+        if !self.running {
+            return;
+        }
+        if self.real_time_clock {
+            self.pending_clock += dt * self.clock_frequency * 2.0;
+            while self.pending_clock >= 1.0 {
+                self.simulator.toggle_clocks(db);
+                self.pending_clock -= 1.0;
+            }
+        }
+        // Long frames are not caught up, otherwise the UI freezes:
+        self.pending_steps = (self.pending_steps + dt * self.speed).min(Self::MAX_SPEED * 0.1);
+        while self.pending_steps >= 1.0 {
+            self.pending_steps -= 1.0;
+            if !self.simulator.step(db) {
+                // Assertions are checked when the circuit is stable, to skip glitches:
+                if !self.simulator.get_violations(db).is_empty() {
+                    self.running = false;
+                    self.pending_steps = 0.0;
+                    break;
+                }
+                if !self.real_time_clock {
+                    self.simulator.toggle_clocks(db);
+                }
+            }
+        }
+        ctx.request_repaint();
+    }
+}
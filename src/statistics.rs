@@ -0,0 +1,138 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use web_time::{Duration, Instant};
+
+use crate::{
+    grid_db::{GridDB, Revision},
+    locale::Locale,
+};
+
+/// Usage statistics, collected only if enabled by the user and kept in the local settings.
+/// They are never sent anywhere, the user may copy them into a bug report.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct UsageStatistics {
+    pub enabled: bool,
+    /// Seconds of the active use
+    pub time_in_app: f64,
+    pub sessions: u32,
+    /// Number of uses of the commands by their names
+    pub commands: BTreeMap<String, u32>,
+    /// Max number of components in an opened project
+    pub largest_design: usize,
+}
+
+impl UsageStatistics {
+    fn format_time(seconds: f64) -> String {
+        let minutes = (seconds / 60.0) as u64;
+        format!("{}h {:02}m", minutes / 60, minutes % 60)
+    }
+
+    /// Rows of the statistics page, also copied as text
+    fn get_rows(&self, locale: &'static Locale) -> Vec<(&str, String)> {
+        let mut rows = vec![
+            (locale.time_in_app, Self::format_time(self.time_in_app)),
+            (locale.sessions, self.sessions.to_string()),
+            (locale.largest_design, self.largest_design.to_string()),
+        ];
+        rows.extend(
+            self.commands
+                .iter()
+                .map(|(name, count)| (name.as_str(), count.to_string())),
+        );
+        rows
+    }
+
+    pub fn to_text(&self, locale: &'static Locale) -> String {
+        self.get_rows(locale)
+            .iter()
+            .map(|(name, value)| format!("{name}: {value}\n"))
+            .collect()
+    }
+}
+
+/// Collects the statistics while the editor is running
+pub struct StatisticsWindow {
+    pub is_open: bool,
+    pub statistics: UsageStatistics,
+    last_frame: Option<Instant>,
+    revision: Option<Revision>,
+}
+
+impl StatisticsWindow {
+    /// Longer time between frames is not counted, the editor was left in the background
+    const MAX_FRAME_GAP: Duration = Duration::from_secs(60);
+
+    pub fn new(mut statistics: UsageStatistics) -> Self {
+        if statistics.enabled {
+            statistics.sessions += 1;
+        }
+        Self {
+            is_open: false,
+            statistics,
+            last_frame: None,
+            revision: None,
+        }
+    }
+
+    pub fn update(&mut self, db: &GridDB) {
+        if !self.statistics.enabled {
+            self.last_frame = None;
+            return;
+        }
+        let now = Instant::now();
+        if let Some(last_frame) = self.last_frame {
+            let elapsed = now - last_frame;
+            if elapsed < Self::MAX_FRAME_GAP {
+                self.statistics.time_in_app += elapsed.as_secs_f64();
+            }
+        }
+        self.last_frame = Some(now);
+        let revision = db.get_revision();
+        if self.revision != Some(revision) {
+            self.revision = Some(revision);
+            let size = db.get_component_ids().len();
+            self.statistics.largest_design = self.statistics.largest_design.max(size);
+        }
+    }
+
+    pub fn record_command(&mut self, name: &str) {
+        if self.statistics.enabled {
+            *self.statistics.commands.entry(name.to_owned()).or_default() += 1;
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, locale: &'static Locale) {
+        let mut is_open = self.is_open;
+        egui::Window::new(locale.usage_statistics)
+            .id("usage_statistics".into())
+            .open(&mut is_open)
+            .show(ctx, |ui| {
+                ui.checkbox(&mut self.statistics.enabled, locale.collect_statistics);
+                ui.label(locale.statistics_are_local);
+                ui.separator();
+                egui::Grid::new("usage_statistics_grid")
+                    .num_columns(2)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        for (name, value) in self.statistics.get_rows(locale) {
+                            ui.label(name);
+                            ui.label(value);
+                            ui.end_row();
+                        }
+                    });
+                ui.horizontal(|ui| {
+                    if ui.button(locale.copy).clicked() {
+                        ctx.copy_text(self.statistics.to_text(locale));
+                    }
+                    if ui.button(locale.reset).clicked() {
+                        self.statistics = UsageStatistics {
+                            enabled: self.statistics.enabled,
+                            ..Default::default()
+                        };
+                    }
+                });
+            });
+        self.is_open = is_open;
+    }
+}
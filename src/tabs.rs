@@ -0,0 +1,279 @@
+//! Projects opened in tabs. The active project is kept by the app,
+//! the background ones are stored here with their own fields and undo history.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    field::Field,
+    grid_db::{GridDB, Revision},
+    locale::Locale,
+    problems::ProblemsPanel,
+    search::SearchPanel,
+    simulation::SimulationPanel,
+    truth_table::TruthTableWindow,
+};
+
+/// Name of the new projects
+pub const UNTITLED: &str = "Untitled";
+
+pub struct ProjectTab {
+    pub field: Field,
+    pub file_name: String,
+    /// Path used by "Save" without the dialog
+    pub file_path: Option<String>,
+    pub simulation_panel: SimulationPanel,
+    pub problems_panel: ProblemsPanel,
+    pub search_panel: SearchPanel,
+    pub truth_table: TruthTableWindow,
+    /// Revision of the project when it was opened or saved, None if it was never saved
+    pub saved_revision: Option<Revision>,
+}
+
+impl ProjectTab {
+    pub fn new() -> Self {
+        let field = Field::new();
+        let saved_revision = Some(field.grid_db.get_revision());
+        Self {
+            field,
+            file_name: UNTITLED.into(),
+            file_path: None,
+            simulation_panel: SimulationPanel::new(),
+            problems_panel: ProblemsPanel::new(),
+            search_panel: SearchPanel::new(),
+            truth_table: TruthTableWindow::new(),
+            saved_revision,
+        }
+    }
+
+    /// The project was changed after it was opened or saved
+    pub fn is_modified(&self) -> bool {
+        self.saved_revision != Some(self.field.grid_db.get_revision())
+    }
+
+    /// Frees the caches and the simulation of the project moved to the background
+    fn unload(&mut self) {
+        self.field.unload_caches();
+        self.simulation_panel.unload(&self.field.grid_db);
+    }
+
+    fn reload(mut self) -> Self {
+        self.simulation_panel.reload(&self.field.grid_db);
+        self
+    }
+
+    fn save(&mut self) -> Option<TabSession> {
+        self.field.store_view();
+        Some(TabSession {
+            file_name: self.file_name.clone(),
+            file_path: self.file_path.clone(),
+            project: self.field.grid_db.dump_to_json()?,
+            modified: self.is_modified(),
+        })
+    }
+
+    /// None if the project is not loaded
+    fn restore(session: TabSession) -> Option<Self> {
+        let mut tab = Self::new();
+        tab.file_name = session.file_name;
+        tab.field.grid_db = GridDB::load_from_json(session.project).ok()?;
+        tab.file_path = session.file_path;
+        tab.saved_revision = (!session.modified).then(|| tab.field.grid_db.get_revision());
+        Some(tab)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct TabSession {
+    file_name: String,
+    file_path: Option<String>,
+    project: String,
+    #[serde(default)]
+    modified: bool,
+}
+
+/// Open projects, restored on the next start
+#[derive(Default, Serialize, Deserialize)]
+pub struct Session {
+    tabs: Vec<TabSession>,
+    active: usize,
+}
+
+pub enum TabAction {
+    Select(usize),
+    Close(usize),
+    New,
+}
+
+#[derive(Default)]
+pub struct Tabs {
+    /// Background tabs in the order of the tab bar, the active one is not included
+    background: Vec<ProjectTab>,
+    /// Position of the active tab in the tab bar
+    active: usize,
+}
+
+impl Tabs {
+    pub fn len(&self) -> usize {
+        self.background.len() + 1
+    }
+
+    /// Position of the active tab in the tab bar
+    pub fn get_active(&self) -> usize {
+        self.active
+    }
+
+    /// Tab at `index` of the tab bar, None for the active one
+    pub fn get_background(&self, index: usize) -> Option<&ProjectTab> {
+        match index.cmp(&self.active) {
+            std::cmp::Ordering::Less => self.background.get(index),
+            std::cmp::Ordering::Equal => None,
+            std::cmp::Ordering::Greater => self.background.get(index - 1),
+        }
+    }
+
+    /// Makes the tab at `index` active, the previously active one is moved to the background
+    pub fn select(&mut self, index: usize, mut active: ProjectTab) -> ProjectTab {
+        active.unload();
+        self.background.insert(self.active, active);
+        self.active = index;
+        self.background.remove(index).reload()
+    }
+
+    /// Moves the active tab to the background, the new tab is the last one
+    pub fn push(&mut self, mut active: ProjectTab) {
+        active.unload();
+        self.background.insert(self.active, active);
+        self.active = self.background.len();
+    }
+
+    /// Closes the tab at `index`. If it's the active one, its neighbor becomes active
+    /// and is returned. The last tab is not closed.
+    pub fn close(&mut self, index: usize) -> Option<ProjectTab> {
+        if self.background.is_empty() {
+            return None;
+        }
+        if index != self.active {
+            let background_index = if index < self.active {
+                index
+            } else {
+                index - 1
+            };
+            self.background.remove(background_index);
+            if index < self.active {
+                self.active -= 1;
+            }
+            return None;
+        }
+        if self.active == self.background.len() {
+            self.active -= 1;
+        }
+        Some(self.background.remove(self.active).reload())
+    }
+
+    /// Tab bar, the active tab is named `active_name`
+    pub fn show(
+        &self,
+        ui: &mut egui::Ui,
+        active_name: &str,
+        locale: &'static Locale,
+    ) -> Option<TabAction> {
+        let mut action = None;
+        ui.horizontal(|ui| {
+            for i in 0..self.len() {
+                let name = match i.cmp(&self.active) {
+                    std::cmp::Ordering::Less => &self.background[i].file_name,
+                    std::cmp::Ordering::Equal => active_name,
+                    std::cmp::Ordering::Greater => &self.background[i - 1].file_name,
+                };
+                let name = if name.is_empty() { UNTITLED } else { name };
+                if ui.selectable_label(i == self.active, name).clicked() && i != self.active {
+                    action = Some(TabAction::Select(i));
+                }
+                if self.len() > 1 && ui.small_button("×").clicked() {
+                    action = Some(TabAction::Close(i));
+                }
+                ui.separator();
+            }
+            if ui.small_button("+").on_hover_text(locale.new_tab).clicked() {
+                action = Some(TabAction::New);
+            }
+        });
+        action
+    }
+
+    /// Session of all tabs, projects failed to be saved are skipped
+    pub fn save_session(&mut self, active: &mut ProjectTab) -> Session {
+        let mut tabs = vec![];
+        let mut active_index = 0;
+        for (i, tab) in self.background.iter_mut().enumerate() {
+            if i == self.active {
+                active_index = tabs.len();
+                tabs.extend(active.save());
+            }
+            tabs.extend(tab.save());
+        }
+        if self.active == self.background.len() {
+            active_index = tabs.len();
+            tabs.extend(active.save());
+        }
+        Session {
+            tabs,
+            active: active_index,
+        }
+    }
+
+    /// Tabs of the session and the active tab, None if no project is restored
+    pub fn restore_session(session: Session) -> Option<(Self, ProjectTab)> {
+        let mut background = vec![];
+        let mut active = None;
+        for (i, tab) in session.tabs.into_iter().enumerate() {
+            let Some(tab) = ProjectTab::restore(tab) else {
+                continue;
+            };
+            if i == session.active {
+                active = Some((background.len(), tab));
+            } else {
+                background.push(tab);
+            }
+        }
+        let (index, active) = match active {
+            Some(active) => active,
+            None => {
+                let active = background.pop()?;
+                (background.len(), active)
+            }
+        };
+        Some((
+            Self {
+                background,
+                active: index,
+            },
+            active,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ProjectTab, Session, Tabs};
+
+    #[test]
+    fn modified_tabs_stay_modified_in_session() {
+        let mut tabs = Tabs::default();
+        let mut modified = ProjectTab::new();
+        assert!(!modified.is_modified());
+        // Never saved:
+        modified.saved_revision = None;
+        assert!(modified.is_modified());
+        tabs.push(modified);
+        let mut active = ProjectTab::new();
+        let session = tabs.save_session(&mut active);
+        let json = serde_json::to_string(&session).unwrap();
+        let session: Session = serde_json::from_str(&json).unwrap();
+
+        let (tabs, active) = Tabs::restore_session(session).unwrap();
+        assert!(!active.is_modified());
+        assert!(tabs.get_background(0).unwrap().is_modified());
+        assert!(tabs.get_background(tabs.get_active()).is_none());
+    }
+}
@@ -0,0 +1,169 @@
+//! Templates window: saving the selection as a template and placing the expanded ones
+
+use editor_core::templates::{Template, TemplateParameter};
+
+use crate::{
+    grid_db::{GridDB, Id},
+    locale::Locale,
+};
+
+pub struct TemplatesWindow {
+    pub is_open: bool,
+    pub templates: Vec<Template>,
+    /// Template of the selection being saved
+    name: String,
+    parameters: Vec<TemplateParameter>,
+    repeat: String,
+    /// Template being placed and its parameter values
+    placing: Option<(usize, Vec<i64>)>,
+}
+
+impl TemplatesWindow {
+    pub fn new(templates: Vec<Template>) -> Self {
+        Self {
+            is_open: false,
+            templates,
+            name: String::new(),
+            parameters: vec![],
+            repeat: String::new(),
+            placing: None,
+        }
+    }
+
+    fn show_new_template(
+        &mut self,
+        ui: &mut egui::Ui,
+        db: &GridDB,
+        selected: &[Id],
+        locale: &'static Locale,
+    ) {
+        ui.horizontal(|ui| {
+            ui.label(locale.name);
+            ui.text_edit_singleline(&mut self.name);
+        });
+        let mut removed = None;
+        egui::Grid::new("template_parameters")
+            .num_columns(3)
+            .show(ui, |ui| {
+                for (i, parameter) in self.parameters.iter_mut().enumerate() {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut parameter.name)
+                            .hint_text("N")
+                            .desired_width(80.0),
+                    );
+                    ui.add(egui::DragValue::new(&mut parameter.default));
+                    if ui.button("🗑").clicked() {
+                        removed = Some(i);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(i) = removed {
+            self.parameters.remove(i);
+        }
+        if ui.button(locale.add_parameter).clicked() {
+            self.parameters.push(TemplateParameter {
+                name: String::new(),
+                default: 1,
+            });
+        }
+        egui::ComboBox::from_label(locale.stages_count)
+            .selected_text(&self.repeat)
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.repeat, String::new(), "-");
+                for parameter in &self.parameters {
+                    ui.selectable_value(&mut self.repeat, parameter.name.clone(), &parameter.name);
+                }
+            });
+        let name = self.name.trim();
+        let can_save = !selected.is_empty() && !name.is_empty();
+        if ui
+            .add_enabled(
+                can_save,
+                egui::Button::new(locale.save_selection_as_template),
+            )
+            .clicked()
+        {
+            let mut template = Template::new(name, &db.extract_fragment(selected));
+            template.parameters = self
+                .parameters
+                .drain(..)
+                .map(|p| TemplateParameter {
+                    name: p.name.trim().to_owned(),
+                    ..p
+                })
+                .filter(|p| !p.name.is_empty())
+                .collect();
+            template.repeat = std::mem::take(&mut self.repeat).trim().to_owned();
+            self.templates.push(template);
+            self.name.clear();
+        }
+    }
+
+    /// Shows the templates, returns the expanded fragment to be placed
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        db: &GridDB,
+        selected: &[Id],
+        locale: &'static Locale,
+    ) -> Option<GridDB> {
+        let mut result = None;
+        let mut is_open = self.is_open;
+        egui::Window::new(locale.templates)
+            .id("templates".into())
+            .open(&mut is_open)
+            .default_width(300.0)
+            .show(ctx, |ui| {
+                if let Some((index, values)) = &mut self.placing
+                    && let Some(template) = self.templates.get(*index)
+                {
+                    ui.strong(&template.name);
+                    egui::Grid::new("template_values")
+                        .num_columns(2)
+                        .show(ui, |ui| {
+                            for (parameter, value) in
+                                template.parameters.iter().zip(values.iter_mut())
+                            {
+                                ui.label(&parameter.name);
+                                ui.add(egui::DragValue::new(value));
+                                ui.end_row();
+                            }
+                        });
+                    let mut cancel = false;
+                    ui.horizontal(|ui| {
+                        if ui.button(locale.place).clicked() {
+                            result = Some(template.expand(values));
+                        }
+                        cancel = ui.button(locale.cancel).clicked();
+                    });
+                    if cancel {
+                        self.placing = None;
+                    }
+                    return;
+                }
+                let mut removed = None;
+                for (i, template) in self.templates.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.button(locale.place).clicked() {
+                            self.placing = Some((i, template.get_defaults()));
+                        }
+                        if ui.button("🗑").clicked() {
+                            removed = Some(i);
+                        }
+                        ui.label(&template.name);
+                    });
+                }
+                if let Some(i) = removed {
+                    self.templates.remove(i);
+                }
+                ui.separator();
+                self.show_new_template(ui, db, selected, locale);
+            });
+        if result.is_some() {
+            self.placing = None;
+        }
+        self.is_open = is_open && result.is_none();
+        result
+    }
+}
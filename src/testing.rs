@@ -0,0 +1 @@
+pub use editor_core::testing::*;
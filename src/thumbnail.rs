@@ -0,0 +1,106 @@
+//! Small previews of the projects, shown in the list of the recent projects.
+//! Components are drawn as filled boxes and nets as lines, so no fonts are needed.
+
+use image::{Rgba, RgbaImage};
+
+use crate::grid_db::GridDB;
+
+pub const THUMBNAIL_SIZE: u32 = 128;
+const MARGIN: f32 = 4.0;
+const BACKGROUND: Rgba<u8> = Rgba([255, 255, 255, 255]);
+const COMPONENT_COLOR: Rgba<u8> = Rgba([150, 150, 160, 255]);
+const NET_COLOR: Rgba<u8> = Rgba([40, 40, 40, 255]);
+
+fn fill_rect(image: &mut RgbaImage, (x1, y1): (f32, f32), (x2, y2): (f32, f32), color: Rgba<u8>) {
+    let (w, h) = (image.width() as f32, image.height() as f32);
+    for y in y1.clamp(0.0, h) as u32..y2.ceil().clamp(0.0, h) as u32 {
+        for x in x1.clamp(0.0, w) as u32..x2.ceil().clamp(0.0, w) as u32 {
+            image.put_pixel(x, y, color);
+        }
+    }
+}
+
+fn draw_line(image: &mut RgbaImage, (x1, y1): (f32, f32), (x2, y2): (f32, f32), color: Rgba<u8>) {
+    let steps = (x2 - x1).abs().max((y2 - y1).abs()).ceil().max(1.0);
+    for i in 0..=steps as u32 {
+        let t = i as f32 / steps;
+        let (x, y) = (x1 + (x2 - x1) * t, y1 + (y2 - y1) * t);
+        if x >= 0.0 && y >= 0.0 && (x as u32) < image.width() && (y as u32) < image.height() {
+            image.put_pixel(x as u32, y as u32, color);
+        }
+    }
+}
+
+/// Preview of the whole schematic fit into the square image
+pub fn render_thumbnail(db: &GridDB) -> RgbaImage {
+    let mut image = RgbaImage::from_pixel(THUMBNAIL_SIZE, THUMBNAIL_SIZE, BACKGROUND);
+    let (min, max) = db.get_bounds();
+    let (cols, rows) = ((max.x - min.x + 1) as f32, (max.y - min.y + 1) as f32);
+    let scale = (THUMBNAIL_SIZE as f32 - 2.0 * MARGIN) / cols.max(rows);
+    // The schematic is centered:
+    let ofs_x = (THUMBNAIL_SIZE as f32 - cols * scale) / 2.0;
+    let ofs_y = (THUMBNAIL_SIZE as f32 - rows * scale) / 2.0;
+    let to_image = |x: f32, y: f32| {
+        (
+            ofs_x + (x - min.x as f32) * scale,
+            ofs_y + (y - min.y as f32) * scale,
+        )
+    };
+    for id in db.get_component_ids() {
+        let comp = db.get_component(&id).unwrap();
+        let pos = comp.get_position();
+        let (w, h) = comp.get_dimension();
+        let p1 = to_image(pos.x as f32, pos.y as f32);
+        let p2 = to_image((pos.x + w) as f32, (pos.y + h) as f32);
+        fill_rect(&mut image, p1, p2, COMPONENT_COLOR);
+    }
+    for id in db.get_net_ids() {
+        let points = &db.get_net(&id).unwrap().points;
+        for pair in points.windows(2) {
+            let p1 = to_image(pair[0].x as f32 + 0.5, pair[0].y as f32 + 0.5);
+            let p2 = to_image(pair[1].x as f32 + 0.5, pair[1].y as f32 + 0.5);
+            draw_line(&mut image, p1, p2, NET_COLOR);
+        }
+    }
+    image
+}
+
+/// Thumbnail encoded as PNG
+pub fn encode_thumbnail(db: &GridDB) -> Option<Vec<u8>> {
+    let mut png = vec![];
+    image::DynamicImage::ImageRgba8(render_thumbnail(db))
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .ok()?;
+    Some(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BACKGROUND, COMPONENT_COLOR, THUMBNAIL_SIZE, encode_thumbnail, render_thumbnail};
+    use crate::{
+        grid_db::{GridDB, PrimitiveType, Rotation},
+        testing::GridDBBuilder,
+    };
+
+    #[test]
+    fn thumbnails() {
+        let mut b = GridDBBuilder::new();
+        let input = b.primitive(PrimitiveType::Input, 0, 0, Rotation::ROT0);
+        let not = b.primitive(PrimitiveType::Not, 20, 20, Rotation::ROT0);
+        b.connect((input, 0), (not, 0));
+        let image = render_thumbnail(&b.build());
+        assert_eq!(image.dimensions(), (THUMBNAIL_SIZE, THUMBNAIL_SIZE));
+        // The corners of the schematic are drawn, the margins are not:
+        assert_eq!(image[(5, 5)], COMPONENT_COLOR);
+        assert_eq!(image[(1, 1)], BACKGROUND);
+        assert!(
+            image
+                .pixels()
+                .any(|p| *p != BACKGROUND && *p != COMPONENT_COLOR)
+        );
+
+        let png = encode_thumbnail(&GridDB::new()).unwrap();
+        let decoded = image::load_from_memory(&png).unwrap();
+        assert_eq!(decoded.width(), THUMBNAIL_SIZE);
+    }
+}
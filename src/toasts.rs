@@ -0,0 +1,60 @@
+use egui::{Align2, Color32, RichText, vec2};
+use web_time::{Duration, Instant};
+
+struct Toast {
+    text: String,
+    created: Instant,
+}
+
+/// Short-living error notifications in the bottom right corner
+pub struct Toasts {
+    items: Vec<Toast>,
+}
+
+impl Toasts {
+    const LIFETIME: Duration = Duration::from_secs(6);
+    const MAX_ITEMS: usize = 5;
+
+    pub fn new() -> Self {
+        Self { items: vec![] }
+    }
+
+    pub fn push(&mut self, text: String) {
+        self.items.push(Toast {
+            text,
+            created: Instant::now(),
+        });
+        if self.items.len() > Self::MAX_ITEMS {
+            self.items.remove(0);
+        }
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context) {
+        self.items
+            .retain(|toast| toast.created.elapsed() < Self::LIFETIME);
+        if self.items.is_empty() {
+            return;
+        }
+        let mut closed = None;
+        egui::Area::new("toasts".into())
+            .anchor(Align2::RIGHT_BOTTOM, vec2(-10.0, -40.0))
+            .order(egui::Order::Tooltip)
+            .show(ctx, |ui| {
+                for (i, toast) in self.items.iter().enumerate() {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new("⚠").color(Color32::ORANGE));
+                            ui.label(&toast.text);
+                            if ui.small_button("🗙").clicked() {
+                                closed = Some(i);
+                            }
+                        });
+                    });
+                }
+            });
+        if let Some(i) = closed {
+            self.items.remove(i);
+        }
+        ctx.request_repaint_after(Duration::from_millis(500));
+    }
+}
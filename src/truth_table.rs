@@ -0,0 +1,138 @@
+//! Truth table window
+
+use std::collections::BTreeSet;
+
+use editor_core::truth_table::{
+    MAX_TRUTH_TABLE_INPUTS, TruthTable, build_truth_table, get_table_io,
+};
+use egui::RichText;
+
+use crate::{
+    grid_db::{GridDB, Id, Logic},
+    locale::Locale,
+};
+
+/// Action chosen by user in the truth table window
+pub enum TruthTableAction {
+    ExportCsv(String),
+}
+
+pub struct TruthTableWindow {
+    pub is_open: bool,
+    /// Inputs and outputs opted out of the table
+    skipped: BTreeSet<Id>,
+    table: Option<TruthTable>,
+}
+
+impl Default for TruthTableWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TruthTableWindow {
+    pub fn new() -> Self {
+        Self {
+            is_open: false,
+            skipped: BTreeSet::new(),
+            table: None,
+        }
+    }
+
+    fn show_io_checkboxes(&mut self, ui: &mut egui::Ui, db: &GridDB, ids: &[Id]) {
+        ui.horizontal_wrapped(|ui| {
+            for id in ids {
+                let mut checked = !self.skipped.contains(id);
+                let name = db
+                    .get_component(id)
+                    .and_then(|comp| comp.get_instance_name(*id))
+                    .unwrap_or_default();
+                if ui.checkbox(&mut checked, name).changed() {
+                    if checked {
+                        self.skipped.remove(id);
+                    } else {
+                        self.skipped.insert(*id);
+                    }
+                }
+            }
+        });
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        db: &GridDB,
+        locale: &'static Locale,
+    ) -> Option<TruthTableAction> {
+        let mut action = None;
+        let mut is_open = self.is_open;
+        egui::Window::new(locale.truth_table)
+            .id("truth_table".into())
+            .open(&mut is_open)
+            .default_width(300.0)
+            .show(ctx, |ui| {
+                let (inputs, outputs) = get_table_io(db);
+                ui.label(locale.inputs);
+                self.show_io_checkboxes(ui, db, &inputs);
+                ui.label(locale.outputs);
+                self.show_io_checkboxes(ui, db, &outputs);
+                let inputs: Vec<Id> = inputs
+                    .into_iter()
+                    .filter(|id| !self.skipped.contains(id))
+                    .collect();
+                let outputs: Vec<Id> = outputs
+                    .into_iter()
+                    .filter(|id| !self.skipped.contains(id))
+                    .collect();
+                ui.separator();
+                let too_many = inputs.len() > MAX_TRUTH_TABLE_INPUTS;
+                if too_many {
+                    ui.colored_label(
+                        ui.visuals().error_fg_color,
+                        format!("{}: {MAX_TRUTH_TABLE_INPUTS}", locale.too_many_inputs),
+                    );
+                }
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(
+                            !too_many && !outputs.is_empty(),
+                            egui::Button::new(locale.evaluate),
+                        )
+                        .clicked()
+                    {
+                        self.table = build_truth_table(db, &inputs, &outputs);
+                    }
+                    if let Some(table) = &self.table
+                        && ui.button(locale.export_csv).clicked()
+                    {
+                        action = Some(TruthTableAction::ExportCsv(table.to_csv()));
+                    }
+                });
+                let Some(table) = &self.table else {
+                    return;
+                };
+                egui::ScrollArea::both().max_height(400.0).show(ui, |ui| {
+                    egui::Grid::new("truth_table_grid")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for name in table.inputs.iter().chain(&table.outputs) {
+                                ui.label(RichText::new(name).strong());
+                            }
+                            ui.end_row();
+                            for (row, outputs) in table.rows.iter().enumerate() {
+                                for input in 0..table.inputs.len() {
+                                    let value = Logic::from(table.get_input(row, input));
+                                    ui.monospace(value.to_str());
+                                }
+                                for value in outputs {
+                                    ui.label(RichText::new(value.to_str()).monospace().strong());
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+        self.is_open = is_open;
+        action
+    }
+}
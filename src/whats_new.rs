@@ -0,0 +1,150 @@
+//! Changelog shown once after the app is updated, and the opt-in check of the newer releases
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::{Arc, Mutex};
+
+use egui_commonmark::{CommonMarkCache, CommonMarkViewer};
+
+use crate::locale::Locale;
+
+const CHANGELOG: &str = include_str!("../CHANGELOG.md");
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+const RELEASES_URL: &str = "https://github.com/nikonufrienko/editor/releases";
+#[cfg(not(target_arch = "wasm32"))]
+const LATEST_RELEASE_API_URL: &str =
+    "https://api.github.com/repos/nikonufrienko/editor/releases/latest";
+
+pub struct WhatsNew {
+    pub is_open: bool,
+    /// Releases are checked on start, native only
+    pub check_updates: bool,
+    cache: CommonMarkCache,
+    /// Tag of the newer release found by the check
+    #[cfg(not(target_arch = "wasm32"))]
+    newer_release: Arc<Mutex<Option<String>>>,
+    notified: bool,
+}
+
+impl WhatsNew {
+    /// Opened if the app version differs from `last_version`, the one of the last run
+    pub fn new(last_version: &str, check_updates: bool) -> Self {
+        let result = Self {
+            is_open: last_version != VERSION,
+            check_updates,
+            cache: CommonMarkCache::default(),
+            #[cfg(not(target_arch = "wasm32"))]
+            newer_release: Arc::new(Mutex::new(None)),
+            notified: false,
+        };
+        #[cfg(not(target_arch = "wasm32"))]
+        if check_updates {
+            result.start_check();
+        }
+        result
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn start_check(&self) {
+        let mut request = ehttp::Request::get(LATEST_RELEASE_API_URL);
+        // Required by the GitHub API:
+        request.headers.insert("User-Agent", crate::APP_ID);
+        let newer_release = self.newer_release.clone();
+        ehttp::fetch(request, move |response| {
+            let tag = response.ok().filter(|r| r.ok).and_then(|r| {
+                let json: serde_json::Value = serde_json::from_slice(&r.bytes).ok()?;
+                json["tag_name"].as_str().map(str::to_owned)
+            });
+            if let Some(tag) = tag
+                && is_newer_version(&tag, VERSION)
+            {
+                *newer_release.lock().unwrap() = Some(tag);
+            }
+        });
+    }
+
+    fn get_newer_release(&self) -> Option<String> {
+        #[cfg(not(target_arch = "wasm32"))]
+        return self.newer_release.lock().unwrap().clone();
+        #[cfg(target_arch = "wasm32")]
+        None
+    }
+
+    /// Newer release found by the check, returned once to be notified about
+    pub fn take_notification(&mut self) -> Option<String> {
+        if self.notified {
+            return None;
+        }
+        let release = self.get_newer_release()?;
+        self.notified = true;
+        Some(release)
+    }
+
+    pub fn show(&mut self, ctx: &egui::Context, locale: &'static Locale) {
+        let newer_release = self.get_newer_release();
+        egui::Window::new(locale.whats_new)
+            .id("whats_new".into())
+            .collapsible(false)
+            .default_size([400.0, 400.0])
+            .open(&mut self.is_open)
+            .show(ctx, |ui| {
+                ui.label(format!("{}: {VERSION}", locale.version));
+                if let Some(release) = newer_release {
+                    ui.hyperlink_to(
+                        format!("{}: {release}", locale.newer_version_available),
+                        RELEASES_URL,
+                    );
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                ui.checkbox(&mut self.check_updates, locale.check_updates)
+                    .on_hover_text(locale.check_updates_hint);
+                ui.separator();
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    CommonMarkViewer::new().show(ui, &mut self.cache, CHANGELOG);
+                });
+            });
+    }
+}
+
+/// Release tag like `v1.2.3` is a newer version than `current`.
+/// Missing segments are zeros, a pre-release like `1.2.3-rc1` is older than `1.2.3`.
+#[cfg(not(target_arch = "wasm32"))]
+fn is_newer_version(tag: &str, current: &str) -> bool {
+    let parse = |version: &str| -> (Vec<u64>, bool) {
+        let version = version.trim_start_matches('v');
+        let (core, pre_release) = match version.split_once('-') {
+            Some((core, _)) => (core, true),
+            None => (version, false),
+        };
+        let mut numbers: Vec<u64> = core
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect();
+        while numbers.last() == Some(&0) {
+            numbers.pop();
+        }
+        (numbers, !pre_release)
+    };
+    parse(tag) > parse(current)
+}
+
+#[cfg(all(test, not(target_arch = "wasm32")))]
+mod tests {
+    use super::is_newer_version;
+
+    #[test]
+    fn newer_versions() {
+        assert!(is_newer_version("v0.2.0", "0.1.9"));
+        assert!(is_newer_version("v0.1.10", "0.1.9"));
+        assert!(!is_newer_version("v0.1.0", "0.1.0"));
+        assert!(!is_newer_version("v0.0.9", "0.1.0"));
+        // Different number of segments:
+        assert!(!is_newer_version("v1.2", "1.2.0"));
+        assert!(!is_newer_version("v1.2.0", "1.2"));
+        assert!(is_newer_version("v1.2.1", "1.2"));
+        // Pre-releases:
+        assert!(!is_newer_version("v1.2.3-rc1", "1.2.3"));
+        assert!(is_newer_version("v1.2.3", "1.2.3-rc1"));
+        assert!(is_newer_version("v1.2.3-rc1", "1.2.2"));
+        assert!(!is_newer_version("v1.2.3-rc2", "1.2.3-rc1"));
+    }
+}
@@ -0,0 +1,103 @@
+use editor_core::worker::{WorkerRequest, WorkerResponse};
+
+/// Request running in the Web Worker, the worker is terminated when the task is dropped
+pub struct WorkerTask {
+    worker: web_sys::Worker,
+    response: std::sync::Arc<egui::mutex::Mutex<Option<WorkerResponse>>>,
+    failed: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    _on_message: eframe::wasm_bindgen::closure::Closure<dyn FnMut(web_sys::MessageEvent)>,
+    _on_error: eframe::wasm_bindgen::closure::Closure<dyn FnMut(eframe::wasm_bindgen::JsValue)>,
+}
+
+impl WorkerTask {
+    /// Loader script generated by trunk for the `worker` binary
+    const WORKER_SCRIPT: &str = "./worker_loader.js";
+
+    /// Starts the request in a new worker, None if workers are not available
+    pub fn start(ctx: &egui::Context, request: &WorkerRequest) -> Option<Self> {
+        use eframe::wasm_bindgen::{JsCast, JsValue, closure::Closure};
+
+        let worker = web_sys::Worker::new(Self::WORKER_SCRIPT).ok()?;
+        let response = std::sync::Arc::new(egui::mutex::Mutex::new(None));
+        let on_message = {
+            let response = response.clone();
+            let ctx = ctx.clone();
+            Closure::<dyn FnMut(web_sys::MessageEvent)>::new(move |event: web_sys::MessageEvent| {
+                *response.lock() = Some(
+                    event
+                        .data()
+                        .as_string()
+                        .and_then(|data| serde_json::from_str(&data).ok())
+                        .unwrap_or(WorkerResponse::Error("Invalid worker response".into())),
+                );
+                ctx.request_repaint();
+            })
+        };
+        worker.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+        // The script is not loaded, it threw or the message can't be decoded:
+        let failed = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false));
+        let on_error = {
+            let failed = failed.clone();
+            let ctx = ctx.clone();
+            Closure::<dyn FnMut(JsValue)>::new(move |_: JsValue| {
+                failed.store(true, std::sync::atomic::Ordering::Relaxed);
+                ctx.request_repaint();
+            })
+        };
+        worker.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+        worker.set_onmessageerror(Some(on_error.as_ref().unchecked_ref()));
+        let request = serde_json::to_string(request).ok()?;
+        worker.post_message(&JsValue::from_str(&request)).ok()?;
+        Some(Self {
+            worker,
+            response,
+            failed,
+            _on_message: on_message,
+            _on_error: on_error,
+        })
+    }
+
+    pub fn take_response(&self) -> Option<WorkerResponse> {
+        self.response.lock().take()
+    }
+
+    /// The worker failed without a response, the request has to be executed in the page
+    pub fn is_failed(&self) -> bool {
+        self.failed.load(std::sync::atomic::Ordering::Relaxed)
+    }
+
+    /// Executes the request in a new worker from an async task.
+    /// None if workers are not available or the worker failed.
+    pub async fn run(request: &WorkerRequest) -> Option<WorkerResponse> {
+        use eframe::wasm_bindgen::{JsCast, JsValue, closure::Closure};
+
+        let worker = web_sys::Worker::new(Self::WORKER_SCRIPT).ok()?;
+        let request = serde_json::to_string(request).ok()?;
+        let mut callbacks = None;
+        let promise = js_sys::Promise::new(&mut |resolve, reject| {
+            let on_message = Closure::<dyn FnMut(web_sys::MessageEvent)>::new(
+                move |event: web_sys::MessageEvent| {
+                    resolve.call1(&JsValue::NULL, &event.data()).ok();
+                },
+            );
+            let on_error = Closure::<dyn FnMut(JsValue)>::new(move |err: JsValue| {
+                reject.call1(&JsValue::NULL, &err).ok();
+            });
+            worker.set_onmessage(Some(on_message.as_ref().unchecked_ref()));
+            worker.set_onerror(Some(on_error.as_ref().unchecked_ref()));
+            worker.set_onmessageerror(Some(on_error.as_ref().unchecked_ref()));
+            callbacks = Some((on_message, on_error));
+        });
+        worker.post_message(&JsValue::from_str(&request)).ok()?;
+        let result = wasm_bindgen_futures::JsFuture::from(promise).await;
+        worker.terminate();
+        drop(callbacks);
+        serde_json::from_str(&result.ok()?.as_string()?).ok()
+    }
+}
+
+impl Drop for WorkerTask {
+    fn drop(&mut self) {
+        self.worker.terminate();
+    }
+}
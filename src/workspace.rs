@@ -0,0 +1,350 @@
+//! Workspace folders registered by the user. Projects of the chosen folder are listed
+//! with their thumbnails, they can be opened, renamed and duplicated. Native only.
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use egui::{mutex::Mutex, vec2};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    component_lib::LIBRARY_SUFFIX,
+    file_managment::{FileManager, SIMULATION_SUFFIX, THUMBNAIL_SUFFIX, split_project_suffix},
+    locale::Locale,
+};
+
+const THUMBNAIL_SIZE: f32 = 48.0;
+/// Files with these extensions are not listed, though they are JSON too
+const SKIPPED_SUFFIXES: [&str; 2] = [SIMULATION_SUFFIX, LIBRARY_SUFFIX];
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct WorkspaceSettings {
+    pub folders: Vec<String>,
+    /// Folder chosen last
+    pub active: Option<String>,
+}
+
+struct ProjectEntry {
+    path: PathBuf,
+    file_name: String,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+impl ProjectEntry {
+    fn get_path(&self) -> String {
+        self.path.to_string_lossy().into_owned()
+    }
+
+    /// Size and date of the last change
+    fn get_metadata(&self) -> String {
+        let size = if self.size < 1024 * 1024 {
+            format!("{:.1} KB", self.size as f64 / 1024.0)
+        } else {
+            format!("{:.1} MB", self.size as f64 / (1024.0 * 1024.0))
+        };
+        match self.modified {
+            Some(modified) => format!("{size}, {}", format_date(modified)),
+            None => size,
+        }
+    }
+}
+
+/// Date in UTC like `2024-01-31`
+fn format_date(time: SystemTime) -> String {
+    let days = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+        / 86400;
+    // Civil date from the days since the epoch, by Howard Hinnant's algorithm:
+    let z = days + 719468;
+    let era = z / 146097;
+    let day_of_era = z % 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    format!("{year}-{month:02}-{day:02}")
+}
+
+fn is_project_file(file_name: &str) -> bool {
+    let (_, suffix) = split_project_suffix(file_name);
+    !suffix.is_empty()
+        && suffix != ".svg" // Usually exported images
+        && !SKIPPED_SUFFIXES.iter().any(|s| file_name.ends_with(s))
+}
+
+/// Projects of the folder sorted by name
+fn scan_folder(folder: &Path) -> Vec<ProjectEntry> {
+    let Ok(entries) = std::fs::read_dir(folder) else {
+        return vec![];
+    };
+    let mut projects: Vec<_> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let file_name = entry.file_name().to_string_lossy().into_owned();
+            let metadata = entry.metadata().ok()?;
+            (metadata.is_file() && is_project_file(&file_name)).then(|| ProjectEntry {
+                path: entry.path(),
+                file_name,
+                size: metadata.len(),
+                modified: metadata.modified().ok(),
+            })
+        })
+        .collect();
+    projects.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+    projects
+}
+
+fn thumbnail_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(THUMBNAIL_SUFFIX);
+    PathBuf::from(name)
+}
+
+/// Renames the project and its thumbnail, the extension is kept if `new_name` has none
+fn rename_project(path: &Path, new_name: &str) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_string_lossy();
+    let (_, suffix) = split_project_suffix(&file_name);
+    let new_name = new_name.trim();
+    if new_name.is_empty() || new_name.contains(['/', '\\']) {
+        return None;
+    }
+    let new_path = match split_project_suffix(new_name) {
+        (_, "") => path.with_file_name(format!("{new_name}{suffix}")),
+        _ => path.with_file_name(new_name),
+    };
+    if new_path.exists() {
+        return None;
+    }
+    std::fs::rename(path, &new_path).ok()?;
+    _ = std::fs::rename(thumbnail_path(path), thumbnail_path(&new_path));
+    Some(new_path)
+}
+
+/// Copies the project and its thumbnail to `name copy`, `name copy 2` and so on
+fn duplicate_project(path: &Path) -> Option<PathBuf> {
+    let file_name = path.file_name()?.to_string_lossy();
+    let (stem, suffix) = split_project_suffix(&file_name);
+    let new_path = (1..)
+        .map(|i| match i {
+            1 => path.with_file_name(format!("{stem} copy{suffix}")),
+            _ => path.with_file_name(format!("{stem} copy {i}{suffix}")),
+        })
+        .find(|path| !path.exists())?;
+    std::fs::copy(path, &new_path).ok()?;
+    _ = std::fs::copy(thumbnail_path(path), thumbnail_path(&new_path));
+    Some(new_path)
+}
+
+pub enum WorkspaceAction {
+    Open(String),
+    /// The project file was moved from the first path to the second
+    Renamed(String, String),
+    Error(&'static str),
+}
+
+pub struct WorkspaceWindow {
+    pub is_open: bool,
+    settings: WorkspaceSettings,
+    projects: Vec<ProjectEntry>,
+    /// Projects are listed again when the folder is changed or refreshed
+    scanned: bool,
+    /// Folder picked in the dialog, to be added
+    picked_folder: Arc<Mutex<Option<PathBuf>>>,
+    /// Index of the project being renamed and its new name
+    renaming: Option<(usize, String)>,
+}
+
+impl WorkspaceWindow {
+    pub fn new(settings: WorkspaceSettings) -> Self {
+        Self {
+            is_open: false,
+            settings,
+            projects: vec![],
+            scanned: false,
+            picked_folder: Arc::new(Mutex::new(None)),
+            renaming: None,
+        }
+    }
+
+    pub fn get_settings(&self) -> WorkspaceSettings {
+        self.settings.clone()
+    }
+
+    fn set_active(&mut self, folder: Option<String>) {
+        self.settings.active = folder;
+        self.scanned = false;
+        self.renaming = None;
+    }
+
+    fn pick_folder(&self) {
+        let picked_folder = self.picked_folder.clone();
+        smol::spawn(async move {
+            if let Some(folder) = rfd::AsyncFileDialog::new().pick_folder().await {
+                *picked_folder.lock() = Some(folder.path().to_path_buf());
+            }
+        })
+        .detach();
+    }
+
+    fn update(&mut self) {
+        let picked_folder = self.picked_folder.lock().take();
+        if let Some(folder) = picked_folder {
+            let folder = folder.to_string_lossy().into_owned();
+            if !self.settings.folders.contains(&folder) {
+                self.settings.folders.push(folder.clone());
+            }
+            self.set_active(Some(folder));
+        }
+        if !self.scanned {
+            self.scanned = true;
+            self.projects = match &self.settings.active {
+                Some(folder) => scan_folder(Path::new(folder)),
+                None => vec![],
+            };
+        }
+    }
+
+    fn show_folders(&mut self, ui: &mut egui::Ui, locale: &'static Locale) {
+        ui.horizontal(|ui| {
+            let folder_name = |folder: &str| {
+                let name = folder.rsplit(['/', '\\']).find(|s| !s.is_empty());
+                name.unwrap_or(folder).to_owned()
+            };
+            let mut active = self.settings.active.clone();
+            let selected = active.as_deref().map(folder_name).unwrap_or_default();
+            egui::ComboBox::from_id_salt("workspace_folder")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    for folder in &self.settings.folders {
+                        ui.selectable_value(&mut active, Some(folder.clone()), folder_name(folder))
+                            .on_hover_text(folder);
+                    }
+                });
+            if active != self.settings.active {
+                self.set_active(active);
+            }
+            if ui.button("+").on_hover_text(locale.add_folder).clicked() {
+                self.pick_folder();
+            }
+            if let Some(folder) = self.settings.active.clone()
+                && ui.button("−").on_hover_text(locale.remove_folder).clicked()
+            {
+                self.settings.folders.retain(|f| *f != folder);
+                self.set_active(self.settings.folders.first().cloned());
+            }
+            if ui.button("⟳").on_hover_text(locale.refresh).clicked() {
+                self.scanned = false;
+            }
+        });
+    }
+
+    /// Shows the workspace, the projects are opened by the app
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        file_manager: &mut FileManager,
+        locale: &'static Locale,
+    ) -> Option<WorkspaceAction> {
+        self.update();
+        let mut action = None;
+        let mut is_open = self.is_open;
+        egui::Window::new(locale.workspace)
+            .id("workspace".into())
+            .open(&mut is_open)
+            .default_width(360.0)
+            .show(ctx, |ui| {
+                self.show_folders(ui, locale);
+                ui.separator();
+                if self.settings.folders.is_empty() {
+                    ui.label(locale.no_workspace_folders);
+                    return;
+                }
+                if self.projects.is_empty() {
+                    ui.label(locale.no_projects_in_folder);
+                    return;
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(400.0)
+                    .show(ui, |ui| {
+                        for i in 0..self.projects.len() {
+                            if let Some(a) = self.show_project(ui, i, file_manager, locale) {
+                                action = Some(a);
+                            }
+                        }
+                    });
+            });
+        self.is_open = is_open;
+        action
+    }
+
+    fn show_project(
+        &mut self,
+        ui: &mut egui::Ui,
+        index: usize,
+        file_manager: &mut FileManager,
+        locale: &'static Locale,
+    ) -> Option<WorkspaceAction> {
+        let mut action = None;
+        let project = &self.projects[index];
+        let (project_path, path) = (project.path.clone(), project.get_path());
+        let (file_name, metadata) = (project.file_name.clone(), project.get_metadata());
+        ui.horizontal(|ui| {
+            let size = vec2(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+            let button = match file_manager.get_thumbnail(ui.ctx(), &path) {
+                Some(image) => egui::Button::image(image.fit_to_exact_size(size)),
+                None => egui::Button::new("📄").min_size(size),
+            };
+            if ui.add(button).on_hover_text(locale.open).clicked() {
+                action = Some(WorkspaceAction::Open(path.clone()));
+            }
+            ui.vertical(|ui| {
+                match &mut self.renaming {
+                    Some((i, new_name)) if *i == index => {
+                        let response = ui.text_edit_singleline(new_name);
+                        response.request_focus();
+                        if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                            self.renaming = None;
+                        } else if response.lost_focus() {
+                            let new_name = std::mem::take(new_name);
+                            self.renaming = None;
+                            action = Some(match rename_project(&project_path, &new_name) {
+                                Some(new_path) => {
+                                    self.scanned = false;
+                                    let new_path = new_path.to_string_lossy().into_owned();
+                                    WorkspaceAction::Renamed(path.clone(), new_path)
+                                }
+                                None => WorkspaceAction::Error(locale.rename_failed),
+                            });
+                        }
+                    }
+                    _ => {
+                        ui.label(&file_name);
+                    }
+                }
+                ui.weak(metadata);
+                ui.horizontal(|ui| {
+                    if ui.small_button(locale.rename).clicked() {
+                        let (stem, _) = split_project_suffix(&file_name);
+                        self.renaming = Some((index, stem.to_owned()));
+                    }
+                    if ui.small_button(locale.duplicate).clicked() {
+                        self.scanned = false;
+                        if duplicate_project(&project_path).is_none() {
+                            action = Some(WorkspaceAction::Error(locale.duplicate_failed));
+                        }
+                    }
+                });
+            });
+        });
+        action
+    }
+}